@@ -0,0 +1,241 @@
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    Attribute, Data, DeriveInput, Error, Expr, Fields, GenericParam, Lifetime, LifetimeParam,
+    LitStr, Result, parse_macro_input,
+};
+
+/// Derives `grammar::derive::Parse`, generating a winnow parser from
+/// `#[grammar(..)]` attributes.
+///
+/// A struct parses its fields in order. Each field is parsed with its
+/// type's own `Parse` impl unless it says otherwise:
+///
+/// - `with = parser` parses the field with any winnow parser expression;
+/// - `prefix = parser` and `suffix = parser` are matched around it, and
+///   their output is dropped;
+/// - `label = "..."` and `expected = "..."` add error context.
+///
+/// On the struct itself, `tag = parser` is matched before the first field,
+/// `sep = parser` between fields, and `label` and `expected` describe the
+/// whole thing. An enum tries its variants in order, like `alt`; every
+/// variant takes the same options as a struct, and a unit variant's tag
+/// defaults to its name.
+///
+/// ```text
+/// #[derive(Parse)]
+/// #[grammar(sep = ' ', label = "request line")]
+/// struct RequestLine<'i> {
+///     method: Method,
+///     #[grammar(with = take_till(1.., ' '), expected = "request target")]
+///     target: &'i str,
+///     #[grammar(prefix = "HTTP/")]
+///     version: Version,
+/// }
+/// ```
+#[proc_macro_derive(Parse, attributes(grammar))]
+pub fn derive_parse(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+const CONTAINER: &[&str] = &["tag", "sep", "label", "expected"];
+const ENUM: &[&str] = &["label", "expected"];
+const FIELD: &[&str] = &["with", "prefix", "suffix", "label", "expected"];
+
+#[derive(Default)]
+struct Options {
+    tag: Option<Expr>,
+    sep: Option<Expr>,
+    with: Option<Expr>,
+    prefix: Option<Expr>,
+    suffix: Option<Expr>,
+    label: Option<LitStr>,
+    expected: Option<LitStr>,
+}
+
+impl Options {
+    fn from_attrs(attrs: &[Attribute], allowed: &[&str]) -> Result<Self> {
+        let mut options = Options::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("grammar")) {
+            attr.parse_nested_meta(|meta| {
+                let key = meta
+                    .path
+                    .get_ident()
+                    .map(ToString::to_string)
+                    .unwrap_or_default();
+                if !allowed.contains(&key.as_str()) {
+                    let message = format!("expected one of: {}", allowed.join(", "));
+                    return Err(meta.error(message));
+                }
+                let value = meta.value()?;
+                match key.as_str() {
+                    "tag" => options.tag = Some(value.parse()?),
+                    "sep" => options.sep = Some(value.parse()?),
+                    "with" => options.with = Some(value.parse()?),
+                    "prefix" => options.prefix = Some(value.parse()?),
+                    "suffix" => options.suffix = Some(value.parse()?),
+                    "label" => options.label = Some(value.parse()?),
+                    _ => options.expected = Some(value.parse()?),
+                }
+                Ok(())
+            })?;
+        }
+        Ok(options)
+    }
+
+    /// Wraps `parser` in the `label` and `expected` contexts, if any.
+    fn context(&self, parser: TokenStream2) -> TokenStream2 {
+        let private = private();
+        let mut parser = parser;
+        if let Some(label) = &self.label {
+            parser = quote!(#private::label(#parser, #label));
+        }
+        if let Some(expected) = &self.expected {
+            parser = quote!(#private::expected(#parser, #expected));
+        }
+        parser
+    }
+}
+
+fn private() -> TokenStream2 {
+    quote!(::grammar::derive::__private)
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+    if let Some(param) = input.generics.type_params().next() {
+        return Err(Error::new_spanned(
+            param,
+            "type parameters are not supported",
+        ));
+    }
+    if let Some(param) = input.generics.const_params().next() {
+        return Err(Error::new_spanned(
+            param,
+            "const parameters are not supported",
+        ));
+    }
+    let mut generics = input.generics.clone();
+    let lifetime = match input.generics.lifetimes().next() {
+        Some(param) => param.lifetime.clone(),
+        None => {
+            let lifetime = Lifetime::new("'__input", Span::call_site());
+            let param = GenericParam::Lifetime(LifetimeParam::new(lifetime.clone()));
+            generics.params.insert(0, param);
+            lifetime
+        }
+    };
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let name = &input.ident;
+    let private = private();
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let options = Options::from_attrs(&input.attrs, CONTAINER)?;
+            sequence(&options, &data.fields, quote!(#name), &lifetime)?
+        }
+        Data::Enum(data) => {
+            let options = Options::from_attrs(&input.attrs, ENUM)?;
+            let mut variants = Vec::new();
+            for variant in &data.variants {
+                let mut variant_options = Options::from_attrs(&variant.attrs, CONTAINER)?;
+                if matches!(variant.fields, Fields::Unit) && variant_options.tag.is_none() {
+                    let tag = LitStr::new(&variant.ident.to_string(), variant.ident.span());
+                    variant_options.tag = Some(syn::parse_quote!(#tag));
+                }
+                let ident = &variant.ident;
+                variants.push(sequence(
+                    &variant_options,
+                    &variant.fields,
+                    quote!(#name::#ident),
+                    &lifetime,
+                )?);
+            }
+            let alternatives = match variants.as_slice() {
+                [] => return Err(Error::new_spanned(name, "an enum needs variants to parse")),
+                [variant] => variant.clone(),
+                _ if variants.len() > 21 => {
+                    return Err(Error::new_spanned(
+                        name,
+                        "at most 21 variants are supported",
+                    ));
+                }
+                _ => quote!(#private::alt((#(#variants,)*))),
+            };
+            options.context(alternatives)
+        }
+        Data::Union(data) => {
+            return Err(Error::new_spanned(
+                data.union_token,
+                "unions are not supported",
+            ));
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::grammar::derive::Parse<#lifetime> for #name #ty_generics #where_clause {
+            fn parse_next(input: &mut &#lifetime str) -> #private::ModalResult<Self> {
+                #private::parse(#body, input)
+            }
+        }
+    })
+}
+
+/// A parser for `fields` in order, building the value with `constructor`.
+fn sequence(
+    options: &Options,
+    fields: &Fields,
+    constructor: TokenStream2,
+    lifetime: &Lifetime,
+) -> Result<TokenStream2> {
+    let private = private();
+    let skip = |parser: &Expr| quote!(#private::parse(#parser, input)?;);
+    let mut steps = Vec::new();
+    if let Some(tag) = &options.tag {
+        steps.push(skip(tag));
+    }
+    let mut names = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let field_options = Options::from_attrs(&field.attrs, FIELD)?;
+        if i > 0
+            && let Some(sep) = &options.sep
+        {
+            steps.push(skip(sep));
+        }
+        if let Some(prefix) = &field_options.prefix {
+            steps.push(skip(prefix));
+        }
+        let ty = &field.ty;
+        let parser = match &field_options.with {
+            Some(with) => quote!(#with),
+            None => quote!(<#ty as ::grammar::derive::Parse<#lifetime>>::parse_next),
+        };
+        let parser = field_options.context(parser);
+        let name = format_ident!("field{}", i);
+        steps.push(quote!(let #name: #ty = #private::parse(#parser, input)?;));
+        if let Some(suffix) = &field_options.suffix {
+            steps.push(skip(suffix));
+        }
+        names.push(name);
+    }
+
+    let value = match fields {
+        Fields::Named(fields) => {
+            let idents = fields.named.iter().map(|field| &field.ident);
+            quote!(#constructor { #(#idents: #names),* })
+        }
+        Fields::Unnamed(_) => quote!(#constructor(#(#names),*)),
+        Fields::Unit => constructor,
+    };
+    let parser = quote! {
+        |input: &mut &#lifetime str| -> #private::ModalResult<Self> {
+            #(#steps)*
+            Ok(#value)
+        }
+    };
+    Ok(options.context(parser))
+}
@@ -0,0 +1,334 @@
+use crate::ParseError;
+
+/// An Apache httpd configuration file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    pub nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    Directive(Directive),
+    Section(Section),
+}
+
+/// A `Name args...` line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Directive {
+    pub name: String,
+    /// Quotes removed.
+    pub args: Vec<String>,
+    /// 1-based line the directive starts on.
+    pub line: usize,
+}
+
+/// A container such as `<VirtualHost *:443>` or `<Directory /var/www>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub name: String,
+    pub args: Vec<String>,
+    pub children: Vec<Node>,
+    pub line: usize,
+}
+
+impl Section {
+    /// Names compare ignoring ASCII case, as httpd does.
+    pub fn is(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+    }
+}
+
+impl Config {
+    /// Every directive named `name` at any depth, with the sections that
+    /// enclose it from the outermost in.
+    pub fn directives<'a>(&'a self, name: &str) -> Vec<(&'a Directive, Vec<&'a Section>)> {
+        fn walk<'a>(
+            nodes: &'a [Node],
+            name: &str,
+            path: &mut Vec<&'a Section>,
+            found: &mut Vec<(&'a Directive, Vec<&'a Section>)>,
+        ) {
+            for node in nodes {
+                match node {
+                    Node::Directive(d) if d.name.eq_ignore_ascii_case(name) => {
+                        found.push((d, path.clone()))
+                    }
+                    Node::Directive(_) => {}
+                    Node::Section(section) => {
+                        path.push(section);
+                        walk(&section.children, name, path, found);
+                        path.pop();
+                    }
+                }
+            }
+        }
+        let mut found = Vec::new();
+        walk(&self.nodes, name, &mut Vec::new(), &mut found);
+        found
+    }
+
+    /// Every section named `name` at any depth, e.g. all `VirtualHost`s.
+    pub fn sections<'a>(&'a self, name: &str) -> Vec<&'a Section> {
+        fn walk<'a>(nodes: &'a [Node], name: &str, found: &mut Vec<&'a Section>) {
+            for node in nodes {
+                if let Node::Section(section) = node {
+                    if section.is(name) {
+                        found.push(section);
+                    }
+                    walk(&section.children, name, found);
+                }
+            }
+        }
+        let mut found = Vec::new();
+        walk(&self.nodes, name, &mut found);
+        found
+    }
+
+    /// The configuration as httpd would see it with the given modules
+    /// loaded: `<IfModule>` sections are replaced by their contents or
+    /// dropped. `!module` negates, as in the config syntax.
+    pub fn with_modules(&self, loaded: &dyn Fn(&str) -> bool) -> Config {
+        fn resolve(nodes: &[Node], loaded: &dyn Fn(&str) -> bool) -> Vec<Node> {
+            let mut out = Vec::new();
+            for node in nodes {
+                match node {
+                    Node::Section(section) if section.is("IfModule") => {
+                        let arg = section.args.first().map_or("", String::as_str);
+                        let enabled = match arg.strip_prefix('!') {
+                            Some(module) => !loaded(module),
+                            None => loaded(arg),
+                        };
+                        if enabled {
+                            out.extend(resolve(&section.children, loaded));
+                        }
+                    }
+                    Node::Section(section) => out.push(Node::Section(Section {
+                        children: resolve(&section.children, loaded),
+                        ..section.clone()
+                    })),
+                    node => out.push(node.clone()),
+                }
+            }
+            out
+        }
+        Config {
+            nodes: resolve(&self.nodes, loaded),
+        }
+    }
+}
+
+/// Parses httpd configuration syntax. A `\` at the very end of a line
+/// continues it on the next; `#` only starts a comment at the beginning of
+/// a line. Sections must be closed, by name, in order.
+pub fn parse_apache_conf(input: &str) -> Result<Config, ParseError> {
+    // Open sections, innermost last, each with the nodes gathered so far.
+    let mut stack: Vec<(Section, usize)> = Vec::new();
+    let mut nodes = Vec::new();
+    for (offset, line, text) in logical_lines(input) {
+        let trimmed = text.trim_start();
+        let at = offset + (text.len() - trimmed.len());
+        let trimmed = trimmed.trim_end();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = trimmed.strip_prefix("</") {
+            let name = name
+                .strip_suffix('>')
+                .ok_or_else(|| ParseError::new(at + trimmed.len(), "expected `>`"))?
+                .trim();
+            let Some((section, _)) = stack.pop() else {
+                return Err(ParseError::new(
+                    at,
+                    format!("`</{name}>` without an open section"),
+                ));
+            };
+            if !section.is(name) {
+                return Err(ParseError::new(
+                    at,
+                    format!("`</{name}>` closes `<{}>`", section.name),
+                ));
+            }
+            match stack.last_mut() {
+                Some((parent, _)) => parent.children.push(Node::Section(section)),
+                None => nodes.push(Node::Section(section)),
+            }
+        } else if let Some(open) = trimmed.strip_prefix('<') {
+            let open = open
+                .strip_suffix('>')
+                .ok_or_else(|| ParseError::new(at + trimmed.len(), "expected `>`"))?;
+            let mut words =
+                words(open).map_err(|i| ParseError::new(at + 1 + i, "unterminated quote"))?;
+            if words.is_empty() {
+                return Err(ParseError::new(at + 1, "expected a section name"));
+            }
+            let name = words.remove(0);
+            stack.push((
+                Section {
+                    name,
+                    args: words,
+                    children: Vec::new(),
+                    line,
+                },
+                at,
+            ));
+        } else {
+            let mut words =
+                words(trimmed).map_err(|i| ParseError::new(at + i, "unterminated quote"))?;
+            let directive = Node::Directive(Directive {
+                name: words.remove(0),
+                args: words,
+                line,
+            });
+            match stack.last_mut() {
+                Some((section, _)) => section.children.push(directive),
+                None => nodes.push(directive),
+            }
+        }
+    }
+    if let Some((section, at)) = stack.pop() {
+        return Err(ParseError::new(
+            at,
+            format!("`<{}>` is never closed", section.name),
+        ));
+    }
+    Ok(Config { nodes })
+}
+
+/// `(offset, line number, text)` for each line, continuations joined.
+fn logical_lines(input: &str) -> Vec<(usize, usize, String)> {
+    let mut lines = Vec::new();
+    let mut pending: Option<(usize, usize, String)> = None;
+    let mut offset = 0;
+    for (index, raw) in input.split_inclusive('\n').enumerate() {
+        let start = offset;
+        offset += raw.len();
+        let text = raw.trim_end_matches(['\n', '\r']);
+        let (text, continues) = match text.strip_suffix('\\') {
+            Some(text) => (text, true),
+            None => (text, false),
+        };
+        let (start, number, mut joined) =
+            pending.take().unwrap_or((start, index + 1, String::new()));
+        joined.push_str(text);
+        if continues {
+            pending = Some((start, number, joined));
+        } else {
+            lines.push((start, number, joined));
+        }
+    }
+    lines.extend(pending);
+    lines
+}
+
+/// Splits on white space, honoring `"..."` and `'...'` with backslash
+/// escapes. Errors with the offset of an unclosed quote.
+fn words(text: &str) -> Result<Vec<String>, usize> {
+    let mut words = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut word = String::new();
+        if c == '"' || c == '\'' {
+            chars.next();
+            loop {
+                match chars.next() {
+                    None => return Err(start),
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, q)) if q == c || q == '\\' => word.push(q),
+                        Some((_, other)) => {
+                            word.push('\\');
+                            word.push(other);
+                        }
+                        None => return Err(start),
+                    },
+                    Some((_, q)) if q == c => break,
+                    Some((_, other)) => word.push(other),
+                }
+            }
+        } else {
+            while let Some(&(_, c)) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+        }
+        words.push(word);
+    }
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONF: &str = r#"# main config
+ServerRoot "/etc/httpd"
+Listen 80
+<IfModule mod_ssl.c>
+    Listen 443
+</IfModule>
+<VirtualHost *:80 [::]:80>
+    ServerName example.com
+    ServerAlias www.example.com \
+                static.example.com
+    <Directory "/var/www/My Site">
+        Options -Indexes +FollowSymLinks
+        Require all granted
+    </directory>
+    <IfModule !mod_rewrite.c>
+        Redirect permanent / "https://example.com/"
+    </IfModule>
+</VirtualHost>
+"#;
+
+    #[test]
+    fn parse_apache_conf_should_build_a_tree() -> Result<(), ParseError> {
+        let config = parse_apache_conf(CONF)?;
+        assert_eq!(config.nodes.len(), 4);
+        let vhost = config.sections("virtualhost")[0];
+        assert_eq!(vhost.args, ["*:80", "[::]:80"]);
+        assert_eq!(vhost.line, 7);
+        let Node::Directive(alias) = &vhost.children[1] else {
+            panic!("expected ServerAlias");
+        };
+        assert_eq!(alias.args, ["www.example.com", "static.example.com"]);
+        let directory = config.sections("Directory")[0];
+        assert_eq!(directory.args, ["/var/www/My Site"]);
+        let requires = config.directives("require");
+        assert_eq!(requires[0].0.args, ["all", "granted"]);
+        assert_eq!(requires[0].0.line, 13);
+        let enclosing: Vec<_> = requires[0].1.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(enclosing, ["VirtualHost", "Directory"]);
+        Ok(())
+    }
+
+    #[test]
+    fn with_modules_should_resolve_if_module() -> Result<(), ParseError> {
+        let config = parse_apache_conf(CONF)?;
+        let ssl = config.with_modules(&|m| m == "mod_ssl.c");
+        assert_eq!(ssl.directives("Listen").len(), 2);
+        assert_eq!(ssl.directives("Redirect").len(), 1);
+        assert!(ssl.sections("IfModule").is_empty());
+        let rewrite = config.with_modules(&|m| m == "mod_rewrite.c");
+        assert_eq!(rewrite.directives("Listen").len(), 1);
+        assert!(rewrite.directives("Redirect").is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_apache_conf_should_report_errors() {
+        let err = parse_apache_conf("<VirtualHost *:80>\n</Directory>\n").unwrap_err();
+        assert_eq!(err.offset(), 19);
+        assert_eq!(err.message(), "`</Directory>` closes `<VirtualHost>`");
+        let err = parse_apache_conf("Listen 80\n  <Location />\n").unwrap_err();
+        assert_eq!(err.offset(), 12);
+        assert!(parse_apache_conf("ServerName \"open").is_err());
+        assert!(parse_apache_conf("</IfModule>").is_err());
+        assert!(parse_apache_conf("<Directory /x\n").is_err());
+    }
+}
@@ -0,0 +1,392 @@
+use std::collections::BTreeMap;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::combinator::{alt, cut_err, delimited, fail, opt, preceded, repeat, terminated};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::{take, take_while};
+
+use crate::ParseError;
+
+/// A bencoded value. Dictionaries keep their keys sorted, which is also
+/// the order they are encoded in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Bencode {
+    Integer(i64),
+    Bytes(Vec<u8>),
+    List(Vec<Bencode>),
+    Dict(BTreeMap<Vec<u8>, Bencode>),
+}
+
+impl Bencode {
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Bencode::Integer(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Bencode::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// The byte string, if it is valid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        std::str::from_utf8(self.as_bytes()?).ok()
+    }
+
+    pub fn as_list(&self) -> Option<&[Bencode]> {
+        match self {
+            Bencode::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Bencode> {
+        match self {
+            Bencode::Dict(entries) => entries.get(key.as_bytes()),
+            _ => None,
+        }
+    }
+
+    /// The canonical encoding. For anything [`parse_bencode`] accepted this
+    /// is the exact input.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode_into(&mut out);
+        out
+    }
+
+    fn encode_into(&self, out: &mut Vec<u8>) {
+        match self {
+            Bencode::Integer(n) => out.extend(format!("i{n}e").bytes()),
+            Bencode::Bytes(b) => encode_bytes(out, b),
+            Bencode::List(items) => {
+                out.push(b'l');
+                for item in items {
+                    item.encode_into(out);
+                }
+                out.push(b'e');
+            }
+            Bencode::Dict(entries) => {
+                out.push(b'd');
+                for (key, value) in entries {
+                    encode_bytes(out, key);
+                    value.encode_into(out);
+                }
+                out.push(b'e');
+            }
+        }
+    }
+}
+
+fn encode_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend(bytes.len().to_string().bytes());
+    out.push(b':');
+    out.extend(bytes);
+}
+
+/// Parses exactly one value. Only the canonical form is accepted: no
+/// leading zeros or `-0`, and dictionary keys in strictly ascending byte
+/// order.
+pub fn parse_bencode(input: &[u8]) -> Result<Bencode, ParseError> {
+    value.parse(input).map_err(ParseError::from)
+}
+
+fn value(input: &mut &[u8]) -> ModalResult<Bencode> {
+    alt((
+        integer.map(Bencode::Integer),
+        bytes.map(Bencode::Bytes),
+        list,
+        dict,
+    ))
+    .context(StrContext::Label("value"))
+    .parse_next(input)
+}
+
+fn integer(input: &mut &[u8]) -> ModalResult<i64> {
+    delimited(
+        'i',
+        cut_err(
+            (opt('-'), take_while(1.., |b: u8| b.is_ascii_digit()))
+                .take()
+                .verify(|n: &[u8]| {
+                    let digits = n.strip_prefix(b"-").unwrap_or(n);
+                    (digits == b"0" && n.len() == 1) || digits[0] != b'0'
+                })
+                .verify_map(|n: &[u8]| std::str::from_utf8(n).ok()?.parse().ok()),
+        )
+        .context(StrContext::Label("integer")),
+        cut_err('e').context(StrContext::Expected(StrContextValue::CharLiteral('e'))),
+    )
+    .parse_next(input)
+}
+
+fn bytes(input: &mut &[u8]) -> ModalResult<Vec<u8>> {
+    let len: usize = take_while(1.., |b: u8| b.is_ascii_digit())
+        .verify(|n: &[u8]| n == b"0" || n[0] != b'0')
+        .verify_map(|n: &[u8]| std::str::from_utf8(n).ok()?.parse().ok())
+        .parse_next(input)?;
+    preceded(
+        cut_err(':').context(StrContext::Expected(StrContextValue::CharLiteral(':'))),
+        cut_err(take(len)).context(StrContext::Label("byte string")),
+    )
+    .map(<[u8]>::to_vec)
+    .parse_next(input)
+}
+
+fn list(input: &mut &[u8]) -> ModalResult<Bencode> {
+    preceded(
+        'l',
+        cut_err(terminated(repeat(0.., value), 'e'))
+            .context(StrContext::Expected(StrContextValue::CharLiteral('e'))),
+    )
+    .map(Bencode::List)
+    .parse_next(input)
+}
+
+fn dict(input: &mut &[u8]) -> ModalResult<Bencode> {
+    'd'.parse_next(input)?;
+    let mut entries = BTreeMap::new();
+    let mut last: Option<Vec<u8>> = None;
+    loop {
+        if opt('e').parse_next(input)?.is_some() {
+            return Ok(Bencode::Dict(entries));
+        }
+        let start = *input;
+        let key = cut_err(bytes)
+            .context(StrContext::Expected(StrContextValue::Description(
+                "a byte string key",
+            )))
+            .parse_next(input)?;
+        if last.as_ref().is_some_and(|last| *last >= key) {
+            *input = start;
+            return cut_err(fail)
+                .context(StrContext::Label(
+                    "dictionary keys must be sorted and unique",
+                ))
+                .parse_next(input);
+        }
+        let value = cut_err(value).parse_next(input)?;
+        last = Some(key.clone());
+        entries.insert(key, value);
+    }
+}
+
+/// The metainfo in a `.torrent` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Torrent {
+    pub announce: Option<String>,
+    /// Tiers of trackers from `announce-list` (BEP 12).
+    pub announce_list: Vec<Vec<String>>,
+    pub comment: Option<String>,
+    pub created_by: Option<String>,
+    /// Seconds since the Unix epoch.
+    pub creation_date: Option<i64>,
+    pub info: Info,
+    /// The `info` dictionary as parsed. The info hash is the SHA-1 of
+    /// `raw_info.encode()`.
+    pub raw_info: Bencode,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Info {
+    /// The file name, or the directory name for multi-file torrents.
+    pub name: String,
+    pub piece_length: u64,
+    /// SHA-1 hashes of each piece.
+    pub pieces: Vec<[u8; 20]>,
+    /// Single-file torrents list one file with an empty path.
+    pub files: Vec<TorrentFile>,
+    pub private: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TorrentFile {
+    pub length: u64,
+    /// Path components below [`Info::name`].
+    pub path: Vec<String>,
+}
+
+impl Info {
+    pub fn total_length(&self) -> u64 {
+        self.files.iter().map(|f| f.length).sum()
+    }
+}
+
+/// Parses a `.torrent` file. Problems with the structure, as opposed to
+/// the encoding, are reported at offset 0.
+pub fn parse_torrent(input: &[u8]) -> Result<Torrent, ParseError> {
+    let root = parse_bencode(input)?;
+    if !matches!(root, Bencode::Dict(_)) {
+        return Err(invalid("a torrent must be a dictionary"));
+    }
+    let raw_info = root
+        .get("info")
+        .filter(|info| matches!(info, Bencode::Dict(_)))
+        .ok_or_else(|| invalid("missing `info` dictionary"))?
+        .clone();
+    let announce_list = match root.get("announce-list") {
+        None => Vec::new(),
+        Some(tiers) => tiers
+            .as_list()
+            .ok_or_else(|| invalid("`announce-list` must be a list"))?
+            .iter()
+            .map(|tier| string_list(tier, "announce-list"))
+            .collect::<Result<_, _>>()?,
+    };
+    Ok(Torrent {
+        announce: optional_string(&root, "announce")?,
+        announce_list,
+        comment: optional_string(&root, "comment")?,
+        created_by: optional_string(&root, "created by")?,
+        creation_date: optional(&root, "creation date", Bencode::as_integer)?,
+        info: info(&raw_info)?,
+        raw_info,
+    })
+}
+
+fn info(dict: &Bencode) -> Result<Info, ParseError> {
+    let name = optional_string(dict, "name")?.ok_or_else(|| invalid("missing `name`"))?;
+    let piece_length = length(dict, "piece length")?;
+    let pieces = dict
+        .get("pieces")
+        .and_then(Bencode::as_bytes)
+        .filter(|p| p.len().is_multiple_of(20))
+        .ok_or_else(|| invalid("`pieces` must be a multiple of 20 bytes"))?
+        .chunks_exact(20)
+        .map(|hash| hash.try_into().unwrap())
+        .collect();
+    let files = match dict.get("files") {
+        Some(files) => files
+            .as_list()
+            .ok_or_else(|| invalid("`files` must be a list"))?
+            .iter()
+            .map(|file| {
+                Ok(TorrentFile {
+                    length: length(file, "length")?,
+                    path: string_list(
+                        file.get("path").ok_or_else(|| invalid("missing `path`"))?,
+                        "path",
+                    )?,
+                })
+            })
+            .collect::<Result<_, ParseError>>()?,
+        None => vec![TorrentFile {
+            length: length(dict, "length")?,
+            path: Vec::new(),
+        }],
+    };
+    Ok(Info {
+        name,
+        piece_length,
+        pieces,
+        files,
+        private: optional(dict, "private", Bencode::as_integer)? == Some(1),
+    })
+}
+
+fn invalid(message: &str) -> ParseError {
+    ParseError::new(0, message)
+}
+
+fn optional<'a, T>(
+    dict: &'a Bencode,
+    key: &str,
+    read: impl Fn(&'a Bencode) -> Option<T>,
+) -> Result<Option<T>, ParseError> {
+    match dict.get(key) {
+        None => Ok(None),
+        Some(value) => read(value)
+            .map(Some)
+            .ok_or_else(|| ParseError::new(0, format!("`{key}` has the wrong type"))),
+    }
+}
+
+fn optional_string(dict: &Bencode, key: &str) -> Result<Option<String>, ParseError> {
+    optional(dict, key, |v| v.as_str().map(str::to_string))
+}
+
+fn length(dict: &Bencode, key: &str) -> Result<u64, ParseError> {
+    optional(dict, key, |v| u64::try_from(v.as_integer()?).ok())?
+        .ok_or_else(|| ParseError::new(0, format!("missing `{key}`")))
+}
+
+fn string_list(list: &Bencode, key: &str) -> Result<Vec<String>, ParseError> {
+    list.as_list()
+        .and_then(|items| {
+            items
+                .iter()
+                .map(|item| item.as_str().map(str::to_string))
+                .collect()
+        })
+        .ok_or_else(|| ParseError::new(0, format!("`{key}` must be a list of strings")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_bencode_should_work() -> Result<(), ParseError> {
+        let input = b"d3:bar4:spam3:fooi-42e4:listl0:i0eee";
+        let value = parse_bencode(input)?;
+        assert_eq!(value.get("bar").and_then(Bencode::as_str), Some("spam"));
+        assert_eq!(value.get("foo").and_then(Bencode::as_integer), Some(-42));
+        assert_eq!(
+            value.get("list").and_then(Bencode::as_list),
+            Some(&[Bencode::Bytes(vec![]), Bencode::Integer(0)][..])
+        );
+        assert_eq!(value.encode(), input);
+        assert_eq!(
+            parse_bencode(b"3:\xff\x00a")?,
+            Bencode::Bytes(vec![0xff, 0, b'a'])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_torrent_should_work() -> Result<(), ParseError> {
+        let mut input = b"d8:announce15:http://t.io/ann13:announce-listll15:http://t.io/ann\
+            el11:udp://b.io/ee10:created by4:test13:creation datei1700000000e4:infod5:filesl\
+            d6:lengthi5e4:pathl1:a5:b.txteed6:lengthi7e4:pathl1:ceee4:name3:dir\
+            12:piece lengthi16384e6:pieces20:"
+            .to_vec();
+        input.extend([7; 20]);
+        input.extend(b"7:privatei1eee");
+        let torrent = parse_torrent(&input)?;
+        assert_eq!(torrent.announce.as_deref(), Some("http://t.io/ann"));
+        assert_eq!(torrent.announce_list[1], ["udp://b.io/"]);
+        assert_eq!(torrent.created_by.as_deref(), Some("test"));
+        assert_eq!(torrent.creation_date, Some(1_700_000_000));
+        assert_eq!(torrent.info.name, "dir");
+        assert_eq!(torrent.info.pieces, [[7; 20]]);
+        assert_eq!(torrent.info.files[0].path, ["a", "b.txt"]);
+        assert_eq!(torrent.info.total_length(), 12);
+        assert!(torrent.info.private);
+        let start = input.windows(6).position(|w| w == b"4:info").unwrap() + 6;
+        assert_eq!(torrent.raw_info.encode(), input[start..input.len() - 1]);
+
+        let single = parse_torrent(b"d4:infod6:lengthi3e4:name1:x12:piece lengthi1e6:pieces0:ee")?;
+        assert_eq!(single.info.files[0].length, 3);
+        assert!(single.info.files[0].path.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_bencode_should_report_errors() {
+        assert_eq!(parse_bencode(b"d1:bi1e1:ai2ee").unwrap_err().offset(), 7);
+        assert_eq!(parse_bencode(b"d1:ai1e1:ai2ee").unwrap_err().offset(), 7);
+        assert_eq!(parse_bencode(b"5:abc").unwrap_err().offset(), 2);
+        assert!(parse_bencode(b"i03e").is_err());
+        assert!(parse_bencode(b"i-0e").is_err());
+        assert!(parse_bencode(b"i12").is_err());
+        assert!(parse_bencode(b"l").is_err());
+        assert!(parse_bencode(b"di1ei2ee").is_err());
+        assert!(parse_bencode(b"i1ei2e").is_err());
+        assert!(parse_torrent(b"d4:infod4:name1:xee").is_err());
+    }
+}
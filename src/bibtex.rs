@@ -0,0 +1,463 @@
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{digit1, multispace0};
+use winnow::combinator::{
+    alt, cut_err, delimited, eof, opt, preceded, repeat, separated, terminated,
+};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::stream::{LocatingSlice, Location};
+use winnow::token::{one_of, take_till, take_while};
+
+use crate::ParseError;
+
+type Input<'i> = LocatingSlice<&'i str>;
+
+/// The contents of a `.bib` file. Text outside `@` entries is ignored,
+/// as BibTeX itself does.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bibliography {
+    pub entries: Vec<Entry>,
+    /// `@string` macros, in definition order.
+    pub strings: Vec<(String, String)>,
+    pub preambles: Vec<String>,
+    pub comments: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Lowercased, as in `article`.
+    pub kind: String,
+    pub key: String,
+    /// Lowercased names with macros expanded, the outer delimiters removed
+    /// and white space collapsed. Inner braces are kept.
+    pub fields: Vec<(String, String)>,
+}
+
+/// A person from an `author` or `editor` list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Name {
+    pub given: String,
+    /// Includes any "von" particle, as in `van Beethoven`.
+    pub family: String,
+    /// `Jr.`, from the `Family, Jr, Given` form.
+    pub suffix: String,
+}
+
+impl Bibliography {
+    pub fn get(&self, key: &str) -> Option<&Entry> {
+        self.entries.iter().find(|e| e.key == key)
+    }
+}
+
+impl Entry {
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(field))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn authors(&self) -> Vec<Name> {
+        self.get("author").map(parse_names).unwrap_or_default()
+    }
+
+    pub fn editors(&self) -> Vec<Name> {
+        self.get("editor").map(parse_names).unwrap_or_default()
+    }
+
+    pub fn year(&self) -> Option<i32> {
+        self.get("year")?.trim_matches(['{', '}']).parse().ok()
+    }
+}
+
+/// Splits a name list on `and`, leaving braced groups such as
+/// `{Barnes and Noble}` whole.
+pub fn parse_names(list: &str) -> Vec<Name> {
+    split_top_level(list, " and ")
+        .into_iter()
+        .map(|name| {
+            let parts = split_top_level(name, ",");
+            match parts.as_slice() {
+                [family] => {
+                    let words = split_top_level(family, " ");
+                    // The family name starts at the first lowercase word
+                    // ("von" part), or is the last word.
+                    let last = words.len().saturating_sub(1);
+                    let split = (0..last)
+                        .find(|&i| words[i].starts_with(|c: char| c.is_lowercase()))
+                        .unwrap_or(last);
+                    Name {
+                        given: words[..split].join(" "),
+                        family: words[split..].join(" "),
+                        suffix: String::new(),
+                    }
+                }
+                [family, given] => Name {
+                    given: given.to_string(),
+                    family: family.to_string(),
+                    suffix: String::new(),
+                },
+                [family, suffix, given, ..] => Name {
+                    given: given.to_string(),
+                    family: family.to_string(),
+                    suffix: suffix.to_string(),
+                },
+                [] => unreachable!("splitting yields at least one part"),
+            }
+        })
+        .filter(|name| !name.family.is_empty() || !name.given.is_empty())
+        .collect()
+}
+
+/// Splits on `separator` outside braces, trimming each part.
+fn split_top_level<'s>(text: &'s str, separator: &str) -> Vec<&'s str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    let mut i = 0;
+    while i < text.len() {
+        match text.as_bytes()[i] {
+            b'{' => depth += 1,
+            b'}' => depth -= 1,
+            _ if depth == 0 && text.as_bytes()[i..].starts_with(separator.as_bytes()) => {
+                parts.push(text[start..i].trim());
+                i += separator.len();
+                start = i;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    parts.push(text[start..].trim());
+    parts.retain(|p| !p.is_empty());
+    if parts.is_empty() {
+        parts.push("");
+    }
+    parts
+}
+
+enum Item {
+    Entry {
+        kind: String,
+        key: String,
+        fields: Vec<(String, Value)>,
+    },
+    String(String, Value),
+    Preamble(Value),
+    Comment(String),
+}
+
+/// A field value before macro expansion: the pieces joined by `#`.
+type Value = Vec<Piece>;
+
+enum Piece {
+    Literal(String),
+    /// A macro name and where it was used.
+    Macro(String, usize),
+}
+
+const MONTHS: [(&str, &str); 12] = [
+    ("jan", "January"),
+    ("feb", "February"),
+    ("mar", "March"),
+    ("apr", "April"),
+    ("may", "May"),
+    ("jun", "June"),
+    ("jul", "July"),
+    ("aug", "August"),
+    ("sep", "September"),
+    ("oct", "October"),
+    ("nov", "November"),
+    ("dec", "December"),
+];
+
+/// Parses a `.bib` file. Macros are expanded as they are read, so a
+/// `@string` must come before its first use; the month abbreviations
+/// `jan` ... `dec` are predefined.
+pub fn parse_bibtex(input: &str) -> Result<Bibliography, ParseError> {
+    let items: Vec<Item> = terminated(repeat(0.., preceded(junk, item)), (junk, eof))
+        .parse(LocatingSlice::new(input))
+        .map_err(ParseError::from)?;
+
+    let mut bib = Bibliography::default();
+    for item in items {
+        match item {
+            Item::Entry { kind, key, fields } => {
+                let fields = fields
+                    .into_iter()
+                    .map(|(name, value)| Ok((name, expand(value, &bib.strings)?)))
+                    .collect::<Result<_, ParseError>>()?;
+                bib.entries.push(Entry { kind, key, fields });
+            }
+            Item::String(name, value) => {
+                let value = expand(value, &bib.strings)?;
+                match bib.strings.iter_mut().find(|(n, _)| *n == name) {
+                    Some(existing) => existing.1 = value,
+                    None => bib.strings.push((name, value)),
+                }
+            }
+            Item::Preamble(value) => {
+                let value = expand(value, &bib.strings)?;
+                bib.preambles.push(value);
+            }
+            Item::Comment(text) => bib.comments.push(text),
+        }
+    }
+    Ok(bib)
+}
+
+fn expand(value: Value, strings: &[(String, String)]) -> Result<String, ParseError> {
+    let mut out = String::new();
+    for piece in value {
+        match piece {
+            Piece::Literal(text) => out.push_str(&text),
+            Piece::Macro(name, at) => {
+                let text = strings
+                    .iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, v)| v.as_str())
+                    .or_else(|| MONTHS.iter().find(|(m, _)| *m == name).map(|(_, v)| *v))
+                    .ok_or_else(|| ParseError::new(at, format!("undefined string `{name}`")))?;
+                out.push_str(text);
+            }
+        }
+    }
+    Ok(out.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Everything up to the next `@`.
+fn junk(input: &mut Input<'_>) -> ModalResult<()> {
+    take_till(0.., '@').void().parse_next(input)
+}
+
+fn item(input: &mut Input<'_>) -> ModalResult<Item> {
+    let kind = preceded(
+        ('@', multispace0),
+        cut_err(name).context(StrContext::Label("entry type")),
+    )
+    .parse_next(input)?
+    .to_ascii_lowercase();
+    let open = preceded(
+        multispace0,
+        cut_err(one_of(['{', '(']))
+            .context(StrContext::Expected(StrContextValue::CharLiteral('{'))),
+    )
+    .parse_next(input)?;
+    let close = if open == '{' { '}' } else { ')' };
+    let item = match kind.as_str() {
+        "comment" => {
+            let text = balanced(close).parse_next(input)?;
+            Item::Comment(text.trim().to_string())
+        }
+        "preamble" => Item::Preamble(value.parse_next(input)?),
+        "string" => {
+            let (name, value) = field.parse_next(input)?;
+            Item::String(name, value)
+        }
+        _ => {
+            let key = preceded(
+                multispace0,
+                take_while(0.., |c: char| c != ',' && c != close && !c.is_whitespace()),
+            )
+            .parse_next(input)?
+            .to_string();
+            let fields = repeat(
+                0..,
+                preceded((multispace0, ','), preceded(multispace0, opt(field))),
+            )
+            .fold(Vec::new, |mut fields, field| {
+                fields.extend(field);
+                fields
+            })
+            .parse_next(input)?;
+            Item::Entry { kind, key, fields }
+        }
+    };
+    preceded(
+        multispace0,
+        cut_err(close).context(StrContext::Expected(StrContextValue::CharLiteral(close))),
+    )
+    .parse_next(input)?;
+    Ok(item)
+}
+
+fn field(input: &mut Input<'_>) -> ModalResult<(String, Value)> {
+    let name = preceded(multispace0, name)
+        .parse_next(input)?
+        .to_ascii_lowercase();
+    preceded(
+        multispace0,
+        cut_err('=').context(StrContext::Expected(StrContextValue::CharLiteral('='))),
+    )
+    .parse_next(input)?;
+    let value = value.parse_next(input)?;
+    Ok((name, value))
+}
+
+fn value(input: &mut Input<'_>) -> ModalResult<Value> {
+    let piece = alt((
+        preceded('{', terminated(balanced('}'), '}')).map(|s| Piece::Literal(s.to_string())),
+        delimited('"', quoted, '"').map(|s: &str| Piece::Literal(s.to_string())),
+        digit1.map(|s: &str| Piece::Literal(s.to_string())),
+        (|i: &mut Input<'_>| Ok(i.current_token_start()), name)
+            .map(|(at, name): (usize, &str)| Piece::Macro(name.to_ascii_lowercase(), at)),
+    ));
+    separated(
+        1..,
+        preceded(
+            multispace0,
+            cut_err(piece).context(StrContext::Label("value")),
+        ),
+        (multispace0, '#'),
+    )
+    .parse_next(input)
+}
+
+/// Text with balanced braces, stopping before an unbalanced `close`.
+fn balanced<'i>(close: char) -> impl FnMut(&mut Input<'i>) -> ModalResult<&'i str> {
+    move |input: &mut Input<'i>| {
+        repeat::<_, _, (), _, _>(
+            0..,
+            alt((
+                take_till(1.., ['{', '}', close]).void(),
+                ('{', balanced('}'), cut_err('}')).void(),
+            )),
+        )
+        .take()
+        .context(StrContext::Expected(StrContextValue::CharLiteral(close)))
+        .parse_next(input)
+    }
+}
+
+/// The inside of `"..."`, where quotes in braces do not count.
+fn quoted<'i>(input: &mut Input<'i>) -> ModalResult<&'i str> {
+    cut_err(
+        repeat::<_, _, (), _, _>(
+            0..,
+            alt((
+                take_till(1.., ['{', '}', '"']).void(),
+                ('{', balanced('}'), '}').void(),
+            )),
+        )
+        .take(),
+    )
+    .parse_next(input)
+}
+
+/// Entry types, field names and macro names.
+fn name<'i>(input: &mut Input<'i>) -> ModalResult<&'i str> {
+    take_while(1.., |c: char| {
+        !c.is_whitespace() && !"\"#%'(),={}@".contains(c)
+    })
+    .verify(|s: &str| !s.starts_with(|c: char| c.is_ascii_digit()))
+    .parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BIB: &str = r#"
+This line is ignored.
+@String{ acm = "ACM" # " Press" }
+@preamble{ "\newcommand{\noop}[1]{}" }
+@comment{ generated {by hand} }
+
+@Article{knuth84,
+    author  = {Donald E. Knuth and van Beethoven, Ludwig and {Barnes and Noble}},
+    title   = "The {\TeX}book: a {"quote"} in {braces "ok"}",
+    journal = acm # { Journal},
+    month   = jan,
+    year    = 1984,
+}
+@book(lamport,
+    author = "Lamport, Jr., Leslie",
+    title = {LaTeX:
+             a document preparation system}
+)
+"#;
+
+    #[test]
+    fn parse_bibtex_should_work() -> Result<(), ParseError> {
+        let bib = parse_bibtex(BIB)?;
+        assert_eq!(bib.strings, [("acm".to_string(), "ACM Press".to_string())]);
+        assert_eq!(bib.preambles, [r"\newcommand{\noop}[1]{}"]);
+        assert_eq!(bib.comments, ["generated {by hand}"]);
+
+        let knuth = bib.get("knuth84").unwrap();
+        assert_eq!(knuth.kind, "article");
+        assert_eq!(
+            knuth.get("title"),
+            Some(r#"The {\TeX}book: a {"quote"} in {braces "ok"}"#)
+        );
+        assert_eq!(knuth.get("JOURNAL"), Some("ACM Press Journal"));
+        assert_eq!(knuth.get("month"), Some("January"));
+        assert_eq!(knuth.year(), Some(1984));
+
+        let lamport = bib.get("lamport").unwrap();
+        assert_eq!(
+            lamport.get("title"),
+            Some("LaTeX: a document preparation system")
+        );
+        assert_eq!(
+            lamport.authors(),
+            [Name {
+                given: "Leslie".into(),
+                family: "Lamport".into(),
+                suffix: "Jr.".into()
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_names_should_split_lists() {
+        let names = parse_names("Donald E. Knuth and van Beethoven, Ludwig and {Barnes and Noble}");
+        let pairs: Vec<_> = names
+            .iter()
+            .map(|n| (n.given.as_str(), n.family.as_str()))
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                ("Donald E.", "Knuth"),
+                ("Ludwig", "van Beethoven"),
+                ("", "{Barnes and Noble}")
+            ]
+        );
+        let von = parse_names("Ludwig van Beethoven");
+        assert_eq!(von[0].family, "van Beethoven");
+    }
+
+    #[test]
+    fn parse_names_should_handle_non_ascii_names() -> Result<(), ParseError> {
+        let bib = parse_bibtex("@book{k, author = {Gödel, Kurt and Émile Borel}}")?;
+        let pairs: Vec<_> = bib.entries[0]
+            .authors()
+            .into_iter()
+            .map(|n| (n.given, n.family))
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                ("Kurt".to_string(), "Gödel".to_string()),
+                ("Émile".to_string(), "Borel".to_string())
+            ]
+        );
+        assert_eq!(parse_names("é")[0].family, "é");
+        Ok(())
+    }
+
+    #[test]
+    fn parse_bibtex_should_report_errors() {
+        let err = parse_bibtex("@misc{a, note = nope}").unwrap_err();
+        assert_eq!(err.offset(), 16);
+        assert!(err.message().contains("nope"));
+        // A quote ends the value even inside a word.
+        assert!(parse_bibtex(r#"@misc{a, title = "a "quote""}"#).is_err());
+        assert!(parse_bibtex("@misc{a, title = {open}").is_err());
+        assert!(parse_bibtex("@misc{a, title {x}}").is_err());
+        assert!(parse_bibtex("@misc{a, title = {x}}} extra").is_ok());
+    }
+}
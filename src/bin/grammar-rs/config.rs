@@ -0,0 +1,198 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use grammar::ParseError;
+use grammar::nginx::LogFormat;
+use grammar::toml::{self, TomlTable, TomlValue};
+use serde::{Deserialize, Serialize};
+
+/// Settings loaded from `config.toml`. Every field is optional so a partial
+/// file only overrides what it mentions; command line flags win over both.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub output: OutputConfig,
+    pub nginx: NginxConfig,
+    /// Named filter expressions, e.g. `errors = "status >= 500"`.
+    pub filters: BTreeMap<String, String>,
+}
+
+impl Config {
+    /// The expression `--filter` stands for: the named filter called
+    /// `filter` if there is one, else `filter` itself.
+    pub fn filter<'a>(&'a self, filter: &'a str) -> &'a str {
+        self.filters.get(filter).map_or(filter, String::as_str)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NginxConfig {
+    pub log_format: LogFormat,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Rust debug representation
+    #[default]
+    Debug,
+    /// JSON; one document per line for line-oriented inputs
+    Json,
+}
+
+pub enum ConfigError {
+    Io(std::io::Error),
+    Parse { input: String, error: ParseError },
+}
+
+/// `$XDG_CONFIG_HOME/grammar-rs/config.toml`, falling back to
+/// `~/.config/grammar-rs/config.toml`.
+pub fn default_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("grammar-rs").join("config.toml"))
+}
+
+/// Loads the config at `path`. A missing file is only an error when the
+/// path was given explicitly.
+pub fn load(path: &Path, explicit: bool) -> Result<Config, ConfigError> {
+    let input = match std::fs::read_to_string(path) {
+        Ok(input) => input,
+        Err(e) if !explicit && e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(Config::default());
+        }
+        Err(e) => return Err(ConfigError::Io(e)),
+    };
+    parse(input)
+}
+
+fn parse(input: String) -> Result<Config, ConfigError> {
+    let table = match toml::parse_toml(&input) {
+        Ok(table) => table,
+        Err(error) => return Err(ConfigError::Parse { input, error }),
+    };
+    // The document is valid TOML; what is left are schema errors, which
+    // serde reports against the value tree rather than the text.
+    serde_json::to_value(TomlValue::Table(table))
+        .and_then(Config::deserialize)
+        .map_err(|e| {
+            let message = e.to_string();
+            let offset = message
+                .split('`')
+                .nth(1)
+                .and_then(|key| key_offset(&input, key))
+                .unwrap_or(0);
+            ConfigError::Parse {
+                error: ParseError::new(offset, message),
+                input,
+            }
+        })
+}
+
+/// Offset of the first line assigning to `key`, to point schema errors at
+/// the setting they are about.
+fn key_offset(input: &str, key: &str) -> Option<usize> {
+    let mut offset = 0;
+    for line in input.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if let Some(rest) = trimmed.strip_prefix(key)
+            && rest.trim_start().starts_with('=')
+        {
+            return Some(offset + line.len() - trimmed.len());
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Renders `config` as a TOML document.
+pub fn to_toml(config: &Config) -> Result<String, serde_json::Error> {
+    match json_to_toml(serde_json::to_value(config)?) {
+        Some(TomlValue::Table(table)) => Ok(toml::to_string(&table)),
+        _ => Ok(String::new()),
+    }
+}
+
+/// TOML has no null, so nulls are left out.
+fn json_to_toml(value: serde_json::Value) -> Option<TomlValue> {
+    use serde_json::Value;
+
+    Some(match value {
+        Value::Null => return None,
+        Value::Bool(b) => TomlValue::Boolean(b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => TomlValue::Integer(i),
+            None => TomlValue::Float(n.as_f64()?),
+        },
+        Value::String(s) => TomlValue::String(s),
+        Value::Array(values) => {
+            TomlValue::Array(values.into_iter().filter_map(json_to_toml).collect())
+        }
+        Value::Object(map) => TomlValue::Table(
+            map.into_iter()
+                .filter_map(|(k, v)| Some((k, json_to_toml(v)?)))
+                .collect::<TomlTable>(),
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_config_should_work() {
+        let input = r#"
+            [nginx]
+            log_format = "common"
+
+            [filters]
+            errors = "status >= 500"
+        "#;
+        let Ok(config) = parse(input.to_string()) else {
+            panic!("config should parse");
+        };
+        assert_eq!(config.output.format, OutputFormat::Debug);
+        assert_eq!(config.nginx.log_format, LogFormat::Common);
+        assert_eq!(config.filters["errors"], "status >= 500");
+        assert_eq!(config.filter("errors"), "status >= 500");
+        assert_eq!(config.filter("status == 404"), "status == 404");
+
+        let Err(ConfigError::Parse { error, .. }) = parse("[nginx]\nformat = 1".to_string()) else {
+            panic!("unknown keys should be rejected");
+        };
+        assert_eq!(error.offset(), 8);
+
+        let Err(ConfigError::Parse { error, .. }) = parse("[nginx\n".to_string()) else {
+            panic!("invalid TOML should be rejected");
+        };
+        assert_eq!(error.offset(), 6);
+    }
+
+    #[test]
+    fn to_toml_should_round_trip() {
+        let mut config = Config::default();
+        config.nginx.log_format = LogFormat::Common;
+        config
+            .filters
+            .insert("errors".to_string(), "status >= 500".to_string());
+        let Ok(written) = to_toml(&config) else {
+            panic!("config should serialize");
+        };
+        let Ok(parsed) = parse(written) else {
+            panic!("serialized config should parse");
+        };
+        assert_eq!(parsed.nginx.log_format, LogFormat::Common);
+        assert_eq!(parsed.filters, config.filters);
+    }
+}
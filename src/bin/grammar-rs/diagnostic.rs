@@ -0,0 +1,146 @@
+use std::path::Path;
+use std::process::ExitCode;
+
+use clap::ValueEnum;
+use grammar::ParseError;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ErrorFormat {
+    /// `path:line:column: error[code]: message`
+    Human,
+    /// One JSON object per line
+    Json,
+}
+
+/// Categories of failure, each mapped to a stable process exit code.
+///
+/// Variants are ordered by precedence: when a run hits several kinds of
+/// errors, the greatest one decides the exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ErrorKind {
+    Parse,
+    Usage,
+    Io,
+}
+
+impl ErrorKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::Parse => "parse",
+            ErrorKind::Usage => "usage",
+            ErrorKind::Io => "io",
+        }
+    }
+
+    pub fn exit_code(self) -> u8 {
+        match self {
+            ErrorKind::Parse => 1,
+            ErrorKind::Usage => 2,
+            ErrorKind::Io => 3,
+        }
+    }
+}
+
+impl Serialize for ErrorKind {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Diagnostic {
+    pub path: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub code: ErrorKind,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn io(path: &Path, err: &std::io::Error) -> Self {
+        Self {
+            path: display_path(path),
+            line: None,
+            column: None,
+            code: ErrorKind::Io,
+            message: err.to_string(),
+        }
+    }
+
+    pub fn usage(path: &Path, message: impl Into<String>) -> Self {
+        Self {
+            path: display_path(path),
+            line: None,
+            column: None,
+            code: ErrorKind::Usage,
+            message: message.into(),
+        }
+    }
+
+    /// Builds a diagnostic for `err`, which was raised while parsing `input`.
+    /// `first_line` is the 1-based line of `input` within the file, if known.
+    pub fn parse(path: &Path, input: &str, first_line: Option<usize>, err: &ParseError) -> Self {
+        let (line, column) = err.line_col(input);
+        Self {
+            path: display_path(path),
+            line: first_line.map(|first| first + line - 1),
+            column: Some(column),
+            code: ErrorKind::Parse,
+            message: err.message().to_string(),
+        }
+    }
+}
+
+fn display_path(path: &Path) -> String {
+    if path == Path::new("-") {
+        "<stdin>".to_string()
+    } else {
+        path.display().to_string()
+    }
+}
+
+/// Writes diagnostics to stderr and remembers the most severe one.
+#[derive(Debug)]
+pub struct Reporter {
+    format: ErrorFormat,
+    worst: Option<ErrorKind>,
+}
+
+impl Reporter {
+    pub fn new(format: ErrorFormat) -> Self {
+        Self {
+            format,
+            worst: None,
+        }
+    }
+
+    pub fn report(&mut self, diagnostic: Diagnostic) {
+        match self.format {
+            ErrorFormat::Human => {
+                let mut location = diagnostic.path.clone();
+                if let Some(line) = diagnostic.line {
+                    location.push_str(&format!(":{}", line));
+                }
+                if let Some(column) = diagnostic.column {
+                    location.push_str(&format!(":{}", column));
+                }
+                eprintln!(
+                    "{}: error[{}]: {}",
+                    location,
+                    diagnostic.code.as_str(),
+                    diagnostic.message
+                );
+            }
+            ErrorFormat::Json => match serde_json::to_string(&diagnostic) {
+                Ok(line) => eprintln!("{}", line),
+                Err(e) => eprintln!("failed to serialize diagnostic: {}", e),
+            },
+        }
+        self.worst = self.worst.max(Some(diagnostic.code));
+    }
+
+    pub fn exit_code(&self) -> ExitCode {
+        ExitCode::from(self.worst.map(ErrorKind::exit_code).unwrap_or(0))
+    }
+}
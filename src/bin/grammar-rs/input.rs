@@ -0,0 +1,399 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, SyncSender};
+
+use clap::Args;
+use grammar::ParseError;
+use grammar::glob::Glob;
+
+use crate::diagnostic::{Diagnostic, Reporter};
+use crate::progress::{Bars, Progress};
+
+/// Results buffered between the workers and the printing thread.
+const CHANNEL_CAPACITY: usize = 1024;
+/// Bytes read per step while searching backwards for `--tail`.
+const TAIL_CHUNK: usize = 64 * 1024;
+
+/// Expands the files given on the command line: `-` is stdin, directories are
+/// walked when `recursive` is set, and patterns such as `access.log*` that the
+/// shell left alone are matched here. Problems are reported and skipped.
+pub fn expand(args: &[PathBuf], recursive: bool, reporter: &mut Reporter) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for arg in args {
+        if arg == Path::new("-") || arg.exists() {
+            collect(arg, recursive, &mut files, reporter);
+            continue;
+        }
+        let pattern = arg.to_string_lossy();
+        if !pattern.contains(['*', '?', '[', '{']) {
+            let e = io::Error::from(io::ErrorKind::NotFound);
+            reporter.report(Diagnostic::io(arg, &e));
+            continue;
+        }
+        let matches = match Glob::new(&pattern) {
+            Ok(glob) => glob_matches(&glob),
+            Err(e) => {
+                reporter.report(Diagnostic::usage(arg, e.to_string()));
+                continue;
+            }
+        };
+        if matches.is_empty() {
+            let e = io::Error::new(io::ErrorKind::NotFound, "no files match the pattern");
+            reporter.report(Diagnostic::io(arg, &e));
+        }
+        for path in matches {
+            collect(&path, recursive, &mut files, reporter);
+        }
+    }
+    files
+}
+
+/// Walks the directories under the pattern's base, no deeper than it can
+/// match, and returns the matching paths in sorted order. Unreadable
+/// directories are skipped, as a shell would.
+fn glob_matches(glob: &Glob) -> Vec<PathBuf> {
+    let base = PathBuf::from(glob.base());
+    let mut matches = Vec::new();
+    let mut pending = vec![(base, 0)];
+    while let Some((dir, depth)) = pending.pop() {
+        let read = if dir.as_os_str().is_empty() {
+            std::fs::read_dir(".")
+        } else {
+            std::fs::read_dir(&dir)
+        };
+        let Ok(entries) = read else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = dir.join(entry.file_name());
+            if glob.is_match(&path.to_string_lossy()) {
+                matches.push(path.clone());
+            }
+            let deeper = glob.max_depth().is_none_or(|max| depth + 1 < max);
+            if deeper && path.is_dir() && !path.is_symlink() {
+                pending.push((path, depth + 1));
+            }
+        }
+    }
+    matches.sort();
+    matches
+}
+
+fn collect(path: &Path, recursive: bool, files: &mut Vec<PathBuf>, reporter: &mut Reporter) {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return;
+    }
+    if !recursive {
+        reporter.report(Diagnostic::usage(
+            path,
+            "is a directory, pass --recursive to read the files inside",
+        ));
+        return;
+    }
+    let entries = match std::fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) => return reporter.report(Diagnostic::io(path, &e)),
+    };
+    let mut children = Vec::new();
+    for entry in entries {
+        match entry {
+            Ok(entry) => children.push(entry.path()),
+            Err(e) => reporter.report(Diagnostic::io(path, &e)),
+        }
+    }
+    children.sort();
+    for child in children {
+        // Symlinked directories are not followed, so loops cannot occur.
+        if child.is_symlink() && child.is_dir() {
+            continue;
+        }
+        collect(&child, recursive, files, reporter);
+    }
+}
+
+/// Opens `path` (or stdin for `-`) together with a progress bar sized to it.
+pub fn open(path: &Path, bars: &Bars) -> io::Result<(Box<dyn Read>, Progress)> {
+    if path == Path::new("-") {
+        let progress = bars.add(path, None);
+        let reader = progress.reader(io::stdin(), 0);
+        Ok((Box::new(reader), progress))
+    } else {
+        open_file(path, bars, None)
+    }
+}
+
+/// Opens a regular file, positioned at its last `tail` lines if given.
+fn open_file(
+    path: &Path,
+    bars: &Bars,
+    tail: Option<usize>,
+) -> io::Result<(Box<dyn Read>, Progress)> {
+    let mut file = File::open(path)?;
+    let total = file.metadata().ok().map(|m| m.len());
+    let start = match tail {
+        Some(n) => tail_offset(&mut file, n)?,
+        None => 0,
+    };
+    file.seek(SeekFrom::Start(start))?;
+    let progress = bars.add(path, total);
+    let reader = progress.reader(file, start);
+    Ok((Box::new(reader), progress))
+}
+
+enum Event {
+    Output(String),
+    Diagnostic(Diagnostic),
+}
+
+/// Where a worker sends what it produced for one input.
+pub struct Sink {
+    tx: SyncSender<Event>,
+}
+
+impl Sink {
+    /// Queues one chunk of output. Returns `false` once nobody is listening
+    /// any more (stdout was closed), so the worker can stop early.
+    pub fn output(&self, s: String) -> bool {
+        self.tx.send(Event::Output(s)).is_ok()
+    }
+
+    pub fn report(&self, diagnostic: Diagnostic) -> bool {
+        self.tx.send(Event::Diagnostic(diagnostic)).is_ok()
+    }
+}
+
+/// Runs `parse` for every input on a pool of worker threads. The calling
+/// thread owns stdout and the reporter, so output of different inputs
+/// interleaves by whole chunks and never mid-line.
+pub fn run<F>(inputs: &[PathBuf], bars: &Bars, reporter: &mut Reporter, parse: F)
+where
+    F: Fn(&Path, &Sink) + Sync,
+{
+    let workers = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(inputs.len());
+    let next = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::sync_channel(CHANNEL_CAPACITY);
+
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            let sink = Sink { tx: tx.clone() };
+            let (next, parse) = (&next, &parse);
+            scope.spawn(move || {
+                while let Some(path) = inputs.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    parse(path, &sink);
+                }
+            });
+        }
+        drop(tx);
+
+        let mut stdout = io::stdout();
+        for event in rx {
+            match event {
+                Event::Output(s) => {
+                    if bars.print(|| writeln!(stdout, "{}", s)).is_err() {
+                        // Dropping the receiver makes every pending send fail,
+                        // which winds the workers down.
+                        break;
+                    }
+                }
+                Event::Diagnostic(d) => bars.suspend(|| reporter.report(d)),
+            }
+        }
+    });
+}
+
+/// Which lines of each input get parsed. Selection happens before parsing,
+/// so skipped lines cost no more than reading them.
+#[derive(Debug, Clone, Default, Args)]
+pub struct Selection {
+    /// Parse a random fraction of lines, e.g. 0.01 for about 1%
+    #[arg(long, value_name = "RATE", value_parser = parse_rate)]
+    pub sample: Option<f64>,
+
+    /// Only look at the first N lines of each input
+    #[arg(long, value_name = "N", conflicts_with = "tail")]
+    pub head: Option<usize>,
+
+    /// Only look at the last N lines of each input
+    #[arg(long, value_name = "N")]
+    pub tail: Option<usize>,
+}
+
+impl Selection {
+    fn sampled(&self) -> bool {
+        self.sample.is_none_or(|rate| fastrand::f64() < rate)
+    }
+}
+
+fn parse_rate(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(rate) if rate > 0.0 && rate <= 1.0 => Ok(rate),
+        _ => Err("expected a number in (0, 1]".to_string()),
+    }
+}
+
+/// Offset of the first byte of the last `n` lines of `file`, found by
+/// scanning backwards from the end so huge files are not read in full.
+fn tail_offset<R: Read + Seek>(file: &mut R, n: usize) -> io::Result<u64> {
+    let len = file.seek(SeekFrom::End(0))?;
+    if n == 0 {
+        return Ok(len);
+    }
+    let mut buf = vec![0; TAIL_CHUNK];
+    let mut pos = len;
+    let mut newlines = 0;
+    while pos > 0 {
+        let read = (buf.len() as u64).min(pos) as usize;
+        pos -= read as u64;
+        file.seek(SeekFrom::Start(pos))?;
+        file.read_exact(&mut buf[..read])?;
+        for (i, _) in buf[..read]
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, b)| **b == b'\n')
+        {
+            let offset = pos + i as u64;
+            // The newline ending the file terminates the last line rather
+            // than starting another one.
+            if offset + 1 == len {
+                continue;
+            }
+            newlines += 1;
+            if newlines == n {
+                return Ok(offset + 1);
+            }
+        }
+    }
+    Ok(0)
+}
+
+/// Feeds the selected lines of `path` to `f` with their 1-based number,
+/// reporting read errors and invalid UTF-8 to `sink`. Line numbers are
+/// unknown when `--tail` seeked past the start of the file. Stops early when
+/// `f` returns `false`.
+pub fn for_each_line<F>(path: &Path, bars: &Bars, sink: &Sink, selection: &Selection, mut f: F)
+where
+    F: FnMut(Option<usize>, &str) -> bool,
+{
+    let opened = if path == Path::new("-") {
+        open(path, bars).map(|(reader, progress)| (reader, progress, false))
+    } else {
+        open_file(path, bars, selection.tail).map(|(reader, progress)| (reader, progress, true))
+    };
+    let (reader, mut progress, seekable) = match opened {
+        Ok(opened) => opened,
+        Err(e) => {
+            sink.report(Diagnostic::io(path, &e));
+            return;
+        }
+    };
+    // Without a seekable file the last lines can only be found by keeping a
+    // window of them until the input ends.
+    let mut window = VecDeque::new();
+    let window_len = selection.tail.filter(|_| !seekable);
+
+    let mut reader = BufReader::new(reader);
+    let mut buf = Vec::new();
+    let mut line_no = 0;
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) => {
+                sink.report(Diagnostic::io(path, &e));
+                break;
+            }
+        }
+        line_no += 1;
+        progress.line();
+        if selection.head.is_some_and(|head| line_no > head) {
+            break;
+        }
+        if let Some(n) = window_len {
+            window.push_back((line_no, buf.clone()));
+            if window.len() > n {
+                window.pop_front();
+            }
+            continue;
+        }
+        let number = if seekable && selection.tail.is_some() {
+            None
+        } else {
+            Some(line_no)
+        };
+        if selection.sampled() && !emit(path, sink, number, &buf, &mut f) {
+            return;
+        }
+    }
+    for (line_no, buf) in window {
+        if selection.sampled() && !emit(path, sink, Some(line_no), &buf, &mut f) {
+            return;
+        }
+    }
+}
+
+fn emit<F>(path: &Path, sink: &Sink, line_no: Option<usize>, buf: &[u8], f: &mut F) -> bool
+where
+    F: FnMut(Option<usize>, &str) -> bool,
+{
+    match std::str::from_utf8(buf) {
+        Ok(line) => f(line_no, line.trim_end_matches(['\n', '\r'])),
+        Err(e) => {
+            let error = ParseError::new(e.valid_up_to(), "invalid UTF-8");
+            let line = String::from_utf8_lossy(buf);
+            sink.report(Diagnostic::parse(path, &line, line_no, &error))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostic::ErrorFormat;
+
+    #[test]
+    fn expand_should_walk_globs_and_directories() -> io::Result<()> {
+        let dir = std::env::temp_dir().join(format!("grammar-rs-expand-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("nested"))?;
+        for name in [
+            "access.log",
+            "access.log.1",
+            "error.log",
+            "nested/access.log",
+        ] {
+            std::fs::write(dir.join(name), "")?;
+        }
+
+        let mut reporter = Reporter::new(ErrorFormat::Json);
+        let files = expand(&[dir.join("access.log*")], false, &mut reporter);
+        assert_eq!(files, [dir.join("access.log"), dir.join("access.log.1")]);
+
+        let files = expand(std::slice::from_ref(&dir), true, &mut reporter);
+        assert_eq!(files.len(), 4);
+        assert_eq!(files[3], dir.join("nested/access.log"));
+
+        assert!(expand(std::slice::from_ref(&dir), false, &mut reporter).is_empty());
+        std::fs::remove_dir_all(&dir)
+    }
+
+    #[test]
+    fn tail_offset_should_find_last_lines() -> io::Result<()> {
+        let mut input = io::Cursor::new(b"a\nbb\nccc\n".to_vec());
+        assert_eq!(tail_offset(&mut input, 1)?, 5);
+        assert_eq!(tail_offset(&mut input, 2)?, 2);
+        assert_eq!(tail_offset(&mut input, 5)?, 0);
+        assert_eq!(tail_offset(&mut input, 0)?, 9);
+
+        let mut input = io::Cursor::new(b"a\nbb".to_vec());
+        assert_eq!(tail_offset(&mut input, 1)?, 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,400 @@
+mod config;
+mod diagnostic;
+mod input;
+mod progress;
+mod top;
+mod uniq;
+
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use grammar::json::parse_json;
+use grammar::nginx::{LogFormat, NginxLog, parse_nginx_log_with};
+use grammar::predicate::{Predicate, parse_predicate};
+use serde::Serialize;
+
+use config::{Config, ConfigError, OutputFormat};
+use diagnostic::{Diagnostic, ErrorFormat, ErrorKind, Reporter};
+use input::{Selection, Sink};
+use progress::Bars;
+
+const EXIT_CODES: &str = "\
+Exit codes:
+  0  success
+  1  at least one input failed to parse
+  2  invalid command line usage
+  3  an input could not be read";
+
+/// Parse structured text with the grammars implemented in this crate.
+#[derive(Debug, Parser)]
+#[command(name = "grammar-rs", version, after_help = EXIT_CODES)]
+struct Cli {
+    /// How diagnostics are written to stderr
+    #[arg(long, value_enum, default_value_t = ErrorFormat::Human, global = true)]
+    error_format: ErrorFormat,
+
+    /// How parsed values are written to stdout [default: from config, else debug]
+    #[arg(long, value_enum, global = true)]
+    output: Option<OutputFormat>,
+
+    /// Config file to use instead of ~/.config/grammar-rs/config.toml
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Never show progress bars
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Read the files inside directories given as inputs, recursively
+    #[arg(short, long, global = true)]
+    recursive: bool,
+
+    /// Tag every output record with the file it came from
+    #[arg(short = 'H', long, global = true)]
+    with_filename: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Parse JSON documents and print their value tree
+    Json {
+        /// Files, directories or glob patterns to parse, `-` reads stdin
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Work with nginx access logs
+    Nginx {
+        #[command(subcommand)]
+        command: NginxCommand,
+    },
+    /// Print a shell completion script to stdout
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Print the config file location and the settings loaded from it
+    Config,
+}
+
+#[derive(Debug, Subcommand)]
+enum NginxCommand {
+    /// Parse access log lines and print one record per line
+    Parse {
+        /// Layout of each line [default: from config, else combined]
+        #[arg(long)]
+        log_format: Option<LogFormat>,
+
+        /// Only keep records matching EXPR, e.g. `status_code >= 500 && path =~ "^/api"`, or
+        /// the named filter EXPR from the config
+        #[arg(long, value_name = "EXPR")]
+        filter: Option<String>,
+
+        #[command(flatten)]
+        selection: Selection,
+
+        /// Files, directories or glob patterns to parse, `-` reads stdin
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Live dashboard of traffic in a log file, following it as it grows
+    Top {
+        /// Layout of each line [default: from config, else combined]
+        #[arg(long)]
+        log_format: Option<LogFormat>,
+
+        file: PathBuf,
+    },
+    /// Count the distinct values of one field, most frequent first
+    Uniq {
+        /// Field to extract from every record
+        #[arg(long, value_enum)]
+        field: uniq::Field,
+
+        /// Layout of each line [default: from config, else combined]
+        #[arg(long)]
+        log_format: Option<LogFormat>,
+
+        /// Only keep records matching EXPR, e.g. `status_code >= 500 && path =~ "^/api"`, or
+        /// the named filter EXPR from the config
+        #[arg(long, value_name = "EXPR")]
+        filter: Option<String>,
+
+        #[command(flatten)]
+        selection: Selection,
+
+        /// Files, directories or glob patterns to parse, `-` reads stdin
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            return if e.use_stderr() {
+                ExitCode::from(ErrorKind::Usage.exit_code())
+            } else {
+                ExitCode::SUCCESS
+            };
+        }
+    };
+
+    let mut reporter = Reporter::new(cli.error_format);
+    let config_path = cli.config.clone().or_else(config::default_path);
+    let config = match &config_path {
+        Some(path) => match config::load(path, cli.config.is_some()) {
+            Ok(config) => config,
+            Err(ConfigError::Io(e)) => {
+                reporter.report(Diagnostic::io(path, &e));
+                return reporter.exit_code();
+            }
+            Err(ConfigError::Parse { input, error }) => {
+                reporter.report(Diagnostic::parse(path, &input, Some(1), &error));
+                return reporter.exit_code();
+            }
+        },
+        None => Config::default(),
+    };
+    let bars = Bars::new(!cli.quiet && std::io::stderr().is_terminal());
+    let ctx = Context {
+        output: cli.output.unwrap_or(config.output.format),
+        with_filename: cli.with_filename,
+    };
+
+    match cli.command {
+        Command::Json { files } => {
+            let files = input::expand(&files, cli.recursive, &mut reporter);
+            input::run(&files, &bars, &mut reporter, |path, sink| {
+                json(path, &ctx, &bars, sink)
+            });
+        }
+        Command::Nginx {
+            command:
+                NginxCommand::Parse {
+                    log_format,
+                    filter,
+                    selection,
+                    files,
+                },
+        } => {
+            let format = log_format.unwrap_or(config.nginx.log_format);
+            let filter = match compile_filter(&config, filter.as_deref()) {
+                Ok(filter) => filter,
+                Err(e) => return usage_error(&e),
+            };
+            let files = input::expand(&files, cli.recursive, &mut reporter);
+            input::run(&files, &bars, &mut reporter, |path, sink| {
+                nginx_parse(path, format, filter.as_ref(), &selection, &ctx, &bars, sink)
+            });
+        }
+        Command::Nginx {
+            command: NginxCommand::Top { log_format, file },
+        } => {
+            let format = log_format.unwrap_or(config.nginx.log_format);
+            if !std::io::stdout().is_terminal() {
+                reporter.report(Diagnostic::usage(&file, "nginx top needs a terminal"));
+            } else if let Err(e) = top::run(&file, format) {
+                reporter.report(Diagnostic::io(&file, &e));
+            }
+        }
+        Command::Nginx {
+            command:
+                NginxCommand::Uniq {
+                    field,
+                    log_format,
+                    filter,
+                    selection,
+                    files,
+                },
+        } => {
+            let format = log_format.unwrap_or(config.nginx.log_format);
+            let filter = match compile_filter(&config, filter.as_deref()) {
+                Ok(filter) => filter,
+                Err(e) => return usage_error(&e),
+            };
+            let files = input::expand(&files, cli.recursive, &mut reporter);
+            let counts = uniq::Counts::new(field, filter);
+            input::run(&files, &bars, &mut reporter, |path, sink| {
+                counts.add_file(path, format, &selection, &bars, sink)
+            });
+            // A closed stdout is not an error worth reporting.
+            let _ = uniq::print(&counts.sorted(), ctx.output);
+        }
+        Command::Completions { shell } => {
+            let mut cmd = Cli::command();
+            let name = cmd.get_name().to_string();
+            clap_complete::generate(shell, &mut cmd, name, &mut std::io::stdout());
+        }
+        Command::Config => {
+            match &config_path {
+                Some(path) => println!("# {}", path.display()),
+                None => println!("# no config directory found"),
+            }
+            match config::to_toml(&config) {
+                Ok(s) => print!("{}", s),
+                Err(e) => eprintln!("failed to serialize config: {}", e),
+            }
+        }
+    }
+    reporter.exit_code()
+}
+
+/// Settings shared by every subcommand once flags and config are merged.
+struct Context {
+    output: OutputFormat,
+    with_filename: bool,
+}
+
+#[derive(Serialize)]
+struct Keyed<'a, T> {
+    path: String,
+    value: &'a T,
+}
+
+fn format_value<T>(value: &T, path: &Path, ctx: &Context, pretty: bool) -> String
+where
+    T: Serialize + std::fmt::Debug,
+{
+    match (ctx.output, ctx.with_filename) {
+        (OutputFormat::Debug, false) if pretty => format!("{:#?}", value),
+        (OutputFormat::Debug, false) => format!("{:?}", value),
+        (OutputFormat::Debug, true) if pretty => format!("{}: {:#?}", path.display(), value),
+        (OutputFormat::Debug, true) => format!("{}: {:?}", path.display(), value),
+        (OutputFormat::Json, with_filename) => {
+            let s = match (with_filename, pretty) {
+                (false, false) => serde_json::to_string(value),
+                (false, true) => serde_json::to_string_pretty(value),
+                (true, pretty) => {
+                    let keyed = Keyed {
+                        path: path.display().to_string(),
+                        value,
+                    };
+                    if pretty {
+                        serde_json::to_string_pretty(&keyed)
+                    } else {
+                        serde_json::to_string(&keyed)
+                    }
+                }
+            };
+            s.unwrap_or_else(|e| format!("failed to serialize value: {}", e))
+        }
+    }
+}
+
+fn json(path: &Path, ctx: &Context, bars: &Bars, sink: &Sink) {
+    let mut input = String::new();
+    let (mut reader, progress) = match input::open(path, bars) {
+        Ok(opened) => opened,
+        Err(e) => {
+            sink.report(Diagnostic::io(path, &e));
+            return;
+        }
+    };
+    if let Err(e) = reader.read_to_string(&mut input) {
+        sink.report(Diagnostic::io(path, &e));
+        return;
+    }
+    drop(progress);
+    match parse_json(&input) {
+        Ok(v) => sink.output(format_value(&v, path, ctx, true)),
+        Err(e) => sink.report(Diagnostic::parse(path, &input, Some(1), &e)),
+    };
+}
+
+/// Compiles `--filter`, which may name a filter from the config. Errors
+/// read like clap's own, since they are about the command line too.
+fn compile_filter(config: &Config, filter: Option<&str>) -> Result<Option<Predicate>, clap::Error> {
+    let Some(filter) = filter else {
+        return Ok(None);
+    };
+    parse_filter(config.filter(filter)).map(Some).map_err(|e| {
+        Cli::command().error(
+            clap::error::ErrorKind::ValueValidation,
+            format!("invalid value '{filter}' for '--filter <EXPR>': {e}"),
+        )
+    })
+}
+
+fn usage_error(error: &clap::Error) -> ExitCode {
+    let _ = error.print();
+    ExitCode::from(ErrorKind::Usage.exit_code())
+}
+
+/// Compiles a filter expression, rejecting field names access log records
+/// lack.
+fn parse_filter(s: &str) -> Result<Predicate, String> {
+    let predicate = parse_predicate(s).map_err(|e| e.to_string())?;
+    if let Some(field) = predicate
+        .fields()
+        .into_iter()
+        .find(|field| !NginxLog::FIELDS.contains(field))
+    {
+        return Err(format!(
+            "unknown field `{}`; expected one of {}",
+            field,
+            NginxLog::FIELDS.join(", ")
+        ));
+    }
+    Ok(predicate)
+}
+
+fn nginx_parse(
+    path: &Path,
+    format: LogFormat,
+    filter: Option<&Predicate>,
+    selection: &Selection,
+    ctx: &Context,
+    bars: &Bars,
+    sink: &Sink,
+) {
+    input::for_each_line(path, bars, sink, selection, |line_no, line| {
+        if line.trim().is_empty() {
+            return true;
+        }
+        match parse_nginx_log_with(line, format) {
+            Ok(log) if filter.is_some_and(|f| !f.eval(&log)) => true,
+            Ok(log) => sink.output(format_value(&log, path, ctx, false)),
+            Err(e) => sink.report(Diagnostic::parse(path, line, line_no, &e)),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_cli() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn compile_filter_should_resolve_named_filters() {
+        let mut config = Config::default();
+        config
+            .filters
+            .insert("errors".to_string(), "status_code >= 500".to_string());
+        let Ok(Some(errors)) = compile_filter(&config, Some("errors")) else {
+            panic!("named filters should compile");
+        };
+        assert_eq!(errors.fields(), ["status_code"]);
+        assert!(matches!(
+            compile_filter(&config, Some("size > 0")),
+            Ok(Some(_))
+        ));
+        assert!(matches!(compile_filter(&config, None), Ok(None)));
+
+        let Err(e) = compile_filter(&config, Some("warnings")) else {
+            panic!("unknown names are not fields");
+        };
+        assert!(e.to_string().contains("unknown field `warnings`"));
+    }
+}
@@ -0,0 +1,107 @@
+use std::io::{IsTerminal, Read};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use grammar::progress::ProgressReader;
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+/// Lines between refreshes of the lines/sec message; formatting it for every
+/// line would dominate the cost of parsing.
+const LINE_UPDATE_INTERVAL: u64 = 4096;
+
+/// The set of progress bars of one run, drawn together on stderr. Hidden when
+/// disabled, which keeps every call site unconditional.
+#[derive(Clone)]
+pub struct Bars {
+    multi: MultiProgress,
+    stdout_tty: bool,
+}
+
+impl Bars {
+    pub fn new(enabled: bool) -> Self {
+        let target = if enabled {
+            ProgressDrawTarget::stderr()
+        } else {
+            ProgressDrawTarget::hidden()
+        };
+        Self {
+            multi: MultiProgress::with_draw_target(target),
+            stdout_tty: std::io::stdout().is_terminal(),
+        }
+    }
+
+    /// Adds a bar for `path`; `total` is its size in bytes when known.
+    pub fn add(&self, path: &Path, total: Option<u64>) -> Progress {
+        let bar = self.multi.add(ProgressBar::with_draw_target(
+            total,
+            ProgressDrawTarget::hidden(),
+        ));
+        let template = if total.is_some() {
+            "{prefix} [{elapsed_precise}] {wide_bar} {bytes}/{total_bytes} ({binary_bytes_per_sec}, {msg}, ETA {eta})"
+        } else {
+            "{prefix} [{elapsed_precise}] {spinner} {bytes} ({binary_bytes_per_sec}, {msg})"
+        };
+        if let Ok(style) = ProgressStyle::with_template(template) {
+            bar.set_style(style);
+        }
+        bar.set_prefix(path.display().to_string());
+        bar.enable_steady_tick(Duration::from_millis(100));
+        Progress {
+            bar,
+            lines: 0,
+            started: Instant::now(),
+        }
+    }
+
+    /// Runs `f` with the bars cleared, so output written to the terminal does
+    /// not get mixed into them.
+    pub fn suspend<T>(&self, f: impl FnOnce() -> T) -> T {
+        self.multi.suspend(f)
+    }
+
+    /// Like [`Bars::suspend`] for output going to stdout, which only needs
+    /// the bars out of the way when both share a terminal.
+    pub fn print<T>(&self, f: impl FnOnce() -> T) -> T {
+        if self.stdout_tty {
+            self.multi.suspend(f)
+        } else {
+            f()
+        }
+    }
+}
+
+/// Progress of a single input.
+pub struct Progress {
+    bar: ProgressBar,
+    lines: u64,
+    started: Instant,
+}
+
+impl Progress {
+    /// Wraps `reader`, which starts `start` bytes into the input, so that
+    /// every byte read advances the bar.
+    pub fn reader<R: Read>(
+        &self,
+        reader: R,
+        start: u64,
+    ) -> ProgressReader<R, impl FnMut(u64) + use<R>> {
+        let bar = self.bar.clone();
+        bar.set_position(start);
+        ProgressReader::new(reader, move |n| bar.set_position(start + n))
+    }
+
+    pub fn line(&mut self) {
+        self.lines += 1;
+        if self.lines.is_multiple_of(LINE_UPDATE_INTERVAL) {
+            let secs = self.started.elapsed().as_secs_f64().max(f64::EPSILON);
+            self.bar
+                .set_message(format!("{:.0} lines/s", self.lines as f64 / secs));
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.bar.finish_and_clear();
+    }
+}
@@ -0,0 +1,286 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+use grammar::nginx::{LogFormat, NginxLog, parse_nginx_log_with};
+use ratatui::Frame;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::widgets::{Block, Paragraph, Row, Sparkline, Table};
+
+/// Seconds of history kept for the requests-per-second sparkline.
+const WINDOW_SECS: usize = 120;
+/// Seconds averaged for the headline QPS figure.
+const QPS_SECS: usize = 10;
+const TOP_N: usize = 15;
+const REFRESH: Duration = Duration::from_millis(250);
+const POLL: Duration = Duration::from_millis(200);
+
+/// Running aggregates over every line seen so far.
+#[derive(Debug, Default)]
+pub struct Stats {
+    total: u64,
+    errors: u64,
+    /// Hits per status class, indexed by `status / 100`.
+    classes: [u64; 6],
+    paths: HashMap<String, u64>,
+    addrs: HashMap<IpAddr, u64>,
+    /// Requests per second of log time, oldest first; the last bucket is the
+    /// second of `latest`.
+    per_second: VecDeque<u64>,
+    latest: Option<i64>,
+}
+
+impl Stats {
+    pub fn record(&mut self, log: &NginxLog) {
+        self.total += 1;
+        let class = (log.status_code / 100) as usize;
+        self.classes[class.min(self.classes.len() - 1)] += 1;
+        *self.paths.entry(log.path.clone()).or_default() += 1;
+        *self.addrs.entry(log.addr).or_default() += 1;
+
+        // QPS is bucketed by the timestamp in the log rather than by arrival,
+        // so replaying an old file still shows its real traffic shape.
+        let ts = log.datetime.timestamp();
+        let latest = *self.latest.get_or_insert(ts);
+        if ts > latest {
+            let gap = ((ts - latest) as usize).min(WINDOW_SECS);
+            self.per_second.extend(std::iter::repeat_n(0, gap));
+            self.latest = Some(ts);
+        } else if self.per_second.is_empty() {
+            self.per_second.push_back(0);
+        }
+        let back = (self.latest.unwrap_or(ts) - ts) as usize;
+        if let Some(idx) = self.per_second.len().checked_sub(back + 1) {
+            self.per_second[idx] += 1;
+        }
+        while self.per_second.len() > WINDOW_SECS {
+            self.per_second.pop_front();
+        }
+    }
+
+    pub fn record_error(&mut self) {
+        self.errors += 1;
+    }
+
+    /// Average requests per second over the trailing [`QPS_SECS`] seconds.
+    pub fn qps(&self) -> f64 {
+        let n = self.per_second.len().min(QPS_SECS);
+        if n == 0 {
+            return 0.0;
+        }
+        self.per_second.iter().rev().take(n).sum::<u64>() as f64 / n as f64
+    }
+
+    fn top<K: ToString>(counts: &HashMap<K, u64>) -> Vec<(String, u64)> {
+        let mut top: Vec<_> = counts.iter().map(|(k, v)| (k.to_string(), *v)).collect();
+        top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top.truncate(TOP_N);
+        top
+    }
+}
+
+enum Update {
+    Log(Box<NginxLog>),
+    Error,
+    Io(io::Error),
+}
+
+/// Shows a live dashboard for `path` until the user quits. The file is read
+/// from the start and then followed like `tail -f`.
+pub fn run(path: &Path, format: LogFormat) -> io::Result<()> {
+    let file = File::open(path)?;
+    let (tx, rx) = mpsc::channel();
+    let stop = AtomicBool::new(false);
+    // Set up the terminal first: once the follower runs, every way out of
+    // the scope has to stop it, or joining it never returns.
+    let mut terminal = ratatui::try_init()?;
+
+    std::thread::scope(|scope| {
+        scope.spawn(|| follow(file, format, &tx, &stop));
+        let result = dashboard(&mut terminal, path, &rx);
+        ratatui::restore();
+        stop.store(true, Ordering::Relaxed);
+        result
+    })
+}
+
+fn dashboard(
+    terminal: &mut ratatui::DefaultTerminal,
+    path: &Path,
+    rx: &Receiver<Update>,
+) -> io::Result<()> {
+    let mut stats = Stats::default();
+    loop {
+        for update in rx.try_iter() {
+            match update {
+                Update::Log(log) => stats.record(&log),
+                Update::Error => stats.record_error(),
+                Update::Io(e) => return Err(e),
+            }
+        }
+        terminal.draw(|frame| render(frame, path, &stats))?;
+
+        if event::poll(REFRESH)?
+            && let Event::Key(key) = event::read()?
+            && quits(key)
+        {
+            return Ok(());
+        }
+    }
+}
+
+fn quits(key: KeyEvent) -> bool {
+    let ctrl_c = key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c');
+    key.kind == KeyEventKind::Press
+        && (ctrl_c || matches!(key.code, KeyCode::Char('q') | KeyCode::Esc))
+}
+
+fn follow(file: File, format: LogFormat, tx: &Sender<Update>, stop: &AtomicBool) {
+    let mut reader = BufReader::new(file);
+    let mut line = String::new();
+    let mut pos = 0u64;
+    while !stop.load(Ordering::Relaxed) {
+        match reader.read_line(&mut line) {
+            // A line without its newline is still being written; keep it and
+            // wait for the rest.
+            Ok(n) if n > 0 && line.ends_with('\n') => {
+                pos += line.len() as u64;
+                let update = match parse_nginx_log_with(line.trim_end(), format) {
+                    Ok(log) => Update::Log(Box::new(log)),
+                    Err(_) => Update::Error,
+                };
+                line.clear();
+                if tx.send(update).is_err() {
+                    return;
+                }
+            }
+            Ok(_) => {
+                std::thread::sleep(POLL);
+                // A file shorter than what was already read has been
+                // truncated or rotated in place: start over.
+                let len = reader.get_ref().metadata().map(|m| m.len()).unwrap_or(pos);
+                if len < pos {
+                    if let Err(e) = reader.seek(SeekFrom::Start(0)) {
+                        let _ = tx.send(Update::Io(e));
+                        return;
+                    }
+                    pos = 0;
+                    line.clear();
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                line.clear();
+                if tx.send(Update::Error).is_err() {
+                    return;
+                }
+            }
+            Err(e) => {
+                let _ = tx.send(Update::Io(e));
+                return;
+            }
+        }
+    }
+}
+
+fn render(frame: &mut Frame, path: &Path, stats: &Stats) {
+    let [header, sparkline, body] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Length(8),
+        Constraint::Min(5),
+    ])
+    .areas(frame.area());
+    let [classes, paths, addrs] = Layout::horizontal([
+        Constraint::Length(20),
+        Constraint::Percentage(60),
+        Constraint::Percentage(40),
+    ])
+    .areas(body);
+
+    let summary = format!(
+        "requests {}   parse errors {}   qps ({}s) {:.1}",
+        stats.total,
+        stats.errors,
+        QPS_SECS,
+        stats.qps()
+    );
+    let title = format!(" {} — q to quit ", path.display());
+    frame.render_widget(
+        Paragraph::new(summary).block(Block::bordered().title(title)),
+        header,
+    );
+
+    let data: Vec<u64> = stats.per_second.iter().copied().collect();
+    let skip = data
+        .len()
+        .saturating_sub(sparkline.width.saturating_sub(2) as usize);
+    frame.render_widget(
+        Sparkline::default()
+            .block(Block::bordered().title(" requests/s "))
+            .data(&data[skip..]),
+        sparkline,
+    );
+
+    let rows = stats
+        .classes
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(class, n)| Row::new(vec![format!("{}xx", class), n.to_string()]));
+    let widths = [Constraint::Length(6), Constraint::Min(6)];
+    frame.render_widget(
+        Table::new(rows, widths).block(Block::bordered().title(" status ")),
+        classes,
+    );
+
+    frame.render_widget(top_table(" top paths ", Stats::top(&stats.paths)), paths);
+    frame.render_widget(top_table(" top clients ", Stats::top(&stats.addrs)), addrs);
+}
+
+fn top_table(title: &str, top: Vec<(String, u64)>) -> Table<'static> {
+    let rows = top
+        .into_iter()
+        .map(|(key, n)| Row::new(vec![n.to_string(), key]));
+    let widths = [Constraint::Length(8), Constraint::Min(10)];
+    Table::new(rows, widths)
+        .header(Row::new(vec!["hits", "value"]))
+        .block(Block::bordered().title(title.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use grammar::nginx::parse_nginx_log;
+
+    use super::*;
+
+    fn log(time: &str, status: u16, path: &str) -> NginxLog {
+        let s = format!(
+            r#"10.0.0.1 - - [17/May/2015:08:05:{} +0000] "GET {} HTTP/1.1" {} 0 "-" "ua""#,
+            time, path, status
+        );
+        parse_nginx_log(&s).unwrap()
+    }
+
+    #[test]
+    fn stats_should_bucket_by_log_time() {
+        let mut stats = Stats::default();
+        stats.record(&log("30", 200, "/a"));
+        stats.record(&log("30", 404, "/b"));
+        stats.record(&log("32", 200, "/a"));
+        // Out of order lines land in their own second.
+        stats.record(&log("31", 503, "/a"));
+
+        assert_eq!(stats.per_second, [2, 1, 1]);
+        assert_eq!(stats.classes[2..], [2, 0, 1, 1]);
+        assert!((stats.qps() - 4.0 / 3.0).abs() < f64::EPSILON);
+        assert_eq!(
+            Stats::top(&stats.paths),
+            [("/a".to_string(), 3), ("/b".to_string(), 1)]
+        );
+    }
+}
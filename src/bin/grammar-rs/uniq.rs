@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use clap::ValueEnum;
+use grammar::nginx::{LogFormat, NginxLog, parse_nginx_log_with};
+use grammar::predicate::Predicate;
+use serde::Serialize;
+
+use crate::config::OutputFormat;
+use crate::diagnostic::Diagnostic;
+use crate::input::{self, Selection, Sink};
+use crate::progress::Bars;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Field {
+    /// Client address
+    Ip,
+    /// Request path, including the query string
+    Path,
+    /// User agent
+    Ua,
+}
+
+impl Field {
+    fn get(self, log: &NginxLog) -> String {
+        match self {
+            Field::Ip => log.addr.to_string(),
+            Field::Path => log.path.clone(),
+            Field::Ua => log.user_agent.clone(),
+        }
+    }
+}
+
+/// Occurrences of each distinct value of one field, merged across all
+/// inputs. Records rejected by the filter are not counted.
+#[derive(Debug)]
+pub struct Counts {
+    field: Field,
+    filter: Option<Predicate>,
+    counts: Mutex<HashMap<String, u64>>,
+}
+
+impl Counts {
+    pub fn new(field: Field, filter: Option<Predicate>) -> Self {
+        Self {
+            field,
+            filter,
+            counts: Mutex::default(),
+        }
+    }
+
+    /// Counts the field over the lines of `path`. The per-file tally is
+    /// merged in one step so workers do not contend on the lock for every
+    /// line.
+    pub fn add_file(
+        &self,
+        path: &Path,
+        format: LogFormat,
+        selection: &Selection,
+        bars: &Bars,
+        sink: &Sink,
+    ) {
+        let mut local: HashMap<String, u64> = HashMap::new();
+        input::for_each_line(path, bars, sink, selection, |line_no, line| {
+            if line.trim().is_empty() {
+                return true;
+            }
+            match parse_nginx_log_with(line, format) {
+                Ok(log) if self.filter.as_ref().is_some_and(|f| !f.eval(&log)) => true,
+                Ok(log) => {
+                    *local.entry(self.field.get(&log)).or_default() += 1;
+                    true
+                }
+                Err(e) => sink.report(Diagnostic::parse(path, line, line_no, &e)),
+            }
+        });
+
+        let mut counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+        for (value, n) in local {
+            *counts.entry(value).or_default() += n;
+        }
+    }
+
+    /// Most frequent first; ties are broken by value so output is stable.
+    pub fn sorted(self) -> Vec<(String, u64)> {
+        let counts = self.counts.into_inner().unwrap_or_else(|e| e.into_inner());
+        let mut sorted: Vec<_> = counts.into_iter().collect();
+        sorted.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        sorted
+    }
+}
+
+#[derive(Serialize)]
+struct Count<'a> {
+    value: &'a str,
+    count: u64,
+}
+
+/// Prints `uniq -c` style lines, or one JSON object per value.
+pub fn print(sorted: &[(String, u64)], output: OutputFormat) -> std::io::Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    for (value, count) in sorted {
+        match output {
+            OutputFormat::Debug => writeln!(stdout, "{:>7} {}", count, value)?,
+            OutputFormat::Json => {
+                let count = Count {
+                    value,
+                    count: *count,
+                };
+                serde_json::to_writer(&mut stdout, &count)?;
+                writeln!(stdout)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sorted_should_order_by_count_then_value() {
+        let counts = Counts::new(Field::Path, None);
+        {
+            let mut map = counts.counts.lock().unwrap();
+            map.insert("/b".to_string(), 2);
+            map.insert("/a".to_string(), 2);
+            map.insert("/c".to_string(), 5);
+        }
+        assert_eq!(
+            counts.sorted(),
+            [
+                ("/c".to_string(), 5),
+                ("/a".to_string(), 2),
+                ("/b".to_string(), 2)
+            ]
+        );
+    }
+}
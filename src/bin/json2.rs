@@ -7,7 +7,7 @@ use anyhow::Result;
 #[grammar = "json.pest"]
 struct JsonParser;
 
-fn parse_json_file(file: &str) -> Result<JSONValue> {
+fn parse_json_file(file: &str) -> Result<JSONValue<'_>> {
     let json = JsonParser::parse(Rule::json, file)?
         .next()
         .ok_or(anyhow::anyhow!("Failed to parse JSON"))?;
@@ -0,0 +1,175 @@
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{digit1, space0};
+use winnow::combinator::{cut_err, delimited, opt, preceded};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::take_while;
+
+use crate::ParseError;
+
+/// Which powers a unit prefix stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prefix {
+    /// Powers of 1000: `kB`, `MB`, `GB`, ...
+    Si,
+    /// Powers of 1024: `KiB`, `MiB`, `GiB`, ...
+    Iec,
+}
+
+impl Prefix {
+    fn base(self) -> u128 {
+        match self {
+            Prefix::Si => 1000,
+            Prefix::Iec => 1024,
+        }
+    }
+}
+
+const PREFIXES: [char; 6] = ['k', 'm', 'g', 't', 'p', 'e'];
+const SI_UNITS: [&str; 6] = ["kB", "MB", "GB", "TB", "PB", "EB"];
+const IEC_UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+/// Parses a size such as `10MB`, `1.5 GiB` or `4096`. `MB` is always SI and
+/// `MiB` always IEC; a bare prefix like `512k` could be either and is
+/// rejected, see [`parse_bytesize_with`].
+pub fn parse_bytesize(input: &str) -> Result<u64, ParseError> {
+    parse_bytesize_with(input, None)
+}
+
+/// Like [`parse_bytesize`], but reads bare prefixes (`k`, `M`, `G`, ...)
+/// with the given meaning, the way `dd` or `ulimit` would.
+pub fn parse_bytesize_with(input: &str, bare: Option<Prefix>) -> Result<u64, ParseError> {
+    delimited(space0, |i: &mut &str| size(i, bare), space0)
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+/// Formats with the largest unit that keeps the number at least 1, and one
+/// decimal place where needed: `512 B`, `1.5 KiB`, `10 MB`.
+pub fn format_bytesize(bytes: u64, prefix: Prefix) -> String {
+    let base = prefix.base() as f64;
+    let mut value = bytes as f64;
+    let mut exponent = 0;
+    while exponent < PREFIXES.len() && (value * 10.0).round() / 10.0 >= base {
+        value /= base;
+        exponent += 1;
+    }
+    let number = if exponent == 0 || value.fract() == 0.0 {
+        format!("{value:.0}")
+    } else {
+        let one_place = format!("{value:.1}");
+        one_place.trim_end_matches(".0").to_string()
+    };
+    let unit = match (exponent, prefix) {
+        (0, _) => "B",
+        (n, Prefix::Si) => SI_UNITS[n - 1],
+        (n, Prefix::Iec) => IEC_UNITS[n - 1],
+    };
+    format!("{number} {unit}")
+}
+
+fn size(input: &mut &str, bare: Option<Prefix>) -> ModalResult<u64> {
+    let whole = digit1
+        .context(StrContext::Expected(StrContextValue::Description("number")))
+        .parse_next(input)?;
+    let fraction = opt(preceded('.', cut_err(digit1))).parse_next(input)?;
+    space0.parse_next(input)?;
+    let unit = take_while(0.., |c: char| c.is_ascii_alphabetic()).parse_next(input)?;
+    let Some(multiplier) = multiplier(unit, bare) else {
+        let message = if bare.is_none() && unit.len() == 1 && unit != "b" && unit != "B" {
+            "an explicit unit; write `kB` (1000) or `KiB` (1024)"
+        } else {
+            "a unit such as `B`, `kB`, `MiB` or `GB`"
+        };
+        return cut_err(winnow::combinator::fail)
+            .context(StrContext::Label("unit"))
+            .context(StrContext::Expected(StrContextValue::Description(message)))
+            .parse_next(input);
+    };
+    // Fractions longer than this cannot name a whole number of bytes.
+    let digits = |s: &str| (s.len() <= 20).then(|| s.parse::<u128>().ok()).flatten();
+    let bytes = match fraction {
+        None => digits(whole).map(|n| n * multiplier),
+        Some(fraction) => {
+            let fraction = fraction.trim_end_matches('0');
+            let scale = 10u128.checked_pow(fraction.len() as u32);
+            match (
+                digits(whole),
+                digits(fraction).or(fraction.is_empty().then_some(0)),
+                scale,
+            ) {
+                (Some(whole), Some(part), Some(scale)) => {
+                    let part = part * multiplier;
+                    (part % scale == 0).then(|| whole * multiplier + part / scale)
+                }
+                _ => None,
+            }
+        }
+    };
+    match bytes.and_then(|b| u64::try_from(b).ok()) {
+        Some(bytes) => Ok(bytes),
+        None => cut_err(winnow::combinator::fail)
+            .context(StrContext::Label("size"))
+            .context(StrContext::Expected(StrContextValue::Description(
+                "a whole number of bytes that fits in 64 bits",
+            )))
+            .parse_next(input),
+    }
+}
+
+/// Bytes per unit, or `None` when the unit is unknown or ambiguous.
+fn multiplier(unit: &str, bare: Option<Prefix>) -> Option<u128> {
+    let lower = unit.to_ascii_lowercase();
+    if lower.is_empty() || lower == "b" {
+        return Some(1);
+    }
+    let mut chars = lower.chars();
+    let first = chars.next()?;
+    let exponent = PREFIXES.iter().position(|p| *p == first)? as u32 + 1;
+    let prefix = match chars.as_str() {
+        "b" => Prefix::Si,
+        "ib" => Prefix::Iec,
+        "" => bare?,
+        _ => return None,
+    };
+    Some(prefix.base().pow(exponent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_bytesize_should_work() -> Result<(), ParseError> {
+        assert_eq!(parse_bytesize("10MB")?, 10_000_000);
+        assert_eq!(parse_bytesize("1.5GiB")?, 1_610_612_736);
+        assert_eq!(parse_bytesize("4096")?, 4096);
+        assert_eq!(parse_bytesize(" 2 kib ")?, 2048);
+        assert_eq!(parse_bytesize("0.5kB")?, 500);
+        assert_eq!(parse_bytesize("16EiB").ok(), None);
+        assert_eq!(parse_bytesize("15EiB")?, 15 << 60);
+        assert_eq!(parse_bytesize_with("512k", Some(Prefix::Iec))?, 524_288);
+        assert_eq!(parse_bytesize_with("512k", Some(Prefix::Si))?, 512_000);
+        Ok(())
+    }
+
+    #[test]
+    fn format_bytesize_should_humanize() {
+        assert_eq!(format_bytesize(512, Prefix::Iec), "512 B");
+        assert_eq!(format_bytesize(1536, Prefix::Iec), "1.5 KiB");
+        assert_eq!(format_bytesize(10_000_000, Prefix::Si), "10 MB");
+        assert_eq!(format_bytesize(999_999, Prefix::Si), "1 MB");
+        assert_eq!(format_bytesize(1_260_000_000_000, Prefix::Si), "1.3 TB");
+        assert_eq!(format_bytesize(u64::MAX, Prefix::Iec), "16 EiB");
+    }
+
+    #[test]
+    fn parse_bytesize_should_reject_ambiguous_forms() {
+        let err = parse_bytesize("512k").unwrap_err();
+        assert_eq!(err.offset(), 4);
+        assert!(err.message().contains("KiB"), "{}", err.message());
+        assert!(parse_bytesize("1.5B").is_err());
+        assert!(parse_bytesize("3 bananas").is_err());
+        assert!(parse_bytesize("MB").is_err());
+    }
+}
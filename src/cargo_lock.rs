@@ -0,0 +1,341 @@
+use std::collections::VecDeque;
+
+use crate::ParseError;
+use crate::semver::{Version, parse_version};
+use crate::toml::{TomlTable, TomlValue, parse_toml};
+
+/// A `Cargo.lock` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lockfile {
+    /// The top-level `version`; lockfiles from before it existed have
+    /// `None`.
+    pub version: Option<i64>,
+    pub packages: Vec<Package>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Package {
+    pub name: String,
+    pub version: Version,
+    /// Where the package comes from, e.g.
+    /// `registry+https://github.com/rust-lang/crates.io-index`. `None` for
+    /// workspace members and path dependencies.
+    pub source: Option<String>,
+    pub checksum: Option<String>,
+    pub dependencies: Vec<Dependency>,
+}
+
+/// An entry of a package's `dependencies` array. Cargo writes only as much
+/// as it takes to tell packages apart: a name, then a version, then a
+/// source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    pub name: String,
+    pub version: Option<Version>,
+    pub source: Option<String>,
+}
+
+impl Dependency {
+    fn matches(&self, package: &Package) -> bool {
+        self.name == package.name
+            && self.version.as_ref().is_none_or(|v| *v == package.version)
+            && self
+                .source
+                .as_ref()
+                .is_none_or(|s| package.source.as_ref() == Some(s))
+    }
+}
+
+/// Which packages depend on which, by index into [`Lockfile::packages`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Graph {
+    dependencies: Vec<Vec<usize>>,
+    dependents: Vec<Vec<usize>>,
+}
+
+impl Graph {
+    pub fn dependencies(&self, package: usize) -> &[usize] {
+        &self.dependencies[package]
+    }
+
+    pub fn dependents(&self, package: usize) -> &[usize] {
+        &self.dependents[package]
+    }
+
+    /// Packages nothing else depends on; usually the workspace members with
+    /// binaries.
+    pub fn roots(&self) -> Vec<usize> {
+        (0..self.dependents.len())
+            .filter(|&i| self.dependents[i].is_empty())
+            .collect()
+    }
+
+    /// Everything `package` pulls in, directly or not, in breadth-first
+    /// order and without `package` itself.
+    pub fn transitive_dependencies(&self, package: usize) -> Vec<usize> {
+        let mut seen = vec![false; self.dependencies.len()];
+        seen[package] = true;
+        let mut queue = VecDeque::from([package]);
+        let mut found = Vec::new();
+        while let Some(next) = queue.pop_front() {
+            for &dependency in &self.dependencies[next] {
+                if !seen[dependency] {
+                    seen[dependency] = true;
+                    found.push(dependency);
+                    queue.push_back(dependency);
+                }
+            }
+        }
+        found
+    }
+}
+
+impl Lockfile {
+    /// Every locked version of the crate called `name`.
+    pub fn find(&self, name: &str) -> Vec<usize> {
+        (0..self.packages.len())
+            .filter(|&i| self.packages[i].name == name)
+            .collect()
+    }
+
+    pub fn graph(&self) -> Graph {
+        let dependencies: Vec<Vec<usize>> = self
+            .packages
+            .iter()
+            .map(|package| {
+                package
+                    .dependencies
+                    .iter()
+                    .filter_map(|d| self.packages.iter().position(|p| d.matches(p)))
+                    .collect()
+            })
+            .collect();
+        let mut dependents = vec![Vec::new(); self.packages.len()];
+        for (from, targets) in dependencies.iter().enumerate() {
+            for &to in targets {
+                dependents[to].push(from);
+            }
+        }
+        Graph {
+            dependencies,
+            dependents,
+        }
+    }
+}
+
+/// Reads a `Cargo.lock`. Checksums are taken from the packages themselves
+/// (lockfile version 2 and later) or from the `[metadata]` table of older
+/// files. Every dependency has to name exactly one locked package. The TOML
+/// itself carries no positions past the syntax, so errors about the
+/// contents point at the start of the input.
+pub fn parse_cargo_lock(input: &str) -> Result<Lockfile, ParseError> {
+    let root = parse_toml(input)?;
+    let error = |message: String| ParseError::new(0, message);
+    let version = match root.get("version") {
+        None => None,
+        Some(TomlValue::Integer(version)) => Some(*version),
+        Some(_) => return Err(error("`version` must be an integer".to_string())),
+    };
+    let tables = match root.get("package") {
+        None => &Vec::new(),
+        Some(TomlValue::Array(tables)) => tables,
+        Some(_) => return Err(error("`package` must be an array of tables".to_string())),
+    };
+    let mut packages = Vec::with_capacity(tables.len());
+    for (i, table) in tables.iter().enumerate() {
+        let TomlValue::Table(table) = table else {
+            return Err(error(format!("package {}: expected a table", i + 1)));
+        };
+        packages.push(
+            package(table).map_err(|message| error(format!("package {}: {message}", i + 1)))?,
+        );
+    }
+
+    if let Some(TomlValue::Table(metadata)) = root.get("metadata") {
+        for (key, value) in metadata {
+            let (Some(id), TomlValue::String(checksum)) = (key.strip_prefix("checksum "), value)
+            else {
+                continue;
+            };
+            let id = dependency(id).map_err(error)?;
+            if let Some(package) = packages.iter_mut().find(|p| id.matches(p)) {
+                package.checksum.get_or_insert_with(|| checksum.clone());
+            }
+        }
+    }
+
+    for package in &packages {
+        for dependency in &package.dependencies {
+            let count = packages.iter().filter(|p| dependency.matches(p)).count();
+            if count != 1 {
+                let problem = if count == 0 { "no" } else { "more than one" };
+                return Err(error(format!(
+                    "`{}` depends on `{}`, which matches {problem} package",
+                    package.name, dependency.name
+                )));
+            }
+        }
+    }
+    Ok(Lockfile { version, packages })
+}
+
+fn package(table: &TomlTable) -> Result<Package, String> {
+    let string = |key: &str| match table.get(key) {
+        None => Ok(None),
+        Some(TomlValue::String(s)) => Ok(Some(s.clone())),
+        Some(_) => Err(format!("`{key}` must be a string")),
+    };
+    let name = string("name")?.ok_or("`name` is missing")?;
+    let version = string("version")?.ok_or("`version` is missing")?;
+    let version = parse_version(&version).map_err(|e| format!("`version`: {}", e.message()))?;
+    let dependencies = match table.get("dependencies") {
+        None => Vec::new(),
+        Some(TomlValue::Array(items)) => items
+            .iter()
+            .map(|item| match item {
+                TomlValue::String(s) => dependency(s),
+                _ => Err("`dependencies` must hold strings".to_string()),
+            })
+            .collect::<Result<_, _>>()?,
+        Some(_) => return Err("`dependencies` must be an array".to_string()),
+    };
+    Ok(Package {
+        name,
+        version,
+        source: string("source")?,
+        checksum: string("checksum")?,
+        dependencies,
+    })
+}
+
+/// `name`, `name version` or `name version (source)`.
+fn dependency(text: &str) -> Result<Dependency, String> {
+    let mut parts = text.splitn(3, ' ');
+    let name = parts.next().unwrap_or_default();
+    let version = parts
+        .next()
+        .map(parse_version)
+        .transpose()
+        .map_err(|e| format!("bad dependency `{text}`: {}", e.message()))?;
+    let source = match parts.next() {
+        None => None,
+        Some(source) => Some(
+            source
+                .strip_prefix('(')
+                .and_then(|s| s.strip_suffix(')'))
+                .ok_or_else(|| format!("bad dependency `{text}`"))?
+                .to_string(),
+        ),
+    };
+    if name.is_empty() {
+        return Err(format!("bad dependency `{text}`"));
+    }
+    Ok(Dependency {
+        name: name.to_string(),
+        version,
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CRATES_IO: &str = "registry+https://github.com/rust-lang/crates.io-index";
+
+    const LOCK: &str = r#"# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = [
+ "bitflags 1.3.2",
+ "bitflags 2.4.0",
+ "log",
+]
+
+[[package]]
+name = "bitflags"
+version = "1.3.2"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "bef38d45163c2f1dde094a7dfd33ccf595c92905c8f8f4fdc18d06fb1037718a"
+
+[[package]]
+name = "bitflags"
+version = "2.4.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "b4682ae6287fcf752ecaabbfcc7b6f9b72aa33933dc23a554d853aea8eea8635"
+dependencies = [
+ "log",
+]
+
+[[package]]
+name = "log"
+version = "0.4.20"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+    #[test]
+    fn parse_cargo_lock_should_work() -> Result<(), ParseError> {
+        let lock = parse_cargo_lock(LOCK)?;
+        assert_eq!(lock.version, Some(3));
+        assert_eq!(lock.packages.len(), 4);
+        let app = &lock.packages[0];
+        assert_eq!(app.source, None);
+        assert_eq!(
+            app.dependencies[0],
+            Dependency {
+                name: "bitflags".into(),
+                version: Some(Version::new(1, 3, 2)),
+                source: None,
+            }
+        );
+        assert_eq!(lock.packages[1].source.as_deref(), Some(CRATES_IO));
+        assert!(
+            lock.packages[2]
+                .checksum
+                .as_deref()
+                .unwrap()
+                .starts_with("b4682ae6")
+        );
+        assert_eq!(lock.packages[3].checksum, None);
+        assert_eq!(lock.find("bitflags"), [1, 2]);
+        Ok(())
+    }
+
+    #[test]
+    fn graph_should_follow_dependencies() -> Result<(), ParseError> {
+        let graph = parse_cargo_lock(LOCK)?.graph();
+        assert_eq!(graph.dependencies(0), [1, 2, 3]);
+        assert_eq!(graph.dependents(3), [0, 2]);
+        assert_eq!(graph.roots(), [0]);
+        assert_eq!(graph.transitive_dependencies(2), [3]);
+        assert_eq!(graph.transitive_dependencies(0), [1, 2, 3]);
+
+        let old = parse_cargo_lock(&format!(
+            "[[package]]\nname = \"a\"\nversion = \"1.0.0\"\ndependencies = [\"b 0.1.0 ({CRATES_IO})\"]\n\n\
+             [[package]]\nname = \"b\"\nversion = \"0.1.0\"\nsource = \"{CRATES_IO}\"\n\n\
+             [metadata]\n\"checksum b 0.1.0 ({CRATES_IO})\" = \"abc123\"\n"
+        ))?;
+        assert_eq!(old.version, None);
+        assert_eq!(old.packages[1].checksum.as_deref(), Some("abc123"));
+        assert_eq!(old.graph().dependencies(0), [1]);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_cargo_lock_should_reject_bad_lockfiles() {
+        let ambiguous = LOCK.replace("\"bitflags 1.3.2\",", "\"bitflags\",");
+        let err = parse_cargo_lock(&ambiguous).unwrap_err();
+        assert_eq!(
+            err.message(),
+            "`app` depends on `bitflags`, which matches more than one package"
+        );
+        assert!(parse_cargo_lock(&LOCK.replace("\"log\",", "\"missing\",")).is_err());
+        assert!(parse_cargo_lock("[[package]]\nname = \"a\"\n").is_err());
+        assert!(parse_cargo_lock("[[package]]\nname = \"a\"\nversion = \"one\"\n").is_err());
+        assert!(parse_cargo_lock("version = 3\n[[package]\n").is_err());
+    }
+}
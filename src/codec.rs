@@ -0,0 +1,286 @@
+use crate::ParseError;
+
+/// A binary-to-text encoding of RFC 4648: base64, base32 or hex.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Encoding {
+    alphabet: &'static [u8],
+    /// Bits per symbol: 6, 5 or 4.
+    bits: u32,
+    pad: bool,
+    /// Whether lowercase input decodes like uppercase (or the reverse).
+    any_case: bool,
+}
+
+const BASE64_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64_URL_ALPHABET: &[u8] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+pub const BASE64: Encoding = Encoding {
+    alphabet: BASE64_ALPHABET,
+    bits: 6,
+    pad: true,
+    any_case: false,
+};
+pub const BASE64_NO_PAD: Encoding = Encoding {
+    pad: false,
+    ..BASE64
+};
+/// The URL- and filename-safe alphabet, with `-` and `_`.
+pub const BASE64_URL: Encoding = Encoding {
+    alphabet: BASE64_URL_ALPHABET,
+    ..BASE64
+};
+/// What JWTs and most URLs carry.
+pub const BASE64_URL_NO_PAD: Encoding = Encoding {
+    pad: false,
+    ..BASE64_URL
+};
+pub const BASE32: Encoding = Encoding {
+    alphabet: b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567",
+    bits: 5,
+    pad: true,
+    any_case: true,
+};
+pub const BASE32_NO_PAD: Encoding = Encoding {
+    pad: false,
+    ..BASE32
+};
+/// Lowercase on output; either case decodes.
+pub const HEX: Encoding = Encoding {
+    alphabet: b"0123456789abcdef",
+    bits: 4,
+    pad: false,
+    any_case: true,
+};
+
+impl Encoding {
+    pub fn encode(&self, bytes: &[u8]) -> String {
+        let mut out = String::with_capacity(bytes.len() * 8 / self.bits as usize + 8);
+        let mut encoder = self.encoder();
+        encoder.push(bytes, &mut out);
+        encoder.finish(&mut out);
+        out
+    }
+
+    /// Errors point at the offending character. Padded encodings also accept
+    /// input without its trailing `=`; the unpadded ones reject `=`.
+    pub fn decode(&self, text: &str) -> Result<Vec<u8>, ParseError> {
+        let mut out = Vec::with_capacity(text.len() * self.bits as usize / 8);
+        let mut decoder = self.decoder();
+        decoder.push(text.as_bytes(), &mut out)?;
+        decoder.finish()?;
+        Ok(out)
+    }
+
+    pub fn encoder(&self) -> Encoder {
+        Encoder {
+            encoding: *self,
+            buffer: 0,
+            bits: 0,
+            symbols: 0,
+        }
+    }
+
+    pub fn decoder(&self) -> Decoder {
+        Decoder {
+            encoding: *self,
+            buffer: 0,
+            bits: 0,
+            symbols: 0,
+            padding: 0,
+            offset: 0,
+        }
+    }
+
+    /// Symbols per padded group: 4 for base64, 8 for base32.
+    fn group(&self) -> usize {
+        match self.bits {
+            6 => 4,
+            5 => 8,
+            _ => 2,
+        }
+    }
+
+    fn value(&self, c: u8) -> Option<u8> {
+        let position = |c: u8| self.alphabet.iter().position(|&a| a == c);
+        position(c)
+            .or_else(|| {
+                self.any_case
+                    .then(|| position(c.to_ascii_uppercase()).or(position(c.to_ascii_lowercase())))
+                    .flatten()
+            })
+            .map(|i| i as u8)
+    }
+}
+
+/// Encodes input that arrives in pieces, so it never has to be held whole.
+#[derive(Debug, Clone)]
+pub struct Encoder {
+    encoding: Encoding,
+    buffer: u32,
+    bits: u32,
+    symbols: usize,
+}
+
+impl Encoder {
+    /// Appends whatever `bytes` complete to `out`; the rest is held back.
+    pub fn push(&mut self, bytes: &[u8], out: &mut String) {
+        let width = self.encoding.bits;
+        for &byte in bytes {
+            self.buffer = (self.buffer << 8 | u32::from(byte)) & 0xffff;
+            self.bits += 8;
+            while self.bits >= width {
+                self.bits -= width;
+                self.emit((self.buffer >> self.bits) as usize, out);
+            }
+        }
+    }
+
+    /// Flushes the held-back bits and any padding.
+    pub fn finish(mut self, out: &mut String) {
+        let width = self.encoding.bits;
+        if self.bits > 0 {
+            self.emit((self.buffer << (width - self.bits)) as usize, out);
+        }
+        if self.encoding.pad {
+            let group = self.encoding.group();
+            while !self.symbols.is_multiple_of(group) {
+                out.push('=');
+                self.symbols += 1;
+            }
+        }
+    }
+
+    fn emit(&mut self, index: usize, out: &mut String) {
+        let mask = (1 << self.encoding.bits) - 1;
+        out.push(self.encoding.alphabet[index & mask] as char);
+        self.symbols += 1;
+    }
+}
+
+/// Decodes text that arrives in pieces; offsets in errors count from the
+/// first piece.
+#[derive(Debug, Clone)]
+pub struct Decoder {
+    encoding: Encoding,
+    buffer: u32,
+    bits: u32,
+    symbols: usize,
+    padding: usize,
+    offset: usize,
+}
+
+impl Decoder {
+    pub fn push(&mut self, text: &[u8], out: &mut Vec<u8>) -> Result<(), ParseError> {
+        let width = self.encoding.bits;
+        for &c in text {
+            let at = self.offset;
+            self.offset += 1;
+            if c == b'=' && self.encoding.pad {
+                self.padding += 1;
+                continue;
+            }
+            if self.padding > 0 {
+                return Err(ParseError::new(at, "unexpected data after padding"));
+            }
+            let value = self
+                .encoding
+                .value(c)
+                .ok_or_else(|| ParseError::new(at, format!("invalid character {:?}", c as char)))?;
+            self.buffer = (self.buffer << width | u32::from(value)) & 0xffff;
+            self.bits += width;
+            self.symbols += 1;
+            if self.bits >= 8 {
+                self.bits -= 8;
+                out.push((self.buffer >> self.bits) as u8);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the input ended on a whole number of bytes, and that
+    /// any padding is the right length.
+    pub fn finish(self) -> Result<(), ParseError> {
+        // A trailing symbol that completed no byte means the input was cut.
+        if self.bits >= self.encoding.bits {
+            return Err(ParseError::new(self.offset, "truncated input"));
+        }
+        if self.padding > 0 && !(self.symbols + self.padding).is_multiple_of(self.encoding.group())
+        {
+            return Err(ParseError::new(self.offset, "wrong amount of padding"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc_4648_vectors_should_round_trip() -> Result<(), ParseError> {
+        let cases = [
+            ("", "", "", ""),
+            ("f", "Zg==", "MY======", "66"),
+            ("fo", "Zm8=", "MZXQ====", "666f"),
+            ("foo", "Zm9v", "MZXW6===", "666f6f"),
+            ("foob", "Zm9vYg==", "MZXW6YQ=", "666f6f62"),
+            ("fooba", "Zm9vYmE=", "MZXW6YTB", "666f6f6261"),
+            ("foobar", "Zm9vYmFy", "MZXW6YTBOI======", "666f6f626172"),
+        ];
+        for (plain, base64, base32, hex) in cases {
+            assert_eq!(BASE64.encode(plain.as_bytes()), base64);
+            assert_eq!(BASE32.encode(plain.as_bytes()), base32);
+            assert_eq!(HEX.encode(plain.as_bytes()), hex);
+            assert_eq!(BASE64.decode(base64)?, plain.as_bytes());
+            assert_eq!(BASE32.decode(base32)?, plain.as_bytes());
+            assert_eq!(HEX.decode(hex)?, plain.as_bytes());
+            let unpadded = base64.trim_end_matches('=');
+            assert_eq!(BASE64_NO_PAD.encode(plain.as_bytes()), unpadded);
+            assert_eq!(BASE64.decode(unpadded)?, plain.as_bytes());
+        }
+        assert_eq!(BASE64_URL_NO_PAD.encode(&[0xfb, 0xff]), "-_8");
+        assert_eq!(BASE64.encode(&[0xfb, 0xff]), "+/8=");
+        assert_eq!(HEX.decode("DEADbeef")?, [0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(BASE32.decode("mzxw6===")?, b"foo");
+        Ok(())
+    }
+
+    #[test]
+    fn streaming_should_match_one_shot() -> Result<(), ParseError> {
+        let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        for encoding in [BASE64, BASE64_URL_NO_PAD, BASE32, HEX] {
+            let mut text = String::new();
+            let mut encoder = encoding.encoder();
+            for chunk in data.chunks(7) {
+                encoder.push(chunk, &mut text);
+            }
+            encoder.finish(&mut text);
+            assert_eq!(text, encoding.encode(&data));
+
+            let mut bytes = Vec::new();
+            let mut decoder = encoding.decoder();
+            for chunk in text.as_bytes().chunks(5) {
+                decoder.push(chunk, &mut bytes)?;
+            }
+            decoder.finish()?;
+            assert_eq!(bytes, data);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn decode_should_reject_bad_input() {
+        let err = BASE64.decode("Zm9v!mFy").unwrap_err();
+        assert_eq!(err.offset(), 4);
+        assert!(BASE64.decode("Zm9vY").is_err());
+        assert!(BASE64.decode("Zg=").is_err());
+        assert!(BASE64.decode("Zg==Zg==").is_err());
+        assert!(BASE64_NO_PAD.decode("Zg==").is_err());
+        assert!(BASE64_URL.decode("+/8=").is_err());
+        assert!(BASE64.decode("zm9v").is_ok());
+        assert!(BASE32.decode("MZXW6Y").is_err());
+        assert!(HEX.decode("abc").is_err());
+        assert!(HEX.decode("0g").is_err());
+    }
+}
@@ -0,0 +1,633 @@
+use std::cell::RefCell;
+use std::ops::{Deref, Range};
+
+use winnow::Parser;
+use winnow::ascii::{multispace0, multispace1};
+use winnow::combinator::{cut_err, fail, opt, trace};
+use winnow::error::{AddContext, ContextError, ErrMode, ModalError, ParserError, StrContext};
+use winnow::stream::{AsChar, Compare, Location, Stream, StreamIsPartial};
+use winnow::token::{any, literal, take_till};
+
+use crate::ParseError;
+
+/// Byte range of a value in the input it was parsed from.
+pub type Span = Range<usize>;
+
+/// Runs `parser` with optional white space on both sides and discards its
+/// output; meant for punctuation such as `[` or `,`.
+pub fn sep_with_space<Input, Output, Error, ParseNext>(
+    mut parser: ParseNext,
+) -> impl Parser<Input, (), Error>
+where
+    Input: Stream + StreamIsPartial,
+    <Input as Stream>::Token: AsChar + Clone,
+    Error: ParserError<Input>,
+    ParseNext: Parser<Input, Output, Error>,
+{
+    trace("sep_with_space", move |input: &mut Input| {
+        let _ = multispace0.parse_next(input)?;
+        parser.parse_next(input)?;
+        multispace0.parse_next(input)?;
+        Ok(())
+    })
+}
+
+/// Like [`sep_with_space`], but keeps the output of `parser`.
+pub fn ws<Input, Output, Error, ParseNext>(
+    mut parser: ParseNext,
+) -> impl Parser<Input, Output, Error>
+where
+    Input: Stream + StreamIsPartial,
+    <Input as Stream>::Token: AsChar + Clone,
+    Error: ParserError<Input>,
+    ParseNext: Parser<Input, Output, Error>,
+{
+    trace("ws", move |input: &mut Input| {
+        multispace0.parse_next(input)?;
+        let output = parser.parse_next(input)?;
+        multispace0.parse_next(input)?;
+        Ok(output)
+    })
+}
+
+/// Runs `parser` and skips the white space after it. Grammars that skip
+/// leading white space once and then wrap every terminal in `lexeme` never
+/// have to think about white space again.
+pub fn lexeme<Input, Output, Error, ParseNext>(
+    mut parser: ParseNext,
+) -> impl Parser<Input, Output, Error>
+where
+    Input: Stream + StreamIsPartial,
+    <Input as Stream>::Token: AsChar + Clone,
+    Error: ParserError<Input>,
+    ParseNext: Parser<Input, Output, Error>,
+{
+    trace("lexeme", move |input: &mut Input| {
+        let output = parser.parse_next(input)?;
+        multispace0.parse_next(input)?;
+        Ok(output)
+    })
+}
+
+/// A [`lexeme`] that returns the text `parser` matched instead of its
+/// output, e.g. `token(("0x", hex_digit1))`.
+pub fn token<Input, Output, Error, ParseNext>(
+    parser: ParseNext,
+) -> impl Parser<Input, <Input as Stream>::Slice, Error>
+where
+    Input: Stream + StreamIsPartial,
+    <Input as Stream>::Token: AsChar + Clone,
+    Error: ParserError<Input>,
+    ParseNext: Parser<Input, Output, Error>,
+{
+    trace("token", lexeme(parser.take()))
+}
+
+/// Skips white space and comments. The comment syntax is configured
+/// with the builder methods or taken from a preset such as [`Trivia::C`],
+/// and the value itself is the parser, so it drops in wherever `multispace0`
+/// would. An unterminated block comment is a cut error at its opening marker.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Trivia {
+    line_comments: &'static [&'static str],
+    block_comments: Option<(&'static str, &'static str)>,
+    nested: bool,
+}
+
+impl Trivia {
+    /// `//` and `/* */`, as in JSONC and most C-like languages.
+    pub const C: Trivia = Trivia::new()
+        .line_comments(&["//"])
+        .block_comments("/*", "*/");
+    /// `#` to the end of the line, as in TOML, YAML and shell scripts.
+    pub const HASH: Trivia = Trivia::new().line_comments(&["#"]);
+    /// `;` to the end of the line, as in Lisp and Scheme.
+    pub const LISP: Trivia = Trivia::new().line_comments(&[";"]);
+
+    /// White space only.
+    pub const fn new() -> Self {
+        Trivia {
+            line_comments: &[],
+            block_comments: None,
+            nested: false,
+        }
+    }
+
+    /// Markers that start a comment running to the end of the line.
+    pub const fn line_comments(mut self, markers: &'static [&'static str]) -> Self {
+        self.line_comments = markers;
+        self
+    }
+
+    pub const fn block_comments(mut self, open: &'static str, close: &'static str) -> Self {
+        self.block_comments = Some((open, close));
+        self
+    }
+
+    /// Whether block comments nest, as in Rust and Swift.
+    pub const fn nested(mut self, nested: bool) -> Self {
+        self.nested = nested;
+        self
+    }
+
+    /// Skips one comment if there is one here.
+    fn comment<I, E>(&self, input: &mut I) -> Result<bool, E>
+    where
+        I: Stream + StreamIsPartial + Compare<&'static str>,
+        <I as Stream>::Token: AsChar + Clone,
+        E: ParserError<I> + ModalError + AddContext<I, StrContext>,
+    {
+        for marker in self.line_comments {
+            if opt(literal(*marker)).parse_next(input)?.is_some() {
+                take_till(0.., '\n').parse_next(input)?;
+                return Ok(true);
+            }
+        }
+        let Some((open, close)) = self.block_comments else {
+            return Ok(false);
+        };
+        let start = input.checkpoint();
+        if opt(literal(open)).parse_next(input)?.is_none() {
+            return Ok(false);
+        }
+        let mut depth = 1;
+        while depth > 0 {
+            if opt(literal(close)).parse_next(input)?.is_some() {
+                depth -= 1;
+            } else if self.nested && opt(literal(open)).parse_next(input)?.is_some() {
+                depth += 1;
+            } else if opt(any).parse_next(input)?.is_none() {
+                input.reset(&start);
+                return cut_err(fail)
+                    .context(StrContext::Label("unterminated block comment"))
+                    .parse_next(input);
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl<I, E> Parser<I, (), E> for Trivia
+where
+    I: Stream + StreamIsPartial + Compare<&'static str>,
+    <I as Stream>::Token: AsChar + Clone,
+    E: ParserError<I> + ModalError + AddContext<I, StrContext>,
+{
+    fn parse_next(&mut self, input: &mut I) -> Result<(), E> {
+        loop {
+            let space = opt(multispace1).parse_next(input)?.is_some();
+            if !self.comment(input)? && !space {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Like [`ws`], but skips `trivia` instead of plain white space.
+pub fn padded<Input, Output, Error, ParseNext>(
+    mut trivia: Trivia,
+    mut parser: ParseNext,
+) -> impl Parser<Input, Output, Error>
+where
+    Input: Stream + StreamIsPartial + Compare<&'static str>,
+    <Input as Stream>::Token: AsChar + Clone,
+    Error: ParserError<Input> + ModalError + AddContext<Input, StrContext>,
+    ParseNext: Parser<Input, Output, Error>,
+{
+    trace("padded", move |input: &mut Input| {
+        trivia.parse_next(input)?;
+        let output = parser.parse_next(input)?;
+        trivia.parse_next(input)?;
+        Ok(output)
+    })
+}
+
+/// Pairs the output of `parser` with the span it consumed. Wrap the input
+/// in a `LocatingSlice` so offsets count from the start of the document.
+pub fn spanned<Input, Output, Error, ParseNext>(
+    parser: ParseNext,
+) -> impl Parser<Input, (Output, Span), Error>
+where
+    Input: Stream + Location,
+    Error: ParserError<Input>,
+    ParseNext: Parser<Input, Output, Error>,
+{
+    trace("spanned", parser.with_span())
+}
+
+/// A syntax node together with where it came from. Parsers that offer
+/// located output build their trees out of these.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Located<T> {
+    pub node: T,
+    pub span: Span,
+}
+
+impl<T> Located<T> {
+    pub fn new(node: T, span: Span) -> Self {
+        Located { node, span }
+    }
+
+    /// Transforms the node, keeping the span.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Located<U> {
+        Located::new(f(self.node), self.span)
+    }
+
+    pub fn as_ref(&self) -> Located<&T> {
+        Located::new(&self.node, self.span.clone())
+    }
+
+    pub fn into_inner(self) -> T {
+        self.node
+    }
+}
+
+impl<T> Deref for Located<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.node
+    }
+}
+
+/// [`spanned`], with the output wrapped in a [`Located`].
+pub fn located<Input, Output, Error, ParseNext>(
+    parser: ParseNext,
+) -> impl Parser<Input, Located<Output>, Error>
+where
+    Input: Stream + Location,
+    Error: ParserError<Input>,
+    ParseNext: Parser<Input, Output, Error>,
+{
+    trace(
+        "located",
+        parser
+            .with_span()
+            .map(|(node, span)| Located::new(node, span)),
+    )
+}
+
+/// The offset of the next token, without consuming anything.
+pub fn position<Input, Error>() -> impl Parser<Input, usize, Error>
+where
+    Input: Stream + Location,
+    Error: ParserError<Input>,
+{
+    trace("position", |input: &mut Input| {
+        Ok(input.current_token_start())
+    })
+}
+
+/// Left recursion without the recursion: `operand` followed by any number
+/// of `operator`s, each folded into the tree built so far. A rule such as
+/// `expr = expr '-' term | term` becomes
+/// `left_assoc(term, preceded('-', term), |a, b| a - b)`, and suffix chains
+/// like `a.b(c)[0]` work the same with a parser for one suffix. Long chains
+/// loop instead of nesting calls, so they cannot overflow the stack.
+pub fn left_assoc<Input, Output, Operator, Error, Operand, ParseOperator, Fold>(
+    mut operand: Operand,
+    mut operator: ParseOperator,
+    mut fold: Fold,
+) -> impl Parser<Input, Output, Error>
+where
+    Input: Stream,
+    Error: ParserError<Input>,
+    Operand: Parser<Input, Output, Error>,
+    ParseOperator: Parser<Input, Operator, Error>,
+    Fold: FnMut(Output, Operator) -> Output,
+{
+    trace("left_assoc", move |input: &mut Input| {
+        let mut tree = operand.parse_next(input)?;
+        loop {
+            let checkpoint = input.checkpoint();
+            let before = input.eof_offset();
+            match operator.parse_next(input) {
+                Ok(_) if input.eof_offset() == before => {
+                    return Err(ParserError::assert(
+                        input,
+                        "`left_assoc` operators must always consume",
+                    ));
+                }
+                Ok(op) => tree = fold(tree, op),
+                Err(e) if e.is_backtrack() => {
+                    input.reset(&checkpoint);
+                    return Ok(tree);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    })
+}
+
+/// Where a recovering parser resumes after an error: the next of `tokens`
+/// that is not nested in brackets or inside a quoted string. A closing
+/// bracket without a matching opening one also stops the skip, since it
+/// most likely ends an enclosing construct.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncSet {
+    tokens: &'static [char],
+    quotes: &'static [char],
+}
+
+impl SyncSet {
+    pub const fn new(tokens: &'static [char]) -> Self {
+        SyncSet {
+            tokens,
+            quotes: &[],
+        }
+    }
+
+    /// Quote characters whose contents are skipped as a whole. A backslash
+    /// escapes the character after it.
+    pub const fn quotes(mut self, quotes: &'static [char]) -> Self {
+        self.quotes = quotes;
+        self
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        self.tokens.contains(&c)
+    }
+
+    /// Whether the input is at a token the skip would stop at.
+    fn at_boundary<I>(&self, input: &I) -> bool
+    where
+        I: Stream,
+        <I as Stream>::Token: AsChar,
+    {
+        input.peek_token().is_none_or(|token| {
+            let c = token.as_char();
+            self.contains(c) || matches!(c, ')' | ']' | '}')
+        })
+    }
+
+    fn skip<I>(&self, input: &mut I)
+    where
+        I: Stream,
+        <I as Stream>::Token: AsChar,
+    {
+        // The closing brackets still expected; a mismatched one is skipped.
+        let mut open = Vec::new();
+        let mut quote = None;
+        let mut escaped = false;
+        while let Some(token) = input.peek_token() {
+            let c = token.as_char();
+            if let Some(q) = quote {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == q {
+                    quote = None;
+                }
+            } else if open.is_empty() && self.at_boundary(input) {
+                return;
+            } else if self.quotes.contains(&c) {
+                quote = Some(c);
+            } else if let Some(close) = match c {
+                '(' => Some(')'),
+                '[' => Some(']'),
+                '{' => Some('}'),
+                _ => None,
+            } {
+                open.push(close);
+            } else if open.last() == Some(&c) {
+                open.pop();
+            }
+            input.next_token();
+        }
+    }
+}
+
+/// Runs `parser`; if it fails, records the error in `errors`, skips to the
+/// next token of `sync` and returns `None` instead of failing. Recording
+/// keeps one error per offset, so a failure that unwinds through several
+/// recovering parsers is only reported once.
+pub fn recover_until<Input, Output, ParseNext>(
+    mut parser: ParseNext,
+    sync: SyncSet,
+    errors: &RefCell<Vec<ParseError>>,
+) -> impl Parser<Input, Option<Output>, ErrMode<ContextError>>
+where
+    Input: Stream + StreamIsPartial + Location,
+    <Input as Stream>::Token: AsChar,
+    ParseNext: Parser<Input, Output, ErrMode<ContextError>>,
+{
+    trace("recover_until", move |input: &mut Input| {
+        let start = input.checkpoint();
+        match parser.parse_next(input) {
+            Ok(output) => Ok(Some(output)),
+            Err(ErrMode::Backtrack(e) | ErrMode::Cut(e)) => {
+                record(errors, input.current_token_start(), &e);
+                input.reset(&start);
+                sync.skip(input);
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    })
+}
+
+/// Like `separated(0.., item, separator)`, but recovers from malformed items
+/// with [`recover_until`] and from junk where a separator should be by
+/// recording it and skipping ahead. Stops in front of the first boundary
+/// of `sync` that no separator follows, which is normally the list's
+/// closing token. Input that ends up nowhere near a separator is the
+/// caller's to report.
+pub fn parse_separated_with_recovery<Input, Output, Sep, ParseNext, SepParser>(
+    item: ParseNext,
+    mut separator: SepParser,
+    sync: SyncSet,
+    errors: &RefCell<Vec<ParseError>>,
+) -> impl Parser<Input, Vec<Output>, ErrMode<ContextError>>
+where
+    Input: Stream + StreamIsPartial + Location,
+    <Input as Stream>::Token: AsChar,
+    ParseNext: Parser<Input, Output, ErrMode<ContextError>>,
+    SepParser: Parser<Input, Sep, ErrMode<ContextError>>,
+{
+    let mut item = recover_until(item, sync, errors);
+    trace("parse_separated_with_recovery", move |input: &mut Input| {
+        let mut items = Vec::new();
+        loop {
+            items.extend(item.parse_next(input)?);
+            loop {
+                let start = input.checkpoint();
+                match separator.parse_next(input) {
+                    Ok(_) => break,
+                    Err(ErrMode::Backtrack(e) | ErrMode::Cut(e)) => {
+                        input.reset(&start);
+                        if sync.at_boundary(input) {
+                            return Ok(items);
+                        }
+                        record(errors, input.current_token_start(), &e);
+                        sync.skip(input);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+    })
+}
+
+fn record(errors: &RefCell<Vec<ParseError>>, offset: usize, error: &ContextError) {
+    let mut errors = errors.borrow_mut();
+    if errors.last().is_none_or(|last| last.offset() != offset) {
+        errors.push(ParseError::from_context(offset, error));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winnow::ModalResult;
+    use winnow::ascii::{alpha1, digit1};
+    use winnow::combinator::{alt, delimited, preceded, separated};
+    use winnow::error::{ContextError, ErrMode};
+    use winnow::stream::{LocatingSlice, Location};
+
+    use super::*;
+
+    #[test]
+    fn ws_and_lexeme_should_skip_white_space() -> ModalResult<()> {
+        let mut input = " \n[ 1 ,2,\t3 ]  rest";
+        let items: Vec<&str> = delimited(
+            sep_with_space('['),
+            separated(0.., lexeme(digit1), sep_with_space(',')),
+            ws(']'),
+        )
+        .parse_next(&mut input)?;
+        assert_eq!(items, ["1", "2", "3"]);
+        assert_eq!(input, "rest");
+        Ok(())
+    }
+
+    #[test]
+    fn token_should_return_the_matched_text() -> ModalResult<()> {
+        let mut input = "foo42  bar";
+        assert_eq!(token((alpha1, digit1)).parse_next(&mut input)?, "foo42");
+        assert_eq!(input, "bar");
+        Ok(())
+    }
+
+    #[test]
+    fn trivia_should_skip_comments() -> ModalResult<()> {
+        let mut input = "; a\n  ;; b\n\tkey";
+        let mut lisp = Trivia::LISP;
+        lisp.parse_next(&mut input)?;
+        assert_eq!(input, "key");
+
+        let mut rust = Trivia::C.nested(true);
+        let mut input = "/* outer /* inner */ still */ // done\nx";
+        rust.parse_next(&mut input)?;
+        assert_eq!(input, "x");
+
+        let mut input = "/* a /* b */ c */";
+        let mut c = Trivia::C;
+        c.parse_next(&mut input)?;
+        assert_eq!(input, "c */");
+
+        let mut input = "  // only comments";
+        let items: Vec<&str> =
+            separated(0.., padded(Trivia::C, digit1), ',').parse_next(&mut input)?;
+        assert!(items.is_empty());
+        assert_eq!(input, "  // only comments");
+        Ok(())
+    }
+
+    #[test]
+    fn trivia_should_report_unterminated_comments() {
+        let err = padded(Trivia::C, digit1::<_, ErrMode<ContextError>>)
+            .parse("1 /* open")
+            .unwrap_err();
+        assert_eq!(err.offset(), 2);
+        assert!(err.to_string().contains("unterminated block comment"));
+    }
+
+    #[test]
+    fn spanned_should_report_byte_ranges() -> ModalResult<()> {
+        let mut input = LocatingSlice::new("[ ab, cde ]");
+        let items: Vec<(&str, Span)> = delimited(
+            sep_with_space('['),
+            separated(0.., lexeme(spanned(alpha1)), sep_with_space(',')),
+            ']',
+        )
+        .parse_next(&mut input)?;
+        assert_eq!(items, [("ab", 2..4), ("cde", 6..9)]);
+        assert_eq!(
+            position::<_, ErrMode<ContextError>>().parse_next(&mut input)?,
+            11
+        );
+
+        let mut input = LocatingSlice::new("  42");
+        let number =
+            preceded(multispace0, located(digit1.parse_to::<u32>())).parse_next(&mut input)?;
+        assert_eq!(number, Located::new(42, 2..4));
+        assert_eq!(number.map(|n| n * 2).node, 84);
+        Ok(())
+    }
+
+    #[test]
+    fn left_assoc_should_fold_from_the_left() -> ModalResult<()> {
+        let long = format!("1{}", "-1".repeat(100_000));
+        let number = || digit1.parse_to::<i64>();
+        let mut difference = left_assoc(number(), preceded('-', cut_err(number())), |a, b| a - b);
+        assert_eq!(difference.parse_next(&mut "10-4-3")?, 3);
+        assert!(difference.parse_next(&mut "10-").is_err());
+
+        #[derive(Debug, PartialEq)]
+        enum Expr<'i> {
+            Name(&'i str),
+            Member(Box<Expr<'i>>, &'i str),
+            Call(Box<Expr<'i>>, Vec<Expr<'i>>),
+        }
+        let chain = left_assoc(
+            alpha1.map(Expr::Name),
+            alt((
+                preceded('.', alpha1).map(Ok),
+                delimited('(', separated(0.., alpha1.map(Expr::Name), ','), ')').map(Err),
+            )),
+            |callee, suffix| match suffix {
+                Ok(member) => Expr::Member(Box::new(callee), member),
+                Err(args) => Expr::Call(Box::new(callee), args),
+            },
+        )
+        .parse_next(&mut "a.b(c)")?;
+        let a = Expr::Member(Box::new(Expr::Name("a")), "b");
+        assert_eq!(chain, Expr::Call(Box::new(a), vec![Expr::Name("c")]));
+
+        assert_eq!(difference.parse_next(&mut long.as_str())?, -99_999);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_separated_with_recovery_should_collect_errors() -> ModalResult<()> {
+        let errors = RefCell::new(Vec::new());
+        let sync = SyncSet::new(&[',', ';']).quotes(&['"']);
+        let mut input = LocatingSlice::new("1,x\",\",(2,3),4 5,6;rest");
+        let items: Vec<u32> = parse_separated_with_recovery(
+            digit1.parse_to(),
+            ','.context(StrContext::Label("list")),
+            sync,
+            &errors,
+        )
+        .parse_next(&mut input)?;
+        assert_eq!(items, [1, 4, 6]);
+        assert_eq!(*input, ";rest");
+        let errors = errors.into_inner();
+        let offsets: Vec<usize> = errors.iter().map(ParseError::offset).collect();
+        assert_eq!(offsets, [2, 7, 14]);
+        assert_eq!(errors[2].message(), "invalid list");
+        Ok(())
+    }
+
+    #[test]
+    fn combinators_should_work_on_other_streams() -> ModalResult<()> {
+        let mut bytes: &[u8] = b"  GET  /";
+        assert_eq!(ws(alpha1).parse_next(&mut bytes)?, b"GET");
+        assert_eq!(bytes, b"/");
+
+        let mut located = LocatingSlice::new("a  b");
+        lexeme(alpha1).parse_next(&mut located)?;
+        assert_eq!(located.current_token_start(), 3);
+        Ok(())
+    }
+}
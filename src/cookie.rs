@@ -0,0 +1,306 @@
+use std::fmt;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::space0;
+use winnow::combinator::{cut_err, delimited, opt, preceded, repeat, separated, terminated};
+use winnow::error::StrContext;
+use winnow::token::take_while;
+
+use crate::ParseError;
+
+/// A parsed `Set-Cookie` header.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SetCookie {
+    pub name: String,
+    pub value: String,
+    pub expires: Option<DateTime<Utc>>,
+    /// Seconds; zero or negative means "expire now".
+    pub max_age: Option<i64>,
+    /// Lowercased, without a leading dot.
+    pub domain: Option<String>,
+    pub path: Option<String>,
+    pub same_site: Option<SameSite>,
+    pub secure: bool,
+    pub http_only: bool,
+    pub partitioned: bool,
+    /// Attributes this parser does not know, in order.
+    pub extensions: Vec<(String, Option<String>)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl SetCookie {
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Serializes in the attribute order browsers document.
+impl fmt::Display for SetCookie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+        if let Some(expires) = &self.expires {
+            write!(
+                f,
+                "; Expires={}",
+                expires.format("%a, %d %b %Y %H:%M:%S GMT")
+            )?;
+        }
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={max_age}")?;
+        }
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={domain}")?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, "; Path={path}")?;
+        }
+        if self.secure {
+            f.write_str("; Secure")?;
+        }
+        if self.http_only {
+            f.write_str("; HttpOnly")?;
+        }
+        if let Some(same_site) = self.same_site {
+            f.write_str(match same_site {
+                SameSite::Strict => "; SameSite=Strict",
+                SameSite::Lax => "; SameSite=Lax",
+                SameSite::None => "; SameSite=None",
+            })?;
+        }
+        if self.partitioned {
+            f.write_str("; Partitioned")?;
+        }
+        for (name, value) in &self.extensions {
+            match value {
+                Some(value) => write!(f, "; {name}={value}")?,
+                None => write!(f, "; {name}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses a request `Cookie` header (`a=1; b=2`) into name/value pairs.
+/// Double quotes around a value are removed.
+pub fn parse_cookie(input: &str) -> Result<Vec<(String, String)>, ParseError> {
+    delimited(
+        space0,
+        separated(0.., pair, (';', space0)),
+        (opt(';'), space0),
+    )
+    .map(|pairs: Vec<(&str, &str)>| {
+        pairs
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    })
+    .parse(input)
+    .map_err(ParseError::from)
+}
+
+/// Formats pairs as a request `Cookie` header.
+pub fn format_cookie<'a>(pairs: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    pairs
+        .into_iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Parses a `Set-Cookie` header. As RFC 6265 asks of user agents,
+/// attributes with unusable values (an unparsable `Expires`, say) are
+/// ignored rather than rejected.
+pub fn parse_set_cookie(input: &str) -> Result<SetCookie, ParseError> {
+    let ((name, value), attributes): (_, Vec<_>) = (
+        preceded(space0, pair),
+        repeat(0.., preceded((space0, ';', space0), attribute)),
+    )
+        .parse(input.trim_end())
+        .map_err(ParseError::from)?;
+    let mut cookie = SetCookie::new(name, value);
+    for (name, value) in attributes {
+        let value = value.map(str::trim);
+        match name.to_ascii_lowercase().as_str() {
+            "expires" => {
+                if let Some(expires) = value.and_then(parse_cookie_date) {
+                    cookie.expires = Some(expires);
+                }
+            }
+            "max-age" => {
+                if let Some(max_age) = value.and_then(|v| v.parse().ok()) {
+                    cookie.max_age = Some(max_age);
+                }
+            }
+            "domain" => {
+                if let Some(domain) = value
+                    .map(|v| v.trim_start_matches('.').trim_start())
+                    .filter(|v| !v.is_empty())
+                {
+                    cookie.domain = Some(domain.to_ascii_lowercase());
+                }
+            }
+            "path" => {
+                if let Some(path) = value.filter(|v| v.starts_with('/')) {
+                    cookie.path = Some(path.to_string());
+                }
+            }
+            "samesite" => {
+                cookie.same_site = match value.map(str::to_ascii_lowercase).as_deref() {
+                    Some("strict") => Some(SameSite::Strict),
+                    Some("lax") => Some(SameSite::Lax),
+                    Some("none") => Some(SameSite::None),
+                    _ => cookie.same_site,
+                };
+            }
+            "secure" => cookie.secure = true,
+            "httponly" => cookie.http_only = true,
+            "partitioned" => cookie.partitioned = true,
+            "" => {}
+            _ => cookie
+                .extensions
+                .push((name.to_string(), value.map(str::to_string))),
+        }
+    }
+    Ok(cookie)
+}
+
+fn pair<'i>(input: &mut &'i str) -> ModalResult<(&'i str, &'i str)> {
+    let name = take_while(1.., |c: char| {
+        c.is_ascii_graphic() && !matches!(c, '=' | ';' | ',')
+    })
+    .context(StrContext::Label("cookie name"))
+    .parse_next(input)?;
+    cut_err('=').parse_next(input)?;
+    let value = cookie_value.parse_next(input)?;
+    Ok((name, value))
+}
+
+/// A cookie value, optionally wrapped in double quotes.
+fn cookie_value<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    let is_octet = |c: char| c.is_ascii_graphic() && !matches!(c, '"' | ',' | ';' | '\\');
+    match opt('"').parse_next(input)? {
+        Some(_) => cut_err(terminated(take_while(0.., is_octet), '"'))
+            .context(StrContext::Label("cookie value"))
+            .parse_next(input),
+        None => take_while(0.., is_octet).parse_next(input),
+    }
+}
+
+/// An attribute of any characters but `;` and controls, which could not be
+/// written back.
+fn attribute<'i>(input: &mut &'i str) -> ModalResult<(&'i str, Option<&'i str>)> {
+    let is_av_octet = |c: char| c != ';' && !c.is_control();
+    (
+        take_while(0.., move |c: char| is_av_octet(c) && c != '=').map(str::trim),
+        opt(preceded('=', take_while(0.., is_av_octet))),
+    )
+        .parse_next(input)
+}
+
+/// `Expires` as sent in practice: RFC 1123, the old RFC 850 dashes, and
+/// ANSI C `asctime`.
+fn parse_cookie_date(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(datetime) = DateTime::parse_from_rfc2822(value) {
+        return Some(datetime.with_timezone(&Utc));
+    }
+    [
+        "%a, %d %b %Y %H:%M:%S GMT",
+        "%A, %d-%b-%y %H:%M:%S GMT",
+        "%a, %d-%b-%Y %H:%M:%S GMT",
+        "%a %b %e %H:%M:%S %Y",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    .map(|naive| naive.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn parse_cookie_should_work() -> Result<(), ParseError> {
+        let pairs = parse_cookie("sid=abc123; theme=\"dark\"; empty=;")?;
+        assert_eq!(
+            pairs,
+            [
+                ("sid".to_string(), "abc123".to_string()),
+                ("theme".into(), "dark".into()),
+                ("empty".into(), "".into()),
+            ]
+        );
+        assert_eq!(
+            format_cookie(pairs.iter().map(|(n, v)| (n.as_str(), v.as_str()))),
+            "sid=abc123; theme=dark; empty="
+        );
+        assert_eq!(parse_cookie("a=1; b").unwrap_err().offset(), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_set_cookie_should_work() -> Result<(), ParseError> {
+        let cookie = parse_set_cookie(
+            "id=a3fWa; Expires=Wed, 21 Oct 2015 07:28:00 GMT; Max-Age=2592000; Domain=.Example.com; Path=/docs; Secure; HttpOnly; SameSite=Lax; Priority=High",
+        )?;
+        assert_eq!(cookie.name, "id");
+        assert_eq!(cookie.value, "a3fWa");
+        assert_eq!(
+            cookie.expires,
+            Some(Utc.with_ymd_and_hms(2015, 10, 21, 7, 28, 0).unwrap())
+        );
+        assert_eq!(cookie.max_age, Some(2592000));
+        assert_eq!(cookie.domain.as_deref(), Some("example.com"));
+        assert_eq!(cookie.path.as_deref(), Some("/docs"));
+        assert!(cookie.secure && cookie.http_only);
+        assert_eq!(cookie.same_site, Some(SameSite::Lax));
+        assert_eq!(
+            cookie.extensions,
+            [("Priority".to_string(), Some("High".to_string()))]
+        );
+
+        let serialized = cookie.to_string();
+        assert_eq!(
+            serialized,
+            "id=a3fWa; Expires=Wed, 21 Oct 2015 07:28:00 GMT; Max-Age=2592000; Domain=example.com; Path=/docs; Secure; HttpOnly; SameSite=Lax; Priority=High"
+        );
+        assert_eq!(parse_set_cookie(&serialized)?, cookie);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_set_cookie_should_ignore_bad_attributes() -> Result<(), ParseError> {
+        let cookie = parse_set_cookie(
+            "a=b; expires=Sunday, 06-Nov-94 08:49:37 GMT; max-age=soon; path=relative; samesite=sometimes",
+        )?;
+        assert_eq!(
+            cookie.expires,
+            Some(Utc.with_ymd_and_hms(1994, 11, 6, 8, 49, 37).unwrap())
+        );
+        assert_eq!(
+            (cookie.max_age, cookie.path, cookie.same_site),
+            (None, None, None)
+        );
+        assert!(parse_set_cookie("=b").is_err());
+        assert!(parse_set_cookie("a=\"b").is_err());
+        let err = parse_set_cookie("a=b; Domain=.\tx.com").unwrap_err();
+        assert_eq!(err.offset(), 13);
+        let cookie = parse_set_cookie("a=b; Domain=. x.com")?;
+        assert_eq!(cookie.domain.as_deref(), Some("x.com"));
+        assert_eq!(parse_set_cookie(&cookie.to_string())?, cookie);
+        Ok(())
+    }
+}
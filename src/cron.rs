@@ -0,0 +1,392 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::digit1;
+use winnow::combinator::{alt, cut_err, opt, preceded, separated};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::take_while;
+
+use crate::ParseError;
+
+/// A parsed cron schedule. Each field is a bit set of the values it
+/// allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schedule {
+    seconds: u64,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// Whether day-of-month and day-of-week were both restricted, in which
+    /// case a day matching either one is enough.
+    either_day: bool,
+}
+
+/// The allowed range and value names of one field.
+struct Spec {
+    name: &'static str,
+    min: u32,
+    max: u32,
+    names: &'static [&'static str],
+}
+
+const SECOND: Spec = Spec {
+    name: "second",
+    min: 0,
+    max: 59,
+    names: &[],
+};
+const MINUTE: Spec = Spec {
+    name: "minute",
+    min: 0,
+    max: 59,
+    names: &[],
+};
+const HOUR: Spec = Spec {
+    name: "hour",
+    min: 0,
+    max: 23,
+    names: &[],
+};
+const DAY_OF_MONTH: Spec = Spec {
+    name: "day of month",
+    min: 1,
+    max: 31,
+    names: &[],
+};
+const MONTH: Spec = Spec {
+    name: "month",
+    min: 1,
+    max: 12,
+    names: &[
+        "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+    ],
+};
+// 7 is accepted as a second spelling of Sunday and folded onto 0.
+const DAY_OF_WEEK: Spec = Spec {
+    name: "day of week",
+    min: 0,
+    max: 7,
+    names: &["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"],
+};
+
+/// Parses a 5-field (`min hour dom month dow`) or 6-field (seconds first)
+/// expression, or one of the `@daily`-style macros.
+pub fn parse_cron(input: &str) -> Result<Schedule, ParseError> {
+    let trimmed = input.trim_start();
+    let base = input.len() - trimmed.len();
+    if let Some(name) = trimmed.strip_prefix('@') {
+        let expansion = match name.trim_end().to_ascii_lowercase().as_str() {
+            "yearly" | "annually" => "0 0 1 1 *",
+            "monthly" => "0 0 1 * *",
+            "weekly" => "0 0 * * 0",
+            "daily" | "midnight" => "0 0 * * *",
+            "hourly" => "0 * * * *",
+            _ => {
+                return Err(ParseError::new(
+                    base,
+                    format!("unknown macro `@{}`", name.trim_end()),
+                ));
+            }
+        };
+        return parse_cron(expansion);
+    }
+
+    let mut fields = Vec::new();
+    let mut offset = 0;
+    for part in input.split([' ', '\t']) {
+        if !part.is_empty() {
+            fields.push((offset, part));
+        }
+        offset += part.len() + 1;
+    }
+    let specs: &[Spec] = match fields.len() {
+        5 => &[MINUTE, HOUR, DAY_OF_MONTH, MONTH, DAY_OF_WEEK],
+        6 => &[SECOND, MINUTE, HOUR, DAY_OF_MONTH, MONTH, DAY_OF_WEEK],
+        n => {
+            return Err(ParseError::new(
+                base,
+                format!("expected 5 or 6 fields, found {n}"),
+            ));
+        }
+    };
+    let mut sets = Vec::with_capacity(6);
+    let mut restricted = Vec::with_capacity(6);
+    for ((offset, text), spec) in fields.iter().zip(specs) {
+        let set = (|input: &mut &str| field(input, spec))
+            .parse(*text)
+            .map_err(|e| {
+                let e = ParseError::from(e);
+                ParseError::new(offset + e.offset(), e.message())
+            })?;
+        sets.push(set);
+        restricted.push(!text.starts_with('*'));
+    }
+    if fields.len() == 5 {
+        sets.insert(0, 1);
+        restricted.insert(0, true);
+    }
+    // Fold Sunday-as-7 onto 0.
+    let days_of_week = (sets[5] | (sets[5] >> 7)) & 0x7f;
+    Ok(Schedule {
+        seconds: sets[0],
+        minutes: sets[1],
+        hours: sets[2],
+        days_of_month: sets[3],
+        months: sets[4],
+        days_of_week,
+        either_day: restricted[3] && restricted[5],
+    })
+}
+
+/// A comma-separated list of `*`, values and ranges, each with an optional
+/// `/step`.
+fn field(input: &mut &str, spec: &Spec) -> ModalResult<u64> {
+    let items: Vec<u64> = separated(1.., |input: &mut &str| item(input, spec), ',')
+        .context(StrContext::Label(spec.name))
+        .parse_next(input)?;
+    Ok(items.into_iter().fold(0, |acc, bits| acc | bits))
+}
+
+fn item(input: &mut &str, spec: &Spec) -> ModalResult<u64> {
+    let (start, end, explicit_end) = alt((
+        '*'.value((spec.min, spec.max, true)),
+        (
+            |input: &mut &str| value(input, spec),
+            opt(preceded(
+                '-',
+                cut_err(|input: &mut &str| value(input, spec)),
+            )),
+        )
+            .map(|(start, end)| match end {
+                Some(end) => (start, end, true),
+                None => (start, spec.max, false),
+            }),
+    ))
+    .parse_next(input)?;
+    let step = opt(preceded(
+        '/',
+        cut_err(digit1.parse_to::<u32>().verify(|step| *step > 0)).context(StrContext::Expected(
+            StrContextValue::Description("positive step"),
+        )),
+    ))
+    .parse_next(input)?;
+    // `5/15` means "from 5 to the end, every 15"; a bare `5` is just 5.
+    let end = match (step, explicit_end) {
+        (None, false) => start,
+        _ => end,
+    };
+    if start > end {
+        return cut_err(winnow::combinator::fail)
+            .context(StrContext::Expected(StrContextValue::Description(
+                "a range that does not run backwards",
+            )))
+            .parse_next(input);
+    }
+    let step = step.unwrap_or(1) as usize;
+    Ok((start..=end).step_by(step).fold(0, |acc, v| acc | (1 << v)))
+}
+
+fn value(input: &mut &str, spec: &Spec) -> ModalResult<u32> {
+    let number = digit1
+        .parse_to::<u32>()
+        .verify(|v| (spec.min..=spec.max).contains(v));
+    let name = take_while(3, |c: char| c.is_ascii_alphabetic()).verify_map(|word: &str| {
+        spec.names
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(word))
+            .map(|i| i as u32 + spec.min)
+    });
+    alt((number, name))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "value in range",
+        )))
+        .parse_next(input)
+}
+
+impl Schedule {
+    /// A schedule from ready-made bit sets, for callers that restrict
+    /// fields cron syntax cannot express. A day must match both day sets.
+    pub(crate) fn from_sets(
+        seconds: u64,
+        minutes: u64,
+        hours: u64,
+        days_of_month: u64,
+        months: u64,
+        days_of_week: u64,
+    ) -> Self {
+        Schedule {
+            seconds,
+            minutes,
+            hours,
+            days_of_month,
+            months,
+            days_of_week,
+            either_day: false,
+        }
+    }
+
+    pub fn matches<Tz: TimeZone>(&self, datetime: &DateTime<Tz>) -> bool {
+        let naive = datetime.naive_local();
+        self.matches_date(naive.date())
+            && bit(self.hours, naive.hour())
+            && bit(self.minutes, naive.minute())
+            && bit(self.seconds, naive.second())
+    }
+
+    /// The first time strictly after `after` that the schedule fires, or
+    /// `None` if it can never fire (e.g. `0 0 30 2 *`).
+    ///
+    /// Local times skipped by a DST transition never fire; repeated ones
+    /// fire on their first occurrence.
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let tz = after.timezone();
+        let mut start = after.naive_local().with_nanosecond(0)? + Duration::seconds(1);
+        loop {
+            let naive = self.next_naive(start)?;
+            match tz.from_local_datetime(&naive) {
+                chrono::LocalResult::Single(dt) if dt > *after => return Some(dt),
+                chrono::LocalResult::Ambiguous(first, second) => {
+                    if first > *after {
+                        return Some(first);
+                    } else if second > *after {
+                        return Some(second);
+                    }
+                }
+                _ => {}
+            }
+            start = naive + Duration::seconds(1);
+        }
+    }
+
+    /// An endless iterator over the firing times after `after`.
+    pub fn upcoming<Tz: TimeZone>(
+        &self,
+        after: DateTime<Tz>,
+    ) -> impl Iterator<Item = DateTime<Tz>> {
+        let schedule = *self;
+        std::iter::successors(schedule.next_after(&after), move |prev| {
+            schedule.next_after(prev)
+        })
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        let dom = bit(self.days_of_month, date.day());
+        let dow = bit(self.days_of_week, date.weekday().num_days_from_sunday());
+        bit(self.months, date.month())
+            && if self.either_day {
+                dom || dow
+            } else {
+                dom && dow
+            }
+    }
+
+    /// Searches wall-clock time field by field, skipping whole months, days,
+    /// hours or minutes that cannot match.
+    fn next_naive(&self, mut t: NaiveDateTime) -> Option<NaiveDateTime> {
+        // Every month/day-of-month/weekday combination repeats within 28
+        // years, so searching further means there is no match at all.
+        let limit = t.year() + 28;
+        while t.year() <= limit {
+            if !bit(self.months, t.month()) {
+                let (year, month) = if t.month() == 12 {
+                    (t.year() + 1, 1)
+                } else {
+                    (t.year(), t.month() + 1)
+                };
+                t = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.matches_date(t.date()) {
+                t = t.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if !bit(self.hours, t.hour()) {
+                t = t.with_minute(0)?.with_second(0)? + Duration::hours(1);
+            } else if !bit(self.minutes, t.minute()) {
+                t = t.with_second(0)? + Duration::minutes(1);
+            } else if !bit(self.seconds, t.second()) {
+                t += Duration::seconds(1);
+            } else {
+                return Some(t);
+            }
+        }
+        None
+    }
+}
+
+fn bit(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{FixedOffset, Utc};
+
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn next_after_should_work() -> Result<(), ParseError> {
+        let cases = [
+            (
+                "*/15 9-17 * * MON-FRI",
+                "2024-03-08T17:50:00Z",
+                "2024-03-11T09:00:00Z",
+            ),
+            ("0 0 29 2 *", "2024-03-01T00:00:00Z", "2028-02-29T00:00:00Z"),
+            (
+                "30 0 1,15 * 5",
+                "2024-03-01T12:00:00Z",
+                "2024-03-08T00:30:00Z",
+            ),
+            ("@monthly", "2024-12-31T23:59:59Z", "2025-01-01T00:00:00Z"),
+            (
+                "*/10 * * * * *",
+                "2024-01-01T00:00:05Z",
+                "2024-01-01T00:00:10Z",
+            ),
+            (
+                "0 12 * Jun sun",
+                "2024-01-01T00:00:00Z",
+                "2024-06-02T12:00:00Z",
+            ),
+            ("0 12 * * 7", "2024-06-02T12:00:00Z", "2024-06-09T12:00:00Z"),
+        ];
+        for (expr, after, expected) in cases {
+            let schedule = parse_cron(expr)?;
+            let next = schedule.next_after(&utc(after));
+            assert_eq!(next, Some(utc(expected)), "{expr}");
+            assert!(schedule.matches(&next.unwrap()));
+        }
+        assert_eq!(
+            parse_cron("0 0 30 2 *")?.next_after(&utc("2024-01-01T00:00:00Z")),
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn upcoming_should_keep_the_time_zone() -> Result<(), ParseError> {
+        let tz = FixedOffset::east_opt(8 * 3600).unwrap();
+        let after = tz.with_ymd_and_hms(2024, 1, 1, 23, 0, 0).unwrap();
+        let times: Vec<_> = parse_cron("@daily")?.upcoming(after).take(2).collect();
+        assert_eq!(times[0], tz.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap());
+        assert_eq!(times[1], tz.with_ymd_and_hms(2024, 1, 3, 0, 0, 0).unwrap());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_cron_should_report_errors() {
+        let err = parse_cron("0 24 * * *").unwrap_err();
+        assert_eq!(err.offset(), 2);
+        assert_eq!(parse_cron("0 0 * FOO *").unwrap_err().offset(), 6);
+        assert_eq!(parse_cron("*/0 * * * *").unwrap_err().offset(), 2);
+        assert!(parse_cron("5-1 * * * *").is_err());
+        assert_eq!(
+            parse_cron("* * *").unwrap_err().message(),
+            "expected 5 or 6 fields, found 3"
+        );
+        assert!(parse_cron("@reboot").is_err());
+    }
+}
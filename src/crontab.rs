@@ -0,0 +1,265 @@
+use crate::ParseError;
+use crate::cron::{Schedule, parse_cron};
+
+/// Whether lines carry a user field between the schedule and the command.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    /// A per-user crontab, as edited by `crontab -e`.
+    #[default]
+    User,
+    /// `/etc/crontab` and `/etc/cron.d/*`.
+    System,
+}
+
+/// A crontab file: the lines that parsed, plus one error for each line that
+/// did not.
+#[derive(Debug, Clone, Default)]
+pub struct Crontab {
+    pub entries: Vec<Entry>,
+    pub errors: Vec<ParseError>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Entry {
+    /// `NAME=value`, in effect for the jobs below it.
+    Env {
+        name: String,
+        value: String,
+    },
+    Job(Job),
+}
+
+#[derive(Debug, Clone)]
+pub struct Job {
+    /// 1-based line number.
+    pub line: usize,
+    pub when: When,
+    /// Only in [`Format::System`] files.
+    pub user: Option<String>,
+    /// The command up to the first unescaped `%`, with `\%` unescaped.
+    pub command: String,
+    /// What follows the first `%`, with the remaining ones turned into
+    /// newlines; cron feeds it to the command's standard input.
+    pub stdin: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum When {
+    Schedule(Schedule),
+    /// `@reboot`: once, when the daemon starts.
+    Reboot,
+}
+
+impl Crontab {
+    pub fn jobs(&self) -> impl Iterator<Item = &Job> {
+        self.entries.iter().filter_map(|entry| match entry {
+            Entry::Job(job) => Some(job),
+            Entry::Env { .. } => None,
+        })
+    }
+
+    /// Each job with the environment assignments above it, later ones
+    /// replacing earlier ones of the same name.
+    pub fn jobs_with_env(&self) -> Vec<(&Job, Vec<(&str, &str)>)> {
+        let mut env: Vec<(&str, &str)> = Vec::new();
+        let mut jobs = Vec::new();
+        for entry in &self.entries {
+            match entry {
+                Entry::Env { name, value } => {
+                    env.retain(|(n, _)| n != name);
+                    env.push((name, value));
+                }
+                Entry::Job(job) => jobs.push((job, env.clone())),
+            }
+        }
+        jobs
+    }
+}
+
+/// Parses a crontab file. Blank lines and `#` comments are skipped. A line
+/// that fails leaves an error, with its offset into `input`, and parsing
+/// carries on with the next one.
+pub fn parse_crontab(input: &str, format: Format) -> Crontab {
+    let mut crontab = Crontab::default();
+    let mut offset = 0;
+    for (index, raw) in input.split_inclusive('\n').enumerate() {
+        let start = offset;
+        offset += raw.len();
+        let line = raw.trim_end_matches(['\n', '\r']);
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let entry = match env(trimmed) {
+            Some((name, value)) => Ok(Entry::Env { name, value }),
+            None => job(line, index + 1, format).map(Entry::Job),
+        };
+        match entry {
+            Ok(entry) => crontab.entries.push(entry),
+            Err(e) => crontab
+                .errors
+                .push(ParseError::new(start + e.offset(), e.message())),
+        }
+    }
+    crontab
+}
+
+/// `NAME = value`, where the value may be quoted to keep its spaces.
+fn env(line: &str) -> Option<(String, String)> {
+    let (name, value) = line.split_once('=')?;
+    let name = name.trim_end();
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return None;
+    }
+    let value = value.trim();
+    let unquoted = ['"', '\'']
+        .into_iter()
+        .find_map(|q| value.strip_prefix(q)?.strip_suffix(q))
+        .unwrap_or(value);
+    Some((name.to_string(), unquoted.to_string()))
+}
+
+fn job(line: &str, number: usize, format: Format) -> Result<Job, ParseError> {
+    let mut words = words(line);
+    let (first, macro_name) = words.next().expect("blank lines are skipped before this");
+    let (when, rest) = if macro_name.starts_with('@') {
+        let when = if macro_name.eq_ignore_ascii_case("@reboot") {
+            When::Reboot
+        } else {
+            When::Schedule(parse_cron(macro_name).map_err(|e| ParseError::new(first, e.message()))?)
+        };
+        (when, &line[first + macro_name.len()..])
+    } else {
+        // Crontab files have no seconds field, so the schedule is exactly
+        // five words.
+        let Some((last, word)) = words.nth(3) else {
+            return Err(ParseError::new(
+                line.len(),
+                "expected 5 schedule fields and a command",
+            ));
+        };
+        let end = last + word.len();
+        let schedule = parse_cron(&line[first..end])
+            .map_err(|e| ParseError::new(first + e.offset(), e.message()))?;
+        (When::Schedule(schedule), &line[end..])
+    };
+    let mut rest = rest.trim_start();
+    let user = match format {
+        Format::User => None,
+        Format::System => {
+            let user = rest.split_whitespace().next().unwrap_or_default();
+            rest = rest[user.len()..].trim_start();
+            Some(user.to_string())
+        }
+    };
+    if rest.is_empty() {
+        let what = match user.as_deref() {
+            Some("") => "a user and a command",
+            _ => "a command",
+        };
+        return Err(ParseError::new(line.len(), format!("expected {what}")));
+    }
+    let (command, stdin) = split_percent(rest);
+    Ok(Job {
+        line: number,
+        when,
+        user,
+        command,
+        stdin,
+    })
+}
+
+/// Words with their byte offsets in `line`.
+fn words(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    line.split([' ', '\t'])
+        .scan(0, |offset, word| {
+            let start = *offset;
+            *offset += word.len() + 1;
+            Some((start, word))
+        })
+        .filter(|(_, word)| !word.is_empty())
+}
+
+fn split_percent(command: &str) -> (String, Option<String>) {
+    let mut parts = vec![String::new()];
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'%') => {
+                parts.last_mut().unwrap().push('%');
+                chars.next();
+            }
+            '%' => parts.push(String::new()),
+            c => parts.last_mut().unwrap().push(c),
+        }
+    }
+    let command = parts.remove(0).trim_end().to_string();
+    let stdin = (!parts.is_empty()).then(|| parts.join("\n"));
+    (command, stdin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYSTEM: &str = "\
+SHELL=/bin/sh
+PATH = \"/usr/sbin:/usr/bin\"
+# m h dom mon dow user command
+17 *\t* * *  root    cd / && run-parts --report /etc/cron.hourly
+@reboot      backup  /usr/local/bin/restore --quiet
+0 0 1 1 *    root    date +\\%Y >> /var/log/years
+MAILTO=ops@example.com
+30 2 * * 0   root    mail -s report admin%Weekly report%done
+";
+
+    #[test]
+    fn parse_crontab_should_work() {
+        let crontab = parse_crontab(SYSTEM, Format::System);
+        assert!(crontab.errors.is_empty(), "{:?}", crontab.errors);
+        let jobs = crontab.jobs_with_env();
+        assert_eq!(jobs.len(), 4);
+        let (hourly, env) = &jobs[0];
+        assert_eq!(hourly.line, 4);
+        assert_eq!(hourly.user.as_deref(), Some("root"));
+        assert_eq!(
+            hourly.command,
+            "cd / && run-parts --report /etc/cron.hourly"
+        );
+        assert_eq!(env, &[("SHELL", "/bin/sh"), ("PATH", "/usr/sbin:/usr/bin")]);
+        assert_eq!(jobs[1].0.when, When::Reboot);
+        assert_eq!(jobs[2].0.command, "date +%Y >> /var/log/years");
+        let (mail, env) = &jobs[3];
+        assert_eq!(mail.command, "mail -s report admin");
+        assert_eq!(mail.stdin.as_deref(), Some("Weekly report\ndone"));
+        assert_eq!(env.last(), Some(&("MAILTO", "ops@example.com")));
+    }
+
+    #[test]
+    fn user_crontabs_have_no_user_field() {
+        let crontab = parse_crontab(
+            "@daily $HOME/bin/sync  \n*/5 * * * * echo hi\n",
+            Format::User,
+        );
+        let jobs: Vec<_> = crontab.jobs().collect();
+        assert_eq!(jobs[0].user, None);
+        assert_eq!(jobs[0].command, "$HOME/bin/sync");
+        assert_eq!(
+            jobs[0].when,
+            When::Schedule(parse_cron("0 0 * * *").unwrap())
+        );
+        assert_eq!(jobs[1].command, "echo hi");
+    }
+
+    #[test]
+    fn bad_lines_should_not_abort_the_file() {
+        let input = "0 25 * * * root a\n* * *\n@hourly root\n@sometimes b\n* * * * * root ok\n";
+        let crontab = parse_crontab(input, Format::System);
+        assert_eq!(crontab.jobs().count(), 1);
+        let offsets: Vec<_> = crontab.errors.iter().map(|e| e.offset()).collect();
+        assert_eq!(offsets, [2, 23, 36, 37]);
+        assert_eq!(crontab.errors[2].message(), "expected a command");
+    }
+}
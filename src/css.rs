@@ -0,0 +1,5 @@
+//! Grammars for pieces of CSS.
+
+pub mod color;
+pub mod declaration;
+pub mod selector;
@@ -0,0 +1,498 @@
+//! CSS color values: hex notation, `rgb()`, `hsl()` and named colors.
+
+use std::fmt;
+use std::str::FromStr;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{digit0, digit1, multispace0};
+use winnow::combinator::{alt, cut_err, delimited, fail, opt, peek, preceded, terminated};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::{one_of, take_while};
+
+use crate::ParseError;
+
+/// A color in sRGB with 8 bits per channel, alpha included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgba {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    /// 255 is opaque.
+    pub a: u8,
+}
+
+impl Rgba {
+    pub const TRANSPARENT: Rgba = Rgba::new(0, 0, 0, 0);
+
+    pub const fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Rgba { r, g, b, a }
+    }
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Rgba::new(r, g, b, 255)
+    }
+
+    /// The CSS name for this exact color, if it has one. Where two names
+    /// share a value the `gray` spelling wins over `grey`.
+    pub fn name(&self) -> Option<&'static str> {
+        if *self == Rgba::TRANSPARENT {
+            return Some("transparent");
+        }
+        let value = u32::from_be_bytes([0, self.r, self.g, self.b]);
+        NAMED
+            .iter()
+            .find(|(_, v)| self.a == 255 && *v == value)
+            .map(|(name, _)| *name)
+    }
+
+    /// The `rgb()` form, with alpha as a number when it is not opaque:
+    /// `rgb(255 0 0)` or `rgb(255 0 0 / 0.5)`.
+    pub fn to_rgb_string(&self) -> String {
+        let Rgba { r, g, b, a } = *self;
+        match a {
+            255 => format!("rgb({r} {g} {b})"),
+            a => format!("rgb({r} {g} {b} / {})", alpha_string(a)),
+        }
+    }
+}
+
+/// The shortest decimal that maps back to the same 8-bit alpha.
+fn alpha_string(a: u8) -> String {
+    (1..=3)
+        .map(|digits| format!("{:.*}", digits, a as f64 / 255.0))
+        .find(|s| {
+            s.parse::<f64>()
+                .is_ok_and(|x| (x * 255.0).round() as u8 == a)
+        })
+        .map(|s| s.trim_end_matches('0').trim_end_matches('.').to_string())
+        .unwrap_or_default()
+}
+
+/// The shortest hex form: `#rgb` when every channel repeats a digit, and
+/// the alpha digits only when the color is not opaque.
+impl fmt::Display for Rgba {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Rgba { r, g, b, a } = *self;
+        let short = [r, g, b, a].iter().all(|c| c >> 4 == c & 0xf);
+        match (short, a) {
+            (true, 255) => write!(f, "#{:x}{:x}{:x}", r & 0xf, g & 0xf, b & 0xf),
+            (true, _) => write!(f, "#{:x}{:x}{:x}{:x}", r & 0xf, g & 0xf, b & 0xf, a & 0xf),
+            (false, 255) => write!(f, "#{r:02x}{g:02x}{b:02x}"),
+            (false, _) => write!(f, "#{r:02x}{g:02x}{b:02x}{a:02x}"),
+        }
+    }
+}
+
+impl FromStr for Rgba {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_color(s)
+    }
+}
+
+/// Parses one color value. Function and color names ignore case, and both
+/// the comma-separated and the space-separated argument syntax work.
+pub fn parse_color(input: &str) -> Result<Rgba, ParseError> {
+    delimited(multispace0, color, multispace0)
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+pub(crate) fn color(input: &mut &str) -> ModalResult<Rgba> {
+    alt((hex, function, named))
+        .context(StrContext::Label("color"))
+        .parse_next(input)
+}
+
+fn hex(input: &mut &str) -> ModalResult<Rgba> {
+    preceded(
+        '#',
+        cut_err(
+            take_while(3..=8, |c: char| c.is_ascii_hexdigit())
+                .verify(|digits: &str| matches!(digits.len(), 3 | 4 | 6 | 8)),
+        )
+        .context(StrContext::Expected(StrContextValue::Description(
+            "3, 4, 6 or 8 hex digits",
+        ))),
+    )
+    .map(|digits: &str| {
+        let digit = |i: usize| u8::from_str_radix(&digits[i..i + 1], 16).unwrap();
+        let pair = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).unwrap();
+        match digits.len() {
+            3 | 4 => {
+                let a = if digits.len() == 4 {
+                    digit(3) * 17
+                } else {
+                    255
+                };
+                Rgba::new(digit(0) * 17, digit(1) * 17, digit(2) * 17, a)
+            }
+            _ => {
+                let a = if digits.len() == 8 { pair(6) } else { 255 };
+                Rgba::new(pair(0), pair(2), pair(4), a)
+            }
+        }
+    })
+    .parse_next(input)
+}
+
+fn named(input: &mut &str) -> ModalResult<Rgba> {
+    let name = take_while(1.., |c: char| c.is_ascii_alphabetic()).parse_next(input)?;
+    if name.eq_ignore_ascii_case("transparent") {
+        return Ok(Rgba::TRANSPARENT);
+    }
+    match NAMED.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)) {
+        Some((_, value)) => {
+            let [_, r, g, b] = value.to_be_bytes();
+            Ok(Rgba::rgb(r, g, b))
+        }
+        None => cut_err(fail)
+            .context(StrContext::Label("color name"))
+            .parse_next(input),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Component {
+    Number(f64),
+    Percent(f64),
+    /// Already converted to degrees.
+    Angle(f64),
+}
+
+fn function(input: &mut &str) -> ModalResult<Rgba> {
+    let name = terminated(take_while(1.., |c: char| c.is_ascii_alphabetic()), '(')
+        .parse_next(input)?
+        .to_ascii_lowercase();
+    let convert: fn([Component; 3], f64) -> Option<Rgba> = match name.as_str() {
+        "rgb" | "rgba" => from_rgb,
+        "hsl" | "hsla" => from_hsl,
+        _ => {
+            return cut_err(fail)
+                .context(StrContext::Label("color function"))
+                .parse_next(input);
+        }
+    };
+    cut_err(
+        terminated(arguments, (multispace0, ')'))
+            .verify_map(|(channels, alpha)| convert(channels, alpha?))
+            .context(StrContext::Label("color arguments")),
+    )
+    .parse_next(input)
+}
+
+/// Three components and an optional alpha, either all separated by commas
+/// or separated by spaces with `/` before the alpha. Alpha defaults to 1.
+fn arguments(input: &mut &str) -> ModalResult<([Component; 3], Option<f64>)> {
+    let first = preceded(multispace0, component).parse_next(input)?;
+    let legacy = opt(peek((multispace0, ','))).parse_next(input)?.is_some();
+    let alpha_sep = if legacy { ',' } else { '/' };
+    let next = |input: &mut &str| {
+        if legacy {
+            preceded((multispace0, ',', multispace0), component).parse_next(input)
+        } else {
+            preceded(take_while(1.., char::is_whitespace), component).parse_next(input)
+        }
+    };
+    let mut rest = [first; 2];
+    for slot in &mut rest {
+        *slot = next(input)?;
+    }
+    let alpha =
+        opt(preceded((multispace0, alpha_sep, multispace0), component)).parse_next(input)?;
+    let alpha = match alpha {
+        None => Some(1.0),
+        Some(Component::Number(n)) => Some(n),
+        Some(Component::Percent(p)) => Some(p / 100.0),
+        Some(Component::Angle(_)) => None,
+    };
+    Ok(([first, rest[0], rest[1]], alpha))
+}
+
+fn component(input: &mut &str) -> ModalResult<Component> {
+    let value = number.parse_next(input)?;
+    let unit = opt(alt((
+        "%",
+        take_while(1.., |c: char| c.is_ascii_alphabetic()),
+    )))
+    .parse_next(input)?;
+    let degrees = match unit.map(str::to_ascii_lowercase).as_deref() {
+        None => return Ok(Component::Number(value)),
+        Some("%") => return Ok(Component::Percent(value)),
+        Some("deg") => value,
+        Some("rad") => value.to_degrees(),
+        Some("grad") => value * 0.9,
+        Some("turn") => value * 360.0,
+        Some(_) => {
+            return cut_err(fail)
+                .context(StrContext::Label("unit"))
+                .parse_next(input);
+        }
+    };
+    Ok(Component::Angle(degrees))
+}
+
+fn number(input: &mut &str) -> ModalResult<f64> {
+    (
+        opt(one_of(['+', '-'])),
+        alt(((digit1, opt(('.', digit0))).void(), ('.', digit1).void())),
+        opt((one_of(['e', 'E']), opt(one_of(['+', '-'])), digit1)),
+    )
+        .take()
+        .parse_to()
+        .parse_next(input)
+}
+
+fn channel(value: f64) -> u8 {
+    value.clamp(0.0, 255.0).round() as u8
+}
+
+fn from_rgb(channels: [Component; 3], alpha: f64) -> Option<Rgba> {
+    let mut rgb = [0; 3];
+    for (out, c) in rgb.iter_mut().zip(channels) {
+        *out = match c {
+            Component::Number(n) => channel(n),
+            Component::Percent(p) => channel(p * 255.0 / 100.0),
+            Component::Angle(_) => return None,
+        };
+    }
+    Some(Rgba::new(rgb[0], rgb[1], rgb[2], channel(alpha * 255.0)))
+}
+
+fn from_hsl([h, s, l]: [Component; 3], alpha: f64) -> Option<Rgba> {
+    let hue = match h {
+        Component::Number(deg) | Component::Angle(deg) => deg.rem_euclid(360.0),
+        Component::Percent(_) => return None,
+    };
+    // Plain numbers are allowed here by the modern syntax.
+    let fraction = |c| match c {
+        Component::Number(n) | Component::Percent(n) => Some((n / 100.0).clamp(0.0, 1.0)),
+        Component::Angle(_) => None,
+    };
+    let (s, l) = (fraction(s)?, fraction(l)?);
+    let k = |n: f64| (n + hue / 30.0) % 12.0;
+    let a = s * l.min(1.0 - l);
+    let f = |n: f64| l - a * (k(n) - 3.0).min(9.0 - k(n)).clamp(-1.0, 1.0);
+    Some(Rgba::new(
+        channel(f(0.0) * 255.0),
+        channel(f(8.0) * 255.0),
+        channel(f(4.0) * 255.0),
+        channel(alpha * 255.0),
+    ))
+}
+
+/// The named colors of CSS Color Level 4, `transparent` aside.
+const NAMED: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_color_should_work() -> Result<(), ParseError> {
+        assert_eq!(parse_color("#fff")?, Rgba::rgb(255, 255, 255));
+        assert_eq!(parse_color("#a1b2c3d4")?, Rgba::new(0xa1, 0xb2, 0xc3, 0xd4));
+        assert_eq!(parse_color("#0f08")?, Rgba::new(0, 255, 0, 0x88));
+        assert_eq!(parse_color("rgb(255, 0, 128)")?, Rgba::rgb(255, 0, 128));
+        assert_eq!(
+            parse_color("RGBA(100%, 50%, 0%, 0.5)")?,
+            Rgba::new(255, 128, 0, 128)
+        );
+        assert_eq!(
+            parse_color("rgb(300 -5 12.4 / 25%)")?,
+            Rgba::new(255, 0, 12, 64)
+        );
+        assert_eq!(parse_color("hsl(120, 100%, 25%)")?, Rgba::rgb(0, 128, 0));
+        assert_eq!(
+            parse_color("hsl(0.5turn 100 50 / .5)")?,
+            Rgba::new(0, 255, 255, 128)
+        );
+        assert_eq!(parse_color(" RebeccaPurple ")?, Rgba::rgb(0x66, 0x33, 0x99));
+        assert_eq!(parse_color("transparent")?, Rgba::TRANSPARENT);
+        Ok(())
+    }
+
+    #[test]
+    fn rgba_should_format() -> Result<(), ParseError> {
+        assert_eq!(Rgba::rgb(255, 0, 0).to_string(), "#f00");
+        assert_eq!(Rgba::new(0x11, 0x22, 0x33, 0x44).to_string(), "#1234");
+        assert_eq!(Rgba::rgb(0xa1, 0xb2, 0xc3).to_string(), "#a1b2c3");
+        assert_eq!(Rgba::new(1, 2, 3, 128).to_string(), "#01020380");
+        assert_eq!(
+            Rgba::new(255, 0, 0, 128).to_rgb_string(),
+            "rgb(255 0 0 / 0.5)"
+        );
+        assert_eq!(Rgba::rgb(1, 2, 3).to_rgb_string(), "rgb(1 2 3)");
+        assert_eq!(parse_color("#808080")?.name(), Some("gray"));
+        assert_eq!(parse_color("#808081")?.name(), None);
+        for color in ["#abc", "#a1b2c3d4", "rgb(10 20 30 / 0.3)"] {
+            let parsed = parse_color(color)?;
+            assert_eq!(parse_color(&parsed.to_string())?, parsed);
+            assert_eq!(parse_color(&parsed.to_rgb_string())?, parsed);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn parse_color_should_report_errors() {
+        assert_eq!(parse_color("#12345").unwrap_err().offset(), 1);
+        assert!(parse_color("blurple").is_err());
+        assert!(parse_color("rgb(1, 2)").is_err());
+        assert!(parse_color("rgb(1 2, 3)").is_err());
+        assert!(parse_color("rgb(1, 2, 3 / 0.5)").is_err());
+        assert!(parse_color("hsl(10%, 50%, 50%)").is_err());
+        assert!(parse_color("lab(50 0 0)").is_err());
+        assert!(parse_color("rgb(1 2 3").is_err());
+    }
+}
@@ -0,0 +1,380 @@
+//! Declaration blocks, the part of a rule between the braces or the
+//! contents of a `style` attribute.
+
+use std::fmt;
+use std::str::FromStr;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{Caseless, digit0, digit1};
+use winnow::combinator::{
+    alt, cut_err, delimited, empty, fail, opt, peek, preceded, repeat, separated, terminated,
+};
+use winnow::error::{ContextError, ErrMode, StrContext, StrContextValue};
+use winnow::stream::Stream;
+use winnow::token::{any, none_of, one_of, take_till, take_while};
+
+use super::color::{Rgba, parse_color};
+use crate::ParseError;
+use crate::combinators::Trivia;
+
+/// Declarations in source order, duplicates kept.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Block {
+    pub declarations: Vec<Declaration>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration {
+    /// Lowercased, except for custom properties such as `--Accent`.
+    pub name: String,
+    pub value: Vec<Value>,
+    pub important: bool,
+}
+
+/// One component of a property value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Ident(String),
+    String(String),
+    Number(f64),
+    Percentage(f64),
+    Dimension(f64, String),
+    /// `#` and what follows it, e.g. a hex color.
+    Hash(String),
+    /// `url(...)`, quoted or not.
+    Url(String),
+    Function(String, Vec<Value>),
+    /// Any other single character: `,`, `/`, the operators in `calc()`.
+    Delim(char),
+}
+
+impl Block {
+    /// The declaration that wins for `name`: the last `!important` one if
+    /// there is any, otherwise the last one.
+    pub fn get(&self, name: &str) -> Option<&Declaration> {
+        let mut named = self
+            .declarations
+            .iter()
+            .rev()
+            .filter(|d| d.name.eq_ignore_ascii_case(name));
+        let last = named.clone().next();
+        named.find(|d| d.important).or(last)
+    }
+
+    /// Adds `other`'s declarations after this block's, so they win unless
+    /// this block marks a property `!important`.
+    pub fn extend(&mut self, other: Block) {
+        self.declarations.extend(other.declarations);
+    }
+}
+
+impl Value {
+    /// The color this component spells, if any.
+    pub fn color(&self) -> Option<Rgba> {
+        match self {
+            Value::Ident(_) | Value::Hash(_) | Value::Function(..) => {
+                parse_color(&self.to_string()).ok()
+            }
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Block {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_declarations(s)
+    }
+}
+
+impl fmt::Display for Block {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, declaration) in self.declarations.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{declaration};")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Declaration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.name)?;
+        write_values(f, &self.value)?;
+        if self.important {
+            f.write_str(" !important")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Ident(ident) => f.write_str(ident),
+            Value::String(s) => write!(f, "\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"")),
+            Value::Number(n) => write!(f, "{n}"),
+            Value::Percentage(n) => write!(f, "{n}%"),
+            Value::Dimension(n, unit) => write!(f, "{n}{unit}"),
+            Value::Hash(hash) => write!(f, "#{hash}"),
+            Value::Url(url) => write!(
+                f,
+                "url(\"{}\")",
+                url.replace('\\', "\\\\").replace('"', "\\\"")
+            ),
+            Value::Function(name, args) => {
+                write!(f, "{name}(")?;
+                write_values(f, args)?;
+                f.write_str(")")
+            }
+            Value::Delim(c) => write!(f, "{c}"),
+        }
+    }
+}
+
+/// Space-separated, except that commas attach to what precedes them.
+fn write_values(f: &mut fmt::Formatter<'_>, values: &[Value]) -> fmt::Result {
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 && *value != Value::Delim(',') {
+            f.write_str(" ")?;
+        }
+        write!(f, "{value}")?;
+    }
+    Ok(())
+}
+
+/// Parses `color: red; margin: 0 auto !important` with comments anywhere
+/// between tokens. Empty declarations are skipped and the last `;` is
+/// optional.
+pub fn parse_declarations(input: &str) -> Result<Block, ParseError> {
+    delimited(
+        trivia,
+        separated(0.., opt(declaration), (';', trivia)),
+        trivia,
+    )
+    .map(|declarations: Vec<Option<Declaration>>| Block {
+        declarations: declarations.into_iter().flatten().collect(),
+    })
+    .parse(input)
+    .map_err(ParseError::from)
+}
+
+/// White space and `/* */` comments.
+fn trivia(input: &mut &str) -> ModalResult<()> {
+    let mut trivia = Trivia::new().block_comments("/*", "*/");
+    trivia.parse_next(input)
+}
+
+fn declaration(input: &mut &str) -> ModalResult<Declaration> {
+    let name = terminated(ident, trivia).parse_next(input)?;
+    cut_err(':')
+        .context(StrContext::Label("declaration"))
+        .context(StrContext::Expected(StrContextValue::CharLiteral(':')))
+        .parse_next(input)?;
+    let value = preceded(trivia, values).parse_next(input)?;
+    let important = opt(terminated(
+        ('!', trivia, cut_err(Caseless("important"))),
+        trivia,
+    ))
+    .parse_next(input)?
+    .is_some();
+    Ok(Declaration {
+        name: if name.starts_with("--") {
+            name
+        } else {
+            name.to_ascii_lowercase()
+        },
+        value,
+        important,
+    })
+}
+
+fn values(input: &mut &str) -> ModalResult<Vec<Value>> {
+    repeat(0.., terminated(value, trivia)).parse_next(input)
+}
+
+fn value(input: &mut &str) -> ModalResult<Value> {
+    alt((
+        url,
+        (ident, opt(function_args)).map(|(name, args)| match args {
+            Some(args) => Value::Function(name, args),
+            None => Value::Ident(name),
+        }),
+        numeric,
+        alt((quoted('"'), quoted('\''))).map(Value::String),
+        preceded('#', cut_err(take_while(1.., is_name_char)))
+            .map(|hash: &str| Value::Hash(hash.to_string())),
+        none_of(|c: char| "!;{}()\"'".contains(c) || c.is_whitespace()).map(Value::Delim),
+    ))
+    .parse_next(input)
+}
+
+fn function_args(input: &mut &str) -> ModalResult<Vec<Value>> {
+    delimited(
+        ('(', trivia),
+        cut_err(values),
+        cut_err(')').context(StrContext::Expected(StrContextValue::CharLiteral(')'))),
+    )
+    .parse_next(input)
+}
+
+fn url(input: &mut &str) -> ModalResult<Value> {
+    preceded(
+        (Caseless("url("), trivia),
+        cut_err(terminated(
+            alt((
+                quoted('"'),
+                quoted('\''),
+                take_till(0.., |c: char| c == ')' || c.is_whitespace()).map(String::from),
+            )),
+            (trivia, ')'),
+        ))
+        .context(StrContext::Label("url")),
+    )
+    .map(Value::Url)
+    .parse_next(input)
+}
+
+fn numeric(input: &mut &str) -> ModalResult<Value> {
+    let start = input.checkpoint();
+    let number = (
+        opt(one_of(['+', '-'])),
+        alt(((digit1, opt(('.', digit0))).void(), ('.', digit1).void())),
+        opt((one_of(['e', 'E']), opt(one_of(['+', '-'])), digit1)),
+    )
+        .take()
+        .parse_to::<f64>()
+        .parse_next(input)?;
+    // Too large to hold, it would read as infinity and print as `inf`.
+    if !number.is_finite() {
+        input.reset(&start);
+        return cut_err(fail)
+            .context(StrContext::Label("number"))
+            .parse_next(input);
+    }
+    alt((
+        '%'.value(Value::Percentage(number)),
+        ident.map(|unit| Value::Dimension(number, unit.to_ascii_lowercase())),
+        empty.value(Value::Number(number)),
+    ))
+    .parse_next(input)
+}
+
+fn quoted<'i>(quote: char) -> impl Parser<&'i str, String, ErrMode<ContextError>> {
+    delimited(
+        quote,
+        repeat(0.., alt((preceded('\\', any), none_of([quote, '\\'])))),
+        cut_err(quote).context(StrContext::Expected(StrContextValue::CharLiteral(quote))),
+    )
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_' || !c.is_ascii()
+}
+
+/// `color`, `-webkit-box`, `--custom`; a lone `-` is a delimiter.
+fn ident(input: &mut &str) -> ModalResult<String> {
+    (
+        opt('-'),
+        alt((
+            '-'.void(),
+            peek(one_of(|c: char| {
+                c.is_alphabetic() || c == '_' || !c.is_ascii()
+            }))
+            .void(),
+        )),
+        take_while(0.., is_name_char),
+    )
+        .take()
+        .map(String::from)
+        .parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_declarations_should_work() -> Result<(), ParseError> {
+        let block = parse_declarations(
+            "COLOR: #f00 /* brand */; ; margin:0 auto!important;\
+             font: italic 12px/1.5 \"Helvetica Neue\", sans-serif;\
+             width: calc(100% - 2 * var(--gap, 1rem)); --Accent: rgb(0 0 255)",
+        )?;
+        assert_eq!(block.declarations.len(), 5);
+        let color = block.get("color").unwrap();
+        assert_eq!(color.value, [Value::Hash("f00".to_string())]);
+        assert_eq!(color.value[0].color(), Some(Rgba::rgb(255, 0, 0)));
+        let margin = block.get("margin").unwrap();
+        assert!(margin.important);
+        assert_eq!(
+            margin.value,
+            [Value::Number(0.0), Value::Ident("auto".to_string())]
+        );
+        let font = &block.declarations[2].value;
+        assert_eq!(font[2], Value::Delim('/'));
+        assert_eq!(font[4], Value::String("Helvetica Neue".to_string()));
+        let Value::Function(name, args) = &block.declarations[3].value[0] else {
+            panic!("expected calc()");
+        };
+        assert_eq!(name, "calc");
+        assert_eq!(args[0], Value::Percentage(100.0));
+        assert_eq!(args[1], Value::Delim('-'));
+        assert_eq!(
+            block.get("--Accent").unwrap().value[0].color(),
+            Some(Rgba::rgb(0, 0, 255))
+        );
+        assert_eq!(
+            block.to_string(),
+            "color: #f00; margin: 0 auto !important; \
+             font: italic 12px / 1.5 \"Helvetica Neue\", sans-serif; \
+             width: calc(100% - 2 * var(--gap, 1rem)); --Accent: rgb(0 0 255);"
+        );
+
+        let block: Block = r#"a: url(\"a.png"); b: url("a\\b")"#.parse()?;
+        assert_eq!(
+            block.get("a").unwrap().value,
+            [Value::Url(r#"\"a.png""#.into())]
+        );
+        assert_eq!(block.get("b").unwrap().value, [Value::Url(r"a\b".into())]);
+        assert_eq!(block.to_string().parse::<Block>()?, block);
+        Ok(())
+    }
+
+    #[test]
+    fn get_should_follow_the_cascade() -> Result<(), ParseError> {
+        let mut block: Block = "color: red !important; color: blue".parse()?;
+        assert_eq!(
+            block.get("Color").unwrap().value[0],
+            Value::Ident("red".to_string())
+        );
+        block.extend("color: green !IMPORTANT; background: url( a.png )".parse()?);
+        assert_eq!(
+            block.get("color").unwrap().value[0],
+            Value::Ident("green".to_string())
+        );
+        assert_eq!(
+            block.get("background").unwrap().value,
+            [Value::Url("a.png".to_string())]
+        );
+        assert!(block.get("border").is_none());
+        assert!(parse_declarations("")?.declarations.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_declarations_should_report_errors() {
+        assert_eq!(parse_declarations("color red").unwrap_err().offset(), 6);
+        assert!(parse_declarations("a: b /* open").is_err());
+        assert!(parse_declarations("a: f(1, 2").is_err());
+        assert!(parse_declarations("a: 'open").is_err());
+        assert!(parse_declarations("a: b !imp").is_err());
+        assert!(parse_declarations("a: {b}").is_err());
+        let err = parse_declarations("width: 1e999px").unwrap_err();
+        assert_eq!((err.offset(), err.message()), (7, "invalid number"));
+    }
+}
@@ -0,0 +1,848 @@
+//! Selectors Level 4, minus namespaces and the parts that need a live
+//! document (`:hover`, `:has()` and friends), matched against [`crate::html`]
+//! trees.
+
+use std::fmt;
+use std::str::FromStr;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::multispace0;
+use winnow::combinator::{alt, cut_err, delimited, fail, opt, peek, preceded, repeat, separated};
+use winnow::error::{ContextError, ErrMode, StrContext, StrContextValue};
+use winnow::token::{any, none_of, one_of, take_till, take_while};
+
+use crate::ParseError;
+use crate::html::{Element, Node, elements};
+
+/// Comma-separated selectors; an element matches when any of them does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectorList(pub Vec<Complex>);
+
+/// Compounds joined by combinators, e.g. `ul > li + li`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Complex {
+    pub first: Compound,
+    /// Each compound with the combinator linking it to the one before.
+    pub rest: Vec<(Combinator, Compound)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Combinator {
+    /// White space.
+    Descendant,
+    /// `>`
+    Child,
+    /// `+`
+    NextSibling,
+    /// `~`
+    SubsequentSibling,
+}
+
+/// Simple selectors that must all hold for one element.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Compound {
+    /// Lowercased; `None` for `*` or when no type is given.
+    pub tag: Option<String>,
+    pub simples: Vec<Simple>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Simple {
+    Id(String),
+    Class(String),
+    Attribute(Attribute),
+    Pseudo(PseudoClass),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribute {
+    /// Lowercased.
+    pub name: String,
+    pub matcher: Option<(AttrOp, String)>,
+    /// Set by the `i` flag, as in `[type=SUBMIT i]`.
+    pub case_insensitive: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttrOp {
+    /// `=`
+    Equals,
+    /// `~=`, one of the whitespace-separated words.
+    Includes,
+    /// `|=`, the value or the value followed by `-`.
+    DashMatch,
+    /// `^=`
+    Prefix,
+    /// `$=`
+    Suffix,
+    /// `*=`
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PseudoClass {
+    Root,
+    Empty,
+    FirstChild,
+    LastChild,
+    OnlyChild,
+    FirstOfType,
+    LastOfType,
+    OnlyOfType,
+    NthChild(Nth),
+    NthLastChild(Nth),
+    NthOfType(Nth),
+    NthLastOfType(Nth),
+    Not(SelectorList),
+    Is(SelectorList),
+    /// Like `Is`, but adds nothing to specificity.
+    Where(SelectorList),
+    Link,
+    Checked,
+    Disabled,
+    Enabled,
+}
+
+/// The `an+b` argument of `:nth-child()` and relatives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Nth {
+    pub a: i32,
+    pub b: i32,
+}
+
+/// Form elements that `:disabled` and `:enabled` apply to.
+const FORM_ELEMENTS: &[&str] = &[
+    "button", "fieldset", "input", "optgroup", "option", "select", "textarea",
+];
+
+impl Nth {
+    /// Whether some `n >= 0` gives `a*n + b == index`, counting from 1.
+    pub fn matches(&self, index: usize) -> bool {
+        let (a, b, i) = (self.a as i64, self.b as i64, index as i64);
+        match a {
+            0 => i == b,
+            _ => (i - b) % a == 0 && (i - b) / a >= 0,
+        }
+    }
+}
+
+/// An element together with the list it sits in, so sibling combinators
+/// and positional pseudo-classes can look around it.
+#[derive(Debug, Clone, Copy)]
+struct Level<'a> {
+    element: &'a Element,
+    /// `None` when the caller could not say; the element then counts as
+    /// an only child.
+    siblings: Option<&'a [Node]>,
+}
+
+impl<'a> Level<'a> {
+    /// Sibling elements before this one, nearest first.
+    fn preceding(&self) -> Vec<Level<'a>> {
+        let Some(siblings) = self.siblings else {
+            return Vec::new();
+        };
+        let mut before: Vec<Level<'a>> = elements(siblings)
+            .take_while(|e| !std::ptr::eq(*e, self.element))
+            .map(|element| Level {
+                element,
+                siblings: Some(siblings),
+            })
+            .collect();
+        before.reverse();
+        before
+    }
+
+    /// 1-based position among the sibling elements `filter` keeps, and
+    /// how many of those there are.
+    fn position(&self, filter: impl Fn(&Element) -> bool) -> (usize, usize) {
+        let Some(siblings) = self.siblings else {
+            return (1, 1);
+        };
+        let kept: Vec<&Element> = elements(siblings).filter(|e| filter(e)).collect();
+        let index = kept
+            .iter()
+            .position(|e| std::ptr::eq(*e, self.element))
+            .map_or(1, |i| i + 1);
+        (index, kept.len())
+    }
+}
+
+impl SelectorList {
+    /// Whether `element` matches, given its ancestors from the outermost
+    /// down to its parent. Sibling information comes from the parent, so a
+    /// top-level element without one counts as an only child.
+    pub fn matches(&self, element: &Element, ancestors: &[&Element]) -> bool {
+        let mut path: Vec<Level> = Vec::with_capacity(ancestors.len() + 1);
+        let mut siblings = None;
+        for element in ancestors.iter().copied().chain([element]) {
+            path.push(Level { element, siblings });
+            siblings = Some(element.children.as_slice());
+        }
+        self.matches_path(&path)
+    }
+
+    fn matches_path(&self, path: &[Level]) -> bool {
+        self.0.iter().any(|complex| complex.matches_path(path))
+    }
+
+    /// Every element in `nodes` and below that matches, in document order.
+    /// `scope` is the element owning `nodes`, which compounds to the left
+    /// may match but which is never returned itself.
+    pub(crate) fn select<'a>(
+        &self,
+        nodes: &'a [Node],
+        scope: Option<&'a Element>,
+    ) -> Vec<&'a Element> {
+        let mut path: Vec<Level<'a>> = scope
+            .map(|element| Level {
+                element,
+                siblings: None,
+            })
+            .into_iter()
+            .collect();
+        let mut found = Vec::new();
+        self.walk(nodes, &mut path, &mut found);
+        found
+    }
+
+    fn walk<'a>(&self, nodes: &'a [Node], path: &mut Vec<Level<'a>>, found: &mut Vec<&'a Element>) {
+        for element in elements(nodes) {
+            path.push(Level {
+                element,
+                siblings: Some(nodes),
+            });
+            if self.matches_path(path) {
+                found.push(element);
+            }
+            self.walk(&element.children, path, found);
+            path.pop();
+        }
+    }
+}
+
+impl Complex {
+    /// `(ids, classes and the like, types)`, compared lexicographically.
+    pub fn specificity(&self) -> (u32, u32, u32) {
+        std::iter::once(&self.first)
+            .chain(self.rest.iter().map(|(_, c)| c))
+            .map(Compound::specificity)
+            .fold((0, 0, 0), |(a, b, c), (x, y, z)| (a + x, b + y, c + z))
+    }
+
+    fn matches_path(&self, path: &[Level]) -> bool {
+        let mut parts: Vec<(Combinator, &Compound)> = vec![(Combinator::Descendant, &self.first)];
+        parts.extend(self.rest.iter().map(|(comb, compound)| (*comb, compound)));
+        matches_parts(&parts, path)
+    }
+}
+
+/// Matches the last part against the last level, then works leftwards;
+/// each part's combinator links it to the part before.
+fn matches_parts(parts: &[(Combinator, &Compound)], path: &[Level]) -> bool {
+    let Some(((combinator, compound), rest)) = parts.split_last() else {
+        return true;
+    };
+    let Some((subject, ancestors)) = path.split_last() else {
+        return false;
+    };
+    if !compound.matches(subject, ancestors) {
+        return false;
+    }
+    if rest.is_empty() {
+        return true;
+    }
+    let with_sibling = |sibling: Level| {
+        let mut path = ancestors.to_vec();
+        path.push(sibling);
+        matches_parts(rest, &path)
+    };
+    match combinator {
+        Combinator::Child => matches_parts(rest, ancestors),
+        Combinator::Descendant => (1..=ancestors.len())
+            .rev()
+            .any(|n| matches_parts(rest, &ancestors[..n])),
+        Combinator::NextSibling => subject
+            .preceding()
+            .first()
+            .is_some_and(|s| with_sibling(*s)),
+        Combinator::SubsequentSibling => subject.preceding().into_iter().any(with_sibling),
+    }
+}
+
+impl Compound {
+    fn specificity(&self) -> (u32, u32, u32) {
+        let mut total = (0, 0, u32::from(self.tag.is_some()));
+        for simple in &self.simples {
+            let (a, b, c) = match simple {
+                Simple::Id(_) => (1, 0, 0),
+                Simple::Class(_) | Simple::Attribute(_) => (0, 1, 0),
+                Simple::Pseudo(PseudoClass::Where(_)) => (0, 0, 0),
+                // The most specific argument counts.
+                Simple::Pseudo(PseudoClass::Not(list) | PseudoClass::Is(list)) => list
+                    .0
+                    .iter()
+                    .map(Complex::specificity)
+                    .max()
+                    .unwrap_or_default(),
+                Simple::Pseudo(_) => (0, 1, 0),
+            };
+            total = (total.0 + a, total.1 + b, total.2 + c);
+        }
+        total
+    }
+
+    fn matches(&self, subject: &Level, ancestors: &[Level]) -> bool {
+        let element = subject.element;
+        self.tag.as_ref().is_none_or(|tag| *tag == element.name)
+            && self.simples.iter().all(|simple| match simple {
+                Simple::Id(id) => element.id() == Some(id.as_str()),
+                Simple::Class(class) => element.has_class(class),
+                Simple::Attribute(attr) => attr.matches(element),
+                Simple::Pseudo(pseudo) => pseudo.matches(subject, ancestors),
+            })
+    }
+}
+
+impl Attribute {
+    fn matches(&self, element: &Element) -> bool {
+        let Some(value) = element.attr(&self.name) else {
+            return false;
+        };
+        let Some((op, expected)) = &self.matcher else {
+            return true;
+        };
+        let (value, expected) = if self.case_insensitive {
+            (value.to_lowercase(), expected.to_lowercase())
+        } else {
+            (value.to_string(), expected.clone())
+        };
+        match op {
+            AttrOp::Equals => value == expected,
+            AttrOp::Includes => value.split_whitespace().any(|w| w == expected),
+            AttrOp::DashMatch => value == expected || value.starts_with(&format!("{expected}-")),
+            // The substring operators never match an empty string.
+            AttrOp::Prefix => !expected.is_empty() && value.starts_with(&expected),
+            AttrOp::Suffix => !expected.is_empty() && value.ends_with(&expected),
+            AttrOp::Contains => !expected.is_empty() && value.contains(&expected),
+        }
+    }
+}
+
+impl PseudoClass {
+    fn matches(&self, subject: &Level, ancestors: &[Level]) -> bool {
+        let element = subject.element;
+        let any = |_: &Element| true;
+        let same_type = |e: &Element| e.name == element.name;
+        let from_end = |(index, count): (usize, usize)| count + 1 - index;
+        let in_path = |list: &SelectorList| {
+            let mut path = ancestors.to_vec();
+            path.push(*subject);
+            list.matches_path(&path)
+        };
+        match self {
+            PseudoClass::Root => ancestors.is_empty(),
+            PseudoClass::Empty => element.children.iter().all(|node| match node {
+                Node::Text(text) => text.is_empty(),
+                Node::Element(_) => false,
+                Node::Comment(_) | Node::Doctype(_) => true,
+            }),
+            PseudoClass::FirstChild => subject.position(any).0 == 1,
+            PseudoClass::LastChild => from_end(subject.position(any)) == 1,
+            PseudoClass::OnlyChild => subject.position(any).1 == 1,
+            PseudoClass::FirstOfType => subject.position(same_type).0 == 1,
+            PseudoClass::LastOfType => from_end(subject.position(same_type)) == 1,
+            PseudoClass::OnlyOfType => subject.position(same_type).1 == 1,
+            PseudoClass::NthChild(nth) => nth.matches(subject.position(any).0),
+            PseudoClass::NthLastChild(nth) => nth.matches(from_end(subject.position(any))),
+            PseudoClass::NthOfType(nth) => nth.matches(subject.position(same_type).0),
+            PseudoClass::NthLastOfType(nth) => nth.matches(from_end(subject.position(same_type))),
+            PseudoClass::Not(list) => !in_path(list),
+            PseudoClass::Is(list) | PseudoClass::Where(list) => in_path(list),
+            PseudoClass::Link => {
+                matches!(element.name.as_str(), "a" | "area") && element.attr("href").is_some()
+            }
+            PseudoClass::Checked => match element.name.as_str() {
+                "input" => element.attr("checked").is_some(),
+                "option" => element.attr("selected").is_some(),
+                _ => false,
+            },
+            PseudoClass::Disabled => {
+                FORM_ELEMENTS.contains(&element.name.as_str()) && element.attr("disabled").is_some()
+            }
+            PseudoClass::Enabled => {
+                FORM_ELEMENTS.contains(&element.name.as_str()) && element.attr("disabled").is_none()
+            }
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            PseudoClass::Root => "root",
+            PseudoClass::Empty => "empty",
+            PseudoClass::FirstChild => "first-child",
+            PseudoClass::LastChild => "last-child",
+            PseudoClass::OnlyChild => "only-child",
+            PseudoClass::FirstOfType => "first-of-type",
+            PseudoClass::LastOfType => "last-of-type",
+            PseudoClass::OnlyOfType => "only-of-type",
+            PseudoClass::NthChild(_) => "nth-child",
+            PseudoClass::NthLastChild(_) => "nth-last-child",
+            PseudoClass::NthOfType(_) => "nth-of-type",
+            PseudoClass::NthLastOfType(_) => "nth-last-of-type",
+            PseudoClass::Not(_) => "not",
+            PseudoClass::Is(_) => "is",
+            PseudoClass::Where(_) => "where",
+            PseudoClass::Link => "link",
+            PseudoClass::Checked => "checked",
+            PseudoClass::Disabled => "disabled",
+            PseudoClass::Enabled => "enabled",
+        }
+    }
+}
+
+impl FromStr for SelectorList {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_selector(s)
+    }
+}
+
+impl fmt::Display for SelectorList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, complex) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{complex}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Complex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.first)?;
+        for (combinator, compound) in &self.rest {
+            f.write_str(match combinator {
+                Combinator::Descendant => " ",
+                Combinator::Child => " > ",
+                Combinator::NextSibling => " + ",
+                Combinator::SubsequentSibling => " ~ ",
+            })?;
+            write!(f, "{compound}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Compound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.tag {
+            Some(tag) => write_ident(f, tag)?,
+            None if self.simples.is_empty() => f.write_str("*")?,
+            None => {}
+        }
+        for simple in &self.simples {
+            match simple {
+                Simple::Id(id) => {
+                    f.write_str("#")?;
+                    write_ident(f, id)?;
+                }
+                Simple::Class(class) => {
+                    f.write_str(".")?;
+                    write_ident(f, class)?;
+                }
+                Simple::Attribute(attr) => write!(f, "{attr}")?,
+                Simple::Pseudo(pseudo) => write!(f, "{pseudo}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Attribute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[")?;
+        write_ident(f, &self.name)?;
+        if let Some((op, value)) = &self.matcher {
+            let op = match op {
+                AttrOp::Equals => "=",
+                AttrOp::Includes => "~=",
+                AttrOp::DashMatch => "|=",
+                AttrOp::Prefix => "^=",
+                AttrOp::Suffix => "$=",
+                AttrOp::Contains => "*=",
+            };
+            let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+            write!(f, "{op}\"{escaped}\"")?;
+        }
+        if self.case_insensitive {
+            f.write_str(" i")?;
+        }
+        f.write_str("]")
+    }
+}
+
+impl fmt::Display for PseudoClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, ":{}", self.name())?;
+        match self {
+            PseudoClass::NthChild(nth)
+            | PseudoClass::NthLastChild(nth)
+            | PseudoClass::NthOfType(nth)
+            | PseudoClass::NthLastOfType(nth) => write!(f, "({nth})"),
+            PseudoClass::Not(list) | PseudoClass::Is(list) | PseudoClass::Where(list) => {
+                write!(f, "({list})")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Nth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.a, self.b) {
+            (0, b) => write!(f, "{b}"),
+            (a, 0) => write!(f, "{a}n"),
+            (a, b) => write!(f, "{a}n{b:+}"),
+        }
+    }
+}
+
+fn write_ident(f: &mut fmt::Formatter<'_>, ident: &str) -> fmt::Result {
+    for (i, c) in ident.chars().enumerate() {
+        // A leading digit can only be written as a hex escape.
+        if i == 0 && c.is_ascii_digit() {
+            write!(f, "\\{:x} ", c as u32)?;
+        } else if !is_ident_char(c) {
+            write!(f, "\\{c}")?;
+        } else {
+            write!(f, "{c}")?;
+        }
+    }
+    Ok(())
+}
+
+/// Parses a selector list such as `nav > a:not([href^="#"]), li:nth-child(2n+1)`.
+pub fn parse_selector(input: &str) -> Result<SelectorList, ParseError> {
+    delimited(multispace0, selector_list, multispace0)
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+fn selector_list(input: &mut &str) -> ModalResult<SelectorList> {
+    separated(1.., complex, (multispace0, ',', multispace0))
+        .map(SelectorList)
+        .parse_next(input)
+}
+
+fn complex(input: &mut &str) -> ModalResult<Complex> {
+    let first = compound(input)?;
+    let mut rest = Vec::new();
+    loop {
+        let combinator = opt(alt((
+            delimited(
+                multispace0,
+                alt((
+                    '>'.value(Combinator::Child),
+                    '+'.value(Combinator::NextSibling),
+                    '~'.value(Combinator::SubsequentSibling),
+                )),
+                multispace0,
+            ),
+            // Whitespace only counts as a combinator when another compound
+            // follows, not before a `,`, a `)` or the end.
+            (take_while(1.., char::is_whitespace), peek_compound).value(Combinator::Descendant),
+        )))
+        .parse_next(input)?;
+        let Some(combinator) = combinator else {
+            return Ok(Complex { first, rest });
+        };
+        let next = cut_err(compound)
+            .context(StrContext::Label("selector"))
+            .context(StrContext::Expected(StrContextValue::Description(
+                "compound selector",
+            )))
+            .parse_next(input)?;
+        rest.push((combinator, next));
+    }
+}
+
+fn peek_compound(input: &mut &str) -> ModalResult<()> {
+    peek(one_of(|c: char| {
+        is_ident_char(c) || matches!(c, '*' | '#' | '.' | '[' | ':' | '\\')
+    }))
+    .void()
+    .parse_next(input)
+}
+
+fn compound(input: &mut &str) -> ModalResult<Compound> {
+    let tag = opt(alt((
+        '*'.value(None),
+        ident.map(|t| Some(t.to_ascii_lowercase())),
+    )))
+    .parse_next(input)?;
+    let simples: Vec<Simple> = repeat(
+        if tag.is_some() { 0.. } else { 1.. },
+        alt((
+            preceded('#', cut_err(ident)).map(Simple::Id),
+            preceded('.', cut_err(ident)).map(Simple::Class),
+            attribute.map(Simple::Attribute),
+            pseudo_class.map(Simple::Pseudo),
+        )),
+    )
+    .parse_next(input)?;
+    Ok(Compound {
+        tag: tag.flatten(),
+        simples,
+    })
+}
+
+fn attribute(input: &mut &str) -> ModalResult<Attribute> {
+    let op = alt((
+        "=".value(AttrOp::Equals),
+        "~=".value(AttrOp::Includes),
+        "|=".value(AttrOp::DashMatch),
+        "^=".value(AttrOp::Prefix),
+        "$=".value(AttrOp::Suffix),
+        "*=".value(AttrOp::Contains),
+    ));
+    let value = alt((quoted('"'), quoted('\''), ident));
+    let flag = preceded(
+        multispace0,
+        alt((
+            one_of(['i', 'I']).value(true),
+            one_of(['s', 'S']).value(false),
+        )),
+    );
+    delimited(
+        ('[', multispace0),
+        cut_err((
+            ident.map(|n| n.to_ascii_lowercase()),
+            opt((delimited(multispace0, op, multispace0), value, opt(flag))),
+        )),
+        cut_err((multispace0, ']')),
+    )
+    .context(StrContext::Label("attribute selector"))
+    .map(|(name, matcher)| Attribute {
+        name,
+        case_insensitive: matcher
+            .as_ref()
+            .is_some_and(|(_, _, flag)| *flag == Some(true)),
+        matcher: matcher.map(|(op, value, _)| (op, value)),
+    })
+    .parse_next(input)
+}
+
+fn quoted<'i>(quote: char) -> impl Parser<&'i str, String, ErrMode<ContextError>> {
+    delimited(
+        quote,
+        repeat(0.., alt((preceded('\\', any), none_of([quote, '\\'])))),
+        cut_err(quote),
+    )
+}
+
+fn pseudo_class(input: &mut &str) -> ModalResult<PseudoClass> {
+    ':'.parse_next(input)?;
+    if opt(peek(':')).parse_next(input)?.is_some() {
+        return cut_err(fail)
+            .context(StrContext::Label("selector"))
+            .context(StrContext::Expected(StrContextValue::Description(
+                "a pseudo-class; pseudo-elements never match",
+            )))
+            .parse_next(input);
+    }
+    let checkpoint = *input;
+    let name = cut_err(ident).parse_next(input)?.to_ascii_lowercase();
+    let simple = match name.as_str() {
+        "root" => Some(PseudoClass::Root),
+        "empty" => Some(PseudoClass::Empty),
+        "first-child" => Some(PseudoClass::FirstChild),
+        "last-child" => Some(PseudoClass::LastChild),
+        "only-child" => Some(PseudoClass::OnlyChild),
+        "first-of-type" => Some(PseudoClass::FirstOfType),
+        "last-of-type" => Some(PseudoClass::LastOfType),
+        "only-of-type" => Some(PseudoClass::OnlyOfType),
+        "link" | "any-link" => Some(PseudoClass::Link),
+        "checked" => Some(PseudoClass::Checked),
+        "disabled" => Some(PseudoClass::Disabled),
+        "enabled" => Some(PseudoClass::Enabled),
+        _ => None,
+    };
+    if let Some(simple) = simple {
+        return Ok(simple);
+    }
+    let nth: Option<fn(Nth) -> PseudoClass> = match name.as_str() {
+        "nth-child" => Some(PseudoClass::NthChild),
+        "nth-last-child" => Some(PseudoClass::NthLastChild),
+        "nth-of-type" => Some(PseudoClass::NthOfType),
+        "nth-last-of-type" => Some(PseudoClass::NthLastOfType),
+        _ => None,
+    };
+    if let Some(nth_class) = nth {
+        return cut_err(delimited(
+            '(',
+            take_till(0.., ')').verify_map(parse_nth),
+            ')',
+        ))
+        .context(StrContext::Label("an+b argument"))
+        .map(nth_class)
+        .parse_next(input);
+    }
+    let list: Option<fn(SelectorList) -> PseudoClass> = match name.as_str() {
+        "not" => Some(PseudoClass::Not),
+        "is" | "matches" => Some(PseudoClass::Is),
+        "where" => Some(PseudoClass::Where),
+        _ => None,
+    };
+    match list {
+        Some(list_class) => cut_err(delimited(
+            ('(', multispace0),
+            selector_list,
+            (multispace0, ')'),
+        ))
+        .map(list_class)
+        .parse_next(input),
+        None => {
+            *input = checkpoint;
+            cut_err(fail)
+                .context(StrContext::Label("pseudo-class"))
+                .parse_next(input)
+        }
+    }
+}
+
+/// `odd`, `even`, `3`, `-n+3`, `2n - 1` and so on.
+fn parse_nth(arg: &str) -> Option<Nth> {
+    let arg: String = arg.split_whitespace().collect();
+    match arg.to_ascii_lowercase().as_str() {
+        "odd" => return Some(Nth { a: 2, b: 1 }),
+        "even" => return Some(Nth { a: 2, b: 0 }),
+        _ => {}
+    }
+    let Some((a, b)) = arg.split_once(['n', 'N']) else {
+        return Some(Nth {
+            a: 0,
+            b: arg.parse().ok()?,
+        });
+    };
+    let a = match a {
+        "" | "+" => 1,
+        "-" => -1,
+        a => a.parse().ok()?,
+    };
+    let b = match b {
+        "" => 0,
+        b if b.starts_with(['+', '-']) => b.parse().ok()?,
+        _ => return None,
+    };
+    Some(Nth { a, b })
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_'
+}
+
+/// An identifier, with `\` escapes resolved: `\31 0` is `10` and `\:` is `:`.
+fn ident(input: &mut &str) -> ModalResult<String> {
+    repeat(
+        1..,
+        alt((
+            take_while(1.., is_ident_char).map(String::from),
+            preceded(
+                '\\',
+                cut_err(alt((
+                    (
+                        take_while(1..=6, |c: char| c.is_ascii_hexdigit()),
+                        opt(one_of([' ', '\t', '\n'])),
+                    )
+                        .verify_map(|(hex, _): (&str, _)| {
+                            u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+                        })
+                        .map(String::from),
+                    any.map(String::from),
+                ))),
+            ),
+        )),
+    )
+    .fold(String::new, |mut ident, part| {
+        ident.push_str(&part);
+        ident
+    })
+    .parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html::parse_html;
+
+    #[test]
+    fn parse_selector_should_work() -> Result<(), ParseError> {
+        let list =
+            parse_selector("ul > li.item:nth-child(2n+1) ~ a[lang|=EN i], #\\31 0:not(.x, p)")?;
+        let complex = &list.0[0];
+        assert_eq!(complex.first.tag.as_deref(), Some("ul"));
+        assert_eq!(complex.rest[1].0, Combinator::SubsequentSibling);
+        assert_eq!(
+            complex.rest[0].1.simples[1],
+            Simple::Pseudo(PseudoClass::NthChild(Nth { a: 2, b: 1 }))
+        );
+        assert_eq!(
+            complex.rest[1].1.simples[0],
+            Simple::Attribute(Attribute {
+                name: "lang".to_string(),
+                matcher: Some((AttrOp::DashMatch, "EN".to_string())),
+                case_insensitive: true,
+            })
+        );
+        assert_eq!(list.0[1].first.simples[0], Simple::Id("10".to_string()));
+        assert_eq!(
+            list.to_string(),
+            "ul > li.item:nth-child(2n+1) ~ a[lang|=\"EN\" i], #\\31 0:not(.x, p)"
+        );
+        assert_eq!(parse_selector(&list.to_string())?, list);
+        assert_eq!(list.0[0].specificity(), (0, 3, 3));
+        assert_eq!(list.0[1].specificity(), (1, 1, 0));
+        assert_eq!(parse_nth("-n + 3"), Some(Nth { a: -1, b: 3 }));
+        assert_eq!(parse_nth("even"), Some(Nth { a: 2, b: 0 }));
+        Ok(())
+    }
+
+    #[test]
+    fn selectors_should_match_html() -> Result<(), ParseError> {
+        let html = parse_html(
+            r#"<ul><li>1</li><li class=x>2</li><li>3</li><li>4</li></ul>
+            <p></p><p lang=en-GB>text</p><input type=checkbox checked><input disabled>"#,
+        );
+        let text = |selector: &str| -> Result<Vec<String>, ParseError> {
+            Ok(html.query(selector)?.iter().map(|e| e.text()).collect())
+        };
+        assert_eq!(text("li:nth-child(odd)")?, ["1", "3"]);
+        assert_eq!(text("li:nth-last-child(-n+2)")?, ["3", "4"]);
+        assert_eq!(text(".x + li")?, ["3"]);
+        assert_eq!(text(".x ~ li")?, ["3", "4"]);
+        assert_eq!(text("li:first-child, li:last-child")?, ["1", "4"]);
+        assert_eq!(text("li:not(.x):not(:first-child)")?, ["3", "4"]);
+        assert_eq!(text("ul:first-of-type > :is(.x, :last-child)")?, ["2", "4"]);
+        assert_eq!(text("p:empty + p[lang|=en]")?, ["text"]);
+        assert_eq!(html.query(":checked")?.len(), 1);
+        assert_eq!(html.query("input:enabled")?.len(), 1);
+        assert_eq!(html.query("p:only-of-type")?.len(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_selector_should_report_errors() {
+        assert_eq!(parse_selector("a::before").unwrap_err().offset(), 2);
+        assert_eq!(parse_selector("a:hover").unwrap_err().offset(), 2);
+        assert!(parse_selector("li:nth-child(2x)").is_err());
+        assert!(parse_selector("a >").is_err());
+        assert!(parse_selector(":not(a").is_err());
+        assert!(parse_selector("a[href=\"x]").is_err());
+        assert!(parse_selector("").is_err());
+    }
+}
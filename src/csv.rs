@@ -0,0 +1,384 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::marker::PhantomData;
+
+use serde::de::value::{Error as DeError, MapDeserializer};
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::combinator::{alt, cut_err, delimited, repeat, separated};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::{none_of, take_while};
+
+use crate::ParseError;
+
+#[derive(Debug)]
+pub enum CsvError {
+    Io(io::Error),
+    Parse(ParseError),
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::Io(e) => write!(f, "{}", e),
+            CsvError::Parse(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+impl From<io::Error> for CsvError {
+    fn from(e: io::Error) -> Self {
+        CsvError::Io(e)
+    }
+}
+
+/// Parses a whole comma separated document into its records.
+pub fn parse_csv(input: &str) -> Result<Vec<Vec<String>>, ParseError> {
+    parse_csv_with(input, ',')
+}
+
+/// Like [`parse_csv`] with another delimiter, e.g. `'\t'` for TSV.
+pub fn parse_csv_with(input: &str, delimiter: char) -> Result<Vec<Vec<String>>, ParseError> {
+    Reader::with_delimiter(input.as_bytes(), delimiter)
+        .collect::<Result<_, _>>()
+        .map_err(|e| match e {
+            CsvError::Parse(e) => e,
+            // Reading from a byte slice cannot fail.
+            CsvError::Io(e) => ParseError::new(0, e.to_string()),
+        })
+}
+
+/// A streaming reader yielding one record at a time. Records may span
+/// several lines when a quoted field contains newlines; blank lines are
+/// skipped.
+pub struct Reader<R> {
+    reader: R,
+    delimiter: char,
+    /// Bytes consumed so far, so errors point into the whole stream.
+    offset: usize,
+    headers: Option<Vec<String>>,
+}
+
+impl<R: BufRead> Reader<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_delimiter(reader, ',')
+    }
+
+    pub fn with_delimiter(reader: R, delimiter: char) -> Self {
+        Self {
+            reader,
+            delimiter,
+            offset: 0,
+            headers: None,
+        }
+    }
+
+    /// The header row, read from the stream on first use.
+    pub fn headers(&mut self) -> Result<&[String], CsvError> {
+        if self.headers.is_none() {
+            let headers = self.read_record().transpose()?.map(|(_, h)| h);
+            self.headers = Some(headers.unwrap_or_default());
+        }
+        Ok(self.headers.as_deref().unwrap_or_default())
+    }
+
+    /// Iterates the remaining records as maps from header to field.
+    pub fn maps(&mut self) -> Maps<'_, R> {
+        Maps { reader: self }
+    }
+
+    /// Deserializes the remaining records into `T`, matching struct fields
+    /// to header names. Numbers and booleans are parsed from the field text
+    /// and empty fields become `None` for optional fields.
+    pub fn deserialize<T: DeserializeOwned>(&mut self) -> Records<'_, R, T> {
+        Records {
+            reader: self,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Reads the next record along with the offset it starts at.
+    fn read_record(&mut self) -> Option<Result<(usize, Vec<String>), CsvError>> {
+        let mut record = String::new();
+        loop {
+            match self.reader.read_line(&mut record) {
+                Ok(0) if record.is_empty() => return None,
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e.into())),
+            }
+            if record.trim_end_matches(['\r', '\n']).is_empty() {
+                self.offset += record.len();
+                record.clear();
+                continue;
+            }
+            // Escaped quotes come in pairs, so an odd count means a quoted
+            // field is still open and its newline belongs to the field.
+            if record.matches('"').count().is_multiple_of(2) {
+                break;
+            }
+        }
+        let offset = self.offset;
+        self.offset += record.len();
+        let line = record.strip_suffix('\n').unwrap_or(&record);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        Some(
+            parse_record(line, self.delimiter)
+                .map(|fields| (offset, fields))
+                .map_err(|e| CsvError::Parse(ParseError::new(offset + e.offset(), e.message()))),
+        )
+    }
+
+    /// Reads the next record and checks it against the header row.
+    fn read_keyed(&mut self) -> Option<Result<(usize, Vec<String>), CsvError>> {
+        let width = match self.headers() {
+            Ok(headers) => headers.len(),
+            Err(e) => return Some(Err(e)),
+        };
+        Some(self.read_record()?.and_then(|(offset, fields)| {
+            if fields.len() == width {
+                Ok((offset, fields))
+            } else {
+                let message = format!(
+                    "record has {} fields but the header has {}",
+                    fields.len(),
+                    width
+                );
+                Err(CsvError::Parse(ParseError::new(offset, message)))
+            }
+        }))
+    }
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+    type Item = Result<Vec<String>, CsvError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.read_record()?.map(|(_, fields)| fields))
+    }
+}
+
+pub struct Maps<'r, R> {
+    reader: &'r mut Reader<R>,
+}
+
+impl<R: BufRead> Iterator for Maps<'_, R> {
+    type Item = Result<HashMap<String, String>, CsvError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = self.reader.read_keyed()?;
+        let headers = self.reader.headers.as_deref().unwrap_or_default();
+        Some(record.map(|(_, fields)| headers.iter().cloned().zip(fields).collect()))
+    }
+}
+
+pub struct Records<'r, R, T> {
+    reader: &'r mut Reader<R>,
+    _marker: PhantomData<T>,
+}
+
+impl<R: BufRead, T: DeserializeOwned> Iterator for Records<'_, R, T> {
+    type Item = Result<T, CsvError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = self.reader.read_keyed()?;
+        let headers = self.reader.headers.as_deref().unwrap_or_default();
+        Some(record.and_then(|(offset, fields)| {
+            let pairs = headers
+                .iter()
+                .map(String::as_str)
+                .zip(fields.iter().map(|f| Field(f)));
+            T::deserialize(MapDeserializer::<_, DeError>::new(pairs))
+                .map_err(|e| CsvError::Parse(ParseError::new(offset, e.to_string())))
+        }))
+    }
+}
+
+/// Parses one record; `line` must not include the record terminator.
+fn parse_record(line: &str, delimiter: char) -> Result<Vec<String>, ParseError> {
+    separated(
+        1..,
+        move |input: &mut &str| parse_field(input, delimiter),
+        delimiter,
+    )
+    .parse(line)
+    .map_err(ParseError::from)
+}
+
+fn parse_field(input: &mut &str, delimiter: char) -> ModalResult<String> {
+    alt((
+        parse_quoted,
+        take_while(0.., move |c| {
+            c != delimiter && c != '"' && c != '\n' && c != '\r'
+        })
+        .map(String::from),
+    ))
+    .parse_next(input)
+}
+
+/// A field in double quotes, where `""` stands for one quote.
+fn parse_quoted(input: &mut &str) -> ModalResult<String> {
+    delimited(
+        '"',
+        repeat(0.., alt((none_of('"').map(Some), "\"\"".value(Some('"'))))),
+        cut_err('"')
+            .context(StrContext::Label("quoted field"))
+            .context(StrContext::Expected(StrContextValue::CharLiteral('"'))),
+    )
+    .map(|chars: Vec<Option<char>>| chars.into_iter().flatten().collect())
+    .parse_next(input)
+}
+
+/// One field handed to serde, parsed according to the type asked for.
+struct Field<'a>(&'a str);
+
+impl<'a> Field<'a> {
+    fn parse<T: std::str::FromStr>(&self, expected: &str) -> Result<T, DeError> {
+        self.0
+            .trim()
+            .parse()
+            .map_err(|_| de::Error::invalid_value(de::Unexpected::Str(self.0), &expected))
+    }
+}
+
+impl<'de> IntoDeserializer<'de, DeError> for Field<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident: $ty:ty),* $(,)?) => {
+        $(fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+            visitor.$visit(self.parse::<$ty>(stringify!($ty))?)
+        })*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Field<'de> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        if self.0.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool: bool,
+        deserialize_i8 => visit_i8: i8,
+        deserialize_i16 => visit_i16: i16,
+        deserialize_i32 => visit_i32: i32,
+        deserialize_i64 => visit_i64: i64,
+        deserialize_u8 => visit_u8: u8,
+        deserialize_u16 => visit_u16: u16,
+        deserialize_u32 => visit_u32: u32,
+        deserialize_u64 => visit_u64: u64,
+        deserialize_f32 => visit_f32: f32,
+        deserialize_f64 => visit_f64: f64,
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 char str string bytes byte_buf unit unit_struct newtype_struct
+        seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[test]
+    fn parse_csv_should_work() -> anyhow::Result<()> {
+        let input = "a,b,c\r\n1,\"two, too\",\"say \"\"hi\"\"\"\n\n\"multi\nline\",,x";
+        assert_eq!(
+            parse_csv(input)?,
+            [
+                vec!["a", "b", "c"],
+                vec!["1", "two, too", "say \"hi\""],
+                vec!["multi\nline", "", "x"],
+            ]
+        );
+        assert_eq!(
+            parse_csv_with("a\tb,c\n", '\t')?,
+            [vec!["a".to_string(), "b,c".to_string()]]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_csv_should_report_offsets() {
+        let err = parse_csv("a,b\n1,\"open\n").unwrap_err();
+        assert_eq!(err.offset(), 11);
+        assert_eq!(err.message(), "expected '\"' while parsing quoted field");
+        let err = parse_csv("a,b\nx,y\"z\n").unwrap_err();
+        assert_eq!(err.line_col("a,b\nx,y\"z\n"), (2, 4));
+    }
+
+    #[test]
+    fn reader_should_map_headers() -> anyhow::Result<()> {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Row {
+            name: String,
+            age: u8,
+            admin: bool,
+            email: Option<String>,
+        }
+
+        let input = "name,age,admin,email\nalice,30,true,a@example.com\nbob,25,false,\n";
+        let rows = Reader::new(input.as_bytes())
+            .deserialize::<Row>()
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(rows[1].email, None);
+        assert_eq!(
+            rows[0],
+            Row {
+                name: "alice".to_string(),
+                age: 30,
+                admin: true,
+                email: Some("a@example.com".to_string()),
+            }
+        );
+
+        let mut reader = Reader::new(input.as_bytes());
+        assert_eq!(reader.headers()?, ["name", "age", "admin", "email"]);
+        let maps = reader.maps().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(maps[1]["name"], "bob");
+
+        let mut reader = Reader::new("a,b\n1\n".as_bytes());
+        let Some(Err(CsvError::Parse(e))) = reader.maps().next() else {
+            panic!("short records should be rejected");
+        };
+        assert_eq!(e.offset(), 4);
+        let mut reader = Reader::new("age\nold\n".as_bytes());
+        assert!(matches!(
+            reader.deserialize::<HashMap<String, u8>>().next(),
+            Some(Err(CsvError::Parse(_)))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,244 @@
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime};
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{Caseless, digit1, space0, space1};
+use winnow::combinator::{alt, cut_err, delimited, opt, preceded};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::{one_of, take_while};
+
+use crate::ParseError;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const DAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/// Parses a timestamp in any of the formats found in logs and configs:
+///
+/// - RFC 3339 / ISO 8601: `2024-01-02T15:04:05.123+08:00`
+/// - loose ISO: `2024-01-02 15:04:05`, `2024/01/02 15:04`, `2024-01-02`
+/// - RFC 2822: `Tue, 2 Jan 2024 15:04:05 GMT`
+/// - nginx/apache access logs: `[02/Jan/2024:15:04:05 +0000]`
+///
+/// Times without an offset are taken to be UTC.
+pub fn parse_datetime(input: &str) -> Result<DateTime<FixedOffset>, ParseError> {
+    delimited(space0, alt((iso, common_log, rfc2822)), space0)
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+/// `YYYY-MM-DD[( |T)HH:MM[:SS[.fff]]][offset]`; `/` may replace `-`.
+fn iso(input: &mut &str) -> ModalResult<DateTime<FixedOffset>> {
+    let year = fixed_digits(4).parse_next(input)?;
+    let sep = one_of(['-', '/']).parse_next(input)?;
+    let month = fixed_digits(2).parse_next(input)?;
+    let day = cut_err(preceded(sep, fixed_digits(2))).parse_next(input)?;
+    let date = date(input, year as i32, month, day)?;
+    let time = opt(preceded(one_of(['T', 't', ' ']), clock))
+        .parse_next(input)?
+        .unwrap_or(NaiveTime::MIN);
+    let offset = opt(preceded(space0, offset)).parse_next(input)?;
+    Ok(finish(date, time, offset.unwrap_or(utc())))
+}
+
+/// `DD/Mon/YYYY:HH:MM:SS +ZZZZ`, optionally in brackets.
+fn common_log(input: &mut &str) -> ModalResult<DateTime<FixedOffset>> {
+    let bracketed = opt('[').parse_next(input)?.is_some();
+    let day = fixed_digits(2).parse_next(input)?;
+    '/'.parse_next(input)?;
+    let month = cut_err(month_name).parse_next(input)?;
+    let year = cut_err(preceded('/', fixed_digits(4))).parse_next(input)?;
+    let date = date(input, year as i32, month, day)?;
+    let time = cut_err(preceded(':', clock)).parse_next(input)?;
+    let offset = cut_err(preceded(space1, offset)).parse_next(input)?;
+    if bracketed {
+        cut_err(']').parse_next(input)?;
+    }
+    Ok(finish(date, time, offset))
+}
+
+/// `[Day, ]D Mon YYYY HH:MM[:SS] zone`, with two-digit years and the
+/// obsolete North American zone names of RFC 822.
+fn rfc2822(input: &mut &str) -> ModalResult<DateTime<FixedOffset>> {
+    opt((
+        take_while(3, |c: char| c.is_ascii_alphabetic())
+            .verify(|d: &str| DAYS.iter().any(|day| day.eq_ignore_ascii_case(d))),
+        ',',
+        space0,
+    ))
+    .parse_next(input)?;
+    let day = take_while(1..=2, |c: char| c.is_ascii_digit())
+        .parse_to::<u32>()
+        .parse_next(input)?;
+    let month = preceded(space1, month_name).parse_next(input)?;
+    let year = cut_err(preceded(space1, digit1))
+        .verify_map(|y: &str| match y.len() {
+            2 => y
+                .parse::<i32>()
+                .ok()
+                .map(|y| if y < 50 { 2000 + y } else { 1900 + y }),
+            4 => y.parse().ok(),
+            _ => None,
+        })
+        .context(StrContext::Label("year"))
+        .parse_next(input)?;
+    let date = date(input, year, month, day)?;
+    let time = cut_err(preceded(space1, clock)).parse_next(input)?;
+    let offset = cut_err(preceded(
+        space1,
+        alt((
+            offset,
+            alt((
+                Caseless("UT").value(0),
+                Caseless("EST").value(-5),
+                Caseless("EDT").value(-4),
+                Caseless("CST").value(-6),
+                Caseless("CDT").value(-5),
+                Caseless("MST").value(-7),
+                Caseless("MDT").value(-6),
+                Caseless("PST").value(-8),
+                Caseless("PDT").value(-7),
+            ))
+            .map(|hours| FixedOffset::east_opt(hours * 3600).unwrap()),
+        )),
+    ))
+    .parse_next(input)?;
+    Ok(finish(date, time, offset))
+}
+
+/// `HH:MM[:SS[.fraction]]`.
+fn clock(input: &mut &str) -> ModalResult<NaiveTime> {
+    (
+        fixed_digits(2),
+        preceded(':', fixed_digits(2)),
+        opt(preceded(':', fixed_digits(2))),
+        opt(preceded(one_of(['.', ',']), digit1)),
+    )
+        .verify_map(
+            |(h, m, s, fraction): (u32, u32, Option<u32>, Option<&str>)| {
+                let nanos = fraction.map_or(0, |f| {
+                    let digits: String = f.chars().chain("000000000".chars()).take(9).collect();
+                    digits.parse().unwrap_or(0)
+                });
+                NaiveTime::from_hms_nano_opt(h, m, s.unwrap_or(0), nanos)
+            },
+        )
+        .context(StrContext::Label("time"))
+        .parse_next(input)
+}
+
+/// `Z`, `UTC`, `GMT`, `+HH:MM` or `+HHMM`.
+fn offset(input: &mut &str) -> ModalResult<FixedOffset> {
+    alt((
+        alt((Caseless("UTC"), Caseless("GMT"), Caseless("Z"))).map(|_| utc()),
+        (
+            one_of(['+', '-']),
+            fixed_digits(2),
+            opt(':'),
+            fixed_digits(2),
+        )
+            .verify_map(|(sign, h, _, m): (char, u32, _, u32)| {
+                let seconds = (h * 3600 + m * 60) as i32;
+                (m < 60)
+                    .then(|| FixedOffset::east_opt(if sign == '-' { -seconds } else { seconds }))
+                    .flatten()
+            }),
+    ))
+    .context(StrContext::Label("UTC offset"))
+    .parse_next(input)
+}
+
+fn month_name(input: &mut &str) -> ModalResult<u32> {
+    take_while(3, |c: char| c.is_ascii_alphabetic())
+        .verify_map(|m: &str| {
+            MONTHS
+                .iter()
+                .position(|name| name.eq_ignore_ascii_case(m))
+                .map(|i| i as u32 + 1)
+        })
+        .context(StrContext::Expected(StrContextValue::Description(
+            "month name",
+        )))
+        .parse_next(input)
+}
+
+fn fixed_digits<'i>(
+    count: usize,
+) -> impl Parser<&'i str, u32, winnow::error::ErrMode<winnow::error::ContextError>> {
+    take_while(count, |c: char| c.is_ascii_digit()).parse_to()
+}
+
+/// Builds a calendar date, failing at the current position for dates such
+/// as February 30th.
+fn date(input: &mut &str, year: i32, month: u32, day: u32) -> ModalResult<NaiveDate> {
+    match NaiveDate::from_ymd_opt(year, month, day) {
+        Some(date) => Ok(date),
+        None => cut_err(winnow::combinator::fail)
+            .context(StrContext::Label("date"))
+            .context(StrContext::Expected(StrContextValue::Description(
+                "a day that exists in that month",
+            )))
+            .parse_next(input),
+    }
+}
+
+fn finish(date: NaiveDate, time: NaiveTime, offset: FixedOffset) -> DateTime<FixedOffset> {
+    date.and_time(time)
+        .and_local_timezone(offset)
+        .single()
+        .expect("fixed offsets are never ambiguous")
+}
+
+fn utc() -> FixedOffset {
+    FixedOffset::east_opt(0).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rfc3339(s: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(s).unwrap()
+    }
+
+    #[test]
+    fn parse_datetime_should_accept_many_formats() -> Result<(), ParseError> {
+        let cases = [
+            ("2024-01-02T15:04:05Z", "2024-01-02T15:04:05Z"),
+            ("2024-01-02t15:04:05.5-07:30", "2024-01-02T15:04:05.5-07:30"),
+            ("2024-01-02 15:04:05", "2024-01-02T15:04:05Z"),
+            ("2024/01/02 15:04 +0800", "2024-01-02T15:04:00+08:00"),
+            ("2024-01-02", "2024-01-02T00:00:00Z"),
+            ("Tue, 2 Jan 2024 15:04:05 GMT", "2024-01-02T15:04:05Z"),
+            ("02 Jan 99 15:04 EST", "1999-01-02T15:04:00-05:00"),
+            ("[17/May/2015:08:05:32 +0000]", "2015-05-17T08:05:32Z"),
+            ("17/May/2015:08:05:32 -0700", "2015-05-17T08:05:32-07:00"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_datetime(input)?, rfc3339(expected), "{input}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn parse_datetime_should_keep_offsets() -> Result<(), ParseError> {
+        let dt = parse_datetime("2024-06-30T23:59:59.123456789+05:45")?;
+        assert_eq!(dt.offset().local_minus_utc(), 5 * 3600 + 45 * 60);
+        assert_eq!(dt.timestamp_subsec_nanos(), 123456789);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_datetime_should_report_errors() {
+        assert_eq!(parse_datetime("2024-02-30").unwrap_err().offset(), 10);
+        assert_eq!(
+            parse_datetime("17/Foo/2015:08:05:32 +0000")
+                .unwrap_err()
+                .offset(),
+            3
+        );
+        assert!(parse_datetime("2024-01-02 25:00").is_err());
+        assert!(parse_datetime("[17/May/2015:08:05:32 +0000").is_err());
+        assert!(parse_datetime("yesterday").is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use winnow::ascii::{dec_int, dec_uint, float};
+use winnow::error::{ContextError, ErrMode, StrContext, StrContextValue};
+use winnow::token::any;
+use winnow::{ModalResult, Parser};
+
+use crate::ParseError;
+
+/// Generates a [`Parse`] impl from `#[grammar(..)]` attributes, so a
+/// sequence of fields becomes a declaration instead of a hand-written
+/// parser. See the `grammar-derive` crate for the options.
+pub use grammar_derive::Parse;
+
+/// A type that can be parsed from the front of a `&str`.
+pub trait Parse<'i>: Sized {
+    fn parse_next(input: &mut &'i str) -> ModalResult<Self>;
+
+    /// Parses all of `input`.
+    fn parse_str(input: &'i str) -> Result<Self, ParseError> {
+        Self::parse_next.parse(input).map_err(ParseError::from)
+    }
+}
+
+macro_rules! parse_with {
+    ($parser:ident => $($ty:ty),*) => {
+        $(impl<'i> Parse<'i> for $ty {
+            fn parse_next(input: &mut &'i str) -> ModalResult<Self> {
+                $parser.parse_next(input)
+            }
+        })*
+    };
+}
+
+parse_with!(dec_uint => u8, u16, u32, u64, u128, usize);
+parse_with!(dec_int => i8, i16, i32, i64, i128, isize);
+parse_with!(float => f32, f64);
+parse_with!(any => char);
+
+/// What the derive macro expands to. Not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use winnow::ModalResult;
+    pub use winnow::combinator::alt;
+
+    use super::*;
+
+    pub fn parse<'i, O>(
+        mut parser: impl Parser<&'i str, O, ErrMode<ContextError>>,
+        input: &mut &'i str,
+    ) -> ModalResult<O> {
+        parser.parse_next(input)
+    }
+
+    pub fn label<'i, O>(
+        parser: impl Parser<&'i str, O, ErrMode<ContextError>>,
+        label: &'static str,
+    ) -> impl Parser<&'i str, O, ErrMode<ContextError>> {
+        parser.context(StrContext::Label(label))
+    }
+
+    pub fn expected<'i, O>(
+        parser: impl Parser<&'i str, O, ErrMode<ContextError>>,
+        expected: &'static str,
+    ) -> impl Parser<&'i str, O, ErrMode<ContextError>> {
+        parser.context(StrContext::Expected(StrContextValue::Description(expected)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winnow::token::take_till;
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Parse)]
+    #[grammar(expected = "HTTP method")]
+    enum Method {
+        #[grammar(tag = "GET")]
+        Get,
+        #[grammar(tag = "POST")]
+        Post,
+        Head,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Parse)]
+    #[grammar(sep = '.')]
+    struct Version(u8, u8);
+
+    #[derive(Debug, PartialEq, Eq, Parse)]
+    #[grammar(sep = ' ', label = "request line")]
+    struct RequestLine<'i> {
+        method: Method,
+        #[grammar(with = take_till(1.., ' '), expected = "request target")]
+        target: &'i str,
+        #[grammar(prefix = "HTTP/")]
+        version: Version,
+    }
+
+    #[derive(Debug, PartialEq, Parse)]
+    enum Shape {
+        #[grammar(tag = "circle ")]
+        Circle(f64),
+        #[grammar(tag = "rect ", sep = 'x')]
+        Rect { width: u32, height: u32 },
+    }
+
+    #[test]
+    fn derived_structs_should_parse_fields_in_order() -> Result<(), ParseError> {
+        let line = RequestLine::parse_str("POST /api/users HTTP/1.1")?;
+        assert_eq!(
+            line,
+            RequestLine {
+                method: Method::Post,
+                target: "/api/users",
+                version: Version(1, 1),
+            }
+        );
+        assert_eq!(Method::parse_str("Head")?, Method::Head);
+        Ok(())
+    }
+
+    #[test]
+    fn derived_enums_should_try_variants_in_order() -> Result<(), ParseError> {
+        assert_eq!(Shape::parse_str("circle 1.5")?, Shape::Circle(1.5));
+        let rect = Shape::Rect {
+            width: 3,
+            height: 4,
+        };
+        assert_eq!(Shape::parse_str("rect 3x4")?, rect);
+        assert!(Shape::parse_str("square 2").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn derived_parsers_should_label_errors() {
+        let err = RequestLine::parse_str("FETCH / HTTP/1.1").unwrap_err();
+        assert_eq!(err.offset(), 0);
+        assert_eq!(
+            err.message(),
+            "expected HTTP method while parsing request line"
+        );
+        let err = RequestLine::parse_str("GET /").unwrap_err();
+        assert_eq!(err.offset(), 5);
+    }
+}
@@ -0,0 +1,311 @@
+use std::fmt::Write;
+
+use crate::ParseError;
+use crate::combinators::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+
+    fn style(self) -> &'static str {
+        match self {
+            Severity::Error => "1;31",
+            Severity::Warning => "1;33",
+            Severity::Note => "1;36",
+        }
+    }
+}
+
+/// A span of the source to underline, with an optional message next to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    /// Primary labels mark the problem itself and are drawn with `^`;
+    /// secondary ones give context and are drawn with `-`.
+    pub primary: bool,
+}
+
+/// A message about the source text, built up with the methods below:
+///
+/// `Diagnostic::error("duplicate key").primary(12..15, "redefined here")`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Diagnostic::new(Severity::Error, message)
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Diagnostic::new(Severity::Warning, message)
+    }
+
+    pub fn primary(self, span: Span, message: impl Into<String>) -> Self {
+        self.label(span, message, true)
+    }
+
+    pub fn secondary(self, span: Span, message: impl Into<String>) -> Self {
+        self.label(span, message, false)
+    }
+
+    pub fn note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    fn label(mut self, span: Span, message: impl Into<String>, primary: bool) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary,
+        });
+        self
+    }
+}
+
+/// The error message, with a caret at the offset parsing stopped at.
+impl From<&ParseError> for Diagnostic {
+    fn from(err: &ParseError) -> Self {
+        Diagnostic::error(err.message()).primary(err.offset()..err.offset(), "")
+    }
+}
+
+/// Turns diagnostics into text in the style of compiler errors:
+///
+/// ```text
+/// error: expected value while parsing object value
+///  --> config.json:2:7
+///   |
+/// 2 |  "b": }
+///   |       ^
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Renderer {
+    color: bool,
+    path: Option<String>,
+}
+
+impl Renderer {
+    pub fn new() -> Self {
+        Renderer::default()
+    }
+
+    /// Whether to style the output with ANSI escape codes. Off by default.
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// The file name shown in front of the location.
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    pub fn render(&self, diagnostic: &Diagnostic, source: &str) -> String {
+        let mut out = String::new();
+        let severity = diagnostic.severity;
+        let _ = writeln!(
+            out,
+            "{}{}",
+            self.paint(severity.style(), severity.as_str()),
+            self.paint("1", &format!(": {}", diagnostic.message))
+        );
+
+        let mut marks: Vec<Mark> = diagnostic
+            .labels
+            .iter()
+            .enumerate()
+            .map(|(index, label)| Mark::new(index, label, source))
+            .collect();
+        marks.sort_by_key(|mark| (mark.line, mark.column));
+        let width = marks
+            .iter()
+            .map(|mark| (mark.line + 1).to_string().len())
+            .max()
+            .unwrap_or(0);
+        let pad = " ".repeat(width);
+
+        let main = marks.iter().find(|mark| mark.primary).or(marks.first());
+        if let Some(main) = main {
+            let location = format!("{}:{}", main.line + 1, main.column + 1);
+            let location = match &self.path {
+                Some(path) => format!("{path}:{location}"),
+                None => location,
+            };
+            let _ = writeln!(out, "{pad}{} {location}", self.paint("1;34", "-->"));
+            let _ = writeln!(out, "{pad} {}", self.paint("1;34", "|"));
+        }
+
+        let mut previous: Option<usize> = None;
+        for mark in &marks {
+            if previous != Some(mark.line) {
+                if let Some(previous) = previous
+                    && mark.line > previous + 1
+                {
+                    let _ = writeln!(out, "{}", self.paint("1;34", "..."));
+                }
+                let number = format!("{:>width$} |", mark.line + 1);
+                let _ = writeln!(out, "{} {}", self.paint("1;34", &number), mark.text);
+                previous = Some(mark.line);
+            }
+            let (symbol, style) = if mark.primary {
+                ('^', severity.style())
+            } else {
+                ('-', "1;34")
+            };
+            let underline = symbol.to_string().repeat(mark.width);
+            let mut line = format!("{}{}", mark.indent, self.paint(style, &underline));
+            let message = &diagnostic.labels[mark.index].message;
+            if !message.is_empty() {
+                line.push(' ');
+                line.push_str(&self.paint(style, message));
+            }
+            let _ = writeln!(out, "{pad} {} {line}", self.paint("1;34", "|"));
+        }
+
+        for note in &diagnostic.notes {
+            let _ = writeln!(out, "{pad} {} note: {note}", self.paint("1;34", "="));
+        }
+        out
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("\x1b[{style}m{text}\x1b[0m")
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// Renders `err` against the `source` it came from, without color.
+pub fn render(err: &ParseError, source: &str) -> String {
+    Renderer::new().render(&err.into(), source)
+}
+
+/// Where a label lands: its 0-based line and column, the text of that line,
+/// and what to print before the underline to line up with it.
+struct Mark<'s> {
+    index: usize,
+    primary: bool,
+    line: usize,
+    column: usize,
+    text: &'s str,
+    indent: String,
+    width: usize,
+}
+
+impl<'s> Mark<'s> {
+    fn new(index: usize, label: &Label, source: &'s str) -> Self {
+        let mut start = label.span.start.min(source.len());
+        while !source.is_char_boundary(start) {
+            start -= 1;
+        }
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |i| start + i);
+        let text = source[line_start..line_end].trim_end_matches('\r');
+        let before = &source[line_start..start];
+        // Spans running past the end of the line are cut off there.
+        let end = label.span.end.min(line_start + text.len()).max(start);
+        let width = source.get(start..end).map_or(0, |s| s.chars().count());
+        Mark {
+            index,
+            primary: label.primary,
+            line: source[..line_start].matches('\n').count(),
+            column: before.chars().count(),
+            text,
+            indent: before
+                .chars()
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect(),
+            width: width.max(1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    #[test]
+    fn render_should_point_at_parse_errors() {
+        let input = "{\"a\": 1,\n \"b\": }";
+        let err = parse_json(input).unwrap_err();
+        assert_eq!(
+            render(&err, input),
+            "error: expected value while parsing object value\n \
+             --> 2:7\n  \
+               |\n\
+             2 |  \"b\": }\n  \
+               |       ^\n"
+        );
+    }
+
+    #[test]
+    fn renderer_should_draw_every_label() {
+        let source = "[server]\nport = 80\n\n\n[server]\n\tport = 8080\n";
+        let diagnostic = Diagnostic::error("duplicate table `server`")
+            .primary(22..28, "redefined here")
+            .secondary(1..7, "first defined here")
+            .note("merge the two tables");
+        let rendered = Renderer::new().path("app.toml").render(&diagnostic, source);
+        assert_eq!(
+            rendered,
+            "error: duplicate table `server`\n \
+             --> app.toml:5:2\n  \
+               |\n\
+             1 | [server]\n  \
+               |  ------ first defined here\n\
+             ...\n\
+             5 | [server]\n  \
+               |  ^^^^^^ redefined here\n  \
+               = note: merge the two tables\n"
+        );
+
+        let diagnostic = Diagnostic::warning("odd port").primary(38..42, "");
+        let rendered = Renderer::new().render(&diagnostic, source);
+        assert!(rendered.ends_with("6 | \tport = 8080\n  | \t       ^^^^\n"));
+    }
+
+    #[test]
+    fn renderer_should_color_on_request() {
+        let err = ParseError::new(3, "expected ':'");
+        let diagnostic = Diagnostic::from(&err);
+        let plain = Renderer::new().render(&diagnostic, "key");
+        assert!(!plain.contains('\x1b'));
+        let colored = Renderer::new().color(true).render(&diagnostic, "key");
+        assert!(colored.starts_with("\x1b[1;31merror\x1b[0m\x1b[1m: expected ':'\x1b[0m\n"));
+        assert!(colored.contains("\x1b[1;31m^\x1b[0m"));
+    }
+}
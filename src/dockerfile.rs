@@ -0,0 +1,637 @@
+use crate::ParseError;
+use crate::json::{JsonValue, parse_json};
+
+/// A parsed Dockerfile.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Dockerfile {
+    /// Parser directives from the top of the file, such as `syntax` and
+    /// `escape`, keys lowercased.
+    pub directives: Vec<(String, String)>,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    /// 1-based line the instruction starts on.
+    pub line: usize,
+    pub instruction: Instruction,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Instruction {
+    From {
+        flags: Vec<Flag>,
+        image: String,
+        alias: Option<String>,
+    },
+    Run {
+        flags: Vec<Flag>,
+        command: Command,
+        heredocs: Vec<Heredoc>,
+    },
+    Cmd(Command),
+    Entrypoint(Command),
+    Copy(Transfer),
+    Add(Transfer),
+    Env(Vec<(String, String)>),
+    Arg(Vec<(String, Option<String>)>),
+    Label(Vec<(String, String)>),
+    Expose(Vec<String>),
+    Volume(Vec<String>),
+    Workdir(String),
+    User(String),
+    Shell(Vec<String>),
+    Stopsignal(String),
+    /// `command` is `None` for `HEALTHCHECK NONE`.
+    Healthcheck {
+        flags: Vec<Flag>,
+        command: Option<Command>,
+    },
+    Onbuild(Box<Instruction>),
+    Maintainer(String),
+}
+
+/// The two ways to write a command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Run through the shell, as written.
+    Shell(String),
+    /// A JSON array, run directly.
+    Exec(Vec<String>),
+}
+
+/// An option like `--from=build` or `--mount=type=cache,target=/root`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Flag {
+    pub name: String,
+    pub value: Option<String>,
+}
+
+/// The arguments of `COPY` and `ADD`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transfer {
+    pub flags: Vec<Flag>,
+    pub sources: Vec<String>,
+    pub dest: String,
+    pub heredocs: Vec<Heredoc>,
+}
+
+/// A `<<EOF` here-document following a `RUN`, `COPY` or `ADD`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heredoc {
+    pub delimiter: String,
+    /// `<<-`: leading tabs are stripped from each line.
+    pub strip_tabs: bool,
+    /// The lines between the marker and the delimiter, each ending in `\n`.
+    pub content: String,
+}
+
+/// A build stage: a `FROM` and the steps up to the next one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stage<'a> {
+    pub image: &'a str,
+    pub alias: Option<&'a str>,
+    pub steps: &'a [Step],
+}
+
+impl Dockerfile {
+    pub fn stages(&self) -> Vec<Stage<'_>> {
+        let starts: Vec<usize> = self
+            .steps
+            .iter()
+            .enumerate()
+            .filter(|(_, step)| matches!(step.instruction, Instruction::From { .. }))
+            .map(|(i, _)| i)
+            .collect();
+        starts
+            .iter()
+            .enumerate()
+            .map(|(n, &start)| {
+                let end = starts.get(n + 1).copied().unwrap_or(self.steps.len());
+                let Instruction::From { image, alias, .. } = &self.steps[start].instruction else {
+                    unreachable!("stages start at FROM");
+                };
+                Stage {
+                    image,
+                    alias: alias.as_deref(),
+                    steps: &self.steps[start + 1..end],
+                }
+            })
+            .collect()
+    }
+}
+
+impl Instruction {
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Instruction::From { .. } => "FROM",
+            Instruction::Run { .. } => "RUN",
+            Instruction::Cmd(_) => "CMD",
+            Instruction::Entrypoint(_) => "ENTRYPOINT",
+            Instruction::Copy(_) => "COPY",
+            Instruction::Add(_) => "ADD",
+            Instruction::Env(_) => "ENV",
+            Instruction::Arg(_) => "ARG",
+            Instruction::Label(_) => "LABEL",
+            Instruction::Expose(_) => "EXPOSE",
+            Instruction::Volume(_) => "VOLUME",
+            Instruction::Workdir(_) => "WORKDIR",
+            Instruction::User(_) => "USER",
+            Instruction::Shell(_) => "SHELL",
+            Instruction::Stopsignal(_) => "STOPSIGNAL",
+            Instruction::Healthcheck { .. } => "HEALTHCHECK",
+            Instruction::Onbuild(_) => "ONBUILD",
+            Instruction::Maintainer(_) => "MAINTAINER",
+        }
+    }
+}
+
+/// Parses a Dockerfile. Continuation lines end in the escape character
+/// (`\`, or whatever `# escape=` picks), comment lines inside a
+/// continuation are dropped, and here-documents are read for `RUN`,
+/// `COPY` and `ADD`. Errors point at the start of the offending
+/// instruction.
+pub fn parse_dockerfile(input: &str) -> Result<Dockerfile, ParseError> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for raw in input.split_inclusive('\n') {
+        lines.push((offset, raw.trim_end_matches(['\n', '\r'])));
+        offset += raw.len();
+    }
+
+    let mut dockerfile = Dockerfile::default();
+    let mut escape = '\\';
+    let mut i = 0;
+    while let Some((offset, line)) = lines.get(i) {
+        let Some((key, value)) = directive(line) else {
+            break;
+        };
+        if key == "escape" {
+            escape = match value.as_str() {
+                "\\" => '\\',
+                "`" => '`',
+                _ => {
+                    return Err(ParseError::new(
+                        *offset,
+                        "escape must be `\\` or a backtick",
+                    ));
+                }
+            };
+        }
+        dockerfile.directives.push((key, value));
+        i += 1;
+    }
+
+    while i < lines.len() {
+        let (start, first) = lines[i];
+        let number = i + 1;
+        i += 1;
+        if first.trim().is_empty() || first.trim_start().starts_with('#') {
+            continue;
+        }
+        let mut text = first.to_string();
+        while let Some(joined) = text.trim_end().strip_suffix(escape) {
+            text = joined.to_string();
+            // A trailing escape on the last line just ends the file.
+            while let Some((_, next)) = lines.get(i) {
+                i += 1;
+                if !next.trim_start().starts_with('#') {
+                    text.push_str(next);
+                    break;
+                }
+            }
+            if i >= lines.len() {
+                break;
+            }
+        }
+        let error = |message: String| ParseError::new(start, message);
+        let text = text.trim();
+        let (keyword, args) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let keyword = keyword.to_ascii_uppercase();
+        let args = args.trim();
+
+        let mut heredocs = Vec::new();
+        if matches!(keyword.as_str(), "RUN" | "COPY" | "ADD") {
+            for (delimiter, strip_tabs) in heredoc_markers(args) {
+                let mut content = String::new();
+                loop {
+                    let Some((_, line)) = lines.get(i) else {
+                        return Err(error(format!(
+                            "here-document `{delimiter}` is never closed"
+                        )));
+                    };
+                    i += 1;
+                    let line = if strip_tabs {
+                        line.trim_start_matches('\t')
+                    } else {
+                        line
+                    };
+                    if line == delimiter {
+                        break;
+                    }
+                    content.push_str(line);
+                    content.push('\n');
+                }
+                heredocs.push(Heredoc {
+                    delimiter,
+                    strip_tabs,
+                    content,
+                });
+            }
+        }
+        let instruction = instruction(&keyword, args, heredocs, escape).map_err(error)?;
+        dockerfile.steps.push(Step {
+            line: number,
+            instruction,
+        });
+    }
+    Ok(dockerfile)
+}
+
+/// `# key=value` before the first instruction.
+fn directive(line: &str) -> Option<(String, String)> {
+    let (key, value) = line.trim().strip_prefix('#')?.split_once('=')?;
+    let key = key.trim().to_ascii_lowercase();
+    let valid = !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric());
+    valid.then(|| (key, value.trim().to_string()))
+}
+
+/// `<<EOF`, `<<-EOF` and `<<"EOF"` markers, in order.
+fn heredoc_markers(args: &str) -> Vec<(String, bool)> {
+    args.split_whitespace()
+        .filter_map(|word| {
+            let marker = word.strip_prefix("<<")?;
+            let (marker, strip_tabs) = match marker.strip_prefix('-') {
+                Some(marker) => (marker, true),
+                None => (marker, false),
+            };
+            let marker = ['"', '\'']
+                .into_iter()
+                .find_map(|q| marker.strip_prefix(q)?.strip_suffix(q))
+                .unwrap_or(marker);
+            let valid = !marker.is_empty()
+                && marker
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_');
+            valid.then(|| (marker.to_string(), strip_tabs))
+        })
+        .collect()
+}
+
+fn instruction(
+    keyword: &str,
+    args: &str,
+    heredocs: Vec<Heredoc>,
+    escape: char,
+) -> Result<Instruction, String> {
+    if args.is_empty() {
+        return Err(format!("`{keyword}` needs arguments"));
+    }
+    let words = || split_words(args, escape);
+    Ok(match keyword {
+        "FROM" => {
+            let words = words()?;
+            let (flags, rest) = flags(&words);
+            match rest {
+                [image] => Instruction::From {
+                    flags,
+                    image: image.clone(),
+                    alias: None,
+                },
+                [image, as_, alias] if as_.eq_ignore_ascii_case("as") => Instruction::From {
+                    flags,
+                    image: image.clone(),
+                    alias: Some(alias.clone()),
+                },
+                _ => return Err("expected `FROM image [AS name]`".to_string()),
+            }
+        }
+        "RUN" => {
+            let (flags, rest) = raw_flags(args, escape)?;
+            Instruction::Run {
+                flags,
+                command: command(rest),
+                heredocs,
+            }
+        }
+        "CMD" => Instruction::Cmd(command(args)),
+        "ENTRYPOINT" => Instruction::Entrypoint(command(args)),
+        "COPY" | "ADD" => {
+            let (flags, rest) = raw_flags(args, escape)?;
+            let mut paths = json_array(rest).map_or_else(|| split_words(rest, escape), Ok)?;
+            if paths.len() < 2 && heredocs.is_empty() || paths.is_empty() {
+                return Err(format!("`{keyword}` needs a source and a destination"));
+            }
+            let dest = paths.pop().unwrap_or_default();
+            let transfer = Transfer {
+                flags,
+                sources: paths,
+                dest,
+                heredocs,
+            };
+            match keyword {
+                "COPY" => Instruction::Copy(transfer),
+                _ => Instruction::Add(transfer),
+            }
+        }
+        "ENV" => {
+            let words = words()?;
+            if words[0].contains('=') {
+                Instruction::Env(pairs(&words)?)
+            } else {
+                // The legacy `ENV name value with spaces` form.
+                let (_, value) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+                Instruction::Env(vec![(words[0].clone(), value.trim().to_string())])
+            }
+        }
+        "LABEL" => Instruction::Label(pairs(&words()?)?),
+        "ARG" => Instruction::Arg(
+            words()?
+                .into_iter()
+                .map(|word| match word.split_once('=') {
+                    Some((name, default)) => (name.to_string(), Some(default.to_string())),
+                    None => (word, None),
+                })
+                .collect(),
+        ),
+        "EXPOSE" => Instruction::Expose(words()?),
+        "VOLUME" => Instruction::Volume(json_array(args).map_or_else(words, Ok)?),
+        "WORKDIR" => Instruction::Workdir(args.to_string()),
+        "USER" => Instruction::User(args.to_string()),
+        "STOPSIGNAL" => Instruction::Stopsignal(args.to_string()),
+        "MAINTAINER" => Instruction::Maintainer(args.to_string()),
+        "SHELL" => Instruction::Shell(
+            json_array(args).ok_or_else(|| "`SHELL` takes a JSON array".to_string())?,
+        ),
+        "HEALTHCHECK" if args.eq_ignore_ascii_case("none") => Instruction::Healthcheck {
+            flags: Vec::new(),
+            command: None,
+        },
+        "HEALTHCHECK" => {
+            let (flags, rest) = raw_flags(args, escape)?;
+            match rest.split_once(char::is_whitespace) {
+                Some((cmd, rest)) if cmd.eq_ignore_ascii_case("cmd") => Instruction::Healthcheck {
+                    flags,
+                    command: Some(command(rest)),
+                },
+                _ => return Err("expected `HEALTHCHECK [options] CMD command`".to_string()),
+            }
+        }
+        "ONBUILD" => {
+            let (keyword, rest) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+            let keyword = keyword.to_ascii_uppercase();
+            if matches!(keyword.as_str(), "ONBUILD" | "FROM" | "MAINTAINER") {
+                return Err(format!("`{keyword}` is not allowed after `ONBUILD`"));
+            }
+            Instruction::Onbuild(Box::new(instruction(
+                &keyword,
+                rest.trim(),
+                heredocs,
+                escape,
+            )?))
+        }
+        _ => return Err(format!("unknown instruction `{keyword}`")),
+    })
+}
+
+/// The exec form when `args` is a JSON array of strings, the shell form
+/// otherwise, as Docker decides.
+fn command(args: &str) -> Command {
+    match json_array(args) {
+        Some(argv) => Command::Exec(argv),
+        None => Command::Shell(args.trim().to_string()),
+    }
+}
+
+fn json_array(args: &str) -> Option<Vec<String>> {
+    if !args.trim_start().starts_with('[') {
+        return None;
+    }
+    let JsonValue::Array(items) = parse_json(args).ok()? else {
+        return None;
+    };
+    items
+        .into_iter()
+        .map(|item| match item {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Leading `--name[=value]` words of already split arguments.
+fn flags(words: &[String]) -> (Vec<Flag>, &[String]) {
+    let count = words.iter().take_while(|w| w.starts_with("--")).count();
+    let flags = words[..count].iter().map(|w| flag(w)).collect();
+    (flags, &words[count..])
+}
+
+/// Leading flags of raw arguments, and the rest left as written so that a
+/// shell command keeps its quoting.
+fn raw_flags(args: &str, escape: char) -> Result<(Vec<Flag>, &str), String> {
+    let mut flags = Vec::new();
+    let mut rest = args.trim_start();
+    while rest.starts_with("--") {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let word = split_words(&rest[..end], escape)?.concat();
+        flags.push(flag(&word));
+        rest = rest[end..].trim_start();
+    }
+    Ok((flags, rest))
+}
+
+fn flag(word: &str) -> Flag {
+    let word = word.trim_start_matches('-');
+    match word.split_once('=') {
+        Some((name, value)) => Flag {
+            name: name.to_string(),
+            value: Some(value.to_string()),
+        },
+        None => Flag {
+            name: word.to_string(),
+            value: None,
+        },
+    }
+}
+
+fn pairs(words: &[String]) -> Result<Vec<(String, String)>, String> {
+    words
+        .iter()
+        .map(|word| {
+            word.split_once('=')
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .ok_or_else(|| format!("expected `key=value`, found `{word}`"))
+        })
+        .collect()
+}
+
+/// Splits on white space the way Docker does for `ENV`, `LABEL` and
+/// friends: quotes group and are removed, and the escape character keeps
+/// the next character literal outside single quotes.
+fn split_words(text: &str, escape: char) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let quoted: String = chars.by_ref().take_while(|c| *c != '\'').collect();
+                word.get_or_insert_default().push_str(&quoted);
+            }
+            '"' => {
+                let word = word.get_or_insert_default();
+                loop {
+                    match chars.next() {
+                        None => return Err("unterminated `\"`".to_string()),
+                        Some('"') => break,
+                        Some(c) if c == escape => word.extend(chars.next()),
+                        Some(c) => word.push(c),
+                    }
+                }
+            }
+            c if c == escape => word.get_or_insert_default().extend(chars.next()),
+            c => word.get_or_insert_default().push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCKERFILE: &str = r#"# syntax=docker/dockerfile:1
+ARG RUST_VERSION=1.85 DEBUG
+FROM --platform=$BUILDPLATFORM rust:${RUST_VERSION} AS build
+WORKDIR /src
+# cache the registry between builds
+RUN --mount=type=cache,target=/usr/local/cargo/registry \
+    # comments inside a continuation are dropped
+    cargo build --release && \
+    strip target/release/app
+COPY --chown=app:app <<EOF /etc/app.toml
+port = 8080
+EOF
+RUN <<-'SH' bash
+	echo "$HOME"
+	SH
+
+FROM gcr.io/distroless/cc
+COPY --from=build /src/target/release/app /usr/local/bin/
+ENV APP_ENV=production GREETING="hello world"
+LABEL org.opencontainers.image.title=app version="1.0"
+EXPOSE 8080/tcp
+HEALTHCHECK --interval=30s CMD ["app", "--health"]
+ENTRYPOINT ["/usr/local/bin/app"]
+CMD serve --port 8080
+"#;
+
+    #[test]
+    fn parse_dockerfile_should_work() -> Result<(), ParseError> {
+        let dockerfile = parse_dockerfile(DOCKERFILE)?;
+        assert_eq!(
+            dockerfile.directives,
+            [("syntax".into(), "docker/dockerfile:1".into())]
+        );
+        let stages = dockerfile.stages();
+        assert_eq!(stages.len(), 2);
+        assert_eq!(stages[0].image, "rust:${RUST_VERSION}");
+        assert_eq!(stages[0].alias, Some("build"));
+        assert_eq!(stages[1].steps.len(), 7);
+
+        let Instruction::Run { flags, command, .. } = &stages[0].steps[1].instruction else {
+            panic!("expected RUN");
+        };
+        assert_eq!(
+            flags[0].value.as_deref(),
+            Some("type=cache,target=/usr/local/cargo/registry")
+        );
+        assert_eq!(
+            *command,
+            Command::Shell("cargo build --release &&     strip target/release/app".to_string())
+        );
+        assert_eq!(stages[0].steps[1].line, 6);
+
+        let Instruction::Copy(copy) = &stages[0].steps[2].instruction else {
+            panic!("expected COPY");
+        };
+        assert_eq!(copy.heredocs[0].content, "port = 8080\n");
+        assert_eq!(copy.dest, "/etc/app.toml");
+        let Instruction::Run { heredocs, .. } = &stages[0].steps[3].instruction else {
+            panic!("expected RUN");
+        };
+        assert_eq!(heredocs[0].delimiter, "SH");
+        assert!(heredocs[0].strip_tabs);
+        assert_eq!(heredocs[0].content, "echo \"$HOME\"\n");
+
+        let steps: Vec<_> = stages[1].steps.iter().map(|s| &s.instruction).collect();
+        assert_eq!(
+            *steps[1],
+            Instruction::Env(vec![
+                ("APP_ENV".into(), "production".into()),
+                ("GREETING".into(), "hello world".into())
+            ])
+        );
+        assert_eq!(
+            *steps[5],
+            Instruction::Entrypoint(Command::Exec(vec!["/usr/local/bin/app".into()]))
+        );
+        assert_eq!(
+            *steps[6],
+            Instruction::Cmd(Command::Shell("serve --port 8080".into()))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn escape_directive_and_legacy_forms() -> Result<(), ParseError> {
+        let dockerfile = parse_dockerfile(
+            "# escape=`\nFROM mcr.microsoft.com/windows/servercore\nRUN dir c:\\ `\n  && echo done\nENV PATH C:\\Program Files\\app\nONBUILD copy . /app\nSHELL [\"powershell\", \"-Command\"]\nHEALTHCHECK NONE\n",
+        )?;
+        let steps: Vec<_> = dockerfile.steps.iter().map(|s| &s.instruction).collect();
+        assert_eq!(
+            *steps[1],
+            Instruction::Run {
+                flags: Vec::new(),
+                command: Command::Shell("dir c:\\   && echo done".into()),
+                heredocs: Vec::new(),
+            }
+        );
+        assert_eq!(
+            *steps[2],
+            Instruction::Env(vec![("PATH".into(), "C:\\Program Files\\app".into())])
+        );
+        assert_eq!(steps[3].keyword(), "ONBUILD");
+        let Instruction::Onbuild(inner) = steps[3] else {
+            panic!("expected ONBUILD");
+        };
+        assert_eq!(inner.keyword(), "COPY");
+        assert_eq!(
+            *steps[4],
+            Instruction::Shell(vec!["powershell".into(), "-Command".into()])
+        );
+        assert_eq!(
+            *steps[5],
+            Instruction::Healthcheck {
+                flags: Vec::new(),
+                command: None
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_dockerfile_should_report_errors() {
+        let err = parse_dockerfile("FROM alpine\nRUNN echo\n").unwrap_err();
+        assert_eq!(err.offset(), 12);
+        assert_eq!(err.message(), "unknown instruction `RUNN`");
+        assert!(parse_dockerfile("FROM alpine\nRUN <<EOF\necho\n").is_err());
+        assert!(parse_dockerfile("COPY onlyone\n").is_err());
+        assert!(parse_dockerfile("FROM a b c d\n").is_err());
+        assert!(parse_dockerfile("ENV A=\"open\n").is_err());
+        assert!(parse_dockerfile("SHELL bash -c\n").is_err());
+        assert!(parse_dockerfile("WORKDIR\n").is_err());
+    }
+}
@@ -0,0 +1,701 @@
+use std::fmt;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{digit0, digit1};
+use winnow::combinator::{alt, cut_err, delimited, fail, opt, peek, preceded, repeat, terminated};
+use winnow::error::{ContextError, ErrMode, StrContext, StrContextValue};
+use winnow::token::{any, take_till, take_while};
+
+use crate::ParseError;
+use crate::combinators::Trivia;
+
+/// A `graph` or `digraph`, kept statement by statement so it can be
+/// edited and written back out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Graph {
+    pub strict: bool,
+    pub directed: bool,
+    pub id: Option<Id>,
+    pub stmts: Vec<Stmt>,
+}
+
+/// An identifier, remembering how it was written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Id {
+    /// A bare name or numeral.
+    Plain(String),
+    /// A double-quoted string with `\"` unescaped. Other backslash
+    /// sequences such as `\n` belong to the attribute and are kept.
+    Quoted(String),
+    /// An HTML string, without the outer `<` `>`.
+    Html(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stmt {
+    Node(NodeId, Vec<Attr>),
+    /// A chain such as `a -> b -> c`, which has at least two targets.
+    Edge(Vec<EdgeTarget>, Vec<Attr>),
+    /// `graph [..]`, `node [..]` or `edge [..]`.
+    Defaults(AttrKind, Vec<Attr>),
+    /// A bare `key = value`.
+    Assign(Attr),
+    Subgraph(Subgraph),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttrKind {
+    Graph,
+    Node,
+    Edge,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attr {
+    pub key: Id,
+    pub value: Id,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeId {
+    pub id: Id,
+    pub port: Option<Port>,
+}
+
+/// `:port`, `:port:compass` or `:compass`. A lone name is stored as `id`
+/// either way, since only the renderer can tell them apart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Port {
+    pub id: Id,
+    pub compass: Option<Id>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EdgeTarget {
+    Node(NodeId),
+    Subgraph(Subgraph),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Subgraph {
+    pub id: Option<Id>,
+    pub stmts: Vec<Stmt>,
+}
+
+const KEYWORDS: [&str; 6] = ["node", "edge", "graph", "digraph", "subgraph", "strict"];
+
+impl Id {
+    /// Picks the plainest spelling that can hold `value`.
+    pub fn new(value: impl Into<String>) -> Self {
+        let value = value.into();
+        let bare =
+            identifier.parse(value.as_str()).is_ok() || numeral.parse(value.as_str()).is_ok();
+        if bare && !is_keyword(&value) {
+            Id::Plain(value)
+        } else {
+            Id::Quoted(value)
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Id::Plain(s) | Id::Quoted(s) | Id::Html(s) => s,
+        }
+    }
+}
+
+impl Attr {
+    pub fn new(key: impl Into<String>, value: impl Into<String>) -> Self {
+        Attr {
+            key: Id::new(key),
+            value: Id::new(value),
+        }
+    }
+}
+
+impl Graph {
+    /// Node names in order of first mention, including those only named by
+    /// edges.
+    pub fn nodes(&self) -> Vec<&str> {
+        let mut nodes = Vec::new();
+        collect_nodes(&self.stmts, &mut nodes);
+        nodes
+    }
+
+    /// Every edge as a pair of node names. Chains are split, and a subgraph
+    /// endpoint stands for each of its nodes.
+    pub fn edges(&self) -> Vec<(&str, &str)> {
+        let mut edges = Vec::new();
+        collect_edges(&self.stmts, &mut edges);
+        edges
+    }
+
+    /// The attributes given to `node` by its own statements, later values
+    /// first. `node [..]` defaults are not applied.
+    pub fn node_attrs(&self, node: &str) -> Vec<&Attr> {
+        let mut attrs = Vec::new();
+        collect_node_attrs(&self.stmts, node, &mut attrs);
+        attrs.reverse();
+        attrs
+    }
+}
+
+fn collect_nodes<'a>(stmts: &'a [Stmt], out: &mut Vec<&'a str>) {
+    let add = |id: &'a Id, out: &mut Vec<&'a str>| {
+        if !out.contains(&id.as_str()) {
+            out.push(id.as_str());
+        }
+    };
+    for stmt in stmts {
+        match stmt {
+            Stmt::Node(node, _) => add(&node.id, out),
+            Stmt::Edge(targets, _) => {
+                for target in targets {
+                    match target {
+                        EdgeTarget::Node(node) => add(&node.id, out),
+                        EdgeTarget::Subgraph(sub) => collect_nodes(&sub.stmts, out),
+                    }
+                }
+            }
+            Stmt::Subgraph(sub) => collect_nodes(&sub.stmts, out),
+            Stmt::Defaults(..) | Stmt::Assign(_) => {}
+        }
+    }
+}
+
+fn collect_edges<'a>(stmts: &'a [Stmt], out: &mut Vec<(&'a str, &'a str)>) {
+    let endpoints = |target: &'a EdgeTarget| match target {
+        EdgeTarget::Node(node) => vec![node.id.as_str()],
+        EdgeTarget::Subgraph(sub) => {
+            let mut nodes = Vec::new();
+            collect_nodes(&sub.stmts, &mut nodes);
+            nodes
+        }
+    };
+    for stmt in stmts {
+        match stmt {
+            Stmt::Edge(targets, _) => {
+                for pair in targets.windows(2) {
+                    if let EdgeTarget::Subgraph(sub) = &pair[0] {
+                        collect_edges(&sub.stmts, out);
+                    }
+                    for from in endpoints(&pair[0]) {
+                        for to in endpoints(&pair[1]) {
+                            out.push((from, to));
+                        }
+                    }
+                }
+                if let Some(EdgeTarget::Subgraph(sub)) = targets.last() {
+                    collect_edges(&sub.stmts, out);
+                }
+            }
+            Stmt::Subgraph(sub) => collect_edges(&sub.stmts, out),
+            _ => {}
+        }
+    }
+}
+
+fn collect_node_attrs<'a>(stmts: &'a [Stmt], node: &str, out: &mut Vec<&'a Attr>) {
+    for stmt in stmts {
+        match stmt {
+            Stmt::Node(id, attrs) if id.id.as_str() == node => out.extend(attrs),
+            Stmt::Subgraph(sub) => collect_node_attrs(&sub.stmts, node, out),
+            _ => {}
+        }
+    }
+}
+
+fn is_keyword(name: &str) -> bool {
+    KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(name))
+}
+
+/// Writes the graph back as DOT, one statement per line.
+impl fmt::Display for Graph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.strict {
+            f.write_str("strict ")?;
+        }
+        f.write_str(if self.directed { "digraph" } else { "graph" })?;
+        if let Some(id) = &self.id {
+            write!(f, " {id}")?;
+        }
+        f.write_str(" {\n")?;
+        write_stmts(f, &self.stmts, self.directed, 4)?;
+        f.write_str("}\n")
+    }
+}
+
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Id::Plain(s) => f.write_str(s),
+            Id::Quoted(s) => write!(f, "\"{}\"", s.replace('"', "\\\"")),
+            Id::Html(s) => write!(f, "<{s}>"),
+        }
+    }
+}
+
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.id)?;
+        if let Some(port) = &self.port {
+            write!(f, ":{}", port.id)?;
+            if let Some(compass) = &port.compass {
+                write!(f, ":{compass}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_stmts(
+    f: &mut fmt::Formatter<'_>,
+    stmts: &[Stmt],
+    directed: bool,
+    indent: usize,
+) -> fmt::Result {
+    for stmt in stmts {
+        write!(f, "{:indent$}", "")?;
+        match stmt {
+            Stmt::Node(node, attrs) => {
+                write!(f, "{node}")?;
+                write_attrs(f, attrs)?;
+            }
+            Stmt::Edge(targets, attrs) => {
+                for (i, target) in targets.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(if directed { " -> " } else { " -- " })?;
+                    }
+                    match target {
+                        EdgeTarget::Node(node) => write!(f, "{node}")?,
+                        EdgeTarget::Subgraph(sub) => write_subgraph(f, sub, directed, indent)?,
+                    }
+                }
+                write_attrs(f, attrs)?;
+            }
+            Stmt::Defaults(kind, attrs) => {
+                f.write_str(match kind {
+                    AttrKind::Graph => "graph",
+                    AttrKind::Node => "node",
+                    AttrKind::Edge => "edge",
+                })?;
+                write_attrs(f, attrs)?;
+            }
+            Stmt::Assign(attr) => write!(f, "{}={}", attr.key, attr.value)?,
+            Stmt::Subgraph(sub) => {
+                write_subgraph(f, sub, directed, indent)?;
+                f.write_str("\n")?;
+                continue;
+            }
+        }
+        f.write_str(";\n")?;
+    }
+    Ok(())
+}
+
+fn write_subgraph(
+    f: &mut fmt::Formatter<'_>,
+    sub: &Subgraph,
+    directed: bool,
+    indent: usize,
+) -> fmt::Result {
+    f.write_str("subgraph ")?;
+    if let Some(id) = &sub.id {
+        write!(f, "{id} ")?;
+    }
+    f.write_str("{\n")?;
+    write_stmts(f, &sub.stmts, directed, indent + 4)?;
+    write!(f, "{:indent$}}}", "")
+}
+
+fn write_attrs(f: &mut fmt::Formatter<'_>, attrs: &[Attr]) -> fmt::Result {
+    if attrs.is_empty() {
+        return Ok(());
+    }
+    f.write_str(" [")?;
+    for (i, attr) in attrs.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        write!(f, "{}={}", attr.key, attr.value)?;
+    }
+    f.write_str("]")
+}
+
+/// Parses a single graph. Keywords are case-insensitive, and lines starting
+/// with `#` are skipped like C preprocessor output.
+pub fn parse_dot(input: &str) -> Result<Graph, ParseError> {
+    delimited(trivia, graph, trivia)
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+fn graph(input: &mut &str) -> ModalResult<Graph> {
+    let strict = opt(terminated(keyword("strict"), trivia))
+        .parse_next(input)?
+        .is_some();
+    let directed = cut_err(alt((
+        keyword("digraph").value(true),
+        keyword("graph").value(false),
+    )))
+    .context(StrContext::Expected(StrContextValue::StringLiteral(
+        "graph",
+    )))
+    .parse_next(input)?;
+    let id = opt(preceded(trivia, id)).parse_next(input)?;
+    let stmts = preceded(expect('{'), |i: &mut &str| stmts(i, directed)).parse_next(input)?;
+    expect('}').parse_next(input)?;
+    Ok(Graph {
+        strict,
+        directed,
+        id,
+        stmts,
+    })
+}
+
+fn stmts(input: &mut &str, directed: bool) -> ModalResult<Vec<Stmt>> {
+    repeat(
+        0..,
+        delimited(trivia, |i: &mut &str| stmt(i, directed), (trivia, opt(';'))),
+    )
+    .parse_next(input)
+}
+
+fn stmt(input: &mut &str, directed: bool) -> ModalResult<Stmt> {
+    let defaults = alt((
+        keyword("graph").value(AttrKind::Graph),
+        keyword("node").value(AttrKind::Node),
+        keyword("edge").value(AttrKind::Edge),
+    ));
+    if let Some(kind) = opt(terminated(defaults, peek((trivia, '[')))).parse_next(input)? {
+        let attrs = attr_lists.parse_next(input)?;
+        return Ok(Stmt::Defaults(kind, attrs));
+    }
+    if peek(opt(alt((keyword("subgraph").void(), "{".void()))))
+        .parse_next(input)?
+        .is_some()
+    {
+        let sub = subgraph(input, directed)?;
+        return edge_rest(input, directed, EdgeTarget::Subgraph(sub)).map(|edge| {
+            edge.unwrap_or_else(|target| match target {
+                EdgeTarget::Subgraph(sub) => Stmt::Subgraph(sub),
+                EdgeTarget::Node(_) => unreachable!(),
+            })
+        });
+    }
+    // Backtracks, so the block's closing brace is reported as expected.
+    let key = id.parse_next(input)?;
+    if opt(preceded(trivia, '=')).parse_next(input)?.is_some() {
+        let value =
+            preceded(trivia, cut_err(id).context(StrContext::Label("value"))).parse_next(input)?;
+        return Ok(Stmt::Assign(Attr { key, value }));
+    }
+    let node = NodeId {
+        id: key,
+        port: opt(port).parse_next(input)?,
+    };
+    match edge_rest(input, directed, EdgeTarget::Node(node))? {
+        Ok(edge) => Ok(edge),
+        Err(EdgeTarget::Node(node)) => {
+            let attrs = opt(attr_lists).parse_next(input)?.unwrap_or_default();
+            Ok(Stmt::Node(node, attrs))
+        }
+        Err(EdgeTarget::Subgraph(_)) => unreachable!(),
+    }
+}
+
+/// The `-> b -> c [..]` after a first target, or the target back when no
+/// edge operator follows.
+fn edge_rest(
+    input: &mut &str,
+    directed: bool,
+    first: EdgeTarget,
+) -> ModalResult<Result<Stmt, EdgeTarget>> {
+    let mut targets = vec![first];
+    while opt(preceded(trivia, |i: &mut &str| edge_op(i, directed)))
+        .parse_next(input)?
+        .is_some()
+    {
+        trivia.parse_next(input)?;
+        let target = if peek(opt(alt((keyword("subgraph").void(), "{".void()))))
+            .parse_next(input)?
+            .is_some()
+        {
+            EdgeTarget::Subgraph(subgraph(input, directed)?)
+        } else {
+            let node = cut_err(id)
+                .context(StrContext::Label("edge target"))
+                .parse_next(input)?;
+            EdgeTarget::Node(NodeId {
+                id: node,
+                port: opt(port).parse_next(input)?,
+            })
+        };
+        targets.push(target);
+    }
+    if targets.len() == 1 {
+        return Ok(Err(targets.pop().unwrap()));
+    }
+    let attrs = opt(attr_lists).parse_next(input)?.unwrap_or_default();
+    Ok(Ok(Stmt::Edge(targets, attrs)))
+}
+
+/// The operator matching the graph kind; the other one is an error.
+fn edge_op(input: &mut &str, directed: bool) -> ModalResult<()> {
+    let (op, wrong) = if directed { ("->", "--") } else { ("--", "->") };
+    if peek(opt(wrong)).parse_next(input)?.is_some() {
+        return cut_err(fail)
+            .context(StrContext::Expected(StrContextValue::StringLiteral(op)))
+            .parse_next(input);
+    }
+    op.void().parse_next(input)
+}
+
+fn subgraph(input: &mut &str, directed: bool) -> ModalResult<Subgraph> {
+    let id = opt(preceded(
+        (keyword("subgraph"), trivia),
+        opt(terminated(id, trivia)),
+    ))
+    .parse_next(input)?
+    .flatten();
+    let stmts = preceded(expect('{'), |i: &mut &str| stmts(i, directed)).parse_next(input)?;
+    expect('}').parse_next(input)?;
+    Ok(Subgraph { id, stmts })
+}
+
+fn port(input: &mut &str) -> ModalResult<Port> {
+    let name = preceded(
+        (trivia, ':', trivia),
+        cut_err(id).context(StrContext::Label("port")),
+    )
+    .parse_next(input)?;
+    let compass = opt(preceded(
+        (trivia, ':', trivia),
+        cut_err(id).context(StrContext::Label("compass point")),
+    ))
+    .parse_next(input)?;
+    Ok(Port { id: name, compass })
+}
+
+/// One or more `[..]` lists, concatenated.
+fn attr_lists(input: &mut &str) -> ModalResult<Vec<Attr>> {
+    let lists: Vec<Vec<Attr>> = repeat(1.., preceded(trivia, attr_list)).parse_next(input)?;
+    Ok(lists.concat())
+}
+
+fn attr_list(input: &mut &str) -> ModalResult<Vec<Attr>> {
+    let attr = (
+        id,
+        preceded(
+            expect('='),
+            preceded(trivia, cut_err(id).context(StrContext::Label("value"))),
+        ),
+    )
+        .map(|(key, value)| Attr { key, value });
+    delimited(
+        '[',
+        repeat(0.., delimited(trivia, attr, (trivia, opt(alt((';', ',')))))),
+        expect(']'),
+    )
+    .parse_next(input)
+}
+
+fn keyword<'i>(word: &'static str) -> impl Parser<&'i str, &'i str, ErrMode<ContextError>> {
+    identifier.verify(move |name: &str| name.eq_ignore_ascii_case(word))
+}
+
+fn id(input: &mut &str) -> ModalResult<Id> {
+    alt((
+        identifier
+            .verify(|name: &str| !is_keyword(name))
+            .map(|s: &str| Id::Plain(s.to_string())),
+        numeral.map(|s: &str| Id::Plain(s.to_string())),
+        quoted.map(Id::Quoted),
+        html.map(Id::Html),
+    ))
+    .parse_next(input)
+}
+
+fn identifier<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    (
+        take_while(1, |c: char| {
+            c.is_ascii_alphabetic() || c == '_' || !c.is_ascii()
+        }),
+        take_while(0.., |c: char| {
+            c.is_ascii_alphanumeric() || c == '_' || !c.is_ascii()
+        }),
+    )
+        .take()
+        .parse_next(input)
+}
+
+fn numeral<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    (
+        opt('-'),
+        alt((('.', digit1).void(), (digit1, opt(('.', digit0))).void())),
+    )
+        .take()
+        .parse_next(input)
+}
+
+/// One or more quoted strings joined with `+`.
+fn quoted(input: &mut &str) -> ModalResult<String> {
+    let first = quoted_part.parse_next(input)?;
+    let rest: Vec<String> =
+        repeat(0.., preceded((trivia, '+', trivia), quoted_part)).parse_next(input)?;
+    Ok(std::iter::once(first).chain(rest).collect())
+}
+
+fn quoted_part(input: &mut &str) -> ModalResult<String> {
+    let piece = alt((
+        take_till(1.., ['"', '\\']).map(str::to_string),
+        preceded('\\', any).map(|c: char| match c {
+            '"' => "\"".to_string(),
+            '\n' => String::new(),
+            c => format!("\\{c}"),
+        }),
+    ));
+    let pieces: Vec<String> = preceded(
+        '"',
+        cut_err(terminated(repeat(0.., piece), '"'))
+            .context(StrContext::Expected(StrContextValue::CharLiteral('"'))),
+    )
+    .parse_next(input)?;
+    Ok(pieces.concat())
+}
+
+/// `<...>` with balanced inner angle brackets.
+fn html(input: &mut &str) -> ModalResult<String> {
+    fn body(input: &mut &str) -> ModalResult<()> {
+        repeat(
+            0..,
+            alt((
+                take_till(1.., ['<', '>']).void(),
+                ('<', body, cut_err('>')).void(),
+            )),
+        )
+        .parse_next(input)
+    }
+    preceded(
+        '<',
+        cut_err(terminated(body.take(), '>'))
+            .context(StrContext::Expected(StrContextValue::CharLiteral('>'))),
+    )
+    .map(str::to_string)
+    .parse_next(input)
+}
+
+fn expect<'i>(c: char) -> impl Parser<&'i str, char, ErrMode<ContextError>> {
+    preceded(
+        trivia,
+        cut_err(c).context(StrContext::Expected(StrContextValue::CharLiteral(c))),
+    )
+}
+
+/// White space, `//` and `/* */` comments, and `#` lines.
+const TRIVIA: Trivia = Trivia::C.line_comments(&["//", "#"]);
+
+fn trivia(input: &mut &str) -> ModalResult<()> {
+    let mut trivia = TRIVIA;
+    trivia.parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_dot_should_work() -> Result<(), ParseError> {
+        let graph = parse_dot(
+            r#"/* build graph */
+strict digraph "deps" {
+    rankdir = LR
+    node [shape=box; color="light" + "blue"] [style=filled]
+    a:out:s -> b -> c [label="say \"hi\"\n", weight=2.5];
+    # preprocessor line
+    d [label=<<b>bold</b>>]
+    subgraph cluster_0 { e; f } -> b
+    { rank=same; g } // trailing
+}"#,
+        )?;
+        assert!(graph.strict && graph.directed);
+        assert_eq!(graph.id, Some(Id::Quoted("deps".into())));
+        assert_eq!(graph.stmts[0], Stmt::Assign(Attr::new("rankdir", "LR")));
+        assert_eq!(
+            graph.stmts[1],
+            Stmt::Defaults(
+                AttrKind::Node,
+                vec![
+                    Attr::new("shape", "box"),
+                    Attr {
+                        key: Id::new("color"),
+                        value: Id::Quoted("lightblue".into())
+                    },
+                    Attr::new("style", "filled"),
+                ]
+            )
+        );
+        let Stmt::Edge(targets, attrs) = &graph.stmts[2] else {
+            panic!("expected an edge");
+        };
+        assert_eq!(
+            targets[0],
+            EdgeTarget::Node(NodeId {
+                id: Id::new("a"),
+                port: Some(Port {
+                    id: Id::new("out"),
+                    compass: Some(Id::new("s"))
+                })
+            })
+        );
+        assert_eq!(attrs[0].value.as_str(), "say \"hi\"\\n");
+        assert_eq!(attrs[1].value, Id::Plain("2.5".into()));
+        assert_eq!(
+            graph.node_attrs("d")[0].value,
+            Id::Html("<b>bold</b>".into())
+        );
+        assert_eq!(graph.nodes(), ["a", "b", "c", "d", "e", "f", "g"]);
+        assert_eq!(
+            graph.edges(),
+            [("a", "b"), ("b", "c"), ("e", "b"), ("f", "b")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn display_should_round_trip() -> Result<(), ParseError> {
+        let mut graph =
+            parse_dot("graph { a -- b -- {c d} [color=red]; subgraph s { e:n } edge [dir=none] }")?;
+        graph.stmts.push(Stmt::Node(
+            NodeId {
+                id: Id::new("new node"),
+                port: None,
+            },
+            vec![Attr::new("label", "say \"x\"")],
+        ));
+        let text = graph.to_string();
+        assert_eq!(
+            text,
+            "graph {\n    a -- b -- subgraph {\n        c;\n        d;\n    } [color=red];\n    \
+             subgraph s {\n        e:n;\n    }\n    edge [dir=none];\n    \
+             \"new node\" [label=\"say \\\"x\\\"\"];\n}\n"
+        );
+        assert_eq!(parse_dot(&text)?, graph);
+        assert_eq!(Id::new("node"), Id::Quoted("node".into()));
+        assert_eq!(Id::new("-1.5"), Id::Plain("-1.5".into()));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_dot_should_report_errors() {
+        let err = parse_dot("digraph { a -- b }").unwrap_err();
+        assert_eq!(err.offset(), 12);
+        let err = parse_dot("graph { a -> b }").unwrap_err();
+        assert_eq!(err.offset(), 10);
+        assert!(parse_dot("graph { a [color=] }").is_err());
+        assert!(parse_dot("graph { a [label=\"open] }").is_err());
+        assert!(parse_dot("tree { }").is_err());
+        assert!(parse_dot("graph { a ").is_err());
+    }
+}
@@ -0,0 +1,201 @@
+use std::time::Duration;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{digit1, multispace0};
+use winnow::combinator::{cut_err, opt, preceded, repeat};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::{one_of, take_while};
+
+use crate::ParseError;
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// Units from largest to smallest, with every spelling accepted for each.
+/// The first spelling is the one [`format_duration`] writes.
+const UNITS: [(u128, &[&str]); 8] = [
+    (
+        7 * 86_400 * NANOS_PER_SEC,
+        &["w", "wk", "wks", "week", "weeks"],
+    ),
+    (86_400 * NANOS_PER_SEC, &["d", "day", "days"]),
+    (3_600 * NANOS_PER_SEC, &["h", "hr", "hrs", "hour", "hours"]),
+    (
+        60 * NANOS_PER_SEC,
+        &["m", "min", "mins", "minute", "minutes"],
+    ),
+    (NANOS_PER_SEC, &["s", "sec", "secs", "second", "seconds"]),
+    (
+        1_000_000,
+        &["ms", "msec", "msecs", "millisecond", "milliseconds"],
+    ),
+    (
+        1_000,
+        &["us", "µs", "usec", "usecs", "microsecond", "microseconds"],
+    ),
+    (1, &["ns", "nsec", "nsecs", "nanosecond", "nanoseconds"]),
+];
+
+/// Parses a duration such as `1h30m15s`, `2 days`, `500ms` or `1.5h`.
+/// Components may be separated by spaces or commas and are summed, so
+/// `1m 90s` is two and a half minutes. A bare `0` is also accepted.
+pub fn parse_duration(input: &str) -> Result<Duration, ParseError> {
+    preceded(multispace0, duration)
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+/// Like [`parse_duration`], but allows a leading `-` or `+`.
+pub fn parse_signed_duration(input: &str) -> Result<chrono::Duration, ParseError> {
+    (
+        preceded(multispace0, opt(one_of(['-', '+']))),
+        duration.verify_map(|d| chrono::Duration::from_std(d).ok()),
+    )
+        .map(|(sign, d)| if sign == Some('-') { -d } else { d })
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+/// Writes the shortest form that parses back to the same duration, e.g.
+/// `1h30m15s` or `1s500ms`.
+pub fn format_duration(duration: Duration) -> String {
+    let mut nanos = duration.as_nanos();
+    if nanos == 0 {
+        return "0s".to_string();
+    }
+    let mut out = String::new();
+    for (size, names) in UNITS {
+        if nanos >= size {
+            out.push_str(&format!("{}{}", nanos / size, names[0]));
+            nanos %= size;
+        }
+    }
+    out
+}
+
+pub fn format_signed_duration(duration: chrono::Duration) -> String {
+    let formatted = format_duration(duration.abs().to_std().unwrap_or_default());
+    if duration < chrono::Duration::zero() {
+        format!("-{formatted}")
+    } else {
+        formatted
+    }
+}
+
+fn duration(input: &mut &str) -> ModalResult<Duration> {
+    if input.trim_end() == "0" {
+        *input = "";
+        return Ok(Duration::ZERO);
+    }
+    let components: Vec<u128> = repeat(
+        1..,
+        (component, multispace0, opt((',', multispace0))).map(|(nanos, _, _)| nanos),
+    )
+    .parse_next(input)?;
+    let total = components
+        .into_iter()
+        .try_fold(0u128, u128::checked_add)
+        .and_then(|nanos| {
+            let secs = u64::try_from(nanos / NANOS_PER_SEC).ok()?;
+            Some(Duration::new(secs, (nanos % NANOS_PER_SEC) as u32))
+        });
+    match total {
+        Some(total) => Ok(total),
+        None => cut_err(winnow::combinator::fail)
+            .context(StrContext::Label("duration"))
+            .context(StrContext::Expected(StrContextValue::Description(
+                "a duration that fits in 64-bit seconds",
+            )))
+            .parse_next(input),
+    }
+}
+
+/// One `<number><unit>` pair, in nanoseconds.
+fn component(input: &mut &str) -> ModalResult<u128> {
+    let whole = digit1
+        .context(StrContext::Expected(StrContextValue::Description("number")))
+        .parse_next(input)?;
+    let fraction = opt(preceded('.', cut_err(digit1))).parse_next(input)?;
+    multispace0.parse_next(input)?;
+    let size = cut_err(
+        take_while(1.., |c: char| c.is_alphabetic())
+            .verify_map(|unit: &str| {
+                let unit = unit.to_lowercase();
+                UNITS
+                    .iter()
+                    .find(|(_, names)| names.contains(&unit.as_str()))
+                    .map(|(size, _)| *size)
+            })
+            .context(StrContext::Label("unit"))
+            .context(StrContext::Expected(StrContextValue::Description(
+                "a unit such as `h`, `m`, `s` or `ms`",
+            ))),
+    )
+    .parse_next(input)?;
+    let nanos = whole.parse::<u128>().ok().and_then(|n| n.checked_mul(size));
+    // Digits beyond nanosecond precision cannot change the result.
+    let fraction = fraction.map_or(0, |digits| {
+        let digits = &digits[..digits.len().min(18)];
+        digits.parse::<u128>().unwrap_or(0) * size / 10u128.pow(digits.len() as u32)
+    });
+    match nanos {
+        Some(nanos) => Ok(nanos + fraction),
+        None => cut_err(winnow::combinator::fail)
+            .context(StrContext::Label("number"))
+            .parse_next(input),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_should_work() -> Result<(), ParseError> {
+        let cases = [
+            ("1h30m15s", 5_415_000),
+            ("2 days", 172_800_000),
+            ("500ms", 500),
+            ("1.5h", 5_400_000),
+            ("1 hour, 2 minutes", 3_720_000),
+            ("1m 90s", 150_000),
+            ("0.25 Seconds", 250),
+            ("0", 0),
+        ];
+        for (input, millis) in cases {
+            assert_eq!(
+                parse_duration(input)?,
+                Duration::from_millis(millis),
+                "{input}"
+            );
+        }
+        assert_eq!(parse_duration("1.000000001s")?, Duration::new(1, 1));
+        assert_eq!(
+            parse_signed_duration("-90s")?,
+            chrono::Duration::seconds(-90)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn format_duration_should_roundtrip() -> Result<(), ParseError> {
+        assert_eq!(format_duration(Duration::from_secs(5415)), "1h30m15s");
+        assert_eq!(format_duration(Duration::from_millis(1500)), "1s500ms");
+        assert_eq!(format_duration(Duration::from_secs(8 * 86_400)), "1w1d");
+        assert_eq!(format_duration(Duration::ZERO), "0s");
+        assert_eq!(format_signed_duration(chrono::Duration::minutes(-5)), "-5m");
+        let odd = Duration::new(93_784, 5_006_007);
+        assert_eq!(parse_duration(&format_duration(odd))?, odd);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_duration_should_report_errors() {
+        assert_eq!(parse_duration("10").unwrap_err().offset(), 2);
+        assert_eq!(parse_duration("5 parsecs").unwrap_err().offset(), 2);
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("1.h").is_err());
+        assert!(parse_duration("-1s").is_err());
+        assert!(parse_duration("99999999999999999999999w").is_err());
+    }
+}
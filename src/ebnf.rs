@@ -0,0 +1,668 @@
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{line_ending, space0, till_line_ending};
+use winnow::combinator::{
+    alt, cut_err, delimited, eof, fail, not, opt, preceded, repeat, terminated,
+};
+use winnow::error::{ContextError, ErrMode, StrContext, StrContextValue};
+use winnow::stream::{LocatingSlice, Stream};
+use winnow::token::{none_of, one_of, take_till, take_while};
+
+use crate::ParseError;
+use crate::combinators::{Span, Trivia};
+
+type Input<'i> = LocatingSlice<&'i str>;
+
+/// The notation a grammar was written in. ABNF rule names ignore case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    Ebnf,
+    Abnf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grammar {
+    pub dialect: Dialect,
+    /// In order of definition; the first one is the start rule by convention.
+    pub rules: Vec<Rule>,
+}
+
+impl Grammar {
+    pub fn rule(&self, name: &str) -> Option<&Rule> {
+        self.rules
+            .iter()
+            .find(|rule| self.same_name(&rule.name, name))
+    }
+
+    fn same_name(&self, a: &str, b: &str) -> bool {
+        match self.dialect {
+            Dialect::Ebnf => a == b,
+            Dialect::Abnf => a.eq_ignore_ascii_case(b),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub name: String,
+    pub expr: Expr,
+    /// Where the name is defined, for pointing at the rule in messages.
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    /// Terminal text. ABNF's quoted strings match ASCII letters in either
+    /// case unless written `%s"..."`.
+    Literal {
+        text: String,
+        caseless: bool,
+    },
+    /// One character in an inclusive range, ABNF `%x30-39` or EBNF
+    /// `"0" .. "9"`.
+    Range(char, char),
+    /// A reference to the rule with this name.
+    Rule(String),
+    Sequence(Vec<Expr>),
+    /// Alternatives in the order written, which is the order a PEG tries
+    /// them in.
+    Choice(Vec<Expr>),
+    /// `[e]` is `0..=1`, `{e}` and `e*` are `0..`, and ABNF `2*3e` is `2..=3`.
+    Repeat {
+        expr: Box<Expr>,
+        min: usize,
+        max: Option<usize>,
+    },
+    /// The PEG lookahead `&e`, which matches where `e` does without
+    /// consuming anything.
+    And(Box<Expr>),
+    /// The PEG lookahead `!e`.
+    Not(Box<Expr>),
+    /// ABNF `<prose>`: a description in words that cannot be matched.
+    Prose(String),
+}
+
+impl Expr {
+    pub fn literal(text: impl Into<String>) -> Self {
+        Expr::Literal {
+            text: text.into(),
+            caseless: false,
+        }
+    }
+
+    pub fn rule(name: impl Into<String>) -> Self {
+        Expr::Rule(name.into())
+    }
+
+    pub fn repeat(self, min: usize, max: Option<usize>) -> Self {
+        Expr::Repeat {
+            expr: Box::new(self),
+            min,
+            max,
+        }
+    }
+
+    pub fn optional(self) -> Self {
+        self.repeat(0, Some(1))
+    }
+
+    /// A sequence, or the only item itself.
+    fn sequence(mut items: Vec<Expr>) -> Self {
+        if items.len() == 1 {
+            items.remove(0)
+        } else {
+            Expr::Sequence(items)
+        }
+    }
+
+    fn alternatives(self) -> Vec<Expr> {
+        match self {
+            Expr::Choice(alternatives) => alternatives,
+            expr => vec![expr],
+        }
+    }
+
+    fn choice(mut alternatives: Vec<Expr>) -> Self {
+        if alternatives.len() == 1 {
+            alternatives.remove(0)
+        } else {
+            Expr::Choice(alternatives)
+        }
+    }
+}
+
+/// EBNF comments: `(* ISO *)` and `// to the end of the line`.
+const TRIVIA: Trivia = Trivia::new()
+    .line_comments(&["//"])
+    .block_comments("(*", "*)");
+
+/// Parses EBNF in the common ISO and W3C styles:
+///
+/// ```text
+/// (* a comment *)
+/// list   = "[" [ item { "," item } ] "]" ;
+/// item   ::= digit+ | name ;
+/// digit  = "0" .. "9" ;
+/// name   = letter (letter | digit)* ;
+/// letter = !digit ("a" .. "z") ;
+/// ```
+///
+/// Rules are defined with `=`, `::=` or `:=` and may end in `;`. Items of a
+/// sequence are separated by white space or `,`, and alternatives by `|` or
+/// the PEG-style `/`. Besides `[optional]`, `{repeated}` and `(grouped)`,
+/// items take the suffixes `?`, `*` and `+`, and the lookahead prefixes `&`
+/// and `!`.
+pub fn parse_ebnf(input: &str) -> Result<Grammar, ParseError> {
+    let rules = delimited(TRIVIA, repeat(0.., ebnf_rule), end)
+        .parse(LocatingSlice::new(input))
+        .map_err(ParseError::from)?;
+    build(Dialect::Ebnf, rules)
+}
+
+/// Parses ABNF as specified by RFC 5234, with RFC 7405's `%s` and `%i`
+/// string prefixes. The core rules such as `ALPHA` and `DIGIT` are left
+/// undefined here; [`crate::peg::Interpreter`] supplies them.
+pub fn parse_abnf(input: &str) -> Result<Grammar, ParseError> {
+    let rules: Vec<Option<Definition>> = terminated(
+        repeat(
+            0..,
+            alt((abnf_rule.map(Some), (space0, c_nl).map(|_| None))),
+        ),
+        (space0, end),
+    )
+    .parse(LocatingSlice::new(input))
+    .map_err(ParseError::from)?;
+    build(Dialect::Abnf, rules.into_iter().flatten().collect())
+}
+
+/// A rule definition; `incremental` for ABNF's `=/`, which adds
+/// alternatives to a rule defined earlier.
+struct Definition {
+    name: String,
+    span: Span,
+    incremental: bool,
+    expr: Expr,
+}
+
+fn build(dialect: Dialect, definitions: Vec<Definition>) -> Result<Grammar, ParseError> {
+    let mut grammar = Grammar {
+        dialect,
+        rules: Vec::new(),
+    };
+    for definition in definitions {
+        let existing = grammar
+            .rules
+            .iter()
+            .position(|rule| grammar.same_name(&rule.name, &definition.name));
+        match (existing, definition.incremental) {
+            (None, false) => grammar.rules.push(Rule {
+                name: definition.name,
+                expr: definition.expr,
+                span: definition.span,
+            }),
+            (Some(i), true) => {
+                let rule = &mut grammar.rules[i];
+                let previous = std::mem::replace(&mut rule.expr, Expr::Choice(Vec::new()));
+                rule.expr = Expr::Choice(
+                    [previous.alternatives(), definition.expr.alternatives()].concat(),
+                );
+            }
+            (Some(_), false) => {
+                return Err(ParseError::new(
+                    definition.span.start,
+                    format!("duplicate rule `{}`", definition.name),
+                ));
+            }
+            (None, true) => {
+                return Err(ParseError::new(
+                    definition.span.start,
+                    format!("`=/` extends undefined rule `{}`", definition.name),
+                ));
+            }
+        }
+    }
+    Ok(grammar)
+}
+
+fn end(input: &mut Input<'_>) -> ModalResult<()> {
+    eof.void()
+        .context(StrContext::Label("grammar"))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "rule definition",
+        )))
+        .parse_next(input)
+}
+
+fn name(input: &mut Input<'_>) -> ModalResult<String> {
+    (
+        one_of(|c: char| c.is_ascii_alphabetic()),
+        take_while(0.., |c: char| {
+            c.is_ascii_alphanumeric() || c == '_' || c == '-'
+        }),
+    )
+        .take()
+        .map(str::to_string)
+        .parse_next(input)
+}
+
+fn lex<'i, O>(
+    parser: impl Parser<Input<'i>, O, ErrMode<ContextError>>,
+) -> impl Parser<Input<'i>, O, ErrMode<ContextError>> {
+    terminated(parser, TRIVIA)
+}
+
+fn defined_as(input: &mut Input<'_>) -> ModalResult<()> {
+    alt(("::=", ":=", "=")).void().parse_next(input)
+}
+
+fn ebnf_rule(input: &mut Input<'_>) -> ModalResult<Definition> {
+    let (name, span) = lex(name.with_span()).parse_next(input)?;
+    lex(defined_as).parse_next(input)?;
+    let expr = cut_err(terminated(choice, opt(lex(';'))))
+        .context(StrContext::Label("rule"))
+        .parse_next(input)?;
+    Ok(Definition {
+        name,
+        span,
+        incremental: false,
+        expr,
+    })
+}
+
+fn choice(input: &mut Input<'_>) -> ModalResult<Expr> {
+    let first = sequence.parse_next(input)?;
+    let rest: Vec<Expr> =
+        repeat(0.., preceded(lex(one_of(['|', '/'])), cut_err(sequence))).parse_next(input)?;
+    Ok(Expr::choice([vec![first], rest].concat()))
+}
+
+fn sequence(input: &mut Input<'_>) -> ModalResult<Expr> {
+    let first = postfix.parse_next(input)?;
+    let rest: Vec<Expr> =
+        repeat(0.., alt((preceded(lex(','), cut_err(postfix)), postfix))).parse_next(input)?;
+    Ok(Expr::sequence([vec![first], rest].concat()))
+}
+
+fn postfix(input: &mut Input<'_>) -> ModalResult<Expr> {
+    let expr = prefix.parse_next(input)?;
+    repeat(0.., lex(one_of(['?', '*', '+'])))
+        .fold(
+            move || expr.clone(),
+            |expr, suffix| match suffix {
+                '?' => expr.optional(),
+                '*' => expr.repeat(0, None),
+                _ => expr.repeat(1, None),
+            },
+        )
+        .parse_next(input)
+}
+
+fn prefix(input: &mut Input<'_>) -> ModalResult<Expr> {
+    alt((
+        preceded(lex('&'), cut_err(prefix)).map(|e| Expr::And(Box::new(e))),
+        preceded(lex('!'), cut_err(prefix)).map(|e| Expr::Not(Box::new(e))),
+        atom,
+    ))
+    .parse_next(input)
+}
+
+fn atom(input: &mut Input<'_>) -> ModalResult<Expr> {
+    let group = |open, close| {
+        delimited(
+            lex(open),
+            cut_err(choice),
+            cut_err(lex(close)).context(StrContext::Expected(StrContextValue::CharLiteral(close))),
+        )
+    };
+    // Only the last alternative adds what was expected, so it does not
+    // trail the errors of strings and groups that failed halfway.
+    alt((
+        terminal,
+        group('(', ')'),
+        group('[', ']').map(Expr::optional),
+        group('{', '}').map(|e| e.repeat(0, None)),
+        terminated(lex(name), not(defined_as))
+            .map(Expr::Rule)
+            .context(StrContext::Expected(StrContextValue::Description(
+                "expression",
+            ))),
+    ))
+    .parse_next(input)
+}
+
+/// A string, or a range between two single-character strings.
+fn terminal(input: &mut Input<'_>) -> ModalResult<Expr> {
+    let start = input.checkpoint();
+    let low = lex(string).parse_next(input)?;
+    if opt(lex("..")).parse_next(input)?.is_none() {
+        return Ok(Expr::literal(low));
+    }
+    let high = cut_err(lex(string)).parse_next(input)?;
+    match (single(&low), single(&high)) {
+        (Some(low), Some(high)) if low <= high => Ok(Expr::Range(low, high)),
+        _ => {
+            input.reset(&start);
+            cut_err(fail)
+                .context(StrContext::Label("character range"))
+                .context(StrContext::Expected(StrContextValue::Description(
+                    "two characters in order",
+                )))
+                .parse_next(input)
+        }
+    }
+}
+
+fn single(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+    chars.next().filter(|_| chars.next().is_none())
+}
+
+/// A single- or double-quoted string with backslash escapes.
+fn string(input: &mut Input<'_>) -> ModalResult<String> {
+    let quote = one_of(['"', '\'']).parse_next(input)?;
+    let escape = alt((
+        'n'.value('\n'),
+        'r'.value('\r'),
+        't'.value('\t'),
+        one_of(['\\', '"', '\'']),
+    ));
+    cut_err(terminated(
+        repeat(
+            0..,
+            alt((none_of([quote, '\\', '\n']), preceded('\\', escape))),
+        ),
+        quote,
+    ))
+    .context(StrContext::Label("string"))
+    .context(StrContext::Expected(StrContextValue::CharLiteral(quote)))
+    .parse_next(input)
+}
+
+fn abnf_rule(input: &mut Input<'_>) -> ModalResult<Definition> {
+    let (name, span) = name.with_span().parse_next(input)?;
+    let incremental = cut_err(preceded(c_wsp, alt(("=/".value(true), "=".value(false)))))
+        .context(StrContext::Label("rule"))
+        .context(StrContext::Expected(StrContextValue::CharLiteral('=')))
+        .parse_next(input)?;
+    let expr = cut_err(delimited(c_wsp, alternation, c_wsp))
+        .context(StrContext::Label("rule"))
+        .parse_next(input)?;
+    cut_err(alt((c_nl, eof.void())))
+        .context(StrContext::Label("rule"))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "end of line",
+        )))
+        .parse_next(input)?;
+    Ok(Definition {
+        name,
+        span,
+        incremental,
+        expr,
+    })
+}
+
+/// White space, which continues onto the next line only if that line is
+/// indented and so does not start a new rule.
+fn c_wsp(input: &mut Input<'_>) -> ModalResult<()> {
+    repeat(
+        0..,
+        alt((
+            one_of([' ', '\t']).void(),
+            (c_nl, one_of([' ', '\t'])).void(),
+        )),
+    )
+    .parse_next(input)
+}
+
+/// A line break, possibly after a `;` comment.
+fn c_nl(input: &mut Input<'_>) -> ModalResult<()> {
+    alt((
+        (';', till_line_ending, alt((line_ending.void(), eof.void()))).void(),
+        line_ending.void(),
+    ))
+    .parse_next(input)
+}
+
+fn alternation(input: &mut Input<'_>) -> ModalResult<Expr> {
+    let first = concatenation.parse_next(input)?;
+    let rest: Vec<Expr> =
+        repeat(0.., preceded((c_wsp, '/', c_wsp), cut_err(concatenation))).parse_next(input)?;
+    Ok(Expr::choice([vec![first], rest].concat()))
+}
+
+fn concatenation(input: &mut Input<'_>) -> ModalResult<Expr> {
+    let first = repetition.parse_next(input)?;
+    let rest: Vec<Expr> = repeat(0.., preceded(c_wsp, repetition)).parse_next(input)?;
+    Ok(Expr::sequence([vec![first], rest].concat()))
+}
+
+fn repetition(input: &mut Input<'_>) -> ModalResult<Expr> {
+    let count = || take_while(1.., |c: char| c.is_ascii_digit()).parse_to::<usize>();
+    let bounds = opt(alt((
+        (opt(count()), '*', opt(count())).map(|(min, _, max)| (min.unwrap_or(0), max)),
+        count().map(|n| (n, Some(n))),
+    )))
+    .parse_next(input)?;
+    let element = match bounds {
+        Some(_) => cut_err(element).parse_next(input)?,
+        None => element.parse_next(input)?,
+    };
+    Ok(match bounds {
+        Some((min, max)) => element.repeat(min, max),
+        None => element,
+    })
+}
+
+fn element(input: &mut Input<'_>) -> ModalResult<Expr> {
+    let group = |open, close| {
+        delimited(
+            (open, c_wsp),
+            cut_err(alternation),
+            cut_err((c_wsp, close))
+                .context(StrContext::Expected(StrContextValue::CharLiteral(close))),
+        )
+    };
+    alt((
+        group('(', ')'),
+        group('[', ']').map(Expr::optional),
+        char_val,
+        num_val,
+        prose_val,
+        name.map(Expr::Rule)
+            .context(StrContext::Expected(StrContextValue::Description(
+                "element",
+            ))),
+    ))
+    .parse_next(input)
+}
+
+/// A quoted string, case-insensitive unless prefixed with `%s`.
+fn char_val(input: &mut Input<'_>) -> ModalResult<Expr> {
+    let caseless = opt(alt(("%s".value(false), "%i".value(true))))
+        .parse_next(input)?
+        .unwrap_or(true);
+    let text = preceded(
+        '"',
+        cut_err(terminated(
+            take_while(0.., |c: char| c != '"' && (' '..='~').contains(&c)),
+            '"',
+        ))
+        .context(StrContext::Label("string"))
+        .context(StrContext::Expected(StrContextValue::CharLiteral('"'))),
+    )
+    .parse_next(input)?;
+    Ok(Expr::Literal {
+        text: text.to_string(),
+        caseless,
+    })
+}
+
+/// `%x41`, a range `%x30-39`, or a string of code points `%d13.10`.
+fn num_val(input: &mut Input<'_>) -> ModalResult<Expr> {
+    let radix = preceded(
+        '%',
+        one_of(['x', 'X', 'd', 'D', 'b', 'B']).map(|c: char| match c.to_ascii_lowercase() {
+            'x' => 16,
+            'd' => 10,
+            _ => 2,
+        }),
+    )
+    .parse_next(input)?;
+    let value = move |input: &mut Input<'_>| {
+        take_while(1.., move |c: char| c.is_digit(radix))
+            .try_map(move |digits| u32::from_str_radix(digits, radix))
+            .try_map(char::try_from)
+            .parse_next(input)
+    };
+    let mut value = cut_err(value)
+        .context(StrContext::Label("numeric value"))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "code point",
+        )));
+    let first = value.parse_next(input)?;
+    if opt('-').parse_next(input)?.is_some() {
+        let last = value.parse_next(input)?;
+        return Ok(Expr::Range(first, last));
+    }
+    let rest: Vec<char> = repeat(0.., preceded('.', value)).parse_next(input)?;
+    Ok(Expr::literal(
+        std::iter::once(first).chain(rest).collect::<String>(),
+    ))
+}
+
+fn prose_val(input: &mut Input<'_>) -> ModalResult<Expr> {
+    preceded(
+        '<',
+        cut_err(terminated(take_till(0.., ['>', '\n']), '>'))
+            .context(StrContext::Label("prose"))
+            .context(StrContext::Expected(StrContextValue::CharLiteral('>'))),
+    )
+    .map(|text: &str| Expr::Prose(text.to_string()))
+    .parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seq(items: impl IntoIterator<Item = Expr>) -> Expr {
+        Expr::Sequence(items.into_iter().collect())
+    }
+
+    fn choice(items: impl IntoIterator<Item = Expr>) -> Expr {
+        Expr::Choice(items.into_iter().collect())
+    }
+
+    #[test]
+    fn parse_ebnf_should_work() -> Result<(), ParseError> {
+        let grammar = parse_ebnf(
+            r#"
+            (* a comment *)
+            list   = "[" [ item { "," item } ] "]" ;
+            item   ::= digit+ | name ; // another
+            digit  = "0" .. "9"
+            name   = !digit letter, (letter / digit)*
+            letter = &'a' .. 'z' "\"" ? ;
+            "#,
+        )?;
+        let names: Vec<&str> = grammar.rules.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["list", "item", "digit", "name", "letter"]);
+        assert_eq!(grammar.rules[0].span, 41..45);
+
+        let item = seq([Expr::literal(","), Expr::rule("item")]).repeat(0, None);
+        let list = seq([
+            Expr::literal("["),
+            seq([Expr::rule("item"), item]).optional(),
+            Expr::literal("]"),
+        ]);
+        assert_eq!(grammar.rules[0].expr, list);
+        let item = choice([Expr::rule("digit").repeat(1, None), Expr::rule("name")]);
+        assert_eq!(grammar.rule("item").map(|r| &r.expr), Some(&item));
+        assert_eq!(grammar.rules[2].expr, Expr::Range('0', '9'));
+        let name = seq([
+            Expr::Not(Box::new(Expr::rule("digit"))),
+            Expr::rule("letter"),
+            choice([Expr::rule("letter"), Expr::rule("digit")]).repeat(0, None),
+        ]);
+        assert_eq!(grammar.rules[3].expr, name);
+        let letter = seq([
+            Expr::And(Box::new(Expr::Range('a', 'z'))),
+            Expr::literal("\"").optional(),
+        ]);
+        assert_eq!(grammar.rules[4].expr, letter);
+        assert!(grammar.rule("Item").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_abnf_should_work() -> Result<(), ParseError> {
+        let grammar = parse_abnf(
+            "; RFC 5234 style\r\n\
+             date   = year \"-\" 2DIGIT \"-\" 2DIGIT ; comment\r\n\
+             year   = 4DIGIT\r\n\
+             \r\n\
+             DIGIT  = %x30-39\r\n\
+             crlf   = %d13.10 / %s\"\\n\"\r\n\
+             crlf   =/ *1<a bare line feed>\r\n\
+             list   = \"[\" [ value\r\n  *( \",\" value ) ] \"]\"",
+        )?;
+        assert_eq!(grammar.rules.len(), 5);
+        let digits = |n| Expr::rule("DIGIT").repeat(n, Some(n));
+        let date = seq([
+            Expr::rule("year"),
+            Expr::Literal {
+                text: "-".into(),
+                caseless: true,
+            },
+            digits(2),
+            Expr::Literal {
+                text: "-".into(),
+                caseless: true,
+            },
+            digits(2),
+        ]);
+        assert_eq!(grammar.rules[0].expr, date);
+        assert_eq!(grammar.rule("YEAR").map(|r| &r.expr), Some(&digits(4)));
+        assert_eq!(
+            grammar.rule("digit").map(|r| &r.expr),
+            Some(&Expr::Range('0', '9'))
+        );
+        let crlf = choice([
+            Expr::literal("\r\n"),
+            Expr::literal("\\n"),
+            Expr::Prose("a bare line feed".into()).repeat(0, Some(1)),
+        ]);
+        assert_eq!(grammar.rule("crlf").map(|r| &r.expr), Some(&crlf));
+        let Some(Expr::Sequence(list)) = grammar.rule("list").map(|r| &r.expr) else {
+            panic!("expected a sequence");
+        };
+        assert_eq!(list.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn grammar_parsers_should_report_errors() {
+        let err = parse_ebnf("a = \"x\" ;\nb = ;").unwrap_err();
+        assert_eq!(err.offset(), 14);
+        assert_eq!(err.message(), "expected expression while parsing rule");
+        let err = parse_ebnf("a = 'x\nb = a").unwrap_err();
+        assert_eq!(err.message(), "expected ''' while parsing string");
+        let err = parse_ebnf("a = 'x' .. 'abc'").unwrap_err();
+        assert_eq!(err.offset(), 4);
+        assert_eq!(
+            err.message(),
+            "expected two characters in order while parsing character range"
+        );
+        let err = parse_ebnf("a = 'x'\na = 'y'").unwrap_err();
+        assert_eq!((err.offset(), err.message()), (8, "duplicate rule `a`"));
+
+        let err = parse_abnf("a = \"x\"\n  / ( b\nc = d").unwrap_err();
+        assert_eq!(err.offset(), 15);
+        assert_eq!(err.message(), "expected ')' while parsing rule");
+        let err = parse_abnf("a = %x110000").unwrap_err();
+        assert_eq!(err.offset(), 6);
+        let err = parse_abnf("a =/ b").unwrap_err();
+        assert_eq!(err.message(), "`=/` extends undefined rule `a`");
+        assert!(parse_abnf(" a = b").is_err());
+    }
+}
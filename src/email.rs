@@ -0,0 +1,385 @@
+use std::fmt;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::multispace1;
+use winnow::combinator::{alt, cut_err, opt, repeat, separated};
+use winnow::error::{ErrMode, StrContext, StrContextValue};
+use winnow::token::{any, take_while};
+
+use crate::ParseError;
+
+/// A mailbox such as `"Jane Doe" <jane@example.com> (work)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mailbox {
+    pub display_name: Option<String>,
+    pub address: EmailAddress,
+    /// Parenthesized comments, in order of appearance.
+    pub comments: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmailAddress {
+    /// Unquoted: `"a b"@x` has the local part `a b`.
+    pub local_part: String,
+    /// A host name, or a bracketed literal such as `[192.0.2.1]`.
+    pub domain: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// The RFC 5322 `mailbox` grammar, minus obsolete syntax.
+    #[default]
+    Strict,
+    /// Only what mail servers accept in practice: no comments, quoted local
+    /// parts or domain literals, a dotted host name, and the SMTP length
+    /// limits.
+    Pragmatic,
+}
+
+impl fmt::Display for EmailAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if is_dot_atom(&self.local_part) {
+            f.write_str(&self.local_part)?;
+        } else {
+            write_quoted(f, &self.local_part)?;
+        }
+        write!(f, "@{}", self.domain)
+    }
+}
+
+/// Formats as `name <address>`, dropping comments.
+impl fmt::Display for Mailbox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.display_name {
+            Some(name) => {
+                if name
+                    .split(' ')
+                    .all(|word| !word.is_empty() && word.chars().all(is_atext))
+                {
+                    f.write_str(name)?;
+                } else {
+                    write_quoted(f, name)?;
+                }
+                write!(f, " <{}>", self.address)
+            }
+            None => write!(f, "{}", self.address),
+        }
+    }
+}
+
+fn write_quoted(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in s.chars() {
+        if matches!(c, '"' | '\\') {
+            f.write_str("\\")?;
+        }
+        write!(f, "{c}")?;
+    }
+    f.write_str("\"")
+}
+
+pub fn parse_email(input: &str) -> Result<Mailbox, ParseError> {
+    parse_email_with(input, Mode::Strict)
+}
+
+pub fn parse_email_with(input: &str, mode: Mode) -> Result<Mailbox, ParseError> {
+    let parsed = mailbox.parse(input).map_err(ParseError::from)?;
+    if mode == Mode::Pragmatic {
+        check_pragmatic(&parsed)?;
+    }
+    Ok(parsed.mailbox)
+}
+
+/// The mailbox plus what the pragmatic checks need to know about how it
+/// was written.
+struct Parsed {
+    mailbox: Mailbox,
+    local_offset: usize,
+    domain_offset: usize,
+    quoted_local: bool,
+    comment_offset: Option<usize>,
+}
+
+fn check_pragmatic(parsed: &Parsed) -> Result<(), ParseError> {
+    let address = &parsed.mailbox.address;
+    if let Some(offset) = parsed.comment_offset {
+        return Err(ParseError::new(offset, "comments are not accepted"));
+    }
+    if parsed.quoted_local {
+        return Err(ParseError::new(
+            parsed.local_offset,
+            "quoted local parts are not accepted",
+        ));
+    }
+    if address.local_part.len() > 64 {
+        return Err(ParseError::new(
+            parsed.local_offset,
+            "the local part is longer than 64 bytes",
+        ));
+    }
+    let domain = &address.domain;
+    if domain.starts_with('[') {
+        return Err(ParseError::new(
+            parsed.domain_offset,
+            "domain literals are not accepted",
+        ));
+    }
+    if address.local_part.len() + 1 + domain.len() > 254 {
+        return Err(ParseError::new(
+            parsed.local_offset,
+            "the address is longer than 254 bytes",
+        ));
+    }
+    let labels: Vec<&str> = domain.split('.').collect();
+    if labels.len() < 2 {
+        return Err(ParseError::new(
+            parsed.domain_offset,
+            "the domain must contain a dot",
+        ));
+    }
+    let mut offset = parsed.domain_offset;
+    for label in &labels {
+        let valid = (1..=63).contains(&label.len())
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            && !label.starts_with('-')
+            && !label.ends_with('-');
+        if !valid {
+            return Err(ParseError::new(
+                offset,
+                format!("invalid domain label `{label}`"),
+            ));
+        }
+        offset += label.len() + 1;
+    }
+    if labels
+        .last()
+        .is_some_and(|tld| tld.chars().all(|c| c.is_ascii_digit()))
+    {
+        return Err(ParseError::new(
+            offset - labels.last().map_or(0, |l| l.len() + 1),
+            "the top-level domain cannot be numeric",
+        ));
+    }
+    Ok(())
+}
+
+fn mailbox(input: &mut &str) -> ModalResult<Parsed> {
+    let len = input.len();
+    let start = *input;
+    let mut comments = Vec::new();
+    // A phrase followed by `<` is a display name; otherwise rewind and read
+    // a bare address.
+    let display_name = match (opt(phrase), cfws, '<').parse_next(input) {
+        Ok((name, leading, _)) => {
+            let (name, in_name) = name.unwrap_or_default();
+            comments.extend(in_name);
+            comments.extend(leading);
+            Some(Some(name).filter(|n| !n.is_empty()))
+        }
+        Err(ErrMode::Backtrack(_)) => {
+            *input = start;
+            None
+        }
+        Err(e) => return Err(e),
+    };
+    comments.extend(cfws(input)?);
+    let local_offset = len - input.len();
+    let (local_part, quoted_local) = cut_err(alt((
+        dot_atom.map(|s: &str| (s.to_string(), false)),
+        quoted_string.map(|s| (s, true)),
+    )))
+    .context(StrContext::Label("local part"))
+    .parse_next(input)?;
+    cut_err('@')
+        .context(StrContext::Expected(StrContextValue::CharLiteral('@')))
+        .parse_next(input)?;
+    let domain_offset = len - input.len();
+    let domain = cut_err(alt((dot_atom, domain_literal)))
+        .context(StrContext::Label("domain"))
+        .parse_next(input)?;
+    comments.extend(cfws(input)?);
+    if display_name.is_some() {
+        cut_err('>')
+            .context(StrContext::Expected(StrContextValue::CharLiteral('>')))
+            .parse_next(input)?;
+        comments.extend(cfws(input)?);
+    }
+    Ok(Parsed {
+        mailbox: Mailbox {
+            display_name: display_name.flatten(),
+            address: EmailAddress {
+                local_part,
+                domain: domain.to_string(),
+            },
+            comments: comments.iter().map(|(_, text)| text.clone()).collect(),
+        },
+        local_offset,
+        domain_offset,
+        quoted_local,
+        comment_offset: comments.first().map(|(remaining, _)| len - remaining),
+    })
+}
+
+fn domain_literal<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    (
+        '[',
+        take_while(0.., |c: char| {
+            c.is_ascii_graphic() && !matches!(c, '[' | ']' | '\\')
+        }),
+        cut_err(']'),
+    )
+        .take()
+        .parse_next(input)
+}
+
+/// Words of a display name joined by single spaces, and the comments
+/// between them.
+fn phrase(input: &mut &str) -> ModalResult<(String, Vec<(usize, String)>)> {
+    let words: Vec<(Vec<(usize, String)>, String)> = repeat(
+        1..,
+        (
+            cfws,
+            alt((take_while(1.., is_atext).map(str::to_string), quoted_string)),
+        ),
+    )
+    .parse_next(input)?;
+    let mut comments = Vec::new();
+    let mut names = Vec::new();
+    for (in_between, word) in words {
+        comments.extend(in_between);
+        names.push(word);
+    }
+    Ok((names.join(" "), comments))
+}
+
+/// Folding white space and comments. Each comment comes with the input
+/// length remaining where it starts, so callers can turn it into an offset.
+fn cfws(input: &mut &str) -> ModalResult<Vec<(usize, String)>> {
+    let mut comments = Vec::new();
+    loop {
+        if opt(multispace1).parse_next(input)?.is_some() {
+            continue;
+        }
+        let remaining = input.len();
+        match opt(comment).parse_next(input)? {
+            Some(text) => comments.push((remaining, text)),
+            None => return Ok(comments),
+        }
+    }
+}
+
+/// A parenthesized comment; nested comments keep their parentheses.
+fn comment(input: &mut &str) -> ModalResult<String> {
+    '('.parse_next(input)?;
+    let mut text = String::new();
+    loop {
+        if input.starts_with('(') {
+            text.push('(');
+            text.push_str(&comment.parse_next(input)?);
+            text.push(')');
+            continue;
+        }
+        let c = cut_err(any)
+            .context(StrContext::Label("comment"))
+            .context(StrContext::Expected(StrContextValue::CharLiteral(')')))
+            .parse_next(input)?;
+        match c {
+            ')' => return Ok(text.trim().to_string()),
+            '\\' => text.push(cut_err(any).parse_next(input)?),
+            c => text.push(c),
+        }
+    }
+}
+
+fn quoted_string(input: &mut &str) -> ModalResult<String> {
+    '"'.parse_next(input)?;
+    let mut text = String::new();
+    loop {
+        let c = cut_err(any)
+            .context(StrContext::Label("quoted string"))
+            .context(StrContext::Expected(StrContextValue::CharLiteral('"')))
+            .parse_next(input)?;
+        match c {
+            '"' => return Ok(text),
+            '\\' => text.push(cut_err(any).parse_next(input)?),
+            // Folding white space inside quotes is unfolded.
+            '\r' | '\n' => {}
+            c => text.push(c),
+        }
+    }
+}
+
+fn dot_atom<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    separated::<_, _, (), _, _, _, _>(1.., take_while(1.., is_atext), '.')
+        .take()
+        .parse_next(input)
+}
+
+fn is_atext(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-/=?^_`{|}~".contains(c) || !c.is_ascii()
+}
+
+fn is_dot_atom(s: &str) -> bool {
+    !s.is_empty()
+        && s.split('.')
+            .all(|part| !part.is_empty() && part.chars().all(is_atext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_email_should_work() -> Result<(), ParseError> {
+        let mailbox = parse_email("jane.doe+tag@example.com")?;
+        assert_eq!(mailbox.display_name, None);
+        assert_eq!(mailbox.address.local_part, "jane.doe+tag");
+        assert_eq!(mailbox.address.domain, "example.com");
+
+        let mailbox =
+            parse_email(r#" "Doe, Jane" (HR (main)) <"jane \"jd\" doe"@[192.0.2.1]> (work) "#)?;
+        assert_eq!(mailbox.display_name.as_deref(), Some("Doe, Jane"));
+        assert_eq!(mailbox.address.local_part, r#"jane "jd" doe"#);
+        assert_eq!(mailbox.address.domain, "[192.0.2.1]");
+        assert_eq!(mailbox.comments, ["HR (main)", "work"]);
+        assert_eq!(
+            mailbox.to_string(),
+            r#""Doe, Jane" <"jane \"jd\" doe"@[192.0.2.1]>"#
+        );
+
+        let mailbox = parse_email("Jane Q Doe <jq@localhost>")?;
+        assert_eq!(mailbox.display_name.as_deref(), Some("Jane Q Doe"));
+        assert_eq!(mailbox.to_string(), "Jane Q Doe <jq@localhost>");
+        Ok(())
+    }
+
+    #[test]
+    fn parse_email_should_reject_invalid_addresses() {
+        assert_eq!(parse_email("jane..doe@x").unwrap_err().offset(), 4);
+        assert_eq!(parse_email("Jane <jane@x").unwrap_err().offset(), 12);
+        assert!(parse_email("jane@").is_err());
+        assert!(parse_email("@example.com").is_err());
+        assert!(parse_email("jane (unclosed@x").is_err());
+    }
+
+    #[test]
+    fn pragmatic_mode_should_follow_mail_servers() -> Result<(), ParseError> {
+        let pragmatic = |s| parse_email_with(s, Mode::Pragmatic);
+        assert_eq!(
+            pragmatic("Jane <jane@mail.example.com>")?.address.domain,
+            "mail.example.com"
+        );
+        assert_eq!(pragmatic("jane@localhost").unwrap_err().offset(), 5);
+        assert_eq!(pragmatic("\"j d\"@example.com").unwrap_err().offset(), 0);
+        assert_eq!(
+            pragmatic("jane@-bad.com").unwrap_err().message(),
+            "invalid domain label `-bad`"
+        );
+        assert_eq!(pragmatic("jane@example.com (x)").unwrap_err().offset(), 17);
+        assert!(pragmatic("jane@[192.0.2.1]").is_err());
+        assert!(pragmatic("jane@1.2.3.4").is_err());
+        assert!(pragmatic(&format!("{}@example.com", "a".repeat(65))).is_err());
+        Ok(())
+    }
+}
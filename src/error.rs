@@ -0,0 +1,169 @@
+use std::fmt;
+
+use winnow::error::{ContextError, StrContext, StrContextValue};
+
+/// A parse failure detached from the input it came from, so it can outlive the
+/// borrowed `&str` and travel through `anyhow` or across threads.
+///
+/// Grammars label what they were parsing with `StrContext::Label` and add
+/// what would have been accepted with `StrContext::Expected` right after it.
+/// Converting a winnow error turns the innermost label into a message like
+/// `expected ',' or ']' while parsing array`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    offset: usize,
+    message: String,
+}
+
+impl ParseError {
+    pub fn new(offset: usize, message: impl Into<String>) -> Self {
+        Self {
+            offset,
+            message: message.into(),
+        }
+    }
+
+    /// An error from a winnow failure at `offset`, for parsers that catch
+    /// errors themselves instead of letting `parse` convert them.
+    pub fn from_context(offset: usize, error: &ContextError) -> Self {
+        Self::new(offset, describe(error))
+    }
+
+    /// Byte offset into the original input where parsing stopped.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// 1-based line and column (counted in chars) of the error within `input`.
+    pub fn line_col(&self, input: &str) -> (usize, usize) {
+        let offset = self.offset.min(input.len());
+        let before = &input[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let column = before[line_start..].chars().count() + 1;
+        (line, column)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl<I> From<winnow::error::ParseError<I, ContextError>> for ParseError {
+    fn from(e: winnow::error::ParseError<I, ContextError>) -> Self {
+        Self::from_context(e.offset(), e.inner())
+    }
+}
+
+/// Renders the innermost labelled context of `error`. Expected values added
+/// by the parser that failed come before its label; without any, those that
+/// were attached along with the label are used.
+fn describe(error: &ContextError) -> String {
+    let contexts: Vec<&StrContext> = error.context().collect();
+    let expected_run = |contexts: &[&StrContext]| -> Vec<String> {
+        contexts
+            .iter()
+            .map_while(|c| match c {
+                StrContext::Expected(value) => Some(expected(value)),
+                _ => None,
+            })
+            .collect()
+    };
+    let label = contexts.iter().enumerate().find_map(|(i, c)| match c {
+        StrContext::Label(label) => Some((i, *label)),
+        _ => None,
+    });
+    let (label, expected) = match label {
+        Some((i, label)) => {
+            let mut values = expected_run(&contexts[..i]);
+            if values.is_empty() {
+                values = expected_run(&contexts[i + 1..]);
+            }
+            (Some(label), values)
+        }
+        None => (None, expected_run(&contexts)),
+    };
+
+    let mut message = match (label, expected.is_empty()) {
+        (None, true) => String::new(),
+        (Some(label), true) => format!("invalid {label}"),
+        (None, false) => format!("expected {}", one_of(&expected)),
+        (Some(label), false) => format!("expected {} while parsing {label}", one_of(&expected)),
+    };
+    if let Some(cause) = error.cause() {
+        if !message.is_empty() {
+            message += "; ";
+        }
+        message += &cause.to_string().replace('\n', "; ");
+    }
+    if message.is_empty() {
+        "unexpected input".to_string()
+    } else {
+        message
+    }
+}
+
+pub(crate) fn one_of(values: &[String]) -> String {
+    match values {
+        [rest @ .., last] if !rest.is_empty() => format!("{} or {last}", rest.join(", ")),
+        _ => values.concat(),
+    }
+}
+
+/// Like winnow's rendering, but with plain quotes, which read better when the
+/// message is embedded in other text.
+fn expected(value: &StrContextValue) -> String {
+    match value {
+        StrContextValue::CharLiteral('\n') => "newline".to_string(),
+        StrContextValue::CharLiteral(c) if c.is_ascii_control() => {
+            format!("`{}`", c.escape_debug())
+        }
+        StrContextValue::CharLiteral(c) => format!("'{c}'"),
+        StrContextValue::StringLiteral(s) => format!("\"{s}\""),
+        StrContextValue::Description(d) => d.to_string(),
+        _ => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_col_should_work() {
+        let input = "ab\ncd\nef";
+        assert_eq!(ParseError::new(0, "x").line_col(input), (1, 1));
+        assert_eq!(ParseError::new(4, "x").line_col(input), (2, 2));
+        assert_eq!(ParseError::new(100, "x").line_col(input), (3, 3));
+    }
+
+    #[test]
+    fn winnow_errors_should_read_as_expected_while_parsing() {
+        use winnow::combinator::{cut_err, preceded};
+        use winnow::{ModalResult, Parser};
+
+        fn document(input: &mut &str) -> ModalResult<char> {
+            preceded('a', cut_err(pair))
+                .context(StrContext::Label("document"))
+                .parse_next(input)
+        }
+        fn pair(input: &mut &str) -> ModalResult<char> {
+            preceded('b', cut_err('c').context(StrContext::Expected('c'.into())))
+                .context(StrContext::Label("pair"))
+                .context(StrContext::Expected(StrContextValue::Description("b")))
+                .parse_next(input)
+        }
+        let message = |input| ParseError::from(document.parse(input).unwrap_err()).message;
+        assert_eq!(message("ab"), "expected 'c' while parsing pair");
+        assert_eq!(message("a"), "expected b while parsing pair");
+        assert_eq!(message("x"), "invalid document");
+    }
+}
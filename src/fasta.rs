@@ -0,0 +1,313 @@
+use std::fmt;
+use std::io::{self, BufRead};
+
+use crate::ParseError;
+
+#[derive(Debug)]
+pub enum FastaError {
+    Io(io::Error),
+    Parse(ParseError),
+}
+
+impl fmt::Display for FastaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FastaError::Io(e) => write!(f, "{}", e),
+            FastaError::Parse(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for FastaError {}
+
+impl From<io::Error> for FastaError {
+    fn from(e: io::Error) -> Self {
+        FastaError::Io(e)
+    }
+}
+
+/// A FASTA record. Line breaks inside the sequence are removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FastaRecord {
+    /// The header up to the first white space.
+    pub id: String,
+    /// The rest of the header, if any.
+    pub description: Option<String>,
+    pub sequence: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FastqRecord {
+    pub id: String,
+    pub description: Option<String>,
+    pub sequence: String,
+    /// The quality line as written; see [`FastqRecord::qualities`].
+    pub quality: String,
+}
+
+impl FastqRecord {
+    /// Phred scores, decoded from the Sanger (offset 33) encoding.
+    pub fn qualities(&self) -> impl Iterator<Item = u8> + '_ {
+        self.quality.bytes().map(|b| b - b'!')
+    }
+
+    /// The chance each base call is wrong.
+    pub fn error_probabilities(&self) -> impl Iterator<Item = f64> + '_ {
+        self.qualities().map(|q| 10f64.powf(-f64::from(q) / 10.0))
+    }
+}
+
+/// Parses a whole FASTA document; see [`FastaReader`] for large files.
+pub fn parse_fasta(input: &str) -> Result<Vec<FastaRecord>, ParseError> {
+    FastaReader::new(input.as_bytes())
+        .collect::<Result<_, _>>()
+        .map_err(into_parse_error)
+}
+
+pub fn parse_fastq(input: &str) -> Result<Vec<FastqRecord>, ParseError> {
+    FastqReader::new(input.as_bytes())
+        .collect::<Result<_, _>>()
+        .map_err(into_parse_error)
+}
+
+fn into_parse_error(e: FastaError) -> ParseError {
+    match e {
+        FastaError::Parse(e) => e,
+        // Reading from a byte slice cannot fail.
+        FastaError::Io(e) => ParseError::new(0, e.to_string()),
+    }
+}
+
+/// Reads lines, keeping count of the bytes consumed so errors point into
+/// the whole stream.
+struct Lines<R> {
+    reader: R,
+    offset: usize,
+    line: String,
+}
+
+impl<R: BufRead> Lines<R> {
+    fn new(reader: R) -> Self {
+        Lines {
+            reader,
+            offset: 0,
+            line: String::new(),
+        }
+    }
+
+    /// The next line without its ending, and where it starts; `None` at
+    /// the end of the stream.
+    fn next(&mut self) -> Result<Option<(usize, &str)>, FastaError> {
+        self.line.clear();
+        let start = self.offset;
+        let read = self.reader.read_line(&mut self.line)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        self.offset += read;
+        Ok(Some((start, self.line.trim_end_matches(['\n', '\r']))))
+    }
+}
+
+fn split_header(header: &str) -> (String, Option<String>) {
+    match header.split_once(char::is_whitespace) {
+        Some((id, rest)) => (id.to_string(), Some(rest.trim().to_string())),
+        None => (header.to_string(), None),
+    }
+}
+
+fn error(offset: usize, message: impl Into<String>) -> FastaError {
+    FastaError::Parse(ParseError::new(offset, message))
+}
+
+/// Yields FASTA records one at a time, holding only the current record in
+/// memory. Blank lines and `;` comment lines are skipped.
+pub struct FastaReader<R> {
+    lines: Lines<R>,
+    /// The header that ended the previous record.
+    header: Option<String>,
+}
+
+impl<R: BufRead> FastaReader<R> {
+    pub fn new(reader: R) -> Self {
+        FastaReader {
+            lines: Lines::new(reader),
+            header: None,
+        }
+    }
+
+    fn read_record(&mut self) -> Result<Option<FastaRecord>, FastaError> {
+        let header = match self.header.take() {
+            Some(header) => header,
+            None => loop {
+                match self.lines.next()? {
+                    None => return Ok(None),
+                    Some((_, line)) if line.trim().is_empty() || line.starts_with(';') => {}
+                    Some((_, line)) if line.starts_with('>') => break line[1..].to_string(),
+                    Some((at, _)) => return Err(error(at, "expected a `>` header line")),
+                }
+            },
+        };
+        let mut sequence = String::new();
+        while let Some((at, line)) = self.lines.next()? {
+            if let Some(next) = line.strip_prefix('>') {
+                self.header = Some(next.to_string());
+                break;
+            }
+            if line.starts_with(';') {
+                continue;
+            }
+            let trimmed = line.trim();
+            let at = at + (line.len() - line.trim_start().len());
+            if let Some(i) =
+                trimmed.find(|c: char| !c.is_ascii_alphabetic() && c != '*' && c != '-')
+            {
+                return Err(error(at + i, "invalid sequence character"));
+            }
+            sequence.push_str(trimmed);
+        }
+        let (id, description) = split_header(&header);
+        Ok(Some(FastaRecord {
+            id,
+            description,
+            sequence,
+        }))
+    }
+}
+
+impl<R: BufRead> Iterator for FastaReader<R> {
+    type Item = Result<FastaRecord, FastaError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+/// Yields four-line FASTQ records one at a time.
+pub struct FastqReader<R> {
+    lines: Lines<R>,
+}
+
+impl<R: BufRead> FastqReader<R> {
+    pub fn new(reader: R) -> Self {
+        FastqReader {
+            lines: Lines::new(reader),
+        }
+    }
+
+    fn read_record(&mut self) -> Result<Option<FastqRecord>, FastaError> {
+        let (id, description) = loop {
+            match self.lines.next()? {
+                None => return Ok(None),
+                Some((_, line)) if line.trim().is_empty() => {}
+                Some((_, line)) if line.starts_with('@') => break split_header(&line[1..]),
+                Some((at, _)) => return Err(error(at, "expected a `@` header line")),
+            }
+        };
+        let end = self.lines.offset;
+        let (at, line) = self
+            .lines
+            .next()?
+            .ok_or_else(|| error(end, "expected a sequence line"))?;
+        if let Some(i) = line.find(|c: char| !c.is_ascii_alphabetic()) {
+            return Err(error(at + i, "invalid sequence character"));
+        }
+        let sequence = line.to_string();
+
+        let end = self.lines.offset;
+        match self.lines.next()? {
+            // The separator may repeat the id, but nothing else.
+            Some((at, line)) if line.starts_with('+') => {
+                let repeated = &line[1..];
+                if !repeated.is_empty() && split_header(repeated).0 != id {
+                    return Err(error(at + 1, "separator names a different record"));
+                }
+            }
+            Some((at, _)) => return Err(error(at, "expected a `+` separator line")),
+            None => return Err(error(end, "expected a `+` separator line")),
+        }
+
+        let end = self.lines.offset;
+        let (at, line) = self
+            .lines
+            .next()?
+            .ok_or_else(|| error(end, "expected a quality line"))?;
+        if let Some(i) = line.find(|c: char| !('!'..='~').contains(&c)) {
+            return Err(error(at + i, "invalid quality character"));
+        }
+        if line.len() != sequence.len() {
+            return Err(error(
+                at,
+                format!("{} quality scores for {} bases", line.len(), sequence.len()),
+            ));
+        }
+        Ok(Some(FastqRecord {
+            id,
+            description,
+            sequence,
+            quality: line.to_string(),
+        }))
+    }
+}
+
+impl<R: BufRead> Iterator for FastqReader<R> {
+    type Item = Result<FastqRecord, FastaError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_fasta_should_work() -> Result<(), ParseError> {
+        let records = parse_fasta(
+            ";old-style comment\n\
+             >seq1 Homo sapiens chr1\n\
+             ACGTACGT\n\
+             ACG\n\
+             \n\
+             >seq2\r\n\
+             MKV*\r\n\
+             >empty\n",
+        )?;
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].id, "seq1");
+        assert_eq!(records[0].description.as_deref(), Some("Homo sapiens chr1"));
+        assert_eq!(records[0].sequence, "ACGTACGTACG");
+        assert_eq!(records[1].description, None);
+        assert_eq!(records[1].sequence, "MKV*");
+        assert_eq!(records[2].sequence, "");
+        Ok(())
+    }
+
+    #[test]
+    fn fastq_reader_should_stream() -> Result<(), FastaError> {
+        let input = "@r1 lane=1\nGATT\n+\n!''*\n@r2\nAC\n+r2\nII\n";
+        let mut reader = FastqReader::new(io::BufReader::with_capacity(4, input.as_bytes()));
+        let first = reader.next().unwrap()?;
+        assert_eq!(first.id, "r1");
+        assert_eq!(first.qualities().collect::<Vec<_>>(), [0, 6, 6, 9]);
+        assert_eq!(first.error_probabilities().next(), Some(1.0));
+        let second = reader.next().unwrap()?;
+        assert_eq!(second.qualities().collect::<Vec<_>>(), [40, 40]);
+        assert!(reader.next().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn parsers_should_report_errors() {
+        let err = parse_fasta(">a\nAC GT\n").unwrap_err();
+        assert_eq!(err.offset(), 5);
+        assert!(parse_fasta("ACGT\n").is_err());
+        let err = parse_fastq("@a\nACGT\n+\nII\n").unwrap_err();
+        assert_eq!(err.offset(), 10);
+        assert!(err.message().contains("2 quality scores for 4 bases"));
+        assert!(parse_fastq("@a\nAC\n+b\nII\n").is_err());
+        assert!(parse_fastq("@a\nAC\n").is_err());
+        assert!(parse_fastq("@a\nAC\n+\nI \n").is_err());
+    }
+}
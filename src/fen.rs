@@ -0,0 +1,344 @@
+use std::fmt;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{digit1, space1};
+use winnow::combinator::{alt, cut_err, fail, opt, preceded, repeat, separated};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::one_of;
+
+use crate::ParseError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Color {
+    White,
+    Black,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    Pawn,
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+    King,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Piece {
+    pub color: Color,
+    pub role: Role,
+}
+
+/// A square; file 0 is `a` and rank 0 is `1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Square {
+    pub file: u8,
+    pub rank: u8,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Castling {
+    pub white_king_side: bool,
+    pub white_queen_side: bool,
+    pub black_king_side: bool,
+    pub black_queen_side: bool,
+}
+
+/// Everything a FEN string records about a game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Position {
+    /// Indexed by rank, then file.
+    pub board: [[Option<Piece>; 8]; 8],
+    pub side_to_move: Color,
+    pub castling: Castling,
+    /// The square a pawn skipped over on the last move.
+    pub en_passant: Option<Square>,
+    /// Half moves since the last capture or pawn move.
+    pub halfmove_clock: u32,
+    /// Starts at 1 and goes up after Black moves.
+    pub fullmove_number: u32,
+}
+
+pub const STARTING_POSITION: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+impl Piece {
+    /// The FEN letter, uppercase for White.
+    pub fn to_char(self) -> char {
+        let c = match self.role {
+            Role::Pawn => 'p',
+            Role::Knight => 'n',
+            Role::Bishop => 'b',
+            Role::Rook => 'r',
+            Role::Queen => 'q',
+            Role::King => 'k',
+        };
+        match self.color {
+            Color::White => c.to_ascii_uppercase(),
+            Color::Black => c,
+        }
+    }
+
+    pub fn from_char(c: char) -> Option<Self> {
+        let role = match c.to_ascii_lowercase() {
+            'p' => Role::Pawn,
+            'n' => Role::Knight,
+            'b' => Role::Bishop,
+            'r' => Role::Rook,
+            'q' => Role::Queen,
+            'k' => Role::King,
+            _ => return None,
+        };
+        let color = if c.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        Some(Piece { color, role })
+    }
+}
+
+impl Square {
+    pub fn new(file: u8, rank: u8) -> Option<Self> {
+        (file < 8 && rank < 8).then_some(Square { file, rank })
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.file) as char, self.rank + 1)
+    }
+}
+
+impl Position {
+    pub fn starting() -> Self {
+        parse_fen(STARTING_POSITION).expect("the starting position is valid")
+    }
+
+    pub fn piece_at(&self, square: Square) -> Option<Piece> {
+        self.board[square.rank as usize][square.file as usize]
+    }
+
+    /// Squares holding `piece`, from a1 towards h8.
+    pub fn find(&self, piece: Piece) -> impl Iterator<Item = Square> + '_ {
+        (0..8u8)
+            .flat_map(|rank| (0..8u8).map(move |file| Square { file, rank }))
+            .filter(move |&square| self.piece_at(square) == Some(piece))
+    }
+}
+
+/// Writes the position back as FEN.
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+            for square in self.board[rank] {
+                match square {
+                    Some(piece) => {
+                        if empty > 0 {
+                            write!(f, "{empty}")?;
+                            empty = 0;
+                        }
+                        write!(f, "{}", piece.to_char())?;
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                write!(f, "{empty}")?;
+            }
+            if rank > 0 {
+                f.write_str("/")?;
+            }
+        }
+        f.write_str(match self.side_to_move {
+            Color::White => " w ",
+            Color::Black => " b ",
+        })?;
+        let c = self.castling;
+        let rights: String = [
+            (c.white_king_side, 'K'),
+            (c.white_queen_side, 'Q'),
+            (c.black_king_side, 'k'),
+            (c.black_queen_side, 'q'),
+        ]
+        .iter()
+        .filter(|(allowed, _)| *allowed)
+        .map(|(_, c)| c)
+        .collect();
+        f.write_str(if rights.is_empty() { "-" } else { &rights })?;
+        match self.en_passant {
+            Some(square) => write!(f, " {square}")?,
+            None => f.write_str(" -")?,
+        }
+        write!(f, " {} {}", self.halfmove_clock, self.fullmove_number)
+    }
+}
+
+/// Parses a FEN string. The two move counters may be left off, as in EPD,
+/// and default to `0 1`. Each side must have exactly one king.
+pub fn parse_fen(input: &str) -> Result<Position, ParseError> {
+    let position = fen.parse(input.trim()).map_err(ParseError::from)?;
+    for color in [Color::White, Color::Black] {
+        let kings = position
+            .find(Piece {
+                color,
+                role: Role::King,
+            })
+            .count();
+        if kings != 1 {
+            return Err(ParseError::new(
+                0,
+                format!("{color:?} has {kings} kings, expected one"),
+            ));
+        }
+    }
+    Ok(position)
+}
+
+fn fen(input: &mut &str) -> ModalResult<Position> {
+    let ranks: Vec<[Option<Piece>; 8]> = separated(8, rank, cut_err('/'))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "eight ranks",
+        )))
+        .parse_next(input)?;
+    let mut board = [[None; 8]; 8];
+    // FEN lists rank 8 first.
+    for (i, rank) in ranks.into_iter().enumerate() {
+        board[7 - i] = rank;
+    }
+    let side_to_move = preceded(
+        space1,
+        cut_err(alt(('w'.value(Color::White), 'b'.value(Color::Black)))).context(
+            StrContext::Expected(StrContextValue::Description("`w` or `b`")),
+        ),
+    )
+    .parse_next(input)?;
+    let castling = preceded(space1, cut_err(castling))
+        .context(StrContext::Label("castling rights"))
+        .parse_next(input)?;
+    let en_passant_rank = match side_to_move {
+        Color::White => 5,
+        Color::Black => 2,
+    };
+    let en_passant = preceded(
+        space1,
+        cut_err(alt((
+            '-'.value(None),
+            square.verify(|s| s.rank == en_passant_rank).map(Some),
+        ))),
+    )
+    .context(StrContext::Label("en passant square"))
+    .parse_next(input)?;
+    let clocks = opt((
+        preceded(space1, cut_err(digit1.parse_to())).context(StrContext::Label("halfmove clock")),
+        preceded(space1, cut_err(digit1.parse_to().verify(|&n: &u32| n > 0)))
+            .context(StrContext::Label("fullmove number")),
+    ))
+    .parse_next(input)?;
+    let (halfmove_clock, fullmove_number) = clocks.unwrap_or((0, 1));
+    Ok(Position {
+        board,
+        side_to_move,
+        castling,
+        en_passant,
+        halfmove_clock,
+        fullmove_number,
+    })
+}
+
+fn rank(input: &mut &str) -> ModalResult<[Option<Piece>; 8]> {
+    let items: Vec<Vec<Option<Piece>>> = repeat(
+        1..,
+        alt((
+            one_of('1'..='8').map(|d: char| vec![None; d as usize - '0' as usize]),
+            one_of(|c: char| Piece::from_char(c).is_some()).map(|c| vec![Piece::from_char(c)]),
+        )),
+    )
+    .parse_next(input)?;
+    let squares = items.concat();
+    match <[Option<Piece>; 8]>::try_from(squares) {
+        Ok(rank) => Ok(rank),
+        Err(_) => cut_err(fail)
+            .context(StrContext::Expected(StrContextValue::Description(
+                "a rank of eight squares",
+            )))
+            .parse_next(input),
+    }
+}
+
+fn castling(input: &mut &str) -> ModalResult<Castling> {
+    if opt('-').parse_next(input)?.is_some() {
+        return Ok(Castling::default());
+    }
+    let letters: String = repeat(1..=4, one_of(['K', 'Q', 'k', 'q'])).parse_next(input)?;
+    Ok(Castling {
+        white_king_side: letters.contains('K'),
+        white_queen_side: letters.contains('Q'),
+        black_king_side: letters.contains('k'),
+        black_queen_side: letters.contains('q'),
+    })
+}
+
+fn square(input: &mut &str) -> ModalResult<Square> {
+    (one_of('a'..='h'), one_of('1'..='8'))
+        .map(|(file, rank): (char, char)| Square {
+            file: file as u8 - b'a',
+            rank: rank as u8 - b'1',
+        })
+        .parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_fen_should_work() -> Result<(), ParseError> {
+        let start = Position::starting();
+        assert_eq!(start.side_to_move, Color::White);
+        assert!(start.castling.black_queen_side);
+        assert_eq!(
+            start.piece_at(Square::new(4, 0).unwrap()),
+            Some(Piece {
+                color: Color::White,
+                role: Role::King
+            })
+        );
+        assert_eq!(start.piece_at(Square::new(4, 4).unwrap()), None);
+
+        let after_e4 = parse_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1")?;
+        assert_eq!(after_e4.en_passant, Square::new(4, 2));
+        assert_eq!(after_e4.en_passant.unwrap().to_string(), "e3");
+
+        let epd = parse_fen("8/8/8/4k3/8/8/8/4K3 w - -")?;
+        assert_eq!(epd.castling, Castling::default());
+        assert_eq!((epd.halfmove_clock, epd.fullmove_number), (0, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn display_should_round_trip() -> Result<(), ParseError> {
+        for fen in [
+            STARTING_POSITION,
+            "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+            "8/5k2/8/8/8/8/1K6/8 b - - 99 120",
+        ] {
+            assert_eq!(parse_fen(fen)?.to_string(), fen);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn parse_fen_should_report_errors() {
+        let err = parse_fen("rnbqkbnr/ppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap_err();
+        assert_eq!(err.offset(), 16);
+        let err = parse_fen("8/8/8/4k3/8/8/8/4K3 w - e3 0 1").unwrap_err();
+        assert_eq!(err.offset(), 24);
+        assert!(parse_fen("8/8/8/8/8/8/8/4K3 w - - 0 1").is_err());
+        assert!(parse_fen("8/8/8/4k3/8/8/8/4K3 x - - 0 1").is_err());
+        assert!(parse_fen("8/8/8/4k3/8/8/8/4K3 w - - 0 0").is_err());
+        assert!(parse_fen("8/8/8/4k3/8/8/8 w - - 0 1").is_err());
+    }
+}
@@ -0,0 +1,57 @@
+//! Random values of the crate's parsed types, for fuzzers (`arbitrary`
+//! feature) and property tests (`proptest` feature).
+//!
+//! Generated values stay within what the matching format can write down,
+//! so formatting one and parsing it back gives the same value:
+//!
+//! - JSON strings hold no `"`, `\` or control characters, since
+//!   `parse_json` reads strings verbatim, and numbers are multiples of a
+//!   quarter in the `i32` range;
+//! - TOML floats are never NaN, which would not equal itself, and
+//!   date-times have whole seconds;
+//! - nginx logs have IPv4 addresses, whole-second UTC times, and paths,
+//!   referers and user agents of printable ASCII without `"`.
+//!
+//! Both generators build values structurally, so shrinking a failing case
+//! removes elements and descends into nested values.
+
+use chrono::{DateTime, FixedOffset, Utc};
+
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+#[cfg(feature = "proptest")]
+pub mod strategy;
+
+/// How deep arrays, objects and tables nest.
+const DEPTH: u32 = 3;
+/// The most elements in a generated collection, and chars in a string.
+const LEN: usize = 8;
+/// The last second of year 9999, the latest time the formats can write.
+const MAX_TIMESTAMP: i64 = 253_402_300_799;
+/// Offsets are within a day either way, in minutes.
+const MAX_OFFSET: i32 = 24 * 60 - 1;
+
+fn json_char(c: char) -> bool {
+    c != '"' && c != '\\' && !c.is_control()
+}
+
+/// A char allowed in a quoted nginx field, where spaces are fine too.
+fn nginx_char(c: char) -> bool {
+    c == ' ' || c.is_ascii_graphic() && c != '"'
+}
+
+fn json_number(quarters: i32) -> f64 {
+    f64::from(quarters) / 4.0
+}
+
+fn utc(timestamp: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(timestamp.clamp(0, MAX_TIMESTAMP), 0).unwrap_or_default()
+}
+
+fn with_offset(timestamp: i64, minutes: i32) -> DateTime<FixedOffset> {
+    let offset = FixedOffset::east_opt(minutes.clamp(-MAX_OFFSET, MAX_OFFSET) * 60)
+        .expect("offsets within a day are valid");
+    // Keeps the local time within the years `utc` allows, too.
+    let margin = i64::from(MAX_OFFSET) * 60;
+    utc(timestamp.clamp(margin, MAX_TIMESTAMP - margin)).with_timezone(&offset)
+}
@@ -0,0 +1,182 @@
+//! `arbitrary::Arbitrary` impls, so fuzz targets can take the crate's
+//! values as input.
+
+use std::net::{IpAddr, Ipv4Addr};
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use super::*;
+use crate::json::JsonValue;
+use crate::nginx::{HttpMethod, HttpVersion, NginxLog};
+use crate::toml::{TomlDatetime, TomlTable, TomlValue};
+
+/// Items from `item` while the data says to go on, at most [`LEN`].
+fn many<'a, T>(
+    u: &mut Unstructured<'a>,
+    mut item: impl FnMut(&mut Unstructured<'a>) -> Result<T>,
+) -> Result<Vec<T>> {
+    let mut items = Vec::new();
+    while items.len() < LEN && u.arbitrary()? {
+        items.push(item(u)?);
+    }
+    Ok(items)
+}
+
+/// A string of chars from the data, with those `allowed` rejects replaced
+/// by `_`.
+fn string(u: &mut Unstructured<'_>, allowed: fn(char) -> bool) -> Result<String> {
+    let chars = many(u, |u| {
+        let c: char = u.arbitrary()?;
+        Ok(if allowed(c) { c } else { '_' })
+    })?;
+    Ok(chars.into_iter().collect())
+}
+
+/// Like [`string`], but never empty.
+fn non_empty(u: &mut Unstructured<'_>, allowed: fn(char) -> bool) -> Result<String> {
+    let s = string(u, allowed)?;
+    Ok(if s.is_empty() { "_".to_string() } else { s })
+}
+
+fn json_value(u: &mut Unstructured<'_>, depth: u32) -> Result<JsonValue> {
+    let kinds = if depth == 0 { 4 } else { 6 };
+    Ok(match u.choose_index(kinds)? {
+        0 => JsonValue::Null,
+        1 => JsonValue::Bool(u.arbitrary()?),
+        2 => JsonValue::Number(json_number(u.arbitrary()?)),
+        3 => JsonValue::String(string(u, json_char)?),
+        4 => JsonValue::Array(many(u, |u| json_value(u, depth - 1))?),
+        _ => JsonValue::Object(
+            many(u, |u| {
+                Ok((string(u, json_char)?, json_value(u, depth - 1)?))
+            })?
+            .into_iter()
+            .collect(),
+        ),
+    })
+}
+
+fn toml_value(u: &mut Unstructured<'_>, depth: u32) -> Result<TomlValue> {
+    let kinds = if depth == 0 { 5 } else { 7 };
+    Ok(match u.choose_index(kinds)? {
+        0 => TomlValue::String(u.arbitrary()?),
+        1 => TomlValue::Integer(u.arbitrary()?),
+        2 => {
+            let f: f64 = u.arbitrary()?;
+            TomlValue::Float(if f.is_nan() { 0.0 } else { f })
+        }
+        3 => TomlValue::Boolean(u.arbitrary()?),
+        4 => TomlValue::Datetime(u.arbitrary()?),
+        5 => TomlValue::Array(many(u, |u| toml_value(u, depth - 1))?),
+        _ => TomlValue::Table(toml_table(u, depth - 1)?),
+    })
+}
+
+fn toml_table(u: &mut Unstructured<'_>, depth: u32) -> Result<TomlTable> {
+    Ok(many(u, |u| Ok((u.arbitrary()?, toml_value(u, depth)?)))?
+        .into_iter()
+        .collect())
+}
+
+impl<'a> Arbitrary<'a> for JsonValue {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        json_value(u, DEPTH)
+    }
+}
+
+impl<'a> Arbitrary<'a> for TomlValue {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        toml_value(u, DEPTH)
+    }
+}
+
+impl<'a> Arbitrary<'a> for TomlDatetime {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let at = with_offset(u.arbitrary()?, u.arbitrary()?);
+        Ok(match u.choose_index(4)? {
+            0 => TomlDatetime::Offset(at),
+            1 => TomlDatetime::Local(at.naive_local()),
+            2 => TomlDatetime::LocalDate(at.date_naive()),
+            _ => TomlDatetime::LocalTime(at.time()),
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for HttpMethod {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        use HttpMethod::*;
+        u.choose(&[Get, Post, Put, Delete, Head, Options, Connect, Trace, Patch])
+            .copied()
+    }
+}
+
+impl<'a> Arbitrary<'a> for HttpVersion {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        use HttpVersion::*;
+        u.choose(&[Http1_0, Http1_1, Http2_0, Http3_0]).copied()
+    }
+}
+
+impl<'a> Arbitrary<'a> for NginxLog {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let addr: [u8; 4] = u.arbitrary()?;
+        Ok(NginxLog {
+            addr: IpAddr::V4(Ipv4Addr::from(addr)),
+            datetime: utc(u.arbitrary()?),
+            method: u.arbitrary()?,
+            path: format!("/{}", string(u, |c| c != ' ' && nginx_char(c))?),
+            http_version: u.arbitrary()?,
+            status_code: u.int_in_range(100..=599)?,
+            size: u.arbitrary()?,
+            referer: non_empty(u, nginx_char)?,
+            user_agent: non_empty(u, nginx_char)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+    use crate::toml::{parse_toml, to_string};
+
+    /// Values built from `runs` buffers of random bytes.
+    fn values<T: for<'a> Arbitrary<'a>>(runs: usize) -> Vec<T> {
+        let mut rng = fastrand::Rng::with_seed(7);
+        (0..runs)
+            .filter_map(|_| {
+                let bytes: Vec<u8> = (0..rng.usize(..512)).map(|_| rng.u8(..)).collect();
+                T::arbitrary(&mut Unstructured::new(&bytes)).ok()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn arbitrary_json_should_round_trip() {
+        for value in values::<JsonValue>(500) {
+            let text = serde_json::to_string(&value).expect("values serialize");
+            assert_eq!(parse_json(&text).ok(), Some(value), "{text}");
+        }
+    }
+
+    #[test]
+    fn arbitrary_toml_should_round_trip() {
+        for value in values::<TomlValue>(500) {
+            let table = TomlTable::from([("key".to_string(), value)]);
+            let text = to_string(&table);
+            assert_eq!(parse_toml(&text).ok(), Some(table), "{text}");
+        }
+    }
+
+    #[test]
+    fn arbitrary_should_be_deterministic() {
+        let bytes = [0x5a; 64];
+        let log = |bytes| NginxLog::arbitrary(&mut Unstructured::new(bytes)).ok();
+        assert!(log(&bytes).is_some());
+        assert_eq!(log(&bytes), log(&bytes));
+        assert_eq!(
+            JsonValue::arbitrary(&mut Unstructured::new(&[])).ok(),
+            Some(JsonValue::Null)
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! proptest strategies, also reachable through `any::<T>()`.
+
+use std::net::{IpAddr, Ipv4Addr};
+
+use proptest::arbitrary::{Arbitrary, any};
+use proptest::collection::{btree_map, hash_map, vec};
+use proptest::prelude::*;
+use proptest::sample::select;
+
+use super::*;
+use crate::json::JsonValue;
+use crate::nginx::{HttpMethod, HttpVersion, NginxLog};
+use crate::toml::{TomlDatetime, TomlTable, TomlValue};
+
+/// Most nodes in a generated tree.
+const SIZE: u32 = 64;
+
+fn json_string() -> impl Strategy<Value = String> + Clone {
+    vec(
+        any::<char>().prop_filter("unquotable", |&c| json_char(c)),
+        0..LEN,
+    )
+    .prop_map(|chars| chars.into_iter().collect())
+}
+
+fn nginx_string(min: usize) -> impl Strategy<Value = String> {
+    vec(
+        select((' '..='~').filter(|&c| nginx_char(c)).collect::<Vec<_>>()),
+        min..LEN,
+    )
+    .prop_map(|chars| chars.into_iter().collect())
+}
+
+pub fn json_value() -> impl Strategy<Value = JsonValue> {
+    let leaf = prop_oneof![
+        Just(JsonValue::Null),
+        any::<bool>().prop_map(JsonValue::Bool),
+        any::<i32>().prop_map(|n| JsonValue::Number(json_number(n))),
+        json_string().prop_map(JsonValue::String),
+    ];
+    leaf.prop_recursive(DEPTH, SIZE, LEN as u32, |inner| {
+        prop_oneof![
+            vec(inner.clone(), 0..LEN).prop_map(JsonValue::Array),
+            hash_map(json_string(), inner, 0..LEN).prop_map(JsonValue::Object),
+        ]
+    })
+}
+
+pub fn toml_datetime() -> impl Strategy<Value = TomlDatetime> {
+    (0..=MAX_TIMESTAMP, -MAX_OFFSET..=MAX_OFFSET, 0..4).prop_map(|(timestamp, offset, kind)| {
+        let at = with_offset(timestamp, offset);
+        match kind {
+            0 => TomlDatetime::Offset(at),
+            1 => TomlDatetime::Local(at.naive_local()),
+            2 => TomlDatetime::LocalDate(at.date_naive()),
+            _ => TomlDatetime::LocalTime(at.time()),
+        }
+    })
+}
+
+pub fn toml_value() -> impl Strategy<Value = TomlValue> {
+    let leaf = prop_oneof![
+        any::<String>().prop_map(TomlValue::String),
+        any::<i64>().prop_map(TomlValue::Integer),
+        any::<f64>()
+            .prop_filter("NaN", |f| !f.is_nan())
+            .prop_map(TomlValue::Float),
+        any::<bool>().prop_map(TomlValue::Boolean),
+        toml_datetime().prop_map(TomlValue::Datetime),
+    ];
+    leaf.prop_recursive(DEPTH, SIZE, LEN as u32, |inner| {
+        prop_oneof![
+            vec(inner.clone(), 0..LEN).prop_map(TomlValue::Array),
+            btree_map(any::<String>(), inner, 0..LEN).prop_map(TomlValue::Table),
+        ]
+    })
+}
+
+pub fn toml_table() -> impl Strategy<Value = TomlTable> {
+    btree_map(any::<String>(), toml_value(), 0..LEN)
+}
+
+pub fn nginx_log() -> impl Strategy<Value = NginxLog> {
+    use HttpMethod::*;
+    use HttpVersion::*;
+    let methods = [Get, Post, Put, Delete, Head, Options, Connect, Trace, Patch];
+    let versions = [Http1_0, Http1_1, Http2_0, Http3_0];
+    (
+        (
+            any::<[u8; 4]>(),
+            0..=MAX_TIMESTAMP,
+            select(methods.to_vec()),
+        ),
+        (nginx_string(0).prop_map(|path| format!("/{}", path.replace(' ', "_")))),
+        (select(versions.to_vec()), 100..=599u16, any::<u64>()),
+        (nginx_string(1), nginx_string(1)),
+    )
+        .prop_map(
+            |(
+                (addr, timestamp, method),
+                path,
+                (http_version, status_code, size),
+                (referer, user_agent),
+            )| {
+                NginxLog {
+                    addr: IpAddr::V4(Ipv4Addr::from(addr)),
+                    datetime: utc(timestamp),
+                    method,
+                    path,
+                    http_version,
+                    status_code,
+                    size,
+                    referer,
+                    user_agent,
+                }
+            },
+        )
+}
+
+macro_rules! arbitrary_with {
+    ($($ty:ty => $strategy:ident),*) => {
+        $(impl Arbitrary for $ty {
+            type Parameters = ();
+            type Strategy = BoxedStrategy<$ty>;
+
+            fn arbitrary_with(_: ()) -> Self::Strategy {
+                $strategy().boxed()
+            }
+        })*
+    };
+}
+
+arbitrary_with!(
+    JsonValue => json_value,
+    TomlValue => toml_value,
+    TomlDatetime => toml_datetime,
+    NginxLog => nginx_log
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+    use crate::nginx::parse_nginx_log;
+    use crate::toml::{parse_toml, to_string};
+
+    /// `log` in nginx's `combined` format.
+    fn combined(log: &NginxLog) -> String {
+        let method = serde_json::to_value(log.method).expect("methods serialize");
+        let version = serde_json::to_value(log.http_version).expect("versions serialize");
+        format!(
+            r#"{} - - [{}] "{} {} {}" {} {} "{}" "{}""#,
+            log.addr,
+            log.datetime.format("%d/%b/%Y:%H:%M:%S %z"),
+            method.as_str().unwrap_or_default(),
+            log.path,
+            version.as_str().unwrap_or_default(),
+            log.status_code,
+            log.size,
+            log.referer,
+            log.user_agent,
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn json_values_should_round_trip(value in any::<JsonValue>()) {
+            let text = serde_json::to_string(&value).expect("values serialize");
+            prop_assert_eq!(parse_json(&text).ok(), Some(value), "{}", text);
+        }
+
+        #[test]
+        fn toml_tables_should_round_trip(table in toml_table()) {
+            let text = to_string(&table);
+            prop_assert_eq!(parse_toml(&text).ok(), Some(table), "{}", text);
+        }
+
+        #[test]
+        fn nginx_logs_should_round_trip(log in any::<NginxLog>()) {
+            let line = combined(&log);
+            prop_assert_eq!(parse_nginx_log(&line).ok(), Some(log), "{}", line);
+        }
+    }
+}
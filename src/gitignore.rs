@@ -0,0 +1,225 @@
+use crate::ParseError;
+use crate::glob::Glob;
+
+/// The patterns of one `.gitignore` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gitignore {
+    pub patterns: Vec<Pattern>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    /// The line as written, trailing white space removed.
+    pub source: String,
+    /// `!pattern`: re-includes what an earlier pattern excluded.
+    pub negated: bool,
+    /// A trailing `/`: only matches directories.
+    pub dir_only: bool,
+    /// A `/` other than the trailing one: matches relative to the file's
+    /// directory instead of at any depth.
+    pub anchored: bool,
+    glob: Glob,
+}
+
+impl Pattern {
+    /// Whether this pattern matches `path`, given relative to the directory
+    /// of its `.gitignore` with `/` separators.
+    pub fn matches(&self, path: &str, is_dir: bool) -> bool {
+        (is_dir || !self.dir_only) && self.glob.is_match(path)
+    }
+}
+
+impl Gitignore {
+    /// `Some(true)` when the last matching pattern ignores `path`,
+    /// `Some(false)` when it re-includes it, `None` when nothing matches.
+    /// Only `path` itself is checked, not its parent directories.
+    pub fn matched(&self, path: &str, is_dir: bool) -> Option<bool> {
+        self.patterns
+            .iter()
+            .rev()
+            .find(|pattern| pattern.matches(path, is_dir))
+            .map(|pattern| !pattern.negated)
+    }
+}
+
+/// `.gitignore` files from a directory tree, each applying below the
+/// directory it was found in. Deeper files take precedence.
+#[derive(Debug, Clone, Default)]
+pub struct Matcher {
+    /// `(directory, patterns)`, the directory relative to the root with no
+    /// trailing `/`, and empty for the root itself.
+    layers: Vec<(String, Gitignore)>,
+}
+
+impl Matcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the patterns of the `.gitignore` in `dir`.
+    pub fn add(&mut self, dir: &str, gitignore: Gitignore) {
+        let dir = dir.trim_matches('/').to_string();
+        self.layers.push((dir, gitignore));
+        // Deepest first, so the first layer with an opinion decides.
+        self.layers
+            .sort_by_key(|(dir, _)| std::cmp::Reverse(depth(dir)));
+    }
+
+    /// Whether `path`, relative to the root, is ignored. As in git, nothing
+    /// inside an ignored directory can be re-included.
+    pub fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        let path = path.trim_matches('/');
+        let mut parents = path.match_indices('/').map(|(i, _)| &path[..i]);
+        parents.any(|parent| self.decide(parent, true)) || self.decide(path, is_dir)
+    }
+
+    fn decide(&self, path: &str, is_dir: bool) -> bool {
+        self.layers
+            .iter()
+            .filter_map(|(dir, gitignore)| {
+                let relative = match dir.as_str() {
+                    "" => path,
+                    dir => path.strip_prefix(dir)?.strip_prefix('/')?,
+                };
+                gitignore.matched(relative, is_dir)
+            })
+            .next()
+            .unwrap_or(false)
+    }
+}
+
+fn depth(dir: &str) -> usize {
+    match dir {
+        "" => 0,
+        dir => dir.split('/').count(),
+    }
+}
+
+/// Parses the contents of a `.gitignore` file. Blank lines and `#`
+/// comments are skipped; `\#`, `\!` and `\ ` escape what would otherwise
+/// be special. A pattern that is not a valid glob, such as an unclosed
+/// `[`, is an error at the start of its line.
+pub fn parse_gitignore(input: &str) -> Result<Gitignore, ParseError> {
+    let mut patterns = Vec::new();
+    let mut offset = 0;
+    for line in input.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let source = trim_end(line.trim_end_matches(['\n', '\r']));
+        if source.is_empty() || source.starts_with('#') {
+            continue;
+        }
+        let (negated, rest) = match source.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, source),
+        };
+        let (dir_only, rest) = match rest.strip_suffix('/') {
+            Some(rest) if !rest.ends_with('\\') => (true, rest),
+            _ => (false, rest),
+        };
+        let anchored = rest.contains('/');
+        let rest = rest.strip_prefix('/').unwrap_or(rest);
+        let escaped = escape_braces(rest);
+        let glob = if anchored || escaped.starts_with("**/") {
+            Glob::new(&escaped)
+        } else {
+            Glob::new(&format!("**/{escaped}"))
+        }
+        .map_err(|e| ParseError::new(start, e.message()))?;
+        patterns.push(Pattern {
+            source: source.to_string(),
+            negated,
+            dir_only,
+            anchored,
+            glob,
+        });
+    }
+    Ok(Gitignore { patterns })
+}
+
+/// Git has no brace expansion, so braces are literal.
+fn escape_braces(pattern: &str) -> String {
+    let mut escaped = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                escaped.push(c);
+                escaped.extend(chars.next());
+            }
+            '{' => escaped.push_str("\\{"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Trailing spaces are dropped unless the last one is escaped.
+fn trim_end(line: &str) -> &str {
+    let trimmed = line.trim_end_matches(' ');
+    if trimmed.ends_with('\\') && trimmed.len() < line.len() {
+        &line[..trimmed.len() + 1]
+    } else {
+        trimmed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_gitignore_should_work() -> Result<(), ParseError> {
+        let gitignore = parse_gitignore(
+            "# build output\n/target\n*.log\n!keep.log\nbuild/\ndocs/**/*.tmp\n\\#notes\nsp\\ \n{a,b}\n",
+        )?;
+        let flags: Vec<_> = gitignore
+            .patterns
+            .iter()
+            .map(|p| (p.source.as_str(), p.negated, p.dir_only, p.anchored))
+            .collect();
+        assert_eq!(flags[0], ("/target", false, false, true));
+        assert_eq!(flags[2], ("!keep.log", true, false, false));
+        assert_eq!(flags[3], ("build/", false, true, false));
+        assert_eq!(flags[6], ("sp\\ ", false, false, false));
+
+        assert_eq!(gitignore.matched("target", true), Some(true));
+        assert_eq!(gitignore.matched("src/target", true), None);
+        assert_eq!(gitignore.matched("a/b/x.log", false), Some(true));
+        assert_eq!(gitignore.matched("keep.log", false), Some(false));
+        assert_eq!(gitignore.matched("build", false), None);
+        assert_eq!(gitignore.matched("src/build", true), Some(true));
+        assert_eq!(gitignore.matched("docs/x.tmp", false), Some(true));
+        assert_eq!(gitignore.matched("docs/a/b/x.tmp", false), Some(true));
+        assert_eq!(gitignore.matched("#notes", false), Some(true));
+        assert_eq!(gitignore.matched("sp ", false), Some(true));
+        assert_eq!(gitignore.matched("{a,b}", false), Some(true));
+        assert_eq!(gitignore.matched("a", false), None);
+        Ok(())
+    }
+
+    #[test]
+    fn matcher_should_layer_nested_files() -> Result<(), ParseError> {
+        let mut matcher = Matcher::new();
+        matcher.add("", parse_gitignore("*.log\nout/\n")?);
+        matcher.add("app", parse_gitignore("!debug.log\n/local\n")?);
+        matcher.add("app/out", parse_gitignore("!keep\n")?);
+        assert!(matcher.is_ignored("x.log", false));
+        assert!(matcher.is_ignored("app/x.log", false));
+        assert!(!matcher.is_ignored("app/debug.log", false));
+        assert!(matcher.is_ignored("debug.log", false));
+        assert!(matcher.is_ignored("app/local/a.rs", false));
+        assert!(!matcher.is_ignored("local/a.rs", false));
+        // Inside an ignored directory nothing comes back.
+        assert!(matcher.is_ignored("app/out/keep", false));
+        assert!(!matcher.is_ignored("src/main.rs", false));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_gitignore_should_report_bad_patterns() {
+        let err = parse_gitignore("ok\n[abc\n").unwrap_err();
+        assert_eq!(err.offset(), 3);
+        assert!(parse_gitignore("\n# only comments\n   \n").is_ok_and(|g| g.patterns.is_empty()));
+    }
+}
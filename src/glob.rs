@@ -0,0 +1,333 @@
+use std::fmt;
+use std::str::FromStr;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::combinator::{alt, cut_err, delimited, opt, preceded, repeat, separated};
+use winnow::error::{ContextError, ErrMode, StrContext, StrContextValue};
+use winnow::token::{any, none_of};
+
+use crate::ParseError;
+
+/// A compiled glob pattern.
+///
+/// `*` and `?` never match `/`. `**` matches across directories when it is
+/// a whole path component, so `src/**/*.rs` matches `src/lib.rs` as well as
+/// `src/bin/main.rs`. `{a,b}` expands to each alternative, and classes
+/// take `!` or `^` for negation. `\` escapes the next character.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    pattern: String,
+    /// One token list per brace expansion.
+    alternatives: Vec<Vec<Token>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Literal(char),
+    /// `?`
+    One,
+    /// `*`
+    Star,
+    /// `**/`: nothing, or any run of whole directories.
+    AnyDirs,
+    /// A trailing `**`: everything that is left.
+    AnyPath,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+/// Pattern syntax before braces are expanded.
+#[derive(Debug, Clone)]
+enum Piece {
+    Token(Token),
+    DoubleStar,
+    Braces(Vec<Vec<Piece>>),
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Result<Self, ParseError> {
+        let pieces = pieces(false).parse(pattern).map_err(ParseError::from)?;
+        let alternatives = expand(&pieces)
+            .into_iter()
+            .map(|pieces| finish(&pieces))
+            .collect();
+        Ok(Glob {
+            pattern: pattern.to_string(),
+            alternatives,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn is_match(&self, path: &str) -> bool {
+        let text: Vec<char> = path.chars().collect();
+        self.alternatives
+            .iter()
+            .any(|tokens| matches(tokens, &text))
+    }
+
+    /// The leading directories that contain no wildcard, which is where a
+    /// directory walk for this pattern has to start. Empty for patterns
+    /// relative to the current directory.
+    pub fn base(&self) -> &str {
+        let end = self
+            .pattern
+            .find(['*', '?', '[', '{', '\\'])
+            .unwrap_or(self.pattern.len());
+        match self.pattern[..end].rfind('/') {
+            Some(0) => "/",
+            Some(i) => &self.pattern[..i],
+            None => "",
+        }
+    }
+
+    /// How many components below `base` a match can have, or `None` when
+    /// `**` makes it unbounded.
+    pub fn max_depth(&self) -> Option<usize> {
+        let base = self.base().chars().filter(|c| *c == '/').count()
+            + usize::from(!self.base().is_empty() && self.base() != "/");
+        self.alternatives
+            .iter()
+            .map(|tokens| {
+                if tokens
+                    .iter()
+                    .any(|t| matches!(t, Token::AnyDirs | Token::AnyPath))
+                {
+                    return None;
+                }
+                let slashes = tokens.iter().filter(|t| **t == Token::Literal('/')).count();
+                Some(slashes + 1 - base.min(slashes + 1))
+            })
+            .try_fold(0, |deepest, depth| depth.map(|d| deepest.max(d)))
+    }
+}
+
+impl FromStr for Glob {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Glob::new(s)
+    }
+}
+
+impl fmt::Display for Glob {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.pattern)
+    }
+}
+
+/// Every string a pattern's braces expand to, e.g. `a{b,c}` to `ab` and `ac`.
+pub fn expand_braces(pattern: &str) -> Result<Vec<String>, ParseError> {
+    let pieces = pieces(false).parse(pattern).map_err(ParseError::from)?;
+    Ok(expand(&pieces)
+        .iter()
+        .map(|pieces| pieces.iter().map(piece_source).collect())
+        .collect())
+}
+
+fn piece_source(piece: &Piece) -> String {
+    match piece {
+        Piece::DoubleStar => "**".to_string(),
+        Piece::Token(Token::Literal(c)) if "*?[]{}\\,".contains(*c) => format!("\\{c}"),
+        Piece::Token(Token::Literal(c)) => c.to_string(),
+        Piece::Token(Token::One) => "?".to_string(),
+        Piece::Token(Token::Class { negated, ranges }) => {
+            let mut s = String::from(if *negated { "[!" } else { "[" });
+            for (lo, hi) in ranges {
+                s.push(*lo);
+                if lo != hi {
+                    s.push('-');
+                    s.push(*hi);
+                }
+            }
+            s.push(']');
+            s
+        }
+        Piece::Token(_) => "*".to_string(),
+        Piece::Braces(_) => unreachable!("braces are expanded first"),
+    }
+}
+
+fn pieces<'i>(in_braces: bool) -> impl Parser<&'i str, Vec<Piece>, ErrMode<ContextError>> {
+    move |input: &mut &'i str| repeat(0.., piece(in_braces)).parse_next(input)
+}
+
+fn piece<'i>(in_braces: bool) -> impl Parser<&'i str, Piece, ErrMode<ContextError>> {
+    move |input: &mut &'i str| {
+        alt((
+            braces.map(Piece::Braces),
+            class.map(Piece::Token),
+            "**".value(Piece::DoubleStar),
+            '*'.value(Piece::Token(Token::Star)),
+            '?'.value(Piece::Token(Token::One)),
+            preceded('\\', cut_err(any))
+                .context(StrContext::Label("escape"))
+                .map(|c| Piece::Token(Token::Literal(c))),
+            none_of(move |c| in_braces && (c == ',' || c == '}'))
+                .map(|c| Piece::Token(Token::Literal(c))),
+        ))
+        .parse_next(input)
+    }
+}
+
+fn braces(input: &mut &str) -> ModalResult<Vec<Vec<Piece>>> {
+    delimited(
+        '{',
+        cut_err(separated(1.., pieces(true), ',')),
+        cut_err('}').context(StrContext::Expected(StrContextValue::CharLiteral('}'))),
+    )
+    .parse_next(input)
+}
+
+fn class(input: &mut &str) -> ModalResult<Token> {
+    '['.parse_next(input)?;
+    let negated = opt(alt(('!', '^'))).parse_next(input)?.is_some();
+    // A `]` straight after the bracket is part of the class.
+    let first = opt(']').parse_next(input)?;
+    let rest: Vec<char> = cut_err(repeat(0.., none_of(']'))).parse_next(input)?;
+    cut_err(']')
+        .context(StrContext::Expected(StrContextValue::CharLiteral(']')))
+        .parse_next(input)?;
+    let chars: Vec<char> = first.into_iter().chain(rest).collect();
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if i + 2 < chars.len() && chars[i + 1] == '-' {
+            ranges.push((chars[i], chars[i + 2]));
+            i += 3;
+        } else {
+            ranges.push((chars[i], chars[i]));
+            i += 1;
+        }
+    }
+    Ok(Token::Class { negated, ranges })
+}
+
+/// The cross product of every brace group's alternatives.
+fn expand(pieces: &[Piece]) -> Vec<Vec<Piece>> {
+    let mut out = vec![Vec::new()];
+    for piece in pieces {
+        match piece {
+            Piece::Braces(alternatives) => {
+                let tails: Vec<Vec<Piece>> =
+                    alternatives.iter().flat_map(|alt| expand(alt)).collect();
+                out = out
+                    .iter()
+                    .flat_map(|head| {
+                        tails.iter().map(move |tail| {
+                            let mut joined = head.clone();
+                            joined.extend(tail.iter().cloned());
+                            joined
+                        })
+                    })
+                    .collect();
+            }
+            piece => out.iter_mut().for_each(|head| head.push(piece.clone())),
+        }
+    }
+    out
+}
+
+/// Decides what each `**` means now that its neighbours are known.
+fn finish(pieces: &[Piece]) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < pieces.len() {
+        match &pieces[i] {
+            Piece::DoubleStar => {
+                let starts_component = matches!(tokens.last(), None | Some(Token::Literal('/')));
+                match pieces.get(i + 1) {
+                    Some(Piece::Token(Token::Literal('/'))) if starts_component => {
+                        tokens.push(Token::AnyDirs);
+                        i += 1;
+                    }
+                    None if starts_component => tokens.push(Token::AnyPath),
+                    _ => tokens.push(Token::Star),
+                }
+            }
+            Piece::Token(token) => tokens.push(token.clone()),
+            Piece::Braces(_) => unreachable!("braces are expanded first"),
+        }
+        i += 1;
+    }
+    tokens
+}
+
+fn matches(tokens: &[Token], text: &[char]) -> bool {
+    let Some((token, rest)) = tokens.split_first() else {
+        return text.is_empty();
+    };
+    match token {
+        Token::Literal(c) => text.first() == Some(c) && matches(rest, &text[1..]),
+        Token::One => text.first().is_some_and(|c| *c != '/') && matches(rest, &text[1..]),
+        Token::Class { negated, ranges } => {
+            text.first().is_some_and(|c| {
+                *c != '/' && ranges.iter().any(|(lo, hi)| (lo..=hi).contains(&c)) != *negated
+            }) && matches(rest, &text[1..])
+        }
+        Token::Star => {
+            let end = text.iter().position(|c| *c == '/').unwrap_or(text.len());
+            (0..=end).any(|i| matches(rest, &text[i..]))
+        }
+        Token::AnyDirs => {
+            matches(rest, text)
+                || (0..text.len())
+                    .filter(|i| text[*i] == '/')
+                    .any(|i| matches(rest, &text[i + 1..]))
+        }
+        Token::AnyPath => (0..=text.len()).any(|i| matches(rest, &text[i..])),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_match_should_work() -> Result<(), ParseError> {
+        let glob = Glob::new("src/**/*.{rs,toml}")?;
+        assert!(glob.is_match("src/lib.rs"));
+        assert!(glob.is_match("src/bin/grammar-rs/main.rs"));
+        assert!(glob.is_match("src/Cargo.toml"));
+        assert!(!glob.is_match("src/lib.rsx"));
+        assert!(!glob.is_match("tests/lib.rs"));
+
+        let glob: Glob = "access.log.[0-9]".parse()?;
+        assert!(glob.is_match("access.log.1"));
+        assert!(!glob.is_match("access.log.x"));
+        assert!(Glob::new("[!a]?")?.is_match("bc"));
+        assert!(!Glob::new("*.log")?.is_match("old/a.log"));
+        assert!(Glob::new("a**b")?.is_match("axxb"));
+        assert!(!Glob::new("a**b")?.is_match("a/b"));
+        assert!(Glob::new("logs/**")?.is_match("logs/2024/01/a.log"));
+        assert!(Glob::new(r"\*[]]")?.is_match("*]"));
+        Ok(())
+    }
+
+    #[test]
+    fn base_and_depth_should_bound_the_walk() -> Result<(), ParseError> {
+        let glob = Glob::new("/var/log/nginx/access.log*")?;
+        assert_eq!(glob.base(), "/var/log/nginx");
+        assert_eq!(glob.max_depth(), Some(1));
+        let glob = Glob::new("logs/{a,b/c}/*.gz")?;
+        assert_eq!(glob.base(), "logs");
+        assert_eq!(glob.max_depth(), Some(3));
+        assert_eq!(Glob::new("*.log")?.base(), "");
+        assert_eq!(Glob::new("*.log")?.max_depth(), Some(1));
+        assert_eq!(Glob::new("a/**/b")?.max_depth(), None);
+        assert_eq!(expand_braces("x{a,b{1,2}}y")?, ["xay", "xb1y", "xb2y"]);
+        Ok(())
+    }
+
+    #[test]
+    fn glob_should_report_errors() {
+        assert_eq!(Glob::new("a{b,c").unwrap_err().offset(), 5);
+        assert!(Glob::new("[abc").is_err());
+        assert!(Glob::new("trailing\\").is_err());
+    }
+}
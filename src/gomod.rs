@@ -0,0 +1,351 @@
+use crate::ParseError;
+
+/// A `go.mod` file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GoMod {
+    pub module: String,
+    /// The `go` directive, e.g. `1.22`.
+    pub go: Option<String>,
+    pub toolchain: Option<String>,
+    pub requires: Vec<Require>,
+    pub replaces: Vec<Replace>,
+    pub excludes: Vec<Module>,
+    pub retracts: Vec<Retract>,
+    /// Directives this parser has no type for, such as `godebug`, with
+    /// their arguments; the go command ignores them too when it does not
+    /// know them.
+    pub other: Vec<(String, Vec<String>)>,
+}
+
+/// A module path, with a version where one is given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Module {
+    pub path: String,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Require {
+    pub path: String,
+    pub version: String,
+    /// Marked `// indirect`: needed only by other dependencies.
+    pub indirect: bool,
+}
+
+/// `old [version] => new [version]`. A new path without a version is a
+/// directory on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replace {
+    pub old: Module,
+    pub new: Module,
+}
+
+/// A retracted version or `[low, high]` range; both ends are the same for
+/// a single version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Retract {
+    pub low: String,
+    pub high: String,
+    /// The comment explaining why, if any.
+    pub rationale: Option<String>,
+}
+
+impl GoMod {
+    pub fn require(&self, path: &str) -> Option<&Require> {
+        self.requires.iter().find(|r| r.path == path)
+    }
+
+    /// Where `path` at `version` really comes from once `replace`s apply.
+    /// A replacement for one version beats one for all versions.
+    pub fn resolve(&self, path: &str, version: &str) -> Module {
+        let exact = self
+            .replaces
+            .iter()
+            .find(|r| r.old.path == path && r.old.version.as_deref() == Some(version));
+        let any = || {
+            self.replaces
+                .iter()
+                .find(|r| r.old.path == path && r.old.version.is_none())
+        };
+        match exact.or_else(any) {
+            Some(replace) => replace.new.clone(),
+            None => Module {
+                path: path.to_string(),
+                version: Some(version.to_string()),
+            },
+        }
+    }
+
+    pub fn is_excluded(&self, path: &str, version: &str) -> bool {
+        self.excludes
+            .iter()
+            .any(|m| m.path == path && m.version.as_deref() == Some(version))
+    }
+}
+
+/// Parses a `go.mod` file. Directives take one line each or, except for
+/// `module`, `go` and `toolchain`, a `verb ( ... )` block of them. `//`
+/// comments are allowed anywhere; one after a requirement may mark it
+/// `indirect`, and one after a `retract` is its rationale. The `module`
+/// directive is required.
+pub fn parse_gomod(input: &str) -> Result<GoMod, ParseError> {
+    let mut gomod = GoMod::default();
+    let mut module = None;
+    // The open block's verb and where it starts.
+    let mut block: Option<(String, usize)> = None;
+    let mut offset = 0;
+    for raw in input.split_inclusive('\n') {
+        let start = offset;
+        offset += raw.len();
+        let line = raw.trim_end_matches(['\n', '\r']);
+        let (tokens, comment) =
+            tokens(line).map_err(|(at, message)| ParseError::new(start + at, message))?;
+        let Some(&(first_at, _)) = tokens.first() else {
+            continue;
+        };
+        let error = |message: String| ParseError::new(start + first_at, message);
+        let words: Vec<&str> = tokens.iter().map(|(_, t)| t.as_str()).collect();
+        match (&block, words.as_slice()) {
+            (Some(_), [")"]) => block = None,
+            (Some((verb, _)), words) => {
+                directive(&mut gomod, verb, words, comment).map_err(error)?;
+            }
+            (None, [verb, "("]) => {
+                if matches!(*verb, "module" | "go" | "toolchain") {
+                    return Err(error(format!("`{verb}` cannot be a block")));
+                }
+                block = Some((verb.to_string(), start + first_at));
+            }
+            (None, ["module", path]) => {
+                if module.is_some() {
+                    return Err(error("repeated `module` directive".to_string()));
+                }
+                module = Some(path.to_string());
+            }
+            (None, ["module", ..]) => return Err(error("usage: module path".to_string())),
+            (None, [verb, args @ ..]) => {
+                directive(&mut gomod, verb, args, comment).map_err(error)?;
+            }
+            (None, []) => unreachable!("empty lines are skipped"),
+        }
+    }
+    if let Some((verb, at)) = block {
+        return Err(ParseError::new(at, format!("`{verb} (` is never closed")));
+    }
+    gomod.module =
+        module.ok_or_else(|| ParseError::new(input.len(), "missing `module` directive"))?;
+    Ok(gomod)
+}
+
+fn directive(
+    gomod: &mut GoMod,
+    verb: &str,
+    args: &[&str],
+    comment: Option<String>,
+) -> Result<(), String> {
+    let module = |path: &str, version: Option<&str>| Module {
+        path: path.to_string(),
+        version: version.map(String::from),
+    };
+    match (verb, args) {
+        ("go", [version]) => gomod.go = Some(version.to_string()),
+        ("toolchain", [name]) => gomod.toolchain = Some(name.to_string()),
+        ("require", [path, version]) => {
+            let indirect = comment
+                .as_deref()
+                .is_some_and(|c| c == "indirect" || c.starts_with("indirect;"));
+            gomod.requires.push(Require {
+                path: path.to_string(),
+                version: version.to_string(),
+                indirect,
+            });
+        }
+        ("exclude", [path, version]) => gomod.excludes.push(module(path, Some(*version))),
+        ("replace", args) => {
+            let usage = "usage: replace old [version] => new [version]";
+            let arrow = args.iter().position(|a| *a == "=>").ok_or(usage)?;
+            let side = |side: &[&str]| match side {
+                [path] => Some(module(path, None)),
+                [path, version] => Some(module(path, Some(*version))),
+                _ => None,
+            };
+            let (Some(old), Some(new)) = (side(&args[..arrow]), side(&args[arrow + 1..])) else {
+                return Err(usage.to_string());
+            };
+            gomod.replaces.push(Replace { old, new });
+        }
+        ("retract", [version]) => gomod.retracts.push(Retract {
+            low: version.to_string(),
+            high: version.to_string(),
+            rationale: comment,
+        }),
+        ("retract", ["[", low, ",", high, "]"]) => gomod.retracts.push(Retract {
+            low: low.to_string(),
+            high: high.to_string(),
+            rationale: comment,
+        }),
+        ("go" | "toolchain" | "require" | "exclude" | "retract", _) => {
+            return Err(format!("malformed `{verb}` directive"));
+        }
+        (verb, args) => gomod.other.push((
+            verb.to_string(),
+            args.iter().map(|a| a.to_string()).collect(),
+        )),
+    }
+    Ok(())
+}
+
+/// Tokens with their offsets, and the text of a trailing `//` comment.
+type Tokens = (Vec<(usize, String)>, Option<String>);
+
+/// Splits a line into tokens, unquoting strings.
+fn tokens(line: &str) -> Result<Tokens, (usize, &'static str)> {
+    let mut tokens = Vec::new();
+    let mut rest = line;
+    loop {
+        let trimmed = rest.trim_start();
+        let at = line.len() - trimmed.len();
+        rest = trimmed;
+        if rest.is_empty() {
+            return Ok((tokens, None));
+        }
+        if let Some(comment) = rest.strip_prefix("//") {
+            return Ok((tokens, Some(comment.trim().to_string())));
+        }
+        let (token, len) = if rest.starts_with("=>") {
+            ("=>".to_string(), 2)
+        } else if rest.starts_with(['(', ')', '[', ']', ',']) {
+            (rest[..1].to_string(), 1)
+        } else if let Some(raw) = rest.strip_prefix('`') {
+            let end = raw.find('`').ok_or((at, "unterminated raw string"))?;
+            (raw[..end].to_string(), end + 2)
+        } else if let Some(quoted) = rest.strip_prefix('"') {
+            let mut value = String::new();
+            let mut chars = quoted.char_indices();
+            let end = loop {
+                match chars.next() {
+                    None => return Err((at, "unterminated string")),
+                    Some((i, '"')) => break i,
+                    Some((_, '\\')) => value.extend(chars.next().map(|(_, c)| c)),
+                    Some((_, c)) => value.push(c),
+                }
+            };
+            (value, end + 2)
+        } else {
+            let end = rest
+                .char_indices()
+                .find(|&(i, c)| {
+                    c.is_whitespace()
+                        || "()[],\"`".contains(c)
+                        || rest[i..].starts_with("//")
+                        || rest[i..].starts_with("=>")
+                })
+                .map_or(rest.len(), |(i, _)| i);
+            (rest[..end].to_string(), end)
+        };
+        tokens.push((at, token));
+        rest = &rest[len..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GOMOD: &str = r#"// Service entry point.
+module example.com/service
+
+go 1.22
+toolchain go1.22.3
+
+require github.com/pkg/errors v0.9.1
+
+require (
+	golang.org/x/net v0.24.0
+	golang.org/x/text v0.14.0 // indirect
+	"example.com/quoted path" v1.0.0
+)
+
+replace golang.org/x/net v0.24.0 => golang.org/x/net v0.25.0
+replace github.com/pkg/errors => ../errors
+
+exclude (
+	golang.org/x/text v0.13.0
+)
+
+retract (
+	v1.0.1 // Published by mistake.
+	[v1.1.0, v1.1.5]
+)
+godebug default=go1.21
+"#;
+
+    #[test]
+    fn parse_gomod_should_work() -> Result<(), ParseError> {
+        let gomod = parse_gomod(GOMOD)?;
+        assert_eq!(gomod.module, "example.com/service");
+        assert_eq!(gomod.go.as_deref(), Some("1.22"));
+        assert_eq!(gomod.toolchain.as_deref(), Some("go1.22.3"));
+        assert_eq!(gomod.requires.len(), 4);
+        assert!(gomod.require("golang.org/x/text").unwrap().indirect);
+        assert!(!gomod.require("golang.org/x/net").unwrap().indirect);
+        assert_eq!(gomod.requires[3].path, "example.com/quoted path");
+        assert_eq!(
+            gomod.retracts[0],
+            Retract {
+                low: "v1.0.1".into(),
+                high: "v1.0.1".into(),
+                rationale: Some("Published by mistake.".into()),
+            }
+        );
+        assert_eq!(
+            (
+                gomod.retracts[1].low.as_str(),
+                gomod.retracts[1].high.as_str()
+            ),
+            ("v1.1.0", "v1.1.5")
+        );
+        assert_eq!(
+            gomod.other,
+            [("godebug".to_string(), vec!["default=go1.21".to_string()])]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn replaces_and_excludes_should_apply() -> Result<(), ParseError> {
+        let gomod = parse_gomod(GOMOD)?;
+        let net = gomod.resolve("golang.org/x/net", "v0.24.0");
+        assert_eq!(net.version.as_deref(), Some("v0.25.0"));
+        assert_eq!(
+            gomod
+                .resolve("golang.org/x/net", "v0.23.0")
+                .version
+                .as_deref(),
+            Some("v0.23.0")
+        );
+        let errors = gomod.resolve("github.com/pkg/errors", "v0.9.1");
+        assert_eq!(
+            errors,
+            Module {
+                path: "../errors".into(),
+                version: None
+            }
+        );
+        assert!(gomod.is_excluded("golang.org/x/text", "v0.13.0"));
+        assert!(!gomod.is_excluded("golang.org/x/text", "v0.14.0"));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_gomod_should_report_errors() {
+        let err = parse_gomod("module a\nrequire b\n").unwrap_err();
+        assert_eq!(err.offset(), 9);
+        assert_eq!(err.message(), "malformed `require` directive");
+        assert!(parse_gomod("go 1.22\n").is_err());
+        assert!(parse_gomod("module a\nrequire (\n  b v1\n").is_err());
+        assert!(parse_gomod("module a\nreplace b v1 c\n").is_err());
+        assert!(parse_gomod("module \"a\n").is_err());
+        assert!(parse_gomod("module a\nmodule b\n").is_err());
+    }
+}
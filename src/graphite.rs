@@ -0,0 +1,193 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ParseError;
+
+/// A line of the Graphite plaintext protocol.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Datapoint {
+    /// Dot-separated, e.g. `servers.web1.cpu.load`.
+    pub path: String,
+    /// `;name=value` tags after the path, as Graphite 1.1 accepts.
+    pub tags: Vec<(String, String)>,
+    pub value: f64,
+    /// Unix seconds; `-1` asks carbon to stamp the point on arrival.
+    pub timestamp: i64,
+}
+
+impl Datapoint {
+    pub fn segments(&self) -> impl Iterator<Item = &str> {
+        self.path.split('.')
+    }
+}
+
+impl FromStr for Datapoint {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        datapoint(s)
+    }
+}
+
+/// `path[;tag=value...] value timestamp`, without a newline.
+impl fmt::Display for Datapoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.path)?;
+        for (name, value) in &self.tags {
+            write!(f, ";{name}={value}")?;
+        }
+        write!(f, " {} {}", self.value, self.timestamp)
+    }
+}
+
+/// Parses plaintext protocol lines. Path segments may hold ASCII letters,
+/// digits and `_-:+@%~`, and none may be empty. Blank lines are skipped;
+/// errors point into `input`.
+pub fn parse_graphite(input: &str) -> Result<Vec<Datapoint>, ParseError> {
+    let mut points = Vec::new();
+    let mut offset = 0;
+    for raw in input.split_inclusive('\n') {
+        let start = offset;
+        offset += raw.len();
+        let line = raw.trim_end_matches(['\n', '\r']);
+        if line.trim().is_empty() {
+            continue;
+        }
+        points.push(datapoint(line).map_err(|e| ParseError::new(start + e.offset(), e.message()))?);
+    }
+    Ok(points)
+}
+
+fn datapoint(line: &str) -> Result<Datapoint, ParseError> {
+    let mut words = line.split([' ', '\t']).scan(0, |at, word| {
+        let start = *at;
+        *at += word.len() + 1;
+        Some((start, word))
+    });
+    let mut words = std::iter::from_fn(move || words.find(|(_, w)| !w.is_empty()));
+    let (at, series) = words
+        .next()
+        .ok_or_else(|| ParseError::new(0, "expected a metric path"))?;
+    let Some((value_at, value)) = words.next() else {
+        return Err(ParseError::new(line.len(), "expected a value"));
+    };
+    let Some((time_at, timestamp)) = words.next() else {
+        return Err(ParseError::new(line.len(), "expected a timestamp"));
+    };
+    if let Some((extra, _)) = words.next() {
+        return Err(ParseError::new(extra, "expected the end of the line"));
+    }
+
+    let mut parts = series.split(';');
+    let path = parts.next().unwrap_or_default();
+    validate_path(path).map_err(|i| ParseError::new(at + i, "invalid metric path"))?;
+    let mut tags = Vec::new();
+    let mut tag_at = at + path.len() + 1;
+    for tag in parts {
+        let valid = tag.split_once('=').filter(|(name, value)| {
+            !name.is_empty()
+                && !name.contains(['!', '^'])
+                && !value.is_empty()
+                && !value.starts_with('~')
+        });
+        let Some((name, value)) = valid else {
+            return Err(ParseError::new(tag_at, "expected `name=value`"));
+        };
+        tags.push((name.to_string(), value.to_string()));
+        tag_at += tag.len() + 1;
+    }
+
+    let value = value
+        .parse()
+        .map_err(|_| ParseError::new(value_at, "expected a number"))?;
+    // Carbon takes fractional seconds and truncates them.
+    let timestamp = timestamp
+        .parse::<i64>()
+        .ok()
+        .or_else(|| {
+            timestamp
+                .parse::<f64>()
+                .ok()
+                .filter(|t| t.is_finite())
+                .map(|t| t as i64)
+        })
+        .ok_or_else(|| ParseError::new(time_at, "expected a Unix timestamp"))?;
+    Ok(Datapoint {
+        path: path.to_string(),
+        tags,
+        value,
+        timestamp,
+    })
+}
+
+/// The offset of the first offending character, or of the empty segment.
+fn validate_path(path: &str) -> Result<(), usize> {
+    let mut at = 0;
+    for segment in path.split('.') {
+        if segment.is_empty() {
+            return Err(at);
+        }
+        if let Some(i) =
+            segment.find(|c: char| !c.is_ascii_alphanumeric() && !"_-:+@%~".contains(c))
+        {
+            return Err(at + i);
+        }
+        at += segment.len() + 1;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_graphite_should_work() -> Result<(), ParseError> {
+        let points = parse_graphite(
+            "servers.web-1.cpu.load 0.64 1700000000\n\nqueue.depth  12\t1700000001.75\r\nstats.hits -1e3 -1\n",
+        )?;
+        assert_eq!(points.len(), 3);
+        assert_eq!(
+            points[0].segments().collect::<Vec<_>>(),
+            ["servers", "web-1", "cpu", "load"]
+        );
+        assert_eq!(points[0].value, 0.64);
+        assert_eq!(points[1].timestamp, 1700000001);
+        assert_eq!(points[2].value, -1000.0);
+        assert_eq!(points[2].timestamp, -1);
+        Ok(())
+    }
+
+    #[test]
+    fn tagged_series_should_round_trip() -> Result<(), ParseError> {
+        let point: Datapoint = "disk.used;datacenter=dc1;server=web01 42.5 1700000000".parse()?;
+        assert_eq!(point.path, "disk.used");
+        assert_eq!(
+            point.tags,
+            [
+                ("datacenter".to_string(), "dc1".to_string()),
+                ("server".to_string(), "web01".to_string())
+            ]
+        );
+        assert_eq!(
+            point.to_string(),
+            "disk.used;datacenter=dc1;server=web01 42.5 1700000000"
+        );
+        assert_eq!(point.to_string().parse::<Datapoint>()?, point);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_graphite_should_validate() {
+        let err = parse_graphite("a.b 1 1\na..b 1 1\n").unwrap_err();
+        assert_eq!(err.offset(), 10);
+        assert_eq!(err.message(), "invalid metric path");
+        assert_eq!("a.b/c 1 1".parse::<Datapoint>().unwrap_err().offset(), 3);
+        assert!("a.b. 1 1".parse::<Datapoint>().is_err());
+        assert!("a;tag 1 1".parse::<Datapoint>().is_err());
+        assert!("a;t=~x 1 1".parse::<Datapoint>().is_err());
+        assert!("a x 1".parse::<Datapoint>().is_err());
+        assert!("a 1".parse::<Datapoint>().is_err());
+        assert!("a 1 1 1".parse::<Datapoint>().is_err());
+    }
+}
@@ -0,0 +1,711 @@
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{digit0, digit1};
+use winnow::combinator::{alt, cut_err, delimited, not, opt, preceded, repeat};
+use winnow::error::{ContextError, ErrMode, StrContext, StrContextValue};
+use winnow::token::{one_of, take_while};
+
+use crate::ParseError;
+pub use crate::combinators::Span;
+use crate::combinators::Trivia;
+use crate::lexer::{Lexer, Token, Tokens, kind, parse_tokens};
+
+/// The lexical tokens of the spec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Punct,
+    Spread,
+    Name,
+    Int,
+    Float,
+    String,
+    BlockString,
+    /// Commas and byte order marks, which are insignificant like white
+    /// space.
+    Ignored,
+}
+
+/// Node spans run from the start of their first token to the end of their
+/// last, leaving out the trivia that follows.
+type Input<'t, 's> = Tokens<'t, 's, Kind>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    pub definitions: Vec<Definition>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Definition {
+    Operation(OperationDefinition),
+    Fragment(FragmentDefinition),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperationDefinition {
+    pub kind: OperationKind,
+    pub name: Option<Name>,
+    pub variables: Vec<VariableDefinition>,
+    pub directives: Vec<Directive>,
+    pub selection_set: SelectionSet,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationKind {
+    Query,
+    Mutation,
+    Subscription,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FragmentDefinition {
+    pub name: Name,
+    pub type_condition: Name,
+    pub directives: Vec<Directive>,
+    pub selection_set: SelectionSet,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Name {
+    pub value: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariableDefinition {
+    pub name: Name,
+    pub ty: Type,
+    pub default: Option<Value>,
+    pub directives: Vec<Directive>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    Named(String),
+    List(Box<Type>),
+    NonNull(Box<Type>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectionSet {
+    pub selections: Vec<Selection>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selection {
+    Field(Field),
+    FragmentSpread(FragmentSpread),
+    InlineFragment(InlineFragment),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub alias: Option<Name>,
+    pub name: Name,
+    pub arguments: Vec<Argument>,
+    pub directives: Vec<Directive>,
+    pub selection_set: Option<SelectionSet>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Argument {
+    pub name: Name,
+    pub value: Value,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FragmentSpread {
+    pub name: Name,
+    pub directives: Vec<Directive>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlineFragment {
+    pub type_condition: Option<Name>,
+    pub directives: Vec<Directive>,
+    pub selection_set: SelectionSet,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Directive {
+    pub name: Name,
+    pub arguments: Vec<Argument>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Variable(String),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Boolean(bool),
+    Null,
+    Enum(String),
+    List(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+/// Parses an executable document: operations and fragments.
+pub fn parse_graphql(input: &str) -> Result<Document, ParseError> {
+    let mut tokens = lexer().tokenize(input)?;
+    tokens.retain(|token| token.kind != Kind::Ignored);
+    parse_tokens(
+        &tokens,
+        repeat(1.., definition).map(|definitions| Document { definitions }),
+    )
+}
+
+/// White space and `#` comments are skipped between tokens.
+fn lexer<'s>() -> Lexer<'s, Kind> {
+    Lexer::new(Trivia::HASH)
+        .rule(Kind::Ignored, |input: &mut &str| {
+            one_of([',', '\u{feff}']).parse_next(input)
+        })
+        .rule(Kind::Punct, |input: &mut &str| {
+            one_of([
+                '!', '$', '&', '(', ')', ':', '=', '@', '[', ']', '{', '|', '}',
+            ])
+            .parse_next(input)
+        })
+        .rule(Kind::Spread, "...")
+        .rule(Kind::Name, raw_name)
+        .rule(Kind::Int, (int, end_of_number))
+        .rule(Kind::Float, (float, end_of_number))
+        .rule(Kind::String, string)
+        .rule(Kind::BlockString, block_string)
+}
+
+fn raw_name<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    (
+        one_of(|c: char| c.is_ascii_alphabetic() || c == '_'),
+        take_while(0.., |c: char| c.is_ascii_alphanumeric() || c == '_'),
+    )
+        .take()
+        .parse_next(input)
+}
+
+fn int(input: &mut &str) -> ModalResult<()> {
+    (
+        opt('-'),
+        alt(("0".void(), (one_of('1'..='9'), digit0).void())),
+    )
+        .void()
+        .parse_next(input)
+}
+
+fn float(input: &mut &str) -> ModalResult<()> {
+    let mut exp = (one_of(['e', 'E']), opt(one_of(['+', '-'])), digit1).void();
+    int.parse_next(input)?;
+    if opt(('.', digit1)).parse_next(input)?.is_some() {
+        opt(exp).parse_next(input)?;
+        return Ok(());
+    }
+    exp.parse_next(input)
+}
+
+/// A number must not run straight into a name or another `.`.
+fn end_of_number(input: &mut &str) -> ModalResult<()> {
+    not(one_of(|c: char| {
+        c.is_ascii_alphanumeric() || c == '_' || c == '.'
+    }))
+    .parse_next(input)
+}
+
+fn punct<'t, 's: 't>(
+    c: char,
+) -> impl Parser<Input<'t, 's>, &'t Token<'s, Kind>, ErrMode<ContextError>> {
+    kind(Kind::Punct)
+        .verify(move |token: &Token<'s, Kind>| token.text.starts_with(c))
+        .context(StrContext::Expected(StrContextValue::CharLiteral(c)))
+}
+
+fn name(input: &mut Input<'_, '_>) -> ModalResult<Name> {
+    kind(Kind::Name)
+        .map(|token: &Token<'_, Kind>| Name {
+            value: token.text.to_string(),
+            span: token.span.clone(),
+        })
+        .context(StrContext::Expected(StrContextValue::Description("name")))
+        .parse_next(input)
+}
+
+/// A name that is exactly `word`.
+fn keyword<'t, 's: 't>(
+    word: &'static str,
+) -> impl Parser<Input<'t, 's>, &'t Token<'s, Kind>, ErrMode<ContextError>> {
+    kind(Kind::Name).verify(move |token: &Token<'s, Kind>| token.text == word)
+}
+
+fn definition(input: &mut Input<'_, '_>) -> ModalResult<Definition> {
+    alt((
+        fragment_definition.map(Definition::Fragment),
+        operation_definition.map(Definition::Operation),
+    ))
+    .context(StrContext::Label("definition"))
+    .parse_next(input)
+}
+
+fn operation_definition(input: &mut Input<'_, '_>) -> ModalResult<OperationDefinition> {
+    let full = (
+        alt((
+            keyword("query").value(OperationKind::Query),
+            keyword("mutation").value(OperationKind::Mutation),
+            keyword("subscription").value(OperationKind::Subscription),
+        )),
+        cut_err((
+            opt(name),
+            opt(variable_definitions).map(Option::unwrap_or_default),
+            directives,
+            selection_set,
+        )),
+    )
+        .map(|(kind, (name, variables, directives, selection_set))| {
+            (kind, name, variables, directives, selection_set)
+        });
+    // `{ ... }` alone is shorthand for an anonymous query.
+    let shorthand =
+        selection_set.map(|set| (OperationKind::Query, None, Vec::new(), Vec::new(), set));
+    alt((full, shorthand))
+        .with_span()
+        .map(
+            |((kind, name, variables, directives, selection_set), span)| OperationDefinition {
+                kind,
+                name,
+                variables,
+                directives,
+                selection_set,
+                span,
+            },
+        )
+        .parse_next(input)
+}
+
+fn fragment_definition(input: &mut Input<'_, '_>) -> ModalResult<FragmentDefinition> {
+    preceded(
+        keyword("fragment"),
+        cut_err((
+            name.verify(|n: &Name| n.value != "on"),
+            preceded(keyword("on"), name),
+            directives,
+            selection_set,
+        )),
+    )
+    .with_span()
+    .map(
+        |((name, type_condition, directives, selection_set), span)| FragmentDefinition {
+            name,
+            type_condition,
+            directives,
+            selection_set,
+            span,
+        },
+    )
+    .context(StrContext::Label("fragment definition"))
+    .parse_next(input)
+}
+
+fn variable_definitions(input: &mut Input<'_, '_>) -> ModalResult<Vec<VariableDefinition>> {
+    delimited(
+        punct('('),
+        cut_err(repeat(1.., variable_definition)),
+        cut_err(punct(')')),
+    )
+    .context(StrContext::Label("variable definitions"))
+    .parse_next(input)
+}
+
+fn variable_definition(input: &mut Input<'_, '_>) -> ModalResult<VariableDefinition> {
+    (
+        preceded(punct('$'), cut_err(name)),
+        cut_err(preceded(punct(':'), ty)),
+        opt(preceded(punct('='), cut_err(value(true)))),
+        directives,
+    )
+        .with_span()
+        .map(
+            |((name, ty, default, directives), span)| VariableDefinition {
+                name,
+                ty,
+                default,
+                directives,
+                span,
+            },
+        )
+        .parse_next(input)
+}
+
+fn ty(input: &mut Input<'_, '_>) -> ModalResult<Type> {
+    let base = alt((
+        delimited(punct('['), cut_err(ty), cut_err(punct(']'))).map(|t| Type::List(Box::new(t))),
+        name.map(|n| Type::Named(n.value)),
+    ))
+    .context(StrContext::Expected(StrContextValue::Description("type")))
+    .parse_next(input)?;
+    Ok(match opt(punct('!')).parse_next(input)? {
+        Some(_) => Type::NonNull(Box::new(base)),
+        None => base,
+    })
+}
+
+fn directives(input: &mut Input<'_, '_>) -> ModalResult<Vec<Directive>> {
+    repeat(
+        0..,
+        (preceded(punct('@'), cut_err(name)), arguments)
+            .with_span()
+            .map(|((name, arguments), span)| Directive {
+                name,
+                arguments,
+                span,
+            }),
+    )
+    .parse_next(input)
+}
+
+fn arguments(input: &mut Input<'_, '_>) -> ModalResult<Vec<Argument>> {
+    opt(delimited(
+        punct('('),
+        cut_err(repeat(1.., argument)),
+        cut_err(punct(')')),
+    ))
+    .map(Option::unwrap_or_default)
+    .context(StrContext::Label("arguments"))
+    .parse_next(input)
+}
+
+fn argument(input: &mut Input<'_, '_>) -> ModalResult<Argument> {
+    (name, cut_err(preceded(punct(':'), value(false))))
+        .with_span()
+        .map(|((name, value), span)| Argument { name, value, span })
+        .parse_next(input)
+}
+
+fn selection_set(input: &mut Input<'_, '_>) -> ModalResult<SelectionSet> {
+    delimited(
+        punct('{'),
+        cut_err(repeat(1.., selection)),
+        cut_err(punct('}')),
+    )
+    .with_span()
+    .map(|(selections, span)| SelectionSet { selections, span })
+    .context(StrContext::Label("selection set"))
+    .parse_next(input)
+}
+
+fn selection(input: &mut Input<'_, '_>) -> ModalResult<Selection> {
+    alt((field.map(Selection::Field), fragment_selection)).parse_next(input)
+}
+
+/// `...` followed by either a named spread or an inline fragment with an
+/// optional type condition.
+fn fragment_selection(input: &mut Input<'_, '_>) -> ModalResult<Selection> {
+    let spread = (name.verify(|n: &Name| n.value != "on"), directives).map(|(name, directives)| {
+        Selection::FragmentSpread(FragmentSpread {
+            name,
+            directives,
+            span: 0..0,
+        })
+    });
+    let inline = (
+        opt(preceded(keyword("on"), cut_err(name))),
+        directives,
+        selection_set,
+    )
+        .map(|(type_condition, directives, selection_set)| {
+            Selection::InlineFragment(InlineFragment {
+                type_condition,
+                directives,
+                selection_set,
+                span: 0..0,
+            })
+        });
+    let (mut selection, span) = preceded(kind(Kind::Spread), cut_err(alt((spread, inline))))
+        .with_span()
+        .parse_next(input)?;
+    match &mut selection {
+        Selection::FragmentSpread(spread) => spread.span = span,
+        Selection::InlineFragment(inline) => inline.span = span,
+        Selection::Field(_) => unreachable!(),
+    }
+    Ok(selection)
+}
+
+fn field(input: &mut Input<'_, '_>) -> ModalResult<Field> {
+    (
+        name,
+        opt(preceded(punct(':'), cut_err(name))),
+        arguments,
+        directives,
+        opt(selection_set),
+    )
+        .with_span()
+        .map(
+            |((first, second, arguments, directives, selection_set), span)| {
+                let (alias, name) = match second {
+                    Some(name) => (Some(first), name),
+                    None => (None, first),
+                };
+                Field {
+                    alias,
+                    name,
+                    arguments,
+                    directives,
+                    selection_set,
+                    span,
+                }
+            },
+        )
+        .parse_next(input)
+}
+
+/// A value; variables are not allowed in constant positions such as
+/// variable defaults.
+fn value<'t, 's: 't>(constant: bool) -> impl Parser<Input<'t, 's>, Value, ErrMode<ContextError>> {
+    move |input: &mut Input<'t, 's>| {
+        alt((
+            preceded(punct('$'), cut_err(name))
+                .verify(move |_: &Name| !constant)
+                .map(|n| Value::Variable(n.value)),
+            kind(Kind::Int)
+                .verify_map(|token: &Token<'_, Kind>| token.text.parse().ok().map(Value::Int)),
+            kind(Kind::Float)
+                .verify_map(|token: &Token<'_, Kind>| token.text.parse().ok().map(Value::Float)),
+            kind(Kind::String).verify_map(|token: &Token<'_, Kind>| {
+                string.parse(token.text).ok().map(Value::String)
+            }),
+            kind(Kind::BlockString).verify_map(|token: &Token<'_, Kind>| {
+                block_string.parse(token.text).ok().map(Value::String)
+            }),
+            kind(Kind::Name).map(|token: &Token<'_, Kind>| match token.text {
+                "true" => Value::Boolean(true),
+                "false" => Value::Boolean(false),
+                "null" => Value::Null,
+                n => Value::Enum(n.to_string()),
+            }),
+            delimited(
+                punct('['),
+                cut_err(repeat(0.., value(constant))),
+                cut_err(punct(']')),
+            )
+            .map(Value::List),
+            delimited(
+                punct('{'),
+                cut_err(repeat(
+                    0..,
+                    (
+                        kind(Kind::Name),
+                        cut_err(preceded(punct(':'), value(constant))),
+                    )
+                        .map(|(k, v): (&Token<'_, Kind>, Value)| (k.text.to_string(), v)),
+                )),
+                cut_err(punct('}')),
+            )
+            .map(Value::Object),
+        ))
+        .context(StrContext::Expected(StrContextValue::Description("value")))
+        .parse_next(input)
+    }
+}
+
+fn string(input: &mut &str) -> ModalResult<String> {
+    '"'.parse_next(input)?;
+    let mut out = String::new();
+    loop {
+        let c = cut_err(one_of(|c: char| c != '\n' && c != '\r'))
+            .context(StrContext::Label("string"))
+            .context(StrContext::Expected(StrContextValue::CharLiteral('"')))
+            .parse_next(input)?;
+        match c {
+            '"' => return Ok(out),
+            '\\' => {
+                let escaped = cut_err(alt((
+                    one_of(['"', '\\', '/']),
+                    'b'.value('\u{8}'),
+                    'f'.value('\u{c}'),
+                    'n'.value('\n'),
+                    'r'.value('\r'),
+                    't'.value('\t'),
+                    preceded('u', take_while(4, |c: char| c.is_ascii_hexdigit())).verify_map(
+                        |hex: &str| u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                    ),
+                )))
+                .context(StrContext::Label("escape sequence"))
+                .parse_next(input)?;
+                out.push(escaped);
+            }
+            c => out.push(c),
+        }
+    }
+}
+
+fn block_string(input: &mut &str) -> ModalResult<String> {
+    "\"\"\"".parse_next(input)?;
+    let mut raw = String::new();
+    loop {
+        if opt("\"\"\"").parse_next(input)?.is_some() {
+            return Ok(dedent(&raw));
+        }
+        if opt("\\\"\"\"").parse_next(input)?.is_some() {
+            raw.push_str("\"\"\"");
+            continue;
+        }
+        let c = cut_err(winnow::token::any)
+            .context(StrContext::Label("block string"))
+            .context(StrContext::Expected(StrContextValue::StringLiteral(
+                "\"\"\"",
+            )))
+            .parse_next(input)?;
+        raw.push(c);
+    }
+}
+
+/// The spec's `BlockStringValue`: removes the common indentation of all
+/// lines but the first, and leading and trailing blank lines.
+fn dedent(raw: &str) -> String {
+    let lines: Vec<&str> = raw
+        .split("\r\n")
+        .flat_map(|l| l.split(['\n', '\r']))
+        .collect();
+    let common = lines
+        .iter()
+        .skip(1)
+        .filter(|l| !l.trim_start_matches([' ', '\t']).is_empty())
+        .map(|l| l.len() - l.trim_start_matches([' ', '\t']).len())
+        .min()
+        .unwrap_or(0);
+    let mut lines: Vec<&str> = lines
+        .iter()
+        .enumerate()
+        .map(|(i, l)| {
+            if i == 0 {
+                l
+            } else {
+                l.get(common..).unwrap_or("")
+            }
+        })
+        .collect();
+    let blank = |l: &&str| l.trim_matches([' ', '\t']).is_empty();
+    while lines.first().is_some_and(blank) {
+        lines.remove(0);
+    }
+    while lines.last().is_some_and(blank) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_graphql_should_work() -> Result<(), ParseError> {
+        let input = r#"# comment
+query Hero($episode: Episode = JEDI, $ids: [ID!]!, $withFriends: Boolean!) @cached(ttl: 60) {
+  hero(episode: $episode, filter: {name: "R2", height: -1.5e2, tags: [a, b]}) {
+    name
+    ...HeroDetails
+    friends @include(if: $withFriends) { name }
+    ... on Droid { primaryFunction }
+    smallPic: profilePic(size: 64)
+  }
+}
+
+fragment HeroDetails on Character { id }
+{ me { id } }
+"#;
+        let doc = parse_graphql(input)?;
+        assert_eq!(doc.definitions.len(), 3);
+        let Definition::Operation(op) = &doc.definitions[0] else {
+            panic!("expected an operation");
+        };
+        assert_eq!(op.kind, OperationKind::Query);
+        assert_eq!(op.name.as_ref().unwrap().value, "Hero");
+        assert_eq!(op.variables[0].default, Some(Value::Enum("JEDI".into())));
+        assert_eq!(
+            op.variables[1].ty,
+            Type::NonNull(Box::new(Type::List(Box::new(Type::NonNull(Box::new(
+                Type::Named("ID".into())
+            ))))))
+        );
+        assert_eq!(op.directives[0].arguments[0].value, Value::Int(60));
+        assert_eq!(
+            &input[op.span.clone()],
+            &input[10..input.find("\n\nfragment").unwrap()]
+        );
+
+        let Selection::Field(hero) = &op.selection_set.selections[0] else {
+            panic!("expected a field");
+        };
+        assert_eq!(&input[hero.name.span.clone()], "hero");
+        assert_eq!(
+            hero.arguments[1].value,
+            Value::Object(vec![
+                ("name".into(), Value::String("R2".into())),
+                ("height".into(), Value::Float(-150.0)),
+                (
+                    "tags".into(),
+                    Value::List(vec![Value::Enum("a".into()), Value::Enum("b".into())])
+                ),
+            ])
+        );
+        let selections = &hero.selection_set.as_ref().unwrap().selections;
+        assert!(
+            matches!(&selections[1], Selection::FragmentSpread(s) if s.name.value == "HeroDetails")
+        );
+        assert!(matches!(&selections[3], Selection::InlineFragment(f)
+            if f.type_condition.as_ref().unwrap().value == "Droid"));
+        let Selection::Field(pic) = &selections[4] else {
+            panic!("expected a field");
+        };
+        assert_eq!(pic.alias.as_ref().unwrap().value, "smallPic");
+        assert_eq!(&input[pic.span.clone()], "smallPic: profilePic(size: 64)");
+
+        let Definition::Fragment(fragment) = &doc.definitions[1] else {
+            panic!("expected a fragment");
+        };
+        assert_eq!(fragment.type_condition.value, "Character");
+        Ok(())
+    }
+
+    #[test]
+    fn parse_strings_should_work() -> Result<(), ParseError> {
+        let doc = parse_graphql(
+            "{ f(a: \"tab\\t\\u00e9\", b: \"\"\"\n    first\n      indented \\\"\"\"\n    \"\"\") }",
+        )?;
+        let Definition::Operation(op) = &doc.definitions[0] else {
+            panic!("expected an operation");
+        };
+        let Selection::Field(f) = &op.selection_set.selections[0] else {
+            panic!("expected a field");
+        };
+        assert_eq!(f.arguments[0].value, Value::String("tab\té".into()));
+        assert_eq!(
+            f.arguments[1].value,
+            Value::String("first\n  indented \"\"\"".into())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_graphql_should_report_errors() {
+        let err = parse_graphql("query Q($a: Int = $b) { x }").unwrap_err();
+        assert_eq!(err.offset(), 18);
+        let err = parse_graphql("{ a(x: 1 }").unwrap_err();
+        assert_eq!(err.offset(), 9);
+        assert!(parse_graphql("{ }").is_err());
+        assert!(parse_graphql("{ a(x: 01) }").is_err());
+        let err = parse_graphql("{ a(x: 1.) }").unwrap_err();
+        assert_eq!(err.offset(), 7);
+        assert_eq!(err.message(), "unexpected character '1'");
+        assert!(parse_graphql("{ a(x: \"open) }").is_err());
+        assert!(parse_graphql("fragment on on T { a }").is_err());
+    }
+}
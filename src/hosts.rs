@@ -0,0 +1,251 @@
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{line_ending, space0, space1, till_line_ending};
+use winnow::combinator::{alt, cut_err, eof, not, opt, peek, preceded, repeat};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::take_while;
+
+use crate::ParseError;
+use crate::ipnet::addr;
+
+/// A hosts file, line by line, so that comments survive an edit.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Hosts {
+    pub lines: Vec<Line>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Line {
+    Entry(Entry),
+    /// The text after `#`.
+    Comment(String),
+    Blank,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub addr: IpAddr,
+    pub canonical: String,
+    pub aliases: Vec<String>,
+    /// A comment after the names, without its `#`.
+    pub comment: Option<String>,
+}
+
+impl Entry {
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.canonical.as_str()).chain(self.aliases.iter().map(String::as_str))
+    }
+}
+
+impl Hosts {
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Entry(entry) => Some(entry),
+            _ => None,
+        })
+    }
+
+    /// Every address listed for `name`, which is compared ignoring ASCII
+    /// case, in file order.
+    pub fn lookup(&self, name: &str) -> Vec<IpAddr> {
+        self.entries()
+            .filter(|entry| entry.names().any(|n| n.eq_ignore_ascii_case(name)))
+            .map(|entry| entry.addr)
+            .collect()
+    }
+
+    /// Appends an entry for `addr`; the first name is the canonical one.
+    /// Does nothing when `names` is empty.
+    pub fn add(&mut self, addr: IpAddr, names: &[&str]) {
+        let Some((canonical, aliases)) = names.split_first() else {
+            return;
+        };
+        self.lines.push(Line::Entry(Entry {
+            addr,
+            canonical: canonical.to_string(),
+            aliases: aliases.iter().map(|a| a.to_string()).collect(),
+            comment: None,
+        }));
+    }
+
+    /// Removes `name` wherever it appears. An entry left without names
+    /// goes away, and an alias takes the place of a removed canonical name.
+    /// Returns whether anything changed.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let mut changed = false;
+        self.lines.retain_mut(|line| {
+            let Line::Entry(entry) = line else {
+                return true;
+            };
+            let count = entry.aliases.len();
+            entry.aliases.retain(|a| !a.eq_ignore_ascii_case(name));
+            changed |= entry.aliases.len() != count;
+            if !entry.canonical.eq_ignore_ascii_case(name) {
+                return true;
+            }
+            changed = true;
+            if entry.aliases.is_empty() {
+                return false;
+            }
+            entry.canonical = entry.aliases.remove(0);
+            true
+        });
+        changed
+    }
+}
+
+impl FromStr for Hosts {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_hosts(s)
+    }
+}
+
+/// One line per entry, each ending in a newline. Entries are written as
+/// the address, a tab and the names separated by spaces.
+impl fmt::Display for Hosts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            match line {
+                Line::Entry(entry) => writeln!(f, "{entry}")?,
+                Line::Comment(text) => writeln!(f, "#{text}")?,
+                Line::Blank => writeln!(f)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\t{}", self.addr, self.canonical)?;
+        for alias in &self.aliases {
+            write!(f, " {alias}")?;
+        }
+        if let Some(comment) = &self.comment {
+            write!(f, " #{comment}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a hosts file. Tabs and spaces mix freely, trailing white space
+/// and `\r\n` line endings are fine, and a `#` starts a comment anywhere,
+/// even straight after a name.
+pub fn parse_hosts(input: &str) -> Result<Hosts, ParseError> {
+    repeat(0.., line)
+        .map(|lines| Hosts { lines })
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+fn line(input: &mut &str) -> ModalResult<Line> {
+    not(eof).parse_next(input)?;
+    space0.parse_next(input)?;
+    let line = alt((
+        comment.map(Line::Comment),
+        peek(end_of_line).value(Line::Blank),
+        cut_err(entry).map(Line::Entry),
+    ))
+    .parse_next(input)?;
+    end_of_line.parse_next(input)?;
+    Ok(line)
+}
+
+fn end_of_line(input: &mut &str) -> ModalResult<()> {
+    preceded(space0, alt((line_ending.void(), eof.void()))).parse_next(input)
+}
+
+fn comment(input: &mut &str) -> ModalResult<String> {
+    preceded('#', till_line_ending)
+        .map(|text: &str| text.trim_end_matches('\r').to_string())
+        .parse_next(input)
+}
+
+fn entry(input: &mut &str) -> ModalResult<Entry> {
+    let addr = addr.parse_next(input)?;
+    let canonical = preceded(
+        space1,
+        name.context(StrContext::Label("host name"))
+            .context(StrContext::Expected(StrContextValue::Description(
+                "a host name after the address",
+            ))),
+    )
+    .parse_next(input)?;
+    let aliases = repeat(0.., preceded(space1, name)).parse_next(input)?;
+    let comment = opt(preceded(space0, comment)).parse_next(input)?;
+    Ok(Entry {
+        addr,
+        canonical,
+        aliases,
+        comment,
+    })
+}
+
+fn name(input: &mut &str) -> ModalResult<String> {
+    take_while(1.., |c: char| !c.is_whitespace() && c != '#')
+        .map(String::from)
+        .parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOSTS: &str = "# static table\r\n127.0.0.1\tlocalhost  \r\n\
+        ::1 localhost ip6-localhost\tip6-loopback\n\n   \n\
+        10.0.0.5  db.internal db # primary\n192.168.1.9 nas#no space\n";
+
+    #[test]
+    fn parse_hosts_should_handle_quirks() -> Result<(), ParseError> {
+        let hosts = parse_hosts(HOSTS)?;
+        assert_eq!(hosts.lines.len(), 7);
+        assert_eq!(hosts.lines[0], Line::Comment(" static table".to_string()));
+        assert_eq!(hosts.lines[3], Line::Blank);
+        let entries: Vec<_> = hosts.entries().collect();
+        assert_eq!(entries[1].aliases, ["ip6-localhost", "ip6-loopback"]);
+        assert_eq!(entries[2].comment.as_deref(), Some(" primary"));
+        assert_eq!(entries[3].canonical, "nas");
+        assert_eq!(
+            hosts.lookup("LOCALHOST"),
+            [
+                "127.0.0.1".parse::<IpAddr>().unwrap(),
+                "::1".parse().unwrap()
+            ]
+        );
+        assert!(parse_hosts("")?.lines.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn edits_should_keep_comments() -> Result<(), ParseError> {
+        let mut hosts = parse_hosts(HOSTS)?;
+        assert!(hosts.remove("db.internal"));
+        assert!(hosts.remove("nas"));
+        assert!(!hosts.remove("missing"));
+        hosts.add("10.0.0.6".parse().unwrap(), &["cache", "redis"]);
+        assert_eq!(
+            hosts.to_string(),
+            "# static table\n127.0.0.1\tlocalhost\n\
+             ::1\tlocalhost ip6-localhost ip6-loopback\n\n\n\
+             10.0.0.5\tdb # primary\n10.0.0.6\tcache redis\n"
+        );
+        assert_eq!(parse_hosts(&hosts.to_string())?, hosts);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_hosts_should_report_errors() {
+        assert_eq!(parse_hosts("127.0.0.1\n").unwrap_err().offset(), 9);
+        assert_eq!(parse_hosts("ok\n").unwrap_err().offset(), 0);
+        assert_eq!(
+            parse_hosts("# x\n  10.0.0.300 a\n").unwrap_err().offset(),
+            6
+        );
+    }
+}
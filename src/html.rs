@@ -0,0 +1,496 @@
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::multispace0;
+use winnow::combinator::{alt, delimited, opt, preceded, repeat};
+use winnow::token::{one_of, take_till, take_while};
+
+use crate::ParseError;
+
+/// Selectors for [`Html::select`]; see [`crate::css::selector`].
+pub use crate::css::selector::SelectorList as Selector;
+
+/// Elements that never have children, so their end tags are optional.
+const VOID: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Elements whose content is raw text up to their end tag.
+const RAW_TEXT: &[&str] = &["script", "style", "textarea", "title"];
+
+/// Block level elements that implicitly close an open `<p>`.
+const CLOSES_P: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "div",
+    "dl",
+    "fieldset",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Element(Element),
+    Text(String),
+    Comment(String),
+    Doctype(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Element {
+    /// Lowercased tag name.
+    pub name: String,
+    /// Attributes in source order, names lowercased.
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Node>,
+}
+
+impl Element {
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.attr("id")
+    }
+
+    pub fn classes(&self) -> impl Iterator<Item = &str> {
+        self.attr("class").unwrap_or_default().split_whitespace()
+    }
+
+    pub fn has_class(&self, class: &str) -> bool {
+        self.classes().any(|c| c == class)
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        elements(&self.children)
+    }
+
+    /// The text of this element and its descendants.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        collect_text(&self.children, &mut text);
+        text
+    }
+
+    /// Descendants of this element matching `selector`, in document order.
+    pub fn select(&self, selector: &Selector) -> Vec<&Element> {
+        selector.select(&self.children, Some(self))
+    }
+}
+
+/// A parsed fragment or document.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Html {
+    pub children: Vec<Node>,
+}
+
+impl Html {
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        elements(&self.children)
+    }
+
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        collect_text(&self.children, &mut text);
+        text
+    }
+
+    /// Every element matching `selector`, in document order.
+    pub fn select(&self, selector: &Selector) -> Vec<&Element> {
+        selector.select(&self.children, None)
+    }
+
+    /// Parses `selector` and returns the matching elements.
+    pub fn query(&self, selector: &str) -> Result<Vec<&Element>, ParseError> {
+        Ok(self.select(&selector.parse()?))
+    }
+}
+
+pub(crate) fn elements(nodes: &[Node]) -> impl Iterator<Item = &Element> {
+    nodes.iter().filter_map(|node| match node {
+        Node::Element(e) => Some(e),
+        _ => None,
+    })
+}
+
+fn collect_text(nodes: &[Node], out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(t) => out.push_str(t),
+            Node::Element(e) => collect_text(&e.children, out),
+            _ => {}
+        }
+    }
+}
+
+/// Parses HTML the way browsers do, roughly: it never fails. Unclosed
+/// elements are closed at the end, stray end tags are ignored and a few
+/// common implied end tags (`<p>`, `<li>`, table cells, ...) are honored.
+pub fn parse_html(input: &str) -> Html {
+    let mut builder = Builder::default();
+    let mut rest = input;
+    while !rest.is_empty() {
+        let token = match parse_token(&mut rest) {
+            Ok(token) => token,
+            // A `<` that does not start markup is ordinary text.
+            Err(_) => {
+                let (lt, tail) = rest.split_at(1);
+                rest = tail;
+                Token::Text(lt.to_string())
+            }
+        };
+        let raw_text = match &token {
+            Token::Start {
+                name,
+                self_closing: false,
+                ..
+            } if RAW_TEXT.contains(&name.as_str()) => Some(name.clone()),
+            _ => None,
+        };
+        builder.push(token);
+        if let Some(name) = raw_text {
+            let end = find_end_tag(rest, &name);
+            let text = &rest[..end];
+            rest = &rest[end..];
+            if !text.is_empty() {
+                let text = if name == "textarea" || name == "title" {
+                    decode(text)
+                } else {
+                    text.to_string()
+                };
+                builder.push(Token::Text(text));
+            }
+        }
+    }
+    builder.finish()
+}
+
+fn find_end_tag(rest: &str, name: &str) -> usize {
+    let lower = rest.to_ascii_lowercase();
+    let needle = format!("</{}", name);
+    lower
+        .match_indices(&needle)
+        .map(|(i, _)| i)
+        .find(|&i| {
+            lower[i + needle.len()..]
+                .chars()
+                .next()
+                .is_none_or(|c| c == '>' || c == '/' || c.is_whitespace())
+        })
+        .unwrap_or(rest.len())
+}
+
+enum Token {
+    Start {
+        name: String,
+        attributes: Vec<(String, String)>,
+        self_closing: bool,
+    },
+    End(String),
+    Text(String),
+    Comment(String),
+    Doctype(String),
+}
+
+fn parse_token(input: &mut &str) -> ModalResult<Token> {
+    alt((
+        parse_comment.map(Token::Comment),
+        parse_doctype.map(Token::Doctype),
+        parse_end_tag.map(Token::End),
+        parse_start_tag,
+        take_till(1.., '<').map(|t: &str| Token::Text(decode(t))),
+    ))
+    .parse_next(input)
+}
+
+/// An unterminated comment runs to the end of the input.
+fn parse_comment(input: &mut &str) -> ModalResult<String> {
+    "<!--".parse_next(input)?;
+    let (body, rest) = input.split_once("-->").unwrap_or((input, ""));
+    *input = rest;
+    Ok(body.to_string())
+}
+
+fn parse_doctype(input: &mut &str) -> ModalResult<String> {
+    delimited("<!", take_till(0.., '>'), opt('>'))
+        .map(|s: &str| {
+            let s = s.trim();
+            s.get(..7)
+                .filter(|d| d.eq_ignore_ascii_case("doctype"))
+                .map_or(s, |_| s[7..].trim())
+                .to_string()
+        })
+        .parse_next(input)
+}
+
+fn tag_name(input: &mut &str) -> ModalResult<String> {
+    (
+        one_of(|c: char| c.is_ascii_alphabetic()),
+        take_till(0.., |c: char| c.is_whitespace() || c == '/' || c == '>'),
+    )
+        .take()
+        .map(str::to_ascii_lowercase)
+        .parse_next(input)
+}
+
+fn parse_end_tag(input: &mut &str) -> ModalResult<String> {
+    delimited("</", tag_name, (take_till(0.., '>'), opt('>'))).parse_next(input)
+}
+
+fn parse_start_tag(input: &mut &str) -> ModalResult<Token> {
+    let name = preceded('<', tag_name).parse_next(input)?;
+    let attributes = repeat(0.., parse_attribute).parse_next(input)?;
+    let tail = take_while(0.., |c: char| c.is_whitespace() || c == '/').parse_next(input)?;
+    let self_closing = tail.ends_with('/');
+    opt('>').parse_next(input)?;
+    Ok(Token::Start {
+        name,
+        attributes,
+        self_closing,
+    })
+}
+
+/// Attributes accept the usual quirks: missing values, unquoted values and
+/// stray slashes between them.
+fn parse_attribute(input: &mut &str) -> ModalResult<(String, String)> {
+    take_while(0.., |c: char| c.is_whitespace() || c == '/').parse_next(input)?;
+    let name = take_till(1.., |c: char| {
+        c.is_whitespace() || matches!(c, '/' | '>' | '=')
+    })
+    .map(str::to_ascii_lowercase)
+    .parse_next(input)?;
+    let value = opt(preceded(
+        (multispace0, '=', multispace0),
+        alt((
+            delimited('"', take_till(0.., '"'), opt('"')),
+            delimited('\'', take_till(0.., '\''), opt('\'')),
+            take_till(0.., |c: char| c.is_whitespace() || c == '>'),
+        )),
+    ))
+    .parse_next(input)?;
+    Ok((name, value.map(decode).unwrap_or_default()))
+}
+
+/// Decodes the common named and numeric character references. Unknown
+/// references are kept verbatim, as browsers do.
+fn decode(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .map(|end| &rest[1..end + 1]);
+        let c = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            _ => {
+                let code = entity.strip_prefix('#')?;
+                let code = match code.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => code.parse().ok()?,
+                };
+                char::from_u32(code)
+            }
+        });
+        match (c, entity) {
+            (Some(c), Some(entity)) => {
+                out.push(c);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[derive(Default)]
+struct Builder {
+    root: Vec<Node>,
+    open: Vec<Element>,
+}
+
+impl Builder {
+    fn push(&mut self, token: Token) {
+        match token {
+            Token::Start {
+                name,
+                attributes,
+                self_closing,
+            } => {
+                self.close_implied(&name);
+                let element = Element {
+                    name,
+                    attributes,
+                    children: Vec::new(),
+                };
+                if self_closing || VOID.contains(&element.name.as_str()) {
+                    self.append(Node::Element(element));
+                } else {
+                    self.open.push(element);
+                }
+            }
+            Token::End(name) => {
+                if let Some(i) = self.open.iter().rposition(|e| e.name == name) {
+                    self.close_to(i);
+                }
+            }
+            Token::Text(text) => match self.current_children().last_mut() {
+                Some(Node::Text(last)) => last.push_str(&text),
+                _ => self.append(Node::Text(text)),
+            },
+            Token::Comment(text) => self.append(Node::Comment(text)),
+            Token::Doctype(text) => self.append(Node::Doctype(text)),
+        }
+    }
+
+    /// Closes the elements that an opening `name` tag ends implicitly.
+    fn close_implied(&mut self, name: &str) {
+        let (closes, boundary): (&[&str], &[&str]) = match name {
+            "li" => (&["li"], &["ul", "ol"]),
+            "dt" | "dd" => (&["dt", "dd"], &["dl"]),
+            "option" => (&["option"], &["select", "datalist"]),
+            "tr" => (&["tr", "td", "th"], &["table", "thead", "tbody", "tfoot"]),
+            "td" | "th" => (&["td", "th"], &["tr", "table"]),
+            "thead" | "tbody" | "tfoot" => {
+                (&["thead", "tbody", "tfoot", "tr", "td", "th"], &["table"])
+            }
+            _ if CLOSES_P.contains(&name) => {
+                (&["p"], &["div", "section", "article", "td", "th", "li"])
+            }
+            _ => return,
+        };
+        for i in (0..self.open.len()).rev() {
+            let open = self.open[i].name.as_str();
+            if closes.contains(&open) {
+                self.close_to(i);
+                return;
+            }
+            if boundary.contains(&open) {
+                return;
+            }
+        }
+    }
+
+    fn close_to(&mut self, index: usize) {
+        while self.open.len() > index {
+            let element = self.open.pop().expect("index is within the stack");
+            self.append(Node::Element(element));
+        }
+    }
+
+    fn current_children(&mut self) -> &mut Vec<Node> {
+        match self.open.last_mut() {
+            Some(e) => &mut e.children,
+            None => &mut self.root,
+        }
+    }
+
+    fn append(&mut self, node: Node) {
+        self.current_children().push(node);
+    }
+
+    fn finish(mut self) -> Html {
+        self.close_to(0);
+        Html {
+            children: self.root,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_html_should_tolerate_quirks() {
+        let html = parse_html(
+            "<!DOCTYPE html><ul><li>one<li class=x>two &amp; &bogus;</ul><p>a<p>b<br><img src=a.png alt>",
+        );
+        assert_eq!(html.children[0], Node::Doctype("html".to_string()));
+        let ul = html.elements().next().unwrap();
+        assert_eq!(ul.name, "ul");
+        let items: Vec<_> = ul.elements().collect();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1].text(), "two & &bogus;");
+        assert!(items[1].has_class("x"));
+
+        let ps: Vec<_> = html.elements().skip(1).collect();
+        assert_eq!(ps.len(), 2);
+        let img = ps[1].elements().nth(1).unwrap();
+        assert_eq!(
+            img.attributes,
+            [("src".into(), "a.png".into()), ("alt".into(), "".into())]
+        );
+    }
+
+    #[test]
+    fn parse_html_should_handle_raw_text_and_stray_tags() {
+        let html =
+            parse_html("<div></span><script>if (a < b) { x = '</div>' }</SCRIPT>1 < 2</div>");
+        let div = html.elements().next().unwrap();
+        let script = div.elements().next().unwrap();
+        assert_eq!(script.text(), "if (a < b) { x = '</div>' }");
+        assert_eq!(div.text(), "if (a < b) { x = '</div>' }1 < 2");
+        assert_eq!(html.children.len(), 1);
+    }
+
+    #[test]
+    fn select_should_match_selectors() -> Result<(), ParseError> {
+        let html = parse_html(
+            r#"<div id=main><ul class="nav top"><li><a href="/a">A</a></li>
+            <li><span><a href="https://x.org">X</a></span></li></ul></div>
+            <a href="/b" rel="nofollow noopener">B</a>"#,
+        );
+        let text = |selector: &str| -> Result<Vec<String>, ParseError> {
+            Ok(html.query(selector)?.iter().map(|e| e.text()).collect())
+        };
+        assert_eq!(text("#main a")?, ["A", "X"]);
+        assert_eq!(text("ul.nav > li > a")?, ["A"]);
+        assert_eq!(text("a[href^=http]")?, ["X"]);
+        assert_eq!(text("a[rel~=noopener], li > a")?, ["A", "B"]);
+        assert_eq!(text("div A[href$='/a']")?, ["A"]);
+        assert!(text("ul.top.nav")?.len() == 1);
+        assert!(text("p")?.is_empty());
+
+        let ul = html.query("ul")?[0];
+        assert_eq!(ul.select(&"ul a".parse()?).len(), 2);
+        assert_eq!(ul.select(&"div a".parse()?).len(), 0);
+        assert!("ul >".parse::<Selector>().is_err());
+        assert!("a[href".parse::<Selector>().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,328 @@
+pub mod header;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::combinator::{alt, cut_err, opt, preceded, repeat, terminated};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::{rest, take, take_while};
+
+use crate::ParseError;
+use crate::nginx::{HttpMethod, HttpVersion};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: HttpMethod,
+    pub target: String,
+    pub version: HttpVersion,
+    pub headers: Headers,
+    /// The decoded body; chunked framing is already removed.
+    pub body: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub version: HttpVersion,
+    pub status: u16,
+    pub reason: String,
+    pub headers: Headers,
+    pub body: Vec<u8>,
+}
+
+/// Header fields in the order received. Lookups ignore ASCII case, and a
+/// repeated name keeps every occurrence.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Headers {
+    fields: Vec<(String, String)>,
+}
+
+impl Headers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.get_all(name).next()
+    }
+
+    pub fn get_all<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a str> {
+        self.fields
+            .iter()
+            .filter(move |(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn append(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.fields.push((name.into(), value.into()));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Whether the last transfer coding is `chunked`.
+    pub fn is_chunked(&self) -> bool {
+        self.get_all("transfer-encoding")
+            .flat_map(|v| v.split(','))
+            .last()
+            .is_some_and(|coding| coding.trim().eq_ignore_ascii_case("chunked"))
+    }
+
+    pub fn content_length(&self) -> Option<u64> {
+        self.get("content-length")?.trim().parse().ok()
+    }
+}
+
+/// Parses one complete request. A body is read according to
+/// `Transfer-Encoding: chunked` or `Content-Length`, and is empty otherwise.
+pub fn parse_request(input: &[u8]) -> Result<Request, ParseError> {
+    request.parse(input).map_err(ParseError::from)
+}
+
+/// Parses one complete response. Without chunked framing or a
+/// `Content-Length`, the body runs to the end of the input.
+pub fn parse_response(input: &[u8]) -> Result<Response, ParseError> {
+    response.parse(input).map_err(ParseError::from)
+}
+
+/// Parses a block of header fields up to and including the blank line.
+pub fn parse_headers(input: &[u8]) -> Result<Headers, ParseError> {
+    headers.parse(input).map_err(ParseError::from)
+}
+
+/// Removes chunked transfer-encoding framing, returning the body and any
+/// trailer fields.
+pub fn decode_chunked(input: &[u8]) -> Result<(Vec<u8>, Headers), ParseError> {
+    chunked.parse(input).map_err(ParseError::from)
+}
+
+fn request(input: &mut &[u8]) -> ModalResult<Request> {
+    let method = take_while(1.., is_tchar)
+        .verify_map(|m: &[u8]| std::str::from_utf8(m).ok()?.parse().ok())
+        .context(StrContext::Label("method"))
+        .parse_next(input)?;
+    cut_err(b' ').parse_next(input)?;
+    let target = cut_err(take_while(1.., |b: u8| b.is_ascii_graphic()))
+        .map(|t: &[u8]| String::from_utf8_lossy(t).into_owned())
+        .context(StrContext::Label("request target"))
+        .parse_next(input)?;
+    cut_err(b' ').parse_next(input)?;
+    let version = cut_err(terminated(version, line_end)).parse_next(input)?;
+    let headers = headers.parse_next(input)?;
+    let body = if headers.is_chunked() {
+        chunked.map(|(body, _)| body).parse_next(input)?
+    } else if headers.contains("content-length") {
+        sized_body(input, &headers)?
+    } else {
+        Vec::new()
+    };
+    Ok(Request {
+        method,
+        target,
+        version,
+        headers,
+        body,
+    })
+}
+
+fn response(input: &mut &[u8]) -> ModalResult<Response> {
+    let version = version.parse_next(input)?;
+    let status = cut_err(preceded(
+        b' ',
+        take(3usize)
+            .verify(|d: &[u8]| d.iter().all(u8::is_ascii_digit))
+            .map(|d: &[u8]| d.iter().fold(0, |n, d| n * 10 + u16::from(d - b'0'))),
+    ))
+    .context(StrContext::Label("status code"))
+    .parse_next(input)?;
+    // Some servers drop the space when the reason phrase is empty.
+    let reason = opt(preceded(
+        b' ',
+        take_while(0.., |b: u8| b != b'\r' && b != b'\n'),
+    ))
+    .map(|r: Option<&[u8]>| String::from_utf8_lossy(r.unwrap_or_default()).into_owned())
+    .parse_next(input)?;
+    cut_err(line_end).parse_next(input)?;
+    let headers = headers.parse_next(input)?;
+    // 1xx, 204 and 304 responses never carry a body.
+    let body = if (100..200).contains(&status) || status == 204 || status == 304 {
+        Vec::new()
+    } else if headers.is_chunked() {
+        chunked.map(|(body, _)| body).parse_next(input)?
+    } else if headers.contains("content-length") {
+        sized_body(input, &headers)?
+    } else {
+        rest.parse_next(input)?.to_vec()
+    };
+    Ok(Response {
+        version,
+        status,
+        reason,
+        headers,
+        body,
+    })
+}
+
+fn version(input: &mut &[u8]) -> ModalResult<HttpVersion> {
+    (
+        b"HTTP/",
+        take_while(1.., |b: u8| b.is_ascii_digit() || b == b'.'),
+    )
+        .take()
+        .verify_map(|v: &[u8]| std::str::from_utf8(v).ok()?.parse().ok())
+        .context(StrContext::Label("HTTP version"))
+        .parse_next(input)
+}
+
+fn line_end(input: &mut &[u8]) -> ModalResult<()> {
+    alt((b"\r\n".void(), b"\n".void()))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "line ending",
+        )))
+        .parse_next(input)
+}
+
+pub(crate) fn headers(input: &mut &[u8]) -> ModalResult<Headers> {
+    let fields = terminated(repeat(0.., field), cut_err(line_end)).parse_next(input)?;
+    Ok(Headers { fields })
+}
+
+fn field(input: &mut &[u8]) -> ModalResult<(String, String)> {
+    let name = take_while(1.., is_tchar).parse_next(input)?;
+    cut_err(b':')
+        .context(StrContext::Expected(StrContextValue::CharLiteral(':')))
+        .parse_next(input)?;
+    let mut value = Vec::new();
+    loop {
+        take_while(0.., [b' ', b'\t']).parse_next(input)?;
+        let line = take_while(0.., |b: u8| b != b'\r' && b != b'\n').parse_next(input)?;
+        value.extend_from_slice(line);
+        cut_err(line_end).parse_next(input)?;
+        // obs-fold: a continuation line starts with white space and is
+        // joined to the value with a single space.
+        if !input.starts_with(b" ") && !input.starts_with(b"\t") {
+            break;
+        }
+        value.push(b' ');
+    }
+    while value.last().is_some_and(|b| *b == b' ' || *b == b'\t') {
+        value.pop();
+    }
+    Ok((
+        String::from_utf8_lossy(name).into_owned(),
+        String::from_utf8_lossy(&value).into_owned(),
+    ))
+}
+
+fn sized_body(input: &mut &[u8], headers: &Headers) -> ModalResult<Vec<u8>> {
+    let Some(length) = headers.content_length() else {
+        return cut_err(winnow::combinator::fail)
+            .context(StrContext::Label("Content-Length"))
+            .context(StrContext::Expected(StrContextValue::Description(
+                "decimal length",
+            )))
+            .parse_next(input);
+    };
+    let body = cut_err(take(length as usize))
+        .context(StrContext::Label("body"))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "as many bytes as Content-Length",
+        )))
+        .parse_next(input)?;
+    Ok(body.to_vec())
+}
+
+fn chunked(input: &mut &[u8]) -> ModalResult<(Vec<u8>, Headers)> {
+    let mut body = Vec::new();
+    loop {
+        let size = cut_err(take_while(1.., |b: u8| b.is_ascii_hexdigit()).verify_map(
+            |hex: &[u8]| usize::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok(),
+        ))
+        .context(StrContext::Label("chunk size"))
+        .parse_next(input)?;
+        // Chunk extensions carry nothing we use.
+        opt((b';', take_while(0.., |b: u8| b != b'\r' && b != b'\n'))).parse_next(input)?;
+        cut_err(line_end).parse_next(input)?;
+        if size == 0 {
+            break;
+        }
+        let data = cut_err(take(size))
+            .context(StrContext::Label("chunk"))
+            .parse_next(input)?;
+        body.extend_from_slice(data);
+        cut_err(line_end).parse_next(input)?;
+    }
+    let trailers = headers.parse_next(input)?;
+    Ok((body, trailers))
+}
+
+fn is_tchar(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_request_should_work() -> Result<(), ParseError> {
+        let input = b"POST /upload?x=1 HTTP/1.1\r\nHost: example.com\r\nX-Long: first\r\n   second \r\nTransfer-Encoding: gzip, chunked\r\n\r\n5;name=v\r\nhello\r\n6\r\n world\r\n0\r\nExpires: never\r\n\r\n";
+        let request = parse_request(input)?;
+        assert_eq!(request.method, HttpMethod::Post);
+        assert_eq!(request.target, "/upload?x=1");
+        assert_eq!(request.version, HttpVersion::Http1_1);
+        assert_eq!(request.headers.get("HOST"), Some("example.com"));
+        assert_eq!(request.headers.get("x-long"), Some("first second"));
+        assert!(request.headers.is_chunked());
+        assert_eq!(request.body, b"hello world");
+
+        let request = parse_request(b"GET / HTTP/1.0\nAccept: */*\nAccept: text/html\n\n")?;
+        assert_eq!(
+            request.headers.get_all("accept").collect::<Vec<_>>(),
+            ["*/*", "text/html"]
+        );
+        assert!(request.body.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_response_should_work() -> Result<(), ParseError> {
+        let response = parse_response(b"HTTP/1.1 404 Not Found\r\nContent-Length: 3\r\n\r\nabc")?;
+        assert_eq!(response.status, 404);
+        assert_eq!(response.reason, "Not Found");
+        assert_eq!(response.body, b"abc");
+
+        let response = parse_response(b"HTTP/1.0 200\r\nServer: x\r\n\r\nto the end")?;
+        assert_eq!(response.reason, "");
+        assert_eq!(response.body, b"to the end");
+
+        let (body, trailers) = decode_chunked(b"3\r\nabc\r\n0\r\n\r\n")?;
+        assert_eq!(body, b"abc");
+        assert!(trailers.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn http_errors_should_have_offsets() {
+        let err = parse_request(b"GET /a\r\n\r\n").unwrap_err();
+        assert_eq!(err.offset(), 6);
+        let err =
+            parse_response(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nshort").unwrap_err();
+        assert_eq!(err.offset(), 39);
+        assert!(parse_request(b"BREW /pot HTTP/1.1\r\n\r\n").is_err());
+        assert!(parse_request(b"PUT / HTTP/1.1\r\nContent-Length: ten\r\n\r\n").is_err());
+        assert!(decode_chunked(b"zz\r\n").is_err());
+        assert!(parse_headers(b"Bad Name: x\r\n\r\n").is_err());
+    }
+}
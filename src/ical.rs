@@ -0,0 +1,378 @@
+pub mod rrule;
+
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+use crate::ParseError;
+use crate::vcard::content_lines;
+pub use crate::vcard::{Param, Property};
+use rrule::{Occurrences, RRule, parse_rrule};
+
+/// A `BEGIN:NAME` ... `END:NAME` block such as `VCALENDAR` or `VEVENT`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Component {
+    /// Uppercased.
+    pub name: String,
+    pub properties: Vec<Property>,
+    pub components: Vec<Component>,
+}
+
+/// A `DATE` or `DATE-TIME` value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DateTimeValue {
+    /// `19970714`, an all-day value.
+    Date(NaiveDate),
+    /// `19970714T173000`, wall-clock time wherever the reader is.
+    Floating(NaiveDateTime),
+    /// `19970714T173000Z`
+    Utc(NaiveDateTime),
+    /// `DTSTART;TZID=Europe/Paris:19970714T173000`. The zone is only named;
+    /// resolving it needs a time zone database.
+    Zoned { tzid: String, time: NaiveDateTime },
+}
+
+/// The commonly used parts of a `VEVENT`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub uid: Option<String>,
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub start: Option<DateTimeValue>,
+    pub end: Option<DateTimeValue>,
+    /// `DURATION`, given instead of `DTEND`.
+    pub duration: Option<Duration>,
+    pub rrule: Option<RRule>,
+    pub exdates: Vec<DateTimeValue>,
+}
+
+impl Component {
+    pub fn get(&self, name: &str) -> Option<&Property> {
+        self.properties
+            .iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The `VEVENT`s directly inside this component. Errors name the
+    /// property that is malformed; they carry no offset.
+    pub fn events(&self) -> Result<Vec<Event>, ParseError> {
+        self.components
+            .iter()
+            .filter(|c| c.name == "VEVENT")
+            .map(Event::from_component)
+            .collect()
+    }
+}
+
+impl DateTimeValue {
+    /// The wall-clock time, midnight for a date.
+    pub fn naive(&self) -> NaiveDateTime {
+        match self {
+            DateTimeValue::Date(date) => date.and_time(chrono::NaiveTime::MIN),
+            DateTimeValue::Floating(time)
+            | DateTimeValue::Utc(time)
+            | DateTimeValue::Zoned { time, .. } => *time,
+        }
+    }
+}
+
+impl Event {
+    fn from_component(component: &Component) -> Result<Event, ParseError> {
+        let text = |name: &str| component.get(name).map(Property::text);
+        let date_time = |name: &str| component.get(name).map(date_time_of).transpose();
+        let error =
+            |name: &str, e: ParseError| ParseError::new(0, format!("{name}: {}", e.message()));
+        let duration = match component.get("DURATION") {
+            Some(p) => Some(
+                parse_duration(&p.value)
+                    .ok_or_else(|| ParseError::new(0, "DURATION: invalid duration"))?,
+            ),
+            None => None,
+        };
+        let mut exdates = Vec::new();
+        for p in component.properties.iter().filter(|p| p.name == "EXDATE") {
+            for value in p.value.split(',') {
+                let tzid = p.param("TZID").and_then(|t| t.values.first());
+                exdates.push(
+                    parse_date_time(value, tzid.map(String::as_str))
+                        .map_err(|e| error("EXDATE", e))?,
+                );
+            }
+        }
+        Ok(Event {
+            uid: text("UID"),
+            summary: text("SUMMARY"),
+            description: text("DESCRIPTION"),
+            location: text("LOCATION"),
+            start: date_time("DTSTART").map_err(|e| error("DTSTART", e))?,
+            end: date_time("DTEND").map_err(|e| error("DTEND", e))?,
+            duration,
+            rrule: component
+                .get("RRULE")
+                .map(|p| parse_rrule(&p.value))
+                .transpose()
+                .map_err(|e| error("RRULE", e))?,
+            exdates,
+        })
+    }
+
+    /// When the event starts, as wall-clock times: just `DTSTART` without
+    /// an `RRULE`, and nothing without a `DTSTART`. `EXDATE`s are skipped.
+    pub fn occurrences(&self) -> impl Iterator<Item = NaiveDateTime> + '_ {
+        let occurrences = match (&self.start, &self.rrule) {
+            (Some(start), Some(rule)) => rule.occurrences(start.naive()),
+            (Some(start), None) => Occurrences::once(start.naive()),
+            (None, _) => Occurrences::none(),
+        };
+        occurrences.filter(|t| !self.exdates.iter().any(|ex| ex.naive() == *t))
+    }
+}
+
+fn date_time_of(property: &Property) -> Result<DateTimeValue, ParseError> {
+    let tzid = property.param("TZID").and_then(|t| t.values.first());
+    parse_date_time(&property.value, tzid.map(String::as_str))
+}
+
+/// `YYYYMMDD`, or `YYYYMMDDTHHMMSS` with an optional `Z`.
+fn parse_date_time(value: &str, tzid: Option<&str>) -> Result<DateTimeValue, ParseError> {
+    let invalid = || ParseError::new(0, format!("invalid date-time `{value}`"));
+    let digits = |s: &str| s.len() == 8 && s.bytes().all(|b| b.is_ascii_digit());
+    let (date, time) = match value.split_once('T') {
+        Some((date, time)) => (date, Some(time)),
+        None => (value, None),
+    };
+    if !digits(date) {
+        return Err(invalid());
+    }
+    let date = NaiveDate::parse_from_str(date, "%Y%m%d").map_err(|_| invalid())?;
+    let Some(time) = time else {
+        return Ok(DateTimeValue::Date(date));
+    };
+    let (clock, utc) = match time.strip_suffix('Z') {
+        Some(clock) => (clock, true),
+        None => (time, false),
+    };
+    if clock.len() != 6 || !clock.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let time = chrono::NaiveTime::parse_from_str(clock, "%H%M%S")
+        .map(|t| date.and_time(t))
+        .map_err(|_| invalid())?;
+    Ok(match (utc, tzid) {
+        (true, _) => DateTimeValue::Utc(time),
+        (false, Some(tzid)) => DateTimeValue::Zoned {
+            tzid: tzid.to_string(),
+            time,
+        },
+        (false, None) => DateTimeValue::Floating(time),
+    })
+}
+
+/// `[+-]P[nW]` or `[+-]P[nD][T[nH][nM][nS]]`.
+fn parse_duration(text: &str) -> Option<Duration> {
+    let (sign, rest) = match text.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let rest = rest.strip_prefix('P')?;
+    let (date, time) = match rest.split_once('T') {
+        Some((date, time)) if !time.is_empty() => (date, Some(time)),
+        Some(_) => return None,
+        None => (rest, None),
+    };
+    let mut total = Duration::zero();
+    let mut any = false;
+    let mut units = |part: &str, allowed: &[(char, i64)]| -> Option<()> {
+        let mut number = String::new();
+        let mut allowed = allowed.iter();
+        for c in part.chars() {
+            if c.is_ascii_digit() {
+                number.push(c);
+                continue;
+            }
+            // Units must come in order, each at most once.
+            let &(_, seconds) = allowed.find(|(unit, _)| *unit == c)?;
+            total += Duration::seconds(number.parse::<i64>().ok()? * seconds);
+            number.clear();
+            any = true;
+        }
+        number.is_empty().then_some(())
+    };
+    units(date, &[('W', 604800), ('D', 86400)])?;
+    if let Some(time) = time {
+        units(time, &[('H', 3600), ('M', 60), ('S', 1)])?;
+    }
+    any.then_some(total * sign)
+}
+
+/// Parses an iCalendar stream into its top-level components, usually a
+/// single `VCALENDAR`. Folded lines are joined first; errors point into
+/// `input`.
+pub fn parse_ical(input: &str) -> Result<Vec<Component>, ParseError> {
+    let mut top = Vec::new();
+    // Open components, innermost last, with where each began.
+    let mut stack: Vec<(usize, Component)> = Vec::new();
+    for (start, property) in content_lines(input)? {
+        match property.name.as_str() {
+            "BEGIN" => stack.push((
+                start,
+                Component {
+                    name: property.value.to_ascii_uppercase(),
+                    properties: Vec::new(),
+                    components: Vec::new(),
+                },
+            )),
+            "END" => {
+                let (_, component) = stack
+                    .pop()
+                    .ok_or_else(|| ParseError::new(start, "END without BEGIN"))?;
+                if !component.name.eq_ignore_ascii_case(&property.value) {
+                    return Err(ParseError::new(
+                        start,
+                        format!("expected END:{}", component.name),
+                    ));
+                }
+                match stack.last_mut() {
+                    Some((_, parent)) => parent.components.push(component),
+                    None => top.push(component),
+                }
+            }
+            _ => match stack.last_mut() {
+                Some((_, component)) => component.properties.push(property),
+                None => return Err(ParseError::new(start, "expected BEGIN:VCALENDAR")),
+            },
+        }
+    }
+    match stack.pop() {
+        Some((start, component)) => Err(ParseError::new(
+            start,
+            format!("missing END:{}", component.name),
+        )),
+        None => Ok(top),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CALENDAR: &str = "BEGIN:VCALENDAR\r\n\
+        VERSION:2.0\r\n\
+        PRODID:-//Example//EN\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:standup@example.com\r\n\
+        DTSTART;TZID=America/New_York:20240101T093000\r\n\
+        DURATION:PT15M\r\n\
+        RRULE:FREQ=WEEKLY;BYDAY=MO,WE,FR;COUNT=5\r\n\
+        EXDATE;TZID=America/New_York:20240103T093000\r\n\
+        SUMMARY:Daily stand-up\\, team A\r\n\
+        DESCRIPTION:A long description that wraps onto \r\n\
+        \x20a second line.\r\n\
+        BEGIN:VALARM\r\n\
+        TRIGGER:-PT5M\r\n\
+        ACTION:DISPLAY\r\n\
+        END:VALARM\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:holiday@example.com\r\n\
+        DTSTART;VALUE=DATE:20240704\r\n\
+        DTEND;VALUE=DATE:20240705\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    #[test]
+    fn parse_ical_should_work() -> Result<(), ParseError> {
+        let calendars = parse_ical(CALENDAR)?;
+        assert_eq!(calendars.len(), 1);
+        let calendar = &calendars[0];
+        assert_eq!(calendar.name, "VCALENDAR");
+        assert_eq!(calendar.get("version").unwrap().value, "2.0");
+        assert_eq!(calendar.components[0].components[0].name, "VALARM");
+
+        let events = calendar.events()?;
+        assert_eq!(events.len(), 2);
+        let standup = &events[0];
+        assert_eq!(standup.summary.as_deref(), Some("Daily stand-up, team A"));
+        assert_eq!(
+            standup.description.as_deref(),
+            Some("A long description that wraps onto a second line.")
+        );
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(9, 30, 0)
+            .unwrap();
+        assert_eq!(
+            standup.start,
+            Some(DateTimeValue::Zoned {
+                tzid: "America/New_York".into(),
+                time: start
+            })
+        );
+        assert_eq!(standup.duration, Some(Duration::minutes(15)));
+        let days: Vec<u32> = standup
+            .occurrences()
+            .map(|t| chrono::Datelike::day(&t))
+            .collect();
+        // COUNT includes the excluded Wednesday.
+        assert_eq!(days, [1, 5, 8, 10]);
+
+        let holiday = &events[1];
+        assert_eq!(
+            holiday.start,
+            Some(DateTimeValue::Date(
+                NaiveDate::from_ymd_opt(2024, 7, 4).unwrap()
+            ))
+        );
+        assert_eq!(holiday.occurrences().count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn values_should_parse() {
+        let at = |h, m, s| {
+            NaiveDate::from_ymd_opt(1997, 7, 14)
+                .unwrap()
+                .and_hms_opt(h, m, s)
+                .unwrap()
+        };
+        assert_eq!(
+            parse_date_time("19970714T173000Z", Some("X")),
+            Ok(DateTimeValue::Utc(at(17, 30, 0)))
+        );
+        assert_eq!(
+            parse_date_time("19970714T173000", None),
+            Ok(DateTimeValue::Floating(at(17, 30, 0)))
+        );
+        assert!(parse_date_time("19970714T1730", None).is_err());
+        assert!(parse_date_time("19971314", None).is_err());
+        assert_eq!(parse_duration("P1W"), Some(Duration::weeks(1)));
+        assert_eq!(
+            parse_duration("-P1DT2H3M4S"),
+            Some(-Duration::seconds(86400 + 7384))
+        );
+        assert_eq!(parse_duration("P"), None);
+        assert_eq!(parse_duration("PT"), None);
+        assert_eq!(parse_duration("PT1S1H"), None);
+    }
+
+    #[test]
+    fn parse_ical_should_report_errors() {
+        let err = parse_ical("BEGIN:VCALENDAR\nBEGIN:VEVENT\nEND:VCALENDAR\n").unwrap_err();
+        assert_eq!(err.offset(), 29);
+        assert_eq!(err.message(), "expected END:VEVENT");
+        let err = parse_ical("BEGIN:VCALENDAR\nBEGIN:VEVENT\n").unwrap_err();
+        assert_eq!(err.offset(), 16);
+        assert!(parse_ical("VERSION:2.0\n").is_err());
+        assert!(parse_ical("END:VEVENT\n").is_err());
+        let calendar = &parse_ical(
+            "BEGIN:VCALENDAR\nBEGIN:VEVENT\nRRULE:FREQ=SOMETIMES\nEND:VEVENT\nEND:VCALENDAR\n",
+        )
+        .unwrap()[0];
+        assert!(
+            calendar
+                .events()
+                .unwrap_err()
+                .message()
+                .starts_with("RRULE")
+        );
+    }
+}
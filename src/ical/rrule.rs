@@ -0,0 +1,507 @@
+use chrono::{Datelike, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc, Weekday};
+
+use super::{DateTimeValue, parse_date_time};
+use crate::ParseError;
+use crate::cron::Schedule;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Frequency {
+    Secondly,
+    Minutely,
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// An RFC 5545 recurrence rule. `BYWEEKNO` and `BYSETPOS` are not
+/// supported and fail to parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RRule {
+    pub freq: Frequency,
+    /// At least 1.
+    pub interval: u32,
+    pub count: Option<u32>,
+    /// Inclusive, compared as wall-clock time.
+    pub until: Option<DateTimeValue>,
+    pub by_second: Vec<u32>,
+    pub by_minute: Vec<u32>,
+    pub by_hour: Vec<u32>,
+    /// `MO`, or `-1FR` for the last Friday of the month (or year).
+    pub by_day: Vec<(Option<i32>, Weekday)>,
+    /// Negative values count from the end of the month.
+    pub by_month_day: Vec<i32>,
+    pub by_year_day: Vec<i32>,
+    pub by_month: Vec<u32>,
+    /// `WKST`, which decides where `FREQ=WEEKLY` intervals begin.
+    pub week_start: Weekday,
+}
+
+/// Parses an `RRULE` value such as `FREQ=MONTHLY;BYDAY=-1FR;COUNT=3`. Errors
+/// point into `text`.
+pub fn parse_rrule(text: &str) -> Result<RRule, ParseError> {
+    let mut rule = RRule {
+        freq: Frequency::Daily,
+        interval: 1,
+        count: None,
+        until: None,
+        by_second: Vec::new(),
+        by_minute: Vec::new(),
+        by_hour: Vec::new(),
+        by_day: Vec::new(),
+        by_month_day: Vec::new(),
+        by_year_day: Vec::new(),
+        by_month: Vec::new(),
+        week_start: Weekday::Mon,
+    };
+    let mut freq = None;
+    let mut at = 0;
+    for part in text.split(';') {
+        let start = at;
+        at += part.len() + 1;
+        let Some((name, value)) = part.split_once('=') else {
+            return Err(ParseError::new(start, "expected `NAME=value`"));
+        };
+        let error = |message: &str| ParseError::new(start + name.len() + 1, message);
+        let list = |min: i32, max: i32, signed: bool| {
+            value
+                .split(',')
+                .map(|n| {
+                    n.parse::<i32>()
+                        .ok()
+                        .filter(|n| {
+                            (min..=max).contains(n) || (signed && (-max..=-min).contains(n))
+                        })
+                        .ok_or_else(|| error(&format!("expected numbers from {min} to {max}")))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let unsigned =
+            |min, max| list(min, max, false).map(|v| v.into_iter().map(|n| n as u32).collect());
+        match name.to_ascii_uppercase().as_str() {
+            "FREQ" => {
+                freq = Some(match value.to_ascii_uppercase().as_str() {
+                    "SECONDLY" => Frequency::Secondly,
+                    "MINUTELY" => Frequency::Minutely,
+                    "HOURLY" => Frequency::Hourly,
+                    "DAILY" => Frequency::Daily,
+                    "WEEKLY" => Frequency::Weekly,
+                    "MONTHLY" => Frequency::Monthly,
+                    "YEARLY" => Frequency::Yearly,
+                    _ => return Err(error("unknown frequency")),
+                })
+            }
+            "INTERVAL" => {
+                rule.interval = value
+                    .parse()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| error("expected a positive interval"))?
+            }
+            "COUNT" => rule.count = Some(value.parse().map_err(|_| error("expected a count"))?),
+            "UNTIL" => {
+                rule.until = Some(parse_date_time(value, None).map_err(|e| error(e.message()))?)
+            }
+            "BYSECOND" => rule.by_second = unsigned(0, 59)?,
+            "BYMINUTE" => rule.by_minute = unsigned(0, 59)?,
+            "BYHOUR" => rule.by_hour = unsigned(0, 23)?,
+            "BYMONTHDAY" => rule.by_month_day = list(1, 31, true)?,
+            "BYYEARDAY" => rule.by_year_day = list(1, 366, true)?,
+            "BYMONTH" => rule.by_month = unsigned(1, 12)?,
+            "BYDAY" => {
+                rule.by_day = value
+                    .split(',')
+                    .map(|day| {
+                        by_day(day).ok_or_else(|| error("expected days such as `MO` or `-1FR`"))
+                    })
+                    .collect::<Result<_, _>>()?
+            }
+            "WKST" => {
+                rule.week_start = weekday(value).ok_or_else(|| error("expected a weekday"))?
+            }
+            "BYWEEKNO" | "BYSETPOS" => {
+                return Err(ParseError::new(start, format!("`{name}` is not supported")));
+            }
+            _ => {
+                return Err(ParseError::new(
+                    start,
+                    format!("unknown rule part `{name}`"),
+                ));
+            }
+        }
+    }
+    rule.freq = freq.ok_or_else(|| ParseError::new(0, "expected `FREQ`"))?;
+    if rule.count.is_some() && rule.until.is_some() {
+        return Err(ParseError::new(0, "`COUNT` and `UNTIL` are exclusive"));
+    }
+    if rule.freq < Frequency::Monthly && rule.by_day.iter().any(|(n, _)| n.is_some()) {
+        return Err(ParseError::new(
+            0,
+            "numbered `BYDAY` needs `FREQ=MONTHLY` or `FREQ=YEARLY`",
+        ));
+    }
+    Ok(rule)
+}
+
+fn weekday(text: &str) -> Option<Weekday> {
+    Some(match text.to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+fn by_day(text: &str) -> Option<(Option<i32>, Weekday)> {
+    let split = text.len().checked_sub(2)?;
+    let (number, day) = (text.get(..split)?, text.get(split..)?);
+    let number = match number {
+        "" => None,
+        n => Some(
+            n.parse::<i32>()
+                .ok()
+                .filter(|n| (1..=53).contains(&n.abs()))?,
+        ),
+    };
+    Some((number, weekday(day)?))
+}
+
+/// Set bits `min..=max`.
+fn all(min: u32, max: u32) -> u64 {
+    (u64::MAX >> (63 - max)) & !((1 << min) - 1)
+}
+
+fn bits(values: impl IntoIterator<Item = u32>) -> u64 {
+    values.into_iter().fold(0, |set, v| set | 1 << v)
+}
+
+impl RRule {
+    /// The rule's occurrences from `start`, which always comes first as
+    /// RFC 5545 asks. Times are wall-clock; the iterator ends after `COUNT`
+    /// or `UNTIL`, and is otherwise endless.
+    pub fn occurrences(&self, start: NaiveDateTime) -> Occurrences {
+        Occurrences {
+            next: Some(start),
+            last: start,
+            rule: Some((self.clone(), self.schedule(start))),
+            start,
+            emitted: 0,
+        }
+    }
+
+    /// What cron can filter: every field but the day ones are exact, and
+    /// the day sets admit at least every day the rule does.
+    fn schedule(&self, start: NaiveDateTime) -> Schedule {
+        let field = |by: &[u32], freq: Frequency, value: u32, max: u32| {
+            if !by.is_empty() {
+                bits(by.iter().copied())
+            } else if self.freq <= freq {
+                all(0, max)
+            } else {
+                1 << value
+            }
+        };
+        let seconds = field(&self.by_second, Frequency::Secondly, start.second(), 59);
+        let minutes = field(&self.by_minute, Frequency::Minutely, start.minute(), 59);
+        let hours = field(&self.by_hour, Frequency::Hourly, start.hour(), 23);
+
+        let any_day = !self.by_day.is_empty()
+            || !self.by_month_day.is_empty()
+            || !self.by_year_day.is_empty();
+        let (mut days_of_month, mut days_of_week) = (all(1, 31), all(0, 6));
+        if !self.by_day.is_empty() {
+            days_of_week = bits(self.by_day.iter().map(|(_, d)| d.num_days_from_sunday()));
+        }
+        if self.by_month_day.iter().all(|d| *d > 0) && !self.by_month_day.is_empty() {
+            days_of_month = bits(self.by_month_day.iter().map(|d| *d as u32));
+        }
+        let mut months = if self.by_month.is_empty() {
+            all(1, 12)
+        } else {
+            bits(self.by_month.iter().copied())
+        };
+        if !any_day {
+            match self.freq {
+                Frequency::Weekly => days_of_week = 1 << start.weekday().num_days_from_sunday(),
+                Frequency::Monthly => days_of_month = 1 << start.day(),
+                Frequency::Yearly => {
+                    days_of_month = 1 << start.day();
+                    if self.by_month.is_empty() {
+                        months = 1 << start.month();
+                    }
+                }
+                _ => {}
+            }
+        }
+        Schedule::from_sets(seconds, minutes, hours, days_of_month, months, days_of_week)
+    }
+
+    /// Whether `t`, which the schedule allows, is in a period `INTERVAL`
+    /// admits and passes the day rules cron cannot express.
+    fn accepts(&self, start: NaiveDateTime, t: NaiveDateTime) -> bool {
+        let truncate = |t: NaiveDateTime, seconds: i64| t.and_utc().timestamp().div_euclid(seconds);
+        let week = |date: NaiveDate| {
+            let back = (date.weekday().num_days_from_monday() + 7
+                - self.week_start.num_days_from_monday())
+                % 7;
+            date.num_days_from_ce() - back as i32
+        };
+        let period = match self.freq {
+            Frequency::Secondly => truncate(t, 1) - truncate(start, 1),
+            Frequency::Minutely => truncate(t, 60) - truncate(start, 60),
+            Frequency::Hourly => truncate(t, 3600) - truncate(start, 3600),
+            Frequency::Daily => i64::from(t.num_days_from_ce() - start.num_days_from_ce()),
+            Frequency::Weekly => i64::from((week(t.date()) - week(start.date())) / 7),
+            Frequency::Monthly => {
+                i64::from((t.year() - start.year()) * 12) + i64::from(t.month())
+                    - i64::from(start.month())
+            }
+            Frequency::Yearly => i64::from(t.year() - start.year()),
+        };
+        period % i64::from(self.interval) == 0 && self.day_matches(t.date())
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let month_length = days_in_month(date) as i32;
+        let year_length = if date.leap_year() { 366 } else { 365 };
+        let day = date.day() as i32;
+        let ordinal = date.ordinal() as i32;
+        let month_day = self.by_month_day.is_empty()
+            || self
+                .by_month_day
+                .iter()
+                .any(|&d| d == day || d == day - month_length - 1);
+        let year_day = self.by_year_day.is_empty()
+            || self
+                .by_year_day
+                .iter()
+                .any(|&d| d == ordinal || d == ordinal - year_length - 1);
+        // Numbered weekdays count within the month, or within the year when
+        // a yearly rule has no `BYMONTH`.
+        let (index, length) = if self.freq == Frequency::Yearly && self.by_month.is_empty() {
+            (ordinal, year_length)
+        } else {
+            (day, month_length)
+        };
+        let week_day = self.by_day.is_empty()
+            || self.by_day.iter().any(|&(n, weekday)| {
+                weekday == date.weekday()
+                    && n.is_none_or(|n| {
+                        n == (index - 1) / 7 + 1 || n == -((length - index) / 7 + 1)
+                    })
+            });
+        month_day && year_day && week_day
+    }
+}
+
+fn days_in_month(date: NaiveDate) -> u32 {
+    let (year, month) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|first| first.pred_opt())
+        .map_or(31, |last| last.day())
+}
+
+/// Candidates the schedule offers but the rule turns down in a row before
+/// the iterator gives up, so that rules no date satisfies still end.
+const MAX_REJECTED: usize = 100_000;
+
+/// The iterator returned by [`RRule::occurrences`].
+#[derive(Debug, Clone)]
+pub struct Occurrences {
+    next: Option<NaiveDateTime>,
+    /// The last candidate considered.
+    last: NaiveDateTime,
+    rule: Option<(RRule, Schedule)>,
+    start: NaiveDateTime,
+    emitted: u32,
+}
+
+impl Occurrences {
+    pub(crate) fn once(start: NaiveDateTime) -> Occurrences {
+        Occurrences {
+            next: Some(start),
+            last: start,
+            rule: None,
+            start,
+            emitted: 0,
+        }
+    }
+
+    pub(crate) fn none() -> Occurrences {
+        Occurrences {
+            next: None,
+            ..Occurrences::once(NaiveDateTime::MIN)
+        }
+    }
+
+    fn advance(&mut self) -> Option<NaiveDateTime> {
+        let (rule, schedule) = self.rule.as_ref()?;
+        for _ in 0..MAX_REJECTED {
+            let candidate = schedule
+                .next_after(&Utc.from_utc_datetime(&self.last))?
+                .naive_utc();
+            self.last = candidate;
+            if rule.accepts(self.start, candidate) {
+                return Some(candidate);
+            }
+        }
+        None
+    }
+}
+
+impl Iterator for Occurrences {
+    type Item = NaiveDateTime;
+
+    fn next(&mut self) -> Option<NaiveDateTime> {
+        let current = self.next.take()?;
+        let ended = self.rule.as_ref().is_some_and(|(rule, _)| {
+            rule.count.is_some_and(|count| self.emitted >= count)
+                || rule
+                    .until
+                    .as_ref()
+                    .is_some_and(|until| current > until.naive())
+        });
+        if ended {
+            return None;
+        }
+        self.emitted += 1;
+        self.next = self.advance();
+        Some(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+    }
+
+    fn dates(rule: &str, start: NaiveDateTime, n: usize) -> Vec<String> {
+        parse_rrule(rule)
+            .unwrap()
+            .occurrences(start)
+            .take(n)
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn occurrences_should_follow_rfc_5545_examples() {
+        let start = at(1997, 9, 2, 9, 0);
+        assert_eq!(
+            dates("FREQ=DAILY;COUNT=3", start, 10),
+            ["1997-09-02 09:00", "1997-09-03 09:00", "1997-09-04 09:00"]
+        );
+        assert_eq!(
+            dates("FREQ=WEEKLY;INTERVAL=2;WKST=SU;BYDAY=TU,TH", start, 4),
+            [
+                "1997-09-02 09:00",
+                "1997-09-04 09:00",
+                "1997-09-16 09:00",
+                "1997-09-18 09:00"
+            ]
+        );
+        assert_eq!(
+            dates(
+                "FREQ=MONTHLY;BYDAY=1FR,-1SU;COUNT=4",
+                at(1997, 9, 5, 9, 0),
+                10
+            ),
+            [
+                "1997-09-05 09:00",
+                "1997-09-28 09:00",
+                "1997-10-03 09:00",
+                "1997-10-26 09:00"
+            ]
+        );
+        assert_eq!(
+            dates("FREQ=MONTHLY;BYMONTHDAY=-3", at(1997, 9, 28, 9, 0), 3),
+            ["1997-09-28 09:00", "1997-10-29 09:00", "1997-11-28 09:00"]
+        );
+        assert_eq!(
+            dates(
+                "FREQ=YEARLY;BYMONTH=1;BYDAY=SU;UNTIL=19980111T090000Z",
+                at(1998, 1, 4, 9, 0),
+                10
+            ),
+            ["1998-01-04 09:00", "1998-01-11 09:00"]
+        );
+        assert_eq!(
+            dates("FREQ=HOURLY;INTERVAL=3;UNTIL=19970902T170000Z", start, 10),
+            ["1997-09-02 09:00", "1997-09-02 12:00", "1997-09-02 15:00"]
+        );
+        assert_eq!(
+            dates(
+                "FREQ=YEARLY;INTERVAL=4;BYMONTH=11;BYDAY=TU;BYMONTHDAY=2,3,4,5,6,7,8",
+                at(1996, 11, 5, 9, 0),
+                3
+            ),
+            ["1996-11-05 09:00", "2000-11-07 09:00", "2004-11-02 09:00"]
+        );
+    }
+
+    #[test]
+    fn defaults_should_come_from_start() {
+        // Yearly on the start's month and day; February 29 only in leap years.
+        assert_eq!(
+            dates("FREQ=YEARLY", at(2024, 2, 29, 8, 30), 3),
+            ["2024-02-29 08:30", "2028-02-29 08:30", "2032-02-29 08:30"]
+        );
+        assert_eq!(
+            dates(
+                "FREQ=MINUTELY;INTERVAL=20;BYHOUR=9,10",
+                at(1997, 9, 2, 9, 0),
+                4
+            ),
+            [
+                "1997-09-02 09:00",
+                "1997-09-02 09:20",
+                "1997-09-02 09:40",
+                "1997-09-02 10:00"
+            ]
+        );
+        assert_eq!(
+            dates("FREQ=YEARLY;BYYEARDAY=1,-1", at(1999, 12, 31, 0, 0), 3),
+            ["1999-12-31 00:00", "2000-01-01 00:00", "2000-12-31 00:00"]
+        );
+        // An impossible rule ends instead of searching forever.
+        assert_eq!(
+            dates(
+                "FREQ=MONTHLY;BYMONTH=2;BYMONTHDAY=30",
+                at(1997, 9, 2, 9, 0),
+                3
+            )
+            .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn parse_rrule_should_report_errors() {
+        let err = parse_rrule("FREQ=DAILY;INTERVAL=0").unwrap_err();
+        assert_eq!(err.offset(), 20);
+        let err = parse_rrule("FREQ=DAILY;BYSETPOS=1").unwrap_err();
+        assert_eq!(err.offset(), 11);
+        assert!(parse_rrule("INTERVAL=2").is_err());
+        assert!(parse_rrule("FREQ=DAILY;BYHOUR=24").is_err());
+        assert!(parse_rrule("FREQ=DAILY;BYDAY=XX").is_err());
+        assert!(parse_rrule("FREQ=WEEKLY;BYDAY=1MO").is_err());
+        assert!(parse_rrule("FREQ=DAILY;COUNT=2;UNTIL=19970101").is_err());
+        assert!(parse_rrule("FREQ=DAILY;FOO=1").is_err());
+        assert!(parse_rrule("FREQ=DAILY;").is_err());
+    }
+}
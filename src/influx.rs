@@ -0,0 +1,303 @@
+use std::fmt;
+use std::str::FromStr;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::dec_int;
+use winnow::combinator::{alt, cut_err, delimited, opt, preceded, repeat, separated};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::{any, none_of, one_of, take_till};
+
+use crate::ParseError;
+
+/// One line of InfluxDB line protocol.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Point {
+    pub measurement: String,
+    pub tags: Vec<(String, String)>,
+    /// At least one.
+    pub fields: Vec<(String, FieldValue)>,
+    /// Nanoseconds since the Unix epoch, unless the writer chose another
+    /// precision.
+    pub timestamp: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    /// A bare number such as `1` or `-1.5e3`.
+    Float(f64),
+    /// `1i`
+    Integer(i64),
+    /// `1u`
+    Unsigned(u64),
+    String(String),
+    Boolean(bool),
+}
+
+impl Point {
+    pub fn tag(&self, key: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn field(&self, key: &str) -> Option<&FieldValue> {
+        self.fields.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+}
+
+impl FromStr for Point {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        point.parse(s).map_err(ParseError::from)
+    }
+}
+
+/// The line protocol, escaped as needed and without a trailing newline.
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&escape(&self.measurement, &[',', ' ']))?;
+        for (key, value) in &self.tags {
+            write!(f, ",{}={}", escape(key, KEY), escape(value, KEY))?;
+        }
+        for (i, (key, value)) in self.fields.iter().enumerate() {
+            let sep = if i == 0 { ' ' } else { ',' };
+            write!(f, "{sep}{}={value}", escape(key, KEY))?;
+        }
+        if let Some(timestamp) = self.timestamp {
+            write!(f, " {timestamp}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldValue::Float(v) => write!(f, "{v}"),
+            FieldValue::Integer(v) => write!(f, "{v}i"),
+            FieldValue::Unsigned(v) => write!(f, "{v}u"),
+            FieldValue::String(s) => {
+                write!(f, "\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+            }
+            FieldValue::Boolean(b) => write!(f, "{b}"),
+        }
+    }
+}
+
+/// What has to be escaped in tag keys, tag values and field keys.
+const KEY: &[char] = &[',', '=', ' '];
+
+fn escape(text: &str, special: &[char]) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if special.contains(&c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Parses a batch of line protocol, one point per line. Blank lines and
+/// `#` comments are skipped. Errors point into `input`.
+pub fn parse_line_protocol(input: &str) -> Result<Vec<Point>, ParseError> {
+    let mut points = Vec::new();
+    let mut offset = 0;
+    for raw in input.split_inclusive('\n') {
+        let start = offset;
+        offset += raw.len();
+        let line = raw.trim_end_matches(['\n', '\r']);
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let at = start + (line.len() - trimmed.len());
+        points.push(
+            trimmed
+                .parse()
+                .map_err(|e: ParseError| ParseError::new(at + e.offset(), e.message()))?,
+        );
+    }
+    Ok(points)
+}
+
+/// Text in which `special` characters must be backslash-escaped. A
+/// backslash before anything else is kept, as InfluxDB does.
+fn escaped<'a>(special: &'a [char]) -> impl FnMut(&mut &str) -> ModalResult<String> + 'a {
+    move |input: &mut &str| {
+        repeat(
+            1..,
+            alt((
+                preceded('\\', one_of(special)).map(String::from),
+                ('\\', any).take().map(String::from),
+                none_of(|c: char| special.contains(&c) || c == '\\').map(String::from),
+            )),
+        )
+        .fold(String::new, |mut s, part| {
+            s.push_str(&part);
+            s
+        })
+        .parse_next(input)
+    }
+}
+
+fn point(input: &mut &str) -> ModalResult<Point> {
+    let measurement = escaped(&[',', ' '])
+        .context(StrContext::Label("measurement"))
+        .parse_next(input)?;
+    let tags = repeat(0.., preceded(',', cut_err(tag))).parse_next(input)?;
+    let fields = preceded(
+        cut_err(' ').context(StrContext::Expected(StrContextValue::Description(
+            "a space before the fields",
+        ))),
+        cut_err(separated(1.., field, ',')),
+    )
+    .parse_next(input)?;
+    let timestamp = opt(preceded(' ', cut_err(dec_int)))
+        .context(StrContext::Label("timestamp"))
+        .parse_next(input)?;
+    Ok(Point {
+        measurement,
+        tags,
+        fields,
+        timestamp,
+    })
+}
+
+fn tag(input: &mut &str) -> ModalResult<(String, String)> {
+    (
+        escaped(KEY).context(StrContext::Label("tag key")),
+        preceded(
+            cut_err('=').context(StrContext::Expected(StrContextValue::CharLiteral('='))),
+            cut_err(escaped(KEY)).context(StrContext::Label("tag value")),
+        ),
+    )
+        .parse_next(input)
+}
+
+fn field(input: &mut &str) -> ModalResult<(String, FieldValue)> {
+    (
+        escaped(KEY).context(StrContext::Label("field key")),
+        preceded(
+            cut_err('=').context(StrContext::Expected(StrContextValue::CharLiteral('='))),
+            cut_err(field_value),
+        ),
+    )
+        .parse_next(input)
+}
+
+fn field_value(input: &mut &str) -> ModalResult<FieldValue> {
+    alt((
+        delimited(
+            '"',
+            repeat(
+                0..,
+                alt((preceded('\\', one_of(['"', '\\'])), none_of('"'))),
+            )
+            .fold(String::new, |mut s, c| {
+                s.push(c);
+                s
+            }),
+            cut_err('"'),
+        )
+        .map(FieldValue::String),
+        take_till(1.., [',', ' ']).verify_map(scalar),
+    ))
+    .context(StrContext::Label("field value"))
+    .context(StrContext::Expected(StrContextValue::Description(
+        "a number, string or boolean",
+    )))
+    .parse_next(input)
+}
+
+fn scalar(text: &str) -> Option<FieldValue> {
+    match text {
+        "t" | "T" | "true" | "True" | "TRUE" => return Some(FieldValue::Boolean(true)),
+        "f" | "F" | "false" | "False" | "FALSE" => return Some(FieldValue::Boolean(false)),
+        _ => {}
+    }
+    if let Some(int) = text.strip_suffix('i') {
+        return int.parse().ok().map(FieldValue::Integer);
+    }
+    if let Some(uint) = text.strip_suffix('u') {
+        return uint.parse().ok().map(FieldValue::Unsigned);
+    }
+    // Rust would also take `inf` and `NaN`, which InfluxDB does not.
+    if !text.starts_with(|c: char| c.is_ascii_digit() || "+-.".contains(c)) {
+        return None;
+    }
+    text.parse()
+        .ok()
+        .filter(|v: &f64| v.is_finite())
+        .map(FieldValue::Float)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_line_protocol_should_work() -> Result<(), ParseError> {
+        let points = parse_line_protocol(
+            "# batch\nweather,location=us-midwest,season=summer temperature=82,humidity=71i,ok=t 1465839830100400200\n\n\
+             disk\\ usage,path=C:\\\\,host=a\\=b\\,c free=1u,note=\"said \\\"hi\\\"\\\\\"\n",
+        )?;
+        assert_eq!(points.len(), 2);
+        let weather = &points[0];
+        assert_eq!(weather.measurement, "weather");
+        assert_eq!(weather.tag("season"), Some("summer"));
+        assert_eq!(weather.field("temperature"), Some(&FieldValue::Float(82.0)));
+        assert_eq!(weather.field("humidity"), Some(&FieldValue::Integer(71)));
+        assert_eq!(weather.field("ok"), Some(&FieldValue::Boolean(true)));
+        assert_eq!(weather.timestamp, Some(1465839830100400200));
+
+        let disk = &points[1];
+        assert_eq!(disk.measurement, "disk usage");
+        assert_eq!(disk.tag("path"), Some("C:\\\\"));
+        assert_eq!(disk.tag("host"), Some("a=b,c"));
+        assert_eq!(disk.field("free"), Some(&FieldValue::Unsigned(1)));
+        assert_eq!(
+            disk.field("note"),
+            Some(&FieldValue::String("said \"hi\"\\".into()))
+        );
+        assert_eq!(disk.timestamp, None);
+        Ok(())
+    }
+
+    #[test]
+    fn points_should_round_trip() -> Result<(), ParseError> {
+        let point = Point {
+            measurement: "cpu load,1m".into(),
+            tags: vec![("host name".into(), "web=1".into())],
+            fields: vec![
+                ("value".into(), FieldValue::Float(0.64)),
+                ("cores".into(), FieldValue::Integer(-4)),
+                ("label".into(), FieldValue::String("a \"b\" c".into())),
+                ("up".into(), FieldValue::Boolean(false)),
+            ],
+            timestamp: Some(1),
+        };
+        let line = point.to_string();
+        assert_eq!(
+            line,
+            "cpu\\ load\\,1m,host\\ name=web\\=1 value=0.64,cores=-4i,label=\"a \\\"b\\\" c\",up=false 1"
+        );
+        assert_eq!(line.parse::<Point>()?, point);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_line_protocol_should_report_errors() {
+        let err = parse_line_protocol("a x=1\nb,t=1\n").unwrap_err();
+        assert_eq!(err.offset(), 11);
+        assert!("a".parse::<Point>().is_err());
+        assert!("a x=".parse::<Point>().is_err());
+        assert!("a x=1.5i".parse::<Point>().is_err());
+        assert!("a x=\"open".parse::<Point>().is_err());
+        assert!("a x=inf".parse::<Point>().is_err());
+        assert!("a x=1 now".parse::<Point>().is_err());
+    }
+}
@@ -0,0 +1,288 @@
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{digit1, multispace0};
+use winnow::combinator::{cut_err, opt, preceded, separated, terminated};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::take_while;
+
+use crate::ParseError;
+
+/// An IPv4 or IPv6 network in CIDR form. The address is always the network
+/// address: host bits are cleared on construction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct IpNet {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpNet {
+    /// Returns `None` if `prefix_len` is longer than the address.
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Option<Self> {
+        let bits = bits(&addr);
+        (prefix_len <= bits).then(|| Self {
+            addr: from_u128(&addr, to_u128(&addr) & mask(bits, prefix_len)),
+            prefix_len,
+        })
+    }
+
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    pub fn netmask(&self) -> IpAddr {
+        from_u128(&self.addr, mask(bits(&self.addr), self.prefix_len))
+    }
+
+    /// The highest address in the network (the broadcast address for IPv4).
+    pub fn last(&self) -> IpAddr {
+        let bits = bits(&self.addr);
+        from_u128(
+            &self.addr,
+            to_u128(&self.addr) | (!mask(bits, self.prefix_len) & mask(bits, bits)),
+        )
+    }
+
+    /// Whether `addr` is in this network. IPv4-mapped IPv6 addresses such
+    /// as `::ffff:10.0.0.1` match IPv4 networks.
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        let addr = match (self.addr, addr) {
+            (IpAddr::V4(_), IpAddr::V6(v6)) => v6.to_canonical(),
+            _ => addr,
+        };
+        addr.is_ipv4() == self.addr.is_ipv4()
+            && to_u128(&addr) & mask(bits(&addr), self.prefix_len) == to_u128(&self.addr)
+    }
+}
+
+impl fmt::Display for IpNet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+impl FromStr for IpNet {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_ipnet(s)
+    }
+}
+
+impl From<IpAddr> for IpNet {
+    fn from(addr: IpAddr) -> Self {
+        Self {
+            addr,
+            prefix_len: bits(&addr),
+        }
+    }
+}
+
+/// Parses `10.0.0.0/8`, `2001:db8::/32`, or a bare address, which becomes
+/// a single-host network.
+pub fn parse_ipnet(input: &str) -> Result<IpNet, ParseError> {
+    cidr.parse(input.trim()).map_err(ParseError::from)
+}
+
+/// Parses a comma-separated list of networks, addresses and inclusive
+/// ranges (`192.168.0.1-192.168.0.50`). Ranges are split into the fewest
+/// networks that cover them exactly.
+pub fn parse_ipnet_list(input: &str) -> Result<Vec<IpNet>, ParseError> {
+    preceded(
+        multispace0,
+        separated(1.., terminated(entry, multispace0), (',', multispace0)),
+    )
+    .map(|entries: Vec<Vec<IpNet>>| entries.concat())
+    .parse(input)
+    .map_err(ParseError::from)
+}
+
+fn entry(input: &mut &str) -> ModalResult<Vec<IpNet>> {
+    let start = addr.parse_next(input)?;
+    if let Some(prefix_len) = opt(preceded('/', cut_err(prefix_len))).parse_next(input)? {
+        return net(input, start, prefix_len).map(|net| vec![net]);
+    }
+    let Some(end) =
+        opt(preceded((multispace0, '-', multispace0), cut_err(addr))).parse_next(input)?
+    else {
+        return Ok(vec![IpNet::from(start)]);
+    };
+    if start.is_ipv4() != end.is_ipv4() || to_u128(&start) > to_u128(&end) {
+        return cut_err(winnow::combinator::fail)
+            .context(StrContext::Label("range"))
+            .context(StrContext::Expected(StrContextValue::Description(
+                "an end address of the same family, not below the start",
+            )))
+            .parse_next(input);
+    }
+    Ok(split_range(start, end))
+}
+
+pub(crate) fn cidr(input: &mut &str) -> ModalResult<IpNet> {
+    let addr = addr.parse_next(input)?;
+    match opt(preceded('/', cut_err(prefix_len))).parse_next(input)? {
+        Some(prefix_len) => net(input, addr, prefix_len),
+        None => Ok(IpNet::from(addr)),
+    }
+}
+
+pub(crate) fn addr(input: &mut &str) -> ModalResult<IpAddr> {
+    take_while(1.., |c: char| c.is_ascii_hexdigit() || c == ':' || c == '.')
+        .try_map(|s: &str| {
+            s.parse::<Ipv4Addr>()
+                .map(IpAddr::V4)
+                .or_else(|_| s.parse::<Ipv6Addr>().map(IpAddr::V6))
+        })
+        .context(StrContext::Label("IP address"))
+        .parse_next(input)
+}
+
+fn prefix_len(input: &mut &str) -> ModalResult<u8> {
+    digit1
+        .parse_to()
+        .context(StrContext::Label("prefix length"))
+        .parse_next(input)
+}
+
+fn net(input: &mut &str, addr: IpAddr, prefix_len: u8) -> ModalResult<IpNet> {
+    match IpNet::new(addr, prefix_len) {
+        Some(net) => Ok(net),
+        None => cut_err(winnow::combinator::fail)
+            .context(StrContext::Label("prefix length"))
+            .context(StrContext::Expected(StrContextValue::Description(
+                if addr.is_ipv4() {
+                    "at most 32"
+                } else {
+                    "at most 128"
+                },
+            )))
+            .parse_next(input),
+    }
+}
+
+/// Covers `start..=end` with the largest aligned blocks that fit.
+fn split_range(start: IpAddr, end: IpAddr) -> Vec<IpNet> {
+    let bits = bits(&start);
+    let (mut lo, hi) = (to_u128(&start), to_u128(&end));
+    let mut nets = Vec::new();
+    loop {
+        // The block may grow while `lo` stays aligned and it ends by `hi`.
+        let mut size = lo.trailing_zeros().min(u32::from(bits));
+        while size > 0 && lo.checked_add(span(size)).is_none_or(|last| last > hi) {
+            size -= 1;
+        }
+        nets.push(IpNet {
+            addr: from_u128(&start, lo),
+            prefix_len: bits - size as u8,
+        });
+        let last = lo + span(size);
+        if last >= hi {
+            return nets;
+        }
+        lo = last + 1;
+    }
+}
+
+fn bits(addr: &IpAddr) -> u8 {
+    if addr.is_ipv4() { 32 } else { 128 }
+}
+
+/// A mask of the top `prefix_len` bits out of `bits`.
+fn mask(bits: u8, prefix_len: u8) -> u128 {
+    match prefix_len {
+        0 => 0,
+        n => (u128::MAX << (128 - n)) >> (128 - bits),
+    }
+}
+
+/// The number of addresses in a block of `2^size`, minus one.
+fn span(size: u32) -> u128 {
+    match size {
+        0 => 0,
+        n => u128::MAX >> (128 - n),
+    }
+}
+
+fn to_u128(addr: &IpAddr) -> u128 {
+    match addr {
+        IpAddr::V4(v4) => u32::from(*v4).into(),
+        IpAddr::V6(v6) => u128::from(*v6),
+    }
+}
+
+fn from_u128(like: &IpAddr, value: u128) -> IpAddr {
+    match like {
+        IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::from(value as u32)),
+        IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::from(value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_ipnet_should_work() -> Result<(), ParseError> {
+        let net = parse_ipnet("10.1.2.3/8")?;
+        assert_eq!(net.to_string(), "10.0.0.0/8");
+        assert_eq!(net.netmask(), ip("255.0.0.0"));
+        assert_eq!(net.last(), ip("10.255.255.255"));
+        assert!(net.contains(ip("10.200.0.1")));
+        assert!(net.contains(ip("::ffff:10.0.0.1")));
+        assert!(!net.contains(ip("11.0.0.1")));
+
+        let net: IpNet = "2001:db8::/32".parse()?;
+        assert!(net.contains(ip("2001:db8:ffff::1")));
+        assert!(!net.contains(ip("2001:db9::1")));
+        assert_eq!(net.last(), ip("2001:db8:ffff:ffff:ffff:ffff:ffff:ffff"));
+
+        assert_eq!(parse_ipnet("::1")?.prefix_len(), 128);
+        assert!(parse_ipnet("0.0.0.0/0")?.contains(ip("8.8.8.8")));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_ipnet_list_should_split_ranges() -> Result<(), ParseError> {
+        let nets = parse_ipnet_list("192.168.0.1-192.168.0.50, 10.0.0.0/8,::1")?;
+        let shown: Vec<_> = nets.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            shown,
+            [
+                "192.168.0.1/32",
+                "192.168.0.2/31",
+                "192.168.0.4/30",
+                "192.168.0.8/29",
+                "192.168.0.16/28",
+                "192.168.0.32/28",
+                "192.168.0.48/31",
+                "192.168.0.50/32",
+                "10.0.0.0/8",
+                "::1/128",
+            ]
+        );
+        let all = parse_ipnet_list("0.0.0.0 - 255.255.255.255")?;
+        assert_eq!(all, [parse_ipnet("0.0.0.0/0")?]);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_ipnet_should_report_errors() {
+        assert_eq!(parse_ipnet("10.0.0.0/33").unwrap_err().offset(), 11);
+        assert!(parse_ipnet("10.0.0/8").is_err());
+        assert!(parse_ipnet("2001:db8::/129").is_err());
+        assert!(parse_ipnet_list("10.0.0.9-10.0.0.1").is_err());
+        assert!(parse_ipnet_list("10.0.0.1-::2").is_err());
+        assert!(parse_ipnet_list("10.0.0.1,").is_err());
+    }
+}
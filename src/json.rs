@@ -1,19 +1,24 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
 
-use winnow::Parser;
-use winnow::Result;
+use serde::Serialize;
+
 use winnow::ascii::digit1;
 use winnow::ascii::multispace0;
-use winnow::combinator::separated;
-use winnow::combinator::separated_pair;
-use winnow::combinator::trace;
-use winnow::combinator::{alt, delimited, opt};
-use winnow::error::{ContextError, ErrMode, ParserError};
-use winnow::stream::{AsChar, Stream, StreamIsPartial};
+use winnow::combinator::{alt, cut_err, delimited, opt, preceded, terminated};
+use winnow::error::{ContextError, ErrMode, StrContext, StrContextValue};
+use winnow::stream::{Compare, FindSlice, LocatingSlice, Location, Stream, StreamIsPartial};
 use winnow::token::take_until;
+use winnow::{ModalResult, Parser};
 
-#[derive(Debug, Clone, PartialEq)]
-enum JsonValue {
+use crate::ParseError;
+pub use crate::combinators::sep_with_space;
+use crate::combinators::{Located, SyncSet, located, parse_separated_with_recovery, recover_until};
+use crate::predicate::{Resolver, Value};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum JsonValue {
     Null,
     Bool(bool),
     Number(f64),
@@ -22,38 +27,135 @@ enum JsonValue {
     Object(HashMap<String, JsonValue>),
 }
 
-fn main() -> anyhow::Result<()> {
-    let s = r#"{
-        "name": "John Doe",
-        "age": 30,
-        "is_student": false,
-        "marks": [90.0, -80.0, 85.1],
-        "address": {
-            "city": "New York",
-            "zip": 10001
+impl JsonValue {
+    /// Follows a dotted path of object keys and array indexes, e.g.
+    /// `items.0.name`. An empty path is the value itself.
+    pub fn get_path(&self, path: &str) -> Option<&JsonValue> {
+        if path.is_empty() {
+            return Some(self);
         }
-    }"#;
+        path.split('.').try_fold(self, |value, key| match value {
+            JsonValue::Object(map) => map.get(key),
+            JsonValue::Array(items) => items.get(key.parse::<usize>().ok()?),
+            _ => None,
+        })
+    }
+}
 
-    let input = &mut (&*s);
-    let v = parse_json(input)?;
-    println!("{:#?}", v);
-    Ok(())
+/// A JSON value that remembers where each of its parts is in the document,
+/// as produced by [`parse_json_located`]. Object members keep their order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonNode {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Located<JsonNode>>),
+    Object(Vec<(Located<String>, Located<JsonNode>)>),
 }
 
-fn parse_json(input: &str) -> anyhow::Result<JsonValue> {
-    let input = &mut (&*input);
-    parse_value(input).map_err(|e: ContextError| anyhow::anyhow!("Failed to parse JSON: {}", e))
+impl From<JsonNode> for JsonValue {
+    fn from(node: JsonNode) -> Self {
+        match node {
+            JsonNode::Null => JsonValue::Null,
+            JsonNode::Bool(b) => JsonValue::Bool(b),
+            JsonNode::Number(n) => JsonValue::Number(n),
+            JsonNode::String(s) => JsonValue::String(s),
+            JsonNode::Array(items) => {
+                JsonValue::Array(items.into_iter().map(|item| item.node.into()).collect())
+            }
+            JsonNode::Object(members) => JsonValue::Object(
+                members
+                    .into_iter()
+                    .map(|(key, value)| (key.node, value.node.into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Fields are dotted paths as in [`JsonValue::get_path`]. Arrays and
+/// objects resolve to nothing; predicates address their members instead.
+impl Resolver for JsonValue {
+    fn resolve(&self, field: &str) -> Option<Value<'_>> {
+        Some(match self.get_path(field)? {
+            JsonValue::Null => Value::Null,
+            JsonValue::Bool(b) => Value::Bool(*b),
+            JsonValue::Number(n) => Value::Number(*n),
+            JsonValue::String(s) => Value::String(s.as_str().into()),
+            JsonValue::Array(_) | JsonValue::Object(_) => return None,
+        })
+    }
+}
+
+/// Parses a complete JSON document, allowing surrounding whitespace.
+pub fn parse_json(input: &str) -> Result<JsonValue, ParseError> {
+    delimited(multispace0, document(parse_value), multispace0)
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+/// Like [`parse_json`], but keeps the span of every value and key. Spans
+/// of arrays and objects run from the opening to the closing bracket.
+pub fn parse_json_located(input: &str) -> Result<Located<JsonNode>, ParseError> {
+    delimited(multispace0, document(located_value), multispace0)
+        .parse(LocatingSlice::new(input))
+        .map_err(ParseError::from)
+}
+
+/// Checks a whole document and reports every error in it rather than just
+/// the first: a malformed array element or object member is skipped up to
+/// the next `,` or closing bracket, and checking carries on from there. An
+/// empty result means `input` is valid JSON.
+pub fn validate_json(input: &str) -> Vec<ParseError> {
+    let errors = RefCell::new(Vec::new());
+    let mut stream = LocatingSlice::new(input);
+    let checked = document(|i: &mut LocatingSlice<&str>| checked_value(i, &errors));
+    let mut checked = (
+        multispace0,
+        recover_until(checked, SyncSet::new(&[]), &errors),
+        multispace0,
+    );
+    if checked.parse_next(&mut stream).is_ok() && !stream.is_empty() {
+        let offset = stream.current_token_start();
+        let mut errors = errors.borrow_mut();
+        if errors.last().is_none_or(|last| last.offset() != offset) {
+            errors.push(ParseError::new(offset, "expected end of document"));
+        }
+    }
+    drop(checked);
+    errors.into_inner()
 }
 
-fn parse_null(input: &mut &str) -> Result<()> {
+/// What the scalar parsers need of their input, so the plain and the
+/// located grammar can share them.
+trait Text<'i>:
+    Stream<Token = char, Slice = &'i str>
+    + StreamIsPartial
+    + Compare<&'static str>
+    + Compare<char>
+    + FindSlice<char>
+{
+}
+
+impl<'i, I> Text<'i> for I where
+    I: Stream<Token = char, Slice = &'i str>
+        + StreamIsPartial
+        + Compare<&'static str>
+        + Compare<char>
+        + FindSlice<char>
+{
+}
+
+fn parse_null<'i>(input: &mut impl Text<'i>) -> ModalResult<()> {
     "null".value(()).parse_next(input)
 }
 
-fn parse_bool(input: &mut &str) -> Result<bool> {
+fn parse_bool<'i>(input: &mut impl Text<'i>) -> ModalResult<bool> {
     alt(("true", "false")).parse_to().parse_next(input)
 }
 
-fn parse_num(input: &mut &str) -> Result<f64> {
+fn parse_num<'i>(input: &mut impl Text<'i>) -> ModalResult<f64> {
     let sign = opt("-").map(|s| s.is_some()).parse_next(input)?;
     let num = digit1.parse_to::<i64>().parse_next(input)?;
     let ret: Result<(), ErrMode<ContextError>> = ".".value(()).parse_next(input);
@@ -82,50 +184,22 @@ fn parse_num(input: &mut &str) -> Result<f64> {
     Ok(v as _)
 }
 
-fn parse_string(input: &mut &str) -> Result<String> {
+fn parse_string<'i>(input: &mut impl Text<'i>) -> ModalResult<String> {
     let ret = delimited('"', take_until(0.., '"'), '"').parse_next(input)?;
     Ok(ret.to_string())
 }
 
-pub fn sep_with_space<Input, Output, Error, ParseNext>(
-    mut parser: ParseNext,
-) -> impl Parser<Input, (), Error>
-where
-    Input: Stream + StreamIsPartial,
-    <Input as Stream>::Token: AsChar + Clone,
-    Error: ParserError<Input>,
-    ParseNext: Parser<Input, Output, Error>,
-{
-    trace("sep_with_space", move |input: &mut Input| {
-        let _ = multispace0.parse_next(input)?;
-        parser.parse_next(input)?;
-        multispace0.parse_next(input)?;
-        Ok(())
-    })
-}
-
-fn parse_array(input: &mut &str) -> Result<Vec<JsonValue>> {
-    let left = sep_with_space('[');
-    let right = sep_with_space(']');
-    let separator = sep_with_space(',');
-    let parse_values = separated(0.., parse_value, separator);
-    delimited(left, parse_values, right).parse_next(input)
+fn parse_array(input: &mut &str) -> ModalResult<Vec<JsonValue>> {
+    preceded('[', items(element(parse_value), "array", ']')).parse_next(input)
 }
 
-fn parse_object(input: &mut &str) -> Result<HashMap<String, JsonValue>> {
-    let left = sep_with_space('{');
-    let right = sep_with_space('}');
-    let pair_separator = sep_with_space(',');
-    let key_value_separator = sep_with_space(':');
-
-    let parse_kv_pair = separated_pair(parse_string, key_value_separator, parse_value);
-
-    let parse_kv = separated(1.., parse_kv_pair, pair_separator);
-
-    delimited(left, parse_kv, right).parse_next(input)
+fn parse_object(input: &mut &str) -> ModalResult<HashMap<String, JsonValue>> {
+    preceded('{', items(member(parse_string, parse_value), "object", '}'))
+        .map(|members: Vec<_>| members.into_iter().collect())
+        .parse_next(input)
 }
 
-fn parse_value(input: &mut &str) -> Result<JsonValue> {
+fn parse_value(input: &mut &str) -> ModalResult<JsonValue> {
     alt((
         parse_null.value(JsonValue::Null),
         parse_bool.map(JsonValue::Bool),
@@ -137,12 +211,149 @@ fn parse_value(input: &mut &str) -> Result<JsonValue> {
     .parse_next(input)
 }
 
+fn located_value(input: &mut LocatingSlice<&str>) -> ModalResult<Located<JsonNode>> {
+    located(alt((
+        parse_null.value(JsonNode::Null),
+        parse_bool.map(JsonNode::Bool),
+        parse_num.map(JsonNode::Number),
+        parse_string.map(JsonNode::String),
+        preceded('[', items(element(located_value), "array", ']')).map(JsonNode::Array),
+        preceded(
+            '{',
+            items(member(located(parse_string), located_value), "object", '}'),
+        )
+        .map(JsonNode::Object),
+    )))
+    .parse_next(input)
+}
+
+fn document<'i, I: Text<'i>, O>(
+    value: impl Parser<I, O, ErrMode<ContextError>>,
+) -> impl Parser<I, O, ErrMode<ContextError>> {
+    value
+        .context(StrContext::Label("JSON document"))
+        .context(StrContext::Expected(StrContextValue::Description("value")))
+}
+
+fn element<'i, I: Text<'i>, O>(
+    value: impl Parser<I, O, ErrMode<ContextError>>,
+) -> impl Parser<I, O, ErrMode<ContextError>> {
+    cut_err(value)
+        .context(StrContext::Label("array element"))
+        .context(StrContext::Expected(StrContextValue::Description("value")))
+}
+
+fn member<'i, I: Text<'i>, K, V>(
+    key: impl Parser<I, K, ErrMode<ContextError>>,
+    value: impl Parser<I, V, ErrMode<ContextError>>,
+) -> impl Parser<I, (K, V), ErrMode<ContextError>> {
+    (
+        cut_err(key)
+            .context(StrContext::Label("object key"))
+            .context(StrContext::Expected(StrContextValue::Description("string"))),
+        cut_err(sep_with_space(':'))
+            .context(StrContext::Label("object member"))
+            .context(StrContext::Expected(StrContextValue::CharLiteral(':'))),
+        cut_err(value)
+            .context(StrContext::Label("object value"))
+            .context(StrContext::Expected(StrContextValue::Description("value"))),
+    )
+        .map(|(key, (), value)| (key, value))
+}
+
+/// The comma-separated items of an array or object, after its opening
+/// bracket and up to and including `close`. White space after `close` is
+/// left alone, so that it stays out of located spans.
+fn items<'i, I: Text<'i>, O>(
+    mut item: impl Parser<I, O, ErrMode<ContextError>>,
+    label: &'static str,
+    close: char,
+) -> impl Parser<I, Vec<O>, ErrMode<ContextError>> {
+    move |input: &mut I| {
+        let mut items = Vec::new();
+        multispace0.parse_next(input)?;
+        if opt(close).parse_next(input)?.is_some() {
+            return Ok(items);
+        }
+        loop {
+            items.push(item.parse_next(input)?);
+            multispace0.parse_next(input)?;
+            if opt(',').parse_next(input)?.is_none() {
+                break;
+            }
+            multispace0.parse_next(input)?;
+        }
+        cut_err(delimiter(close, label, close)).parse_next(input)?;
+        Ok(items)
+    }
+}
+
+/// `c` in a container closed by `close`; where it is missing, a `,` or
+/// `close` could have gone.
+fn delimiter<'i, I: Text<'i>>(
+    c: char,
+    label: &'static str,
+    close: char,
+) -> impl Parser<I, char, ErrMode<ContextError>> {
+    c.context(StrContext::Label(label))
+        .context(StrContext::Expected(StrContextValue::CharLiteral(',')))
+        .context(StrContext::Expected(StrContextValue::CharLiteral(close)))
+}
+
+fn checked_value(
+    input: &mut LocatingSlice<&str>,
+    errors: &RefCell<Vec<ParseError>>,
+) -> ModalResult<()> {
+    const ARRAY: SyncSet = SyncSet::new(&[',', ']']).quotes(&['"']);
+    const OBJECT: SyncSet = SyncSet::new(&[',', '}']).quotes(&['"']);
+    let value = |i: &mut LocatingSlice<&str>| checked_value(i, errors);
+    alt((
+        parse_null,
+        parse_bool.void(),
+        parse_num.void(),
+        parse_string.void(),
+        preceded(
+            '[',
+            checked_items(element(value), "array", ']', ARRAY, errors),
+        ),
+        preceded(
+            '{',
+            checked_items(member(parse_string, value), "object", '}', OBJECT, errors),
+        ),
+    ))
+    .parse_next(input)
+}
+
+/// [`items`] for [`validate_json`], which records malformed items and moves
+/// on to the next one.
+fn checked_items<'i, O>(
+    item: impl Parser<LocatingSlice<&'i str>, O, ErrMode<ContextError>>,
+    label: &'static str,
+    close: char,
+    sync: SyncSet,
+    errors: &RefCell<Vec<ParseError>>,
+) -> impl Parser<LocatingSlice<&'i str>, (), ErrMode<ContextError>> {
+    let items = parse_separated_with_recovery(
+        terminated(item, multispace0),
+        (delimiter(',', label, close), multispace0),
+        sync,
+        errors,
+    );
+    preceded(
+        multispace0,
+        alt((
+            close.void(),
+            (items, cut_err(delimiter(close, label, close))).void(),
+        )),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn test_parse_null() -> Result<(), ContextError> {
+    fn test_parse_null() -> ModalResult<()> {
         let s = "null";
         let input = &mut (&*s);
         parse_null(input)?;
@@ -151,7 +362,7 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_bool() -> Result<(), ContextError> {
+    fn test_parse_bool() -> ModalResult<()> {
         let input = "true";
         let result = parse_bool(&mut (&*input))?;
         assert!(result);
@@ -164,7 +375,7 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_num() -> Result<(), ContextError> {
+    fn test_parse_num() -> ModalResult<()> {
         let input = "123";
         let result = parse_num(&mut (&*input))?;
         assert_eq!(result, 123.0);
@@ -193,7 +404,7 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_string() -> Result<(), ContextError> {
+    fn test_parse_string() -> ModalResult<()> {
         let input = r#""hello""#;
         let result = parse_string(&mut (&*input))?;
         assert_eq!(result, "hello");
@@ -202,7 +413,7 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_array() -> Result<(), ContextError> {
+    fn test_parse_array() -> ModalResult<()> {
         let input = r#"[1, 2, 3]"#;
         let result = parse_array(&mut (&*input))?;
 
@@ -229,7 +440,7 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_object() -> Result<(), ContextError> {
+    fn test_parse_object() -> ModalResult<()> {
         let input = r#"{"a": 1, "b": 2}"#;
         let result = parse_object(&mut (&*input))?;
         let mut expected = HashMap::new();
@@ -253,4 +464,92 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_parse_json() -> anyhow::Result<()> {
+        let input = "\n  [true, null]\n";
+        let result = parse_json(input)?;
+        assert_eq!(
+            result,
+            JsonValue::Array(vec![JsonValue::Bool(true), JsonValue::Null])
+        );
+
+        let input = "{\"a\": 1,\n \"b\": }";
+        let err = parse_json(input).unwrap_err();
+        assert_eq!(err.line_col(input), (2, 7));
+        assert_eq!(err.message(), "expected value while parsing object value");
+
+        let err = parse_json("[1, 2 3]").unwrap_err();
+        assert_eq!(err.offset(), 6);
+        assert_eq!(err.message(), "expected ',' or ']' while parsing array");
+        assert_eq!(parse_json("{ }")?, JsonValue::Object(HashMap::new()));
+        let err = parse_json("nul").unwrap_err();
+        assert_eq!(err.message(), "expected value while parsing JSON document");
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_json_located_should_work() -> anyhow::Result<()> {
+        let input = r#" {"a": [1, true ], "b": null} "#;
+        let root = parse_json_located(input)?;
+        assert_eq!(root.span, 1..29);
+        let JsonNode::Object(members) = &root.node else {
+            panic!("expected an object, got {:?}", root.node);
+        };
+        let (key, value) = &members[0];
+        assert_eq!((key.span.clone(), value.span.clone()), (2..5, 7..17));
+        let JsonNode::Array(items) = &value.node else {
+            panic!("expected an array, got {:?}", value.node);
+        };
+        assert_eq!(&input[items[1].span.clone()], "true");
+        assert_eq!(JsonValue::from(root.node), parse_json(input)?);
+
+        let err = parse_json_located("[1, ]").unwrap_err();
+        assert_eq!(err.offset(), 4);
+        assert_eq!(err.message(), "expected value while parsing array element");
+        Ok(())
+    }
+
+    #[test]
+    fn validate_json_should_report_every_error() {
+        let input = r#"{"a": [1, x, 3], "b" 2, "c": {"d": ], "e": "]"}, "f": true} x"#;
+        let errors: Vec<(usize, String)> = validate_json(input)
+            .into_iter()
+            .map(|e| (e.offset(), e.message().to_string()))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                (10, "expected value while parsing array element".to_string()),
+                (21, "expected ':' while parsing object member".to_string()),
+                (35, "expected value while parsing object value".to_string()),
+                (60, "expected end of document".to_string()),
+            ]
+        );
+
+        assert!(validate_json(r#" {"a": [1, {"b": null}], "c": "d"} "#).is_empty());
+        let errors = validate_json("[[1, 2");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].offset(), 6);
+        assert_eq!(
+            errors[0].message(),
+            "expected ',' or ']' while parsing array"
+        );
+    }
+
+    #[test]
+    fn json_value_should_resolve_paths() -> anyhow::Result<()> {
+        let value = parse_json(r#"{"user": {"name": "ann", "roles": ["admin"]}, "age": 41}"#)?;
+        assert_eq!(
+            value.get_path("user.roles.0"),
+            Some(&JsonValue::String("admin".to_string()))
+        );
+        assert_eq!(value.get_path("user.roles.1"), None);
+        let predicate = crate::predicate::parse_predicate(
+            "age > 40 && user.name in ['ann', 'bob'] && !user.roles",
+        )?;
+        assert!(predicate.eval(&value));
+        Ok(())
+    }
 }
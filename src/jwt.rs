@@ -0,0 +1,210 @@
+use crate::ParseError;
+use crate::codec::BASE64_URL_NO_PAD;
+use crate::json::{JsonValue, parse_json};
+
+/// A JSON Web Token, decoded but not verified.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Jwt {
+    pub header: Header,
+    pub claims: Claims,
+    /// The raw signature bytes; checking them is up to the caller.
+    pub signature: Vec<u8>,
+}
+
+/// The JOSE header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Header {
+    /// `alg`, e.g. `RS256`, or `none` for unsecured tokens.
+    pub alg: String,
+    pub typ: Option<String>,
+    pub kid: Option<String>,
+    /// The whole header object.
+    pub json: JsonValue,
+}
+
+/// The registered claims of RFC 7519 plus the full claims object. Dates
+/// are Unix seconds, with any fraction dropped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Claims {
+    pub iss: Option<String>,
+    pub sub: Option<String>,
+    /// `aud` may be a string or an array of them; a string becomes a
+    /// single entry.
+    pub aud: Vec<String>,
+    pub exp: Option<i64>,
+    pub nbf: Option<i64>,
+    pub iat: Option<i64>,
+    pub jti: Option<String>,
+    pub json: JsonValue,
+}
+
+impl Claims {
+    /// A claim by name, registered or not.
+    pub fn get(&self, name: &str) -> Option<&JsonValue> {
+        match &self.json {
+            JsonValue::Object(claims) => claims.get(name),
+            _ => None,
+        }
+    }
+
+    /// Whether `now` falls outside `nbf..exp`. Missing bounds are open.
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.exp.is_some_and(|exp| now >= exp) || self.nbf.is_some_and(|nbf| now < nbf)
+    }
+}
+
+/// Decodes a compact JWS token, `header.claims.signature`, without checking
+/// the signature: use it to route or inspect a token, never to trust one.
+/// Errors point at the segment that is wrong.
+pub fn decode_unverified(token: &str) -> Result<Jwt, ParseError> {
+    let token = token.trim();
+    let mut segments = token.split('.');
+    let (Some(header), Some(claims), Some(signature), None) = (
+        segments.next(),
+        segments.next(),
+        segments.next(),
+        segments.next(),
+    ) else {
+        return Err(ParseError::new(0, "expected three `.`-separated segments"));
+    };
+    let claims_at = header.len() + 1;
+    let signature_at = claims_at + claims.len() + 1;
+
+    let header_json = object(header, 0, "header")?;
+    let claims_json = object(claims, claims_at, "claims")?;
+    let signature = BASE64_URL_NO_PAD
+        .decode(signature)
+        .map_err(|e| ParseError::new(signature_at + e.offset(), "signature is not base64url"))?;
+
+    let error =
+        |at: usize, name: &str, what: &str| ParseError::new(at, format!("`{name}` must be {what}"));
+    let header_string = |name: &str| match field(&header_json, name) {
+        None => Ok(None),
+        Some(JsonValue::String(s)) => Ok(Some(s.clone())),
+        Some(_) => Err(error(0, name, "a string")),
+    };
+    let claim_string = |name: &str| match field(&claims_json, name) {
+        None => Ok(None),
+        Some(JsonValue::String(s)) => Ok(Some(s.clone())),
+        Some(_) => Err(error(claims_at, name, "a string")),
+    };
+    let claim_date = |name: &str| match field(&claims_json, name) {
+        None => Ok(None),
+        Some(JsonValue::Number(n)) if n.is_finite() => Ok(Some(n.floor() as i64)),
+        Some(_) => Err(error(claims_at, name, "a number")),
+    };
+    let aud = match field(&claims_json, "aud") {
+        None => Vec::new(),
+        Some(JsonValue::String(s)) => vec![s.clone()],
+        Some(JsonValue::Array(items)) => items
+            .iter()
+            .map(|item| match item {
+                JsonValue::String(s) => Ok(s.clone()),
+                _ => Err(error(claims_at, "aud", "a string or an array of strings")),
+            })
+            .collect::<Result<_, _>>()?,
+        Some(_) => return Err(error(claims_at, "aud", "a string or an array of strings")),
+    };
+
+    Ok(Jwt {
+        header: Header {
+            alg: header_string("alg")?.ok_or_else(|| ParseError::new(0, "header has no `alg`"))?,
+            typ: header_string("typ")?,
+            kid: header_string("kid")?,
+            json: header_json.clone(),
+        },
+        claims: Claims {
+            iss: claim_string("iss")?,
+            sub: claim_string("sub")?,
+            aud,
+            exp: claim_date("exp")?,
+            nbf: claim_date("nbf")?,
+            iat: claim_date("iat")?,
+            jti: claim_string("jti")?,
+            json: claims_json.clone(),
+        },
+        signature,
+    })
+}
+
+fn field<'a>(json: &'a JsonValue, name: &str) -> Option<&'a JsonValue> {
+    match json {
+        JsonValue::Object(map) => map.get(name),
+        _ => None,
+    }
+}
+
+/// A base64url segment holding a JSON object.
+fn object(segment: &str, at: usize, what: &str) -> Result<JsonValue, ParseError> {
+    let bytes = BASE64_URL_NO_PAD
+        .decode(segment)
+        .map_err(|e| ParseError::new(at + e.offset(), format!("{what} is not base64url")))?;
+    let text = String::from_utf8(bytes)
+        .map_err(|_| ParseError::new(at, format!("{what} is not UTF-8")))?;
+    match parse_json(&text) {
+        Ok(json @ JsonValue::Object(_)) => Ok(json),
+        Ok(_) => Err(ParseError::new(at, format!("{what} is not a JSON object"))),
+        Err(e) => Err(ParseError::new(at, format!("{what}: {}", e.message()))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The example token from RFC 7519, section 3.1.
+    const TOKEN: &str = "eyJ0eXAiOiJKV1QiLA0KICJhbGciOiJIUzI1NiJ9\
+        .eyJpc3MiOiJqb2UiLA0KICJleHAiOjEzMDA4MTkzODAsDQogImh0dHA6Ly9leGFtcGxlLmNvbS9pc19yb290Ijp0cnVlfQ\
+        .dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+
+    #[test]
+    fn decode_unverified_should_work() -> Result<(), ParseError> {
+        let jwt = decode_unverified(TOKEN)?;
+        assert_eq!(jwt.header.alg, "HS256");
+        assert_eq!(jwt.header.typ.as_deref(), Some("JWT"));
+        assert_eq!(jwt.header.kid, None);
+        assert_eq!(jwt.claims.iss.as_deref(), Some("joe"));
+        assert_eq!(jwt.claims.exp, Some(1300819380));
+        assert_eq!(
+            jwt.claims.get("http://example.com/is_root"),
+            Some(&JsonValue::Bool(true))
+        );
+        assert_eq!(jwt.signature.len(), 32);
+        assert!(jwt.claims.is_expired(1300819380));
+        assert!(!jwt.claims.is_expired(1300819379));
+        Ok(())
+    }
+
+    #[test]
+    fn audience_may_be_a_string_or_an_array() -> Result<(), ParseError> {
+        // {"alg":"none"} . {"aud":["a","b"],"sub":"1","iat":1.9,"nbf":10}
+        let jwt = decode_unverified(
+            "eyJhbGciOiJub25lIn0.eyJhdWQiOlsiYSIsImIiXSwic3ViIjoiMSIsImlhdCI6MS45LCJuYmYiOjEwfQ.",
+        )?;
+        assert_eq!(jwt.header.alg, "none");
+        assert_eq!(jwt.claims.aud, ["a", "b"]);
+        assert_eq!(jwt.claims.sub.as_deref(), Some("1"));
+        assert_eq!(jwt.claims.iat, Some(1));
+        assert!(jwt.claims.is_expired(9));
+        assert!(jwt.signature.is_empty());
+        // {"alg":"none"} . {"aud":"a"}
+        let jwt = decode_unverified("eyJhbGciOiJub25lIn0.eyJhdWQiOiJhIn0.")?;
+        assert_eq!(jwt.claims.aud, ["a"]);
+        Ok(())
+    }
+
+    #[test]
+    fn decode_unverified_should_report_errors() {
+        assert!(decode_unverified("abc.def").is_err());
+        assert!(decode_unverified("a.b.c.d").is_err());
+        // {"typ":"JWT"} has no `alg`.
+        let err = decode_unverified("eyJ0eXAiOiJKV1QifQ.eyJhIjoxfQ.").unwrap_err();
+        assert_eq!(err.message(), "header has no `alg`");
+        // {"alg":"none"} . [1]
+        let err = decode_unverified("eyJhbGciOiJub25lIn0.WzFd.").unwrap_err();
+        assert_eq!(err.offset(), 20);
+        // {"alg":"none"} . {"exp":"soon"}
+        assert!(decode_unverified("eyJhbGciOiJub25lIn0.eyJleHAiOiJzb29uIn0.").is_err());
+        assert!(decode_unverified("eyJhbGciOiJub25lIn0.eyJhIjoxfQ.a+b").is_err());
+    }
+}
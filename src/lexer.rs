@@ -0,0 +1,220 @@
+use std::fmt::Debug;
+
+use winnow::combinator::trace;
+use winnow::error::{ContextError, ErrMode, ParserError};
+use winnow::stream::{Location, TokenSlice};
+use winnow::token::any;
+use winnow::{ModalResult, Parser};
+
+use crate::ParseError;
+use crate::combinators::{Span, Trivia};
+
+/// The input of a parser that runs over lexed tokens.
+pub type Tokens<'t, 's, K> = TokenSlice<'t, Token<'s, K>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'s, K> {
+    pub kind: K,
+    pub text: &'s str,
+    pub span: Span,
+    /// The white space and comments between the previous token and this one,
+    /// for tools such as formatters that must not lose them.
+    pub leading_trivia: &'s str,
+}
+
+/// Positions in a [`Tokens`] stream are byte offsets into the source, so
+/// `with_span` and friends work the same as on a `LocatingSlice`.
+impl<K> Location for Token<'_, K> {
+    fn previous_token_end(&self) -> usize {
+        self.span.end
+    }
+
+    fn current_token_start(&self) -> usize {
+        self.span.start
+    }
+}
+
+type Rule<'s, K> = (K, Box<dyn Fn(&mut &'s str) -> ModalResult<()> + 's>);
+
+/// Splits source text into tokens. Each rule is a winnow parser for one kind
+/// of token; at every position the rule that matches the longest text wins,
+/// and the one added first breaks ties, so keywords go before identifiers.
+pub struct Lexer<'s, K> {
+    trivia: Trivia,
+    rules: Vec<Rule<'s, K>>,
+}
+
+impl<'s, K: Clone> Lexer<'s, K> {
+    /// A lexer that skips `trivia` between tokens.
+    pub fn new(trivia: Trivia) -> Self {
+        Lexer {
+            trivia,
+            rules: Vec::new(),
+        }
+    }
+
+    /// Adds a rule. Only what it consumes matters, not its output.
+    pub fn rule<P, O>(mut self, kind: K, parser: P) -> Self
+    where
+        P: Parser<&'s str, O, ErrMode<ContextError>> + Clone + 's,
+    {
+        let rule = move |input: &mut &'s str| parser.clone().void().parse_next(input);
+        self.rules.push((kind, Box::new(rule)));
+        self
+    }
+
+    pub fn tokenize(&self, source: &'s str) -> Result<Vec<Token<'s, K>>, ParseError> {
+        let mut tokens = Vec::new();
+        let mut rest = source;
+        loop {
+            let before = rest;
+            let mut trivia = self.trivia;
+            let skipped: ModalResult<()> = trivia.parse_next(&mut rest);
+            if let Err(ErrMode::Backtrack(e) | ErrMode::Cut(e)) = skipped {
+                return Err(ParseError::from_context(source.len() - rest.len(), &e));
+            }
+            let leading_trivia = &before[..before.len() - rest.len()];
+            if rest.is_empty() {
+                return Ok(tokens);
+            }
+
+            let start = source.len() - rest.len();
+            let mut best: Option<(usize, &K)> = None;
+            for (kind, rule) in &self.rules {
+                let mut input = rest;
+                if rule(&mut input).is_ok() {
+                    let len = rest.len() - input.len();
+                    if len > best.map_or(0, |(longest, _)| longest) {
+                        best = Some((len, kind));
+                    }
+                }
+            }
+            let Some((len, kind)) = best else {
+                let c = rest.chars().next().unwrap_or_default();
+                return Err(ParseError::new(
+                    start,
+                    format!("unexpected character {c:?}"),
+                ));
+            };
+            tokens.push(Token {
+                kind: kind.clone(),
+                text: &rest[..len],
+                span: start..start + len,
+                leading_trivia,
+            });
+            rest = &rest[len..];
+        }
+    }
+}
+
+/// Matches one token of the given kind.
+pub fn kind<'t, 's, K, Error>(kind: K) -> impl Parser<Tokens<'t, 's, K>, &'t Token<'s, K>, Error>
+where
+    's: 't,
+    K: PartialEq + Debug + Clone + 't,
+    Error: ParserError<Tokens<'t, 's, K>>,
+{
+    trace(
+        "kind",
+        any.verify(move |token: &Token<'s, K>| token.kind == kind),
+    )
+}
+
+/// Runs `parser` over all of `tokens`. Errors are reported at the start of
+/// the token they happened at, or after the last one at the end of input.
+pub fn parse_tokens<'t, 's, K, O>(
+    tokens: &'t [Token<'s, K>],
+    mut parser: impl Parser<Tokens<'t, 's, K>, O, ErrMode<ContextError>>,
+) -> Result<O, ParseError>
+where
+    's: 't,
+    K: Debug + Clone + 't,
+{
+    parser.parse(TokenSlice::new(tokens)).map_err(|e| {
+        let end = tokens.last().map_or(0, |token| token.span.end);
+        let offset = tokens.get(e.offset()).map_or(end, |token| token.span.start);
+        ParseError::from_context(offset, e.inner())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use winnow::ascii::{alpha1, digit1};
+    use winnow::combinator::{alt, cut_err, delimited, repeat};
+    use winnow::error::{StrContext, StrContextValue};
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Kind {
+        Let,
+        Ident,
+        Number,
+        Plus,
+        Equals,
+    }
+
+    fn lexer<'s>() -> Lexer<'s, Kind> {
+        Lexer::new(Trivia::C)
+            .rule(Kind::Let, "let")
+            .rule(Kind::Ident, alpha1)
+            .rule(Kind::Number, digit1)
+            .rule(Kind::Plus, '+')
+            .rule(Kind::Equals, '=')
+    }
+
+    #[test]
+    fn tokenize_should_work() -> anyhow::Result<()> {
+        let tokens = lexer().tokenize("let lets = /* two */ 1 + 1 // done")?;
+        let kinds: Vec<Kind> = tokens.iter().map(|token| token.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                Kind::Let,
+                Kind::Ident,
+                Kind::Equals,
+                Kind::Number,
+                Kind::Plus,
+                Kind::Number
+            ]
+        );
+        assert_eq!((tokens[1].text, tokens[1].span.clone()), ("lets", 4..8));
+        assert_eq!(tokens[3].leading_trivia, " /* two */ ");
+
+        let err = lexer().tokenize("let x = 1 - 2").unwrap_err();
+        assert_eq!(err.offset(), 10);
+        assert_eq!(err.message(), "unexpected character '-'");
+        Ok(())
+    }
+
+    #[test]
+    fn parse_tokens_should_report_byte_offsets() -> anyhow::Result<()> {
+        fn sum(input: &mut Tokens<'_, '_, Kind>) -> ModalResult<u32> {
+            let number = || {
+                kind(Kind::Number)
+                    .try_map(|token: &Token<'_, Kind>| token.text.parse::<u32>())
+                    .context(StrContext::Expected(StrContextValue::Description("number")))
+            };
+            let first = number().parse_next(input)?;
+            let rest: Vec<u32> = repeat(0.., (kind(Kind::Plus), cut_err(number())).map(|(_, n)| n))
+                .parse_next(input)?;
+            Ok(first + rest.iter().sum::<u32>())
+        }
+        fn binding(input: &mut Tokens<'_, '_, Kind>) -> ModalResult<(String, u32)> {
+            let name = delimited(kind(Kind::Let), kind(Kind::Ident), kind(Kind::Equals))
+                .parse_next(input)?;
+            let value = alt((sum, kind(Kind::Ident).value(0))).parse_next(input)?;
+            Ok((name.text.to_string(), value))
+        }
+
+        let source = "let total = 1 + 2 + 39";
+        let tokens = lexer().tokenize(source)?;
+        assert_eq!(parse_tokens(&tokens, binding)?, ("total".to_string(), 42));
+
+        let source = "let total = 1 + ";
+        let err = parse_tokens(&lexer().tokenize(source)?, binding).unwrap_err();
+        assert_eq!(err.offset(), 15);
+        assert_eq!(err.message(), "expected number");
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+// Lets the code `grammar-derive` generates name this crate from inside it too.
+extern crate self as grammar;
+
+pub mod apache;
+pub mod bencode;
+pub mod bibtex;
+pub mod bytesize;
+pub mod cargo_lock;
+pub mod codec;
+pub mod combinators;
+pub mod cookie;
+pub mod cron;
+pub mod crontab;
+pub mod css;
+pub mod csv;
+pub mod datetime;
+pub mod derive;
+pub mod diagnostics;
+pub mod dockerfile;
+pub mod dot;
+pub mod duration;
+pub mod ebnf;
+pub mod email;
+mod error;
+pub mod fasta;
+pub mod fen;
+#[cfg(any(feature = "arbitrary", feature = "proptest"))]
+pub mod generate;
+pub mod gitignore;
+pub mod glob;
+pub mod gomod;
+pub mod graphite;
+pub mod graphql;
+pub mod hosts;
+pub mod html;
+pub mod http;
+pub mod ical;
+pub mod influx;
+pub mod ipnet;
+pub mod json;
+pub mod jwt;
+pub mod lexer;
+pub mod lua;
+pub mod m3u;
+pub mod mac;
+pub mod markdown;
+pub mod memcached;
+pub mod memo;
+pub mod multipart;
+pub mod netpbm;
+pub mod nginx;
+pub mod nmea;
+pub mod obj;
+pub mod passwd;
+pub mod peg;
+pub mod pem;
+pub mod php;
+pub mod pratt;
+pub mod predicate;
+pub mod procfile;
+pub mod progress;
+pub mod prometheus;
+pub mod properties;
+pub mod proto;
+pub mod python;
+pub mod railroad;
+pub mod regex_syntax;
+pub mod requirements;
+pub mod robots;
+pub mod semver;
+pub mod sexpr;
+pub mod shellwords;
+pub mod smtp;
+pub mod sql;
+pub mod ssh;
+pub mod statsd;
+pub mod subtitle;
+pub mod tap;
+pub mod testing;
+pub mod textproto;
+pub mod thrift;
+pub mod toml;
+pub mod uri;
+pub mod urlencoded;
+pub mod user_agent;
+pub mod vcard;
+pub mod vcf;
+pub mod wkt;
+pub mod xml;
+
+pub use error::ParseError;
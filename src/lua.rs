@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{digit0, digit1, hex_digit1, multispace0};
+use winnow::combinator::{alt, cut_err, delimited, fail, opt, preceded, repeat, terminated};
+use winnow::error::{ContextError, ErrMode, StrContext, StrContextValue};
+use winnow::token::{none_of, one_of, take, take_till, take_until, take_while};
+
+use crate::ParseError;
+use crate::combinators::Trivia;
+use crate::json::JsonValue;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LuaValue {
+    Nil,
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Table(Table),
+}
+
+/// A table constructor. Positional items are kept apart from keyed
+/// fields; in Lua they would have the keys `1`, `2`, ...
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Table {
+    pub items: Vec<LuaValue>,
+    /// Both `name = v` and `[k] = v`, in source order.
+    pub fields: Vec<(LuaValue, LuaValue)>,
+}
+
+impl Table {
+    /// The last field with a string key, as Lua would see it.
+    pub fn get(&self, key: &str) -> Option<&LuaValue> {
+        self.fields
+            .iter()
+            .rev()
+            .find(|(k, _)| matches!(k, LuaValue::String(s) if s == key))
+            .map(|(_, v)| v)
+    }
+}
+
+impl LuaValue {
+    pub fn as_table(&self) -> Option<&Table> {
+        match self {
+            LuaValue::Table(table) => Some(table),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            LuaValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// How a key is spelled once it becomes a JSON object member.
+    fn key_string(&self) -> String {
+        match self {
+            LuaValue::Nil => "nil".to_string(),
+            LuaValue::Boolean(b) => b.to_string(),
+            LuaValue::Integer(n) => n.to_string(),
+            LuaValue::Float(x) => x.to_string(),
+            LuaValue::String(s) => s.clone(),
+            // Tables as keys have no useful spelling; fall back to the
+            // debug form so nothing is silently dropped.
+            LuaValue::Table(table) => format!("{table:?}"),
+        }
+    }
+}
+
+/// A table with only positional items becomes an array, and any other table
+/// an object whose positional items are keyed `"1"`, `"2"`, ... `nil` maps
+/// to `null`.
+impl From<LuaValue> for JsonValue {
+    fn from(value: LuaValue) -> Self {
+        match value {
+            LuaValue::Nil => JsonValue::Null,
+            LuaValue::Boolean(b) => JsonValue::Bool(b),
+            LuaValue::Integer(n) => JsonValue::Number(n as f64),
+            LuaValue::Float(x) => JsonValue::Number(x),
+            LuaValue::String(s) => JsonValue::String(s),
+            LuaValue::Table(table) if table.fields.is_empty() => {
+                JsonValue::Array(table.items.into_iter().map(JsonValue::from).collect())
+            }
+            LuaValue::Table(table) => {
+                let mut object = HashMap::new();
+                for (i, item) in table.items.into_iter().enumerate() {
+                    object.insert((i + 1).to_string(), JsonValue::from(item));
+                }
+                for (key, value) in table.fields {
+                    object.insert(key.key_string(), JsonValue::from(value));
+                }
+                JsonValue::Object(object)
+            }
+        }
+    }
+}
+
+/// Parses one Lua value, usually a table constructor. A leading `return`,
+/// as in files loaded with `dofile`, is allowed. Only literals are
+/// accepted; variables, calls and operators other than a leading minus
+/// are errors.
+pub fn parse_lua(input: &str) -> Result<LuaValue, ParseError> {
+    delimited(
+        (trivia, opt(terminated(keyword("return"), trivia))),
+        value,
+        (trivia, opt(';'), trivia),
+    )
+    .parse(input)
+    .map_err(ParseError::from)
+}
+
+fn value(input: &mut &str) -> ModalResult<LuaValue> {
+    alt((
+        table.map(LuaValue::Table),
+        string.map(LuaValue::String),
+        number,
+        keyword("nil").value(LuaValue::Nil),
+        keyword("true").value(LuaValue::Boolean(true)),
+        keyword("false").value(LuaValue::Boolean(false)),
+    ))
+    .context(StrContext::Label("value"))
+    .parse_next(input)
+}
+
+fn table(input: &mut &str) -> ModalResult<Table> {
+    '{'.parse_next(input)?;
+    let mut table = Table::default();
+    loop {
+        trivia.parse_next(input)?;
+        if opt('}').parse_next(input)?.is_some() {
+            return Ok(table);
+        }
+        match cut_err(field).parse_next(input)? {
+            (Some(key), value) => table.fields.push((key, value)),
+            (None, value) => table.items.push(value),
+        }
+        trivia.parse_next(input)?;
+        if opt(one_of([',', ';'])).parse_next(input)?.is_none() {
+            cut_err('}')
+                .context(StrContext::Expected(StrContextValue::CharLiteral('}')))
+                .parse_next(input)?;
+            return Ok(table);
+        }
+    }
+}
+
+fn field(input: &mut &str) -> ModalResult<(Option<LuaValue>, LuaValue)> {
+    alt((
+        // `[[...]]` and `[=[...]=]` open long strings, not keys.
+        long_string.map(|s| (None, LuaValue::String(s))),
+        (
+            delimited(('[', trivia), value, (trivia, ']')),
+            cut_err(preceded((trivia, '=', trivia), value)),
+        )
+            .map(|(k, v)| (Some(k), v)),
+        (terminated(name, (trivia, '=', trivia)), cut_err(value))
+            .map(|(k, v)| (Some(LuaValue::String(k.to_string())), v)),
+        value.map(|v| (None, v)),
+    ))
+    .parse_next(input)
+}
+
+fn name<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    (
+        one_of(|c: char| c.is_ascii_alphabetic() || c == '_'),
+        take_while(0.., |c: char| c.is_ascii_alphanumeric() || c == '_'),
+    )
+        .take()
+        .parse_next(input)
+}
+
+fn keyword<'i>(word: &'static str) -> impl Parser<&'i str, &'i str, ErrMode<ContextError>> {
+    name.verify(move |n: &str| n == word)
+}
+
+fn number(input: &mut &str) -> ModalResult<LuaValue> {
+    let negative = opt(terminated('-', trivia)).parse_next(input)?.is_some();
+    let sign = if negative { -1 } else { 1 };
+    if let Some(digits) = opt(preceded(alt(("0x", "0X")), cut_err(hex_digit1))).parse_next(input)? {
+        // Hex integers wrap around like in Lua 5.3.
+        let n = digits.chars().fold(0i64, |n, c| {
+            n.wrapping_mul(16)
+                .wrapping_add(c.to_digit(16).unwrap() as i64)
+        });
+        return Ok(LuaValue::Integer(n.wrapping_mul(sign)));
+    }
+    let text: &str =
+        alt(((digit1, opt(('.', digit0))).take(), ('.', digit1).take())).parse_next(input)?;
+    let exponent: Option<&str> =
+        opt((one_of(['e', 'E']), opt(one_of(['+', '-'])), cut_err(digit1)).take())
+            .parse_next(input)?;
+    if exponent.is_none()
+        && !text.contains('.')
+        && let Ok(n) = text.parse::<i64>()
+    {
+        return Ok(LuaValue::Integer(n * sign));
+    }
+    let literal = format!("{text}{}", exponent.unwrap_or(""));
+    match literal.parse::<f64>() {
+        Ok(x) => Ok(LuaValue::Float(x * sign as f64)),
+        Err(_) => fail.parse_next(input),
+    }
+}
+
+fn string(input: &mut &str) -> ModalResult<String> {
+    alt((quoted('"'), quoted('\''), long_string)).parse_next(input)
+}
+
+fn quoted<'i>(quote: char) -> impl Parser<&'i str, String, ErrMode<ContextError>> {
+    preceded(
+        quote,
+        cut_err(terminated(
+            repeat(
+                0..,
+                alt((
+                    preceded('\\', escape),
+                    none_of([quote, '\\', '\n']).map(Some),
+                )),
+            )
+            .map(|chars: Vec<Option<char>>| chars.into_iter().flatten().collect()),
+            quote,
+        ))
+        .context(StrContext::Label("string")),
+    )
+}
+
+/// The character an escape stands for; `\z` and an escaped line break
+/// before the next line's indent stand for nothing.
+fn escape(input: &mut &str) -> ModalResult<Option<char>> {
+    alt((
+        one_of(['n', 't', 'r', 'a', 'b', 'f', 'v', '\\', '"', '\'', '\n']).map(|c| {
+            Some(match c {
+                'n' => '\n',
+                't' => '\t',
+                'r' => '\r',
+                'a' => '\x07',
+                'b' => '\x08',
+                'f' => '\x0c',
+                'v' => '\x0b',
+                c => c,
+            })
+        }),
+        preceded('z', multispace0).value(None),
+        preceded('x', take(2usize))
+            .verify_map(|hex: &str| u8::from_str_radix(hex, 16).ok())
+            .map(|b| Some(char::from(b))),
+        take_while(1..=3, |c: char| c.is_ascii_digit())
+            .verify_map(|digits: &str| digits.parse::<u8>().ok())
+            .map(|b| Some(char::from(b))),
+        preceded("u{", cut_err(terminated(hex_digit1, '}')))
+            .verify_map(|hex: &str| u32::from_str_radix(hex, 16).ok().and_then(char::from_u32))
+            .map(Some),
+    ))
+    .context(StrContext::Label("escape sequence"))
+    .parse_next(input)
+}
+
+/// `[[...]]`, with any number of `=` between the brackets. Escapes are not
+/// processed and a line break right after the opening is dropped.
+fn long_string(input: &mut &str) -> ModalResult<String> {
+    let level = delimited('[', take_while(0.., '='), '[').parse_next(input)?;
+    let close = format!("]{level}]");
+    let body: &str = cut_err(terminated(take_until(0.., close.as_str()), close.as_str()))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "closing long bracket",
+        )))
+        .parse_next(input)?;
+    let body = body
+        .strip_prefix("\r\n")
+        .or_else(|| body.strip_prefix('\n'))
+        .unwrap_or(body);
+    Ok(body.to_string())
+}
+
+/// White space, `--` line comments and `--[[ ]]` block comments. The
+/// block can be any long bracket, such as `--[==[ ]==]`, which has no fixed
+/// closing marker, so [`Trivia`] only skips the white space between
+/// comments.
+fn trivia(input: &mut &str) -> ModalResult<()> {
+    let mut space = Trivia::new();
+    loop {
+        space.parse_next(input)?;
+        if opt("--").parse_next(input)?.is_none() {
+            return Ok(());
+        }
+        alt((long_string.void(), take_till(0.., '\n').void())).parse_next(input)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_lua_should_work() -> Result<(), ParseError> {
+        let value = parse_lua(
+            "return {\n\
+             \x20 a = 1, -- the first\n\
+             \x20 [\"k\"] = {2, 3.5; -4},\n\
+             \x20 4,\n\
+             \x20 [10] = 'it\\'s\\n', s = [==[\nraw ]] text]==],\n\
+             \x20 --[[ a\n block ]] flag = true, none = nil, hex = 0xff, big = 1e3,\n\
+             }",
+        )?;
+        let table = value.as_table().unwrap();
+        assert_eq!(table.items, [LuaValue::Integer(4)]);
+        assert_eq!(table.get("a"), Some(&LuaValue::Integer(1)));
+        assert_eq!(
+            table.get("k").and_then(LuaValue::as_table).unwrap().items,
+            [
+                LuaValue::Integer(2),
+                LuaValue::Float(3.5),
+                LuaValue::Integer(-4)
+            ]
+        );
+        assert_eq!(
+            table.fields[2],
+            (
+                LuaValue::Integer(10),
+                LuaValue::String("it's\n".to_string())
+            )
+        );
+        assert_eq!(
+            table.get("s").and_then(LuaValue::as_str),
+            Some("raw ]] text")
+        );
+        assert_eq!(table.get("flag"), Some(&LuaValue::Boolean(true)));
+        assert_eq!(table.get("hex"), Some(&LuaValue::Integer(255)));
+        assert_eq!(table.get("big"), Some(&LuaValue::Float(1000.0)));
+        assert_eq!(
+            parse_lua("'\\65\\x42\\u{43}\\z\n   D'")?,
+            LuaValue::String("ABCD".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn lua_value_should_convert_to_json() -> Result<(), ParseError> {
+        let json = JsonValue::from(parse_lua(
+            "{name = 'x', tags = {'a', 'b'}, 7, [true] = {}}",
+        )?);
+        assert_eq!(
+            json.get_path("name"),
+            Some(&JsonValue::String("x".to_string()))
+        );
+        assert_eq!(
+            json.get_path("tags"),
+            Some(&JsonValue::Array(vec![
+                JsonValue::String("a".to_string()),
+                JsonValue::String("b".to_string()),
+            ]))
+        );
+        assert_eq!(json.get_path("1"), Some(&JsonValue::Number(7.0)));
+        assert_eq!(json.get_path("true"), Some(&JsonValue::Array(vec![])));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_lua_should_report_errors() {
+        assert_eq!(parse_lua("{a = 1 b = 2}").unwrap_err().offset(), 7);
+        assert_eq!(parse_lua("{a = }").unwrap_err().offset(), 5);
+        assert!(parse_lua("{x}").is_err());
+        assert!(parse_lua("{[1] 2}").is_err());
+        assert!(parse_lua("'open").is_err());
+        assert!(parse_lua("[[open").is_err());
+        assert!(parse_lua("{} {}").is_err());
+    }
+}
@@ -0,0 +1,424 @@
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::combinator::{alt, cut_err, delimited, separated, separated_pair};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::{take_till, take_while};
+
+use crate::ParseError;
+
+/// An M3U playlist. HLS media playlists fill in `segments`, master
+/// playlists `variants` and `renditions`; a plain M3U is just segments
+/// without durations.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Playlist {
+    /// Whether the file began with `#EXTM3U`.
+    pub extended: bool,
+    pub version: Option<u64>,
+    /// `#EXT-X-TARGETDURATION`, in seconds.
+    pub target_duration: Option<u64>,
+    /// `#EXT-X-MEDIA-SEQUENCE`: the number of the first segment.
+    pub media_sequence: u64,
+    /// `VOD` or `EVENT`.
+    pub playlist_type: Option<String>,
+    /// `#EXT-X-ENDLIST` was seen: no segments will be added.
+    pub end_list: bool,
+    pub independent_segments: bool,
+    pub segments: Vec<Segment>,
+    pub variants: Vec<Variant>,
+    /// `#EXT-X-MEDIA` alternative renditions such as audio tracks.
+    pub renditions: Vec<Attributes>,
+    /// Every other tag, as `(name, value)` without the `#`.
+    pub tags: Vec<(String, Option<String>)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub uri: String,
+    /// From `#EXTINF`, in seconds.
+    pub duration: Option<f64>,
+    pub title: Option<String>,
+    /// The media sequence number.
+    pub sequence: u64,
+    pub byte_range: Option<ByteRange>,
+    /// A `#EXT-X-DISCONTINUITY` came before this segment.
+    pub discontinuity: bool,
+    /// The `#EXT-X-KEY` in force, unless it was `METHOD=NONE`.
+    pub key: Option<Attributes>,
+    /// The `#EXT-X-MAP` initialization section in force.
+    pub map: Option<Attributes>,
+    pub program_date_time: Option<String>,
+}
+
+/// `#EXT-X-BYTERANGE:<length>[@<offset>]`, with a missing offset resolved
+/// to the end of the previous sub-range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub length: u64,
+    pub offset: u64,
+}
+
+/// A `#EXT-X-STREAM-INF` and the URI that follows it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    pub uri: String,
+    /// `BANDWIDTH`, in bits per second.
+    pub bandwidth: u64,
+    pub attributes: Attributes,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    Integer(u64),
+    Float(f64),
+    /// A quoted string, without its quotes.
+    String(String),
+    /// An unquoted keyword such as `AES-128` or `YES`.
+    Enum(String),
+    /// `0x...`
+    Hex(Vec<u8>),
+    /// `1920x1080`
+    Resolution(u32, u32),
+}
+
+/// An HLS attribute list: `NAME=value,NAME="value"`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Attributes(pub Vec<(String, AttributeValue)>);
+
+impl Attributes {
+    pub fn get(&self, name: &str) -> Option<&AttributeValue> {
+        self.0.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    /// A quoted or unquoted string attribute.
+    pub fn text(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            AttributeValue::String(s) | AttributeValue::Enum(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl Variant {
+    pub fn resolution(&self) -> Option<(u32, u32)> {
+        match self.attributes.get("RESOLUTION")? {
+            AttributeValue::Resolution(w, h) => Some((*w, *h)),
+            _ => None,
+        }
+    }
+
+    /// `CODECS`, split at commas.
+    pub fn codecs(&self) -> Vec<&str> {
+        self.attributes
+            .text("CODECS")
+            .map(|c| c.split(',').map(str::trim).collect())
+            .unwrap_or_default()
+    }
+}
+
+impl Playlist {
+    /// The sum of the segment durations, in seconds.
+    pub fn duration(&self) -> f64 {
+        self.segments.iter().filter_map(|s| s.duration).sum()
+    }
+}
+
+/// Parses an M3U or M3U8 playlist. Comments and blank lines are skipped;
+/// errors point into `input`.
+pub fn parse_m3u(input: &str) -> Result<Playlist, ParseError> {
+    let mut playlist = Playlist::default();
+    // Tags that apply to the next URI.
+    let mut inf: Option<(Option<f64>, Option<String>)> = None;
+    let mut stream_inf: Option<(usize, Attributes)> = None;
+    let mut byte_range: Option<(u64, Option<u64>)> = None;
+    let mut discontinuity = false;
+    let mut program_date_time = None;
+    // Tags that apply until replaced.
+    let mut key = None;
+    let mut map = None;
+    let mut next_offset: Option<(String, u64)> = None;
+
+    let mut offset = 0;
+    for (number, raw) in input.split_inclusive('\n').enumerate() {
+        let start = offset;
+        offset += raw.len();
+        let line = raw.trim_end_matches(['\n', '\r']);
+        let line = if number == 0 {
+            line.strip_prefix('\u{feff}').unwrap_or(line)
+        } else {
+            line
+        };
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let at = start + (raw.len() - raw.trim_start().len());
+        if number == 0 && trimmed == "#EXTM3U" {
+            playlist.extended = true;
+            continue;
+        }
+        let Some(tag) = trimmed.strip_prefix('#') else {
+            // A URI closes whatever was pending.
+            let uri = trimmed.to_string();
+            if let Some((_, attributes)) = stream_inf.take() {
+                let bandwidth = match attributes.get("BANDWIDTH") {
+                    Some(AttributeValue::Integer(b)) => *b,
+                    _ => return Err(ParseError::new(at, "variant without `BANDWIDTH`")),
+                };
+                playlist.variants.push(Variant {
+                    uri,
+                    bandwidth,
+                    attributes,
+                });
+                continue;
+            }
+            let range = byte_range.take().map(|(length, offset)| {
+                let offset = offset
+                    .or_else(|| {
+                        next_offset
+                            .as_ref()
+                            .filter(|(last, _)| *last == uri)
+                            .map(|(_, end)| *end)
+                    })
+                    .unwrap_or(0);
+                ByteRange { length, offset }
+            });
+            next_offset = range.map(|r| (uri.clone(), r.offset + r.length));
+            let (duration, title) = inf.take().unwrap_or_default();
+            playlist.segments.push(Segment {
+                uri,
+                duration,
+                title,
+                sequence: playlist.media_sequence + playlist.segments.len() as u64,
+                byte_range: range,
+                discontinuity: std::mem::take(&mut discontinuity),
+                key: key.clone(),
+                map: map.clone(),
+                program_date_time: program_date_time.take(),
+            });
+            continue;
+        };
+        if !tag.starts_with("EXT") {
+            continue;
+        }
+        let (name, value) = match tag.split_once(':') {
+            Some((name, value)) => (name, Some(value)),
+            None => (tag, None),
+        };
+        // Where the value starts, for errors inside it.
+        let value_at = at + name.len() + 2;
+        let required =
+            || value.ok_or_else(|| ParseError::new(at, format!("`{name}` needs a value")));
+        let integer = || {
+            required()?
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| ParseError::new(value_at, "expected an integer"))
+        };
+        let list = || {
+            attributes.parse(required()?).map_err(|e| {
+                let e = ParseError::from(e);
+                ParseError::new(value_at + e.offset(), e.message())
+            })
+        };
+        match name {
+            "EXTINF" => {
+                let (duration, title) = required()?.split_once(',').unwrap_or((required()?, ""));
+                let duration = duration
+                    .trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|d| d.is_finite() && *d >= 0.0)
+                    .ok_or_else(|| ParseError::new(value_at, "expected a duration"))?;
+                let title = title.trim();
+                inf = Some((
+                    Some(duration),
+                    (!title.is_empty()).then(|| title.to_string()),
+                ));
+            }
+            "EXT-X-STREAM-INF" => stream_inf = Some((at, list()?)),
+            "EXT-X-MEDIA" => playlist.renditions.push(list()?),
+            "EXT-X-KEY" => {
+                let attributes = list()?;
+                key = (attributes.text("METHOD") != Some("NONE")).then_some(attributes);
+            }
+            "EXT-X-MAP" => map = Some(list()?),
+            "EXT-X-BYTERANGE" => {
+                let text = required()?;
+                let (length, offset) = match text.split_once('@') {
+                    Some((length, offset)) => (length, Some(offset)),
+                    None => (text, None),
+                };
+                let number = |s: &str| {
+                    s.trim()
+                        .parse::<u64>()
+                        .map_err(|_| ParseError::new(value_at, "expected `<length>[@<offset>]`"))
+                };
+                byte_range = Some((number(length)?, offset.map(number).transpose()?));
+            }
+            "EXT-X-VERSION" => playlist.version = Some(integer()?),
+            "EXT-X-TARGETDURATION" => playlist.target_duration = Some(integer()?),
+            "EXT-X-MEDIA-SEQUENCE" => playlist.media_sequence = integer()?,
+            "EXT-X-PLAYLIST-TYPE" => playlist.playlist_type = Some(required()?.to_string()),
+            "EXT-X-PROGRAM-DATE-TIME" => program_date_time = Some(required()?.to_string()),
+            "EXT-X-DISCONTINUITY" => discontinuity = true,
+            "EXT-X-ENDLIST" => playlist.end_list = true,
+            "EXT-X-INDEPENDENT-SEGMENTS" => playlist.independent_segments = true,
+            _ => playlist
+                .tags
+                .push((name.to_string(), value.map(str::to_string))),
+        }
+    }
+    if let Some((at, _)) = stream_inf {
+        return Err(ParseError::new(at, "`EXT-X-STREAM-INF` without a URI"));
+    }
+    Ok(playlist)
+}
+
+fn attributes(input: &mut &str) -> ModalResult<Attributes> {
+    separated(
+        1..,
+        separated_pair(
+            take_while(1.., ('A'..='Z', '0'..='9', '-'))
+                .map(String::from)
+                .context(StrContext::Label("attribute name")),
+            cut_err('=').context(StrContext::Expected(StrContextValue::CharLiteral('='))),
+            cut_err(alt((
+                delimited('"', take_till(0.., '"'), cut_err('"'))
+                    .map(|s: &str| AttributeValue::String(s.to_string())),
+                take_till(1.., ',').verify_map(attribute_value),
+            )))
+            .context(StrContext::Label("attribute value")),
+        ),
+        ',',
+    )
+    .map(Attributes)
+    .parse_next(input)
+}
+
+fn attribute_value(text: &str) -> Option<AttributeValue> {
+    if let Some(hex) = text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+        return crate::codec::HEX.decode(hex).ok().map(AttributeValue::Hex);
+    }
+    if let Some((w, h)) = text.split_once('x')
+        && let (Ok(w), Ok(h)) = (w.parse(), h.parse())
+    {
+        return Some(AttributeValue::Resolution(w, h));
+    }
+    if text.bytes().all(|b| b.is_ascii_digit()) {
+        return text.parse().ok().map(AttributeValue::Integer);
+    }
+    if text.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.')
+        && let Ok(f) = text.parse::<f64>()
+    {
+        return Some(AttributeValue::Float(f));
+    }
+    Some(AttributeValue::Enum(text.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn media_playlists_should_parse() -> Result<(), ParseError> {
+        let playlist = parse_m3u(
+            "#EXTM3U\n\
+             #EXT-X-VERSION:4\n\
+             #EXT-X-TARGETDURATION:10\n\
+             #EXT-X-MEDIA-SEQUENCE:7\n\
+             #EXT-X-PLAYLIST-TYPE:VOD\n\
+             #EXT-X-KEY:METHOD=AES-128,URI=\"key.bin\",IV=0x000102030405060708090a0b0c0d0e0f\n\
+             #EXTINF:9.009,Intro\n\
+             #EXT-X-BYTERANGE:1000@0\n\
+             main.ts\n\
+             #EXTINF:9.009,\n\
+             #EXT-X-BYTERANGE:500\n\
+             main.ts\n\
+             #EXT-X-DISCONTINUITY\n\
+             #EXT-X-KEY:METHOD=NONE\n\
+             #EXTINF:3.003\n\
+             ad.ts\n\
+             #EXT-X-ENDLIST\n",
+        )?;
+        assert!(playlist.extended);
+        assert_eq!(playlist.version, Some(4));
+        assert_eq!(playlist.target_duration, Some(10));
+        assert_eq!(playlist.playlist_type.as_deref(), Some("VOD"));
+        assert!(playlist.end_list);
+        assert_eq!(playlist.segments.len(), 3);
+        let [first, second, ad] = &playlist.segments[..] else {
+            unreachable!()
+        };
+        assert_eq!(first.title.as_deref(), Some("Intro"));
+        assert_eq!(first.sequence, 7);
+        let key = first.key.as_ref().unwrap();
+        assert_eq!(key.text("METHOD"), Some("AES-128"));
+        assert_eq!(key.text("URI"), Some("key.bin"));
+        assert_eq!(key.get("IV"), Some(&AttributeValue::Hex((0..16).collect())));
+        assert_eq!(
+            second.byte_range,
+            Some(ByteRange {
+                length: 500,
+                offset: 1000
+            })
+        );
+        assert_eq!(second.title, None);
+        assert!(ad.discontinuity);
+        assert_eq!(ad.key, None);
+        assert_eq!(ad.sequence, 9);
+        assert!((playlist.duration() - 21.021).abs() < 1e-9);
+        Ok(())
+    }
+
+    #[test]
+    fn master_playlists_should_parse() -> Result<(), ParseError> {
+        let playlist = parse_m3u(
+            "#EXTM3U\r\n\
+             #EXT-X-INDEPENDENT-SEGMENTS\r\n\
+             #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"English\",DEFAULT=YES,URI=\"en.m3u8\"\r\n\
+             #EXT-X-STREAM-INF:BANDWIDTH=1280000,AVERAGE-BANDWIDTH=1000000,RESOLUTION=640x360,FRAME-RATE=29.970,CODECS=\"avc1.4d401e,mp4a.40.2\",AUDIO=\"aac\"\r\n\
+             low/index.m3u8\r\n\
+             #EXT-X-STREAM-INF:BANDWIDTH=7680000\r\n\
+             hi/index.m3u8\r\n\
+             #EXT-X-START:TIME-OFFSET=-5\r\n",
+        )?;
+        assert!(playlist.independent_segments);
+        assert_eq!(playlist.renditions[0].text("NAME"), Some("English"));
+        assert_eq!(playlist.renditions[0].text("DEFAULT"), Some("YES"));
+        let low = &playlist.variants[0];
+        assert_eq!(low.uri, "low/index.m3u8");
+        assert_eq!(low.bandwidth, 1280000);
+        assert_eq!(low.resolution(), Some((640, 360)));
+        assert_eq!(low.codecs(), ["avc1.4d401e", "mp4a.40.2"]);
+        assert_eq!(
+            low.attributes.get("FRAME-RATE"),
+            Some(&AttributeValue::Float(29.97))
+        );
+        assert_eq!(playlist.variants[1].resolution(), None);
+        assert_eq!(
+            playlist.tags,
+            [(
+                "EXT-X-START".to_string(),
+                Some("TIME-OFFSET=-5".to_string())
+            )]
+        );
+        let plain = parse_m3u("# my mix\nsong.mp3\n\nhttp://example.com/stream\n")?;
+        assert!(!plain.extended);
+        assert_eq!(plain.segments[1].uri, "http://example.com/stream");
+        assert_eq!(plain.segments[0].duration, None);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_m3u_should_report_errors() {
+        let err = parse_m3u("#EXTM3U\n#EXTINF:abc,\nx.ts\n").unwrap_err();
+        assert_eq!(err.offset(), 16);
+        let err = parse_m3u("#EXTM3U\n#EXT-X-KEY:METHOD=AES-128,URI=\"k\n").unwrap_err();
+        assert_eq!(err.offset(), 40);
+        assert!(parse_m3u("#EXT-X-STREAM-INF:BANDWIDTH=1\n").is_err());
+        assert!(parse_m3u("#EXT-X-STREAM-INF:RESOLUTION=1x1\nv.m3u8\n").is_err());
+        assert!(parse_m3u("#EXT-X-BYTERANGE:ten\nx.ts\n").is_err());
+        assert!(parse_m3u("#EXT-X-VERSION\n").is_err());
+        assert!(parse_m3u("#EXT-X-MAP:uri=\"x\"\n").is_err());
+    }
+}
@@ -0,0 +1,210 @@
+use std::fmt;
+use std::str::FromStr;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::combinator::{alt, cut_err, peek, preceded, repeat};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::{one_of, take_while};
+
+use crate::ParseError;
+
+/// A 48-bit IEEE 802 MAC address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct MacAddr(pub [u8; 6]);
+
+/// The vendor prefix: the first three octets of a MAC address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Oui(pub [u8; 3]);
+
+/// How [`MacAddr::format`] separates the hex digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Notation {
+    /// `00:1a:2b:3c:4d:5e`
+    #[default]
+    Colon,
+    /// `00-1a-2b-3c-4d-5e`, as Windows prints them.
+    Dash,
+    /// `001a.2b3c.4d5e`, as Cisco prints them.
+    Dot,
+    /// `001a2b3c4d5e`
+    Bare,
+}
+
+impl MacAddr {
+    pub const BROADCAST: MacAddr = MacAddr([0xff; 6]);
+
+    pub fn oui(&self) -> Oui {
+        Oui([self.0[0], self.0[1], self.0[2]])
+    }
+
+    /// Group addresses have the least significant bit of the first octet set.
+    pub fn is_multicast(&self) -> bool {
+        self.0[0] & 0x01 != 0
+    }
+
+    /// Locally administered addresses are assigned by software rather than
+    /// burned in by the vendor, so their OUI means nothing.
+    pub fn is_local(&self) -> bool {
+        self.0[0] & 0x02 != 0
+    }
+
+    pub fn format(&self, notation: Notation, uppercase: bool) -> String {
+        let hex: Vec<String> = self
+            .0
+            .iter()
+            .map(|b| {
+                if uppercase {
+                    format!("{b:02X}")
+                } else {
+                    format!("{b:02x}")
+                }
+            })
+            .collect();
+        match notation {
+            Notation::Colon => hex.join(":"),
+            Notation::Dash => hex.join("-"),
+            Notation::Dot => hex
+                .chunks(2)
+                .map(<[_]>::concat)
+                .collect::<Vec<_>>()
+                .join("."),
+            Notation::Bare => hex.concat(),
+        }
+    }
+}
+
+impl fmt::Display for MacAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format(Notation::Colon, false))
+    }
+}
+
+impl fmt::Display for Oui {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02x}:{:02x}:{:02x}", self.0[0], self.0[1], self.0[2])
+    }
+}
+
+impl FromStr for MacAddr {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_mac(s)
+    }
+}
+
+/// Parses any of the [`Notation`]s, in either case. With colons or dashes
+/// a leading zero may be dropped (`0:1a:2b:3c:4d:5e`), as BSD tools print.
+pub fn parse_mac(input: &str) -> Result<MacAddr, ParseError> {
+    mac.parse(input.trim()).map_err(ParseError::from)
+}
+
+/// Parses a vendor prefix written like the start of a MAC address:
+/// `00:1A:2B`, `00-1a-2b` or `001a2b`.
+pub fn parse_oui(input: &str) -> Result<Oui, ParseError> {
+    alt((separated_octets::<3>, bare_octets::<3>))
+        .map(Oui)
+        .parse(input.trim())
+        .map_err(ParseError::from)
+}
+
+pub(crate) fn mac(input: &mut &str) -> ModalResult<MacAddr> {
+    alt((separated_octets::<6>, dotted, bare_octets::<6>))
+        .map(MacAddr)
+        .context(StrContext::Label("MAC address"))
+        .parse_next(input)
+}
+
+/// `N` octets of one or two hex digits, joined by `:` or `-` throughout.
+fn separated_octets<const N: usize>(input: &mut &str) -> ModalResult<[u8; N]> {
+    let first = octet.parse_next(input)?;
+    let sep = peek(one_of([':', '-'])).parse_next(input)?;
+    let rest: Vec<u8> = cut_err(repeat(N - 1, preceded(sep, octet)))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "hex octets separated consistently",
+        )))
+        .parse_next(input)?;
+    let mut octets = [first; N];
+    octets[1..].copy_from_slice(&rest);
+    Ok(octets)
+}
+
+/// Three groups of four hex digits joined by dots.
+fn dotted(input: &mut &str) -> ModalResult<[u8; 6]> {
+    let group = || take_while(4, |c: char| c.is_ascii_hexdigit());
+    let (a, _, b, _, c) = (
+        group(),
+        '.',
+        cut_err(group()),
+        cut_err('.'),
+        cut_err(group()),
+    )
+        .parse_next(input)?;
+    Ok(hex_octets([a, b, c].concat().as_str()))
+}
+
+fn bare_octets<const N: usize>(input: &mut &str) -> ModalResult<[u8; N]> {
+    take_while(2 * N, |c: char| c.is_ascii_hexdigit())
+        .map(hex_octets)
+        .parse_next(input)
+}
+
+fn octet(input: &mut &str) -> ModalResult<u8> {
+    take_while(1..=2, |c: char| c.is_ascii_hexdigit())
+        .map(|hex| u8::from_str_radix(hex, 16).expect("one or two hex digits"))
+        .parse_next(input)
+}
+
+/// Decodes a string already checked to hold `2 * N` hex digits.
+fn hex_octets<const N: usize>(hex: &str) -> [u8; N] {
+    let mut octets = [0; N];
+    for (octet, pair) in octets.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *octet = u8::from_str_radix(std::str::from_utf8(pair).unwrap(), 16).unwrap();
+    }
+    octets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC: MacAddr = MacAddr([0x00, 0x1a, 0x2b, 0x3c, 0x4d, 0x5e]);
+
+    #[test]
+    fn parse_mac_should_accept_all_notations() -> Result<(), ParseError> {
+        for input in [
+            "00:1a:2b:3c:4d:5e",
+            "00-1A-2B-3C-4D-5E",
+            "001a.2b3c.4d5e",
+            "001A2B3C4D5E",
+            "0:1a:2b:3c:4d:5e",
+        ] {
+            assert_eq!(parse_mac(input)?, MAC, "{input}");
+        }
+        assert_eq!(parse_oui("00-1A-2B")?, MAC.oui());
+        assert_eq!(MAC.oui().to_string(), "00:1a:2b");
+        Ok(())
+    }
+
+    #[test]
+    fn mac_should_format() {
+        assert_eq!(MAC.to_string(), "00:1a:2b:3c:4d:5e");
+        assert_eq!(MAC.format(Notation::Dash, true), "00-1A-2B-3C-4D-5E");
+        assert_eq!(MAC.format(Notation::Dot, false), "001a.2b3c.4d5e");
+        assert_eq!(MAC.format(Notation::Bare, false), "001a2b3c4d5e");
+        assert!(MacAddr::BROADCAST.is_multicast());
+        assert!(!MAC.is_multicast() && !MAC.is_local());
+        assert!(MacAddr([0x02, 0, 0, 0, 0, 1]).is_local());
+    }
+
+    #[test]
+    fn parse_mac_should_report_errors() {
+        assert_eq!(parse_mac("00:1a-2b:3c:4d:5e").unwrap_err().offset(), 5);
+        assert!(parse_mac("00:1a:2b:3c:4d").is_err());
+        assert!(parse_mac("001a.2b3c").is_err());
+        assert!(parse_mac("001a2b3c4d5").is_err());
+        assert!(parse_mac("00:1a:2b:3c:4d:5e:6f").is_err());
+        assert!(parse_mac("zz:1a:2b:3c:4d:5e").is_err());
+    }
+}
@@ -0,0 +1,861 @@
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::space0;
+use winnow::combinator::{alt, delimited, eof, opt, preceded, terminated};
+use winnow::error::{ContextError, ErrMode};
+use winnow::token::{one_of, rest, take_till, take_while};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Heading {
+        level: u8,
+        content: Vec<Inline>,
+    },
+    Paragraph(Vec<Inline>),
+    CodeBlock {
+        info: Option<String>,
+        code: String,
+    },
+    /// `start` is set for ordered lists. A tight list has no blank lines
+    /// between its items and renders them without `<p>`.
+    List {
+        start: Option<u64>,
+        tight: bool,
+        items: Vec<Vec<Block>>,
+    },
+    BlockQuote(Vec<Block>),
+    ThematicBreak,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Inline {
+    Text(String),
+    Emphasis(Vec<Inline>),
+    Strong(Vec<Inline>),
+    Code(String),
+    Link {
+        content: Vec<Inline>,
+        url: String,
+        title: Option<String>,
+    },
+    Image {
+        alt: String,
+        url: String,
+        title: Option<String>,
+    },
+    LineBreak,
+    SoftBreak,
+}
+
+/// Parses a document in two phases: lines are first grouped into blocks,
+/// then the text of paragraphs and headings is parsed into inlines. Any
+/// input is valid Markdown, so this cannot fail.
+pub fn parse_markdown(input: &str) -> Vec<Block> {
+    let lines: Vec<&str> = input.lines().collect();
+    parse_blocks(&lines)
+}
+
+/// Runs a line recognizer that has to consume the whole line.
+fn recognize<'a, O>(
+    mut parser: impl Parser<&'a str, O, ErrMode<ContextError>>,
+    line: &'a str,
+) -> Option<O> {
+    parser.parse(line).ok()
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn is_blank(line: &str) -> bool {
+    line.trim().is_empty()
+}
+
+fn up_to_three_spaces<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    take_while(0..=3, ' ').parse_next(input)
+}
+
+fn atx_heading<'i>(input: &mut &'i str) -> ModalResult<(u8, &'i str)> {
+    let level = preceded(up_to_three_spaces, take_while(1..=6, '#'))
+        .map(|h: &str| h.len() as u8)
+        .parse_next(input)?;
+    let text = alt((eof, preceded(one_of([' ', '\t']), rest))).parse_next(input)?;
+    // A closing run of `#`s only counts when a space separates it.
+    let text = text.trim();
+    let stripped = text.trim_end_matches('#');
+    let text = if stripped.is_empty() || stripped.ends_with([' ', '\t']) {
+        stripped.trim_end()
+    } else {
+        text
+    };
+    Ok((level, text))
+}
+
+fn thematic_break(input: &mut &str) -> ModalResult<()> {
+    up_to_three_spaces(input)?;
+    let c = one_of(['*', '-', '_']).parse_next(input)?;
+    rest.verify(|r: &str| {
+        r.chars().filter(|&x| x == c).count() >= 2
+            && r.chars().all(|x| x == c || x == ' ' || x == '\t')
+    })
+    .void()
+    .parse_next(input)
+}
+
+fn setext_underline(input: &mut &str) -> ModalResult<u8> {
+    terminated(
+        preceded(
+            up_to_three_spaces,
+            alt((take_while(1.., '=').value(1), take_while(1.., '-').value(2))),
+        ),
+        (space0, eof),
+    )
+    .parse_next(input)
+}
+
+struct Fence<'a> {
+    indent: usize,
+    marker: char,
+    len: usize,
+    info: &'a str,
+}
+
+fn fence_open<'i>(input: &mut &'i str) -> ModalResult<Fence<'i>> {
+    let indent = up_to_three_spaces.parse_next(input)?.len();
+    let run = alt((take_while(3.., '`'), take_while(3.., '~'))).parse_next(input)?;
+    let marker = if run.starts_with('`') { '`' } else { '~' };
+    let info = rest
+        .verify(|info: &str| marker == '~' || !info.contains('`'))
+        .parse_next(input)?;
+    Ok(Fence {
+        indent,
+        marker,
+        len: run.len(),
+        info: info.trim(),
+    })
+}
+
+fn is_fence_close(line: &str, fence: &Fence<'_>) -> bool {
+    recognize(
+        (
+            up_to_three_spaces,
+            take_while(fence.len.., fence.marker),
+            space0,
+        ),
+        line,
+    )
+    .is_some()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ListKind {
+    Bullet(char),
+    Ordered(char),
+}
+
+struct ListMarker<'a> {
+    kind: ListKind,
+    start: u64,
+    /// Column where the item's content starts; continuation lines must be
+    /// indented at least this far to belong to the item.
+    width: usize,
+    content: &'a str,
+}
+
+fn list_marker<'i>(input: &mut &'i str) -> ModalResult<ListMarker<'i>> {
+    let lead = up_to_three_spaces.parse_next(input)?.len();
+    let (kind, start, marker_len) = alt((
+        one_of(['-', '*', '+']).map(|c| (ListKind::Bullet(c), 1, 1)),
+        (
+            take_while(1..=9, |c: char| c.is_ascii_digit()),
+            one_of(['.', ')']),
+        )
+            .map(|(n, d): (&str, char)| {
+                (ListKind::Ordered(d), n.parse().unwrap_or(0), n.len() + 1)
+            }),
+    ))
+    .parse_next(input)?;
+    let after_marker = *input;
+    let spaces = alt((eof.value(0), take_while(1.., ' ').map(str::len))).parse_next(input)?;
+    let content = rest.parse_next(input)?;
+    // Five or more spaces start an indented code block inside the item,
+    // whose content then begins one space after the marker.
+    let (spaces, content) = if spaces > 4 {
+        (1, &after_marker[1..])
+    } else {
+        (spaces.max(1), content)
+    };
+    Ok(ListMarker {
+        kind,
+        start,
+        width: lead + marker_len + spaces,
+        content,
+    })
+}
+
+fn is_block_quote(line: &str) -> bool {
+    recognize((up_to_three_spaces, '>', rest), line).is_some()
+}
+
+/// Whether `line` starts a block that ends a paragraph before it.
+fn interrupts_paragraph(line: &str) -> bool {
+    is_blank(line)
+        || recognize(atx_heading, line).is_some()
+        || recognize(fence_open, line).is_some()
+        || recognize(thematic_break, line).is_some()
+        || is_block_quote(line)
+        // Only non-empty items interrupt, and ordered ones only from 1, so
+        // that numbers like "2024." at a line start stay in the paragraph.
+        || recognize(list_marker, line).is_some_and(|m| {
+            !m.content.trim().is_empty() && (matches!(m.kind, ListKind::Bullet(_)) || m.start == 1)
+        })
+}
+
+fn parse_blocks(lines: &[&str]) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if is_blank(line) {
+            i += 1;
+        } else if let Some((level, text)) = recognize(atx_heading, line) {
+            blocks.push(Block::Heading {
+                level,
+                content: parse_inlines(text),
+            });
+            i += 1;
+        } else if let Some(fence) = recognize(fence_open, line) {
+            let mut code = String::new();
+            i += 1;
+            while i < lines.len() && !is_fence_close(lines[i], &fence) {
+                let l = lines[i];
+                code.push_str(&l[indent(l).min(fence.indent)..]);
+                code.push('\n');
+                i += 1;
+            }
+            // Skip the closing fence; an unclosed one runs to the end.
+            i += 1;
+            let info = fence.info.split_whitespace().next().map(String::from);
+            blocks.push(Block::CodeBlock { info, code });
+        } else if indent(line) >= 4 {
+            let mut end = i;
+            let mut j = i;
+            while j < lines.len() && (is_blank(lines[j]) || indent(lines[j]) >= 4) {
+                if !is_blank(lines[j]) {
+                    end = j + 1;
+                }
+                j += 1;
+            }
+            let code = lines[i..end]
+                .iter()
+                .map(|l| format!("{}\n", l.get(4..).unwrap_or_default()))
+                .collect();
+            blocks.push(Block::CodeBlock { info: None, code });
+            i = end;
+        } else if recognize(thematic_break, line).is_some() {
+            blocks.push(Block::ThematicBreak);
+            i += 1;
+        } else if is_block_quote(line) {
+            let mut inner = Vec::new();
+            while i < lines.len() {
+                let l = lines[i];
+                if let Some(stripped) = l.trim_start().strip_prefix('>') {
+                    inner.push(stripped.strip_prefix(' ').unwrap_or(stripped));
+                } else if !inner.last().is_none_or(|last| is_blank(last))
+                    && !interrupts_paragraph(l)
+                {
+                    // Lazy continuation of a quoted paragraph.
+                    inner.push(l);
+                } else {
+                    break;
+                }
+                i += 1;
+            }
+            blocks.push(Block::BlockQuote(parse_blocks(&inner)));
+        } else if let Some(marker) = recognize(list_marker, line) {
+            let (list, next) = parse_list(lines, i, marker);
+            blocks.push(list);
+            i = next;
+        } else {
+            let mut text = vec![line.trim_start()];
+            i += 1;
+            let mut heading = None;
+            while i < lines.len() {
+                if let Some(level) = recognize(setext_underline, lines[i]) {
+                    heading = Some(level);
+                    i += 1;
+                    break;
+                }
+                if interrupts_paragraph(lines[i]) {
+                    break;
+                }
+                text.push(lines[i].trim_start());
+                i += 1;
+            }
+            let content = parse_inlines(text.join("\n").trim_end());
+            blocks.push(match heading {
+                Some(level) => Block::Heading { level, content },
+                None => Block::Paragraph(content),
+            });
+        }
+    }
+    blocks
+}
+
+/// Parses the list starting at `lines[start]`, returning it with the index
+/// of the first line after it.
+fn parse_list(lines: &[&str], start: usize, first: ListMarker<'_>) -> (Block, usize) {
+    let kind = first.kind;
+    let number = matches!(kind, ListKind::Ordered(_)).then_some(first.start);
+    let mut items = Vec::new();
+    let mut tight = true;
+    let mut i = start;
+    let mut marker = Some(first);
+    while let Some(m) = marker.take() {
+        let mut item = vec![m.content];
+        i += 1;
+        while i < lines.len() {
+            let l = lines[i];
+            if is_blank(l) {
+                item.push("");
+            } else if indent(l) >= m.width {
+                item.push(&l[m.width..]);
+            } else if !is_blank(item.last().copied().unwrap_or_default())
+                && !interrupts_paragraph(l)
+                && recognize(list_marker, l).is_none()
+            {
+                item.push(l);
+            } else {
+                break;
+            }
+            i += 1;
+        }
+        // Trailing blank lines belong between items, not to this one.
+        let mut trailing = 0;
+        while item.len() > 1 && item.last().is_some_and(|l| is_blank(l)) {
+            item.pop();
+            trailing += 1;
+        }
+        i -= trailing;
+        let blocks = parse_blocks(&item);
+        if blocks.len() > 1 && item.iter().any(|l| is_blank(l)) {
+            tight = false;
+        }
+        items.push(blocks);
+
+        let mut next = i;
+        while next < lines.len() && is_blank(lines[next]) {
+            next += 1;
+        }
+        // `* * *` is a thematic break even inside a `*` list.
+        if let Some(m) = lines
+            .get(next)
+            .filter(|l| recognize(thematic_break, l).is_none())
+            .and_then(|l| recognize(list_marker, l))
+            .filter(|m| m.kind == kind)
+        {
+            if next > i {
+                tight = false;
+            }
+            i = next;
+            marker = Some(m);
+        }
+    }
+    let list = Block::List {
+        start: number,
+        tight,
+        items,
+    };
+    (list, i)
+}
+
+/// ASCII punctuation, which a backslash may escape.
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation()
+}
+
+/// Characters that may start something other than plain text.
+fn is_special(c: char) -> bool {
+    matches!(c, '\\' | '`' | '*' | '_' | '[' | '!' | '<' | '\n' | ' ')
+}
+
+/// Parses the inline content of a paragraph or heading.
+pub fn parse_inlines(text: &str) -> Vec<Inline> {
+    let mut input = text;
+    let mut out: Vec<Inline> = Vec::new();
+    while !input.is_empty() {
+        // An underscore inside a word never starts emphasis.
+        let intraword = input.starts_with('_')
+            && matches!(out.last(), Some(Inline::Text(t)) if t.ends_with(|c: char| c.is_alphanumeric()));
+        let start = input;
+        let node = if intraword {
+            None
+        } else {
+            parse_inline.parse_next(&mut input).ok()
+        };
+        if node.is_none() {
+            input = start;
+        }
+        let node = node.unwrap_or_else(|| {
+            let c = input.chars().next().unwrap_or_default();
+            input = &input[c.len_utf8()..];
+            Inline::Text(c.to_string())
+        });
+        match (out.last_mut(), node) {
+            (Some(Inline::Text(last)), Inline::Text(t)) => last.push_str(&t),
+            (_, node) => out.push(node),
+        }
+    }
+    out
+}
+
+fn parse_inline(input: &mut &str) -> ModalResult<Inline> {
+    alt((
+        take_till(1.., is_special).map(|t: &str| Inline::Text(t.to_string())),
+        preceded('\\', one_of(is_punctuation)).map(|c: char| Inline::Text(c.to_string())),
+        alt(((take_while(2.., ' '), '\n').void(), "\\\n".void())).value(Inline::LineBreak),
+        (space0, '\n', space0).value(Inline::SoftBreak),
+        take_while(1.., ' ').map(|t: &str| Inline::Text(t.to_string())),
+        parse_code_span,
+        preceded('!', parse_link).map(|(content, url, title)| Inline::Image {
+            alt: plain_text(&content),
+            url,
+            title,
+        }),
+        parse_link.map(|(content, url, title)| Inline::Link {
+            content,
+            url,
+            title,
+        }),
+        parse_autolink,
+        parse_emphasis,
+    ))
+    .parse_next(input)
+}
+
+fn parse_code_span(input: &mut &str) -> ModalResult<Inline> {
+    let ticks = take_while(1.., '`').parse_next(input)?;
+    // The span ends at the next run of exactly as many backticks.
+    let mut search = 0;
+    while let Some(found) = input[search..].find(ticks) {
+        let at = search + found;
+        let run = input[at..].len() - input[at..].trim_start_matches('`').len();
+        if run == ticks.len() {
+            let code = input[..at].replace('\n', " ");
+            *input = &input[at + run..];
+            let stripped = code.strip_prefix(' ').and_then(|c| c.strip_suffix(' '));
+            let code = match stripped {
+                Some(c) if !code.trim().is_empty() => c.to_string(),
+                _ => code,
+            };
+            return Ok(Inline::Code(code));
+        }
+        search = at + run;
+    }
+    // Without a closing run the backticks are literal.
+    Ok(Inline::Text(ticks.to_string()))
+}
+
+type LinkParts = (Vec<Inline>, String, Option<String>);
+
+fn parse_link(input: &mut &str) -> ModalResult<LinkParts> {
+    let text = bracketed(input)?;
+    '('.parse_next(input)?;
+    space0.parse_next(input)?;
+    let url = alt((
+        delimited('<', take_till(0.., ['>', '\n']), '>'),
+        link_destination,
+    ))
+    .parse_next(input)?;
+    let title = opt(preceded(
+        take_while(1.., [' ', '\n']),
+        alt((
+            delimited('"', take_till(0.., '"'), '"'),
+            delimited('\'', take_till(0.., '\''), '\''),
+            delimited('(', take_till(0.., ')'), ')'),
+        )),
+    ))
+    .parse_next(input)?;
+    (take_while(0.., [' ', '\n']), ')').parse_next(input)?;
+    Ok((
+        parse_inlines(text),
+        url.to_string(),
+        title.map(String::from),
+    ))
+}
+
+/// The text between `[` and its matching `]`, allowing nested brackets and
+/// escapes.
+fn bracketed<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    '['.parse_next(input)?;
+    let mut depth = 0;
+    let mut chars = input.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '[' => depth += 1,
+            ']' if depth == 0 => {
+                let text = &input[..i];
+                *input = &input[i + 1..];
+                return Ok(text);
+            }
+            ']' => depth -= 1,
+            _ => {}
+        }
+    }
+    winnow::combinator::fail.parse_next(input)
+}
+
+/// A bare destination, which may contain balanced parentheses.
+fn link_destination<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    let mut depth = 0;
+    let end = input
+        .char_indices()
+        .find(|&(_, c)| match c {
+            '(' => {
+                depth += 1;
+                false
+            }
+            ')' if depth == 0 => true,
+            ')' => {
+                depth -= 1;
+                false
+            }
+            c => c.is_whitespace() || c.is_control(),
+        })
+        .map_or(input.len(), |(i, _)| i);
+    let url = &input[..end];
+    *input = &input[end..];
+    Ok(url)
+}
+
+fn parse_autolink(input: &mut &str) -> ModalResult<Inline> {
+    delimited(
+        '<',
+        (
+            take_while(2..=32, |c: char| {
+                c.is_ascii_alphanumeric() || matches!(c, '+' | '.' | '-')
+            }),
+            ':',
+            take_till(0.., |c: char| c.is_whitespace() || c == '<' || c == '>'),
+        )
+            .take(),
+        '>',
+    )
+    .map(|url: &str| Inline::Link {
+        content: vec![Inline::Text(url.to_string())],
+        url: url.to_string(),
+        title: None,
+    })
+    .parse_next(input)
+}
+
+/// `*em*`, `**strong**` and `***both***`, or the same with underscores.
+/// The closing delimiter is the first one that is not preceded by
+/// whitespace; runs of the other length in between are nested emphasis and
+/// skipped over.
+fn parse_emphasis(input: &mut &str) -> ModalResult<Inline> {
+    let marker = one_of(['*', '_']).parse_next(input)?;
+    let run = 1 + input.len() - input.trim_start_matches(marker).len();
+    let len = run.min(3);
+    *input = &input[len - 1..];
+    if input.starts_with(char::is_whitespace) || input.is_empty() {
+        return winnow::combinator::fail.parse_next(input);
+    }
+    let close =
+        find_closing(input, marker, len).ok_or_else(|| ErrMode::Backtrack(ContextError::new()))?;
+    let content = parse_inlines(&input[..close]);
+    *input = &input[close + len..];
+    Ok(match len {
+        1 => Inline::Emphasis(content),
+        2 => Inline::Strong(content),
+        _ => Inline::Emphasis(vec![Inline::Strong(content)]),
+    })
+}
+
+fn find_closing(text: &str, marker: char, len: usize) -> Option<usize> {
+    let mut chars = text.char_indices().peekable();
+    let mut prev = None;
+    while let Some((i, c)) = chars.next() {
+        if c == '\\' {
+            chars.next();
+            prev = Some('\\');
+            continue;
+        }
+        if c == '`' {
+            // Delimiters inside code spans do not count.
+            let mut rest = &text[i..];
+            if let Ok(Inline::Code(_)) = parse_code_span.parse_next(&mut rest) {
+                let end = text.len() - rest.len();
+                while chars.peek().is_some_and(|&(j, _)| j < end) {
+                    chars.next();
+                }
+                prev = Some('`');
+                continue;
+            }
+        }
+        if c != marker {
+            prev = Some(c);
+            continue;
+        }
+        let run = text[i..].len() - text[i..].trim_start_matches(marker).len();
+        let after = text[i + run..].chars().next();
+        let flanking = prev.is_some_and(|p| !p.is_whitespace());
+        let word_after = marker == '_' && after.is_some_and(char::is_alphanumeric);
+        if run >= len && flanking && !word_after {
+            // Extra delimiters in front close nested emphasis, as in `em***`.
+            return Some(i + run - len);
+        }
+        for _ in 1..run {
+            chars.next();
+        }
+        prev = Some(marker);
+    }
+    None
+}
+
+fn plain_text(inlines: &[Inline]) -> String {
+    let mut out = String::new();
+    for inline in inlines {
+        match inline {
+            Inline::Text(t) | Inline::Code(t) => out.push_str(t),
+            Inline::Emphasis(c) | Inline::Strong(c) | Inline::Link { content: c, .. } => {
+                out.push_str(&plain_text(c))
+            }
+            Inline::Image { alt, .. } => out.push_str(alt),
+            Inline::LineBreak | Inline::SoftBreak => out.push(' '),
+        }
+    }
+    out
+}
+
+/// Renders blocks as HTML, in the shape the CommonMark reference
+/// implementation produces.
+pub fn to_html(blocks: &[Block]) -> String {
+    let mut out = String::new();
+    for block in blocks {
+        render_block(&mut out, block);
+    }
+    out
+}
+
+fn render_block(out: &mut String, block: &Block) {
+    match block {
+        Block::Heading { level, content } => {
+            out.push_str(&format!("<h{}>", level));
+            render_inlines(out, content);
+            out.push_str(&format!("</h{}>\n", level));
+        }
+        Block::Paragraph(content) => {
+            out.push_str("<p>");
+            render_inlines(out, content);
+            out.push_str("</p>\n");
+        }
+        Block::CodeBlock { info, code } => {
+            match info {
+                Some(lang) => {
+                    out.push_str(&format!("<pre><code class=\"language-{}\">", escape(lang)))
+                }
+                None => out.push_str("<pre><code>"),
+            }
+            out.push_str(&escape(code));
+            out.push_str("</code></pre>\n");
+        }
+        Block::List {
+            start,
+            tight,
+            items,
+        } => {
+            let tag = match start {
+                Some(1) => "<ol>".to_string(),
+                Some(n) => format!("<ol start=\"{}\">", n),
+                None => "<ul>".to_string(),
+            };
+            out.push_str(&tag);
+            out.push('\n');
+            for item in items {
+                out.push_str("<li>");
+                for block in item {
+                    match block {
+                        Block::Paragraph(content) if *tight => render_inlines(out, content),
+                        block => {
+                            if !out.ends_with('\n') {
+                                out.push('\n');
+                            }
+                            render_block(out, block);
+                        }
+                    }
+                }
+                out.push_str("</li>\n");
+            }
+            out.push_str(if start.is_some() {
+                "</ol>\n"
+            } else {
+                "</ul>\n"
+            });
+        }
+        Block::BlockQuote(blocks) => {
+            out.push_str("<blockquote>\n");
+            for block in blocks {
+                render_block(out, block);
+            }
+            out.push_str("</blockquote>\n");
+        }
+        Block::ThematicBreak => out.push_str("<hr />\n"),
+    }
+}
+
+fn render_inlines(out: &mut String, inlines: &[Inline]) {
+    for inline in inlines {
+        match inline {
+            Inline::Text(t) => out.push_str(&escape(t)),
+            Inline::Emphasis(c) => {
+                out.push_str("<em>");
+                render_inlines(out, c);
+                out.push_str("</em>");
+            }
+            Inline::Strong(c) => {
+                out.push_str("<strong>");
+                render_inlines(out, c);
+                out.push_str("</strong>");
+            }
+            Inline::Code(code) => out.push_str(&format!("<code>{}</code>", escape(code))),
+            Inline::Link {
+                content,
+                url,
+                title,
+            } => {
+                out.push_str(&format!("<a href=\"{}\"", escape(url)));
+                if let Some(title) = title {
+                    out.push_str(&format!(" title=\"{}\"", escape(title)));
+                }
+                out.push('>');
+                render_inlines(out, content);
+                out.push_str("</a>");
+            }
+            Inline::Image { alt, url, title } => {
+                out.push_str(&format!(
+                    "<img src=\"{}\" alt=\"{}\"",
+                    escape(url),
+                    escape(alt)
+                ));
+                if let Some(title) = title {
+                    out.push_str(&format!(" title=\"{}\"", escape(title)));
+                }
+                out.push_str(" />");
+            }
+            Inline::LineBreak => out.push_str("<br />\n"),
+            Inline::SoftBreak => out.push('\n'),
+        }
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn html(input: &str) -> String {
+        to_html(&parse_markdown(input))
+    }
+
+    #[test]
+    fn parse_blocks_should_work() {
+        let blocks = parse_markdown(
+            "# Title #\n\nSome *text*\ncontinued\n\n---\n\n```rust extra\nfn main() {}\n```\n",
+        );
+        assert_eq!(
+            blocks,
+            [
+                Block::Heading {
+                    level: 1,
+                    content: vec![Inline::Text("Title".into())]
+                },
+                Block::Paragraph(vec![
+                    Inline::Text("Some ".into()),
+                    Inline::Emphasis(vec![Inline::Text("text".into())]),
+                    Inline::SoftBreak,
+                    Inline::Text("continued".into()),
+                ]),
+                Block::ThematicBreak,
+                Block::CodeBlock {
+                    info: Some("rust".into()),
+                    code: "fn main() {}\n".into()
+                },
+            ]
+        );
+        assert_eq!(
+            html("Title\n===\nSub\n---"),
+            "<h1>Title</h1>\n<h2>Sub</h2>\n"
+        );
+        assert_eq!(
+            html("    code\n      more\n\ntext"),
+            "<pre><code>code\n  more\n</code></pre>\n<p>text</p>\n"
+        );
+        assert_eq!(
+            html("> quoted\nlazy\n> > nested"),
+            "<blockquote>\n<p>quoted\nlazy</p>\n<blockquote>\n<p>nested</p>\n</blockquote>\n</blockquote>\n"
+        );
+    }
+
+    #[test]
+    fn parse_lists_should_work() {
+        assert_eq!(
+            html("- one\n- two\n  - nested\n- three"),
+            "<ul>\n<li>one</li>\n<li>two\n<ul>\n<li>nested</li>\n</ul>\n</li>\n<li>three</li>\n</ul>\n"
+        );
+        assert_eq!(
+            html("3. a\n\n4. b\n\n   more\n"),
+            "<ol start=\"3\">\n<li>\n<p>a</p>\n</li>\n<li>\n<p>b</p>\n<p>more</p>\n</li>\n</ol>\n"
+        );
+        // A different bullet starts a new list.
+        assert_eq!(
+            html("- a\n+ b"),
+            "<ul>\n<li>a</li>\n</ul>\n<ul>\n<li>b</li>\n</ul>\n"
+        );
+    }
+
+    #[test]
+    fn parse_inlines_should_work() {
+        assert_eq!(
+            parse_inlines("**bold *and em*** `a ``b`` c` snake_case_name"),
+            [
+                Inline::Strong(vec![
+                    Inline::Text("bold ".into()),
+                    Inline::Emphasis(vec![Inline::Text("and em".into())]),
+                ]),
+                Inline::Text(" ".into()),
+                Inline::Code("a ``b`` c".into()),
+                Inline::Text(" snake_case_name".into()),
+            ]
+        );
+        assert_eq!(
+            html(r#"[a *link*](/url "title") ![img](a.png) <https://x.org> \*not\* 2 < 3"#),
+            "<p><a href=\"/url\" title=\"title\">a <em>link</em></a> <img src=\"a.png\" alt=\"img\" /> <a href=\"https://x.org\">https://x.org</a> *not* 2 &lt; 3</p>\n"
+        );
+        assert_eq!(
+            html("hard  \nbreak\\\nagain"),
+            "<p>hard<br />\nbreak<br />\nagain</p>\n"
+        );
+        assert_eq!(html("[unclosed *star"), "<p>[unclosed *star</p>\n");
+        assert_eq!(
+            html("***both***"),
+            "<p><em><strong>both</strong></em></p>\n"
+        );
+    }
+}
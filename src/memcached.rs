@@ -0,0 +1,511 @@
+use std::str::FromStr;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::combinator::{alt, cut_err, fail, opt, preceded, repeat, terminated};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::{take, take_while};
+
+use crate::ParseError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Store(Store),
+    /// `get` and `gets`; the latter asks for CAS values.
+    Get {
+        keys: Vec<String>,
+        cas: bool,
+    },
+    /// `gat` and `gats`.
+    GetAndTouch {
+        exptime: i64,
+        keys: Vec<String>,
+        cas: bool,
+    },
+    Delete {
+        key: String,
+        noreply: bool,
+    },
+    Incr {
+        key: String,
+        delta: u64,
+        noreply: bool,
+    },
+    Decr {
+        key: String,
+        delta: u64,
+        noreply: bool,
+    },
+    Touch {
+        key: String,
+        exptime: i64,
+        noreply: bool,
+    },
+    FlushAll {
+        delay: Option<i64>,
+        noreply: bool,
+    },
+    Verbosity {
+        level: u32,
+        noreply: bool,
+    },
+    Stats(Vec<String>),
+    Version,
+    Quit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreKind {
+    Set,
+    Add,
+    Replace,
+    Append,
+    Prepend,
+    /// Store only if the item still has this CAS value.
+    Cas(u64),
+}
+
+/// A storage command and its data block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Store {
+    pub kind: StoreKind,
+    pub key: String,
+    pub flags: u32,
+    /// Seconds from now, or a Unix time when above 30 days; negative
+    /// values expire the item at once.
+    pub exptime: i64,
+    pub data: Vec<u8>,
+    pub noreply: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response {
+    /// `VALUE` blocks up to `END`. A bare `END` is an empty list.
+    Values(Vec<Item>),
+    Stored,
+    NotStored,
+    Exists,
+    NotFound,
+    Deleted,
+    Touched,
+    Ok,
+    Error,
+    ClientError(String),
+    ServerError(String),
+    /// The new value after `incr` or `decr`.
+    Number(u64),
+    /// `STAT` lines up to `END`.
+    Stats(Vec<(String, String)>),
+    Version(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub key: String,
+    pub flags: u32,
+    pub data: Vec<u8>,
+    /// Present in answers to `gets` and `gats`.
+    pub cas: Option<u64>,
+}
+
+/// Keys are at most this many bytes long.
+pub const MAX_KEY_LENGTH: usize = 250;
+
+/// Parses a single command, including the data block of storage commands.
+pub fn parse_command(input: &[u8]) -> Result<Command, ParseError> {
+    command.parse(input).map_err(ParseError::from)
+}
+
+/// Parses pipelined commands, as a client would write them back to back.
+pub fn parse_commands(input: &[u8]) -> Result<Vec<Command>, ParseError> {
+    repeat(0.., command).parse(input).map_err(ParseError::from)
+}
+
+pub fn parse_response(input: &[u8]) -> Result<Response, ParseError> {
+    response.parse(input).map_err(ParseError::from)
+}
+
+fn command(input: &mut &[u8]) -> ModalResult<Command> {
+    let start = *input;
+    let name = word.parse_next(input)?;
+    let kind = match name {
+        b"set" => Some(StoreKind::Set),
+        b"add" => Some(StoreKind::Add),
+        b"replace" => Some(StoreKind::Replace),
+        b"append" => Some(StoreKind::Append),
+        b"prepend" => Some(StoreKind::Prepend),
+        b"cas" => Some(StoreKind::Cas(0)),
+        _ => None,
+    };
+    if let Some(kind) = kind {
+        return store(input, kind).map(Command::Store);
+    }
+    let command = match name {
+        b"get" | b"gets" => Command::Get {
+            keys: keys(input)?,
+            cas: name == b"gets",
+        },
+        b"gat" | b"gats" => Command::GetAndTouch {
+            exptime: argument(input, "exptime")?,
+            keys: keys(input)?,
+            cas: name == b"gats",
+        },
+        b"delete" => Command::Delete {
+            key: key_argument(input)?,
+            noreply: noreply(input)?,
+        },
+        b"incr" | b"decr" => {
+            let key = key_argument(input)?;
+            let delta = argument(input, "delta")?;
+            let noreply = noreply(input)?;
+            if name == b"incr" {
+                Command::Incr {
+                    key,
+                    delta,
+                    noreply,
+                }
+            } else {
+                Command::Decr {
+                    key,
+                    delta,
+                    noreply,
+                }
+            }
+        }
+        b"touch" => Command::Touch {
+            key: key_argument(input)?,
+            exptime: argument(input, "exptime")?,
+            noreply: noreply(input)?,
+        },
+        b"flush_all" => Command::FlushAll {
+            delay: opt(preceded(spaces, number)).parse_next(input)?,
+            noreply: noreply(input)?,
+        },
+        b"verbosity" => Command::Verbosity {
+            level: argument(input, "level")?,
+            noreply: noreply(input)?,
+        },
+        b"stats" => {
+            Command::Stats(repeat(0.., preceded(spaces, word.map(lossy))).parse_next(input)?)
+        }
+        b"version" => Command::Version,
+        b"quit" => Command::Quit,
+        _ => {
+            *input = start;
+            return cut_err(fail)
+                .context(StrContext::Label("unknown command"))
+                .parse_next(input);
+        }
+    };
+    cut_err(line_end).parse_next(input)?;
+    Ok(command)
+}
+
+/// The rest of a storage command: its arguments, then the data block.
+fn store(input: &mut &[u8], kind: StoreKind) -> ModalResult<Store> {
+    let key = key_argument(input)?;
+    let flags = argument(input, "flags")?;
+    let exptime = argument(input, "exptime")?;
+    let bytes: usize = argument(input, "byte count")?;
+    let kind = match kind {
+        StoreKind::Cas(_) => StoreKind::Cas(argument(input, "cas unique")?),
+        kind => kind,
+    };
+    let noreply = noreply(input)?;
+    cut_err(line_end).parse_next(input)?;
+    Ok(Store {
+        kind,
+        key,
+        flags,
+        exptime,
+        data: data(input, bytes)?,
+        noreply,
+    })
+}
+
+fn response(input: &mut &[u8]) -> ModalResult<Response> {
+    let start = *input;
+    let name = word.parse_next(input)?;
+    let response = match name {
+        b"VALUE" => {
+            *input = start;
+            let items = repeat(1.., item).parse_next(input)?;
+            terminated(cut_err(b"END"), cut_err(line_end))
+                .context(StrContext::Expected(StrContextValue::StringLiteral("END")))
+                .parse_next(input)?;
+            return Ok(Response::Values(items));
+        }
+        b"STAT" => {
+            *input = start;
+            let stats = repeat(1.., stat).parse_next(input)?;
+            terminated(cut_err(b"END"), cut_err(line_end))
+                .context(StrContext::Expected(StrContextValue::StringLiteral("END")))
+                .parse_next(input)?;
+            return Ok(Response::Stats(stats));
+        }
+        b"END" => Response::Values(Vec::new()),
+        b"STORED" => Response::Stored,
+        b"NOT_STORED" => Response::NotStored,
+        b"EXISTS" => Response::Exists,
+        b"NOT_FOUND" => Response::NotFound,
+        b"DELETED" => Response::Deleted,
+        b"TOUCHED" => Response::Touched,
+        b"OK" => Response::Ok,
+        b"ERROR" => Response::Error,
+        b"CLIENT_ERROR" => Response::ClientError(message(input)?),
+        b"SERVER_ERROR" => Response::ServerError(message(input)?),
+        b"VERSION" => Response::Version(message(input)?),
+        digits if digits.iter().all(u8::is_ascii_digit) => {
+            *input = start;
+            Response::Number(number(input)?)
+        }
+        _ => {
+            *input = start;
+            return cut_err(fail)
+                .context(StrContext::Label("unknown response"))
+                .parse_next(input);
+        }
+    };
+    cut_err(line_end).parse_next(input)?;
+    Ok(response)
+}
+
+fn item(input: &mut &[u8]) -> ModalResult<Item> {
+    b"VALUE".parse_next(input)?;
+    let key = key_argument(input)?;
+    let flags = argument(input, "flags")?;
+    let bytes = argument(input, "byte count")?;
+    let cas = opt(preceded(spaces, number)).parse_next(input)?;
+    cut_err(line_end).parse_next(input)?;
+    let data = data(input, bytes)?;
+    Ok(Item {
+        key,
+        flags,
+        data,
+        cas,
+    })
+}
+
+fn stat(input: &mut &[u8]) -> ModalResult<(String, String)> {
+    b"STAT ".parse_next(input)?;
+    let name = cut_err(word.map(lossy))
+        .context(StrContext::Label("stat name"))
+        .parse_next(input)?;
+    let value = message.parse_next(input)?;
+    cut_err(line_end).parse_next(input)?;
+    Ok((name, value))
+}
+
+/// A data block of exactly `len` bytes and its `\r\n`. The length comes
+/// from the header, so the data may hold any bytes, line breaks included.
+fn data(input: &mut &[u8], len: usize) -> ModalResult<Vec<u8>> {
+    terminated(
+        cut_err(take(len))
+            .context(StrContext::Label("data block"))
+            .context(StrContext::Expected(StrContextValue::Description(
+                "as many bytes as announced",
+            ))),
+        cut_err(b"\r\n").context(StrContext::Expected(StrContextValue::Description(
+            "\\r\\n after the data block",
+        ))),
+    )
+    .map(<[u8]>::to_vec)
+    .parse_next(input)
+}
+
+/// The rest of the line after a single space, if any.
+fn message(input: &mut &[u8]) -> ModalResult<String> {
+    opt(preceded(
+        b' ',
+        take_while(0.., |b: u8| b != b'\r' && b != b'\n'),
+    ))
+    .map(|m: Option<&[u8]>| lossy(m.unwrap_or_default()))
+    .parse_next(input)
+}
+
+fn keys(input: &mut &[u8]) -> ModalResult<Vec<String>> {
+    repeat(1.., preceded(spaces, key))
+        .context(StrContext::Expected(StrContextValue::Description("a key")))
+        .parse_next(input)
+}
+
+fn key_argument(input: &mut &[u8]) -> ModalResult<String> {
+    cut_err(preceded(spaces, key))
+        .context(StrContext::Expected(StrContextValue::Description("a key")))
+        .parse_next(input)
+}
+
+fn key(input: &mut &[u8]) -> ModalResult<String> {
+    word.verify(|k: &[u8]| k.len() <= MAX_KEY_LENGTH)
+        .map(lossy)
+        .parse_next(input)
+}
+
+fn argument<T: FromStr>(input: &mut &[u8], label: &'static str) -> ModalResult<T> {
+    cut_err(preceded(spaces, number))
+        .context(StrContext::Label(label))
+        .parse_next(input)
+}
+
+fn number<T: FromStr>(input: &mut &[u8]) -> ModalResult<T> {
+    take_while(1.., |b: u8| b.is_ascii_digit() || b == b'-')
+        .verify_map(|n: &[u8]| std::str::from_utf8(n).ok()?.parse().ok())
+        .parse_next(input)
+}
+
+fn noreply(input: &mut &[u8]) -> ModalResult<bool> {
+    opt(preceded(spaces, b"noreply"))
+        .map(|n| n.is_some())
+        .parse_next(input)
+}
+
+/// A run of printable bytes other than space.
+fn word<'i>(input: &mut &'i [u8]) -> ModalResult<&'i [u8]> {
+    take_while(1.., |b: u8| b.is_ascii_graphic()).parse_next(input)
+}
+
+fn spaces(input: &mut &[u8]) -> ModalResult<()> {
+    take_while(1.., b' ').void().parse_next(input)
+}
+
+fn lossy(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// The protocol asks for `\r\n`, but servers accept a bare `\n` on
+/// command lines.
+fn line_end(input: &mut &[u8]) -> ModalResult<()> {
+    alt((b"\r\n".void(), b"\n".void()))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "line ending",
+        )))
+        .parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_command_should_work() -> Result<(), ParseError> {
+        assert_eq!(
+            parse_command(b"set user:1 5 3600 7 noreply\r\nab\r\ncde\r\n")?,
+            Command::Store(Store {
+                kind: StoreKind::Set,
+                key: "user:1".to_string(),
+                flags: 5,
+                exptime: 3600,
+                data: b"ab\r\ncde".to_vec(),
+                noreply: true,
+            })
+        );
+        let commands = parse_commands(
+            b"cas k 0 -1 1 99\r\nx\r\ngets a b\r\ngat 10 a\nincr n 5\r\nflush_all 0\r\n\
+              stats slabs\r\nquit\r\n",
+        )?;
+        assert_eq!(commands.len(), 7);
+        assert!(matches!(
+            &commands[0],
+            Command::Store(Store {
+                kind: StoreKind::Cas(99),
+                exptime: -1,
+                ..
+            })
+        ));
+        assert_eq!(
+            commands[1],
+            Command::Get {
+                keys: vec!["a".to_string(), "b".to_string()],
+                cas: true
+            }
+        );
+        assert!(matches!(
+            commands[2],
+            Command::GetAndTouch { exptime: 10, .. }
+        ));
+        assert!(matches!(
+            commands[3],
+            Command::Incr {
+                delta: 5,
+                noreply: false,
+                ..
+            }
+        ));
+        assert_eq!(
+            commands[4],
+            Command::FlushAll {
+                delay: Some(0),
+                noreply: false
+            }
+        );
+        assert_eq!(commands[5], Command::Stats(vec!["slabs".to_string()]));
+        assert_eq!(commands[6], Command::Quit);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_response_should_work() -> Result<(), ParseError> {
+        assert_eq!(
+            parse_response(b"VALUE a 1 3\r\n\x00\r\n\r\nVALUE b 0 0 42\r\n\r\nEND\r\n")?,
+            Response::Values(vec![
+                Item {
+                    key: "a".to_string(),
+                    flags: 1,
+                    data: b"\x00\r\n".to_vec(),
+                    cas: None,
+                },
+                Item {
+                    key: "b".to_string(),
+                    flags: 0,
+                    data: Vec::new(),
+                    cas: Some(42),
+                },
+            ])
+        );
+        assert_eq!(parse_response(b"END\r\n")?, Response::Values(vec![]));
+        assert_eq!(parse_response(b"NOT_STORED\r\n")?, Response::NotStored);
+        assert_eq!(parse_response(b"43\r\n")?, Response::Number(43));
+        assert_eq!(
+            parse_response(b"CLIENT_ERROR bad data chunk\r\n")?,
+            Response::ClientError("bad data chunk".to_string())
+        );
+        assert_eq!(
+            parse_response(b"STAT pid 42\r\nSTAT version 1.6.21\r\nEND\r\n")?,
+            Response::Stats(vec![
+                ("pid".to_string(), "42".to_string()),
+                ("version".to_string(), "1.6.21".to_string()),
+            ])
+        );
+        assert_eq!(
+            parse_response(b"VERSION 1.6.21\r\n")?,
+            Response::Version("1.6.21".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parsers_should_report_errors() {
+        assert_eq!(
+            parse_command(b"set k 0 0 9\r\nabc\r\n")
+                .unwrap_err()
+                .offset(),
+            13
+        );
+        assert_eq!(
+            parse_command(b"set k 0 0 2\r\nabc\r\n")
+                .unwrap_err()
+                .offset(),
+            15
+        );
+        assert_eq!(parse_command(b"fetch k\r\n").unwrap_err().offset(), 0);
+        assert_eq!(
+            parse_command(b"set k x 0 1\r\na\r\n").unwrap_err().offset(),
+            6
+        );
+        assert!(parse_command(b"get\r\n").is_err());
+        assert!(parse_command(&[b"get ".as_slice(), &[b'k'; 251], b"\r\n"].concat()).is_err());
+        assert!(parse_command(b"delete k").is_err());
+        assert!(parse_response(b"VALUE a 0 1\r\nx\r\n").is_err());
+        assert!(parse_response(b"WHAT\r\n").is_err());
+    }
+}
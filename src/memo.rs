@@ -0,0 +1,165 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+
+use winnow::Parser;
+use winnow::stream::{Stateful, Stream};
+
+/// The input of a grammar that memoizes rules with [`memo`].
+pub type Memoized<'m, I> = Stateful<I, &'m Memo>;
+
+/// A rule and how much input was left when it started.
+type Key = (&'static str, usize);
+
+/// Results of memoized rules, keyed by rule and position. One table serves
+/// one parse of one input.
+#[derive(Default)]
+pub struct Memo {
+    results: RefCell<HashMap<Key, Box<dyn Any>>>,
+}
+
+impl Memo {
+    pub fn new() -> Self {
+        Memo::default()
+    }
+
+    /// Wraps `input` so the rules parsing it share this table.
+    pub fn input<I>(&self, input: I) -> Memoized<'_, I> {
+        Stateful { input, state: self }
+    }
+
+    /// How many rule applications have been recorded.
+    pub fn len(&self) -> usize {
+        self.results.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Debug for Memo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Memo").field("len", &self.len()).finish()
+    }
+}
+
+/// What a rule did at one position: its output and how much input was left
+/// after it, or the error it failed with.
+type Entry<O, E> = Result<(O, usize), E>;
+
+/// Packrat memoization: runs `parser` at most once per position, replaying
+/// its result, failures included, whenever the rule is tried there again.
+/// That keeps grammars that backtrack a lot, such as long chains of `alt`
+/// over alternatives sharing a prefix, linear in the input.
+///
+/// `rule` names the parser and must be unique within the grammar. Outputs
+/// are cloned out of the table, so they have to own their data.
+pub fn memo<'m, I, O, E>(
+    rule: &'static str,
+    mut parser: impl Parser<Memoized<'m, I>, O, E>,
+) -> impl Parser<Memoized<'m, I>, O, E>
+where
+    I: Stream,
+    O: Clone + 'static,
+    E: Clone + 'static,
+{
+    move |input: &mut Memoized<'m, I>| {
+        let key = (rule, input.eof_offset());
+        let memo = input.state;
+        let hit = memo
+            .results
+            .borrow()
+            .get(&key)
+            .and_then(|entry| entry.downcast_ref::<Entry<O, E>>())
+            .cloned();
+        if let Some(entry) = hit {
+            return entry.map(|(output, rest)| {
+                input.next_slice(key.1 - rest);
+                output
+            });
+        }
+
+        let entry = parser
+            .parse_next(input)
+            .map(|output| (output, input.eof_offset()));
+        memo.results
+            .borrow_mut()
+            .insert(key, Box::new(entry.clone()));
+        entry.map(|(output, _)| output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use winnow::ModalResult;
+    use winnow::ascii::digit1;
+    use winnow::combinator::{alt, repeat, separated_pair};
+    use winnow::error::{ContextError, ErrMode};
+
+    use super::*;
+    use crate::ParseError;
+
+    type Input<'m, 'i> = Memoized<'m, &'i str>;
+
+    /// Every alternative starts with a number, so without memoization it is
+    /// parsed again for each one that fails.
+    fn grammar<'m, 'i>(
+        calls: &'m Cell<usize>,
+        memoize: bool,
+    ) -> impl Parser<Input<'m, 'i>, Vec<u32>, ErrMode<ContextError>> {
+        let mut number = move |input: &mut Input<'m, 'i>| -> ModalResult<u32> {
+            calls.set(calls.get() + 1);
+            digit1.parse_to().parse_next(input)
+        };
+        let number = move |input: &mut Input<'m, 'i>| {
+            if memoize {
+                memo("number", number).parse_next(input)
+            } else {
+                number.parse_next(input)
+            }
+        };
+        repeat(
+            1..,
+            alt((
+                separated_pair(number, '+', number).map(|(a, b)| a + b),
+                separated_pair(number, '-', number).map(|(a, b)| a - b),
+                separated_pair(number, ';', number).map(|(a, _)| a),
+                number,
+            )),
+        )
+    }
+
+    #[test]
+    fn memo_should_run_each_rule_once_per_position() -> Result<(), ParseError> {
+        let calls = Cell::new(0);
+        let memo = Memo::new();
+        let sums = grammar(&calls, true)
+            .parse(memo.input("7;1"))
+            .map_err(ParseError::from)?;
+        assert_eq!(sums, [7]);
+        assert_eq!(calls.get(), 3);
+        assert_eq!(memo.len(), 3);
+
+        let calls = Cell::new(0);
+        let memo = Memo::new();
+        grammar(&calls, false)
+            .parse(memo.input("7;1"))
+            .map_err(ParseError::from)?;
+        assert_eq!(calls.get(), 8);
+        assert!(memo.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn memo_should_replay_failures() {
+        let calls = Cell::new(0);
+        let memo = Memo::new();
+        let err = grammar(&calls, true).parse(memo.input("x")).unwrap_err();
+        assert_eq!(ParseError::from(err).offset(), 0);
+        assert_eq!(calls.get(), 1);
+    }
+}
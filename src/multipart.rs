@@ -0,0 +1,195 @@
+use winnow::Parser;
+
+use crate::ParseError;
+use crate::http::header::{
+    ContentDisposition, MediaType, parse_content_disposition, parse_content_type,
+};
+use crate::http::{Headers, headers};
+
+/// One body part. The body borrows from the input, so large uploads are
+/// never copied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part<'a> {
+    pub headers: Headers,
+    pub body: &'a [u8],
+}
+
+impl Part<'_> {
+    pub fn content_disposition(&self) -> Option<ContentDisposition> {
+        parse_content_disposition(self.headers.get("content-disposition")?).ok()
+    }
+
+    /// The form field name from `Content-Disposition`.
+    pub fn name(&self) -> Option<String> {
+        self.content_disposition()?.name().map(str::to_string)
+    }
+
+    pub fn filename(&self) -> Option<String> {
+        self.content_disposition()?.filename()
+    }
+
+    /// The part's `Content-Type`, which defaults to `text/plain`.
+    pub fn content_type(&self) -> Option<MediaType> {
+        match self.headers.get("content-type") {
+            Some(value) => parse_content_type(value).ok(),
+            None => parse_content_type("text/plain").ok(),
+        }
+    }
+}
+
+/// Iterates over the parts of a multipart body one at a time. Iteration
+/// stops after the first error.
+#[derive(Debug, Clone)]
+pub struct Multipart<'a> {
+    input: &'a [u8],
+    delimiter: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl<'a> Multipart<'a> {
+    pub fn new(input: &'a [u8], boundary: &str) -> Self {
+        Self {
+            input,
+            delimiter: [b"--", boundary.as_bytes()].concat(),
+            pos: 0,
+            done: false,
+        }
+    }
+
+    /// Reads the boundary from a `multipart/*` Content-Type value.
+    pub fn with_content_type(input: &'a [u8], content_type: &str) -> Result<Self, ParseError> {
+        let media = parse_content_type(content_type)?;
+        if media.ty != "multipart" {
+            return Err(ParseError::new(
+                0,
+                format!("`{}` is not a multipart type", media.essence()),
+            ));
+        }
+        let boundary = media
+            .param("boundary")
+            .filter(|b| (1..=70).contains(&b.len()))
+            .ok_or_else(|| ParseError::new(0, "missing or invalid boundary parameter"))?;
+        Ok(Self::new(input, boundary))
+    }
+
+    fn next_part(&mut self) -> Result<Option<Part<'a>>, ParseError> {
+        if self.pos == 0 {
+            // Anything before the first delimiter is a preamble; the
+            // delimiter may open the body or follow a line break.
+            let first = if self.input.starts_with(&self.delimiter) {
+                0
+            } else {
+                find(
+                    self.input,
+                    0,
+                    &[b"\r\n", self.delimiter.as_slice()].concat(),
+                )
+                .map(|at| at + 2)
+                .ok_or_else(|| ParseError::new(0, "missing opening boundary"))?
+            };
+            self.pos = first + self.delimiter.len();
+        }
+        let rest = &self.input[self.pos..];
+        if rest.starts_with(b"--") {
+            // Close delimiter; the epilogue is ignored.
+            return Ok(None);
+        }
+        let padding = rest
+            .iter()
+            .take_while(|b| matches!(b, b' ' | b'\t'))
+            .count();
+        let rest = &rest[padding..];
+        let line_end = if rest.starts_with(b"\r\n") {
+            2
+        } else if rest.starts_with(b"\n") {
+            1
+        } else {
+            return Err(ParseError::new(
+                self.pos + padding,
+                "expected a line break after the boundary",
+            ));
+        };
+        let headers_start = self.pos + padding + line_end;
+        let mut remaining = &self.input[headers_start..];
+        let headers = headers
+            .parse_next(&mut remaining)
+            .map_err(|_| ParseError::new(headers_start, "invalid part headers"))?;
+        let body_start = self.input.len() - remaining.len();
+        let close = [b"\r\n", self.delimiter.as_slice()].concat();
+        let body_end = find(self.input, body_start, &close)
+            .ok_or_else(|| ParseError::new(body_start, "part is not terminated by a boundary"))?;
+        self.pos = body_end + close.len();
+        Ok(Some(Part {
+            headers,
+            body: &self.input[body_start..body_end],
+        }))
+    }
+}
+
+impl<'a> Iterator for Multipart<'a> {
+    type Item = Result<Part<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let part = self.next_part().transpose();
+        self.done = !matches!(part, Some(Ok(_)));
+        part
+    }
+}
+
+/// Splits a whole body into its parts.
+pub fn parse_multipart<'a>(input: &'a [u8], boundary: &str) -> Result<Vec<Part<'a>>, ParseError> {
+    Multipart::new(input, boundary).collect()
+}
+
+fn find(haystack: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    haystack[from..]
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|at| from + at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &[u8] = b"preamble\r\n--XyZ\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nHello\r\nworld\r\n--XyZ \r\nContent-Disposition: form-data; name=\"upload\"; filename=\"a.bin\"\r\nContent-Type: application/octet-stream\r\n\r\n\x00\x01--XyZ\x02\r\n--XyZ--\r\nepilogue";
+
+    #[test]
+    fn parse_multipart_should_work() -> Result<(), ParseError> {
+        let parts = parse_multipart(BODY, "XyZ")?;
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].name().as_deref(), Some("title"));
+        assert_eq!(parts[0].body, b"Hello\r\nworld");
+        assert_eq!(parts[0].content_type().unwrap().essence(), "text/plain");
+        assert_eq!(parts[1].filename().as_deref(), Some("a.bin"));
+        assert_eq!(parts[1].body, b"\x00\x01--XyZ\x02");
+        assert_eq!(
+            parts[1].content_type().unwrap().essence(),
+            "application/octet-stream"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn multipart_should_stream_parts() -> Result<(), ParseError> {
+        let mut parts =
+            Multipart::with_content_type(BODY, "multipart/form-data; boundary=\"XyZ\"")?;
+        assert_eq!(parts.next().unwrap()?.body, b"Hello\r\nworld");
+        assert!(parts.next().unwrap().is_ok());
+        assert!(parts.next().is_none());
+        assert!(Multipart::with_content_type(BODY, "text/plain").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_multipart_should_report_errors() {
+        let err = parse_multipart(b"--b\r\nA: 1\r\n\r\nunterminated", "b").unwrap_err();
+        assert_eq!(err.offset(), 13);
+        assert!(parse_multipart(b"no boundary here", "b").is_err());
+        assert!(parse_multipart(b"--bjunk\r\n\r\n\r\n--b--", "b").is_err());
+    }
+}
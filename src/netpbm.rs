@@ -0,0 +1,266 @@
+use std::fmt;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{digit1, multispace0, multispace1};
+use winnow::combinator::{alt, cut_err, preceded, repeat, terminated};
+use winnow::error::{ContextError, ErrMode, FromExternalError, StrContext, StrContextValue};
+use winnow::token::one_of;
+
+use crate::ParseError;
+use crate::combinators::Trivia;
+
+/// The plain (ASCII) Netpbm formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// `P1`, one bit per pixel, 1 being black.
+    Bitmap,
+    /// `P2`
+    Graymap,
+    /// `P3`, red, green and blue per pixel.
+    Pixmap,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub format: Format,
+    pub width: usize,
+    pub height: usize,
+    /// The value of a full-intensity sample; always 1 for bitmaps.
+    pub max_value: u16,
+    /// Row-major, `channels()` samples per pixel.
+    pub samples: Vec<u16>,
+}
+
+impl Format {
+    pub fn channels(self) -> usize {
+        match self {
+            Format::Pixmap => 3,
+            Format::Bitmap | Format::Graymap => 1,
+        }
+    }
+
+    fn magic(self) -> &'static str {
+        match self {
+            Format::Bitmap => "P1",
+            Format::Graymap => "P2",
+            Format::Pixmap => "P3",
+        }
+    }
+}
+
+impl Image {
+    /// A black image.
+    pub fn new(format: Format, width: usize, height: usize, max_value: u16) -> Self {
+        let (max_value, black) = match format {
+            Format::Bitmap => (1, 1),
+            _ => (max_value, 0),
+        };
+        Image {
+            format,
+            width,
+            height,
+            max_value,
+            samples: vec![black; width * height * format.channels()],
+        }
+    }
+
+    /// The samples of the pixel at column `x`, row `y`.
+    pub fn pixel(&self, x: usize, y: usize) -> Option<&[u16]> {
+        let channels = self.format.channels();
+        (x < self.width && y < self.height).then(|| {
+            let at = (y * self.width + x) * channels;
+            &self.samples[at..at + channels]
+        })
+    }
+
+    pub fn pixel_mut(&mut self, x: usize, y: usize) -> Option<&mut [u16]> {
+        let channels = self.format.channels();
+        (x < self.width && y < self.height).then(|| {
+            let at = (y * self.width + x) * channels;
+            &mut self.samples[at..at + channels]
+        })
+    }
+}
+
+/// Writes the plain format, wrapping rows to stay within the 70 characters
+/// per line the format allows.
+impl fmt::Display for Image {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.format.magic())?;
+        writeln!(f, "{} {}", self.width, self.height)?;
+        if self.format != Format::Bitmap {
+            writeln!(f, "{}", self.max_value)?;
+        }
+        let row_len = self.width * self.format.channels();
+        for row in self.samples.chunks(row_len.max(1)) {
+            let mut line_len = 0;
+            for sample in row {
+                let text = sample.to_string();
+                if line_len > 0 && line_len + 1 + text.len() > 70 {
+                    f.write_str("\n")?;
+                    line_len = 0;
+                } else if line_len > 0 {
+                    f.write_str(" ")?;
+                    line_len += 1;
+                }
+                f.write_str(&text)?;
+                line_len += text.len();
+            }
+            f.write_str("\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a `P1`, `P2` or `P3` image. Bitmap samples may be run together,
+/// as in `0110`.
+pub fn parse_netpbm(input: &str) -> Result<Image, ParseError> {
+    terminated(image, (trivia, multispace0))
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+fn image(input: &mut &str) -> ModalResult<Image> {
+    let format = cut_err(alt((
+        "P1".value(Format::Bitmap),
+        "P2".value(Format::Graymap),
+        "P3".value(Format::Pixmap),
+    )))
+    .context(StrContext::Expected(StrContextValue::Description(
+        "P1, P2 or P3",
+    )))
+    .parse_next(input)?;
+    let width = preceded(separator, number("width")).parse_next(input)?;
+    let height = preceded(separator, number("height")).parse_next(input)?;
+    let max_value = if format == Format::Bitmap {
+        1
+    } else {
+        preceded(separator, number("maximum value"))
+            .verify(|&max| (1..=u16::MAX as usize).contains(&max))
+            .context(StrContext::Expected(StrContextValue::Description(
+                "a maximum value from 1 to 65535",
+            )))
+            .parse_next(input)? as u16
+    };
+
+    let Some(count) = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(format.channels()))
+    else {
+        return Err(ErrMode::Cut(ContextError::from_external_error(
+            input, TooLarge,
+        )));
+    };
+    let samples = if format == Format::Bitmap {
+        let bit = one_of(['0', '1']).map(|c| u16::from(c == '1'));
+        repeat(
+            count,
+            preceded(trivia, cut_err(bit).context(StrContext::Label("bit"))),
+        )
+        .parse_next(input)?
+    } else {
+        let sample = number("sample")
+            .verify(move |&s| s <= max_value as usize)
+            .context(StrContext::Expected(StrContextValue::Description(
+                "a sample no larger than the maximum value",
+            )))
+            .map(|s| s as u16);
+        repeat(count, preceded(separator, cut_err(sample))).parse_next(input)?
+    };
+    Ok(Image {
+        format,
+        width,
+        height,
+        max_value,
+        samples,
+    })
+}
+
+/// The sample count of an image does not fit in memory's address space.
+#[derive(Debug)]
+struct TooLarge;
+
+impl fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("image too large")
+    }
+}
+
+impl std::error::Error for TooLarge {}
+
+fn number<'i>(what: &'static str) -> impl Parser<&'i str, usize, ErrMode<ContextError>> {
+    cut_err(digit1.parse_to()).context(StrContext::Label(what))
+}
+
+/// Required white space, with any comments in it.
+fn separator(input: &mut &str) -> ModalResult<()> {
+    cut_err((multispace1, trivia))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "white space",
+        )))
+        .void()
+        .parse_next(input)
+}
+
+/// White space and `#` comments.
+fn trivia(input: &mut &str) -> ModalResult<()> {
+    let mut trivia = Trivia::HASH;
+    trivia.parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_netpbm_should_work() -> Result<(), ParseError> {
+        let bitmap = parse_netpbm("P1\n# a J\n3 2\n010\n1 1 0\n")?;
+        assert_eq!(bitmap.format, Format::Bitmap);
+        assert_eq!(bitmap.samples, [0, 1, 0, 1, 1, 0]);
+        assert_eq!(bitmap.pixel(1, 1), Some(&[1][..]));
+
+        let pixmap = parse_netpbm("P3 2 1 # comment\n255\n255 0 0  0 0 255\n")?;
+        assert_eq!(pixmap.max_value, 255);
+        assert_eq!(pixmap.pixel(1, 0), Some(&[0, 0, 255][..]));
+        assert_eq!(pixmap.pixel(2, 0), None);
+
+        let graymap = parse_netpbm("P2\n2 2\n65535\n0 1\n65535 7")?;
+        assert_eq!(graymap.samples, [0, 1, 65535, 7]);
+        Ok(())
+    }
+
+    #[test]
+    fn display_should_round_trip() -> Result<(), ParseError> {
+        let mut image = Image::new(Format::Graymap, 30, 2, 255);
+        image.pixel_mut(29, 1).unwrap()[0] = 128;
+        let text = image.to_string();
+        assert!(text.starts_with("P2\n30 2\n255\n"));
+        assert!(text.lines().all(|l| l.len() <= 70));
+        assert_eq!(parse_netpbm(&text)?, image);
+
+        let bitmap = Image::new(Format::Bitmap, 2, 1, 9);
+        assert_eq!(bitmap.to_string(), "P1\n2 1\n1 1\n");
+        Ok(())
+    }
+
+    #[test]
+    fn parse_netpbm_should_report_errors() {
+        let err = parse_netpbm("P2\n2 1\n9\n3 10\n").unwrap_err();
+        assert_eq!(err.offset(), 11);
+        let err = parse_netpbm("P3\n1 1\n255\n0 0\n").unwrap_err();
+        assert_eq!(err.offset(), 15);
+        assert!(parse_netpbm("P6\n1 1\n255\n").is_err());
+        assert!(parse_netpbm("P2\n1 1\n0\n0\n").is_err());
+        assert!(parse_netpbm("P1\n1 1\n0 1\n").is_err());
+    }
+
+    #[test]
+    fn parse_netpbm_should_reject_oversized_images() {
+        let err = parse_netpbm("P2 9999999999999999992 2 255 0 1 2 3").unwrap_err();
+        assert_eq!(err.message(), "image too large");
+        assert_eq!(err.offset(), 28);
+        let err = parse_netpbm("P3 6148914691236517206 1 255 0 0 0").unwrap_err();
+        assert_eq!(err.message(), "image too large");
+    }
+}
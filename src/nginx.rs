@@ -0,0 +1,525 @@
+pub mod conf;
+
+use std::net::{IpAddr, Ipv4Addr};
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use winnow::Result;
+use winnow::ascii::space0;
+use winnow::combinator::{alt, delimited};
+use winnow::error::{ContextError, StrContext, StrContextValue};
+use winnow::token::{take_till, take_until};
+use winnow::{Parser, ascii::digit1, combinator::separated};
+
+use crate::ParseError;
+use crate::combinators::Located;
+use crate::datetime;
+use crate::predicate::{Resolver, Value};
+use crate::uri::{Uri, parse_uri};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NginxLog {
+    pub addr: IpAddr,
+    pub datetime: DateTime<Utc>,
+    pub method: HttpMethod,
+    pub path: String,
+    pub http_version: HttpVersion,
+    pub status_code: u16,
+    pub size: u64,
+    pub referer: String,
+    pub user_agent: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpMethod {
+    Get,
+    Post,
+    Put,
+    Delete,
+    Head,
+    Options,
+    Connect,
+    Trace,
+    Patch,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum HttpVersion {
+    #[serde(rename = "HTTP/1.0")]
+    Http1_0,
+    #[serde(rename = "HTTP/1.1")]
+    Http1_1,
+    #[serde(rename = "HTTP/2.0")]
+    Http2_0,
+    #[serde(rename = "HTTP/3.0")]
+    Http3_0,
+}
+
+/// A [`NginxLog`] with the span of each `log_format` variable in the line.
+/// Quoted and bracketed fields are located with their delimiters; the
+/// referer and user agent are `None` for formats that do not log them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocatedNginxLog {
+    pub addr: Located<IpAddr>,
+    pub datetime: Located<DateTime<Utc>>,
+    pub request: Located<(HttpMethod, String, HttpVersion)>,
+    pub status_code: Located<u16>,
+    pub size: Located<u64>,
+    pub referer: Option<Located<String>>,
+    pub user_agent: Option<Located<String>>,
+}
+
+impl From<LocatedNginxLog> for NginxLog {
+    fn from(log: LocatedNginxLog) -> Self {
+        let (method, path, http_version) = log.request.node;
+        let dash = || "-".to_string();
+        NginxLog {
+            addr: log.addr.node,
+            datetime: log.datetime.node,
+            method,
+            path,
+            http_version,
+            status_code: log.status_code.node,
+            size: log.size.node,
+            referer: log.referer.map_or_else(dash, Located::into_inner),
+            user_agent: log.user_agent.map_or_else(dash, Located::into_inner),
+        }
+    }
+}
+
+impl NginxLog {
+    /// The field names predicates can use; see the [`Resolver`] impl.
+    /// `status` is accepted as a shorter name for `status_code`.
+    pub const FIELDS: [&str; 10] = [
+        "addr",
+        "datetime",
+        "method",
+        "path",
+        "http_version",
+        "status_code",
+        "status",
+        "size",
+        "referer",
+        "user_agent",
+    ];
+
+    /// The request target as a URI. `path` keeps the raw text because
+    /// clients send all kinds of malformed targets that nginx still logs.
+    pub fn uri(&self) -> Result<Uri, ParseError> {
+        parse_uri(&self.path)
+    }
+}
+
+/// Fields are named as in the serialized record; `datetime` is RFC 3339
+/// text, so it compares correctly against literals in the same form.
+impl Resolver for NginxLog {
+    fn resolve(&self, field: &str) -> Option<Value<'_>> {
+        Some(match field {
+            "addr" => Value::Ip(self.addr),
+            "datetime" => Value::String(self.datetime.to_rfc3339().into()),
+            "method" => Value::String(self.method.as_str().into()),
+            "path" => Value::String(self.path.as_str().into()),
+            "http_version" => Value::String(self.http_version.as_str().into()),
+            "status_code" | "status" => Value::Number(self.status_code.into()),
+            "size" => Value::Number(self.size as f64),
+            "referer" => Value::String(self.referer.as_str().into()),
+            "user_agent" => Value::String(self.user_agent.as_str().into()),
+            _ => return None,
+        })
+    }
+}
+
+impl HttpMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HttpMethod::Get => "GET",
+            HttpMethod::Post => "POST",
+            HttpMethod::Put => "PUT",
+            HttpMethod::Delete => "DELETE",
+            HttpMethod::Head => "HEAD",
+            HttpMethod::Options => "OPTIONS",
+            HttpMethod::Connect => "CONNECT",
+            HttpMethod::Trace => "TRACE",
+            HttpMethod::Patch => "PATCH",
+        }
+    }
+}
+
+impl HttpVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HttpVersion::Http1_0 => "HTTP/1.0",
+            HttpVersion::Http1_1 => "HTTP/1.1",
+            HttpVersion::Http2_0 => "HTTP/2.0",
+            HttpVersion::Http3_0 => "HTTP/3.0",
+        }
+    }
+}
+
+/// Access log layouts understood by the parser, named after nginx's
+/// predefined `log_format`s.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// `$remote_addr - $remote_user [$time_local] "$request" $status $body_bytes_sent "$http_referer" "$http_user_agent"`
+    #[default]
+    Combined,
+    /// `combined` without the trailing referer and user agent; both are
+    /// reported as `-`.
+    Common,
+}
+
+impl LogFormat {
+    /// The `log_format` string nginx defines this layout with.
+    pub fn template(&self) -> &'static str {
+        match self {
+            LogFormat::Combined => {
+                r#"$remote_addr - $remote_user [$time_local] "$request" $status $body_bytes_sent "$http_referer" "$http_user_agent""#
+            }
+            LogFormat::Common => {
+                r#"$remote_addr - $remote_user [$time_local] "$request" $status $body_bytes_sent"#
+            }
+        }
+    }
+
+    /// The layout a `log_format` string describes, if the parser knows it.
+    /// Runs of white space compare equal.
+    pub fn from_template(template: &str) -> Option<LogFormat> {
+        let normalize = |s: &str| s.split_whitespace().collect::<Vec<_>>().join(" ");
+        let template = normalize(template);
+        [LogFormat::Combined, LogFormat::Common]
+            .into_iter()
+            .find(|format| normalize(format.template()) == template)
+    }
+}
+
+//93.180.71.3 - - [17/May/2015:08:05:32 +0000] "GET /downloads/product_1 HTTP/1.1" 304 0 "-" "Debian APT-HTTP/1.3 (0.8.16~exp12ubuntu10.21)"
+/// Parses a single access log line in the default `combined` format.
+pub fn parse_nginx_log(input: &str) -> Result<NginxLog, ParseError> {
+    parse_nginx_log_with(input, LogFormat::Combined)
+}
+
+pub fn parse_nginx_log_with(input: &str, format: LogFormat) -> Result<NginxLog, ParseError> {
+    parse_nginx_log_located(input, format).map(NginxLog::from)
+}
+
+/// Like [`parse_nginx_log_with`], but keeps where each field was.
+pub fn parse_nginx_log_located<'i>(
+    input: &'i str,
+    format: LogFormat,
+) -> Result<LocatedNginxLog, ParseError> {
+    (|rest: &mut &'i str| nginx_log(rest, input, format))
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+fn nginx_log<'i>(input: &mut &'i str, line: &'i str, format: LogFormat) -> Result<LocatedNginxLog> {
+    let addr = field(line, parse_ip).parse_next(input)?;
+    parse_ignore(input)?;
+    let datetime = field(line, parse_datetime).parse_next(input)?;
+    let request = field(line, parse_http).parse_next(input)?;
+    let status_code = field(line, parse_status).parse_next(input)?;
+    let size = field(line, parse_body_bytes).parse_next(input)?;
+    let (referer, user_agent) = match format {
+        LogFormat::Combined => (
+            Some(
+                field(line, |s: &mut &'i str| parse_quoted_string(s, "referer"))
+                    .parse_next(input)?,
+            ),
+            Some(
+                field(line, |s: &mut &'i str| parse_quoted_string(s, "user agent"))
+                    .parse_next(input)?,
+            ),
+        ),
+        LogFormat::Common => (None, None),
+    };
+    Ok(LocatedNginxLog {
+        addr,
+        datetime,
+        request,
+        status_code,
+        size,
+        referer,
+        user_agent,
+    })
+}
+
+/// Locates the output of `parser` within `line`. The field parsers eat the
+/// spaces after themselves; those are left out of the span.
+fn field<'i, O>(
+    line: &'i str,
+    mut parser: impl Parser<&'i str, O, ContextError>,
+) -> impl Parser<&'i str, Located<O>, ContextError> {
+    move |input: &mut &'i str| {
+        let before = *input;
+        let node = parser.parse_next(input)?;
+        let start = line.len() - before.len();
+        let text = &before[..before.len() - input.len()];
+        Ok(Located::new(node, start..start + text.trim_end().len()))
+    }
+}
+
+fn parse_ip(input: &mut &str) -> Result<IpAddr> {
+    let digits: Vec<u8> = separated(4, digit1.parse_to::<u8>(), ".")
+        .context(StrContext::Label("remote address"))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "IPv4 address",
+        )))
+        .parse_next(input)?;
+    space0(input)?;
+    Ok(IpAddr::V4(Ipv4Addr::new(
+        digits[0], digits[1], digits[2], digits[3],
+    )))
+}
+
+fn parse_ignore(input: &mut &str) -> Result<()> {
+    "- - "
+        .context(StrContext::Label("remote user"))
+        .context(StrContext::Expected(StrContextValue::StringLiteral("- - ")))
+        .parse_next(input)?;
+    Ok(())
+}
+
+fn parse_datetime(input: &mut &str) -> Result<DateTime<Utc>> {
+    let datetime = delimited(
+        '['.context(StrContext::Expected(StrContextValue::CharLiteral('['))),
+        take_till(0.., ']')
+            .try_map(datetime::parse_datetime)
+            .context(StrContext::Expected(StrContextValue::Description(
+                "date and time",
+            ))),
+        ']'.context(StrContext::Expected(StrContextValue::CharLiteral(']'))),
+    )
+    .context(StrContext::Label("local time"))
+    .parse_next(input)?;
+    space0(input)?;
+    Ok(datetime.with_timezone(&Utc))
+}
+
+fn parse_http(input: &mut &str) -> Result<(HttpMethod, String, HttpVersion)> {
+    let parse = (parse_http_method, parse_url, parse_http_version);
+    let (method, url, version) = delimited(
+        '"'.context(StrContext::Expected(StrContextValue::CharLiteral('"'))),
+        parse,
+        '"'.context(StrContext::Expected(StrContextValue::CharLiteral('"'))),
+    )
+    .context(StrContext::Label("HTTP request line"))
+    .parse_next(input)?;
+    space0(input)?;
+    Ok((method, url, version))
+}
+
+fn parse_http_method(input: &mut &str) -> Result<HttpMethod> {
+    let method = alt((
+        "GET", "POST", "PUT", "DELETE", "HEAD", "OPTIONS", "CONNECT", "TRACE", "PATCH",
+    ))
+    .parse_to()
+    .context(StrContext::Expected(StrContextValue::Description(
+        "HTTP method",
+    )))
+    .parse_next(input)?;
+    space0(input)?;
+    Ok(method)
+}
+
+fn parse_url(input: &mut &str) -> Result<String> {
+    let url = take_till(1.., ' ')
+        .context(StrContext::Expected(StrContextValue::Description(
+            "request target",
+        )))
+        .parse_next(input)?;
+    space0(input)?;
+    Ok(url.to_string())
+}
+
+fn parse_http_version(input: &mut &str) -> Result<HttpVersion> {
+    let version = alt(("HTTP/1.0", "HTTP/1.1", "HTTP/2.0", "HTTP/3.0"))
+        .parse_to()
+        .context(StrContext::Expected(StrContextValue::Description(
+            "HTTP version",
+        )))
+        .parse_next(input)?;
+    space0(input)?;
+    Ok(version)
+}
+
+fn parse_status(s: &mut &str) -> Result<u16> {
+    let ret = digit1
+        .parse_to()
+        .context(StrContext::Label("status code"))
+        .context(StrContext::Expected(StrContextValue::Description("number")))
+        .parse_next(s)?;
+    space0(s)?;
+    Ok(ret)
+}
+
+fn parse_body_bytes(s: &mut &str) -> Result<u64> {
+    let ret = digit1
+        .parse_to()
+        .context(StrContext::Label("body size"))
+        .context(StrContext::Expected(StrContextValue::Description("number")))
+        .parse_next(s)?;
+    space0(s)?;
+    Ok(ret)
+}
+
+fn parse_quoted_string(s: &mut &str, label: &'static str) -> Result<String> {
+    let ret = delimited(
+        '"'.context(StrContext::Expected(StrContextValue::CharLiteral('"'))),
+        take_until(1.., '"'),
+        '"'.context(StrContext::Expected(StrContextValue::CharLiteral('"'))),
+    )
+    .context(StrContext::Label(label))
+    .parse_next(s)?;
+    space0(s)?;
+    Ok(ret.to_string())
+}
+
+impl FromStr for HttpMethod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "GET" => Ok(HttpMethod::Get),
+            "POST" => Ok(HttpMethod::Post),
+            "PUT" => Ok(HttpMethod::Put),
+            "DELETE" => Ok(HttpMethod::Delete),
+            "HEAD" => Ok(HttpMethod::Head),
+            "OPTIONS" => Ok(HttpMethod::Options),
+            "CONNECT" => Ok(HttpMethod::Connect),
+            "TRACE" => Ok(HttpMethod::Trace),
+            "PATCH" => Ok(HttpMethod::Patch),
+            _ => Err(anyhow::anyhow!("Invalid http method")),
+        }
+    }
+}
+
+impl FromStr for HttpVersion {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "HTTP/1.0" => Ok(HttpVersion::Http1_0),
+            "HTTP/1.1" => Ok(HttpVersion::Http1_1),
+            "HTTP/2.0" => Ok(HttpVersion::Http2_0),
+            "HTTP/3.0" => Ok(HttpVersion::Http3_0),
+            _ => Err(anyhow::anyhow!("Invalid http version")),
+        }
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "combined" => Ok(LogFormat::Combined),
+            "common" => Ok(LogFormat::Common),
+            _ => Err(anyhow::anyhow!("Invalid log format")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn parse_ip_should_work() -> Result<()> {
+        let mut s = "1.1.1.1";
+        let ip = parse_ip(&mut s).unwrap();
+        assert_eq!(s, "");
+        assert_eq!(ip, IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_datetime_should_work() -> Result<()> {
+        let mut s = "[17/May/2015:08:05:32 +0000]";
+        let dt = parse_datetime(&mut s).unwrap();
+        assert_eq!(s, "");
+        assert_eq!(dt, Utc.with_ymd_and_hms(2015, 5, 17, 8, 5, 32).unwrap());
+
+        let mut s = "[31/Feb/2015:08:05:32 +0000]";
+        assert!(parse_datetime(&mut s).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_http_should_work() -> Result<()> {
+        let mut s = "\"GET /downloads/product_1 HTTP/1.1\"";
+        let (method, url, protocol) = parse_http(&mut s).unwrap();
+        assert_eq!(s, "");
+        assert_eq!(method, HttpMethod::Get);
+        assert_eq!(url, "/downloads/product_1");
+        assert_eq!(protocol, HttpVersion::Http1_1);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_nginx_log_should_work() -> anyhow::Result<()> {
+        let s = r#"93.180.71.3 - - [17/May/2015:08:05:32 +0000] "GET /downloads/product_1 HTTP/1.1" 304 0 "-" "Debian APT-HTTP/1.3 (0.8.16~exp12ubuntu10.21)""#;
+        let log = parse_nginx_log(s)?;
+        assert_eq!(log.addr, IpAddr::V4(Ipv4Addr::new(93, 180, 71, 3)));
+        assert_eq!(log.status_code, 304);
+        assert_eq!(
+            log.user_agent,
+            "Debian APT-HTTP/1.3 (0.8.16~exp12ubuntu10.21)"
+        );
+
+        let err = parse_nginx_log("93.180.71.3 - - [").unwrap_err();
+        assert_eq!(err.offset(), 17);
+        assert!(
+            err.message()
+                .starts_with("expected date and time while parsing local time")
+        );
+
+        let err = parse_nginx_log(&s.replace("GET", "FETCH")).unwrap_err();
+        assert_eq!(err.offset(), 46);
+        assert_eq!(
+            err.message(),
+            "expected HTTP method while parsing HTTP request line"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_common_log_should_work() -> anyhow::Result<()> {
+        let s = r#"127.0.0.1 - - [17/May/2015:08:05:32 +0000] "POST /login HTTP/1.0" 200 512"#;
+        let log = parse_nginx_log_with(s, LogFormat::Common)?;
+        assert_eq!(log.method, HttpMethod::Post);
+        assert_eq!(log.uri()?.path_segments().collect::<Vec<_>>(), ["login"]);
+        assert_eq!(log.size, 512);
+        assert_eq!(log.referer, "-");
+        let filter = crate::predicate::parse_predicate(
+            "method == 'POST' && status < 300 && addr in [127.0.0.0/8]",
+        )?;
+        assert!(filter.eval(&log));
+        assert!(parse_nginx_log(s).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_nginx_log_located_should_work() -> anyhow::Result<()> {
+        let s =
+            r#"10.0.0.1 - - [17/May/2015:08:05:32 +0000] "GET / HTTP/1.1" 200 5 "-" "curl/8.0""#;
+        let log = parse_nginx_log_located(s, LogFormat::Combined)?;
+        assert_eq!(&s[log.addr.span.clone()], "10.0.0.1");
+        assert_eq!(&s[log.request.span.clone()], r#""GET / HTTP/1.1""#);
+        assert_eq!(log.status_code.span, 59..62);
+        assert_eq!(
+            &s[log.user_agent.as_ref().unwrap().span.clone()],
+            r#""curl/8.0""#
+        );
+        assert_eq!(NginxLog::from(log), parse_nginx_log(s)?);
+
+        let log = parse_nginx_log_located(&s[..64], LogFormat::Common)?;
+        assert_eq!(log.size.span, 63..64);
+        assert!(log.referer.is_none());
+        Ok(())
+    }
+}
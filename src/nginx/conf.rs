@@ -0,0 +1,378 @@
+use std::fmt;
+use std::str::FromStr;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::combinator::{alt, cut_err, delimited, preceded, repeat, terminated};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::{any, none_of, take_till};
+
+use super::LogFormat;
+use crate::ParseError;
+use crate::combinators::Trivia;
+
+/// How deep `include`s may nest before [`Config::expand_includes`] assumes
+/// a cycle.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// An nginx configuration file: the directives of the main context.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    pub directives: Vec<Directive>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Directive {
+    pub name: String,
+    /// Arguments with quotes and escapes removed.
+    pub args: Vec<String>,
+    /// The `{ ... }` context, for block directives like `http` and `server`.
+    pub block: Option<Vec<Directive>>,
+}
+
+impl Directive {
+    /// Directives named `name` directly inside this one's block.
+    pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Directive> {
+        self.block.iter().flatten().filter(move |d| d.name == name)
+    }
+}
+
+impl Config {
+    /// Every directive named `name`, at any depth, in file order.
+    pub fn find<'a>(&'a self, name: &str) -> Vec<&'a Directive> {
+        fn walk<'a>(directives: &'a [Directive], name: &str, found: &mut Vec<&'a Directive>) {
+            for directive in directives {
+                if directive.name == name {
+                    found.push(directive);
+                }
+                if let Some(block) = &directive.block {
+                    walk(block, name, found);
+                }
+            }
+        }
+        let mut found = Vec::new();
+        walk(&self.directives, name, &mut found);
+        found
+    }
+
+    /// `log_format` definitions by name. nginx joins the format's strings,
+    /// so the result is one template; `combined` is predefined.
+    pub fn log_formats(&self) -> Vec<(&str, String)> {
+        let mut formats = vec![("combined", LogFormat::Combined.template().to_string())];
+        for directive in self.find("log_format") {
+            let Some((name, rest)) = directive.args.split_first() else {
+                continue;
+            };
+            let strings = rest.iter().skip_while(|arg| arg.starts_with("escape="));
+            formats.push((name, strings.map(String::as_str).collect()));
+        }
+        formats
+    }
+
+    /// Each `access_log` path with the layout it is written in, when the
+    /// access-log parser understands that layout. `off` is left out.
+    pub fn access_logs(&self) -> Vec<(&str, Option<LogFormat>)> {
+        let formats = self.log_formats();
+        self.find("access_log")
+            .into_iter()
+            .filter_map(|directive| {
+                let path = directive.args.first().filter(|path| *path != "off")?;
+                let name = directive.args.get(1).map_or("combined", String::as_str);
+                let format = formats
+                    .iter()
+                    .rev()
+                    .find(|(n, _)| *n == name)
+                    .and_then(|(_, template)| LogFormat::from_template(template));
+                Some((path.as_str(), format))
+            })
+            .collect()
+    }
+
+    /// Replaces each `include` with the directives of the files it names.
+    /// `load` gets the include's argument, a path or a glob, and returns the
+    /// contents of the matching files.
+    pub fn expand_includes(
+        &mut self,
+        load: &mut dyn FnMut(&str) -> Vec<String>,
+    ) -> Result<(), ParseError> {
+        expand(&mut self.directives, load, 0)
+    }
+}
+
+fn expand(
+    directives: &mut Vec<Directive>,
+    load: &mut dyn FnMut(&str) -> Vec<String>,
+    depth: usize,
+) -> Result<(), ParseError> {
+    let mut expanded = Vec::with_capacity(directives.len());
+    for mut directive in directives.drain(..) {
+        if directive.name != "include" {
+            if let Some(block) = &mut directive.block {
+                expand(block, load, depth)?;
+            }
+            expanded.push(directive);
+            continue;
+        }
+        if depth == MAX_INCLUDE_DEPTH {
+            return Err(ParseError::new(0, "includes nest too deeply"));
+        }
+        for source in directive
+            .args
+            .first()
+            .map(|path| load(path))
+            .unwrap_or_default()
+        {
+            let mut included = parse_nginx_conf(&source)?.directives;
+            expand(&mut included, load, depth + 1)?;
+            expanded.extend(included);
+        }
+    }
+    *directives = expanded;
+    Ok(())
+}
+
+impl FromStr for Config {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_nginx_conf(s)
+    }
+}
+
+/// Four spaces per level, one directive per line.
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_directives(f, &self.directives, 0)
+    }
+}
+
+fn write_directives(
+    f: &mut fmt::Formatter<'_>,
+    directives: &[Directive],
+    depth: usize,
+) -> fmt::Result {
+    let indent = "    ".repeat(depth);
+    for directive in directives {
+        write!(f, "{indent}{}", quote(&directive.name))?;
+        for arg in &directive.args {
+            write!(f, " {}", quote(arg))?;
+        }
+        match &directive.block {
+            Some(block) => {
+                writeln!(f, " {{")?;
+                write_directives(f, block, depth + 1)?;
+                writeln!(f, "{indent}}}")?;
+            }
+            None => writeln!(f, ";")?,
+        }
+    }
+    Ok(())
+}
+
+/// Writes a name or argument so it reads back as the same text, quoting
+/// it when it has anything a bare word would take as syntax.
+fn quote(arg: &str) -> String {
+    let plain =
+        !arg.is_empty() && !arg.contains(|c: char| c.is_whitespace() || "\"';{}#\\".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+/// Parses nginx configuration syntax. `include`s stay as directives; see
+/// [`Config::expand_includes`].
+pub fn parse_nginx_conf(input: &str) -> Result<Config, ParseError> {
+    terminated(directives, trivia)
+        .map(|directives| Config { directives })
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+/// White space and `#` comments.
+fn trivia(input: &mut &str) -> ModalResult<()> {
+    let mut trivia = Trivia::HASH;
+    trivia.parse_next(input)
+}
+
+fn directives(input: &mut &str) -> ModalResult<Vec<Directive>> {
+    repeat(0.., preceded(trivia, directive)).parse_next(input)
+}
+
+/// nginx reads names like any other token, so they may be quoted too.
+fn directive(input: &mut &str) -> ModalResult<Directive> {
+    let name = arg.parse_next(input)?;
+    let args = repeat(0.., preceded(trivia, arg)).parse_next(input)?;
+    trivia.parse_next(input)?;
+    let block = cut_err(alt((
+        ';'.map(|_| None),
+        delimited('{', directives, (trivia, cut_err('}'))).map(Some),
+    )))
+    .context(StrContext::Label("directive"))
+    .context(StrContext::Expected(StrContextValue::Description(
+        "`;` or a block",
+    )))
+    .parse_next(input)?;
+    Ok(Directive { name, args, block })
+}
+
+fn arg(input: &mut &str) -> ModalResult<String> {
+    alt((quoted('"'), quoted('\''), word)).parse_next(input)
+}
+
+/// An unquoted argument. `${var}` may contain the braces that otherwise end
+/// a word.
+fn word(input: &mut &str) -> ModalResult<String> {
+    repeat(
+        1..,
+        alt((
+            ("${", take_till(0.., '}'), cut_err('}'))
+                .take()
+                .map(String::from),
+            preceded('\\', any).map(unescape),
+            none_of(|c: char| c.is_whitespace() || "\"';{}\\".contains(c)).map(String::from),
+        )),
+    )
+    .fold(String::new, |mut word, part| {
+        word.push_str(&part);
+        word
+    })
+    .parse_next(input)
+}
+
+fn quoted(quote: char) -> impl FnMut(&mut &str) -> ModalResult<String> {
+    move |input: &mut &str| {
+        delimited(
+            quote,
+            repeat(
+                0..,
+                alt((
+                    preceded('\\', any).map(unescape),
+                    none_of([quote, '\\']).map(String::from),
+                )),
+            )
+            .fold(String::new, |mut s, part| {
+                s.push_str(&part);
+                s
+            }),
+            cut_err(quote).context(StrContext::Expected(StrContextValue::CharLiteral(quote))),
+        )
+        .parse_next(input)
+    }
+}
+
+/// nginx resolves a few escapes and keeps the backslash before anything
+/// else, which regular expressions rely on.
+fn unescape(c: char) -> String {
+    match c {
+        'n' => "\n".to_string(),
+        'r' => "\r".to_string(),
+        't' => "\t".to_string(),
+        '"' | '\'' | '\\' | ' ' | ';' | '{' | '}' | '$' => c.to_string(),
+        c => format!("\\{c}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONF: &str = r#"
+user www-data;  # run as
+http {
+    log_format  main  '$remote_addr - $remote_user [$time_local] "$request" '
+                      '$status $body_bytes_sent';
+    log_format json escape=json '{"ip":"$remote_addr"}';
+    access_log /var/log/nginx/access.log;
+    include mime.types;
+
+    server {
+        listen 80;
+        server_name example.com "www.example.com";
+        access_log /var/log/nginx/main.log main;
+        access_log /var/log/nginx/json.log json;
+        location ~ \.php$ {
+            fastcgi_param SCRIPT "${document_root}$fastcgi_script_name";
+        }
+        access_log off;
+    }
+}
+"#;
+
+    #[test]
+    fn parse_nginx_conf_should_build_a_tree() -> Result<(), ParseError> {
+        let config = parse_nginx_conf(CONF)?;
+        assert_eq!(config.directives.len(), 2);
+        assert_eq!(config.directives[0].args, ["www-data"]);
+        let http = &config.directives[1];
+        let server = http.children("server").next().unwrap();
+        let names: Vec<_> = server.children("server_name").collect();
+        assert_eq!(names[0].args, ["example.com", "www.example.com"]);
+        let location = server.children("location").next().unwrap();
+        assert_eq!(location.args, ["~", r"\.php$"]);
+        assert_eq!(
+            config.find("fastcgi_param")[0].args,
+            ["SCRIPT", "${document_root}$fastcgi_script_name"]
+        );
+        assert_eq!(parse_nginx_conf(&config.to_string())?, config);
+        Ok(())
+    }
+
+    #[test]
+    fn access_logs_should_resolve_formats() -> Result<(), ParseError> {
+        let mut config = parse_nginx_conf(CONF)?;
+        let logs = config.access_logs();
+        assert_eq!(
+            logs,
+            [
+                ("/var/log/nginx/access.log", Some(LogFormat::Combined)),
+                ("/var/log/nginx/main.log", Some(LogFormat::Common)),
+                ("/var/log/nginx/json.log", None),
+            ]
+        );
+
+        config.expand_includes(&mut |path| match path {
+            "mime.types" => vec!["types { text/html html; }".to_string()],
+            _ => Vec::new(),
+        })?;
+        assert!(config.find("include").is_empty());
+        assert_eq!(config.find("text/html")[0].args, ["html"]);
+
+        let mut looping = parse_nginx_conf("include self.conf;")?;
+        assert!(
+            looping
+                .expand_includes(&mut |_| vec!["include self.conf;".to_string()])
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn display_should_round_trip_escapes() -> Result<(), ParseError> {
+        let config = parse_nginx_conf(
+            r#"cd\';
+root "a\"b" c\ d 'it\'s' \{x\};
+location ~ \.php$ { }
+"a b";"#,
+        )?;
+        assert_eq!(config.directives[0].name, "cd'");
+        assert_eq!(config.directives[1].args, ["a\"b", "c d", "it's", "{x}"]);
+        assert_eq!(config.directives[3].name, "a b");
+        let text = config.to_string();
+        assert!(text.starts_with("\"cd'\";\n"));
+        assert_eq!(parse_nginx_conf(&text)?, config);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_nginx_conf_should_report_errors() {
+        assert_eq!(
+            parse_nginx_conf("worker_processes 4").unwrap_err().offset(),
+            18
+        );
+        assert!(parse_nginx_conf("http { server { }").is_err());
+        assert!(parse_nginx_conf("root '/var/www;").is_err());
+        assert!(parse_nginx_conf("}").is_err());
+    }
+}
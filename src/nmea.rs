@@ -0,0 +1,444 @@
+use chrono::{NaiveDate, NaiveTime};
+
+use crate::ParseError;
+
+/// One `$`- or `!`-prefixed sentence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sentence {
+    /// `GP`, `GN`, ...; `P` for proprietary sentences.
+    pub talker: String,
+    /// `GGA`, `RMC`, ...; for proprietary sentences, the rest of the
+    /// address.
+    pub kind: String,
+    /// The raw comma-separated fields after the address.
+    pub fields: Vec<String>,
+    pub checksum: Option<u8>,
+    pub data: Data,
+}
+
+/// The decoded fields of the sentence types this module knows. Empty
+/// fields are `None`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Data {
+    Gga(Gga),
+    Rmc(Rmc),
+    Gsv(Gsv),
+    Gll(Gll),
+    /// Anything else; see [`Sentence::fields`].
+    Unknown,
+}
+
+/// Fix data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gga {
+    pub time: Option<NaiveTime>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// 0 for no fix, 1 for GPS, 2 for DGPS, ...
+    pub quality: u8,
+    pub satellites: Option<u8>,
+    pub hdop: Option<f64>,
+    /// Metres above mean sea level.
+    pub altitude: Option<f64>,
+    /// Metres from the WGS84 ellipsoid to mean sea level.
+    pub geoid_separation: Option<f64>,
+}
+
+/// Recommended minimum data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rmc {
+    pub time: Option<NaiveTime>,
+    /// `A`; `V` means the receiver warns the data is void.
+    pub valid: bool,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub speed_knots: Option<f64>,
+    /// Degrees true.
+    pub course: Option<f64>,
+    pub date: Option<NaiveDate>,
+    /// Degrees, negative to the west.
+    pub magnetic_variation: Option<f64>,
+}
+
+/// One page of satellites in view.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gsv {
+    pub total_messages: u8,
+    pub message_number: u8,
+    pub satellites_in_view: u8,
+    pub satellites: Vec<Satellite>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Satellite {
+    pub prn: u16,
+    pub elevation: Option<u8>,
+    pub azimuth: Option<u16>,
+    /// dB-Hz; `None` when the satellite is not tracked.
+    pub snr: Option<u8>,
+}
+
+/// Geographic position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gll {
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub time: Option<NaiveTime>,
+    pub valid: bool,
+}
+
+/// Parses one sentence, with or without its line ending. A `*hh` checksum
+/// is optional, but must match when present.
+pub fn parse_sentence(line: &str) -> Result<Sentence, ParseError> {
+    let line = line.trim_end_matches(['\n', '\r']);
+    let body = line
+        .strip_prefix(['$', '!'])
+        .ok_or_else(|| ParseError::new(0, "expected `$` or `!`"))?;
+    let (body, checksum) = match body.rsplit_once('*') {
+        Some((body, hex)) => {
+            let at = 2 + body.len();
+            let expected = u8::from_str_radix(hex, 16)
+                .ok()
+                .filter(|_| hex.len() == 2)
+                .ok_or_else(|| ParseError::new(at, "expected a two-digit hex checksum"))?;
+            let actual = body.bytes().fold(0, |sum, b| sum ^ b);
+            if actual != expected {
+                return Err(ParseError::new(
+                    at,
+                    format!("checksum mismatch: expected {expected:02X}, computed {actual:02X}"),
+                ));
+            }
+            (body, Some(expected))
+        }
+        None => (body, None),
+    };
+
+    let mut parts = Fields::new(body);
+    let (_, address) = parts.next_raw();
+    if address.len() < 3 || !address.bytes().all(|b| b.is_ascii_alphanumeric()) {
+        return Err(ParseError::new(
+            1,
+            "expected a sentence address such as `GPGGA`",
+        ));
+    }
+    let (talker, kind) = match address.strip_prefix('P') {
+        Some(kind) => ("P", kind),
+        None => address.split_at(2),
+    };
+    let data = match kind {
+        "GGA" => Data::Gga(Gga {
+            time: parts.time()?,
+            latitude: parts.coordinate(2, 'N', 'S')?,
+            longitude: parts.coordinate(3, 'E', 'W')?,
+            quality: parts.number()?.unwrap_or(0),
+            satellites: parts.number()?,
+            hdop: parts.number()?,
+            altitude: parts.unit_number("M")?,
+            geoid_separation: parts.unit_number("M")?,
+        }),
+        "RMC" => Data::Rmc({
+            let time = parts.time()?;
+            let valid = parts.status()?;
+            Rmc {
+                time,
+                valid,
+                latitude: parts.coordinate(2, 'N', 'S')?,
+                longitude: parts.coordinate(3, 'E', 'W')?,
+                speed_knots: parts.number()?,
+                course: parts.number()?,
+                date: parts.date()?,
+                magnetic_variation: parts.signed(|d| d == "W")?,
+            }
+        }),
+        "GSV" => Data::Gsv({
+            let total_messages = parts.number()?.unwrap_or(0);
+            let message_number = parts.number()?.unwrap_or(0);
+            let satellites_in_view = parts.number()?.unwrap_or(0);
+            let mut satellites = Vec::new();
+            // Up to four satellites follow, then possibly a signal id.
+            while parts.remaining() >= 4 {
+                let Some(prn) = parts.number()? else {
+                    parts.skip(3);
+                    continue;
+                };
+                satellites.push(Satellite {
+                    prn,
+                    elevation: parts.number()?,
+                    azimuth: parts.number()?,
+                    snr: parts.number()?,
+                });
+            }
+            Gsv {
+                total_messages,
+                message_number,
+                satellites_in_view,
+                satellites,
+            }
+        }),
+        "GLL" => Data::Gll(Gll {
+            latitude: parts.coordinate(2, 'N', 'S')?,
+            longitude: parts.coordinate(3, 'E', 'W')?,
+            time: parts.time()?,
+            valid: parts.status()?,
+        }),
+        _ => Data::Unknown,
+    };
+    Ok(Sentence {
+        talker: talker.to_string(),
+        kind: kind.to_string(),
+        fields: parts
+            .all
+            .iter()
+            .skip(1)
+            .map(|(_, f)| f.to_string())
+            .collect(),
+        checksum,
+        data,
+    })
+}
+
+/// Parses a log of sentences, one per line. Blank lines are skipped; errors
+/// point into `input`.
+pub fn parse_nmea(input: &str) -> Result<Vec<Sentence>, ParseError> {
+    let mut sentences = Vec::new();
+    let mut offset = 0;
+    for line in input.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        if line.trim().is_empty() {
+            continue;
+        }
+        let sentence =
+            parse_sentence(line).map_err(|e| ParseError::new(start + e.offset(), e.message()))?;
+        sentences.push(sentence);
+    }
+    Ok(sentences)
+}
+
+/// The fields of a sentence body with their offsets in the line, which
+/// starts one byte before the body.
+struct Fields<'a> {
+    all: Vec<(usize, &'a str)>,
+    next: usize,
+}
+
+impl<'a> Fields<'a> {
+    fn new(body: &'a str) -> Self {
+        let mut all = Vec::new();
+        let mut at = 1;
+        for field in body.split(',') {
+            all.push((at, field));
+            at += field.len() + 1;
+        }
+        Fields { all, next: 0 }
+    }
+
+    fn remaining(&self) -> usize {
+        self.all.len().saturating_sub(self.next)
+    }
+
+    fn skip(&mut self, n: usize) {
+        self.next += n;
+    }
+
+    /// The next field, or an empty one past the end so that truncated
+    /// sentences read as missing values.
+    fn next_raw(&mut self) -> (usize, &'a str) {
+        let end = self.all.last().map_or(1, |(at, f)| at + f.len());
+        let field = self.all.get(self.next).copied().unwrap_or((end, ""));
+        self.next += 1;
+        field
+    }
+
+    fn number<T: std::str::FromStr>(&mut self) -> Result<Option<T>, ParseError> {
+        let (at, field) = self.next_raw();
+        if field.is_empty() {
+            return Ok(None);
+        }
+        field
+            .parse()
+            .map(Some)
+            .map_err(|_| ParseError::new(at, format!("invalid number {field:?}")))
+    }
+
+    /// A number followed by a unit field such as `M`.
+    fn unit_number(&mut self, unit: &str) -> Result<Option<f64>, ParseError> {
+        let value = self.number()?;
+        let (at, field) = self.next_raw();
+        if !field.is_empty() && field != unit {
+            return Err(ParseError::new(at, format!("expected unit `{unit}`")));
+        }
+        Ok(value)
+    }
+
+    /// A number followed by a direction letter, negated when `negative`
+    /// holds for it.
+    fn signed(&mut self, negative: impl Fn(&str) -> bool) -> Result<Option<f64>, ParseError> {
+        let value: Option<f64> = self.number()?;
+        let (_, direction) = self.next_raw();
+        Ok(value.map(|v| if negative(direction) { -v } else { v }))
+    }
+
+    /// `ddmm.mmmm` (or `dddmm.mmmm` for longitude) and a hemisphere,
+    /// converted to decimal degrees.
+    fn coordinate(
+        &mut self,
+        degree_digits: usize,
+        positive: char,
+        negative: char,
+    ) -> Result<Option<f64>, ParseError> {
+        let (at, field) = self.next_raw();
+        let (hemisphere_at, hemisphere) = self.next_raw();
+        if field.is_empty() {
+            return Ok(None);
+        }
+        let invalid = || ParseError::new(at, format!("invalid coordinate {field:?}"));
+        let degrees: f64 = field
+            .get(..degree_digits)
+            .and_then(|d| d.parse().ok())
+            .ok_or_else(invalid)?;
+        let minutes: f64 = field[degree_digits..].parse().map_err(|_| invalid())?;
+        if minutes >= 60.0 {
+            return Err(invalid());
+        }
+        let value = degrees + minutes / 60.0;
+        match hemisphere.chars().next() {
+            Some(c) if c == positive && hemisphere.len() == 1 => Ok(Some(value)),
+            Some(c) if c == negative && hemisphere.len() == 1 => Ok(Some(-value)),
+            _ => Err(ParseError::new(
+                hemisphere_at,
+                format!("expected `{positive}` or `{negative}`"),
+            )),
+        }
+    }
+
+    /// `hhmmss` with optional fractional seconds, in UTC.
+    fn time(&mut self) -> Result<Option<NaiveTime>, ParseError> {
+        let (at, field) = self.next_raw();
+        if field.is_empty() {
+            return Ok(None);
+        }
+        let format = if field.contains('.') {
+            "%H%M%S%.f"
+        } else {
+            "%H%M%S"
+        };
+        NaiveTime::parse_from_str(field, format)
+            .map(Some)
+            .map_err(|_| ParseError::new(at, format!("invalid time {field:?}")))
+    }
+
+    /// `ddmmyy`; two-digit years before 80 are taken as 20xx.
+    fn date(&mut self) -> Result<Option<NaiveDate>, ParseError> {
+        let (at, field) = self.next_raw();
+        if field.is_empty() {
+            return Ok(None);
+        }
+        let invalid = || ParseError::new(at, format!("invalid date {field:?}"));
+        if field.len() != 6 || !field.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let part = |i: usize| field[i..i + 2].parse::<u32>().unwrap();
+        let year = part(4) as i32;
+        let year = if year < 80 { 2000 + year } else { 1900 + year };
+        NaiveDate::from_ymd_opt(year, part(2), part(0))
+            .map(Some)
+            .ok_or_else(invalid)
+    }
+
+    /// `A` (valid) or `V` (void).
+    fn status(&mut self) -> Result<bool, ParseError> {
+        match self.next_raw() {
+            (_, "A") => Ok(true),
+            (_, "V" | "") => Ok(false),
+            (at, _) => Err(ParseError::new(at, "expected `A` or `V`")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sentence_should_work() -> Result<(), ParseError> {
+        let gga = parse_sentence(
+            "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47\r\n",
+        )?;
+        assert_eq!((gga.talker.as_str(), gga.kind.as_str()), ("GP", "GGA"));
+        assert_eq!(gga.checksum, Some(0x47));
+        let Data::Gga(fix) = &gga.data else {
+            panic!("expected GGA");
+        };
+        assert_eq!(fix.time, NaiveTime::from_hms_opt(12, 35, 19));
+        assert!((fix.latitude.unwrap() - 48.1173).abs() < 1e-9);
+        assert!((fix.longitude.unwrap() - 11.516_666_666).abs() < 1e-6);
+        assert_eq!(fix.satellites, Some(8));
+        assert_eq!(fix.altitude, Some(545.4));
+
+        let rmc = parse_sentence(
+            "$GPRMC,225446.50,A,4916.45,N,12311.12,W,000.5,054.7,191194,020.3,E*43",
+        )?;
+        let Data::Rmc(rmc) = &rmc.data else {
+            panic!("expected RMC");
+        };
+        assert!(rmc.valid);
+        assert!(rmc.longitude.unwrap() < -123.0);
+        assert_eq!(rmc.date, NaiveDate::from_ymd_opt(1994, 11, 19));
+        assert_eq!(rmc.magnetic_variation, Some(20.3));
+        assert_eq!(rmc.time, NaiveTime::from_hms_milli_opt(22, 54, 46, 500));
+
+        let unknown = parse_sentence("$PGRME,15.0,M,45.0,M,25.0,M")?;
+        assert_eq!(
+            (unknown.talker.as_str(), unknown.kind.as_str()),
+            ("P", "GRME")
+        );
+        assert_eq!(unknown.data, Data::Unknown);
+        assert_eq!(unknown.fields[0], "15.0");
+        Ok(())
+    }
+
+    #[test]
+    fn parse_nmea_should_read_logs() -> Result<(), ParseError> {
+        let sentences = parse_nmea(
+            "$GPGSV,2,1,08,01,40,083,46,02,17,308,,13,07,344,39,14,22,228,45*71\n\
+             \n\
+             $GNGLL,4916.45,N,12311.12,W,225444,A\n",
+        )?;
+        let Data::Gsv(gsv) = &sentences[0].data else {
+            panic!("expected GSV");
+        };
+        assert_eq!(gsv.satellites_in_view, 8);
+        assert_eq!(gsv.satellites.len(), 4);
+        assert_eq!(
+            gsv.satellites[1],
+            Satellite {
+                prn: 2,
+                elevation: Some(17),
+                azimuth: Some(308),
+                snr: None
+            }
+        );
+        let Data::Gll(gll) = &sentences[1].data else {
+            panic!("expected GLL");
+        };
+        assert!(gll.valid);
+        assert_eq!(sentences[1].checksum, None);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_sentence_should_report_errors() {
+        let err =
+            parse_sentence("$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*48")
+                .unwrap_err();
+        assert_eq!(err.offset(), 63);
+        assert!(err.message().contains("47"));
+        let err = parse_nmea("$GPGLL,4916.45,N,12311.12,W,225444,A\n$GPGLL,4916.45,X,,,,A\n")
+            .unwrap_err();
+        assert_eq!(err.offset(), 37 + 15);
+        assert!(parse_sentence("GPGGA,1").is_err());
+        assert!(parse_sentence("$GPRMC,256000,A").is_err());
+        assert!(parse_sentence("$GPRMC,,A,,,,,,,311399").is_err());
+    }
+}
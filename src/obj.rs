@@ -0,0 +1,371 @@
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{dec_int, digit1, float, space0, space1, till_line_ending};
+use winnow::combinator::{alt, cut_err, eof, opt, preceded, repeat, terminated};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::stream::{LocatingSlice, Location};
+use winnow::token::take_till;
+
+use crate::ParseError;
+
+type Input<'i> = LocatingSlice<&'i str>;
+
+/// Geometry read from an OBJ file. Face indices are resolved to 0-based
+/// positions in the vertex lists.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mesh {
+    /// `v x y z [w]`; `w` and trailing vertex colours are dropped.
+    pub positions: Vec<[f64; 3]>,
+    /// `vt u [v [w]]`, missing components being 0.
+    pub tex_coords: Vec<[f64; 3]>,
+    pub normals: Vec<[f64; 3]>,
+    pub faces: Vec<Face>,
+    /// `g` groups, each with the faces declared while it was current.
+    pub groups: Vec<Group>,
+    /// `o` objects, likewise.
+    pub objects: Vec<Group>,
+    /// Files named by `mtllib`.
+    pub material_libraries: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Face {
+    pub vertices: Vec<FaceVertex>,
+    /// The `usemtl` in effect.
+    pub material: Option<String>,
+    /// The `s` group in effect; 0 is `s off`.
+    pub smoothing_group: u32,
+}
+
+/// One corner of a face: `v`, `v/vt`, `v//vn` or `v/vt/vn`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FaceVertex {
+    pub position: usize,
+    pub tex_coord: Option<usize>,
+    pub normal: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    pub name: String,
+    /// Indices into [`Mesh::faces`].
+    pub faces: Vec<usize>,
+}
+
+impl Mesh {
+    /// The faces split into triangles, fanning out from each first corner.
+    pub fn triangles(&self) -> impl Iterator<Item = [FaceVertex; 3]> + '_ {
+        self.faces.iter().flat_map(|face| {
+            let v = &face.vertices;
+            (1..v.len() - 1).map(move |i| [v[0], v[i], v[i + 1]])
+        })
+    }
+}
+
+enum Record {
+    Position(Vec<f64>),
+    TexCoord(Vec<f64>),
+    Normal(Vec<f64>),
+    Face(Vec<RawVertex>),
+    Groups(Vec<String>),
+    Object(String),
+    MaterialLibraries(Vec<String>),
+    UseMaterial(String),
+    Smoothing(u32),
+    Other,
+}
+
+/// Indices as written, each with its offset in the line.
+type RawIndex = (i64, usize);
+
+struct RawVertex {
+    position: RawIndex,
+    tex_coord: Option<RawIndex>,
+    normal: Option<RawIndex>,
+}
+
+/// Parses an OBJ file. Vertex data must come before the faces using it, as
+/// negative indices count back from the vertices read so far. Statements
+/// other than geometry and grouping (curves, `l`, `p`, ...) are skipped.
+pub fn parse_obj(input: &str) -> Result<Mesh, ParseError> {
+    let mut mesh = Mesh::default();
+    let mut groups = vec!["default".to_string()];
+    let mut object: Option<String> = None;
+    let mut material = None;
+    let mut smoothing_group = 0;
+
+    let mut offset = 0;
+    let mut pending = String::new();
+    let mut pending_start = 0;
+    for raw in input.split_inclusive('\n') {
+        let start = offset;
+        offset += raw.len();
+        let line = raw.trim_end_matches(['\n', '\r']);
+        // A trailing backslash joins the next line.
+        if let Some(joined) = line.strip_suffix('\\') {
+            if pending.is_empty() {
+                pending_start = start;
+            }
+            // Blanking the backslash and line break keeps offsets exact.
+            pending.push_str(joined);
+            pending.push_str(&" ".repeat(raw.len() - joined.len()));
+            continue;
+        }
+        let (start, line) = if pending.is_empty() {
+            (start, line.to_string())
+        } else {
+            pending.push_str(line);
+            (pending_start, std::mem::take(&mut pending))
+        };
+        let record = record
+            .parse(LocatingSlice::new(line.as_str()))
+            .map_err(|e| {
+                let e = ParseError::from(e);
+                ParseError::new(start + e.offset(), e.message())
+            })?;
+        match record {
+            Record::Position(v) => mesh.positions.push([v[0], v[1], v[2]]),
+            Record::TexCoord(v) => mesh.tex_coords.push([
+                v[0],
+                v.get(1).copied().unwrap_or(0.0),
+                v.get(2).copied().unwrap_or(0.0),
+            ]),
+            Record::Normal(v) => mesh.normals.push([v[0], v[1], v[2]]),
+            Record::Face(raw) => {
+                let resolve = |(index, at): RawIndex, count: usize, what: &str| {
+                    let resolved = if index > 0 {
+                        usize::try_from(index - 1).ok()
+                    } else {
+                        count.checked_sub(index.unsigned_abs() as usize)
+                    };
+                    resolved
+                        .filter(|&i| index != 0 && i < count)
+                        .ok_or_else(|| {
+                            ParseError::new(
+                                start + at,
+                                format!("{what} index {index} out of range (have {count})"),
+                            )
+                        })
+                };
+                let vertices = raw
+                    .into_iter()
+                    .map(|v| {
+                        Ok(FaceVertex {
+                            position: resolve(v.position, mesh.positions.len(), "vertex")?,
+                            tex_coord: v
+                                .tex_coord
+                                .map(|i| resolve(i, mesh.tex_coords.len(), "texture"))
+                                .transpose()?,
+                            normal: v
+                                .normal
+                                .map(|i| resolve(i, mesh.normals.len(), "normal"))
+                                .transpose()?,
+                        })
+                    })
+                    .collect::<Result<_, ParseError>>()?;
+                let index = mesh.faces.len();
+                mesh.faces.push(Face {
+                    vertices,
+                    material: material.clone(),
+                    smoothing_group,
+                });
+                for name in &groups {
+                    add_face(&mut mesh.groups, name, index);
+                }
+                if let Some(name) = &object {
+                    add_face(&mut mesh.objects, name, index);
+                }
+            }
+            Record::Groups(names) => {
+                groups = names;
+                if groups.is_empty() {
+                    groups.push("default".to_string());
+                }
+            }
+            Record::Object(name) => object = Some(name),
+            Record::MaterialLibraries(files) => mesh.material_libraries.extend(files),
+            Record::UseMaterial(name) => material = Some(name),
+            Record::Smoothing(group) => smoothing_group = group,
+            Record::Other => {}
+        }
+    }
+    Ok(mesh)
+}
+
+fn add_face(groups: &mut Vec<Group>, name: &str, face: usize) {
+    match groups.iter_mut().find(|g| g.name == name) {
+        Some(group) => group.faces.push(face),
+        None => groups.push(Group {
+            name: name.to_string(),
+            faces: vec![face],
+        }),
+    }
+}
+
+fn record(input: &mut Input<'_>) -> ModalResult<Record> {
+    let keyword = preceded(space0, take_till(0.., [' ', '\t', '#'])).parse_next(input)?;
+    let record = match keyword {
+        "" => Record::Other,
+        "v" => Record::Position(numbers(input, 3, 7)?),
+        "vt" => Record::TexCoord(numbers(input, 1, 3)?),
+        "vn" => Record::Normal(numbers(input, 3, 3)?),
+        "f" => Record::Face(
+            repeat(3.., preceded(space1, face_vertex))
+                .context(StrContext::Label("face"))
+                .context(StrContext::Expected(StrContextValue::Description(
+                    "at least three vertices",
+                )))
+                .parse_next(input)?,
+        ),
+        "g" => Record::Groups(names.parse_next(input)?),
+        "o" => Record::Object(names.parse_next(input)?.join(" ")),
+        "mtllib" => Record::MaterialLibraries(names.parse_next(input)?),
+        "usemtl" => Record::UseMaterial(names.parse_next(input)?.join(" ")),
+        "s" => Record::Smoothing(
+            preceded(
+                space1,
+                cut_err(alt(("off".value(0), digit1.parse_to()))).context(StrContext::Expected(
+                    StrContextValue::Description("a smoothing group"),
+                )),
+            )
+            .parse_next(input)?,
+        ),
+        _ => {
+            till_line_ending.parse_next(input)?;
+            return Ok(Record::Other);
+        }
+    };
+    end.parse_next(input)?;
+    Ok(record)
+}
+
+/// Between `min` and `max` numbers.
+fn numbers(input: &mut Input<'_>, min: usize, max: usize) -> ModalResult<Vec<f64>> {
+    let values: Vec<f64> =
+        repeat(0..=max, preceded(space1, float::<_, f64, _>)).parse_next(input)?;
+    if values.len() < min {
+        return cut_err(winnow::combinator::fail)
+            .context(StrContext::Expected(StrContextValue::Description(
+                "a number",
+            )))
+            .parse_next(input);
+    }
+    Ok(values)
+}
+
+fn face_vertex(input: &mut Input<'_>) -> ModalResult<RawVertex> {
+    let position = index.parse_next(input)?;
+    let (tex_coord, normal) = opt(preceded('/', (opt(index), opt(preceded('/', index)))))
+        .parse_next(input)?
+        .unwrap_or_default();
+    Ok(RawVertex {
+        position,
+        tex_coord,
+        normal,
+    })
+}
+
+fn index(input: &mut Input<'_>) -> ModalResult<RawIndex> {
+    let at = input.current_token_start();
+    let index: i64 = dec_int.parse_next(input)?;
+    Ok((index, at))
+}
+
+/// Space-separated names running to the end of the line.
+fn names(input: &mut Input<'_>) -> ModalResult<Vec<String>> {
+    repeat(
+        0..,
+        preceded(space1, take_till(1.., [' ', '\t', '#']).map(str::to_string)),
+    )
+    .parse_next(input)
+}
+
+/// Trailing space and an optional comment.
+fn end(input: &mut Input<'_>) -> ModalResult<()> {
+    terminated(space0, opt(('#', till_line_ending)))
+        .void()
+        .parse_next(input)?;
+    cut_err(eof)
+        .context(StrContext::Expected(StrContextValue::Description(
+            "end of line",
+        )))
+        .void()
+        .parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CUBE_PART: &str = "# exported\n\
+        mtllib cube.mtl extra.mtl\n\
+        o Cube\n\
+        v 1.0 1.0 -1.0\n\
+        v 1.0 -1.0 -1.0 1.0\n\
+        v 1.0 1.0 1.0 0.5 0.5 0.5\n\
+        v -1e0 .5 1 \\\n\
+        \x20\n\
+        vt 0.625 0.5\n\
+        vt 0.875\n\
+        vn 0 1 0\n\
+        usemtl Material\n\
+        s off\n\
+        g top side\n\
+        f 1/1/1 2/2/1 3/1/1 4/2/1\n\
+        g\n\
+        s 1\n\
+        f -4//1 -3//1 -2//1 # relative\n\
+        l 1 2\n";
+
+    #[test]
+    fn parse_obj_should_work() -> Result<(), ParseError> {
+        let mesh = parse_obj(CUBE_PART)?;
+        assert_eq!(mesh.material_libraries, ["cube.mtl", "extra.mtl"]);
+        assert_eq!(mesh.positions.len(), 4);
+        assert_eq!(mesh.positions[3], [-1.0, 0.5, 1.0]);
+        assert_eq!(mesh.tex_coords[1], [0.875, 0.0, 0.0]);
+        assert_eq!(mesh.normals, [[0.0, 1.0, 0.0]]);
+        let quad = &mesh.faces[0];
+        assert_eq!(quad.material.as_deref(), Some("Material"));
+        assert_eq!(quad.smoothing_group, 0);
+        assert_eq!(
+            quad.vertices[1],
+            FaceVertex {
+                position: 1,
+                tex_coord: Some(1),
+                normal: Some(0)
+            }
+        );
+        let tri = &mesh.faces[1];
+        assert_eq!(tri.smoothing_group, 1);
+        assert_eq!(
+            tri.vertices.iter().map(|v| v.position).collect::<Vec<_>>(),
+            [0, 1, 2]
+        );
+        assert_eq!(tri.vertices[0].tex_coord, None);
+        let names: Vec<_> = mesh
+            .groups
+            .iter()
+            .map(|g| (g.name.as_str(), g.faces.clone()))
+            .collect();
+        assert_eq!(
+            names,
+            [("top", vec![0]), ("side", vec![0]), ("default", vec![1])]
+        );
+        assert_eq!(mesh.objects[0].faces, [0, 1]);
+        assert_eq!(mesh.triangles().count(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_obj_should_report_errors() {
+        let err = parse_obj("v 1 2 3\nv 1 2 3\nv 1 2 3\nf 1 2 4\n").unwrap_err();
+        assert_eq!(err.offset(), 30);
+        assert!(err.message().contains("out of range"));
+        let err = parse_obj("v 1 2\n").unwrap_err();
+        assert_eq!(err.offset(), 5);
+        assert!(parse_obj("v 1 2 3\nf 1 1\n").is_err());
+        assert!(parse_obj("v 1 2 3\nf 0 1 1\n").is_err());
+        assert!(parse_obj("v 1 2 x\n").is_err());
+    }
+}
@@ -0,0 +1,342 @@
+use std::fmt;
+use std::str::FromStr;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::digit1;
+use winnow::combinator::{cut_err, opt, preceded, separated};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::take_till;
+
+use crate::ParseError;
+
+/// A line of `/etc/passwd`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Passwd {
+    pub name: String,
+    /// Usually `x`, meaning the hash lives in `/etc/shadow`.
+    pub password: String,
+    pub uid: u32,
+    pub gid: u32,
+    /// Free text, conventionally comma-separated; see [`Passwd::gecos_fields`].
+    pub gecos: String,
+    pub home: String,
+    pub shell: String,
+}
+
+/// The conventional parts of a GECOS field. Missing parts are empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gecos<'a> {
+    pub full_name: &'a str,
+    pub room: &'a str,
+    pub work_phone: &'a str,
+    pub home_phone: &'a str,
+    /// Whatever follows the first four, commas included.
+    pub other: &'a str,
+}
+
+/// A line of `/etc/group`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    pub name: String,
+    pub password: String,
+    pub gid: u32,
+    pub members: Vec<String>,
+}
+
+/// A line of `/etc/shadow`. Dates count days since 1970-01-01; empty
+/// fields are `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shadow {
+    pub name: String,
+    /// The hash, or `*` / `!`-prefixed for accounts that cannot log in.
+    pub password: String,
+    pub last_change: Option<u32>,
+    pub min_days: Option<u32>,
+    pub max_days: Option<u32>,
+    pub warn_days: Option<u32>,
+    pub inactive_days: Option<u32>,
+    pub expires: Option<u32>,
+    /// Reserved, kept as written.
+    pub flag: String,
+}
+
+impl Passwd {
+    pub fn gecos_fields(&self) -> Gecos<'_> {
+        let mut parts = self.gecos.splitn(5, ',');
+        let mut next = || parts.next().unwrap_or_default();
+        Gecos {
+            full_name: next(),
+            room: next(),
+            work_phone: next(),
+            home_phone: next(),
+            other: next(),
+        }
+    }
+
+    /// Whether the shell is one of the usual "no login" placeholders.
+    pub fn is_nologin(&self) -> bool {
+        matches!(self.shell.rsplit('/').next(), Some("nologin" | "false"))
+    }
+}
+
+impl Shadow {
+    /// A `!` in front of the hash locks the password without losing it.
+    pub fn is_locked(&self) -> bool {
+        self.password.starts_with('!')
+    }
+}
+
+impl FromStr for Passwd {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        passwd.parse(s).map_err(ParseError::from)
+    }
+}
+
+impl FromStr for Group {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        group.parse(s).map_err(ParseError::from)
+    }
+}
+
+impl FromStr for Shadow {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        shadow.parse(s).map_err(ParseError::from)
+    }
+}
+
+impl fmt::Display for Passwd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Passwd {
+            name,
+            password,
+            uid,
+            gid,
+            gecos,
+            home,
+            shell,
+        } = self;
+        write!(f, "{name}:{password}:{uid}:{gid}:{gecos}:{home}:{shell}")
+    }
+}
+
+impl fmt::Display for Group {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}:{}",
+            self.name,
+            self.password,
+            self.gid,
+            self.members.join(",")
+        )
+    }
+}
+
+impl fmt::Display for Shadow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let days = |d: Option<u32>| d.map(|d| d.to_string()).unwrap_or_default();
+        write!(
+            f,
+            "{}:{}:{}:{}:{}:{}:{}:{}:{}",
+            self.name,
+            self.password,
+            days(self.last_change),
+            days(self.min_days),
+            days(self.max_days),
+            days(self.warn_days),
+            days(self.inactive_days),
+            days(self.expires),
+            self.flag
+        )
+    }
+}
+
+/// Entries of a passwd file, one per non-blank line. A bad line yields an
+/// error, with the offset into `input`, and the rest still follow.
+pub fn parse_passwd(input: &str) -> impl Iterator<Item = Result<Passwd, ParseError>> + '_ {
+    records(input, passwd)
+}
+
+/// Entries of a group file; see [`parse_passwd`].
+pub fn parse_group(input: &str) -> impl Iterator<Item = Result<Group, ParseError>> + '_ {
+    records(input, group)
+}
+
+/// Entries of a shadow file; see [`parse_passwd`].
+pub fn parse_shadow(input: &str) -> impl Iterator<Item = Result<Shadow, ParseError>> + '_ {
+    records(input, shadow)
+}
+
+fn records<'a, T: 'a>(
+    input: &'a str,
+    mut record: fn(&mut &str) -> ModalResult<T>,
+) -> impl Iterator<Item = Result<T, ParseError>> + 'a {
+    let mut offset = 0;
+    input.split_inclusive('\n').filter_map(move |line| {
+        let start = offset;
+        offset += line.len();
+        let line = line.trim_end_matches(['\n', '\r']);
+        if line.trim().is_empty() {
+            return None;
+        }
+        Some(record.parse(line).map_err(|e| {
+            let e = ParseError::from(e);
+            ParseError::new(start + e.offset(), e.message())
+        }))
+    })
+}
+
+fn field<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    take_till(0.., ':').parse_next(input)
+}
+
+/// The `:` before the next field, which has to be there.
+fn colon(name: &'static str) -> impl FnMut(&mut &str) -> ModalResult<()> {
+    move |input: &mut &str| {
+        cut_err(':')
+            .context(StrContext::Label(name))
+            .context(StrContext::Expected(StrContextValue::CharLiteral(':')))
+            .void()
+            .parse_next(input)
+    }
+}
+
+fn id(name: &'static str) -> impl FnMut(&mut &str) -> ModalResult<u32> {
+    move |input: &mut &str| {
+        cut_err(digit1.parse_to())
+            .context(StrContext::Label(name))
+            .context(StrContext::Expected(StrContextValue::Description(
+                "a number",
+            )))
+            .parse_next(input)
+    }
+}
+
+fn days(name: &'static str) -> impl FnMut(&mut &str) -> ModalResult<Option<u32>> {
+    move |input: &mut &str| {
+        opt(digit1.parse_to())
+            .context(StrContext::Label(name))
+            .parse_next(input)
+    }
+}
+
+fn passwd(input: &mut &str) -> ModalResult<Passwd> {
+    let name = field.parse_next(input)?;
+    let password = preceded(colon("password"), field).parse_next(input)?;
+    let uid = preceded(colon("uid"), id("uid")).parse_next(input)?;
+    let gid = preceded(colon("gid"), id("gid")).parse_next(input)?;
+    let gecos = preceded(colon("gecos"), field).parse_next(input)?;
+    let home = preceded(colon("home"), field).parse_next(input)?;
+    let shell = preceded(colon("shell"), field).parse_next(input)?;
+    Ok(Passwd {
+        name: name.to_string(),
+        password: password.to_string(),
+        uid,
+        gid,
+        gecos: gecos.to_string(),
+        home: home.to_string(),
+        shell: shell.to_string(),
+    })
+}
+
+fn group(input: &mut &str) -> ModalResult<Group> {
+    let name = field.parse_next(input)?;
+    let password = preceded(colon("password"), field).parse_next(input)?;
+    let gid = preceded(colon("gid"), id("gid")).parse_next(input)?;
+    let members: Vec<&str> = preceded(
+        colon("members"),
+        separated(0.., take_till(1.., [':', ',']), ','),
+    )
+    .parse_next(input)?;
+    Ok(Group {
+        name: name.to_string(),
+        password: password.to_string(),
+        gid,
+        members: members.into_iter().map(String::from).collect(),
+    })
+}
+
+fn shadow(input: &mut &str) -> ModalResult<Shadow> {
+    let name = field.parse_next(input)?;
+    let password = preceded(colon("password"), field).parse_next(input)?;
+    let last_change = preceded(colon("last change"), days("last change")).parse_next(input)?;
+    let min_days = preceded(colon("minimum age"), days("minimum age")).parse_next(input)?;
+    let max_days = preceded(colon("maximum age"), days("maximum age")).parse_next(input)?;
+    let warn_days = preceded(colon("warning period"), days("warning period")).parse_next(input)?;
+    let inactive_days =
+        preceded(colon("inactivity period"), days("inactivity period")).parse_next(input)?;
+    let expires = preceded(colon("expiration"), days("expiration")).parse_next(input)?;
+    let flag = preceded(colon("flag"), field).parse_next(input)?;
+    Ok(Shadow {
+        name: name.to_string(),
+        password: password.to_string(),
+        last_change,
+        min_days,
+        max_days,
+        warn_days,
+        inactive_days,
+        expires,
+        flag: flag.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_passwd_should_work() -> Result<(), ParseError> {
+        let input = "root:x:0:0:root:/root:/bin/bash\n\n\
+            alice:x:1000:1000:Alice Liddell,Room 3,555-1234,,extra,more:/home/alice:/bin/zsh\r\n\
+            daemon:*:1:1::/usr/sbin:/usr/sbin/nologin\n";
+        let entries = parse_passwd(input).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[1].uid, 1000);
+        let gecos = entries[1].gecos_fields();
+        assert_eq!(gecos.full_name, "Alice Liddell");
+        assert_eq!(gecos.work_phone, "555-1234");
+        assert_eq!(gecos.home_phone, "");
+        assert_eq!(gecos.other, "extra,more");
+        assert!(entries[2].is_nologin());
+        assert_eq!(entries[2].gecos_fields().full_name, "");
+        assert_eq!(entries[0].to_string(), "root:x:0:0:root:/root:/bin/bash");
+        Ok(())
+    }
+
+    #[test]
+    fn group_and_shadow_should_parse() -> Result<(), ParseError> {
+        let groups = parse_group("wheel:x:10:root,alice\nnogroup:x:65534:\n")
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(groups[0].members, ["root", "alice"]);
+        assert!(groups[1].members.is_empty());
+        assert_eq!(groups[0].to_string(), "wheel:x:10:root,alice");
+
+        let line = "alice:!$6$salt$hash:19700:0:99999:7:::";
+        let shadow: Shadow = line.parse()?;
+        assert!(shadow.is_locked());
+        assert_eq!(shadow.last_change, Some(19700));
+        assert_eq!(shadow.max_days, Some(99999));
+        assert_eq!(shadow.inactive_days, None);
+        assert_eq!(shadow.to_string(), line);
+        Ok(())
+    }
+
+    #[test]
+    fn bad_lines_should_not_stop_the_iterator() {
+        let input = "root:x:0:0:root:/root:/bin/bash\nbob:x:-1:0::/:/bin/sh\nshort:x:1\nok:x:2:2::/:/bin/sh\n";
+        let results: Vec<_> = parse_passwd(input).collect();
+        assert_eq!(results.len(), 4);
+        assert!(results[0].is_ok() && results[3].is_ok());
+        assert_eq!(results[1].as_ref().unwrap_err().offset(), 38);
+        assert_eq!(results[2].as_ref().unwrap_err().offset(), 63);
+        assert!("a:x:1:1::".parse::<Passwd>().is_err());
+        assert!("a:x:1:1:::/bin/sh:extra".parse::<Passwd>().is_err());
+    }
+}
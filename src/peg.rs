@@ -0,0 +1,415 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+use crate::ParseError;
+use crate::combinators::Span;
+use crate::ebnf::{Dialect, Expr, Grammar, Rule, parse_abnf};
+use crate::error::one_of;
+
+/// The RFC 5234 core rules, available to every ABNF grammar that does not
+/// define them itself.
+const CORE_RULES: &str = "\
+ALPHA  = %x41-5A / %x61-7A
+BIT    = \"0\" / \"1\"
+CHAR   = %x01-7F
+CR     = %x0D
+CRLF   = CR LF
+CTL    = %x00-1F / %x7F
+DIGIT  = %x30-39
+DQUOTE = %x22
+HEXDIG = DIGIT / \"A\" / \"B\" / \"C\" / \"D\" / \"E\" / \"F\"
+HTAB   = %x09
+LF     = %x0A
+LWSP   = *(WSP / CRLF WSP)
+OCTET  = %x00-FF
+SP     = %x20
+VCHAR  = %x21-7E
+WSP    = SP / HTAB
+";
+
+/// A match of one rule: which rule, the span of input it covered, and the
+/// matches of the rules it referenced, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node<'g> {
+    pub rule: &'g str,
+    pub span: Span,
+    pub children: Vec<Node<'g>>,
+}
+
+impl<'g> Node<'g> {
+    pub fn text<'i>(&self, input: &'i str) -> &'i str {
+        &input[self.span.clone()]
+    }
+
+    /// The descendants matched by `rule`, in the order they start.
+    pub fn find(&self, rule: &str) -> Vec<&Node<'g>> {
+        let mut found = Vec::new();
+        self.collect(rule, &mut found);
+        found
+    }
+
+    fn collect<'n>(&'n self, rule: &str, found: &mut Vec<&'n Node<'g>>) {
+        for child in &self.children {
+            if child.rule == rule {
+                found.push(child);
+            }
+            child.collect(rule, found);
+        }
+    }
+}
+
+/// Runs a grammar from [`crate::ebnf`] over input text, as a PEG:
+/// alternatives are tried in order and the first that matches wins, and
+/// repetitions take as much as they can without giving any back. Grammars
+/// written for ABNF, whose alternatives are unordered, may need their
+/// longer alternatives moved first.
+///
+/// Left recursion is reported instead of looping forever.
+#[derive(Debug)]
+pub struct Interpreter<'g> {
+    rules: Vec<&'g Rule>,
+    index: HashMap<String, usize>,
+    dialect: Dialect,
+}
+
+impl<'g> Interpreter<'g> {
+    /// Checks that every rule the grammar refers to is defined.
+    pub fn new(grammar: &'g Grammar) -> Result<Self, ParseError> {
+        let mut interpreter = Interpreter {
+            rules: Vec::new(),
+            index: HashMap::new(),
+            dialect: grammar.dialect,
+        };
+        for rule in &grammar.rules {
+            interpreter.add(rule);
+        }
+        if grammar.dialect == Dialect::Abnf {
+            for rule in &CORE.rules {
+                if interpreter.lookup(&rule.name).is_none() {
+                    interpreter.add(rule);
+                }
+            }
+        }
+        for rule in &interpreter.rules {
+            if let Some(name) = undefined(&interpreter, &rule.expr) {
+                return Err(ParseError::new(
+                    rule.span.start,
+                    format!("rule `{}` refers to undefined rule `{name}`", rule.name),
+                ));
+            }
+        }
+        Ok(interpreter)
+    }
+
+    /// Matches all of `input` against the first rule of the grammar.
+    pub fn parse(&self, input: &str) -> Result<Node<'g>, ParseError> {
+        let Some(start) = self.rules.first() else {
+            return Err(ParseError::new(0, "the grammar has no rules"));
+        };
+        self.parse_rule(&start.name, input)
+    }
+
+    /// Matches all of `input` against the rule called `rule`.
+    pub fn parse_rule(&self, rule: &str, input: &str) -> Result<Node<'g>, ParseError> {
+        let Some(index) = self.lookup(rule) else {
+            return Err(ParseError::new(0, format!("undefined rule `{rule}`")));
+        };
+        let mut run = Run {
+            interpreter: self,
+            input,
+            farthest: 0,
+            expected: Vec::new(),
+            context: Vec::new(),
+            stack: Vec::new(),
+            active: HashSet::new(),
+            quiet: 0,
+        };
+        let mut nodes = Vec::new();
+        let end = run.rule(index, 0, &mut nodes)?;
+        match end {
+            Some(end) if end == input.len() => Ok(nodes.remove(0)),
+            Some(end) => {
+                run.expect(end, "end of input".to_string());
+                Err(run.error())
+            }
+            None => Err(run.error()),
+        }
+    }
+
+    fn add(&mut self, rule: &'g Rule) {
+        self.index.insert(self.key(&rule.name), self.rules.len());
+        self.rules.push(rule);
+    }
+
+    fn lookup(&self, name: &str) -> Option<usize> {
+        self.index.get(&self.key(name)).copied()
+    }
+
+    fn key(&self, name: &str) -> String {
+        match self.dialect {
+            Dialect::Ebnf => name.to_string(),
+            Dialect::Abnf => name.to_ascii_lowercase(),
+        }
+    }
+}
+
+static CORE: LazyLock<Grammar> =
+    LazyLock::new(|| parse_abnf(CORE_RULES).expect("the core rules are valid ABNF"));
+
+/// The first rule `expr` refers to that the interpreter does not know.
+fn undefined<'e>(interpreter: &Interpreter, expr: &'e Expr) -> Option<&'e str> {
+    match expr {
+        Expr::Rule(name) => interpreter.lookup(name).is_none().then_some(name.as_str()),
+        Expr::Sequence(items) | Expr::Choice(items) => {
+            items.iter().find_map(|item| undefined(interpreter, item))
+        }
+        Expr::Repeat { expr, .. } | Expr::And(expr) | Expr::Not(expr) => {
+            undefined(interpreter, expr)
+        }
+        Expr::Literal { .. } | Expr::Range(..) | Expr::Prose(_) => None,
+    }
+}
+
+/// The state of one parse. Failures are reported at the farthest offset
+/// any terminal failed at, with every terminal that was tried there.
+struct Run<'a, 'g, 'i> {
+    interpreter: &'a Interpreter<'g>,
+    input: &'i str,
+    farthest: usize,
+    expected: Vec<String>,
+    /// The rules active at every failure at `farthest`.
+    context: Vec<&'g str>,
+    stack: Vec<&'g str>,
+    /// The rules being matched and where, to catch left recursion.
+    active: HashSet<(usize, usize)>,
+    /// Inside `!e`, where failing is what the grammar hopes for.
+    quiet: usize,
+}
+
+impl<'g> Run<'_, 'g, '_> {
+    /// Matches `expr` at `pos`, returning where the match ends. Rules it
+    /// matched are added to `nodes`, which is left alone if it fails.
+    fn matches(
+        &mut self,
+        expr: &'g Expr,
+        pos: usize,
+        nodes: &mut Vec<Node<'g>>,
+    ) -> Result<Option<usize>, ParseError> {
+        let rest = &self.input[pos..];
+        let len = nodes.len();
+        let end = match expr {
+            Expr::Literal { text, caseless } => {
+                let matched = rest.get(..text.len()).is_some_and(|prefix| {
+                    if *caseless {
+                        prefix.eq_ignore_ascii_case(text)
+                    } else {
+                        prefix == text
+                    }
+                });
+                if !matched {
+                    self.expect(pos, format!("{text:?}"));
+                }
+                matched.then_some(pos + text.len())
+            }
+            Expr::Range(low, high) => match rest.chars().next() {
+                Some(c) if (*low..=*high).contains(&c) => Some(pos + c.len_utf8()),
+                _ => {
+                    self.expect(pos, format!("{low:?}..{high:?}"));
+                    None
+                }
+            },
+            Expr::Rule(name) => {
+                let index = self.interpreter.lookup(name).expect("checked by new");
+                self.rule(index, pos, nodes)?
+            }
+            Expr::Sequence(items) => {
+                let mut end = Some(pos);
+                for item in items {
+                    let Some(pos) = end else { break };
+                    end = self.matches(item, pos, nodes)?;
+                }
+                end
+            }
+            Expr::Choice(alternatives) => {
+                let mut end = None;
+                for alternative in alternatives {
+                    end = self.matches(alternative, pos, nodes)?;
+                    if end.is_some() {
+                        break;
+                    }
+                }
+                end
+            }
+            Expr::Repeat { expr, min, max } => {
+                let mut count = 0;
+                let mut end = pos;
+                while max.is_none_or(|max| count < max) {
+                    match self.matches(expr, end, nodes)? {
+                        Some(next) if next > end => end = next,
+                        // An empty match would repeat forever.
+                        Some(_) => {
+                            count = count.max(*min);
+                            break;
+                        }
+                        None => break,
+                    }
+                    count += 1;
+                }
+                (count >= *min).then_some(end)
+            }
+            Expr::And(expr) => self.matches(expr, pos, &mut Vec::new())?.map(|_| pos),
+            Expr::Not(expr) => {
+                self.quiet += 1;
+                let matched = self.matches(expr, pos, &mut Vec::new());
+                self.quiet -= 1;
+                matched?.is_none().then_some(pos)
+            }
+            Expr::Prose(text) => {
+                return Err(ParseError::new(pos, format!("cannot match prose <{text}>")));
+            }
+        };
+        if end.is_none() {
+            nodes.truncate(len);
+        }
+        Ok(end)
+    }
+
+    fn rule(
+        &mut self,
+        index: usize,
+        pos: usize,
+        nodes: &mut Vec<Node<'g>>,
+    ) -> Result<Option<usize>, ParseError> {
+        let rule = self.interpreter.rules[index];
+        if !self.active.insert((index, pos)) {
+            return Err(ParseError::new(
+                pos,
+                format!("rule `{}` is left recursive", rule.name),
+            ));
+        }
+        self.stack.push(&rule.name);
+        let mut children = Vec::new();
+        let end = self.matches(&rule.expr, pos, &mut children);
+        self.stack.pop();
+        self.active.remove(&(index, pos));
+        let end = end?;
+        if let Some(end) = end {
+            nodes.push(Node {
+                rule: &rule.name,
+                span: pos..end,
+                children,
+            });
+        }
+        Ok(end)
+    }
+
+    fn expect(&mut self, pos: usize, what: String) {
+        if self.quiet > 0 || pos < self.farthest {
+            return;
+        }
+        if pos > self.farthest || self.expected.is_empty() {
+            self.farthest = pos;
+            self.expected.clear();
+            self.context = self.stack.clone();
+        }
+        let common = self
+            .context
+            .iter()
+            .zip(&self.stack)
+            .take_while(|(a, b)| a == b)
+            .count();
+        self.context.truncate(common);
+        if !self.expected.contains(&what) {
+            self.expected.push(what);
+        }
+    }
+
+    fn error(&self) -> ParseError {
+        let expected = match self.expected.as_slice() {
+            [] => "unexpected input".to_string(),
+            expected => format!("expected {}", one_of(expected)),
+        };
+        let message = match self.context.last() {
+            Some(rule) => format!("{expected} while parsing {rule}"),
+            None => expected,
+        };
+        ParseError::new(self.farthest, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ebnf::parse_ebnf;
+
+    const LISTS: &str = r#"
+        value  = ws (number | list) ws ;
+        list   = "[" ws [ value { "," value } ] "]" ;
+        number = digit+ ;
+        digit  = "0" .. "9" ;
+        ws     = { " " } ;
+    "#;
+
+    #[test]
+    fn interpreter_should_build_parse_trees() -> Result<(), ParseError> {
+        let grammar = parse_ebnf(LISTS)?;
+        let interpreter = Interpreter::new(&grammar)?;
+        let input = "[1, [23]]";
+        let tree = interpreter.parse(input)?;
+        assert_eq!((tree.rule, tree.span.clone()), ("value", 0..9));
+        let numbers: Vec<&str> = tree.find("number").iter().map(|n| n.text(input)).collect();
+        assert_eq!(numbers, ["1", "23"]);
+        let list = tree.find("list").first().map(|n| n.children.len());
+        assert_eq!(list, Some(3), "ws and two values");
+
+        let number = interpreter.parse_rule("number", "42")?;
+        let digits: Vec<_> = number.children.iter().map(|d| d.span.clone()).collect();
+        assert_eq!(digits, [0..1, 1..2]);
+        Ok(())
+    }
+
+    #[test]
+    fn interpreter_should_report_the_farthest_failure() -> Result<(), ParseError> {
+        let grammar = parse_ebnf(LISTS)?;
+        let interpreter = Interpreter::new(&grammar)?;
+        let err = interpreter.parse("[1, ]").unwrap_err();
+        assert_eq!(err.offset(), 4);
+        assert_eq!(
+            err.message(),
+            r#"expected " ", '0'..'9' or "[" while parsing value"#
+        );
+        let err = interpreter.parse("[1] 2").unwrap_err();
+        assert_eq!(err.offset(), 4);
+        assert_eq!(err.message(), r#"expected " " or end of input"#);
+
+        let grammar = parse_ebnf("a = b ;")?;
+        let err = Interpreter::new(&grammar).unwrap_err();
+        assert_eq!(err.message(), "rule `a` refers to undefined rule `b`");
+        let grammar = parse_ebnf(r#"expr = expr "+" "1" | "1" ;"#)?;
+        let err = Interpreter::new(&grammar)?.parse("1+1").unwrap_err();
+        assert_eq!(err.message(), "rule `expr` is left recursive");
+        Ok(())
+    }
+
+    #[test]
+    fn interpreter_should_run_abnf_with_core_rules() -> Result<(), ParseError> {
+        let grammar = parse_abnf(
+            "header = name \":\" *WSP value\n\
+             name   = 1*(ALPHA / \"-\")\n\
+             value  = *VCHAR\n",
+        )?;
+        let interpreter = Interpreter::new(&grammar)?;
+        let input = "content-type:  text/plain";
+        let tree = interpreter.parse(input)?;
+        let fields: Vec<&str> = tree
+            .children
+            .iter()
+            .filter(|n| n.rule != "WSP")
+            .map(|n| n.text(input))
+            .collect();
+        assert_eq!(fields, ["content-type", "text/plain"]);
+        assert!(interpreter.parse_rule("NAME", "X-Id").is_ok());
+        assert!(interpreter.parse("content-type text").is_err());
+        Ok(())
+    }
+}
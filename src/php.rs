@@ -0,0 +1,371 @@
+use std::fmt;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::digit1;
+use winnow::combinator::{alt, cut_err, fail, opt, preceded, repeat, terminated};
+use winnow::error::{ContextError, ErrMode, StrContext, StrContextValue};
+use winnow::token::{one_of, take_till, take_while};
+
+use crate::ParseError;
+
+/// A value in PHP's `serialize()` format.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PhpValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    /// PHP arrays are ordered maps; entries keep their serialized order.
+    Array(Vec<(Key, PhpValue)>),
+    Object(Object),
+    /// A class with its own `Serializable` format, kept as written.
+    Custom {
+        class: String,
+        data: String,
+    },
+    /// A backed or pure enum case, as `Class:Case`.
+    Enum(String),
+    /// `R:n;`, a PHP `&` reference to the `n`th value read.
+    Reference(usize),
+    /// `r:n;`, the same object appearing again.
+    ObjectReference(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Key {
+    Int(i64),
+    String(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Object {
+    pub class: String,
+    /// Property names as serialized; see [`Object::get`].
+    pub properties: Vec<(String, PhpValue)>,
+}
+
+impl Object {
+    /// Looks a property up by its plain name. Private and protected names
+    /// are serialized with a `\0Class\0` or `\0*\0` prefix, which is
+    /// ignored here.
+    pub fn get(&self, name: &str) -> Option<&PhpValue> {
+        self.properties
+            .iter()
+            .find(|(key, _)| plain_name(key) == name)
+            .map(|(_, value)| value)
+    }
+}
+
+fn plain_name(property: &str) -> &str {
+    match property.strip_prefix('\0') {
+        Some(rest) => rest.split_once('\0').map_or(rest, |(_, name)| name),
+        None => property,
+    }
+}
+
+impl PhpValue {
+    /// Looks up a string key in an array, or a property of an object.
+    pub fn get(&self, key: &str) -> Option<&PhpValue> {
+        match self {
+            PhpValue::Array(entries) => entries
+                .iter()
+                .find(|(k, _)| matches!(k, Key::String(s) if s == key))
+                .map(|(_, v)| v),
+            PhpValue::Object(object) => object.get(key),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            PhpValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// Writes the value back in `serialize()` format.
+impl fmt::Display for PhpValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PhpValue::Null => f.write_str("N;"),
+            PhpValue::Bool(b) => write!(f, "b:{};", u8::from(*b)),
+            PhpValue::Int(n) => write!(f, "i:{n};"),
+            PhpValue::Float(x) if x.is_nan() => f.write_str("d:NAN;"),
+            PhpValue::Float(x) if x.is_infinite() => {
+                write!(f, "d:{}INF;", if *x < 0.0 { "-" } else { "" })
+            }
+            PhpValue::Float(x) => write!(f, "d:{x};"),
+            PhpValue::String(s) => write!(f, "s:{}:\"{s}\";", s.len()),
+            PhpValue::Array(entries) => {
+                write!(f, "a:{}:{{", entries.len())?;
+                for (key, value) in entries {
+                    write!(f, "{key}{value}")?;
+                }
+                f.write_str("}")
+            }
+            PhpValue::Object(object) => {
+                write!(
+                    f,
+                    "O:{}:\"{}\":{}:{{",
+                    object.class.len(),
+                    object.class,
+                    object.properties.len()
+                )?;
+                for (name, value) in &object.properties {
+                    write!(f, "s:{}:\"{name}\";{value}", name.len())?;
+                }
+                f.write_str("}")
+            }
+            PhpValue::Custom { class, data } => {
+                write!(f, "C:{}:\"{class}\":{}:{{{data}}}", class.len(), data.len())
+            }
+            PhpValue::Enum(case) => write!(f, "E:{}:\"{case}\";", case.len()),
+            PhpValue::Reference(n) => write!(f, "R:{n};"),
+            PhpValue::ObjectReference(n) => write!(f, "r:{n};"),
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key::Int(n) => write!(f, "i:{n};"),
+            Key::String(s) => write!(f, "s:{}:\"{s}\";", s.len()),
+        }
+    }
+}
+
+/// Parses one serialized value. String lengths count bytes, so a length
+/// that ends inside a multi-byte character is an error.
+pub fn parse_php(input: &str) -> Result<PhpValue, ParseError> {
+    value.parse(input).map_err(ParseError::from)
+}
+
+/// Parses session data in PHP's default `php` handler format:
+/// `name|value` pairs with nothing between them.
+pub fn parse_php_session(input: &str) -> Result<Vec<(String, PhpValue)>, ParseError> {
+    repeat(
+        0..,
+        (
+            terminated(
+                take_till(1.., '|').map(str::to_string),
+                cut_err('|').context(StrContext::Expected(StrContextValue::CharLiteral('|'))),
+            ),
+            cut_err(value),
+        ),
+    )
+    .parse(input)
+    .map_err(ParseError::from)
+}
+
+fn value(input: &mut &str) -> ModalResult<PhpValue> {
+    alt((
+        "N;".value(PhpValue::Null),
+        scalar('b', alt(('0'.value(false), '1'.value(true)))).map(PhpValue::Bool),
+        scalar('i', integer).map(PhpValue::Int),
+        scalar('d', float).map(PhpValue::Float),
+        scalar('R', digit1.parse_to()).map(PhpValue::Reference),
+        scalar('r', digit1.parse_to()).map(PhpValue::ObjectReference),
+        terminated(preceded("s:", cut_err(string)), cut_err(';')).map(PhpValue::String),
+        terminated(preceded("E:", cut_err(string)), cut_err(';')).map(PhpValue::Enum),
+        preceded("a:", cut_err(array)),
+        preceded("O:", cut_err(object)),
+        preceded("C:", cut_err(custom)),
+    ))
+    .context(StrContext::Label("value"))
+    .parse_next(input)
+}
+
+/// `t:<body>;` for the one-letter type tag `tag`.
+fn scalar<'i, O>(
+    tag: char,
+    body: impl Parser<&'i str, O, ErrMode<ContextError>>,
+) -> impl Parser<&'i str, O, ErrMode<ContextError>> {
+    preceded((tag, ':'), cut_err(terminated(body, ';')))
+}
+
+fn integer(input: &mut &str) -> ModalResult<i64> {
+    (opt(one_of(['+', '-'])), digit1)
+        .take()
+        .parse_to()
+        .parse_next(input)
+}
+
+fn float(input: &mut &str) -> ModalResult<f64> {
+    alt((
+        "NAN".value(f64::NAN),
+        "INF".value(f64::INFINITY),
+        "-INF".value(f64::NEG_INFINITY),
+        take_while(1.., |c: char| {
+            c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | 'e' | 'E')
+        })
+        .parse_to(),
+    ))
+    .parse_next(input)
+}
+
+fn length(input: &mut &str) -> ModalResult<usize> {
+    terminated(digit1.parse_to(), ':')
+        .context(StrContext::Label("length"))
+        .parse_next(input)
+}
+
+/// `<len>:"<bytes>"`, without the trailing `;` since objects reuse it for
+/// class names.
+fn string(input: &mut &str) -> ModalResult<String> {
+    let len = length.parse_next(input)?;
+    '"'.parse_next(input)?;
+    let text = bytes(input, len)?;
+    cut_err('"')
+        .context(StrContext::Expected(StrContextValue::CharLiteral('"')))
+        .parse_next(input)?;
+    Ok(text.to_string())
+}
+
+fn bytes<'i>(input: &mut &'i str, len: usize) -> ModalResult<&'i str> {
+    if len > input.len() {
+        return cut_err(fail)
+            .context(StrContext::Label("length past the end of input"))
+            .parse_next(input);
+    }
+    if !input.is_char_boundary(len) {
+        return cut_err(fail)
+            .context(StrContext::Label("length splits a character"))
+            .parse_next(input);
+    }
+    let (text, rest) = input.split_at(len);
+    *input = rest;
+    Ok(text)
+}
+
+/// The `{...}` body shared by arrays and objects, holding `count` pairs.
+fn entries<K>(
+    input: &mut &str,
+    count: usize,
+    mut key: impl FnMut(&mut &str) -> ModalResult<K>,
+) -> ModalResult<Vec<(K, PhpValue)>> {
+    '{'.parse_next(input)?;
+    let mut entries = Vec::with_capacity(count.min(1024));
+    for _ in 0..count {
+        let k = key(input)?;
+        let v = value.parse_next(input)?;
+        entries.push((k, v));
+    }
+    '}'.context(StrContext::Expected(StrContextValue::CharLiteral('}')))
+        .parse_next(input)?;
+    Ok(entries)
+}
+
+fn key(input: &mut &str) -> ModalResult<Key> {
+    alt((
+        scalar('i', integer).map(Key::Int),
+        terminated(preceded("s:", cut_err(string)), cut_err(';')).map(Key::String),
+    ))
+    .context(StrContext::Expected(StrContextValue::Description(
+        "an integer or string key",
+    )))
+    .parse_next(input)
+}
+
+fn property(input: &mut &str) -> ModalResult<String> {
+    terminated(preceded("s:", cut_err(string)), cut_err(';'))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "a property name",
+        )))
+        .parse_next(input)
+}
+
+fn array(input: &mut &str) -> ModalResult<PhpValue> {
+    let count = length.parse_next(input)?;
+    entries(input, count, key).map(PhpValue::Array)
+}
+
+fn object(input: &mut &str) -> ModalResult<PhpValue> {
+    let class = terminated(string, ':').parse_next(input)?;
+    let count = length.parse_next(input)?;
+    let properties = entries(input, count, property)?;
+    Ok(PhpValue::Object(Object { class, properties }))
+}
+
+fn custom(input: &mut &str) -> ModalResult<PhpValue> {
+    let class = terminated(string, ':').parse_next(input)?;
+    let len = length.parse_next(input)?;
+    '{'.parse_next(input)?;
+    let data = bytes(input, len)?.to_string();
+    '}'.context(StrContext::Expected(StrContextValue::CharLiteral('}')))
+        .parse_next(input)?;
+    Ok(PhpValue::Custom { class, data })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_php_should_work() -> Result<(), ParseError> {
+        let value = parse_php(
+            "a:5:{s:3:\"foo\";i:1;i:7;a:2:{i:0;d:0.5;i:1;b:1;}s:5:\"café\";s:2:\"é\";\
+             s:3:\"obj\";O:4:\"User\":3:{s:4:\"name\";s:3:\"Ann\";\
+             s:8:\"\0User\0id\";i:-3;s:7:\"\0*\0role\";N;}s:4:\"same\";r:6;}",
+        )?;
+        assert_eq!(value.get("foo"), Some(&PhpValue::Int(1)));
+        let PhpValue::Array(entries) = &value else {
+            panic!("expected an array");
+        };
+        assert_eq!(
+            entries[1],
+            (
+                Key::Int(7),
+                PhpValue::Array(vec![
+                    (Key::Int(0), PhpValue::Float(0.5)),
+                    (Key::Int(1), PhpValue::Bool(true)),
+                ])
+            )
+        );
+        assert_eq!(value.get("café").and_then(PhpValue::as_str), Some("é"));
+        let user = value.get("obj").unwrap();
+        assert_eq!(user.get("name").and_then(PhpValue::as_str), Some("Ann"));
+        assert_eq!(user.get("id"), Some(&PhpValue::Int(-3)));
+        assert_eq!(user.get("role"), Some(&PhpValue::Null));
+        assert_eq!(value.get("same"), Some(&PhpValue::ObjectReference(6)));
+        assert_eq!(
+            parse_php("C:3:\"Foo\":6:{a:0:{}}")?,
+            PhpValue::Custom {
+                class: "Foo".to_string(),
+                data: "a:0:{}".to_string()
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn display_should_round_trip() -> Result<(), ParseError> {
+        for serialized in [
+            "a:2:{i:0;s:5:\"a\"b;c\";s:1:\"k\";R:2;}",
+            "O:8:\"stdClass\":1:{s:1:\"x\";d:-INF;}",
+            "E:11:\"Suit:Hearts\";",
+            "b:0;",
+        ] {
+            assert_eq!(parse_php(serialized)?.to_string(), serialized);
+        }
+        let session = parse_php_session("user|s:3:\"ann\";count|i:4;")?;
+        assert_eq!(session[0].0, "user");
+        assert_eq!(session[1].1, PhpValue::Int(4));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_php_should_report_errors() {
+        assert_eq!(parse_php("s:5:\"abc\";").unwrap_err().offset(), 10);
+        assert_eq!(parse_php("s:1:\"é\";").unwrap_err().offset(), 5);
+        assert_eq!(parse_php("a:2:{i:0;i:1;}").unwrap_err().offset(), 13);
+        assert!(parse_php("a:1:{d:1.5;i:1;}").is_err());
+        assert!(parse_php("i:1").is_err());
+        assert!(parse_php("x:1;").is_err());
+        assert!(parse_php("N;N;").is_err());
+        assert!(parse_php_session("user").is_err());
+    }
+}
@@ -0,0 +1,273 @@
+use std::rc::Rc;
+
+use winnow::Parser;
+use winnow::error::{ModalError, ParserError};
+use winnow::stream::Stream;
+
+/// Which side of a chain of operators at the same precedence groups first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assoc {
+    /// `a - b - c` is `(a - b) - c`.
+    Left,
+    /// `a ^ b ^ c` is `a ^ (b ^ c)`.
+    Right,
+}
+
+type Unary<'a, O> = Box<dyn FnOnce(O) -> O + 'a>;
+type Binary<'a, O> = Box<dyn FnOnce(O, O) -> O + 'a>;
+type Operator<'a, I, T, E> = Box<dyn FnMut(&mut I) -> Result<T, E> + 'a>;
+
+struct Prefix<'a, I, O, E> {
+    precedence: u8,
+    parse: Operator<'a, I, Unary<'a, O>, E>,
+}
+
+enum Fold<'a, O> {
+    Infix(Binary<'a, O>),
+    Postfix(Unary<'a, O>),
+}
+
+/// An infix or postfix operator: anything that comes after an operand.
+struct Trailing<'a, I, O, E> {
+    precedence: u8,
+    assoc: Assoc,
+    parse: Operator<'a, I, Fold<'a, O>, E>,
+}
+
+/// A precedence-climbing expression parser built from an operator table.
+///
+/// Higher precedences bind tighter. Each operator is a parser of its own,
+/// whose output is handed to the fold that builds the node, so one entry can
+/// cover a family of symbols or carry extra syntax such as `BETWEEN a AND b`.
+/// Operators are tried in the order they were added, like `alt`, and the
+/// first that matches is the one used, so add `<=` before `<`. Operators
+/// should consume the white space after them, as the operand parser does.
+///
+/// Once an operator has matched, a missing operand after it is a cut error.
+pub struct Pratt<'a, I, O, E> {
+    operand: Operator<'a, I, O, E>,
+    prefix: Vec<Prefix<'a, I, O, E>>,
+    trailing: Vec<Trailing<'a, I, O, E>>,
+}
+
+impl<'a, I, O, E> Pratt<'a, I, O, E>
+where
+    I: Stream + 'a,
+    O: 'a,
+    E: ParserError<I> + ModalError + 'a,
+{
+    /// An engine with no operators yet, where `operand` parses the atoms:
+    /// literals, names and parenthesized sub-expressions.
+    pub fn new(mut operand: impl Parser<I, O, E> + 'a) -> Self {
+        Pratt {
+            operand: Box::new(move |input| operand.parse_next(input)),
+            prefix: Vec::new(),
+            trailing: Vec::new(),
+        }
+    }
+
+    /// A prefix operator, whose operand extends over every operator that
+    /// binds at least as tightly as `precedence`.
+    pub fn prefix<Op: 'a>(
+        mut self,
+        precedence: u8,
+        mut op: impl Parser<I, Op, E> + 'a,
+        fold: impl Fn(Op, O) -> O + 'a,
+    ) -> Self {
+        let fold = Rc::new(fold);
+        let parse = move |input: &mut I| {
+            let op = op.parse_next(input)?;
+            let fold = Rc::clone(&fold);
+            Ok(Box::new(move |operand| fold(op, operand)) as Unary<'a, O>)
+        };
+        self.prefix.push(Prefix {
+            precedence,
+            parse: Box::new(parse),
+        });
+        self
+    }
+
+    pub fn infix<Op: 'a>(
+        mut self,
+        precedence: u8,
+        assoc: Assoc,
+        mut op: impl Parser<I, Op, E> + 'a,
+        fold: impl Fn(O, Op, O) -> O + 'a,
+    ) -> Self {
+        let fold = Rc::new(fold);
+        let parse = move |input: &mut I| {
+            let op = op.parse_next(input)?;
+            let fold = Rc::clone(&fold);
+            Ok(Fold::Infix(Box::new(move |lhs, rhs| fold(lhs, op, rhs))))
+        };
+        self.trailing.push(Trailing {
+            precedence,
+            assoc,
+            parse: Box::new(parse),
+        });
+        self
+    }
+
+    pub fn postfix<Op: 'a>(
+        mut self,
+        precedence: u8,
+        mut op: impl Parser<I, Op, E> + 'a,
+        fold: impl Fn(O, Op) -> O + 'a,
+    ) -> Self {
+        let fold = Rc::new(fold);
+        let parse = move |input: &mut I| {
+            let op = op.parse_next(input)?;
+            let fold = Rc::clone(&fold);
+            Ok(Fold::Postfix(Box::new(move |operand| fold(operand, op))))
+        };
+        self.trailing.push(Trailing {
+            precedence,
+            assoc: Assoc::Left,
+            parse: Box::new(parse),
+        });
+        self
+    }
+
+    /// Parses an expression made of operators that bind at least as tightly
+    /// as `min_precedence`, leaving looser ones for the caller.
+    pub fn parse_at(&mut self, input: &mut I, min_precedence: u8) -> Result<O, E> {
+        let mut lhs = self.unary(input)?;
+        loop {
+            let checkpoint = input.checkpoint();
+            let Some((precedence, assoc, fold)) = self.trailing(input)? else {
+                break;
+            };
+            if precedence < min_precedence {
+                input.reset(&checkpoint);
+                break;
+            }
+            lhs = match fold {
+                Fold::Postfix(fold) => fold(lhs),
+                Fold::Infix(fold) => {
+                    let rhs = match assoc {
+                        Assoc::Left => precedence.saturating_add(1),
+                        Assoc::Right => precedence,
+                    };
+                    fold(lhs, self.required(input, rhs)?)
+                }
+            };
+        }
+        Ok(lhs)
+    }
+
+    /// The prefix operators in front of an operand, applied to it.
+    fn unary(&mut self, input: &mut I) -> Result<O, E> {
+        let checkpoint = input.checkpoint();
+        for i in 0..self.prefix.len() {
+            match (self.prefix[i].parse)(input) {
+                Ok(fold) => {
+                    let operand = self.required(input, self.prefix[i].precedence)?;
+                    return Ok(fold(operand));
+                }
+                Err(e) if e.is_backtrack() => input.reset(&checkpoint),
+                Err(e) => return Err(e),
+            }
+        }
+        (self.operand)(input)
+    }
+
+    /// The first infix or postfix operator that matches, if any does.
+    fn trailing(&mut self, input: &mut I) -> Result<Option<(u8, Assoc, Fold<'a, O>)>, E> {
+        let checkpoint = input.checkpoint();
+        for op in &mut self.trailing {
+            match (op.parse)(input) {
+                Ok(fold) => return Ok(Some((op.precedence, op.assoc, fold))),
+                Err(e) if e.is_backtrack() => input.reset(&checkpoint),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(None)
+    }
+
+    fn required(&mut self, input: &mut I, min_precedence: u8) -> Result<O, E> {
+        self.parse_at(input, min_precedence)
+            .map_err(ModalError::cut)
+    }
+}
+
+impl<'a, I, O, E> Parser<I, O, E> for Pratt<'a, I, O, E>
+where
+    I: Stream + 'a,
+    O: 'a,
+    E: ParserError<I> + ModalError + 'a,
+{
+    fn parse_next(&mut self, input: &mut I) -> Result<O, E> {
+        self.parse_at(input, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winnow::ModalResult;
+    use winnow::ascii::{dec_int, multispace0};
+    use winnow::combinator::{alt, cut_err, delimited, terminated};
+    use winnow::error::{ContextError, ErrMode, StrContext, StrContextValue};
+
+    use super::*;
+    use crate::ParseError;
+
+    fn op<'i>(symbol: &'static str) -> impl Parser<&'i str, &'i str, ErrMode<ContextError>> {
+        terminated(symbol, multispace0)
+    }
+
+    fn arithmetic<'i>() -> Pratt<'i, &'i str, i64, ErrMode<ContextError>> {
+        Pratt::new(terminated(atom, multispace0))
+            .prefix(3, op("-"), |_, n: i64| -n)
+            .infix(1, Assoc::Left, op("+"), |a, _, b| a + b)
+            .infix(1, Assoc::Left, op("-"), |a, _, b| a - b)
+            .infix(4, Assoc::Right, op("**"), |a, _, b| a.pow(b as u32))
+            .infix(2, Assoc::Left, op("*"), |a, _, b| a * b)
+            .infix(2, Assoc::Left, op("/"), |a, _, b| a / b)
+            .postfix(5, op("!"), |n, _| (1..=n).product())
+    }
+
+    fn atom(input: &mut &str) -> ModalResult<i64> {
+        alt((
+            dec_int,
+            delimited(op("("), cut_err(arithmetic()), cut_err(')')),
+        ))
+        .context(StrContext::Label("expression"))
+        .context(StrContext::Expected(StrContextValue::Description("number")))
+        .parse_next(input)
+    }
+
+    fn eval(input: &str) -> Result<i64, ParseError> {
+        delimited(multispace0, arithmetic(), multispace0)
+            .parse(input)
+            .map_err(ParseError::from)
+    }
+
+    #[test]
+    fn pratt_should_respect_precedence() -> Result<(), ParseError> {
+        assert_eq!(eval("1 + 2 * 3")?, 7);
+        assert_eq!(eval("(1 + 2) * 3")?, 9);
+        assert_eq!(eval("10 - 4 - 3")?, 3);
+        assert_eq!(eval("2 * 3!")?, 12);
+        assert_eq!(eval("-2 * 3 + 1")?, -5);
+        Ok(())
+    }
+
+    #[test]
+    fn pratt_should_respect_associativity() -> Result<(), ParseError> {
+        assert_eq!(eval("2 ** 3 ** 2")?, 512);
+        assert_eq!(eval("2 ** 3 * 2")?, 16);
+        assert_eq!(eval("-2 ** 2")?, -4);
+        assert_eq!(eval("3! !")?, 720);
+        Ok(())
+    }
+
+    #[test]
+    fn pratt_should_require_operands() {
+        let err = eval("1 + 2 *").unwrap_err();
+        assert_eq!(err.offset(), 7);
+        assert_eq!(err.message(), "expected number while parsing expression");
+        let err = eval("- ").unwrap_err();
+        assert_eq!(err.offset(), 2);
+        assert!(eval("1 2").is_err());
+    }
+}
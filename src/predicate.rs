@@ -0,0 +1,428 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::net::IpAddr;
+
+use regex::Regex;
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{digit1, multispace0};
+use winnow::combinator::{alt, cut_err, delimited, not, opt, preceded, repeat, separated};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::{any, none_of, one_of, take_while};
+
+use crate::ParseError;
+use crate::ipnet::{self, IpNet};
+use crate::pratt::{Assoc, Pratt};
+
+/// A compiled condition such as
+/// `status >= 500 && !(path =~ "^/health") || addr in [10.0.0.0/8, ::1]`.
+#[derive(Debug, Clone)]
+pub enum Predicate {
+    And(Box<Predicate>, Box<Predicate>),
+    Or(Box<Predicate>, Box<Predicate>),
+    Not(Box<Predicate>),
+    Compare {
+        field: String,
+        op: CmpOp,
+        value: Literal,
+    },
+    Matches {
+        field: String,
+        regex: Regex,
+        negated: bool,
+    },
+    In {
+        field: String,
+        values: Vec<Literal>,
+        negated: bool,
+    },
+    /// A bare field name: true when the field is present and truthy.
+    Truthy(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    /// An address or CIDR block; equality against an address means
+    /// containment.
+    Net(IpNet),
+}
+
+/// A field value handed out by a [`Resolver`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value<'a> {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(Cow<'a, str>),
+    Ip(IpAddr),
+}
+
+/// Looks up fields by name for [`Predicate::eval`]. Each record type
+/// decides what its names are; `None` means the field does not exist, and
+/// every condition on a missing field is false.
+pub trait Resolver {
+    fn resolve(&self, field: &str) -> Option<Value<'_>>;
+}
+
+impl Predicate {
+    pub fn eval<R: Resolver + ?Sized>(&self, record: &R) -> bool {
+        match self {
+            Predicate::And(a, b) => a.eval(record) && b.eval(record),
+            Predicate::Or(a, b) => a.eval(record) || b.eval(record),
+            Predicate::Not(p) => !p.eval(record),
+            Predicate::Compare { field, op, value } => {
+                record.resolve(field).is_some_and(|v| match op {
+                    CmpOp::Eq => equals(&v, value),
+                    CmpOp::Ne => !equals(&v, value),
+                    CmpOp::Lt => compare(&v, value) == Some(Ordering::Less),
+                    CmpOp::Le => compare(&v, value).is_some_and(Ordering::is_le),
+                    CmpOp::Gt => compare(&v, value) == Some(Ordering::Greater),
+                    CmpOp::Ge => compare(&v, value).is_some_and(Ordering::is_ge),
+                })
+            }
+            Predicate::Matches {
+                field,
+                regex,
+                negated,
+            } => record
+                .resolve(field)
+                .is_some_and(|v| text(&v).is_some_and(|text| regex.is_match(&text) != *negated)),
+            Predicate::In {
+                field,
+                values,
+                negated,
+            } => record
+                .resolve(field)
+                .is_some_and(|v| values.iter().any(|lit| equals(&v, lit)) != *negated),
+            Predicate::Truthy(field) => record.resolve(field).is_some_and(|v| match v {
+                Value::Null => false,
+                Value::Bool(b) => b,
+                Value::Number(n) => n != 0.0,
+                Value::String(s) => !s.is_empty(),
+                Value::Ip(_) => true,
+            }),
+        }
+    }
+
+    /// Every field name the predicate reads, in order of appearance, so a
+    /// caller can reject names its records do not have.
+    pub fn fields(&self) -> Vec<&str> {
+        match self {
+            Predicate::And(a, b) | Predicate::Or(a, b) => {
+                let mut fields = a.fields();
+                fields.extend(b.fields());
+                fields
+            }
+            Predicate::Not(p) => p.fields(),
+            Predicate::Compare { field, .. }
+            | Predicate::Matches { field, .. }
+            | Predicate::In { field, .. }
+            | Predicate::Truthy(field) => vec![field.as_str()],
+        }
+    }
+}
+
+impl std::str::FromStr for Predicate {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_predicate(s)
+    }
+}
+
+/// Parses and compiles a predicate. `&&` binds tighter than `||`, and `!`
+/// tighter than both.
+pub fn parse_predicate(input: &str) -> Result<Predicate, ParseError> {
+    delimited(multispace0, expr, multispace0)
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+fn equals(value: &Value, literal: &Literal) -> bool {
+    match (value, literal) {
+        (Value::Ip(ip), Literal::Net(net)) => net.contains(*ip),
+        (Value::String(s), Literal::Net(net)) => s.parse().is_ok_and(|ip| net.contains(ip)),
+        _ => compare(value, literal) == Some(Ordering::Equal),
+    }
+}
+
+/// Orders a value against a literal of a compatible type. Strings that
+/// look like numbers compare numerically with numbers.
+fn compare(value: &Value, literal: &Literal) -> Option<Ordering> {
+    match (value, literal) {
+        (Value::Null, Literal::Null) => Some(Ordering::Equal),
+        (Value::Bool(a), Literal::Bool(b)) => Some(a.cmp(b)),
+        (Value::Number(a), Literal::Number(b)) => a.partial_cmp(b),
+        (Value::String(a), Literal::Number(b)) => a.trim().parse::<f64>().ok()?.partial_cmp(b),
+        (Value::Number(a), Literal::String(b)) => a.partial_cmp(&b.trim().parse::<f64>().ok()?),
+        (Value::String(a), Literal::String(b)) => Some(a.as_ref().cmp(b.as_str())),
+        (Value::Ip(a), Literal::String(b)) => b.parse::<IpAddr>().ok().map(|b| a.cmp(&b)),
+        _ => None,
+    }
+}
+
+/// The text a regex is matched against.
+fn text<'a>(value: &'a Value) -> Option<Cow<'a, str>> {
+    match value {
+        Value::Null => None,
+        Value::Bool(b) => Some(b.to_string().into()),
+        Value::Number(n) => Some(n.to_string().into()),
+        Value::String(s) => Some(Cow::Borrowed(s.as_ref())),
+        Value::Ip(ip) => Some(ip.to_string().into()),
+    }
+}
+
+fn expr(input: &mut &str) -> ModalResult<Predicate> {
+    Pratt::new(atom)
+        .prefix(3, ('!', not('='), not('~'), multispace0), |_, p| {
+            Predicate::Not(Box::new(p))
+        })
+        .infix(
+            1,
+            Assoc::Left,
+            (multispace0, "||", multispace0),
+            |a, _, b| Predicate::Or(Box::new(a), Box::new(b)),
+        )
+        .infix(
+            2,
+            Assoc::Left,
+            (multispace0, "&&", multispace0),
+            |a, _, b| Predicate::And(Box::new(a), Box::new(b)),
+        )
+        .parse_next(input)
+}
+
+fn atom(input: &mut &str) -> ModalResult<Predicate> {
+    alt((
+        delimited(
+            ('(', multispace0),
+            cut_err(expr),
+            cut_err((multispace0, ')'))
+                .context(StrContext::Expected(StrContextValue::CharLiteral(')'))),
+        ),
+        condition,
+    ))
+    .parse_next(input)
+}
+
+fn condition(input: &mut &str) -> ModalResult<Predicate> {
+    let field = field
+        .context(StrContext::Label("condition"))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "field name",
+        )))
+        .parse_next(input)?
+        .to_string();
+    let checkpoint = *input;
+    multispace0.parse_next(input)?;
+    if let Some(negated) = opt(alt(("=~".value(false), "!~".value(true)))).parse_next(input)? {
+        multispace0.parse_next(input)?;
+        let regex = cut_err(string.try_map(|s| Regex::new(&s)))
+            .context(StrContext::Label("regular expression"))
+            .parse_next(input)?;
+        return Ok(Predicate::Matches {
+            field,
+            regex,
+            negated,
+        });
+    }
+    if let Some(negated) = opt(alt((
+        ("not", multispace0, "in").value(true),
+        "in".value(false),
+    )))
+    .parse_next(input)?
+    {
+        multispace0.parse_next(input)?;
+        let values = cut_err(delimited(
+            ('[', multispace0),
+            separated(0.., literal, (multispace0, ',', multispace0)),
+            (multispace0, opt((',', multispace0)), ']'),
+        ))
+        .context(StrContext::Label("list"))
+        .parse_next(input)?;
+        return Ok(Predicate::In {
+            field,
+            values,
+            negated,
+        });
+    }
+    if let Some(op) = opt(alt((
+        "==".value(CmpOp::Eq),
+        "!=".value(CmpOp::Ne),
+        "<=".value(CmpOp::Le),
+        ">=".value(CmpOp::Ge),
+        "<".value(CmpOp::Lt),
+        ">".value(CmpOp::Gt),
+    )))
+    .parse_next(input)?
+    {
+        multispace0.parse_next(input)?;
+        let value = cut_err(literal).parse_next(input)?;
+        return Ok(Predicate::Compare { field, op, value });
+    }
+    *input = checkpoint;
+    Ok(Predicate::Truthy(field))
+}
+
+/// `status`, `request.headers.host`, `items.0`.
+fn field<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    (
+        one_of(|c: char| c.is_ascii_alphabetic() || c == '_'),
+        take_while(0.., |c: char| {
+            c.is_ascii_alphanumeric() || c == '_' || c == '.'
+        }),
+    )
+        .take()
+        .verify(|name: &str| !matches!(name, "true" | "false" | "null" | "in" | "not"))
+        .parse_next(input)
+}
+
+fn literal(input: &mut &str) -> ModalResult<Literal> {
+    alt((
+        string.map(Literal::String),
+        ipnet::cidr.map(Literal::Net),
+        number.map(Literal::Number),
+        keyword("true").value(Literal::Bool(true)),
+        keyword("false").value(Literal::Bool(false)),
+        keyword("null").value(Literal::Null),
+    ))
+    .context(StrContext::Label("literal"))
+    .context(StrContext::Expected(StrContextValue::Description(
+        "a string, number, boolean, null or IP network",
+    )))
+    .parse_next(input)
+}
+
+fn keyword<'i>(
+    word: &'static str,
+) -> impl Parser<&'i str, &'i str, winnow::error::ErrMode<winnow::error::ContextError>> {
+    (
+        word,
+        not(one_of(|c: char| c.is_ascii_alphanumeric() || c == '_')),
+    )
+        .map(|(w, _)| w)
+}
+
+fn number(input: &mut &str) -> ModalResult<f64> {
+    (
+        opt('-'),
+        digit1,
+        opt(('.', digit1)),
+        opt((one_of(['e', 'E']), opt(one_of(['+', '-'])), digit1)),
+    )
+        .take()
+        .parse_to()
+        .parse_next(input)
+}
+
+/// A single- or double-quoted string with backslash escapes.
+fn string(input: &mut &str) -> ModalResult<String> {
+    let quote = one_of(['"', '\'']).parse_next(input)?;
+    let chars: String = cut_err(repeat(
+        0..,
+        alt((
+            preceded(
+                '\\',
+                any.map(|c| match c {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    c => c,
+                }),
+            ),
+            none_of(move |c: char| c == quote || c == '\\'),
+        )),
+    ))
+    .parse_next(input)?;
+    cut_err(quote)
+        .context(StrContext::Expected(StrContextValue::Description(
+            "closing quote",
+        )))
+        .parse_next(input)?;
+    Ok(chars)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    struct Record(HashMap<&'static str, Value<'static>>);
+
+    impl Resolver for Record {
+        fn resolve(&self, field: &str) -> Option<Value<'_>> {
+            self.0.get(field).cloned()
+        }
+    }
+
+    fn record() -> Record {
+        Record(HashMap::from([
+            ("status", Value::Number(503.0)),
+            ("path", Value::String("/api/users?id=7".into())),
+            ("addr", Value::Ip("10.1.2.3".parse().unwrap())),
+            ("cached", Value::Bool(false)),
+            ("bytes", Value::String("1024".into())),
+        ]))
+    }
+
+    #[test]
+    fn predicate_should_evaluate() -> Result<(), ParseError> {
+        let record = record();
+        let cases = [
+            ("status >= 500", true),
+            ("status == 503 && path =~ '^/api/'", true),
+            ("status < 500 || !cached", true),
+            ("!(status >= 500)", false),
+            ("addr in [192.168.0.0/16, 10.0.0.0/8]", true),
+            ("addr == 10.1.2.3", true),
+            ("addr not in [10.0.0.0/8]", false),
+            ("status in [200, 204]", false),
+            ("path !~ \"users\"", false),
+            ("bytes > 1000", true),
+            ("missing == 1 || missing != 1", false),
+            ("cached || status != 503", false),
+            ("status >= 500 && status < 600 && !(path =~ 'health')", true),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_predicate(input)?.eval(&record), expected, "{input}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn predicate_should_respect_precedence() -> Result<(), ParseError> {
+        let p = parse_predicate("a || b && !c")?;
+        let Predicate::Or(left, right) = &p else {
+            panic!("expected ||, got {p:?}");
+        };
+        assert!(matches!(**left, Predicate::Truthy(ref f) if f == "a"));
+        assert!(matches!(**right, Predicate::And(_, _)));
+        assert_eq!(p.fields(), ["a", "b", "c"]);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_predicate_should_report_errors() {
+        assert_eq!(parse_predicate("status >= ").unwrap_err().offset(), 10);
+        assert_eq!(parse_predicate("(a && b").unwrap_err().offset(), 7);
+        assert!(parse_predicate("path =~ '('").is_err());
+        assert!(parse_predicate("a in [1, 2").is_err());
+        assert!(parse_predicate("a &&").is_err());
+        assert!(parse_predicate("== 1").is_err());
+    }
+}
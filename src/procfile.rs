@@ -0,0 +1,189 @@
+use crate::ParseError;
+use crate::shellwords::parse_shellwords;
+
+/// A Procfile: one process type per line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Procfile {
+    pub processes: Vec<Process>,
+}
+
+/// `name: command`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Process {
+    /// 1-based line number.
+    pub line: usize,
+    pub name: String,
+    /// The command as written, trimmed.
+    pub command: String,
+}
+
+impl Procfile {
+    /// The process type called `name`. A later line wins over an earlier
+    /// one, as in foreman.
+    pub fn get(&self, name: &str) -> Option<&Process> {
+        self.processes.iter().rev().find(|p| p.name == name)
+    }
+}
+
+impl Process {
+    /// The command split into arguments, without expanding anything.
+    pub fn argv(&self) -> Result<Vec<String>, ParseError> {
+        parse_shellwords(&self.command)
+    }
+
+    /// The command with `$NAME` and `${NAME}` replaced by what `env`
+    /// returns for them (nothing when it returns `None`), split into
+    /// arguments. Variables in single quotes and after a backslash are left
+    /// alone, as the shell would.
+    pub fn argv_with_env(
+        &self,
+        env: &mut dyn FnMut(&str) -> Option<String>,
+    ) -> Result<Vec<String>, ParseError> {
+        parse_shellwords(&expand(&self.command, env))
+    }
+}
+
+/// Parses a Procfile. Blank lines and `#` comments are skipped; every other
+/// line needs a name of letters, digits, `_` or `-`, a colon and a
+/// command.
+pub fn parse_procfile(input: &str) -> Result<Procfile, ParseError> {
+    let mut procfile = Procfile::default();
+    let mut offset = 0;
+    for (index, raw) in input.split_inclusive('\n').enumerate() {
+        let start = offset;
+        offset += raw.len();
+        let line = raw.trim_end_matches(['\n', '\r']);
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let at = start + (line.len() - trimmed.len());
+        let Some((name, command)) = trimmed.split_once(':') else {
+            return Err(ParseError::new(at + trimmed.len(), "expected `:`"));
+        };
+        let name = name.trim_end();
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid {
+            return Err(ParseError::new(at, "expected a process name"));
+        }
+        let command = command.trim();
+        if command.is_empty() {
+            return Err(ParseError::new(at + trimmed.len(), "expected a command"));
+        }
+        procfile.processes.push(Process {
+            line: index + 1,
+            name: name.to_string(),
+            command: command.to_string(),
+        });
+    }
+    Ok(procfile)
+}
+
+fn expand(command: &str, env: &mut dyn FnMut(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(command.len());
+    let mut chars = command.chars().peekable();
+    let is_name = |c: &char| c.is_ascii_alphanumeric() || *c == '_';
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                out.push(c);
+                out.extend(chars.next());
+            }
+            '\'' => {
+                out.push(c);
+                for c in chars.by_ref() {
+                    out.push(c);
+                    if c == '\'' {
+                        break;
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'{') => {
+                chars.next();
+                let name: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                out.push_str(&env(&name).unwrap_or_default());
+            }
+            '$' if chars.peek().is_some_and(is_name) => {
+                let mut name = String::new();
+                while let Some(c) = chars.next_if(is_name) {
+                    name.push(c);
+                }
+                out.push_str(&env(&name).unwrap_or_default());
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_procfile_should_work() -> Result<(), ParseError> {
+        let procfile = parse_procfile(
+            "# processes\nweb: bundle exec puma -C config/puma.rb\n\nworker:   bundle exec sidekiq -q \"default low\"\r\nrelease-task: rake db:migrate\n",
+        )?;
+        assert_eq!(procfile.processes.len(), 3);
+        let web = procfile.get("web").unwrap();
+        assert_eq!(web.line, 2);
+        assert_eq!(
+            web.argv()?,
+            ["bundle", "exec", "puma", "-C", "config/puma.rb"]
+        );
+        assert_eq!(
+            procfile.get("worker").unwrap().argv()?,
+            ["bundle", "exec", "sidekiq", "-q", "default low"]
+        );
+        assert_eq!(
+            procfile.get("release-task").unwrap().command,
+            "rake db:migrate"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn argv_with_env_should_expand_variables() -> Result<(), ParseError> {
+        let procfile = parse_procfile(
+            "web: serve --port $PORT --root \"${ROOT}/public\" '$PORT' \\$HOME $UNSET\n",
+        )?;
+        let argv = procfile.processes[0].argv_with_env(&mut |name| match name {
+            "PORT" => Some("5000".to_string()),
+            "ROOT" => Some("/app dir".to_string()),
+            _ => None,
+        })?;
+        assert_eq!(
+            argv,
+            [
+                "serve",
+                "--port",
+                "5000",
+                "--root",
+                "/app dir/public",
+                "$PORT",
+                "$HOME"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_procfile_should_report_errors() {
+        assert_eq!(
+            parse_procfile("web: a\nworker b\n").unwrap_err().offset(),
+            15
+        );
+        assert_eq!(
+            parse_procfile("web: a\n  bad name: b\n")
+                .unwrap_err()
+                .offset(),
+            9
+        );
+        assert!(parse_procfile("web:   \n").is_err());
+        assert!(parse_procfile(": cmd\n").is_err());
+    }
+}
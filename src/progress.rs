@@ -0,0 +1,64 @@
+use std::io::{self, Read};
+
+/// Reader adapter that reports the running total of bytes read, so callers
+/// can drive progress bars without knowing what sits on top of the reader.
+pub struct ProgressReader<R, F> {
+    inner: R,
+    bytes_read: u64,
+    on_progress: F,
+}
+
+impl<R, F> ProgressReader<R, F>
+where
+    F: FnMut(u64),
+{
+    pub fn new(inner: R, on_progress: F) -> Self {
+        Self {
+            inner,
+            bytes_read: 0,
+            on_progress,
+        }
+    }
+
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R, F> Read for ProgressReader<R, F>
+where
+    R: Read,
+    F: FnMut(u64),
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n > 0 {
+            self.bytes_read += n as u64;
+            (self.on_progress)(self.bytes_read);
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader};
+
+    use super::*;
+
+    #[test]
+    fn progress_reader_should_report_totals() -> io::Result<()> {
+        let mut seen = Vec::new();
+        let reader = ProgressReader::new(&b"one\ntwo\n"[..], |n| seen.push(n));
+        let lines = BufReader::with_capacity(4, reader)
+            .lines()
+            .collect::<io::Result<Vec<_>>>()?;
+        assert_eq!(lines, ["one", "two"]);
+        assert_eq!(seen, [4, 8]);
+        Ok(())
+    }
+}
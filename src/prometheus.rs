@@ -0,0 +1,533 @@
+use std::fmt;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{dec_int, space0, space1};
+use winnow::combinator::{alt, cut_err, delimited, opt, preceded, repeat, separated, terminated};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::{any, none_of, one_of, rest, take_till, take_while};
+
+use crate::ParseError;
+
+/// The samples of one metric with its `# HELP` and `# TYPE` metadata.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricFamily {
+    pub name: String,
+    pub help: Option<String>,
+    pub kind: MetricType,
+    pub samples: Vec<Sample>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricType {
+    Counter,
+    Gauge,
+    Histogram,
+    Summary,
+    Untyped,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    /// The sample's own name, which for histograms and summaries carries a
+    /// `_bucket`, `_sum` or `_count` suffix.
+    pub name: String,
+    pub labels: Vec<(String, String)>,
+    pub value: f64,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: Option<i64>,
+}
+
+/// One labelled series of a histogram family.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    /// The series' labels, without `le`.
+    pub labels: Vec<(String, String)>,
+    /// `(upper bound, cumulative count)`, as listed.
+    pub buckets: Vec<(f64, f64)>,
+    pub sum: Option<f64>,
+    pub count: Option<f64>,
+}
+
+/// One labelled series of a summary family.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    /// The series' labels, without `quantile`.
+    pub labels: Vec<(String, String)>,
+    /// `(quantile, value)`, as listed.
+    pub quantiles: Vec<(f64, f64)>,
+    pub sum: Option<f64>,
+    pub count: Option<f64>,
+}
+
+impl MetricType {
+    fn suffixes(self) -> &'static [&'static str] {
+        match self {
+            MetricType::Histogram => &["_bucket", "_sum", "_count"],
+            MetricType::Summary => &["_sum", "_count"],
+            _ => &[],
+        }
+    }
+}
+
+impl Sample {
+    pub fn label(&self, name: &str) -> Option<&str> {
+        self.labels
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+impl MetricFamily {
+    fn owns(&self, sample: &str) -> bool {
+        sample == self.name
+            || self
+                .kind
+                .suffixes()
+                .iter()
+                .any(|suffix| sample.strip_suffix(suffix) == Some(&self.name))
+    }
+
+    /// The family's series grouped by labels; empty unless it is a
+    /// histogram.
+    pub fn histograms(&self) -> Vec<Histogram> {
+        if self.kind != MetricType::Histogram {
+            return Vec::new();
+        }
+        let mut series: Vec<Histogram> = Vec::new();
+        for (suffix, labels, sample) in self.series("le") {
+            let index = match series.iter().position(|h| h.labels == labels) {
+                Some(index) => index,
+                None => {
+                    series.push(Histogram {
+                        labels,
+                        buckets: Vec::new(),
+                        sum: None,
+                        count: None,
+                    });
+                    series.len() - 1
+                }
+            };
+            let histogram = &mut series[index];
+            match suffix {
+                "_bucket" => {
+                    if let Some(le) = sample.label("le").and_then(|le| parse_float(le).ok()) {
+                        histogram.buckets.push((le, sample.value));
+                    }
+                }
+                "_sum" => histogram.sum = Some(sample.value),
+                "_count" => histogram.count = Some(sample.value),
+                _ => {}
+            }
+        }
+        series
+    }
+
+    /// The family's series grouped by labels; empty unless it is a
+    /// summary.
+    pub fn summaries(&self) -> Vec<Summary> {
+        if self.kind != MetricType::Summary {
+            return Vec::new();
+        }
+        let mut series: Vec<Summary> = Vec::new();
+        for (suffix, labels, sample) in self.series("quantile") {
+            let index = match series.iter().position(|s| s.labels == labels) {
+                Some(index) => index,
+                None => {
+                    series.push(Summary {
+                        labels,
+                        quantiles: Vec::new(),
+                        sum: None,
+                        count: None,
+                    });
+                    series.len() - 1
+                }
+            };
+            let summary = &mut series[index];
+            match suffix {
+                "" => {
+                    if let Some(q) = sample.label("quantile").and_then(|q| parse_float(q).ok()) {
+                        summary.quantiles.push((q, sample.value));
+                    }
+                }
+                "_sum" => summary.sum = Some(sample.value),
+                "_count" => summary.count = Some(sample.value),
+                _ => {}
+            }
+        }
+        series
+    }
+
+    /// Each sample with its name suffix and its labels minus `except`.
+    fn series(&self, except: &str) -> impl Iterator<Item = (&str, Vec<(String, String)>, &Sample)> {
+        self.samples.iter().map(move |sample| {
+            let suffix = &sample.name[self.name.len()..];
+            let labels = sample
+                .labels
+                .iter()
+                .filter(|(n, _)| n != except)
+                .cloned()
+                .collect();
+            (suffix, labels, sample)
+        })
+    }
+}
+
+impl fmt::Display for MetricType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MetricType::Counter => "counter",
+            MetricType::Gauge => "gauge",
+            MetricType::Histogram => "histogram",
+            MetricType::Summary => "summary",
+            MetricType::Untyped => "untyped",
+        })
+    }
+}
+
+/// Writes the family back in the text format, `# HELP` and `# TYPE` first.
+impl fmt::Display for MetricFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(help) = &self.help {
+            let help = help.replace('\\', "\\\\").replace('\n', "\\n");
+            writeln!(f, "# HELP {} {help}", self.name)?;
+        }
+        writeln!(f, "# TYPE {} {}", self.name, self.kind)?;
+        for sample in &self.samples {
+            writeln!(f, "{sample}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Sample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        if !self.labels.is_empty() {
+            f.write_str("{")?;
+            for (i, (name, value)) in self.labels.iter().enumerate() {
+                let sep = if i == 0 { "" } else { "," };
+                let value = value
+                    .replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+                    .replace('\n', "\\n");
+                write!(f, "{sep}{name}=\"{value}\"")?;
+            }
+            f.write_str("}")?;
+        }
+        match self.value {
+            v if v.is_nan() => f.write_str(" NaN")?,
+            v if v == f64::INFINITY => f.write_str(" +Inf")?,
+            v if v == f64::NEG_INFINITY => f.write_str(" -Inf")?,
+            v => write!(f, " {v}")?,
+        }
+        if let Some(timestamp) = self.timestamp {
+            write!(f, " {timestamp}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the Prometheus text exposition format. A sample belongs to the
+/// family declared for its name, or for its name minus `_bucket`, `_sum`
+/// or `_count` when that family is a histogram or summary; samples
+/// without metadata get an untyped family of their own. Errors point into
+/// `input`.
+pub fn parse_prometheus(input: &str) -> Result<Vec<MetricFamily>, ParseError> {
+    let mut families: Vec<MetricFamily> = Vec::new();
+    let mut offset = 0;
+    for raw in input.split_inclusive('\n') {
+        let start = offset;
+        offset += raw.len();
+        let line = raw.trim_end_matches(['\n', '\r']);
+        if line.trim().is_empty() {
+            continue;
+        }
+        let parsed = entry.parse(line).map_err(|e| {
+            let e = ParseError::from(e);
+            ParseError::new(start + e.offset(), e.message())
+        })?;
+        let error = |message: String| ParseError::new(start, message);
+        match parsed {
+            Entry::Comment => {}
+            Entry::Help(name, help) => {
+                let family = family(&mut families, &name);
+                if family.help.is_some() {
+                    return Err(error(format!("second `HELP` for `{name}`")));
+                }
+                family.help = Some(help);
+            }
+            Entry::Type(name, kind) => {
+                let family = family(&mut families, &name);
+                if !family.samples.is_empty() {
+                    return Err(error(format!("`TYPE` for `{name}` after its samples")));
+                }
+                if family.kind != MetricType::Untyped {
+                    return Err(error(format!("second `TYPE` for `{name}`")));
+                }
+                family.kind = kind;
+            }
+            Entry::Sample(sample) => {
+                match families.iter_mut().rev().find(|f| f.owns(&sample.name)) {
+                    Some(family) => family.samples.push(sample),
+                    None => families.push(MetricFamily {
+                        name: sample.name.clone(),
+                        help: None,
+                        kind: MetricType::Untyped,
+                        samples: vec![sample],
+                    }),
+                }
+            }
+        }
+    }
+    Ok(families)
+}
+
+fn family<'a>(families: &'a mut Vec<MetricFamily>, name: &str) -> &'a mut MetricFamily {
+    match families.iter().position(|f| f.name == name) {
+        Some(index) => &mut families[index],
+        None => {
+            families.push(MetricFamily {
+                name: name.to_string(),
+                help: None,
+                kind: MetricType::Untyped,
+                samples: Vec::new(),
+            });
+            families.last_mut().expect("just pushed")
+        }
+    }
+}
+
+enum Entry {
+    Help(String, String),
+    Type(String, MetricType),
+    Sample(Sample),
+    Comment,
+}
+
+fn entry(input: &mut &str) -> ModalResult<Entry> {
+    alt((
+        preceded(
+            ('#', space1, "HELP", space1),
+            cut_err((terminated(metric_name, space0), help_text)),
+        )
+        .map(|(name, help)| Entry::Help(name.to_string(), help)),
+        preceded(
+            ('#', space1, "TYPE", space1),
+            cut_err((terminated(metric_name, space1), metric_type, space0)),
+        )
+        .map(|(name, kind, _)| Entry::Type(name.to_string(), kind)),
+        ('#', rest).map(|_| Entry::Comment),
+        sample.map(Entry::Sample),
+    ))
+    .parse_next(input)
+}
+
+fn metric_name<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    (
+        one_of(|c: char| c.is_ascii_alphabetic() || c == '_' || c == ':'),
+        take_while(0.., |c: char| {
+            c.is_ascii_alphanumeric() || c == '_' || c == ':'
+        }),
+    )
+        .take()
+        .context(StrContext::Label("metric name"))
+        .parse_next(input)
+}
+
+fn help_text(input: &mut &str) -> ModalResult<String> {
+    repeat(
+        0..,
+        alt((
+            preceded('\\', any).map(|c| match c {
+                'n' => "\n".to_string(),
+                '\\' => "\\".to_string(),
+                c => format!("\\{c}"),
+            }),
+            none_of('\\').map(String::from),
+        )),
+    )
+    .fold(String::new, |mut s, part| {
+        s.push_str(&part);
+        s
+    })
+    .parse_next(input)
+}
+
+fn metric_type(input: &mut &str) -> ModalResult<MetricType> {
+    alt((
+        "counter".value(MetricType::Counter),
+        "gauge".value(MetricType::Gauge),
+        "histogram".value(MetricType::Histogram),
+        "summary".value(MetricType::Summary),
+        "untyped".value(MetricType::Untyped),
+    ))
+    .context(StrContext::Label("metric type"))
+    .context(StrContext::Expected(StrContextValue::Description(
+        "counter, gauge, histogram, summary or untyped",
+    )))
+    .parse_next(input)
+}
+
+fn sample(input: &mut &str) -> ModalResult<Sample> {
+    let name = metric_name.parse_next(input)?;
+    let labels = opt(delimited(
+        ('{', space0),
+        terminated(
+            separated(0.., label, (space0, ',', space0)),
+            (space0, opt(','), space0),
+        ),
+        cut_err('}').context(StrContext::Expected(StrContextValue::CharLiteral('}'))),
+    ))
+    .parse_next(input)?
+    .unwrap_or_default();
+    let value = preceded(
+        space1,
+        cut_err(take_till(1.., [' ', '\t']).try_map(parse_float)),
+    )
+    .context(StrContext::Label("sample value"))
+    .context(StrContext::Expected(StrContextValue::Description(
+        "a number",
+    )))
+    .parse_next(input)?;
+    let timestamp = opt(preceded(space1, dec_int)).parse_next(input)?;
+    space0.parse_next(input)?;
+    Ok(Sample {
+        name: name.to_string(),
+        labels,
+        value,
+        timestamp,
+    })
+}
+
+fn label(input: &mut &str) -> ModalResult<(String, String)> {
+    let name = (
+        one_of(|c: char| c.is_ascii_alphabetic() || c == '_'),
+        take_while(0.., |c: char| c.is_ascii_alphanumeric() || c == '_'),
+    )
+        .take()
+        .parse_next(input)?;
+    let value = preceded(
+        (space0, '=', space0),
+        cut_err(delimited(
+            '"',
+            repeat(
+                0..,
+                alt((
+                    preceded('\\', any).map(|c| if c == 'n' { '\n' } else { c }),
+                    none_of(['"', '\\']),
+                )),
+            )
+            .fold(String::new, |mut s, c| {
+                s.push(c);
+                s
+            }),
+            '"',
+        ))
+        .context(StrContext::Label("label value"))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "a quoted string",
+        ))),
+    )
+    .parse_next(input)?;
+    Ok((name.to_string(), value))
+}
+
+/// Go's float syntax: `+Inf`, `-Inf` and `NaN` as well as numbers.
+fn parse_float(s: &str) -> Result<f64, std::num::ParseFloatError> {
+    s.parse()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPOSITION: &str = r#"# HELP http_requests_total The total number of HTTP requests.
+# TYPE http_requests_total counter
+http_requests_total{method="post",code="200"} 1027 1395066363000
+http_requests_total{method="post",code="400",} 3 1395066363000
+
+# A comment that is not metadata.
+msdos_file_access_time_seconds{path="C:\\DIR\\FILE.TXT",error="Cannot find file:\n\"FILE.TXT\""} 1.458255915e9
+
+# HELP http_request_duration_seconds A histogram of the request duration.
+# TYPE http_request_duration_seconds histogram
+http_request_duration_seconds_bucket{le="0.05"} 24054
+http_request_duration_seconds_bucket{le="0.5"} 129389
+http_request_duration_seconds_bucket{le="+Inf"} 144320
+http_request_duration_seconds_sum 53423
+http_request_duration_seconds_count 144320
+
+# TYPE rpc_duration_seconds summary
+rpc_duration_seconds{quantile="0.5"} 4773
+rpc_duration_seconds{quantile="0.99"} 76656
+rpc_duration_seconds_sum 1.7560473e+07
+rpc_duration_seconds_count 2693
+process_start_time_seconds -Inf
+"#;
+
+    #[test]
+    fn parse_prometheus_should_group_families() -> Result<(), ParseError> {
+        let families = parse_prometheus(EXPOSITION)?;
+        let names: Vec<_> = families.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "http_requests_total",
+                "msdos_file_access_time_seconds",
+                "http_request_duration_seconds",
+                "rpc_duration_seconds",
+                "process_start_time_seconds"
+            ]
+        );
+        let requests = &families[0];
+        assert_eq!(requests.kind, MetricType::Counter);
+        assert_eq!(
+            requests.help.as_deref(),
+            Some("The total number of HTTP requests.")
+        );
+        assert_eq!(requests.samples[1].label("code"), Some("400"));
+        assert_eq!(requests.samples[0].timestamp, Some(1395066363000));
+        let msdos = &families[1];
+        assert_eq!(msdos.kind, MetricType::Untyped);
+        assert_eq!(msdos.samples[0].label("path"), Some("C:\\DIR\\FILE.TXT"));
+        assert_eq!(
+            msdos.samples[0].label("error"),
+            Some("Cannot find file:\n\"FILE.TXT\"")
+        );
+        assert_eq!(families[4].samples[0].value, f64::NEG_INFINITY);
+        Ok(())
+    }
+
+    #[test]
+    fn histograms_and_summaries_should_collect_series() -> Result<(), ParseError> {
+        let families = parse_prometheus(EXPOSITION)?;
+        let histograms = families[2].histograms();
+        assert_eq!(histograms.len(), 1);
+        assert_eq!(
+            histograms[0].buckets,
+            [(0.05, 24054.0), (0.5, 129389.0), (f64::INFINITY, 144320.0)]
+        );
+        assert_eq!(histograms[0].count, Some(144320.0));
+        let summaries = families[3].summaries();
+        assert_eq!(summaries[0].quantiles, [(0.5, 4773.0), (0.99, 76656.0)]);
+        assert_eq!(summaries[0].sum, Some(1.7560473e7));
+        assert!(families[0].histograms().is_empty());
+
+        let written: String = families.iter().map(|f| f.to_string()).collect();
+        assert_eq!(parse_prometheus(&written)?, families);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_prometheus_should_report_errors() {
+        let err = parse_prometheus("a 1\nb{x=\"1\" 2\n").unwrap_err();
+        assert_eq!(err.offset(), 12);
+        assert!(parse_prometheus("# TYPE a countr\n").is_err());
+        assert!(parse_prometheus("a 1\n# TYPE a gauge\n").is_err());
+        assert!(parse_prometheus("# TYPE a gauge\n# TYPE a gauge\n").is_err());
+        assert!(parse_prometheus("a one\n").is_err());
+        assert!(parse_prometheus("a{x=1} 1\n").is_err());
+    }
+}
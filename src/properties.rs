@@ -0,0 +1,338 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ParseError;
+
+/// A Java `.properties` file, line by line, so that it can be written back
+/// with its comments and layout.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Properties {
+    pub lines: Vec<Line>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Line {
+    Property(Property),
+    /// A `#` or `!` comment line, as written.
+    Comment(String),
+    /// A line of nothing but white space, as written.
+    Blank(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Property {
+    pub key: String,
+    pub value: String,
+    /// The source lines, continuations included. Written back as long as
+    /// they still say `key` and `value`.
+    raw: Option<String>,
+}
+
+impl Property {
+    pub fn new(key: impl Into<String>, value: impl Into<String>) -> Self {
+        Property {
+            key: key.into(),
+            value: value.into(),
+            raw: None,
+        }
+    }
+}
+
+impl Properties {
+    pub fn iter(&self) -> impl Iterator<Item = &Property> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Property(property) => Some(property),
+            _ => None,
+        })
+    }
+
+    /// The value of `key`. Like `java.util.Properties`, a later line
+    /// overrides an earlier one.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.iter()
+            .filter(|p| p.key == key)
+            .last()
+            .map(|p| p.value.as_str())
+    }
+
+    /// Changes the line that sets `key`, or appends one.
+    pub fn set(&mut self, key: &str, value: &str) {
+        let existing = self.lines.iter_mut().rev().find_map(|line| match line {
+            Line::Property(p) if p.key == key => Some(p),
+            _ => None,
+        });
+        match existing {
+            Some(property) => *property = Property::new(key, value),
+            None => self.lines.push(Line::Property(Property::new(key, value))),
+        }
+    }
+
+    /// Removes every line that sets `key`. Returns whether there was one.
+    pub fn remove(&mut self, key: &str) -> bool {
+        let count = self.lines.len();
+        self.lines
+            .retain(|line| !matches!(line, Line::Property(p) if p.key == key));
+        self.lines.len() != count
+    }
+}
+
+impl FromStr for Properties {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_properties(s)
+    }
+}
+
+/// Untouched lines come back exactly as they were read; new and changed
+/// properties are written as `key=value`. Every line ends in `\n`.
+impl fmt::Display for Properties {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            match line {
+                Line::Property(property) => writeln!(f, "{property}")?,
+                Line::Comment(text) | Line::Blank(text) => writeln!(f, "{text}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Property {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(raw) = &self.raw {
+            let unchanged = parse_properties(raw).is_ok_and(|parsed| {
+                parsed
+                    .iter()
+                    .next()
+                    .is_some_and(|p| p.key == self.key && p.value == self.value)
+            });
+            if unchanged {
+                return f.write_str(raw);
+            }
+        }
+        write!(
+            f,
+            "{}={}",
+            escape(&self.key, true),
+            escape(&self.value, false)
+        )
+    }
+}
+
+/// Escapes what a reader would otherwise take for a separator, a comment
+/// or a line break. In values only leading spaces need it.
+fn escape(text: &str, is_key: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    for (i, c) in text.chars().enumerate() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\x0c' => out.push_str("\\f"),
+            ' ' if is_key || i == 0 => out.push_str("\\ "),
+            '=' | ':' | '#' | '!' if is_key => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn is_space(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\x0c')
+}
+
+/// Parses a `.properties` file as `java.util.Properties.load` reads it.
+/// Keys end at the first unescaped `=`, `:` or white space; a line ending
+/// in an odd number of backslashes continues on the next, whose leading
+/// white space is dropped; `\uXXXX` escapes may spell surrogate pairs.
+pub fn parse_properties(input: &str) -> Result<Properties, ParseError> {
+    let mut physical = Vec::new();
+    let mut start = 0;
+    let mut rest = input;
+    while !rest.is_empty() {
+        let end = rest.find(['\n', '\r']).unwrap_or(rest.len());
+        physical.push((start, &rest[..end]));
+        let terminator = if rest[end..].starts_with("\r\n") {
+            2
+        } else {
+            (end < rest.len()) as usize
+        };
+        start += end + terminator;
+        rest = &rest[end + terminator..];
+    }
+
+    let mut properties = Properties::default();
+    let mut lines = physical.into_iter();
+    while let Some((start, line)) = lines.next() {
+        let trimmed = line.trim_start_matches(is_space);
+        if trimmed.is_empty() {
+            properties.lines.push(Line::Blank(line.to_string()));
+            continue;
+        }
+        if trimmed.starts_with(['#', '!']) {
+            properties.lines.push(Line::Comment(line.to_string()));
+            continue;
+        }
+        // The logical line's characters with their offsets in `input`, the
+        // backslashes that join lines left out.
+        let mut chars = Vec::new();
+        let mut raw = line.to_string();
+        let mut piece = (start + line.len() - trimmed.len(), trimmed);
+        loop {
+            let (at, text) = piece;
+            let continues = text.chars().rev().take_while(|c| *c == '\\').count() % 2 == 1;
+            let text = if continues {
+                &text[..text.len() - 1]
+            } else {
+                text
+            };
+            chars.extend(text.char_indices().map(|(i, c)| (at + i, c)));
+            if !continues {
+                break;
+            }
+            let Some((start, next)) = lines.next() else {
+                break;
+            };
+            raw.push('\n');
+            raw.push_str(next);
+            let trimmed = next.trim_start_matches(is_space);
+            piece = (start + next.len() - trimmed.len(), trimmed);
+        }
+
+        let mut key_end = 0;
+        while let Some(&(_, c)) = chars.get(key_end) {
+            if c == '=' || c == ':' || is_space(c) {
+                break;
+            }
+            key_end += if c == '\\' { 2 } else { 1 };
+        }
+        let key_end = key_end.min(chars.len());
+        let mut value_start = key_end;
+        while chars.get(value_start).is_some_and(|(_, c)| is_space(*c)) {
+            value_start += 1;
+        }
+        if chars
+            .get(value_start)
+            .is_some_and(|(_, c)| *c == '=' || *c == ':')
+        {
+            value_start += 1;
+        }
+        while chars.get(value_start).is_some_and(|(_, c)| is_space(*c)) {
+            value_start += 1;
+        }
+        properties.lines.push(Line::Property(Property {
+            key: unescape(&chars[..key_end])?,
+            value: unescape(&chars[value_start..])?,
+            raw: Some(raw),
+        }));
+    }
+    Ok(properties)
+}
+
+fn unescape(chars: &[(usize, char)]) -> Result<String, ParseError> {
+    let mut units = Vec::with_capacity(chars.len());
+    let mut i = 0;
+    while let Some(&(at, c)) = chars.get(i) {
+        i += 1;
+        if c != '\\' {
+            units.extend_from_slice(c.encode_utf16(&mut [0; 2]));
+            continue;
+        }
+        let Some(&(_, escaped)) = chars.get(i) else {
+            break;
+        };
+        i += 1;
+        let c = match escaped {
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            'f' => '\x0c',
+            'u' => {
+                let hex: String = chars.iter().skip(i).take(4).map(|(_, c)| c).collect();
+                let unit = (hex.len() == 4)
+                    .then(|| u16::from_str_radix(&hex, 16).ok())
+                    .flatten()
+                    .ok_or_else(|| ParseError::new(at, "malformed `\\uXXXX` escape"))?;
+                units.push(unit);
+                i += 4;
+                continue;
+            }
+            c => c,
+        };
+        units.extend_from_slice(c.encode_utf16(&mut [0; 2]));
+    }
+    String::from_utf16(&units).map_err(|_| {
+        ParseError::new(
+            chars.first().map_or(0, |(at, _)| *at),
+            "unpaired surrogate in a `\\u` escape",
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &str = "# Application settings
+! generated, do not edit
+
+app.name = My App
+app.title:Caf\\u00e9 \\uD83D\\uDE00
+greeting       Hello, \\
+               world
+key\\ with\\ spaces=value
+empty=
+path=C:\\\\temp\\\\new
+tabbed\t=\t\\ leading space
+";
+
+    #[test]
+    fn parse_properties_should_work() -> Result<(), ParseError> {
+        let properties = parse_properties(FILE)?;
+        assert_eq!(properties.get("app.name"), Some("My App"));
+        assert_eq!(properties.get("app.title"), Some("Café 😀"));
+        assert_eq!(properties.get("greeting"), Some("Hello, world"));
+        assert_eq!(properties.get("key with spaces"), Some("value"));
+        assert_eq!(properties.get("empty"), Some(""));
+        assert_eq!(properties.get("path"), Some("C:\\temp\\new"));
+        assert_eq!(properties.get("tabbed"), Some(" leading space"));
+        assert_eq!(properties.iter().count(), 7);
+        Ok(())
+    }
+
+    #[test]
+    fn writer_should_round_trip_untouched_lines() -> Result<(), ParseError> {
+        let mut properties = parse_properties(FILE)?;
+        assert_eq!(properties.to_string(), FILE);
+
+        properties.set("app.name", "Other: App");
+        properties.set("new key", " x\ny");
+        assert!(properties.remove("empty"));
+        assert!(!properties.remove("missing"));
+        let written = properties.to_string();
+        assert!(written.starts_with(
+            "# Application settings\n! generated, do not edit\n\napp.name=Other: App\n"
+        ));
+        assert!(written.contains("greeting       Hello, \\\n               world\n"));
+        assert!(written.ends_with("new\\ key=\\ x\\ny\n"));
+        let reread = parse_properties(&written)?;
+        assert_eq!(reread.get("new key"), Some(" x\ny"));
+        assert_eq!(reread.get("app.name"), Some("Other: App"));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_properties_should_report_bad_escapes() {
+        let err = parse_properties("a=1\nb=\\u12G4\n").unwrap_err();
+        assert_eq!(err.offset(), 6);
+        assert!(parse_properties("a=\\uD83D").is_err());
+        assert!(parse_properties("a=\\u00").is_err());
+        assert_eq!(parse_properties("a=b\\").unwrap().get("a"), Some("b"));
+    }
+}
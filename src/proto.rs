@@ -0,0 +1,935 @@
+use std::ops::RangeInclusive;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::combinator::{alt, cut_err, not, opt, preceded, repeat, separated, terminated};
+use winnow::error::{ContextError, ErrMode, StrContext, StrContextValue};
+use winnow::token::{one_of, take_while};
+
+use crate::ParseError;
+use crate::combinators::Trivia;
+use crate::textproto::{self, Value};
+
+/// The largest field number the wire format can encode.
+const MAX_FIELD: i64 = 536_870_911;
+
+/// One `.proto` file. Names are kept as written; resolving type references
+/// against the package and imports is left to the caller.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileDescriptor {
+    pub package: Option<String>,
+    pub imports: Vec<Import>,
+    pub options: Vec<ProtoOption>,
+    pub messages: Vec<MessageDescriptor>,
+    pub enums: Vec<EnumDescriptor>,
+    pub services: Vec<ServiceDescriptor>,
+    /// In proto3 these only declare custom options.
+    pub extensions: Vec<Extend>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Import {
+    pub path: String,
+    pub kind: ImportKind,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ImportKind {
+    #[default]
+    Default,
+    Public,
+    Weak,
+}
+
+/// An `option` statement or a bracketed field option. Custom option names
+/// keep their parentheses, as in `(my.opt).sub`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtoOption {
+    pub name: String,
+    /// Aggregate values are written in the text format, so they come back
+    /// as `Value::Message`.
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MessageDescriptor {
+    pub name: String,
+    /// Every field in declaration order, including those inside a oneof.
+    pub fields: Vec<FieldDescriptor>,
+    pub oneofs: Vec<OneofDescriptor>,
+    pub messages: Vec<MessageDescriptor>,
+    pub enums: Vec<EnumDescriptor>,
+    pub extensions: Vec<Extend>,
+    pub reserved: Vec<Reserved>,
+    pub options: Vec<ProtoOption>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDescriptor {
+    pub name: String,
+    pub number: u32,
+    pub label: Label,
+    pub ty: FieldType,
+    pub options: Vec<ProtoOption>,
+    /// Index into the message's `oneofs`.
+    pub oneof: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Label {
+    /// No label. Map fields are singular here even though they are
+    /// repeated on the wire.
+    #[default]
+    Singular,
+    Optional,
+    Repeated,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldType {
+    Double,
+    Float,
+    Int32,
+    Int64,
+    Uint32,
+    Uint64,
+    Sint32,
+    Sint64,
+    Fixed32,
+    Fixed64,
+    Sfixed32,
+    Sfixed64,
+    Bool,
+    String,
+    Bytes,
+    /// A message or enum, possibly qualified and possibly starting with `.`.
+    Named(String),
+    Map(Box<FieldType>, Box<FieldType>),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OneofDescriptor {
+    pub name: String,
+    pub options: Vec<ProtoOption>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reserved {
+    /// `max` is stored as the largest number allowed in that position.
+    Numbers(RangeInclusive<i64>),
+    Name(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EnumDescriptor {
+    pub name: String,
+    pub values: Vec<EnumValue>,
+    pub reserved: Vec<Reserved>,
+    pub options: Vec<ProtoOption>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumValue {
+    pub name: String,
+    pub number: i32,
+    pub options: Vec<ProtoOption>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServiceDescriptor {
+    pub name: String,
+    pub methods: Vec<MethodDescriptor>,
+    pub options: Vec<ProtoOption>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodDescriptor {
+    pub name: String,
+    pub input_type: String,
+    pub output_type: String,
+    pub client_streaming: bool,
+    pub server_streaming: bool,
+    pub options: Vec<ProtoOption>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extend {
+    pub extendee: String,
+    pub fields: Vec<FieldDescriptor>,
+}
+
+impl FileDescriptor {
+    /// Looks a message up by its path below the package, e.g. `Outer.Inner`.
+    pub fn message(&self, path: &str) -> Option<&MessageDescriptor> {
+        let mut names = path.split('.');
+        let first = names.next()?;
+        let mut message = self.messages.iter().find(|m| m.name == first)?;
+        for name in names {
+            message = message.messages.iter().find(|m| m.name == name)?;
+        }
+        Some(message)
+    }
+
+    pub fn enum_type(&self, path: &str) -> Option<&EnumDescriptor> {
+        let (enums, name) = match path.rsplit_once('.') {
+            Some((parent, name)) => (&self.message(parent)?.enums, name),
+            None => (&self.enums, path),
+        };
+        enums.iter().find(|e| e.name == name)
+    }
+}
+
+impl MessageDescriptor {
+    pub fn field(&self, name: &str) -> Option<&FieldDescriptor> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    pub fn oneof_fields(&self, index: usize) -> impl Iterator<Item = &FieldDescriptor> {
+        self.fields
+            .iter()
+            .filter(move |field| field.oneof == Some(index))
+    }
+}
+
+impl FieldType {
+    fn scalar(name: &str) -> Option<Self> {
+        Some(match name {
+            "double" => FieldType::Double,
+            "float" => FieldType::Float,
+            "int32" => FieldType::Int32,
+            "int64" => FieldType::Int64,
+            "uint32" => FieldType::Uint32,
+            "uint64" => FieldType::Uint64,
+            "sint32" => FieldType::Sint32,
+            "sint64" => FieldType::Sint64,
+            "fixed32" => FieldType::Fixed32,
+            "fixed64" => FieldType::Fixed64,
+            "sfixed32" => FieldType::Sfixed32,
+            "sfixed64" => FieldType::Sfixed64,
+            "bool" => FieldType::Bool,
+            "string" => FieldType::String,
+            "bytes" => FieldType::Bytes,
+            _ => return None,
+        })
+    }
+
+    /// Integral and string scalars; floats, bytes and messages cannot key
+    /// a map.
+    fn is_map_key(&self) -> bool {
+        !matches!(
+            self,
+            FieldType::Double
+                | FieldType::Float
+                | FieldType::Bytes
+                | FieldType::Named(_)
+                | FieldType::Map(..)
+        )
+    }
+}
+
+/// Parses a proto3 file. The `syntax = "proto3";` line is required, since
+/// protoc reads a file without one as proto2.
+pub fn parse_proto(input: &str) -> Result<FileDescriptor, ParseError> {
+    (
+        preceded(trivia, syntax),
+        repeat(0.., preceded(trivia, top_level)).fold(FileDescriptor::default, |mut file, decl| {
+            match decl {
+                TopLevel::Package(package) => file.package = Some(package),
+                TopLevel::Import(import) => file.imports.push(import),
+                TopLevel::Option(option) => file.options.push(option),
+                TopLevel::Message(message) => file.messages.push(message),
+                TopLevel::Enum(e) => file.enums.push(e),
+                TopLevel::Service(service) => file.services.push(service),
+                TopLevel::Extend(extend) => file.extensions.push(extend),
+                TopLevel::Empty => {}
+            }
+            file
+        }),
+        trivia,
+    )
+        .map(|(_, file, _)| file)
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+enum TopLevel {
+    Package(String),
+    Import(Import),
+    Option(ProtoOption),
+    Message(MessageDescriptor),
+    Enum(EnumDescriptor),
+    Service(ServiceDescriptor),
+    Extend(Extend),
+    Empty,
+}
+
+enum Member {
+    Field(FieldDescriptor),
+    Oneof(OneofDescriptor, Vec<FieldDescriptor>),
+    Message(MessageDescriptor),
+    Enum(EnumDescriptor),
+    Extend(Extend),
+    Option(ProtoOption),
+    Reserved(Vec<Reserved>),
+    Empty,
+}
+
+fn syntax(input: &mut &str) -> ModalResult<()> {
+    (
+        cut_err(keyword("syntax")).context(StrContext::Expected(StrContextValue::StringLiteral(
+            "syntax",
+        ))),
+        expect('='),
+        trivia,
+    )
+        .parse_next(input)?;
+    cut_err(textproto::string.verify(|v| *v == Value::String("proto3".to_string())))
+        .context(StrContext::Expected(StrContextValue::StringLiteral(
+            "\"proto3\"",
+        )))
+        .parse_next(input)?;
+    semicolon(input)
+}
+
+fn top_level(input: &mut &str) -> ModalResult<TopLevel> {
+    alt((
+        package.map(TopLevel::Package),
+        import.map(TopLevel::Import),
+        option_statement.map(TopLevel::Option),
+        message.map(TopLevel::Message),
+        enumeration.map(TopLevel::Enum),
+        service.map(TopLevel::Service),
+        extend.map(TopLevel::Extend),
+        ';'.map(|_| TopLevel::Empty),
+    ))
+    .context(StrContext::Label("top-level definition"))
+    .parse_next(input)
+}
+
+fn package(input: &mut &str) -> ModalResult<String> {
+    preceded(
+        keyword("package"),
+        cut_err(terminated(preceded(trivia, full_ident), semicolon)),
+    )
+    .map(str::to_string)
+    .parse_next(input)
+}
+
+fn import(input: &mut &str) -> ModalResult<Import> {
+    keyword("import").parse_next(input)?;
+    let kind = opt(preceded(
+        trivia,
+        alt((
+            keyword("public").value(ImportKind::Public),
+            keyword("weak").value(ImportKind::Weak),
+        )),
+    ))
+    .parse_next(input)?
+    .unwrap_or_default();
+    let path = cut_err(preceded(trivia, string_literal))
+        .context(StrContext::Label("import path"))
+        .parse_next(input)?;
+    semicolon(input)?;
+    Ok(Import { path, kind })
+}
+
+fn option_statement(input: &mut &str) -> ModalResult<ProtoOption> {
+    preceded(
+        keyword("option"),
+        cut_err(terminated(option_body, semicolon)),
+    )
+    .parse_next(input)
+}
+
+/// `name = constant`, shared by statements and bracketed field options.
+fn option_body(input: &mut &str) -> ModalResult<ProtoOption> {
+    let name = preceded(trivia, option_name).parse_next(input)?;
+    expect('=').parse_next(input)?;
+    let value = preceded(trivia, cut_err(constant))
+        .context(StrContext::Label("option value"))
+        .parse_next(input)?;
+    Ok(ProtoOption {
+        name: name.to_string(),
+        value,
+    })
+}
+
+fn option_name<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    (
+        alt((ident.void(), ('(', type_name, ')').void())),
+        repeat::<_, _, (), _, _>(0.., ('.', ident)),
+    )
+        .take()
+        .parse_next(input)
+}
+
+fn constant(input: &mut &str) -> ModalResult<Value> {
+    alt((
+        textproto::string,
+        textproto::message.map(Value::Message),
+        preceded('+', textproto::number),
+        textproto::number,
+        full_ident.map(|id| match id {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            _ => Value::Enum(id.to_string()),
+        }),
+    ))
+    .parse_next(input)
+}
+
+/// The optional `[a = 1, b = 2]` after a field or enum value.
+fn field_options(input: &mut &str) -> ModalResult<Vec<ProtoOption>> {
+    opt(preceded(
+        (trivia, '['),
+        cut_err(terminated(
+            separated(1.., option_body, preceded(trivia, ',')),
+            expect(']'),
+        )),
+    ))
+    .map(Option::unwrap_or_default)
+    .parse_next(input)
+}
+
+fn message(input: &mut &str) -> ModalResult<MessageDescriptor> {
+    let name = preceded(keyword("message"), cut_err(preceded(trivia, ident)))
+        .context(StrContext::Label("message name"))
+        .parse_next(input)?;
+    expect('{').parse_next(input)?;
+    let mut message = MessageDescriptor {
+        name: name.to_string(),
+        ..Default::default()
+    };
+    loop {
+        trivia(input)?;
+        if opt('}').parse_next(input)?.is_some() {
+            return Ok(message);
+        }
+        match cut_err(member)
+            .context(StrContext::Label("message member"))
+            .parse_next(input)?
+        {
+            Member::Field(field) => message.fields.push(field),
+            Member::Oneof(oneof, fields) => {
+                let index = message.oneofs.len();
+                message.oneofs.push(oneof);
+                message
+                    .fields
+                    .extend(fields.into_iter().map(|field| FieldDescriptor {
+                        oneof: Some(index),
+                        ..field
+                    }));
+            }
+            Member::Message(nested) => message.messages.push(nested),
+            Member::Enum(e) => message.enums.push(e),
+            Member::Extend(extend) => message.extensions.push(extend),
+            Member::Option(option) => message.options.push(option),
+            Member::Reserved(reserved) => message.reserved.extend(reserved),
+            Member::Empty => {}
+        }
+    }
+}
+
+fn member(input: &mut &str) -> ModalResult<Member> {
+    alt((
+        message.map(Member::Message),
+        enumeration.map(Member::Enum),
+        oneof.map(|(oneof, fields)| Member::Oneof(oneof, fields)),
+        extend.map(Member::Extend),
+        option_statement.map(Member::Option),
+        (|i: &mut &str| reserved(i, MAX_FIELD)).map(Member::Reserved),
+        ';'.map(|_| Member::Empty),
+        map_field.map(Member::Field),
+        field.map(Member::Field),
+    ))
+    .parse_next(input)
+}
+
+fn field(input: &mut &str) -> ModalResult<FieldDescriptor> {
+    let label = opt(terminated(
+        alt((
+            keyword("repeated").value(Label::Repeated),
+            keyword("optional").value(Label::Optional),
+        )),
+        trivia,
+    ))
+    .parse_next(input)?
+    .unwrap_or_default();
+    let ty = field_type.parse_next(input)?;
+    field_rest(input, label, ty)
+}
+
+fn map_field(input: &mut &str) -> ModalResult<FieldDescriptor> {
+    ("map", trivia, '<').parse_next(input)?;
+    let key = preceded(trivia, cut_err(field_type.verify(FieldType::is_map_key)))
+        .context(StrContext::Label("map key type"))
+        .parse_next(input)?;
+    expect(',').parse_next(input)?;
+    let value = preceded(trivia, cut_err(field_type))
+        .context(StrContext::Label("map value type"))
+        .parse_next(input)?;
+    expect('>').parse_next(input)?;
+    let ty = FieldType::Map(Box::new(key), Box::new(value));
+    field_rest(input, Label::Singular, ty)
+}
+
+/// Everything after the type: `name = number [options];`.
+fn field_rest(input: &mut &str, label: Label, ty: FieldType) -> ModalResult<FieldDescriptor> {
+    let name = preceded(trivia, cut_err(ident))
+        .context(StrContext::Label("field name"))
+        .parse_next(input)?;
+    expect('=').parse_next(input)?;
+    let number = preceded(
+        trivia,
+        cut_err(integer.verify(|n| (1..=MAX_FIELD).contains(n) && !(19000..=19999).contains(n))),
+    )
+    .context(StrContext::Label("field number"))
+    .parse_next(input)?;
+    let options = field_options(input)?;
+    semicolon(input)?;
+    Ok(FieldDescriptor {
+        name: name.to_string(),
+        number: number as u32,
+        label,
+        ty,
+        options,
+        oneof: None,
+    })
+}
+
+fn field_type(input: &mut &str) -> ModalResult<FieldType> {
+    type_name
+        .map(|name| FieldType::scalar(name).unwrap_or_else(|| FieldType::Named(name.to_string())))
+        .parse_next(input)
+}
+
+fn oneof(input: &mut &str) -> ModalResult<(OneofDescriptor, Vec<FieldDescriptor>)> {
+    let name = preceded(keyword("oneof"), cut_err(preceded(trivia, ident)))
+        .context(StrContext::Label("oneof name"))
+        .parse_next(input)?;
+    expect('{').parse_next(input)?;
+    let mut oneof = OneofDescriptor {
+        name: name.to_string(),
+        options: Vec::new(),
+    };
+    let mut fields = Vec::new();
+    loop {
+        trivia(input)?;
+        if opt('}').parse_next(input)?.is_some() {
+            return Ok((oneof, fields));
+        }
+        if let Some(option) = opt(option_statement).parse_next(input)? {
+            oneof.options.push(option);
+        } else if opt(';').parse_next(input)?.is_none() {
+            // Members of a oneof cannot be labelled or be maps.
+            let field = cut_err(field.verify(|f: &FieldDescriptor| f.label == Label::Singular))
+                .context(StrContext::Label("oneof field"))
+                .parse_next(input)?;
+            fields.push(field);
+        }
+    }
+}
+
+fn enumeration(input: &mut &str) -> ModalResult<EnumDescriptor> {
+    let name = preceded(keyword("enum"), cut_err(preceded(trivia, ident)))
+        .context(StrContext::Label("enum name"))
+        .parse_next(input)?;
+    expect('{').parse_next(input)?;
+    let mut e = EnumDescriptor {
+        name: name.to_string(),
+        ..Default::default()
+    };
+    loop {
+        trivia(input)?;
+        if opt('}').parse_next(input)?.is_some() {
+            return Ok(e);
+        }
+        if let Some(option) = opt(option_statement).parse_next(input)? {
+            e.options.push(option);
+        } else if let Some(reserved) =
+            opt(|i: &mut &str| reserved(i, i32::MAX.into())).parse_next(input)?
+        {
+            e.reserved.extend(reserved);
+        } else if opt(';').parse_next(input)?.is_none() {
+            // proto3 makes the first value the default, so it must be zero.
+            let first = e.values.is_empty();
+            let value = cut_err(enum_value.verify(|v: &EnumValue| !first || v.number == 0))
+                .context(StrContext::Label("enum value"))
+                .context(StrContext::Expected(StrContextValue::Description(
+                    "the first value to be zero",
+                )))
+                .parse_next(input)?;
+            e.values.push(value);
+        }
+    }
+}
+
+fn enum_value(input: &mut &str) -> ModalResult<EnumValue> {
+    let name = ident.parse_next(input)?;
+    expect('=').parse_next(input)?;
+    let number = preceded(
+        trivia,
+        cut_err(integer.verify_map(|n| i32::try_from(n).ok())),
+    )
+    .context(StrContext::Label("enum number"))
+    .parse_next(input)?;
+    let options = field_options(input)?;
+    semicolon(input)?;
+    Ok(EnumValue {
+        name: name.to_string(),
+        number,
+        options,
+    })
+}
+
+/// `reserved 2, 9 to 11, 40 to max;` or `reserved "foo", "bar";`.
+fn reserved(input: &mut &str, max: i64) -> ModalResult<Vec<Reserved>> {
+    let range = move |input: &mut &str| {
+        let start = integer.parse_next(input)?;
+        let end = opt(preceded(
+            (trivia, keyword("to"), trivia),
+            cut_err(alt((keyword("max").value(max), integer))),
+        ))
+        .parse_next(input)?;
+        Ok(Reserved::Numbers(start..=end.unwrap_or(start)))
+    };
+    preceded(
+        keyword("reserved"),
+        cut_err(terminated(
+            alt((
+                separated(
+                    1..,
+                    preceded(trivia, string_literal.map(Reserved::Name)),
+                    preceded(trivia, ','),
+                ),
+                separated(1.., preceded(trivia, range), preceded(trivia, ',')),
+            )),
+            semicolon,
+        )),
+    )
+    .context(StrContext::Label("reserved"))
+    .parse_next(input)
+}
+
+fn service(input: &mut &str) -> ModalResult<ServiceDescriptor> {
+    let name = preceded(keyword("service"), cut_err(preceded(trivia, ident)))
+        .context(StrContext::Label("service name"))
+        .parse_next(input)?;
+    expect('{').parse_next(input)?;
+    let mut service = ServiceDescriptor {
+        name: name.to_string(),
+        ..Default::default()
+    };
+    loop {
+        trivia(input)?;
+        if opt('}').parse_next(input)?.is_some() {
+            return Ok(service);
+        }
+        if let Some(option) = opt(option_statement).parse_next(input)? {
+            service.options.push(option);
+        } else if opt(';').parse_next(input)?.is_none() {
+            let method = cut_err(rpc)
+                .context(StrContext::Label("rpc"))
+                .parse_next(input)?;
+            service.methods.push(method);
+        }
+    }
+}
+
+fn rpc(input: &mut &str) -> ModalResult<MethodDescriptor> {
+    let name = preceded(keyword("rpc"), cut_err(preceded(trivia, ident)))
+        .context(StrContext::Label("method name"))
+        .parse_next(input)?;
+    let (client_streaming, input_type) = message_type(input)?;
+    preceded(trivia, cut_err(keyword("returns")))
+        .context(StrContext::Expected(StrContextValue::StringLiteral(
+            "returns",
+        )))
+        .parse_next(input)?;
+    let (server_streaming, output_type) = message_type(input)?;
+    trivia(input)?;
+    let mut options = Vec::new();
+    if opt('{').parse_next(input)?.is_some() {
+        loop {
+            trivia(input)?;
+            if opt('}').parse_next(input)?.is_some() {
+                break;
+            }
+            if opt(';').parse_next(input)?.is_none() {
+                options.push(cut_err(option_statement).parse_next(input)?);
+            }
+        }
+    } else {
+        semicolon(input)?;
+    }
+    Ok(MethodDescriptor {
+        name: name.to_string(),
+        input_type,
+        output_type,
+        client_streaming,
+        server_streaming,
+        options,
+    })
+}
+
+/// `(Type)` or `(stream Type)`.
+fn message_type(input: &mut &str) -> ModalResult<(bool, String)> {
+    expect('(').parse_next(input)?;
+    trivia(input)?;
+    let streaming = opt(terminated(keyword("stream"), trivia))
+        .parse_next(input)?
+        .is_some();
+    let name = cut_err(type_name)
+        .context(StrContext::Label("message type"))
+        .parse_next(input)?;
+    expect(')').parse_next(input)?;
+    Ok((streaming, name.to_string()))
+}
+
+fn extend(input: &mut &str) -> ModalResult<Extend> {
+    let extendee = preceded(keyword("extend"), cut_err(preceded(trivia, type_name)))
+        .context(StrContext::Label("extended type"))
+        .parse_next(input)?;
+    expect('{').parse_next(input)?;
+    let mut fields = Vec::new();
+    loop {
+        trivia(input)?;
+        if opt('}').parse_next(input)?.is_some() {
+            return Ok(Extend {
+                extendee: extendee.to_string(),
+                fields,
+            });
+        }
+        if opt(';').parse_next(input)?.is_none() {
+            fields.push(
+                cut_err(field)
+                    .context(StrContext::Label("extension field"))
+                    .parse_next(input)?,
+            );
+        }
+    }
+}
+
+fn integer(input: &mut &str) -> ModalResult<i64> {
+    textproto::number
+        .verify_map(|v| match v {
+            Value::Integer(n) => i64::try_from(n).ok(),
+            _ => None,
+        })
+        .parse_next(input)
+}
+
+fn string_literal(input: &mut &str) -> ModalResult<String> {
+    textproto::string
+        .verify_map(|v| match v {
+            Value::String(s) => Some(s),
+            _ => None,
+        })
+        .parse_next(input)
+}
+
+/// A reference to a message or enum type.
+fn type_name<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    (opt('.'), full_ident).take().parse_next(input)
+}
+
+fn full_ident<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    (ident, repeat::<_, _, (), _, _>(0.., ('.', ident)))
+        .take()
+        .parse_next(input)
+}
+
+fn ident<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    (
+        one_of(|c: char| c.is_ascii_alphabetic() || c == '_'),
+        take_while(0.., is_ident_char),
+    )
+        .take()
+        .parse_next(input)
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// A whole word, so `message` does not match the start of `messages`.
+fn keyword<'i>(word: &'static str) -> impl Parser<&'i str, &'i str, ErrMode<ContextError>> {
+    terminated(word, not(one_of(is_ident_char)))
+}
+
+fn expect<'i>(c: char) -> impl Parser<&'i str, char, ErrMode<ContextError>> {
+    preceded(
+        trivia,
+        cut_err(c).context(StrContext::Expected(StrContextValue::CharLiteral(c))),
+    )
+}
+
+fn semicolon(input: &mut &str) -> ModalResult<()> {
+    expect(';').void().parse_next(input)
+}
+
+/// White space, `//` line comments and `/* */` block comments.
+fn trivia(input: &mut &str) -> ModalResult<()> {
+    let mut trivia = Trivia::C;
+    trivia.parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEARCH: &str = r#"
+        // Search API.
+        syntax = "proto3";
+
+        package example.search.v1;
+
+        import "google/protobuf/timestamp.proto";
+        import public "other.proto";
+
+        option java_package = "com.example.search";
+        option (my.file_opt) = { level: 2 tags: "a" };
+
+        message SearchRequest {
+          string query = 1;
+          optional int32 page_number = 2 [deprecated = true, (validate.rules).int32.gte = 0];
+          repeated Corpus corpora = 3;
+          map<string, google.protobuf.Timestamp> seen = 4;
+          oneof filter {
+            string author = 5;
+            uint64 after_id = 0x10;
+          }
+          reserved 6, 9 to 11, 100 to max;
+          reserved "foo", "bar";
+
+          enum Corpus {
+            option allow_alias = true;
+            CORPUS_UNSPECIFIED = 0;
+            CORPUS_WEB = 1;
+            CORPUS_ALIAS = 1 [(custom) = -2];
+          }
+
+          message Result { /* nested */ string url = 1; }
+        }
+
+        service SearchService {
+          rpc Search(SearchRequest) returns (stream .example.search.v1.SearchRequest.Result);
+          rpc Watch(stream SearchRequest) returns (stream SearchRequest) {
+            option idempotency_level = NO_SIDE_EFFECTS;
+          }
+        }
+    "#;
+
+    #[test]
+    fn parse_proto_should_work() -> Result<(), ParseError> {
+        let file = parse_proto(SEARCH)?;
+        assert_eq!(file.package.as_deref(), Some("example.search.v1"));
+        assert_eq!(file.imports[1].kind, ImportKind::Public);
+        assert_eq!(file.options[0].name, "java_package");
+        assert_eq!(file.options[1].name, "(my.file_opt)");
+        assert!(
+            matches!(&file.options[1].value, Value::Message(m) if m.get("level") == Some(&Value::Integer(2)))
+        );
+
+        let request = file.message("SearchRequest").unwrap();
+        let page = request.field("page_number").unwrap();
+        assert_eq!(
+            (page.number, page.label, &page.ty),
+            (2, Label::Optional, &FieldType::Int32)
+        );
+        assert_eq!(page.options[1].name, "(validate.rules).int32.gte");
+        assert_eq!(
+            request.field("corpora").unwrap().ty,
+            FieldType::Named("Corpus".to_string())
+        );
+        assert_eq!(
+            request.field("seen").unwrap().ty,
+            FieldType::Map(
+                Box::new(FieldType::String),
+                Box::new(FieldType::Named("google.protobuf.Timestamp".to_string()))
+            )
+        );
+        assert_eq!(
+            request.reserved[..3],
+            [
+                Reserved::Numbers(6..=6),
+                Reserved::Numbers(9..=11),
+                Reserved::Numbers(100..=MAX_FIELD),
+            ]
+        );
+        assert_eq!(request.reserved[4], Reserved::Name("bar".to_string()));
+
+        let corpus = file.enum_type("SearchRequest.Corpus").unwrap();
+        assert_eq!(corpus.values[2].number, 1);
+        assert_eq!(corpus.values[2].options[0].value, Value::Integer(-2));
+        assert_eq!(
+            file.message("SearchRequest.Result").unwrap().fields[0].name,
+            "url"
+        );
+
+        let methods = &file.services[0].methods;
+        assert_eq!(
+            methods[0].output_type,
+            ".example.search.v1.SearchRequest.Result"
+        );
+        assert!(!methods[0].client_streaming && methods[0].server_streaming);
+        assert!(methods[1].client_streaming);
+        assert_eq!(
+            methods[1].options[0].value,
+            Value::Enum("NO_SIDE_EFFECTS".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn oneof_fields_should_stay_in_order() -> Result<(), ParseError> {
+        let file = parse_proto(SEARCH)?;
+        let request = file.message("SearchRequest").unwrap();
+        assert_eq!(request.oneofs[0].name, "filter");
+        let names: Vec<_> = request.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "query",
+                "page_number",
+                "corpora",
+                "seen",
+                "author",
+                "after_id"
+            ]
+        );
+        let members: Vec<_> = request.oneof_fields(0).map(|f| f.number).collect();
+        assert_eq!(members, [5, 16]);
+        assert_eq!(request.field("query").unwrap().oneof, None);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_proto_should_report_errors() {
+        assert_eq!(parse_proto("syntax = \"proto2\";").unwrap_err().offset(), 9);
+        assert!(parse_proto("message A {}").is_err());
+        let proto3 = "syntax = \"proto3\";\n";
+        assert_eq!(
+            parse_proto(&format!("{proto3}message A {{ int32 a = 0; }}"))
+                .unwrap_err()
+                .offset(),
+            41
+        );
+        assert!(parse_proto(&format!("{proto3}message A {{ int32 a = 19000; }}")).is_err());
+        assert!(parse_proto(&format!("{proto3}enum E {{ A = 1; }}")).is_err());
+        assert!(
+            parse_proto(&format!(
+                "{proto3}message A {{ map<double, int32> m = 1; }}"
+            ))
+            .is_err()
+        );
+        assert!(
+            parse_proto(&format!(
+                "{proto3}message A {{ oneof o {{ repeated int32 a = 1; }} }}"
+            ))
+            .is_err()
+        );
+        assert!(parse_proto(&format!("{proto3}/* unterminated")).is_err());
+        assert!(parse_proto(&format!("{proto3}message A {{ string s = 1 }}")).is_err());
+    }
+}
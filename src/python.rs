@@ -0,0 +1,625 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::line_ending;
+use winnow::combinator::{alt, cut_err, delimited, empty, fail, opt, preceded, repeat, terminated};
+use winnow::error::{ContextError, ErrMode, StrContext, StrContextValue};
+use winnow::token::{any, one_of, take, take_while};
+
+use crate::ParseError;
+use crate::combinators::Trivia;
+use crate::json::JsonValue;
+
+/// A value as written by Python's `repr`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PyValue {
+    None,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Bytes(Vec<u8>),
+    List(Vec<PyValue>),
+    Tuple(Vec<PyValue>),
+    Set(Vec<PyValue>),
+    /// Items in source order; later duplicates are kept.
+    Dict(Vec<(PyValue, PyValue)>),
+}
+
+impl PyValue {
+    /// The value for a string key, as a dict lookup would find it.
+    pub fn get(&self, key: &str) -> Option<&PyValue> {
+        match self {
+            PyValue::Dict(items) => items
+                .iter()
+                .rev()
+                .find(|(k, _)| matches!(k, PyValue::Str(s) if s == key))
+                .map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            PyValue::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// Formats the value the way `repr` would.
+impl fmt::Display for PyValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn items(f: &mut fmt::Formatter<'_>, values: &[PyValue]) -> fmt::Result {
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{value}")?;
+            }
+            Ok(())
+        }
+        match self {
+            PyValue::None => f.write_str("None"),
+            PyValue::Bool(true) => f.write_str("True"),
+            PyValue::Bool(false) => f.write_str("False"),
+            PyValue::Int(n) => write!(f, "{n}"),
+            PyValue::Float(x) if x.is_nan() => f.write_str("nan"),
+            PyValue::Float(x) if x.is_infinite() => {
+                f.write_str(if *x > 0.0 { "inf" } else { "-inf" })
+            }
+            // Like `repr`: exponents outside 1e-4..1e16, and else always a
+            // decimal point.
+            PyValue::Float(x) if *x != 0.0 && !(1e-4..1e16).contains(&x.abs()) => {
+                let text = format!("{x:e}");
+                let (mantissa, exponent) = text.split_once('e').unwrap_or((&text, "0"));
+                match exponent.strip_prefix('-') {
+                    Some(digits) => write!(f, "{mantissa}e-{digits:0>2}"),
+                    None => write!(f, "{mantissa}e+{exponent:0>2}"),
+                }
+            }
+            PyValue::Float(x) if x.fract() == 0.0 => write!(f, "{x:.1}"),
+            PyValue::Float(x) => write!(f, "{x}"),
+            PyValue::Str(s) => write_quoted(f, s.chars(), false),
+            PyValue::Bytes(b) => {
+                f.write_str("b")?;
+                write_quoted(f, b.iter().map(|&b| char::from(b)), true)
+            }
+            PyValue::List(values) => {
+                f.write_str("[")?;
+                items(f, values)?;
+                f.write_str("]")
+            }
+            PyValue::Tuple(values) => {
+                f.write_str("(")?;
+                items(f, values)?;
+                f.write_str(if values.len() == 1 { ",)" } else { ")" })
+            }
+            PyValue::Set(values) if values.is_empty() => f.write_str("set()"),
+            PyValue::Set(values) => {
+                f.write_str("{")?;
+                items(f, values)?;
+                f.write_str("}")
+            }
+            PyValue::Dict(entries) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{key}: {value}")?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+/// Single quotes unless the text holds a `'` and no `"`, like CPython.
+fn write_quoted(
+    f: &mut fmt::Formatter<'_>,
+    chars: impl Iterator<Item = char> + Clone,
+    bytes: bool,
+) -> fmt::Result {
+    let quote = if chars.clone().any(|c| c == '\'') && !chars.clone().any(|c| c == '"') {
+        '"'
+    } else {
+        '\''
+    };
+    write!(f, "{quote}")?;
+    for c in chars {
+        match c {
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c == quote => write!(f, "\\{c}")?,
+            c if (c as u32) < 0x20 || c == '\x7f' || (bytes && !c.is_ascii()) => {
+                write!(f, "\\x{:02x}", c as u32)?
+            }
+            c => write!(f, "{c}")?,
+        }
+    }
+    write!(f, "{quote}")
+}
+
+/// Sequences and sets become arrays and bytes become text, with invalid
+/// UTF-8 replaced. Dict keys that are not strings are spelled with `repr`.
+impl From<PyValue> for JsonValue {
+    fn from(value: PyValue) -> Self {
+        match value {
+            PyValue::None => JsonValue::Null,
+            PyValue::Bool(b) => JsonValue::Bool(b),
+            PyValue::Int(n) => JsonValue::Number(n as f64),
+            PyValue::Float(x) => JsonValue::Number(x),
+            PyValue::Str(s) => JsonValue::String(s),
+            PyValue::Bytes(b) => JsonValue::String(String::from_utf8_lossy(&b).into_owned()),
+            PyValue::List(values) | PyValue::Tuple(values) | PyValue::Set(values) => {
+                JsonValue::Array(values.into_iter().map(JsonValue::from).collect())
+            }
+            PyValue::Dict(entries) => JsonValue::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let key = match key {
+                            PyValue::Str(s) => s,
+                            key => key.to_string(),
+                        };
+                        (key, JsonValue::from(value))
+                    })
+                    .collect::<HashMap<_, _>>(),
+            ),
+        }
+    }
+}
+
+/// Parses a single Python literal, such as the output of `repr` on builtin
+/// containers. `#` comments are skipped. Complex numbers and f-strings are
+/// not literals and are rejected, as are integers beyond the `i64` range.
+pub fn parse_python(input: &str) -> Result<PyValue, ParseError> {
+    delimited(trivia, value, trivia)
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+fn value(input: &mut &str) -> ModalResult<PyValue> {
+    alt((
+        list,
+        paren,
+        brace,
+        strings,
+        number,
+        keyword("None").value(PyValue::None),
+        keyword("True").value(PyValue::Bool(true)),
+        keyword("False").value(PyValue::Bool(false)),
+        (keyword("set"), '(', trivia, ')').value(PyValue::Set(Vec::new())),
+    ))
+    .context(StrContext::Label("value"))
+    .parse_next(input)
+}
+
+fn keyword<'i>(word: &'static str) -> impl Parser<&'i str, &'i str, ErrMode<ContextError>> {
+    take_while(1.., |c: char| c.is_ascii_alphanumeric() || c == '_')
+        .verify(move |w: &str| w == word)
+}
+
+/// Comma-separated values up to `close`, allowing a trailing comma. Also
+/// reports whether any comma was seen, which is what makes `(1,)` a tuple.
+fn elements(input: &mut &str, close: char) -> ModalResult<(Vec<PyValue>, bool)> {
+    let mut values = Vec::new();
+    let mut comma = false;
+    loop {
+        trivia.parse_next(input)?;
+        if opt(close).parse_next(input)?.is_some() {
+            return Ok((values, comma));
+        }
+        values.push(cut_err(value).parse_next(input)?);
+        trivia.parse_next(input)?;
+        if opt(',').parse_next(input)?.is_some() {
+            comma = true;
+        } else {
+            cut_err(close)
+                .context(StrContext::Expected(StrContextValue::CharLiteral(close)))
+                .parse_next(input)?;
+            return Ok((values, comma));
+        }
+    }
+}
+
+fn list(input: &mut &str) -> ModalResult<PyValue> {
+    '['.parse_next(input)?;
+    let (values, _) = elements(input, ']')?;
+    Ok(PyValue::List(values))
+}
+
+/// A tuple, or a parenthesized value when there is one item and no comma.
+fn paren(input: &mut &str) -> ModalResult<PyValue> {
+    '('.parse_next(input)?;
+    let (mut values, comma) = elements(input, ')')?;
+    if values.len() == 1 && !comma {
+        return Ok(values.remove(0));
+    }
+    Ok(PyValue::Tuple(values))
+}
+
+/// A dict or a set; the first item decides which, and `{}` is a dict.
+fn brace(input: &mut &str) -> ModalResult<PyValue> {
+    '{'.parse_next(input)?;
+    trivia.parse_next(input)?;
+    if opt('}').parse_next(input)?.is_some() {
+        return Ok(PyValue::Dict(Vec::new()));
+    }
+    let first = cut_err(value).parse_next(input)?;
+    trivia.parse_next(input)?;
+    if opt(':').parse_next(input)?.is_none() {
+        let mut values = vec![first];
+        if opt(',').parse_next(input)?.is_some() {
+            values.extend(elements(input, '}')?.0);
+        } else {
+            cut_err('}')
+                .context(StrContext::Expected(StrContextValue::CharLiteral('}')))
+                .parse_next(input)?;
+        }
+        return Ok(PyValue::Set(values));
+    }
+    let mut entries = Vec::new();
+    let mut key = first;
+    loop {
+        let item = cut_err(preceded(trivia, value)).parse_next(input)?;
+        entries.push((key, item));
+        trivia.parse_next(input)?;
+        if opt(',').parse_next(input)?.is_none() {
+            break;
+        }
+        trivia.parse_next(input)?;
+        if opt('}').parse_next(input)?.is_some() {
+            return Ok(PyValue::Dict(entries));
+        }
+        key = cut_err(value).parse_next(input)?;
+        trivia.parse_next(input)?;
+        cut_err(':')
+            .context(StrContext::Expected(StrContextValue::CharLiteral(':')))
+            .parse_next(input)?;
+    }
+    cut_err('}')
+        .context(StrContext::Expected(StrContextValue::CharLiteral('}')))
+        .parse_next(input)?;
+    Ok(PyValue::Dict(entries))
+}
+
+fn number(input: &mut &str) -> ModalResult<PyValue> {
+    let negative = opt(terminated(one_of(['+', '-']), trivia))
+        .parse_next(input)?
+        .is_some_and(|sign| sign == '-');
+    let sign = if negative { -1.0 } else { 1.0 };
+    if let Some(word) = opt(alt((keyword("inf"), keyword("nan")))).parse_next(input)? {
+        let x = if word == "inf" {
+            f64::INFINITY
+        } else {
+            f64::NAN
+        };
+        return Ok(PyValue::Float(sign * x));
+    }
+    if let Some((radix, digits)) = opt(preceded(
+        '0',
+        alt((
+            preceded(one_of(['x', 'X']), digits(16)).map(|d| (16, d)),
+            preceded(one_of(['o', 'O']), digits(8)).map(|d| (8, d)),
+            preceded(one_of(['b', 'B']), digits(2)).map(|d| (2, d)),
+        )),
+    ))
+    .parse_next(input)?
+    {
+        return match i64::from_str_radix(&digits, radix) {
+            Ok(n) => Ok(PyValue::Int(if negative { -n } else { n })),
+            Err(_) => too_large(input),
+        };
+    }
+    let whole = opt(digits(10)).parse_next(input)?;
+    let fraction = opt(preceded('.', opt(digits(10)))).parse_next(input)?;
+    if whole.is_none() && fraction.as_ref().is_none_or(Option::is_none) {
+        return fail.parse_next(input);
+    }
+    let exponent = opt(preceded(
+        one_of(['e', 'E']),
+        cut_err((opt(one_of(['+', '-'])), digits(10))),
+    ))
+    .parse_next(input)?;
+    if opt(one_of(['j', 'J'])).parse_next(input)?.is_some() {
+        return cut_err(fail)
+            .context(StrContext::Label("complex numbers are not supported"))
+            .parse_next(input);
+    }
+    let whole = whole.unwrap_or_default();
+    if fraction.is_none() && exponent.is_none() {
+        return match whole.parse::<i64>() {
+            Ok(n) => Ok(PyValue::Int(if negative { -n } else { n })),
+            Err(_) => too_large(input),
+        };
+    }
+    let mut literal = whole;
+    if let Some(fraction) = fraction {
+        literal.push('.');
+        literal.push_str(&fraction.unwrap_or_default());
+    }
+    if let Some((exponent_sign, digits)) = exponent {
+        literal.push('e');
+        literal.extend(exponent_sign);
+        literal.push_str(&digits);
+    }
+    match literal.parse::<f64>() {
+        Ok(x) => Ok(PyValue::Float(sign * x)),
+        Err(_) => fail.parse_next(input),
+    }
+}
+
+fn too_large<T>(input: &mut &str) -> ModalResult<T> {
+    cut_err(fail)
+        .context(StrContext::Label("integer does not fit in 64 bits"))
+        .parse_next(input)
+}
+
+/// Digits in `radix` with single underscores between them, which are
+/// dropped.
+fn digits<'i>(radix: u32) -> impl Parser<&'i str, String, ErrMode<ContextError>> {
+    let digit = move |c: char| c.is_digit(radix);
+    (
+        take_while(1.., digit),
+        repeat::<_, _, (), _, _>(0.., ('_', cut_err(take_while(1.., digit)))),
+    )
+        .take()
+        .map(|s: &str| s.replace('_', ""))
+}
+
+/// One or more string literals; adjacent ones are joined as in Python.
+/// Mixing text and bytes is an error.
+fn strings(input: &mut &str) -> ModalResult<PyValue> {
+    let (first_bytes, mut text) = string.parse_next(input)?;
+    loop {
+        let checkpoint = *input;
+        trivia.parse_next(input)?;
+        let at = *input;
+        match opt(string).parse_next(input)? {
+            Some((bytes, more)) if bytes == first_bytes => text.push_str(&more),
+            Some(_) => {
+                *input = at;
+                return cut_err(fail)
+                    .context(StrContext::Label("cannot mix bytes and text literals"))
+                    .parse_next(input);
+            }
+            None => {
+                *input = checkpoint;
+                break;
+            }
+        }
+    }
+    if first_bytes {
+        Ok(PyValue::Bytes(text.chars().map(|c| c as u8).collect()))
+    } else {
+        Ok(PyValue::Str(text))
+    }
+}
+
+/// A single literal with its optional prefix. Bytes come back as a string
+/// of chars below U+0100, one per byte.
+fn string(input: &mut &str) -> ModalResult<(bool, String)> {
+    let prefix: &str = take_while(0..=2, ['r', 'R', 'b', 'B', 'u', 'U'])
+        .verify(|p: &str| {
+            matches!(
+                p.to_ascii_lowercase().as_str(),
+                "" | "r" | "u" | "b" | "rb" | "br"
+            )
+        })
+        .parse_next(input)?;
+    let quote = alt((
+        "'''".value("'''"),
+        "\"\"\"".value("\"\"\""),
+        "'".value("'"),
+        "\"".value("\""),
+    ))
+    .parse_next(input)?;
+    let prefix = prefix.to_ascii_lowercase();
+    let raw = prefix.contains('r');
+    let bytes = prefix.contains('b');
+    let mut out = String::new();
+    loop {
+        if let Some(rest) = input.strip_prefix(quote) {
+            *input = rest;
+            return Ok((bytes, out));
+        }
+        let c = match input.chars().next() {
+            Some('\n') if quote.len() == 1 => None,
+            c => c,
+        };
+        match c {
+            None => {
+                return cut_err(fail)
+                    .context(StrContext::Expected(StrContextValue::StringLiteral(quote)))
+                    .parse_next(input);
+            }
+            Some('\\') if raw => {
+                // A backslash still stops the quote from ending the string.
+                let escaped: &str = (any, any).take().parse_next(input)?;
+                out.push_str(escaped);
+            }
+            Some('\\') => {
+                '\\'.parse_next(input)?;
+                if let Some(c) = cut_err(|i: &mut &str| escape(i, bytes)).parse_next(input)? {
+                    out.push(c);
+                }
+            }
+            Some(c) if bytes && !c.is_ascii() => {
+                return cut_err(fail)
+                    .context(StrContext::Label("bytes may only hold ASCII characters"))
+                    .parse_next(input);
+            }
+            Some(c) => {
+                *input = &input[c.len_utf8()..];
+                out.push(c);
+            }
+        }
+    }
+}
+
+/// What a backslash escape stands for; an escaped line break stands for
+/// nothing. Unknown escapes keep their backslash, as Python does.
+fn escape(input: &mut &str, bytes: bool) -> ModalResult<Option<char>> {
+    let hex = |n: usize| {
+        take(n)
+            .verify(|h: &str| h.chars().all(|c| c.is_ascii_hexdigit()))
+            .map(|h: &str| u32::from_str_radix(h, 16).unwrap())
+    };
+    let unicode = alt((preceded('u', hex(4)), preceded('U', hex(8))))
+        .verify_map(char::from_u32)
+        .verify(move |_| !bytes);
+    alt((
+        line_ending.value(None),
+        one_of(['\\', '\'', '"', 'a', 'b', 'f', 'n', 'r', 't', 'v']).map(|c| {
+            Some(match c {
+                'a' => '\x07',
+                'b' => '\x08',
+                'f' => '\x0c',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'v' => '\x0b',
+                c => c,
+            })
+        }),
+        take_while(1..=3, '0'..='7')
+            .map(|o: &str| u32::from_str_radix(o, 8).unwrap())
+            .verify_map(|o| {
+                if bytes {
+                    u8::try_from(o).ok().map(char::from)
+                } else {
+                    char::from_u32(o)
+                }
+            })
+            .map(Some),
+        preceded('x', hex(2)).map(char::from_u32),
+        unicode.map(Some),
+        empty.value(Some('\\')),
+    ))
+    .context(StrContext::Label("escape sequence"))
+    .parse_next(input)
+}
+
+/// White space and `#` comments.
+fn trivia(input: &mut &str) -> ModalResult<()> {
+    let mut trivia = Trivia::HASH;
+    trivia.parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(text: &str) -> PyValue {
+        PyValue::Str(text.to_string())
+    }
+
+    #[test]
+    fn parse_python_should_work() -> Result<(), ParseError> {
+        let value = parse_python(
+            "{'name': \"it's\", 'ids': [1_000, -0x1F, 0o17, 0b101, 2.5e-3, .5, 1.],\n\
+             \x20# a comment\n\
+             \x20(1, 2): (None,), 'flags': {True, False}, 'empty': set(),\n\
+             \x20'raw': r'a\\d', 'data': b'\\x00ab' b'c', 'joined': 'a' \"b\",\n\
+             \x20'esc': u'\\u00e9\\t\\101\\q', 'long': '''x\n'y'''}",
+        )?;
+        assert_eq!(value.get("name"), Some(&s("it's")));
+        assert_eq!(
+            value.get("ids"),
+            Some(&PyValue::List(vec![
+                PyValue::Int(1000),
+                PyValue::Int(-31),
+                PyValue::Int(15),
+                PyValue::Int(5),
+                PyValue::Float(0.0025),
+                PyValue::Float(0.5),
+                PyValue::Float(1.0),
+            ]))
+        );
+        let PyValue::Dict(entries) = &value else {
+            panic!("expected a dict");
+        };
+        assert_eq!(
+            entries[2],
+            (
+                PyValue::Tuple(vec![PyValue::Int(1), PyValue::Int(2)]),
+                PyValue::Tuple(vec![PyValue::None])
+            )
+        );
+        assert_eq!(
+            value.get("flags"),
+            Some(&PyValue::Set(vec![
+                PyValue::Bool(true),
+                PyValue::Bool(false)
+            ]))
+        );
+        assert_eq!(value.get("empty"), Some(&PyValue::Set(vec![])));
+        assert_eq!(value.get("raw"), Some(&s("a\\d")));
+        assert_eq!(value.get("data"), Some(&PyValue::Bytes(b"\0abc".to_vec())));
+        assert_eq!(value.get("joined"), Some(&s("ab")));
+        assert_eq!(value.get("esc"), Some(&s("é\tA\\q")));
+        assert_eq!(value.get("long"), Some(&s("x\n'y")));
+        assert_eq!(parse_python("(1)")?, PyValue::Int(1));
+        assert_eq!(
+            parse_python("[-inf]")?,
+            PyValue::List(vec![PyValue::Float(f64::NEG_INFINITY)])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn display_should_match_repr() -> Result<(), ParseError> {
+        for repr in [
+            "{'a': [1, 2.0, None], (1,): {True}, 'b': b'\\x00\\xff'}",
+            "\"it's\"",
+            "('x', set(), {}, ())",
+        ] {
+            assert_eq!(parse_python(repr)?.to_string(), repr);
+        }
+        let json = JsonValue::from(parse_python("{'a': (1, 'x'), 2: None}")?);
+        assert_eq!(
+            json.get_path("a.1"),
+            Some(&JsonValue::String("x".to_string()))
+        );
+        assert_eq!(json.get_path("2"), Some(&JsonValue::Null));
+        Ok(())
+    }
+
+    #[test]
+    fn display_should_write_floats_like_repr() -> Result<(), ParseError> {
+        for (x, repr) in [
+            (1e100, "1e+100"),
+            (-1.5e16, "-1.5e+16"),
+            (9999999999999998.0, "9999999999999998.0"),
+            (1.5e-5, "1.5e-05"),
+            (5e-324, "5e-324"),
+            (0.0001, "0.0001"),
+            (-0.0, "-0.0"),
+            (2.5, "2.5"),
+        ] {
+            assert_eq!(PyValue::Float(x).to_string(), repr);
+            assert_eq!(parse_python(repr)?, PyValue::Float(x));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn parse_python_should_report_errors() {
+        assert_eq!(parse_python("[1, 2").unwrap_err().offset(), 5);
+        assert_eq!(parse_python("{'a': 1, 'b' 2}").unwrap_err().offset(), 13);
+        assert_eq!(parse_python("'a' b'c'").unwrap_err().offset(), 4);
+        assert!(parse_python("'open").is_err());
+        assert!(parse_python("1__0").is_err());
+        assert!(parse_python("3j").is_err());
+        assert!(parse_python("f'x'").is_err());
+        assert!(parse_python("99999999999999999999").is_err());
+        assert!(parse_python("b'é'").is_err());
+        assert!(parse_python("[1] [2]").is_err());
+    }
+}
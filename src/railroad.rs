@@ -0,0 +1,477 @@
+use std::fmt::Write;
+
+use crate::dot::{Attr, AttrKind, EdgeTarget, Graph, Id, NodeId, Stmt};
+use crate::ebnf::{Expr, Grammar};
+
+/// Horizontal space taken by one character of box text.
+const CHAR_WIDTH: i32 = 8;
+/// Height of a terminal or rule box.
+const BOX_HEIGHT: i32 = 22;
+/// Radius of the arcs joining branches to the main line.
+const ARC: i32 = 10;
+/// Gap between items in a sequence and between stacked branches.
+const GAP: i32 = 10;
+/// Space around a diagram, and between the diagrams of a grammar.
+const MARGIN: i32 = 20;
+/// Height of a rule name or loop annotation.
+const LABEL_HEIGHT: i32 = 14;
+
+const STYLE: &str = "path { stroke: black; stroke-width: 2; fill: none; } \
+rect { stroke: black; stroke-width: 2; fill: #f6f6e8; } \
+rect.rule { fill: #e8eef6; } \
+rect.prose { fill: white; stroke-dasharray: 4 2; } \
+rect.group { fill: none; stroke-width: 1; stroke-dasharray: 4 2; } \
+text { font: 13px monospace; } \
+text.box { text-anchor: middle; } \
+text.name { font-weight: bold; }";
+
+/// Renders every rule of `grammar` as a railroad diagram, stacked in one
+/// SVG document under their names.
+pub fn svg(grammar: &Grammar) -> String {
+    let diagrams: Vec<(&str, Diagram)> = grammar
+        .rules
+        .iter()
+        .map(|rule| (rule.name.as_str(), Diagram::new(&rule.expr)))
+        .collect();
+    let width = diagrams
+        .iter()
+        .map(|(name, diagram)| diagram.width.max(text_width(name)))
+        .max()
+        .unwrap_or(0)
+        + 4 * MARGIN;
+    let height = diagrams
+        .iter()
+        .map(|(_, diagram)| LABEL_HEIGHT + diagram.up + diagram.down + MARGIN)
+        .sum::<i32>()
+        + MARGIN;
+
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+         viewBox=\"0 0 {width} {height}\">\n<style>{STYLE}</style>\n"
+    );
+    let mut top = MARGIN;
+    for (name, diagram) in &diagrams {
+        let _ = writeln!(
+            out,
+            "<text class=\"name\" x=\"{MARGIN}\" y=\"{}\">{}</text>",
+            top + LABEL_HEIGHT - 4,
+            escape(name)
+        );
+        top += LABEL_HEIGHT;
+        let y = top + diagram.up;
+        let (start, end) = (MARGIN + MARGIN / 2, MARGIN + MARGIN / 2 + diagram.width);
+        let _ = writeln!(
+            out,
+            "<path d=\"M{MARGIN} {} v{BOX_HEIGHT} M{MARGIN} {y} H{start} M{end} {y} H{} M{} {} v{BOX_HEIGHT}\"/>",
+            y - BOX_HEIGHT / 2,
+            end + MARGIN / 2,
+            end + MARGIN / 2,
+            y - BOX_HEIGHT / 2,
+        );
+        diagram.draw(&mut out, start, y);
+        top = y + diagram.down + MARGIN;
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+/// The rules of `grammar` as a directed graph, with an edge from each rule
+/// to every rule it refers to. Referenced rules the grammar does not
+/// define, such as ABNF's core rules, are drawn dashed.
+pub fn dot(grammar: &Grammar) -> Graph {
+    let mut stmts = vec![Stmt::Defaults(
+        AttrKind::Node,
+        vec![Attr::new("shape", "box")],
+    )];
+    let mut undefined: Vec<&str> = Vec::new();
+    let mut edges = Vec::new();
+    for rule in &grammar.rules {
+        stmts.push(Stmt::Node(node(&rule.name), Vec::new()));
+        let mut names = Vec::new();
+        references(&rule.expr, &mut names);
+        let mut targets: Vec<&str> = Vec::new();
+        for name in names {
+            let target = match grammar.rule(name) {
+                Some(defined) => defined.name.as_str(),
+                None => {
+                    if !undefined.iter().any(|seen| seen.eq_ignore_ascii_case(name)) {
+                        undefined.push(name);
+                    }
+                    name
+                }
+            };
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+        edges.extend(targets.into_iter().map(|target| {
+            Stmt::Edge(
+                vec![
+                    EdgeTarget::Node(node(&rule.name)),
+                    EdgeTarget::Node(node(target)),
+                ],
+                Vec::new(),
+            )
+        }));
+    }
+    stmts.extend(
+        undefined
+            .into_iter()
+            .map(|name| Stmt::Node(node(name), vec![Attr::new("style", "dashed")])),
+    );
+    stmts.extend(edges);
+    Graph {
+        strict: false,
+        directed: true,
+        id: Some(Id::new("grammar")),
+        stmts,
+    }
+}
+
+fn node(name: &str) -> NodeId {
+    NodeId {
+        id: Id::new(name),
+        port: None,
+    }
+}
+
+/// Rule names in `expr` in the order they are written, repeats included.
+fn references<'e>(expr: &'e Expr, names: &mut Vec<&'e str>) {
+    match expr {
+        Expr::Rule(name) => names.push(name),
+        Expr::Sequence(items) | Expr::Choice(items) => {
+            for item in items {
+                references(item, names);
+            }
+        }
+        Expr::Repeat { expr, .. } | Expr::And(expr) | Expr::Not(expr) => references(expr, names),
+        Expr::Literal { .. } | Expr::Range(..) | Expr::Prose(_) => {}
+    }
+}
+
+/// A laid out piece of a railroad diagram. `up` and `down` are how far it
+/// reaches above and below the line it is entered and left on.
+struct Diagram {
+    kind: Kind,
+    width: i32,
+    up: i32,
+    down: i32,
+}
+
+enum Kind {
+    Skip,
+    Box {
+        text: String,
+        class: &'static str,
+    },
+    Sequence(Vec<Diagram>),
+    /// Branches stacked downwards, each with its offset from the main line.
+    Choice(Vec<(Diagram, i32)>),
+    /// One or more of the item, with the way back this far below the line.
+    Loop {
+        item: Box<Diagram>,
+        back: i32,
+        label: Option<String>,
+    },
+    /// A dashed frame around a lookahead.
+    Group {
+        item: Box<Diagram>,
+        label: &'static str,
+    },
+}
+
+impl Diagram {
+    fn new(expr: &Expr) -> Self {
+        match expr {
+            Expr::Literal { text, .. } => Diagram::boxed(format!("{text:?}"), "literal"),
+            Expr::Range(from, to) => Diagram::boxed(format!("{from:?}..{to:?}"), "literal"),
+            Expr::Rule(name) => Diagram::boxed(name.clone(), "rule"),
+            Expr::Prose(text) => Diagram::boxed(format!("<{text}>"), "prose"),
+            Expr::Sequence(items) => Diagram::sequence(items.iter().map(Diagram::new).collect()),
+            Expr::Choice(items) => Diagram::choice(items.iter().map(Diagram::new).collect()),
+            Expr::Repeat { expr, min, max } => Diagram::repeat(Diagram::new(expr), *min, *max),
+            Expr::And(expr) => Diagram::group(Diagram::new(expr), "followed by"),
+            Expr::Not(expr) => Diagram::group(Diagram::new(expr), "not followed by"),
+        }
+    }
+
+    fn skip() -> Self {
+        Diagram {
+            kind: Kind::Skip,
+            width: 0,
+            up: 0,
+            down: 0,
+        }
+    }
+
+    fn boxed(text: String, class: &'static str) -> Self {
+        Diagram {
+            width: text_width(&text) + 2 * GAP,
+            up: BOX_HEIGHT / 2,
+            down: BOX_HEIGHT / 2,
+            kind: Kind::Box { text, class },
+        }
+    }
+
+    fn sequence(items: Vec<Diagram>) -> Self {
+        let gaps = GAP * (items.len() as i32 - 1).max(0);
+        Diagram {
+            width: items.iter().map(|item| item.width).sum::<i32>() + gaps,
+            up: items.iter().map(|item| item.up).max().unwrap_or(0),
+            down: items.iter().map(|item| item.down).max().unwrap_or(0),
+            kind: Kind::Sequence(items),
+        }
+    }
+
+    fn choice(items: Vec<Diagram>) -> Self {
+        let mut branches: Vec<(Diagram, i32)> = Vec::with_capacity(items.len());
+        for item in items {
+            let offset = match branches.last() {
+                None => 0,
+                Some((previous, offset)) => {
+                    (offset + previous.down + GAP + item.up).max(offset + 2 * ARC)
+                }
+            };
+            branches.push((item, offset));
+        }
+        let (up, down) = match (branches.first(), branches.last()) {
+            (Some((first, _)), Some((last, offset))) => (first.up, offset + last.down),
+            _ => (0, 0),
+        };
+        Diagram {
+            width: branches
+                .iter()
+                .map(|(item, _)| item.width)
+                .max()
+                .unwrap_or(0)
+                + 4 * ARC,
+            up,
+            down,
+            kind: Kind::Choice(branches),
+        }
+    }
+
+    fn repeat(item: Diagram, min: usize, max: Option<usize>) -> Self {
+        let label = match (min, max) {
+            (0 | 1, None) | (0, Some(1)) => None,
+            (min, Some(max)) if min == max => Some(format!("{min} times")),
+            (0, Some(max)) => Some(format!("at most {max}")),
+            (min, None) => Some(format!("at least {min}")),
+            (min, Some(max)) => Some(format!("{min} to {max}")),
+        };
+        match (min, max) {
+            (_, Some(0)) => Diagram::skip(),
+            (0, Some(1)) => Diagram::choice(vec![Diagram::skip(), item]),
+            (0, _) => Diagram::choice(vec![Diagram::skip(), Diagram::looped(item, label)]),
+            (1, Some(1)) => item,
+            _ => Diagram::looped(item, label),
+        }
+    }
+
+    fn looped(item: Diagram, label: Option<String>) -> Self {
+        let back = (item.down + GAP).max(2 * ARC);
+        let label_height = if label.is_some() { LABEL_HEIGHT } else { 0 };
+        let label_width = label.as_deref().map_or(0, text_width);
+        Diagram {
+            width: item.width.max(label_width) + 4 * ARC,
+            up: item.up,
+            down: back + label_height,
+            kind: Kind::Loop {
+                item: Box::new(item),
+                back,
+                label,
+            },
+        }
+    }
+
+    fn group(item: Diagram, label: &'static str) -> Self {
+        Diagram {
+            width: item.width.max(text_width(label)) + 2 * GAP,
+            up: item.up.max(ARC) + GAP + LABEL_HEIGHT,
+            down: item.down.max(ARC) + GAP,
+            kind: Kind::Group {
+                item: Box::new(item),
+                label,
+            },
+        }
+    }
+
+    /// Writes the diagram entered at (`x`, `y`) and left at
+    /// (`x + width`, `y`).
+    fn draw(&self, out: &mut String, x: i32, y: i32) {
+        match &self.kind {
+            Kind::Skip => line(out, x, x + self.width, y),
+            Kind::Box { text, class } => {
+                let rx = if *class == "literal" {
+                    BOX_HEIGHT / 2
+                } else {
+                    0
+                };
+                let _ = writeln!(
+                    out,
+                    "<rect class=\"{class}\" x=\"{x}\" y=\"{}\" width=\"{}\" height=\"{BOX_HEIGHT}\" rx=\"{rx}\"/>",
+                    y - self.up,
+                    self.width,
+                );
+                let _ = writeln!(
+                    out,
+                    "<text class=\"box\" x=\"{}\" y=\"{}\">{}</text>",
+                    x + self.width / 2,
+                    y + 4,
+                    escape(text)
+                );
+            }
+            Kind::Sequence(items) => {
+                let mut x = x;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        line(out, x, x + GAP, y);
+                        x += GAP;
+                    }
+                    item.draw(out, x, y);
+                    x += item.width;
+                }
+            }
+            Kind::Choice(branches) => {
+                let (left, right) = (x + 2 * ARC, x + self.width - 2 * ARC);
+                for (item, offset) in branches {
+                    let branch = y + offset;
+                    if *offset == 0 {
+                        line(out, x, left, y);
+                        line(out, right, x + self.width, y);
+                    } else {
+                        let _ = writeln!(
+                            out,
+                            "<path d=\"M{x} {y} q{ARC} 0 {ARC} {ARC} V{} q0 {ARC} {ARC} {ARC}\"/>",
+                            branch - ARC,
+                        );
+                        let _ = writeln!(
+                            out,
+                            "<path d=\"M{right} {branch} q{ARC} 0 {ARC} -{ARC} V{} q0 -{ARC} {ARC} -{ARC}\"/>",
+                            y + ARC,
+                        );
+                    }
+                    item.draw(out, left, branch);
+                    line(out, left + item.width, right, branch);
+                }
+            }
+            Kind::Loop { item, back, label } => {
+                let (left, right) = (x + 2 * ARC, x + self.width - 2 * ARC);
+                line(out, x, left, y);
+                item.draw(out, left, y);
+                line(out, left + item.width, x + self.width, y);
+                let _ = writeln!(
+                    out,
+                    "<path d=\"M{right} {y} q{ARC} 0 {ARC} {ARC} V{} q0 {ARC} -{ARC} {ARC} H{left} \
+                     q-{ARC} 0 -{ARC} -{ARC} V{} q0 -{ARC} {ARC} -{ARC}\"/>",
+                    y + back - ARC,
+                    y + ARC,
+                );
+                if let Some(label) = label {
+                    let _ = writeln!(
+                        out,
+                        "<text class=\"box\" x=\"{}\" y=\"{}\">{}</text>",
+                        x + self.width / 2,
+                        y + back + LABEL_HEIGHT - 2,
+                        escape(label)
+                    );
+                }
+            }
+            Kind::Group { item, label } => {
+                let top = y - self.up + LABEL_HEIGHT;
+                let _ = writeln!(
+                    out,
+                    "<rect class=\"group\" x=\"{x}\" y=\"{top}\" width=\"{}\" height=\"{}\"/>",
+                    self.width,
+                    y + self.down - top,
+                );
+                let _ = writeln!(out, "<text x=\"{x}\" y=\"{}\">{label}</text>", top - 4);
+                line(out, x, x + GAP, y);
+                item.draw(out, x + GAP, y);
+                line(out, x + GAP + item.width, x + self.width, y);
+            }
+        }
+    }
+}
+
+fn line(out: &mut String, from: i32, to: i32, y: i32) {
+    if to > from {
+        let _ = writeln!(out, "<path d=\"M{from} {y} H{to}\"/>");
+    }
+}
+
+fn text_width(text: &str) -> i32 {
+    text.chars().count() as i32 * CHAR_WIDTH
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParseError;
+    use crate::dot::parse_dot;
+    use crate::ebnf::{parse_abnf, parse_ebnf};
+
+    #[test]
+    fn svg_should_draw_every_rule() -> Result<(), ParseError> {
+        let grammar = parse_ebnf(
+            r#"list = "[" [ item { "," item } ] "]" ;
+               item = digit+ | "<" name ">" ;"#,
+        )?;
+        let svg = svg(&grammar);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.ends_with("</svg>\n"));
+        assert!(svg.contains("<text class=\"name\" x=\"20\" y=\"30\">list</text>"));
+        assert!(svg.contains(">item</text>"));
+        assert!(svg.contains(">&quot;&lt;&quot;</text>"));
+        assert_eq!(svg.matches("<rect class=\"literal\"").count(), 5);
+        assert_eq!(svg.matches("<rect class=\"rule\"").count(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn svg_should_annotate_bounded_repeats() -> Result<(), ParseError> {
+        let grammar = parse_abnf("hex = 2*4HEXDIG / 3DIGIT / <anything else>\r\n")?;
+        let svg = svg(&grammar);
+        assert!(svg.contains(">2 to 4</text>"));
+        assert!(svg.contains(">3 times</text>"));
+        assert!(svg.contains("<rect class=\"prose\""));
+        assert!(svg.contains(">&lt;anything else&gt;</text>"));
+        Ok(())
+    }
+
+    #[test]
+    fn dot_should_link_rules_to_their_references() -> Result<(), ParseError> {
+        let grammar = parse_abnf(
+            "list = item *(\",\" item)\r\nitem = NAME / \"(\" List \")\"\r\nname = ALPHA\r\n",
+        )?;
+        let graph = dot(&grammar);
+        assert_eq!(
+            graph.edges(),
+            [
+                ("list", "item"),
+                ("item", "name"),
+                ("item", "list"),
+                ("name", "ALPHA")
+            ]
+        );
+        assert_eq!(graph.node_attrs("ALPHA"), [&Attr::new("style", "dashed")]);
+        assert_eq!(parse_dot(&graph.to_string())?, graph);
+        Ok(())
+    }
+}
@@ -0,0 +1,778 @@
+use std::fmt::Write;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::digit1;
+use winnow::combinator::{
+    alt, cut_err, delimited, eof, fail, not, opt, peek, preceded, repeat, terminated,
+};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::stream::{LocatingSlice, Location};
+use winnow::token::{any, none_of, one_of, take_till, take_while};
+
+use crate::ParseError;
+pub use crate::combinators::Span;
+
+type Input<'i> = LocatingSlice<&'i str>;
+
+/// A parsed pattern in the syntax of the `regex` crate. Nothing here runs
+/// the pattern; the tree is for linting and explaining it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ast {
+    pub kind: AstKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AstKind {
+    /// The empty pattern, as in either side of `|` in `(|a)`.
+    Empty,
+    Literal(char),
+    /// `.`
+    Dot,
+    Assertion(Assertion),
+    Perl(PerlClass),
+    Unicode(UnicodeClass),
+    Class(Class),
+    Repetition(Repetition),
+    Group(Group),
+    /// `(?i)` and the like, which apply to the rest of the enclosing group.
+    Flags(Flags),
+    Concat(Vec<Ast>),
+    Alternation(Vec<Ast>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assertion {
+    /// `^`
+    StartLine,
+    /// `$`
+    EndLine,
+    /// `\A`
+    StartText,
+    /// `\z`
+    EndText,
+    /// `\b`
+    WordBoundary,
+    /// `\B`
+    NotWordBoundary,
+}
+
+/// `\d`, `\s`, `\w` and their upper-case negations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PerlClass {
+    pub kind: PerlKind,
+    pub negated: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PerlKind {
+    Digit,
+    Space,
+    Word,
+}
+
+/// `\pL`, `\p{Greek}` or `\P{...}`. The name is kept as written, so it may
+/// be a `key=value` pair like `scx=Hira`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnicodeClass {
+    pub name: String,
+    pub negated: bool,
+}
+
+/// A bracketed class such as `[^a-z\d]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Class {
+    pub negated: bool,
+    pub items: Vec<ClassItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClassItem {
+    Literal(char),
+    Range(char, char),
+    Perl(PerlClass),
+    Unicode(UnicodeClass),
+    /// `[:alpha:]` or `[:^alpha:]`.
+    Ascii {
+        name: String,
+        negated: bool,
+    },
+    Class(Class),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Repetition {
+    pub min: u32,
+    /// `None` when unbounded.
+    pub max: Option<u32>,
+    /// `false` for the lazy forms ending in `?`.
+    pub greedy: bool,
+    pub ast: Box<Ast>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    pub kind: GroupKind,
+    pub ast: Box<Ast>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupKind {
+    /// Capture indices count opening parentheses from 1, as in `$1`.
+    Capture {
+        index: u32,
+        name: Option<String>,
+    },
+    NonCapturing(Flags),
+}
+
+/// Flags turned on and off, e.g. `i` and `s` for `(?i-s)`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Flags {
+    pub enabled: Vec<char>,
+    pub disabled: Vec<char>,
+}
+
+impl Ast {
+    /// Names of the named capture groups in the order they open.
+    pub fn capture_names(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.walk(&mut |ast| {
+            if let AstKind::Group(Group {
+                kind:
+                    GroupKind::Capture {
+                        name: Some(name), ..
+                    },
+                ..
+            }) = &ast.kind
+            {
+                names.push(name.as_str());
+            }
+        });
+        names
+    }
+
+    /// Calls `f` on this node and every node below it, parents first.
+    pub fn walk<'a>(&'a self, f: &mut impl FnMut(&'a Ast)) {
+        f(self);
+        match &self.kind {
+            AstKind::Repetition(rep) => rep.ast.walk(f),
+            AstKind::Group(group) => group.ast.walk(f),
+            AstKind::Concat(items) | AstKind::Alternation(items) => {
+                items.iter().for_each(|item| item.walk(f))
+            }
+            _ => {}
+        }
+    }
+
+    /// Describes the pattern in English, one node per line, with children
+    /// indented below their parent.
+    pub fn explain(&self) -> String {
+        let mut out = String::new();
+        self.explain_into(&mut out, 0);
+        out
+    }
+
+    fn explain_into(&self, out: &mut String, depth: usize) {
+        let _ = write!(out, "{:indent$}", "", indent = depth * 2);
+        let children: &[Ast] = match &self.kind {
+            AstKind::Empty => {
+                out.push_str("nothing\n");
+                &[]
+            }
+            AstKind::Literal(c) => {
+                let _ = writeln!(out, "the character {c:?}");
+                &[]
+            }
+            AstKind::Dot => {
+                out.push_str("any character except a newline\n");
+                &[]
+            }
+            AstKind::Assertion(assertion) => {
+                out.push_str(match assertion {
+                    Assertion::StartLine => "the start of a line\n",
+                    Assertion::EndLine => "the end of a line\n",
+                    Assertion::StartText => "the start of the text\n",
+                    Assertion::EndText => "the end of the text\n",
+                    Assertion::WordBoundary => "a word boundary\n",
+                    Assertion::NotWordBoundary => "a position that is not a word boundary\n",
+                });
+                &[]
+            }
+            AstKind::Perl(class) => {
+                let _ = writeln!(out, "{}", perl_name(class));
+                &[]
+            }
+            AstKind::Unicode(class) => {
+                let not = if class.negated { "not " } else { "" };
+                let _ = writeln!(out, "a character {not}in Unicode class {}", class.name);
+                &[]
+            }
+            AstKind::Class(class) => {
+                let not = if class.negated { "not " } else { "" };
+                let _ = writeln!(out, "a character {not}in {}", describe_class(class));
+                &[]
+            }
+            AstKind::Repetition(rep) => {
+                let count = match (rep.min, rep.max) {
+                    (0, None) => "zero or more".to_string(),
+                    (1, None) => "one or more".to_string(),
+                    (0, Some(1)) => "optionally".to_string(),
+                    (min, None) => format!("at least {min}"),
+                    (min, Some(max)) if min == max => format!("exactly {min}"),
+                    (min, Some(max)) => format!("between {min} and {max}"),
+                };
+                let lazy = if rep.greedy {
+                    ""
+                } else {
+                    ", as few as possible,"
+                };
+                let _ = writeln!(out, "{count}{lazy} of:");
+                std::slice::from_ref(&*rep.ast)
+            }
+            AstKind::Group(group) => {
+                match &group.kind {
+                    GroupKind::Capture { index, name: None } => {
+                        let _ = writeln!(out, "capture group {index}:");
+                    }
+                    GroupKind::Capture {
+                        index,
+                        name: Some(name),
+                    } => {
+                        let _ = writeln!(out, "capture group {index} ({name}):");
+                    }
+                    GroupKind::NonCapturing(flags) if flags == &Flags::default() => {
+                        out.push_str("group:\n");
+                    }
+                    GroupKind::NonCapturing(flags) => {
+                        let _ = writeln!(out, "group with {}:", describe_flags(flags));
+                    }
+                }
+                std::slice::from_ref(&*group.ast)
+            }
+            AstKind::Flags(flags) => {
+                let _ = writeln!(out, "from here on, {}", describe_flags(flags));
+                &[]
+            }
+            AstKind::Concat(items) => {
+                out.push_str("in sequence:\n");
+                items
+            }
+            AstKind::Alternation(items) => {
+                out.push_str("one of:\n");
+                items
+            }
+        };
+        for child in children {
+            child.explain_into(out, depth + 1);
+        }
+    }
+}
+
+fn perl_name(class: &PerlClass) -> &'static str {
+    match (class.kind, class.negated) {
+        (PerlKind::Digit, false) => "a digit",
+        (PerlKind::Digit, true) => "a non-digit",
+        (PerlKind::Space, false) => "whitespace",
+        (PerlKind::Space, true) => "a non-whitespace character",
+        (PerlKind::Word, false) => "a word character",
+        (PerlKind::Word, true) => "a non-word character",
+    }
+}
+
+fn describe_class(class: &Class) -> String {
+    let parts: Vec<String> = class
+        .items
+        .iter()
+        .map(|item| match item {
+            ClassItem::Literal(c) => format!("{c:?}"),
+            ClassItem::Range(a, b) => format!("{a:?} to {b:?}"),
+            ClassItem::Perl(perl) => perl_name(perl).to_string(),
+            ClassItem::Unicode(u) if u.negated => format!("not Unicode class {}", u.name),
+            ClassItem::Unicode(u) => format!("Unicode class {}", u.name),
+            ClassItem::Ascii { name, negated } if *negated => format!("not [:{name}:]"),
+            ClassItem::Ascii { name, .. } => format!("[:{name}:]"),
+            ClassItem::Class(nested) if nested.negated => {
+                format!("not ({})", describe_class(nested))
+            }
+            ClassItem::Class(nested) => format!("({})", describe_class(nested)),
+        })
+        .collect();
+    parts.join(", ")
+}
+
+fn describe_flags(flags: &Flags) -> String {
+    let name = |c: &char| match c {
+        'i' => "case-insensitive",
+        'm' => "multi-line",
+        's' => "dot matches newline",
+        'U' => "swapped greediness",
+        'u' => "Unicode",
+        _ => "CRLF mode",
+    };
+    let on = flags.enabled.iter().map(name);
+    let off = flags.disabled.iter().map(|c| format!("no {}", name(c)));
+    on.map(str::to_string)
+        .chain(off)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Parses a pattern in the syntax of the `regex` crate. Verbose mode
+/// (`(?x)`) changes how the rest of the pattern is read and is rejected.
+pub fn parse_regex(pattern: &str) -> Result<Ast, ParseError> {
+    let mut ast = terminated(
+        alternation,
+        cut_err(eof).context(StrContext::Expected(StrContextValue::Description(
+            "no unmatched `)`",
+        ))),
+    )
+    .parse(LocatingSlice::new(pattern))
+    .map_err(ParseError::from)?;
+    number_groups(&mut ast, &mut 0);
+    Ok(ast)
+}
+
+/// Numbers capture groups in the order their parentheses open.
+fn number_groups(ast: &mut Ast, next: &mut u32) {
+    match &mut ast.kind {
+        AstKind::Group(group) => {
+            if let GroupKind::Capture { index, .. } = &mut group.kind {
+                *next += 1;
+                *index = *next;
+            }
+            number_groups(&mut group.ast, next);
+        }
+        AstKind::Repetition(rep) => number_groups(&mut rep.ast, next),
+        AstKind::Concat(items) | AstKind::Alternation(items) => {
+            items.iter_mut().for_each(|item| number_groups(item, next))
+        }
+        _ => {}
+    }
+}
+
+fn alternation(input: &mut Input<'_>) -> ModalResult<Ast> {
+    let ((first, rest), span): ((Ast, Vec<Ast>), _) = (concat, repeat(0.., preceded('|', concat)))
+        .with_span()
+        .parse_next(input)?;
+    if rest.is_empty() {
+        return Ok(first);
+    }
+    let mut items = rest;
+    items.insert(0, first);
+    Ok(Ast {
+        kind: AstKind::Alternation(items),
+        span,
+    })
+}
+
+fn concat(input: &mut Input<'_>) -> ModalResult<Ast> {
+    let (mut items, span): (Vec<Ast>, _) = repeat(0.., repeated).with_span().parse_next(input)?;
+    let kind = match items.len() {
+        0 => AstKind::Empty,
+        1 => return Ok(items.pop().unwrap()),
+        _ => AstKind::Concat(items),
+    };
+    Ok(Ast { kind, span })
+}
+
+fn repeated(input: &mut Input<'_>) -> ModalResult<Ast> {
+    let start = input.current_token_start();
+    let ast = atom(input)?;
+    let Some(((min, max), greedy)) =
+        opt((quantifier, opt('?').map(|lazy| lazy.is_none()))).parse_next(input)?
+    else {
+        return Ok(ast);
+    };
+    Ok(Ast {
+        kind: AstKind::Repetition(Repetition {
+            min,
+            max,
+            greedy,
+            ast: Box::new(ast),
+        }),
+        span: start..input.current_token_start(),
+    })
+}
+
+fn quantifier(input: &mut Input<'_>) -> ModalResult<(u32, Option<u32>)> {
+    alt((
+        '*'.value((0, None)),
+        '+'.value((1, None)),
+        '?'.value((0, Some(1))),
+        preceded(
+            '{',
+            cut_err(terminated((number, opt(preceded(',', opt(number)))), '}'))
+                .map(|(min, max)| match max {
+                    None => (min, Some(min)),
+                    Some(max) => (min, max),
+                })
+                .verify(|(min, max)| max.is_none_or(|max| *min <= max))
+                .context(StrContext::Label("counted repetition")),
+        ),
+    ))
+    .parse_next(input)
+}
+
+fn number(input: &mut Input<'_>) -> ModalResult<u32> {
+    digit1.parse_to().parse_next(input)
+}
+
+fn atom(input: &mut Input<'_>) -> ModalResult<Ast> {
+    alt((
+        group,
+        class.map(AstKind::Class),
+        '.'.value(AstKind::Dot),
+        '^'.value(AstKind::Assertion(Assertion::StartLine)),
+        '$'.value(AstKind::Assertion(Assertion::EndLine)),
+        preceded('\\', cut_err(escape)),
+        (peek(one_of(['*', '+', '?', '{'])), cut_err(fail))
+            .map(|(_, kind)| kind)
+            .context(StrContext::Label("repetition"))
+            .context(StrContext::Expected(StrContextValue::Description(
+                "something to repeat",
+            ))),
+        none_of(['|', ')']).map(AstKind::Literal),
+    ))
+    .with_span()
+    .map(|(kind, span)| Ast { kind, span })
+    .parse_next(input)
+}
+
+fn escape(input: &mut Input<'_>) -> ModalResult<AstKind> {
+    alt((
+        perl_class.map(AstKind::Perl),
+        unicode_class.map(AstKind::Unicode),
+        'A'.value(AstKind::Assertion(Assertion::StartText)),
+        'z'.value(AstKind::Assertion(Assertion::EndText)),
+        'b'.value(AstKind::Assertion(Assertion::WordBoundary)),
+        'B'.value(AstKind::Assertion(Assertion::NotWordBoundary)),
+        escaped_char.map(AstKind::Literal),
+    ))
+    .context(StrContext::Label("escape sequence"))
+    .parse_next(input)
+}
+
+/// What follows `\` when it stands for a single character.
+fn escaped_char(input: &mut Input<'_>) -> ModalResult<char> {
+    let hex = |digits: usize| {
+        alt((
+            delimited('{', take_while(1..=8, |c: char| c.is_ascii_hexdigit()), '}'),
+            take_while(digits, |c: char| c.is_ascii_hexdigit()),
+        ))
+        .verify_map(|h: &str| u32::from_str_radix(h, 16).ok().and_then(char::from_u32))
+    };
+    alt((
+        preceded('x', cut_err(hex(2))),
+        preceded('u', cut_err(hex(4))),
+        preceded('U', cut_err(hex(8))),
+        any.verify_map(|c| match c {
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            'r' => Some('\r'),
+            'f' => Some('\x0c'),
+            'v' => Some('\x0b'),
+            'a' => Some('\x07'),
+            _ if "\\.+*?()|[]{}^$#&-~ ".contains(c) => Some(c),
+            _ => None,
+        }),
+    ))
+    .parse_next(input)
+}
+
+fn perl_class(input: &mut Input<'_>) -> ModalResult<PerlClass> {
+    one_of(['d', 'D', 's', 'S', 'w', 'W'])
+        .map(|c: char| PerlClass {
+            kind: match c.to_ascii_lowercase() {
+                'd' => PerlKind::Digit,
+                's' => PerlKind::Space,
+                _ => PerlKind::Word,
+            },
+            negated: c.is_ascii_uppercase(),
+        })
+        .parse_next(input)
+}
+
+fn unicode_class(input: &mut Input<'_>) -> ModalResult<UnicodeClass> {
+    let negated = one_of(['p', 'P']).map(|c| c == 'P').parse_next(input)?;
+    let name = cut_err(alt((
+        delimited('{', take_till(1.., '}'), '}'),
+        one_of(|c: char| c.is_ascii_alphabetic()).take(),
+    )))
+    .context(StrContext::Label("Unicode class"))
+    .parse_next(input)?;
+    Ok(UnicodeClass {
+        name: name.to_string(),
+        negated,
+    })
+}
+
+fn class(input: &mut Input<'_>) -> ModalResult<Class> {
+    '['.parse_next(input)?;
+    let negated = opt('^').parse_next(input)?.is_some();
+    // A `]` right after the opening bracket is a literal.
+    let first = opt(']'.value(ClassItem::Literal(']'))).parse_next(input)?;
+    let rest: Vec<ClassItem> = cut_err(terminated(repeat(0.., class_item), ']'))
+        .context(StrContext::Expected(StrContextValue::CharLiteral(']')))
+        .parse_next(input)?;
+    Ok(Class {
+        negated,
+        items: first.into_iter().chain(rest).collect(),
+    })
+}
+
+fn class_item(input: &mut Input<'_>) -> ModalResult<ClassItem> {
+    alt((
+        ascii_class,
+        class.map(ClassItem::Class),
+        preceded(
+            '\\',
+            alt((
+                perl_class.map(ClassItem::Perl),
+                unicode_class.map(ClassItem::Unicode),
+            )),
+        ),
+        range,
+    ))
+    .parse_next(input)
+}
+
+fn ascii_class(input: &mut Input<'_>) -> ModalResult<ClassItem> {
+    delimited(
+        "[:",
+        (
+            opt('^').map(|negated| negated.is_some()),
+            take_while(1.., |c: char| c.is_ascii_lowercase()),
+        ),
+        ":]",
+    )
+    .map(|(negated, name): (bool, &str)| ClassItem::Ascii {
+        name: name.to_string(),
+        negated,
+    })
+    .parse_next(input)
+}
+
+/// A literal, or a range when a `-` that does not close the class follows.
+fn range(input: &mut Input<'_>) -> ModalResult<ClassItem> {
+    let start = class_char.parse_next(input)?;
+    let Some(end) = opt(preceded(
+        ('-', not(']')),
+        cut_err(class_char.verify(|end| *end >= start)).context(StrContext::Label("class range")),
+    ))
+    .parse_next(input)?
+    else {
+        return Ok(ClassItem::Literal(start));
+    };
+    Ok(ClassItem::Range(start, end))
+}
+
+fn class_char(input: &mut Input<'_>) -> ModalResult<char> {
+    alt((
+        preceded('\\', cut_err(escaped_char)).context(StrContext::Label("escape sequence")),
+        none_of(['\\', ']', '[']),
+    ))
+    .parse_next(input)
+}
+
+enum GroupPrefix {
+    Named(String),
+    NonCapturing(Flags),
+    Flags(Flags),
+}
+
+fn group(input: &mut Input<'_>) -> ModalResult<AstKind> {
+    '('.parse_next(input)?;
+    let prefix = opt(preceded('?', cut_err(group_prefix))).parse_next(input)?;
+    let kind = match prefix {
+        None => GroupKind::Capture {
+            index: 0,
+            name: None,
+        },
+        Some(GroupPrefix::Named(name)) => GroupKind::Capture {
+            index: 0,
+            name: Some(name),
+        },
+        Some(GroupPrefix::NonCapturing(flags)) => GroupKind::NonCapturing(flags),
+        Some(GroupPrefix::Flags(flags)) => return Ok(AstKind::Flags(flags)),
+    };
+    let ast = alternation(input)?;
+    cut_err(')')
+        .context(StrContext::Expected(StrContextValue::CharLiteral(')')))
+        .parse_next(input)?;
+    Ok(AstKind::Group(Group {
+        kind,
+        ast: Box::new(ast),
+    }))
+}
+
+fn group_prefix(input: &mut Input<'_>) -> ModalResult<GroupPrefix> {
+    let name = (
+        one_of(|c: char| c.is_ascii_alphabetic() || c == '_'),
+        take_while(0.., |c: char| c.is_ascii_alphanumeric() || c == '_'),
+    )
+        .take();
+    alt((
+        delimited((opt('P'), '<'), cut_err(name), cut_err('>'))
+            .map(|name: &str| GroupPrefix::Named(name.to_string()))
+            .context(StrContext::Label("group name")),
+        (flags, alt((':'.value(false), ')'.value(true))))
+            .verify(|(flags, statement)| !statement || *flags != Flags::default())
+            .map(|(flags, statement)| {
+                if statement {
+                    GroupPrefix::Flags(flags)
+                } else {
+                    GroupPrefix::NonCapturing(flags)
+                }
+            })
+            .context(StrContext::Label("group flags")),
+    ))
+    .parse_next(input)
+}
+
+fn flags(input: &mut Input<'_>) -> ModalResult<Flags> {
+    let flag = || one_of(['i', 'm', 's', 'U', 'u', 'R']);
+    (repeat(0.., flag()), opt(preceded('-', repeat(1.., flag()))))
+        .map(|(enabled, disabled)| Flags {
+            enabled,
+            disabled: disabled.unwrap_or_default(),
+        })
+        .parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lit(c: char, at: usize) -> Ast {
+        Ast {
+            kind: AstKind::Literal(c),
+            span: at..at + 1,
+        }
+    }
+
+    #[test]
+    fn parse_regex_should_work() -> Result<(), ParseError> {
+        let ast = parse_regex("a|bc*?")?;
+        assert_eq!(
+            ast,
+            Ast {
+                kind: AstKind::Alternation(vec![
+                    lit('a', 0),
+                    Ast {
+                        kind: AstKind::Concat(vec![
+                            lit('b', 2),
+                            Ast {
+                                kind: AstKind::Repetition(Repetition {
+                                    min: 0,
+                                    max: None,
+                                    greedy: false,
+                                    ast: Box::new(lit('c', 3)),
+                                }),
+                                span: 3..6,
+                            },
+                        ]),
+                        span: 2..6,
+                    },
+                ]),
+                span: 0..6,
+            }
+        );
+
+        let ast =
+            parse_regex(r"^(?P<year>\d{4})-(\d{2,})(?i:x)(?<tail>[^\]a-z[:alpha:]\pL-]+)\z$")?;
+        assert_eq!(ast.capture_names(), ["year", "tail"]);
+        let AstKind::Concat(items) = &ast.kind else {
+            panic!("expected a concatenation");
+        };
+        let AstKind::Group(tail) = &items[5].kind else {
+            panic!("expected a group");
+        };
+        assert_eq!(
+            tail.kind,
+            GroupKind::Capture {
+                index: 3,
+                name: Some("tail".to_string())
+            }
+        );
+        let AstKind::Repetition(rep) = &tail.ast.kind else {
+            panic!("expected a repetition");
+        };
+        assert_eq!(
+            rep.ast.kind,
+            AstKind::Class(Class {
+                negated: true,
+                items: vec![
+                    ClassItem::Literal(']'),
+                    ClassItem::Range('a', 'z'),
+                    ClassItem::Ascii {
+                        name: "alpha".to_string(),
+                        negated: false
+                    },
+                    ClassItem::Unicode(UnicodeClass {
+                        name: "L".to_string(),
+                        negated: false
+                    }),
+                    ClassItem::Literal('-'),
+                ],
+            })
+        );
+        assert_eq!(items[5].span, 31..62);
+        assert_eq!(
+            parse_regex(r"\x41\u{1F600}\.")?.kind,
+            AstKind::Concat(vec![
+                Ast {
+                    kind: AstKind::Literal('A'),
+                    span: 0..4
+                },
+                Ast {
+                    kind: AstKind::Literal('😀'),
+                    span: 4..13
+                },
+                Ast {
+                    kind: AstKind::Literal('.'),
+                    span: 13..15
+                },
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn explain_should_describe_each_node() -> Result<(), ParseError> {
+        let ast = parse_regex(r"(?i)^(\w+)@[a-z.]{2,}|$")?;
+        assert_eq!(
+            ast.explain(),
+            "\
+one of:
+  in sequence:
+    from here on, case-insensitive
+    the start of a line
+    capture group 1:
+      one or more of:
+        a word character
+    the character '@'
+    at least 2 of:
+      a character in 'a' to 'z', '.'
+  the end of a line
+"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_regex_should_report_errors() {
+        assert_eq!(parse_regex("ab(c").unwrap_err().offset(), 4);
+        assert_eq!(parse_regex("a**").unwrap_err().offset(), 2);
+        assert!(parse_regex("*a").is_err());
+        assert!(parse_regex("a)").is_err());
+        assert!(parse_regex("[z-a]").is_err());
+        assert!(parse_regex("[abc").is_err());
+        assert!(parse_regex("a{3,1}").is_err());
+        assert!(parse_regex(r"\q").is_err());
+        assert!(parse_regex("(?x) a").is_err());
+        assert!(parse_regex("(?)").is_err());
+        assert!(parse_regex("(?P<1>a)").is_err());
+    }
+}
@@ -0,0 +1,675 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{space0, space1};
+use winnow::combinator::{
+    alt, cut_err, delimited, not, opt, preceded, repeat, separated, terminated,
+};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::{take_till, take_while};
+
+use crate::ParseError;
+
+/// How deep `-r` files may nest before [`Requirements::expand_includes`]
+/// assumes a cycle.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// A pip requirements file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Requirements {
+    pub lines: Vec<Line>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Line {
+    Requirement {
+        requirement: Requirement,
+        /// `--hash=sha256:...` values, algorithm included.
+        hashes: Vec<String>,
+    },
+    /// `-r file`: another requirements file.
+    Include(String),
+    /// `-c file`: a constraints file.
+    Constraint(String),
+    /// `-e path-or-url`.
+    Editable(String),
+    /// Any other option, such as `--index-url`, without its dashes.
+    Option { name: String, value: Option<String> },
+}
+
+/// A PEP 508 dependency specifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Requirement {
+    pub name: String,
+    pub extras: Vec<String>,
+    pub specifiers: Vec<Specifier>,
+    /// `name @ url`; a requirement has either this or specifiers.
+    pub url: Option<String>,
+    pub marker: Option<Marker>,
+}
+
+/// `>=1.2`, `~=2.0`, `==1.*` and the like.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Specifier {
+    pub op: Op,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    /// `~=`
+    Compatible,
+    Equal,
+    NotEqual,
+    LessEq,
+    GreaterEq,
+    Less,
+    Greater,
+    /// `===`, plain string equality.
+    Arbitrary,
+}
+
+/// An environment marker, the part after `;`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Marker {
+    And(Box<Marker>, Box<Marker>),
+    Or(Box<Marker>, Box<Marker>),
+    Compare {
+        left: MarkerValue,
+        op: MarkerOp,
+        right: MarkerValue,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkerValue {
+    /// An environment variable such as `python_version`.
+    Variable(String),
+    Literal(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkerOp {
+    Version(Op),
+    In,
+    NotIn,
+}
+
+impl Requirements {
+    pub fn requirements(&self) -> impl Iterator<Item = &Requirement> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Requirement { requirement, .. } => Some(requirement),
+            _ => None,
+        })
+    }
+
+    /// Replaces each `-r` line with the lines of the file it names. `load`
+    /// gets the path as written and returns the file's contents, or `None`
+    /// to keep the line.
+    pub fn expand_includes(
+        &mut self,
+        load: &mut dyn FnMut(&str) -> Option<String>,
+    ) -> Result<(), ParseError> {
+        expand(&mut self.lines, load, 0)
+    }
+}
+
+fn expand(
+    lines: &mut Vec<Line>,
+    load: &mut dyn FnMut(&str) -> Option<String>,
+    depth: usize,
+) -> Result<(), ParseError> {
+    let mut expanded = Vec::with_capacity(lines.len());
+    for line in lines.drain(..) {
+        let Line::Include(path) = &line else {
+            expanded.push(line);
+            continue;
+        };
+        let Some(source) = load(path) else {
+            expanded.push(line);
+            continue;
+        };
+        if depth == MAX_INCLUDE_DEPTH {
+            return Err(ParseError::new(0, "`-r` files nest too deeply"));
+        }
+        let mut included = parse_requirements(&source)?.lines;
+        expand(&mut included, load, depth + 1)?;
+        expanded.extend(included);
+    }
+    *lines = expanded;
+    Ok(())
+}
+
+impl Requirement {
+    /// Whether the requirement applies; one without a marker always does.
+    pub fn applies(&self, env: &dyn Fn(&str) -> Option<String>) -> bool {
+        self.marker.as_ref().is_none_or(|m| m.evaluate(env))
+    }
+}
+
+impl Marker {
+    /// Evaluates the marker with `env` supplying variables; unknown
+    /// variables are empty strings. Comparisons use release numbers when
+    /// both sides look like versions and plain strings otherwise, as PEP 508
+    /// says.
+    pub fn evaluate(&self, env: &dyn Fn(&str) -> Option<String>) -> bool {
+        let value = |v: &MarkerValue| match v {
+            MarkerValue::Variable(name) => env(name).unwrap_or_default(),
+            MarkerValue::Literal(s) => s.clone(),
+        };
+        match self {
+            Marker::And(a, b) => a.evaluate(env) && b.evaluate(env),
+            Marker::Or(a, b) => a.evaluate(env) || b.evaluate(env),
+            Marker::Compare { left, op, right } => {
+                let (left, right) = (value(left), value(right));
+                match op {
+                    MarkerOp::In => right.contains(&left),
+                    MarkerOp::NotIn => !right.contains(&left),
+                    MarkerOp::Version(op) => compare(&left, *op, &right),
+                }
+            }
+        }
+    }
+}
+
+fn release(version: &str) -> Option<Vec<u64>> {
+    version.split('.').map(|part| part.parse().ok()).collect()
+}
+
+fn compare(left: &str, op: Op, right: &str) -> bool {
+    if op == Op::Arbitrary {
+        return left == right;
+    }
+    let (Some(l), Some(r)) = (release(left), release(right)) else {
+        return match op {
+            Op::Equal => left == right,
+            Op::NotEqual => left != right,
+            Op::Less => left < right,
+            Op::LessEq => left <= right,
+            Op::Greater => left > right,
+            Op::GreaterEq => left >= right,
+            Op::Compatible | Op::Arbitrary => false,
+        };
+    };
+    // Missing components count as zero, so `3.8 == 3.8.0`.
+    let len = l.len().max(r.len());
+    let pad = |v: &[u64]| {
+        (0..len)
+            .map(|i| v.get(i).copied().unwrap_or(0))
+            .collect::<Vec<_>>()
+    };
+    let ordering = pad(&l).cmp(&pad(&r));
+    match op {
+        Op::Equal => ordering == Ordering::Equal,
+        Op::NotEqual => ordering != Ordering::Equal,
+        Op::Less => ordering == Ordering::Less,
+        Op::LessEq => ordering != Ordering::Greater,
+        Op::Greater => ordering == Ordering::Greater,
+        Op::GreaterEq => ordering != Ordering::Less,
+        Op::Compatible => {
+            let prefix = r.len().saturating_sub(1).max(1);
+            ordering != Ordering::Less && l.get(..prefix) == r.get(..prefix)
+        }
+        Op::Arbitrary => unreachable!("handled above"),
+    }
+}
+
+impl FromStr for Requirement {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_requirement(s)
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Op::Compatible => "~=",
+            Op::Equal => "==",
+            Op::NotEqual => "!=",
+            Op::LessEq => "<=",
+            Op::GreaterEq => ">=",
+            Op::Less => "<",
+            Op::Greater => ">",
+            Op::Arbitrary => "===",
+        })
+    }
+}
+
+/// The canonical form: no spaces around specifiers, `; ` before the marker.
+impl fmt::Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        if !self.extras.is_empty() {
+            write!(f, "[{}]", self.extras.join(","))?;
+        }
+        if let Some(url) = &self.url {
+            write!(f, " @ {url}")?;
+        }
+        for (i, spec) in self.specifiers.iter().enumerate() {
+            let sep = if i == 0 { "" } else { "," };
+            write!(f, "{sep}{}{}", spec.op, spec.version)?;
+        }
+        if let Some(marker) = &self.marker {
+            // A URL would otherwise swallow the `;`.
+            let space = if self.url.is_some() { " " } else { "" };
+            write!(f, "{space}; {marker}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Marker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Marker::Or(a, b) => write!(f, "{a} or {b}"),
+            Marker::And(a, b) => {
+                let operand = |m: &Marker| match m {
+                    Marker::Or(..) => format!("({m})"),
+                    _ => m.to_string(),
+                };
+                write!(f, "{} and {}", operand(a), operand(b))
+            }
+            Marker::Compare { left, op, right } => {
+                let op = match op {
+                    MarkerOp::Version(op) => op.to_string(),
+                    MarkerOp::In => "in".to_string(),
+                    MarkerOp::NotIn => "not in".to_string(),
+                };
+                write!(f, "{left} {op} {right}")
+            }
+        }
+    }
+}
+
+impl fmt::Display for MarkerValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkerValue::Variable(name) => f.write_str(name),
+            MarkerValue::Literal(s) if s.contains('"') => write!(f, "'{s}'"),
+            MarkerValue::Literal(s) => write!(f, "\"{s}\""),
+        }
+    }
+}
+
+/// Parses a single PEP 508 requirement, e.g.
+/// `requests[socks]>=2.8,<3; python_version >= "3.8"`.
+pub fn parse_requirement(input: &str) -> Result<Requirement, ParseError> {
+    delimited(space0, requirement, space0)
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+/// Parses a requirements file the way pip reads it: a `\` at the end of a
+/// line continues it, `#` starts a comment at the start of a line or after
+/// white space, and lines starting with `-` are options. Errors point into
+/// `input`.
+pub fn parse_requirements(input: &str) -> Result<Requirements, ParseError> {
+    let mut requirements = Requirements::default();
+    let mut offset = 0;
+    let mut pending: Option<(usize, String)> = None;
+    for raw in input.split_inclusive('\n') {
+        let start = offset;
+        offset += raw.len();
+        let text = raw.trim_end_matches(['\n', '\r']);
+        let (start, mut line) = pending.take().unwrap_or((start, String::new()));
+        match text.strip_suffix('\\') {
+            Some(text) => {
+                line.push_str(text);
+                pending = Some((start, line));
+            }
+            None => {
+                line.push_str(text);
+                logical_line(&line, start, &mut requirements)?;
+            }
+        }
+    }
+    if let Some((start, line)) = pending {
+        logical_line(&line, start, &mut requirements)?;
+    }
+    Ok(requirements)
+}
+
+fn logical_line(
+    line: &str,
+    start: usize,
+    requirements: &mut Requirements,
+) -> Result<(), ParseError> {
+    let comment = line
+        .char_indices()
+        .find(|&(i, c)| c == '#' && (i == 0 || line[..i].ends_with([' ', '\t'])))
+        .map_or(line.len(), |(i, _)| i);
+    let text = line[..comment].trim_end();
+    let trimmed = text.trim_start();
+    if trimmed.is_empty() {
+        return Ok(());
+    }
+    let at = start + (text.len() - trimmed.len());
+    let error = |e: ParseError| ParseError::new(at + e.offset(), e.message());
+
+    if trimmed.starts_with('-') {
+        let (name, value) = option(trimmed);
+        let required = || {
+            value.clone().ok_or_else(|| {
+                ParseError::new(at + trimmed.len(), format!("`{name}` needs a value"))
+            })
+        };
+        let line = match name.as_str() {
+            "r" | "requirement" => Line::Include(required()?),
+            "c" | "constraint" => Line::Constraint(required()?),
+            "e" | "editable" => Line::Editable(required()?),
+            _ => Line::Option { name, value },
+        };
+        requirements.lines.push(line);
+        return Ok(());
+    }
+
+    // Per-requirement options follow the requirement after white space.
+    let split = [" --", "\t--"]
+        .iter()
+        .filter_map(|sep| trimmed.find(sep))
+        .min()
+        .unwrap_or(trimmed.len());
+    let requirement = parse_requirement(&trimmed[..split]).map_err(error)?;
+    let mut hashes = Vec::new();
+    let mut words = trimmed[split..].split_whitespace();
+    while let Some(word) = words.next() {
+        let (name, value) = match word.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (word, words.next()),
+        };
+        match (name, value) {
+            ("--hash", Some(hash)) => hashes.push(hash.to_string()),
+            _ => {
+                let offset = at + word.as_ptr() as usize - trimmed.as_ptr() as usize;
+                return Err(ParseError::new(
+                    offset,
+                    format!("unsupported option `{name}`"),
+                ));
+            }
+        }
+    }
+    requirements.lines.push(Line::Requirement {
+        requirement,
+        hashes,
+    });
+    Ok(())
+}
+
+/// `-r file`, `-rfile`, `--requirement file` or `--requirement=file`.
+fn option(text: &str) -> (String, Option<String>) {
+    let (flag, rest) = text.split_once([' ', '\t']).unwrap_or((text, ""));
+    let rest = rest.trim();
+    let rest = (!rest.is_empty()).then(|| rest.to_string());
+    if let Some(long) = flag.strip_prefix("--") {
+        return match long.split_once('=') {
+            Some((name, value)) => (name.to_string(), Some(value.to_string())),
+            None => (long.to_string(), rest),
+        };
+    }
+    let short = &flag[1..];
+    match short.char_indices().nth(1) {
+        Some((i, _)) => (short[..i].to_string(), Some(short[i..].to_string())),
+        None => (short.to_string(), rest),
+    }
+}
+
+fn requirement(input: &mut &str) -> ModalResult<Requirement> {
+    let name = identifier
+        .context(StrContext::Label("requirement"))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "a project name",
+        )))
+        .parse_next(input)?;
+    let extras: Vec<&str> = opt(delimited(
+        ('[', space0),
+        separated(0.., identifier, (space0, ',', space0)),
+        (space0, cut_err(']')),
+    ))
+    .parse_next(input)?
+    .unwrap_or_default();
+    space0.parse_next(input)?;
+    let url = opt(preceded(
+        ('@', space0),
+        cut_err(take_till(1.., |c: char| c.is_whitespace()))
+            .context(StrContext::Expected(StrContextValue::Description("a URL"))),
+    ))
+    .parse_next(input)?;
+    let (specifiers, marker) = match url {
+        // After a URL the `;` must be preceded by white space.
+        Some(_) => (
+            Vec::new(),
+            opt(preceded((space1, ';'), cut_err(marker))).parse_next(input)?,
+        ),
+        None => (
+            alt((
+                delimited(('(', space0), specifiers, (space0, cut_err(')'))),
+                specifiers,
+            ))
+            .parse_next(input)?,
+            opt(preceded((space0, ';'), cut_err(marker))).parse_next(input)?,
+        ),
+    };
+    Ok(Requirement {
+        name: name.to_string(),
+        extras: extras.into_iter().map(String::from).collect(),
+        specifiers,
+        url: url.map(String::from),
+        marker,
+    })
+}
+
+fn identifier<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    take_while(1.., |c: char| {
+        c.is_ascii_alphanumeric() || "._-".contains(c)
+    })
+    .parse_next(input)
+}
+
+fn specifiers(input: &mut &str) -> ModalResult<Vec<Specifier>> {
+    separated(0.., preceded(space0, specifier), (space0, ',')).parse_next(input)
+}
+
+fn specifier(input: &mut &str) -> ModalResult<Specifier> {
+    let op = op.parse_next(input)?;
+    space0.parse_next(input)?;
+    let version = cut_err(take_while(1.., |c: char| {
+        c.is_ascii_alphanumeric() || "-_.*+!".contains(c)
+    }))
+    .context(StrContext::Label("specifier"))
+    .context(StrContext::Expected(StrContextValue::Description(
+        "a version",
+    )))
+    .parse_next(input)?;
+    Ok(Specifier {
+        op,
+        version: version.to_string(),
+    })
+}
+
+fn op(input: &mut &str) -> ModalResult<Op> {
+    alt((
+        "===".value(Op::Arbitrary),
+        "~=".value(Op::Compatible),
+        "==".value(Op::Equal),
+        "!=".value(Op::NotEqual),
+        "<=".value(Op::LessEq),
+        ">=".value(Op::GreaterEq),
+        "<".value(Op::Less),
+        ">".value(Op::Greater),
+    ))
+    .parse_next(input)
+}
+
+/// `or` binds looser than `and`.
+fn marker(input: &mut &str) -> ModalResult<Marker> {
+    let first = marker_and.parse_next(input)?;
+    let rest: Vec<_> =
+        repeat(0.., preceded(keyword("or"), cut_err(marker_and))).parse_next(input)?;
+    Ok(rest
+        .into_iter()
+        .fold(first, |a, b| Marker::Or(Box::new(a), Box::new(b))))
+}
+
+fn marker_and(input: &mut &str) -> ModalResult<Marker> {
+    let first = marker_atom.parse_next(input)?;
+    let rest: Vec<_> =
+        repeat(0.., preceded(keyword("and"), cut_err(marker_atom))).parse_next(input)?;
+    Ok(rest
+        .into_iter()
+        .fold(first, |a, b| Marker::And(Box::new(a), Box::new(b))))
+}
+
+fn keyword(word: &'static str) -> impl FnMut(&mut &str) -> ModalResult<()> {
+    move |input: &mut &str| {
+        (
+            space0,
+            word,
+            not(take_while(1, |c: char| {
+                c.is_ascii_alphanumeric() || c == '_'
+            })),
+        )
+            .void()
+            .parse_next(input)
+    }
+}
+
+fn marker_atom(input: &mut &str) -> ModalResult<Marker> {
+    space0.parse_next(input)?;
+    alt((
+        delimited(('(', space0), marker, (space0, cut_err(')'))),
+        (
+            marker_value,
+            preceded(space0, marker_op),
+            preceded(space0, cut_err(marker_value)),
+        )
+            .map(|(left, op, right)| Marker::Compare { left, op, right }),
+    ))
+    .context(StrContext::Label("marker"))
+    .parse_next(input)
+}
+
+fn marker_op(input: &mut &str) -> ModalResult<MarkerOp> {
+    alt((
+        op.map(MarkerOp::Version),
+        terminated("in", space1).value(MarkerOp::In),
+        ("not", space1, "in", space1).value(MarkerOp::NotIn),
+    ))
+    .parse_next(input)
+}
+
+fn marker_value(input: &mut &str) -> ModalResult<MarkerValue> {
+    alt((
+        delimited('"', take_till(0.., '"'), cut_err('"'))
+            .map(|s: &str| MarkerValue::Literal(s.to_string())),
+        delimited('\'', take_till(0.., '\''), cut_err('\''))
+            .map(|s: &str| MarkerValue::Literal(s.to_string())),
+        take_while(1.., |c: char| {
+            c.is_ascii_alphanumeric() || c == '_' || c == '.'
+        })
+        .map(|s: &str| MarkerValue::Variable(s.to_string())),
+    ))
+    .parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &str = "\
+# production dependencies
+--index-url https://pypi.org/simple
+-r base.txt
+-c constraints.txt
+-e git+https://github.com/psf/requests.git#egg=requests
+Django>=4.2,<5  # LTS
+requests[security, socks] ~= 2.31
+pywin32 >= 306 ; sys_platform == \"win32\" and python_version >= '3.8'
+numpy==1.26.4 \\
+    --hash=sha256:aaaa \\
+    --hash=sha256:bbbb
+pip @ https://github.com/pypa/pip/archive/22.0.2.zip ; python_version < \"3.12\"
+";
+
+    #[test]
+    fn parse_requirements_should_work() -> Result<(), ParseError> {
+        let file = parse_requirements(FILE)?;
+        assert_eq!(
+            file.lines[0],
+            Line::Option {
+                name: "index-url".into(),
+                value: Some("https://pypi.org/simple".into())
+            }
+        );
+        assert_eq!(file.lines[1], Line::Include("base.txt".into()));
+        assert_eq!(file.lines[2], Line::Constraint("constraints.txt".into()));
+        assert_eq!(
+            file.lines[3],
+            Line::Editable("git+https://github.com/psf/requests.git#egg=requests".into())
+        );
+        let requirements: Vec<_> = file.requirements().collect();
+        assert_eq!(requirements[0].to_string(), "Django>=4.2,<5");
+        assert_eq!(requirements[1].extras, ["security", "socks"]);
+        assert_eq!(requirements[1].specifiers[0].op, Op::Compatible);
+        assert_eq!(
+            requirements[2].to_string(),
+            "pywin32>=306; sys_platform == \"win32\" and python_version >= \"3.8\""
+        );
+        let Line::Requirement { hashes, .. } = &file.lines[7] else {
+            panic!("expected numpy");
+        };
+        assert_eq!(hashes, &["sha256:aaaa", "sha256:bbbb"]);
+        assert_eq!(
+            requirements[4].url.as_deref(),
+            Some("https://github.com/pypa/pip/archive/22.0.2.zip")
+        );
+        assert!(requirements[4].marker.is_some());
+
+        let mut file = file;
+        file.expand_includes(&mut |path| (path == "base.txt").then(|| "six\n".to_string()))?;
+        assert_eq!(file.requirements().next().unwrap().name, "six");
+        Ok(())
+    }
+
+    #[test]
+    fn markers_should_evaluate() -> Result<(), ParseError> {
+        let linux = |name: &str| match name {
+            "sys_platform" => Some("linux".to_string()),
+            "python_version" => Some("3.10".to_string()),
+            _ => None,
+        };
+        let applies = |s: &str| parse_requirement(s).map(|r| r.applies(&linux));
+        assert!(!applies("pywin32; sys_platform == 'win32'")?);
+        assert!(applies(
+            "a; python_version > '3.9' and python_version != '3.11'"
+        )?);
+        assert!(applies(
+            "a; sys_platform == 'win32' or (python_version ~= '3.8' and 'linux' in sys_platform)"
+        )?);
+        assert!(!applies("a; python_version < '3.8.0'")?);
+        assert!(applies("a; 'docs' not in extra")?);
+        let marker = parse_requirement(
+            "a; (os_name == 'nt' or os_name == 'posix') and implementation_name == 'cpython'",
+        )?;
+        assert_eq!(
+            marker.to_string(),
+            "a; (os_name == \"nt\" or os_name == \"posix\") and implementation_name == \"cpython\""
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_requirements_should_report_errors() {
+        let err = parse_requirements("six\nDjango>=\n").unwrap_err();
+        assert_eq!(err.offset(), 12);
+        assert!(parse_requirements("numpy --no-binary :all:\n").is_err());
+        assert!(parse_requirements("-r\n").is_err());
+        assert!(parse_requirement("a[b").is_err());
+        assert!(parse_requirement("a; os_name ==").is_err());
+        assert!(parse_requirement("a; (os_name == 'nt'").is_err());
+    }
+}
@@ -0,0 +1,209 @@
+/// A parsed `robots.txt`, following RFC 9309 and the way Google reads it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Robots {
+    pub groups: Vec<Group>,
+    /// `Sitemap:` URLs, which apply to every crawler wherever they appear.
+    pub sitemaps: Vec<String>,
+}
+
+/// Rules shared by the user agents listed on consecutive lines above them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Group {
+    pub user_agents: Vec<String>,
+    pub rules: Vec<Rule>,
+    /// Seconds; not part of the RFC, but widely honored.
+    pub crawl_delay: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub allow: bool,
+    /// A path prefix where `*` matches anything and a trailing `$` anchors
+    /// the end.
+    pub pattern: String,
+}
+
+impl Robots {
+    /// Whether `user_agent` may fetch `path`. Of the rules that match, the
+    /// longest pattern wins and `Allow` wins a tie; no match means allowed.
+    ///
+    /// `user_agent` is a product token such as `Googlebot` or a whole
+    /// `User-Agent` header as found in access logs.
+    pub fn is_allowed(&self, user_agent: &str, path: &str) -> bool {
+        if path == "/robots.txt" {
+            return true;
+        }
+        self.groups_for(user_agent)
+            .flat_map(|group| &group.rules)
+            .filter(|rule| rule.matches(path))
+            .max_by_key(|rule| (rule.pattern.len(), rule.allow))
+            .is_none_or(|rule| rule.allow)
+    }
+
+    /// The crawl delay the groups for `user_agent` ask for.
+    pub fn crawl_delay(&self, user_agent: &str) -> Option<f64> {
+        self.groups_for(user_agent)
+            .find_map(|group| group.crawl_delay)
+    }
+
+    /// Groups naming the most specific agent that applies, falling back to
+    /// `*`. Groups for the same agent are merged, as crawlers do.
+    fn groups_for<'a>(&'a self, user_agent: &str) -> impl Iterator<Item = &'a Group> {
+        let user_agent = user_agent.to_ascii_lowercase();
+        let token = user_agent
+            .split(['/', ' '])
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        let best = self
+            .groups
+            .iter()
+            .flat_map(|group| &group.user_agents)
+            .map(|agent| agent.to_ascii_lowercase())
+            .filter(|agent| *agent != "*")
+            .filter(|agent| *agent == token || user_agent.contains(agent.as_str()))
+            .max_by_key(|agent| (*agent == token, agent.len()))
+            .unwrap_or_else(|| "*".to_string());
+        self.groups.iter().filter(move |group| {
+            group
+                .user_agents
+                .iter()
+                .any(|agent| agent.eq_ignore_ascii_case(&best))
+        })
+    }
+}
+
+impl Rule {
+    pub fn matches(&self, path: &str) -> bool {
+        // An empty `Disallow:` disallows nothing.
+        if self.pattern.is_empty() {
+            return false;
+        }
+        let (pattern, anchored) = match self.pattern.strip_suffix('$') {
+            Some(pattern) => (pattern, true),
+            None => (self.pattern.as_str(), false),
+        };
+        wildcard(pattern.as_bytes(), path.as_bytes(), anchored)
+    }
+}
+
+fn wildcard(pattern: &[u8], path: &[u8], anchored: bool) -> bool {
+    match pattern.split_first() {
+        None => !anchored || path.is_empty(),
+        Some((b'*', rest)) => (0..=path.len()).any(|i| wildcard(rest, &path[i..], anchored)),
+        Some((c, rest)) => path.first() == Some(c) && wildcard(rest, &path[1..], anchored),
+    }
+}
+
+/// Parses a `robots.txt` file. Crawlers have to cope with whatever a site
+/// serves, so lines that are not `key: value` pairs and unknown keys are
+/// skipped rather than rejected, and rules before any `User-agent` line
+/// are dropped.
+pub fn parse_robots(input: &str) -> Robots {
+    let mut robots = Robots::default();
+    // Whether the last group still collects user agents.
+    let mut open = false;
+    for line in input.lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "user-agent" | "useragent" | "user agent" => {
+                if !open {
+                    robots.groups.push(Group::default());
+                    open = true;
+                }
+                if let Some(group) = robots.groups.last_mut() {
+                    group.user_agents.push(value.to_string());
+                }
+            }
+            "sitemap" | "site-map" if !value.is_empty() => robots.sitemaps.push(value.to_string()),
+            key => {
+                let Some(group) = robots.groups.last_mut() else {
+                    continue;
+                };
+                open = false;
+                match key {
+                    "allow" => group.rules.push(Rule {
+                        allow: true,
+                        pattern: value.to_string(),
+                    }),
+                    "disallow" | "dissallow" | "disalow" => group.rules.push(Rule {
+                        allow: false,
+                        pattern: value.to_string(),
+                    }),
+                    "crawl-delay" => group.crawl_delay = value.parse().ok(),
+                    _ => {}
+                }
+            }
+        }
+    }
+    robots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROBOTS: &str = "\
+# example
+User-agent: Googlebot
+User-agent: bingbot
+Disallow: /private/
+Allow: /private/press*.html$
+Crawl-delay: 2.5
+
+user-agent: *
+disallow: /
+allow: /$
+Sitemap: https://example.com/sitemap.xml
+
+User-agent: googlebot
+Disallow: /tmp
+";
+
+    #[test]
+    fn parse_robots_should_group_rules() {
+        let robots = parse_robots(ROBOTS);
+        assert_eq!(robots.groups.len(), 3);
+        assert_eq!(robots.groups[0].user_agents, ["Googlebot", "bingbot"]);
+        assert_eq!(robots.groups[0].rules.len(), 2);
+        assert_eq!(robots.groups[0].crawl_delay, Some(2.5));
+        assert_eq!(robots.sitemaps, ["https://example.com/sitemap.xml"]);
+        assert_eq!(robots.crawl_delay("BingBot"), Some(2.5));
+        assert_eq!(robots.crawl_delay("other"), None);
+
+        let robots = parse_robots("Disallow: /\n<html>\nUser-agent: *\nNoindex: /x");
+        assert_eq!(robots.groups.len(), 1);
+        assert!(robots.groups[0].rules.is_empty());
+    }
+
+    #[test]
+    fn is_allowed_should_prefer_the_longest_match() {
+        let robots = parse_robots(ROBOTS);
+        assert!(!robots.is_allowed("Googlebot", "/private/a.html"));
+        assert!(robots.is_allowed("Googlebot", "/private/press-2024.html"));
+        assert!(!robots.is_allowed("Googlebot", "/private/press-2024.html?x"));
+        assert!(!robots.is_allowed("Googlebot", "/tmp/x"));
+        assert!(robots.is_allowed("Googlebot", "/public"));
+        assert!(robots.is_allowed("bingbot", "/tmp/x"));
+        assert!(robots.is_allowed("curl", "/"));
+        assert!(!robots.is_allowed("curl", "/index.html"));
+        assert!(robots.is_allowed("curl", "/robots.txt"));
+
+        let tie = parse_robots("User-agent: *\nDisallow: /page\nAllow: /page");
+        assert!(tie.is_allowed("x", "/page"));
+        assert!(parse_robots("User-agent: *\nDisallow:").is_allowed("x", "/"));
+    }
+
+    #[test]
+    fn is_allowed_should_match_full_user_agent_headers() {
+        let robots = parse_robots(ROBOTS);
+        let ua = "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)";
+        assert!(!robots.is_allowed(ua, "/tmp/x"));
+        assert!(!robots.is_allowed("Mozilla/5.0 (X11; Linux x86_64)", "/a"));
+        assert!(Robots::default().is_allowed("anyone", "/anything"));
+    }
+}
@@ -0,0 +1,550 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{digit1, space0};
+use winnow::combinator::{alt, cut_err, opt, preceded, separated};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::{one_of, take_while};
+
+use crate::ParseError;
+
+/// A semantic version; ordering follows the SemVer 2.0 precedence rules, so
+/// build metadata is ignored by comparisons.
+#[derive(Debug, Clone, Eq)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Vec<Identifier>,
+    pub build: Vec<String>,
+}
+
+/// A dot-separated pre-release identifier.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Identifier {
+    // Declared first: numeric identifiers sort below alphanumeric ones.
+    Numeric(u64),
+    AlphaNumeric(String),
+}
+
+/// Comparator sets joined by `||`; a version matches when every comparator
+/// of at least one set accepts it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionReq {
+    pub sets: Vec<Vec<Comparator>>,
+}
+
+/// A single constraint such as `>=1.2` or `~0.3.1`. Missing components
+/// stay `None`, which matters for the caret and tilde semantics.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comparator {
+    pub op: Op,
+    pub major: u64,
+    pub minor: Option<u64>,
+    pub patch: Option<u64>,
+    pub pre: Vec<Identifier>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Exact,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+    Tilde,
+    Caret,
+    /// `1.*`, `1.2.x`; a bare `*` is a set with no comparators at all.
+    Wildcard,
+}
+
+impl Version {
+    pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+            pre: Vec::new(),
+            build: Vec::new(),
+        }
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| cmp_pre(&self.pre, &other.pre))
+    }
+}
+
+/// A version without a pre-release sorts after every pre-release of it.
+fn cmp_pre(a: &[Identifier], b: &[Identifier]) -> Ordering {
+    match (a.is_empty(), b.is_empty()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => a.cmp(b),
+    }
+}
+
+impl VersionReq {
+    pub fn matches(&self, version: &Version) -> bool {
+        self.sets.iter().any(|set| {
+            set.iter().all(|c| c.matches(version))
+                // Pre-releases only match when a comparator opts into that
+                // exact major.minor.patch with a pre-release of its own.
+                && (version.pre.is_empty() || set.iter().any(|c| c.allows_pre(version)))
+        })
+    }
+}
+
+impl Comparator {
+    pub fn matches(&self, v: &Version) -> bool {
+        match self.op {
+            Op::Exact | Op::Wildcard => self.matches_exact(v),
+            Op::Greater => self.matches_greater(v),
+            Op::GreaterEq => self.matches_exact(v) || self.matches_greater(v),
+            Op::Less => self.matches_less(v),
+            Op::LessEq => self.matches_exact(v) || self.matches_less(v),
+            Op::Tilde => self.matches_tilde(v),
+            Op::Caret => self.matches_caret(v),
+        }
+    }
+
+    fn allows_pre(&self, v: &Version) -> bool {
+        self.major == v.major
+            && self.minor == Some(v.minor)
+            && self.patch == Some(v.patch)
+            && !self.pre.is_empty()
+    }
+
+    fn matches_exact(&self, v: &Version) -> bool {
+        v.major == self.major
+            && self.minor.is_none_or(|minor| v.minor == minor)
+            && match self.patch {
+                Some(patch) => v.patch == patch && v.pre == self.pre,
+                None => true,
+            }
+    }
+
+    fn matches_greater(&self, v: &Version) -> bool {
+        if v.major != self.major {
+            return v.major > self.major;
+        }
+        let Some(minor) = self.minor else {
+            return false;
+        };
+        if v.minor != minor {
+            return v.minor > minor;
+        }
+        let Some(patch) = self.patch else {
+            return false;
+        };
+        if v.patch != patch {
+            return v.patch > patch;
+        }
+        cmp_pre(&v.pre, &self.pre) == Ordering::Greater
+    }
+
+    fn matches_less(&self, v: &Version) -> bool {
+        if v.major != self.major {
+            return v.major < self.major;
+        }
+        let Some(minor) = self.minor else {
+            return false;
+        };
+        if v.minor != minor {
+            return v.minor < minor;
+        }
+        let Some(patch) = self.patch else {
+            return false;
+        };
+        if v.patch != patch {
+            return v.patch < patch;
+        }
+        cmp_pre(&v.pre, &self.pre) == Ordering::Less
+    }
+
+    /// `~1.2.3` is `>=1.2.3, <1.3.0`; `~1.2` and `~1` behave like `=`.
+    fn matches_tilde(&self, v: &Version) -> bool {
+        match (self.minor, self.patch) {
+            (Some(minor), Some(patch)) => {
+                v.major == self.major
+                    && v.minor == minor
+                    && (v.patch > patch || (v.patch == patch && cmp_pre(&v.pre, &self.pre).is_ge()))
+            }
+            _ => self.matches_exact(v),
+        }
+    }
+
+    /// The leftmost non-zero component must stay the same.
+    fn matches_caret(&self, v: &Version) -> bool {
+        if v.major != self.major {
+            return false;
+        }
+        let Some(minor) = self.minor else {
+            return true;
+        };
+        let Some(patch) = self.patch else {
+            return if self.major > 0 {
+                v.minor >= minor
+            } else {
+                v.minor == minor
+            };
+        };
+        let at_least = (v.minor, v.patch) > (minor, patch)
+            || ((v.minor, v.patch) == (minor, patch) && cmp_pre(&v.pre, &self.pre).is_ge());
+        if self.major > 0 {
+            at_least
+        } else if minor > 0 {
+            v.minor == minor && at_least
+        } else {
+            v.minor == 0 && v.patch == patch && cmp_pre(&v.pre, &self.pre).is_ge()
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        write_pre(f, &self.pre)?;
+        if !self.build.is_empty() {
+            write!(f, "+{}", self.build.join("."))?;
+        }
+        Ok(())
+    }
+}
+
+fn write_pre(f: &mut fmt::Formatter<'_>, pre: &[Identifier]) -> fmt::Result {
+    for (i, id) in pre.iter().enumerate() {
+        f.write_str(if i == 0 { "-" } else { "." })?;
+        match id {
+            Identifier::Numeric(n) => write!(f, "{n}")?,
+            Identifier::AlphaNumeric(s) => f.write_str(s)?,
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for Comparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self.op {
+            Op::Exact => "=",
+            Op::Greater => ">",
+            Op::GreaterEq => ">=",
+            Op::Less => "<",
+            Op::LessEq => "<=",
+            Op::Tilde => "~",
+            Op::Caret => "^",
+            Op::Wildcard => "",
+        })?;
+        write!(f, "{}", self.major)?;
+        for part in [self.minor, self.patch] {
+            match part {
+                Some(n) => write!(f, ".{n}")?,
+                None if self.op == Op::Wildcard => return f.write_str(".*"),
+                None => return Ok(()),
+            }
+        }
+        write_pre(f, &self.pre)
+    }
+}
+
+impl fmt::Display for VersionReq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, set) in self.sets.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" || ")?;
+            }
+            if set.is_empty() {
+                f.write_str("*")?;
+            }
+            for (j, comparator) in set.iter().enumerate() {
+                if j > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{comparator}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Version {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_version(s)
+    }
+}
+
+impl FromStr for VersionReq {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_version_req(s)
+    }
+}
+
+/// Parses a full `MAJOR.MINOR.PATCH[-pre][+build]` version.
+pub fn parse_version(input: &str) -> Result<Version, ParseError> {
+    version.parse(input).map_err(ParseError::from)
+}
+
+/// Parses a requirement such as `^1.2`, `>=1, <2` or `~0.3.1 || >=2.0.0-rc.1`.
+/// A bare version means `^`, as in Cargo, and comparators may be separated
+/// by commas or, npm style, by whitespace alone.
+pub fn parse_version_req(input: &str) -> Result<VersionReq, ParseError> {
+    separated(1.., comparator_set, (space0, "||", space0))
+        .map(|sets| VersionReq { sets })
+        .parse(input.trim())
+        .map_err(ParseError::from)
+}
+
+fn version(input: &mut &str) -> ModalResult<Version> {
+    let major = number.parse_next(input)?;
+    let minor = cut_err(preceded('.', number)).parse_next(input)?;
+    let patch = cut_err(preceded('.', number)).parse_next(input)?;
+    let pre = opt(preceded('-', cut_err(pre_release)))
+        .parse_next(input)?
+        .unwrap_or_default();
+    let build = opt(preceded('+', cut_err(build_metadata)))
+        .parse_next(input)?
+        .unwrap_or_default();
+    Ok(Version {
+        major,
+        minor,
+        patch,
+        pre,
+        build,
+    })
+}
+
+/// A numeric component; leading zeros are not allowed.
+fn number(input: &mut &str) -> ModalResult<u64> {
+    digit1
+        .verify(|d: &str| d == "0" || !d.starts_with('0'))
+        .parse_to()
+        .context(StrContext::Expected(StrContextValue::Description(
+            "number without leading zeros",
+        )))
+        .parse_next(input)
+}
+
+fn pre_release(input: &mut &str) -> ModalResult<Vec<Identifier>> {
+    separated(
+        1..,
+        alphanumeric_word
+            .verify(|w: &str| {
+                !w.bytes().all(|b| b.is_ascii_digit()) || w == "0" || !w.starts_with('0')
+            })
+            .map(|w: &str| match w.parse() {
+                Ok(n) if w.bytes().all(|b| b.is_ascii_digit()) => Identifier::Numeric(n),
+                _ => Identifier::AlphaNumeric(w.to_string()),
+            }),
+        '.',
+    )
+    .context(StrContext::Label("pre-release"))
+    .parse_next(input)
+}
+
+fn build_metadata(input: &mut &str) -> ModalResult<Vec<String>> {
+    separated(1.., alphanumeric_word.map(str::to_string), '.')
+        .context(StrContext::Label("build metadata"))
+        .parse_next(input)
+}
+
+fn alphanumeric_word<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    take_while(1.., |c: char| c.is_ascii_alphanumeric() || c == '-').parse_next(input)
+}
+
+/// A version component or one of the `*`, `x`, `X` wildcards.
+fn part(input: &mut &str) -> ModalResult<Option<u64>> {
+    alt((number.map(Some), one_of(['*', 'x', 'X']).value(None))).parse_next(input)
+}
+
+fn comparator_set(input: &mut &str) -> ModalResult<Vec<Comparator>> {
+    let set: Vec<Option<Comparator>> = separated(
+        1..,
+        comparator,
+        alt(((space0, ',', space0).void(), (' ', space0).void())),
+    )
+    .parse_next(input)?;
+    // `*` accepts everything, so it adds no constraint to the set.
+    Ok(set.into_iter().flatten().collect())
+}
+
+fn comparator(input: &mut &str) -> ModalResult<Option<Comparator>> {
+    let op = opt(alt((
+        ">=".value(Op::GreaterEq),
+        "<=".value(Op::LessEq),
+        ">".value(Op::Greater),
+        "<".value(Op::Less),
+        "=".value(Op::Exact),
+        "~".value(Op::Tilde),
+        "^".value(Op::Caret),
+    )))
+    .parse_next(input)?;
+    space0.parse_next(input)?;
+    // Without an operator this may be the `||` after a set, so only commit
+    // once one was seen.
+    let major = match op {
+        Some(_) => cut_err(part)
+            .context(StrContext::Label("version requirement"))
+            .parse_next(input)?,
+        None => part.parse_next(input)?,
+    };
+    let Some(major) = major else {
+        return Ok(None);
+    };
+    let minor = opt(preceded('.', cut_err(part))).parse_next(input)?;
+    let patch = match minor {
+        Some(Some(_)) => opt(preceded('.', cut_err(part))).parse_next(input)?,
+        _ => None,
+    };
+    let wildcard = minor == Some(None) || patch == Some(None);
+    let (minor, patch) = (minor.flatten(), patch.flatten());
+    let pre = match patch {
+        Some(_) => opt(preceded('-', cut_err(pre_release)))
+            .parse_next(input)?
+            .unwrap_or_default(),
+        None => Vec::new(),
+    };
+    // Build metadata never affects matching.
+    opt(preceded('+', cut_err(build_metadata))).parse_next(input)?;
+    let op = match op {
+        Some(op) => op,
+        None if wildcard => Op::Wildcard,
+        None => Op::Caret,
+    };
+    Ok(Some(Comparator {
+        op,
+        major,
+        minor,
+        patch,
+        pre,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(s: &str) -> Version {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_version_should_work() -> Result<(), ParseError> {
+        let version = parse_version("1.2.3-alpha.10.beta+build.007")?;
+        assert_eq!((version.major, version.minor, version.patch), (1, 2, 3));
+        assert_eq!(
+            version.pre,
+            [
+                Identifier::AlphaNumeric("alpha".into()),
+                Identifier::Numeric(10),
+                Identifier::AlphaNumeric("beta".into())
+            ]
+        );
+        assert_eq!(version.build, ["build", "007"]);
+        assert_eq!(version.to_string(), "1.2.3-alpha.10.beta+build.007");
+
+        let mut versions: Vec<Version> = [
+            "1.0.0",
+            "1.0.0-rc.1",
+            "1.0.0-beta.11",
+            "1.0.0-beta.2",
+            "1.0.0-alpha.beta",
+            "1.0.0-alpha.1",
+            "1.0.0-alpha",
+        ]
+        .into_iter()
+        .map(v)
+        .collect();
+        versions.sort();
+        assert_eq!(
+            versions.iter().map(Version::to_string).collect::<Vec<_>>(),
+            [
+                "1.0.0-alpha",
+                "1.0.0-alpha.1",
+                "1.0.0-alpha.beta",
+                "1.0.0-beta.2",
+                "1.0.0-beta.11",
+                "1.0.0-rc.1",
+                "1.0.0",
+            ]
+        );
+        assert_eq!(v("1.0.0+a"), v("1.0.0+b"));
+
+        assert_eq!(parse_version("1.02.3").unwrap_err().offset(), 2);
+        assert!(parse_version("1.2").is_err());
+        assert!(parse_version("1.2.3-01").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn matches_should_follow_cargo_semantics() -> Result<(), ParseError> {
+        let cases = [
+            ("^1.2", "1.9.0", true),
+            ("^1.2", "2.0.0", false),
+            ("^1.2", "1.1.9", false),
+            ("^0.3.1", "0.3.9", true),
+            ("^0.3.1", "0.4.0", false),
+            ("^0.0.3", "0.0.4", false),
+            ("1.2.3", "1.4.0", true),
+            ("~0.3.1", "0.3.5", true),
+            ("~0.3.1", "0.4.0", false),
+            ("~1", "1.9.9", true),
+            (">=1, <2", "1.5.0", true),
+            (">=1, <2", "2.0.0", false),
+            (">= 1.2.0 < 1.3", "1.2.7", true),
+            ("1.*", "1.7.3", true),
+            ("1.2.x", "1.3.0", false),
+            ("*", "3.1.4", true),
+            ("<1 || >=3", "3.0.0", true),
+            ("<1 || >=3", "2.0.0", false),
+            ("=1.2.3", "1.2.3+meta", true),
+            // Pre-releases need an explicit opt-in on the same version.
+            (">=1.0.0", "2.0.0-rc.1", false),
+            (">=2.0.0-rc.1", "2.0.0-rc.2", true),
+            (">=2.0.0-rc.1", "2.0.1-rc.1", false),
+            ("^1.2.3-beta", "1.2.3", true),
+        ];
+        for (req, version, expected) in cases {
+            let parsed = parse_version_req(req)?;
+            assert_eq!(parsed.matches(&v(version)), expected, "{req} vs {version}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn parse_version_req_should_work() -> Result<(), ParseError> {
+        let req = parse_version_req(">=1.2, <2.0.0-0 || 3.x || *")?;
+        assert_eq!(req.sets.len(), 3);
+        assert_eq!(req.sets[0][0].op, Op::GreaterEq);
+        assert_eq!(req.sets[0][1].pre, [Identifier::Numeric(0)]);
+        assert_eq!(req.to_string(), ">=1.2, <2.0.0-0 || 3.* || *");
+        assert_eq!(parse_version_req("1.2")?.sets[0][0].op, Op::Caret);
+        assert!(parse_version_req("").is_err());
+        assert!(parse_version_req(">=").is_err());
+        assert!(parse_version_req("^1.2 ||").is_err());
+        assert_eq!(parse_version_req("1.2.3.4").unwrap_err().offset(), 5);
+        Ok(())
+    }
+}
@@ -0,0 +1,306 @@
+use std::fmt;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::combinator::{alt, cut_err, delimited, preceded, repeat, terminated};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::{any, none_of, take_while};
+
+use crate::ParseError;
+use crate::combinators::Trivia;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sexp {
+    Symbol(String),
+    String(String),
+    Integer(i64),
+    Float(f64),
+    /// `#t` and `#f`.
+    Bool(bool),
+    List(Vec<Sexp>),
+}
+
+/// The reader macros and the forms they expand to.
+const SUGAR: [(&str, &str); 4] = [
+    (",@", "unquote-splicing"),
+    ("'", "quote"),
+    ("`", "quasiquote"),
+    (",", "unquote"),
+];
+
+impl Sexp {
+    pub fn symbol(name: impl Into<String>) -> Self {
+        Sexp::Symbol(name.into())
+    }
+
+    pub fn as_symbol(&self) -> Option<&str> {
+        match self {
+            Sexp::Symbol(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Sexp]> {
+        match self {
+            Sexp::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Lays the expression out so no line is longer than `width` where
+    /// possible. Lists that do not fit put each element after the head on
+    /// its own line, indented two spaces.
+    pub fn pretty(&self, width: usize) -> String {
+        let mut out = String::new();
+        self.pretty_into(&mut out, 0, width);
+        out
+    }
+
+    fn pretty_into(&self, out: &mut String, indent: usize, width: usize) {
+        let flat = self.to_string();
+        let items = match self {
+            Sexp::List(items) if indent + flat.len() > width && items.len() > 1 => items,
+            _ => {
+                out.push_str(&flat);
+                return;
+            }
+        };
+        if let Some(sugar) = self.sugar() {
+            out.push_str(sugar);
+            items[1].pretty_into(out, indent + sugar.len(), width);
+            return;
+        }
+        out.push('(');
+        items[0].pretty_into(out, indent + 1, width);
+        for item in &items[1..] {
+            out.push('\n');
+            out.push_str(&" ".repeat(indent + 2));
+            item.pretty_into(out, indent + 2, width);
+        }
+        out.push(')');
+    }
+
+    /// The reader-macro prefix this list prints as, if any.
+    fn sugar(&self) -> Option<&'static str> {
+        let Sexp::List(items) = self else {
+            return None;
+        };
+        match items.as_slice() {
+            [Sexp::Symbol(head), operand] => {
+                SUGAR
+                    .iter()
+                    .find(|(_, name)| name == head)
+                    .map(|(prefix, _)| match (*prefix, operand) {
+                        // `,@x` would read back as unquote-splicing.
+                        (",", Sexp::Symbol(s)) if s.starts_with('@') => ", ",
+                        (prefix, _) => prefix,
+                    })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Sexp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sexp::Symbol(s) => f.write_str(s),
+            Sexp::String(s) => {
+                f.write_str("\"")?;
+                for c in s.chars() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\t' => f.write_str("\\t")?,
+                        c => write!(f, "{c}")?,
+                    }
+                }
+                f.write_str("\"")
+            }
+            Sexp::Integer(n) => write!(f, "{n}"),
+            // Keep a decimal point so the value reads back as a float.
+            Sexp::Float(x) if x.is_finite() && x.fract() == 0.0 => write!(f, "{x:.1}"),
+            Sexp::Float(x) => write!(f, "{x}"),
+            Sexp::Bool(true) => f.write_str("#t"),
+            Sexp::Bool(false) => f.write_str("#f"),
+            Sexp::List(items) => {
+                if let Some(sugar) = self.sugar() {
+                    return write!(f, "{sugar}{}", items[1]);
+                }
+                f.write_str("(")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+/// Parses exactly one expression.
+pub fn parse_sexp(input: &str) -> Result<Sexp, ParseError> {
+    delimited(trivia, datum, trivia)
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+/// Parses a sequence of top-level expressions, as in a config file.
+pub fn parse_sexps(input: &str) -> Result<Vec<Sexp>, ParseError> {
+    preceded(trivia, repeat(0.., terminated(datum, trivia)))
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+fn datum(input: &mut &str) -> ModalResult<Sexp> {
+    alt((list, quoted, string.map(Sexp::String), atom))
+        .context(StrContext::Label("expression"))
+        .parse_next(input)
+}
+
+fn list(input: &mut &str) -> ModalResult<Sexp> {
+    preceded(
+        '(',
+        cut_err(terminated(
+            preceded(trivia, repeat(0.., terminated(datum, trivia))),
+            ')',
+        ))
+        .context(StrContext::Expected(StrContextValue::CharLiteral(')'))),
+    )
+    .map(Sexp::List)
+    .parse_next(input)
+}
+
+/// `'x` reads as `(quote x)`, and likewise for the other reader macros.
+fn quoted(input: &mut &str) -> ModalResult<Sexp> {
+    let name = alt((
+        ",@".value(SUGAR[0].1),
+        '\''.value(SUGAR[1].1),
+        '`'.value(SUGAR[2].1),
+        ','.value(SUGAR[3].1),
+    ))
+    .parse_next(input)?;
+    let quoted = cut_err(preceded(trivia, datum)).parse_next(input)?;
+    Ok(Sexp::List(vec![Sexp::symbol(name), quoted]))
+}
+
+fn string(input: &mut &str) -> ModalResult<String> {
+    preceded(
+        '"',
+        cut_err(terminated(
+            repeat(
+                0..,
+                alt((
+                    preceded(
+                        '\\',
+                        any.verify_map(|c| match c {
+                            'n' => Some('\n'),
+                            't' => Some('\t'),
+                            'r' => Some('\r'),
+                            '"' | '\\' => Some(c),
+                            _ => None,
+                        }),
+                    ),
+                    none_of(['"', '\\']),
+                )),
+            ),
+            '"',
+        ))
+        .context(StrContext::Label("string")),
+    )
+    .parse_next(input)
+}
+
+/// Numbers, booleans and symbols share one token syntax and are told apart
+/// after reading.
+fn atom(input: &mut &str) -> ModalResult<Sexp> {
+    take_while(1.., |c: char| {
+        !c.is_whitespace() && !matches!(c, '(' | ')' | '"' | ';' | '\'' | '`' | ',')
+    })
+    .map(|token: &str| {
+        let numeric = token
+            .trim_start_matches(['+', '-'])
+            .starts_with(|c: char| c.is_ascii_digit() || c == '.');
+        match token {
+            "#t" => Sexp::Bool(true),
+            "#f" => Sexp::Bool(false),
+            _ if numeric => token
+                .parse()
+                .map(Sexp::Integer)
+                .or_else(|_| token.parse().map(Sexp::Float))
+                .unwrap_or_else(|_| Sexp::symbol(token)),
+            _ => Sexp::symbol(token),
+        }
+    })
+    .parse_next(input)
+}
+
+/// White space and `;` line comments.
+fn trivia(input: &mut &str) -> ModalResult<()> {
+    let mut trivia = Trivia::LISP;
+    trivia.parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sym(s: &str) -> Sexp {
+        Sexp::symbol(s)
+    }
+
+    #[test]
+    fn parse_sexp_should_work() -> Result<(), ParseError> {
+        let sexp = parse_sexp("(define (f x) ; doubles\n  (* 2.5 x -3 \"a\\\"b\" #t 1+))")?;
+        assert_eq!(
+            sexp,
+            Sexp::List(vec![
+                sym("define"),
+                Sexp::List(vec![sym("f"), sym("x")]),
+                Sexp::List(vec![
+                    sym("*"),
+                    Sexp::Float(2.5),
+                    sym("x"),
+                    Sexp::Integer(-3),
+                    Sexp::String("a\"b".to_string()),
+                    Sexp::Bool(true),
+                    sym("1+"),
+                ]),
+            ])
+        );
+        assert_eq!(parse_sexp("`(a ,b ,@c 'd)")?.to_string(), "`(a ,b ,@c 'd)");
+        assert_eq!(parse_sexp("'x")?, Sexp::List(vec![sym("quote"), sym("x")]));
+        let unquote = parse_sexp(", @x")?;
+        assert_eq!(unquote, Sexp::List(vec![sym("unquote"), sym("@x")]));
+        assert_eq!(unquote.to_string(), ", @x");
+        assert_eq!(parse_sexp(&unquote.to_string())?, unquote);
+        assert_eq!(parse_sexps("a (b) ; end")?.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn pretty_should_break_long_lists() -> Result<(), ParseError> {
+        let sexp = parse_sexp("(server (listen 8080) (root \"/var/www\") (tags 'web))")?;
+        assert_eq!(sexp.pretty(80), sexp.to_string());
+        assert_eq!(
+            sexp.pretty(24),
+            "(server\n  (listen 8080)\n  (root \"/var/www\")\n  (tags 'web))"
+        );
+        assert_eq!(parse_sexp(&sexp.pretty(10))?, sexp);
+        assert_eq!(Sexp::Float(2.0).to_string(), "2.0");
+        Ok(())
+    }
+
+    #[test]
+    fn parse_sexp_should_report_errors() {
+        assert_eq!(parse_sexp("(a (b c)").unwrap_err().offset(), 8);
+        assert!(parse_sexp("\"open").is_err());
+        assert!(parse_sexp("'").is_err());
+        assert!(parse_sexp("a b").is_err());
+        assert!(parse_sexp(")").is_err());
+        assert!(parse_sexp("\"bad \\q escape\"").is_err());
+    }
+}
@@ -0,0 +1,239 @@
+use std::borrow::Cow;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::combinator::{alt, cut_err, delimited, opt, preceded, repeat, terminated};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::{any, one_of, take_till, take_while};
+
+use crate::ParseError;
+use crate::combinators::Trivia;
+
+/// Which quoting rules to split by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// `sh` quoting: `'...'`, `"..."` and backslash escapes, plus `#`
+    /// comments at the start of a word.
+    #[default]
+    Posix,
+    /// The rules of the Microsoft C runtime, which `CommandLineToArgvW`
+    /// also follows: backslashes are literal unless they precede a `"`.
+    Windows,
+}
+
+/// Splits a command line into its arguments the way `sh` would, without
+/// expanding variables or globs. Unterminated quotes and a trailing
+/// backslash are errors.
+pub fn parse_shellwords(input: &str) -> Result<Vec<String>, ParseError> {
+    parse_shellwords_with(input, Mode::Posix)
+}
+
+/// Like [`parse_shellwords`], with a choice of rules. Windows command lines
+/// have no invalid form, so that mode never fails.
+pub fn parse_shellwords_with(input: &str, mode: Mode) -> Result<Vec<String>, ParseError> {
+    match mode {
+        Mode::Posix => preceded(blank, repeat(0.., terminated(word, blank)))
+            .parse(input)
+            .map_err(ParseError::from),
+        Mode::Windows => Ok(split_windows(input)),
+    }
+}
+
+/// Quotes each argument only where `sh` needs it and joins them with
+/// spaces, so that `parse_shellwords` gives the arguments back.
+pub fn join<S: AsRef<str>>(args: &[S]) -> String {
+    let quote = |arg: &str| {
+        let safe = |c: char| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c);
+        if !arg.is_empty() && arg.chars().all(safe) {
+            arg.to_string()
+        } else {
+            format!("'{}'", arg.replace('\'', r"'\''"))
+        }
+    };
+    args.iter()
+        .map(|arg| quote(arg.as_ref()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// White space, comments and line continuations between words.
+fn blank(input: &mut &str) -> ModalResult<()> {
+    let mut trivia = Trivia::HASH;
+    loop {
+        trivia.parse_next(input)?;
+        if opt("\\\n").parse_next(input)?.is_none() {
+            return Ok(());
+        }
+    }
+}
+
+fn word(input: &mut &str) -> ModalResult<String> {
+    repeat(
+        1..,
+        alt((
+            single_quoted.map(Cow::Borrowed),
+            double_quoted,
+            escaped.map(Cow::Borrowed),
+            unquoted.map(Cow::Borrowed),
+        )),
+    )
+    .fold(String::new, |mut word, part| {
+        word.push_str(&part);
+        word
+    })
+    .parse_next(input)
+}
+
+fn unquoted<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    take_while(1.., |c: char| {
+        !c.is_whitespace() && !matches!(c, '\'' | '"' | '\\')
+    })
+    .parse_next(input)
+}
+
+/// A backslash outside quotes keeps the next character, except that a
+/// backslash-newline disappears.
+fn escaped<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    preceded(
+        '\\',
+        cut_err(any.take())
+            .context(StrContext::Label("escape"))
+            .context(StrContext::Expected(StrContextValue::Description(
+                "a character after `\\`",
+            ))),
+    )
+    .map(|c| if c == "\n" { "" } else { c })
+    .parse_next(input)
+}
+
+fn single_quoted<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    delimited(
+        '\'',
+        take_till(0.., '\''),
+        cut_err('\'').context(StrContext::Expected(StrContextValue::CharLiteral('\''))),
+    )
+    .parse_next(input)
+}
+
+/// Inside double quotes a backslash only escapes `$`, `` ` ``, `"`, `\`
+/// and newline; before anything else it is literal.
+fn double_quoted<'i>(input: &mut &'i str) -> ModalResult<Cow<'i, str>> {
+    preceded(
+        '"',
+        cut_err(terminated(
+            repeat(
+                0..,
+                alt((
+                    preceded('\\', one_of(['$', '`', '"', '\\', '\n']).take())
+                        .map(|c| if c == "\n" { "" } else { c }),
+                    "\\",
+                    take_while(1.., |c| c != '"' && c != '\\'),
+                )),
+            )
+            .fold(
+                || Cow::Borrowed(""),
+                |quoted: Cow<'i, str>, part| match quoted {
+                    Cow::Borrowed("") => Cow::Borrowed(part),
+                    quoted => Cow::Owned(quoted.into_owned() + part),
+                },
+            ),
+            '"',
+        ))
+        .context(StrContext::Expected(StrContextValue::CharLiteral('"'))),
+    )
+    .parse_next(input)
+}
+
+fn split_windows(input: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut arg = String::new();
+    let mut in_word = false;
+    let mut quoted = false;
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' if !quoted => {
+                if in_word {
+                    args.push(std::mem::take(&mut arg));
+                    in_word = false;
+                }
+            }
+            '\\' => {
+                in_word = true;
+                let mut count = 1;
+                while chars.next_if_eq(&'\\').is_some() {
+                    count += 1;
+                }
+                if chars.peek() == Some(&'"') {
+                    arg.extend(std::iter::repeat_n('\\', count / 2));
+                    if count % 2 == 1 {
+                        arg.push('"');
+                        chars.next();
+                    }
+                } else {
+                    arg.extend(std::iter::repeat_n('\\', count));
+                }
+            }
+            '"' => {
+                in_word = true;
+                // `""` inside quotes is a literal quote.
+                if quoted && chars.next_if_eq(&'"').is_some() {
+                    arg.push('"');
+                } else {
+                    quoted = !quoted;
+                }
+            }
+            c => {
+                in_word = true;
+                arg.push(c);
+            }
+        }
+    }
+    if in_word {
+        args.push(arg);
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_shellwords_should_work() -> Result<(), ParseError> {
+        assert_eq!(
+            parse_shellwords(r#"grep -e 'a b' "c \"d\" \x" e\ f '' # comment"#)?,
+            ["grep", "-e", "a b", r#"c "d" \x"#, "e f", ""]
+        );
+        assert_eq!(
+            parse_shellwords("ls \\\n  -l x#y 'it'\\''s'")?,
+            ["ls", "-l", "x#y", "it's"]
+        );
+        assert!(parse_shellwords("  ")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn windows_mode_should_follow_crt_rules() -> Result<(), ParseError> {
+        let split = |s| parse_shellwords_with(s, Mode::Windows);
+        assert_eq!(split(r#"a\\\"b "c d" e\f"#)?, [r#"a\"b"#, "c d", r"e\f"]);
+        assert_eq!(
+            split(r#"C:\dir\\ "x""y" "unterminated"#)?,
+            [r"C:\dir\\", r#"x"y"#, "unterminated"]
+        );
+        assert_eq!(split(r#""" a\\"b c""#)?, ["", r"a\b c"]);
+        Ok(())
+    }
+
+    #[test]
+    fn join_should_round_trip() -> Result<(), ParseError> {
+        let args = ["echo", "it's", "", "a b", "--x=1"];
+        let line = join(&args);
+        assert_eq!(line, r"echo 'it'\''s' '' 'a b' --x=1");
+        assert_eq!(parse_shellwords(&line)?, args);
+        assert_eq!(parse_shellwords("echo 'open").unwrap_err().offset(), 10);
+        assert!(parse_shellwords("echo \"open").is_err());
+        assert!(parse_shellwords("trailing\\").is_err());
+        Ok(())
+    }
+}
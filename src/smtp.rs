@@ -0,0 +1,434 @@
+use std::fmt;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{Caseless, digit1, space1};
+use winnow::combinator::{alt, cut_err, eof, fail, opt, preceded, repeat, terminated};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::{one_of, take_till, take_while};
+
+use crate::ParseError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Helo(String),
+    Ehlo(String),
+    /// `from` is empty for the null reverse path `<>`, as used by bounces.
+    Mail {
+        from: String,
+        params: Vec<Param>,
+    },
+    Rcpt {
+        to: String,
+        params: Vec<Param>,
+    },
+    Data,
+    /// `BDAT` from the CHUNKING extension.
+    Bdat {
+        size: u64,
+        last: bool,
+    },
+    Rset,
+    Vrfy(String),
+    Expn(String),
+    Help(Option<String>),
+    Noop(Option<String>),
+    Quit,
+    StartTls,
+    Auth {
+        mechanism: String,
+        initial_response: Option<String>,
+    },
+}
+
+/// An ESMTP parameter such as `SIZE=1024` or `SMTPUTF8`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    pub keyword: String,
+    pub value: Option<String>,
+}
+
+/// A reply, joined from all of its lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reply {
+    pub code: u16,
+    /// The RFC 3463 code at the start of the text, if the server sent one.
+    pub enhanced: Option<EnhancedStatus>,
+    /// The text of each line, without the code or the enhanced status.
+    pub lines: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplyClass {
+    /// 2xx
+    Success,
+    /// 3xx, e.g. the go-ahead after `DATA`.
+    Intermediate,
+    /// 4xx; the client may try again later.
+    TransientFailure,
+    /// 5xx
+    PermanentFailure,
+}
+
+/// A `class.subject.detail` code such as `5.1.1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnhancedStatus {
+    pub class: u8,
+    pub subject: u16,
+    pub detail: u16,
+}
+
+impl fmt::Display for EnhancedStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.class, self.subject, self.detail)
+    }
+}
+
+impl Reply {
+    pub fn class(&self) -> ReplyClass {
+        match self.code / 100 {
+            2 => ReplyClass::Success,
+            3 => ReplyClass::Intermediate,
+            4 => ReplyClass::TransientFailure,
+            _ => ReplyClass::PermanentFailure,
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        matches!(self.class(), ReplyClass::Success | ReplyClass::Intermediate)
+    }
+
+    /// All lines joined with a space.
+    pub fn text(&self) -> String {
+        self.lines.join(" ")
+    }
+
+    /// The parameters of an extension advertised in an `EHLO` reply, matched
+    /// case-insensitively. The first line is the greeting and is skipped.
+    pub fn extension(&self, keyword: &str) -> Option<&str> {
+        self.lines.iter().skip(1).find_map(|line| {
+            let (name, params) = line.split_once(' ').unwrap_or((line, ""));
+            name.eq_ignore_ascii_case(keyword).then_some(params)
+        })
+    }
+}
+
+/// Parses one command line. The line ending is optional.
+pub fn parse_command(input: &str) -> Result<Command, ParseError> {
+    terminated(command, opt(line_end))
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+/// Parses one reply, which may span several lines.
+pub fn parse_reply(input: &str) -> Result<Reply, ParseError> {
+    reply.parse(input).map_err(ParseError::from)
+}
+
+/// Parses replies sent back to back, as when commands are pipelined.
+pub fn parse_replies(input: &str) -> Result<Vec<Reply>, ParseError> {
+    repeat(0.., reply).parse(input).map_err(ParseError::from)
+}
+
+fn command(input: &mut &str) -> ModalResult<Command> {
+    let start = *input;
+    let verb = take_while(1.., |c: char| c.is_ascii_alphabetic()).parse_next(input)?;
+    let command = match verb.to_ascii_uppercase().as_str() {
+        "HELO" => Command::Helo(argument(input, "domain")?),
+        "EHLO" => Command::Ehlo(argument(input, "domain")?),
+        "MAIL" => {
+            let from = path(input, "FROM:")?;
+            Command::Mail {
+                from,
+                params: params(input)?,
+            }
+        }
+        "RCPT" => {
+            let to = path(input, "TO:")?;
+            if to.is_empty() {
+                return cut_err(fail)
+                    .context(StrContext::Label("recipient must not be empty"))
+                    .parse_next(input);
+            }
+            Command::Rcpt {
+                to,
+                params: params(input)?,
+            }
+        }
+        "DATA" => Command::Data,
+        "BDAT" => Command::Bdat {
+            size: cut_err(preceded(space1, digit1.parse_to()))
+                .context(StrContext::Label("chunk size"))
+                .parse_next(input)?,
+            last: opt(preceded(space1, Caseless("LAST")))
+                .parse_next(input)?
+                .is_some(),
+        },
+        "RSET" => Command::Rset,
+        "VRFY" => Command::Vrfy(argument(input, "user")?),
+        "EXPN" => Command::Expn(argument(input, "list")?),
+        "HELP" => Command::Help(opt(|i: &mut &str| argument(i, "topic")).parse_next(input)?),
+        "NOOP" => Command::Noop(opt(|i: &mut &str| argument(i, "argument")).parse_next(input)?),
+        "QUIT" => Command::Quit,
+        "STARTTLS" => Command::StartTls,
+        "AUTH" => Command::Auth {
+            mechanism: cut_err(preceded(space1, word))
+                .context(StrContext::Label("mechanism"))
+                .parse_next(input)?
+                .to_ascii_uppercase(),
+            initial_response: opt(preceded(space1, word.map(str::to_string))).parse_next(input)?,
+        },
+        _ => {
+            *input = start;
+            return cut_err(fail)
+                .context(StrContext::Label("unknown command"))
+                .parse_next(input);
+        }
+    };
+    cut_err(alt((line_end, eof.void())))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "end of line",
+        )))
+        .parse_next(input)?;
+    Ok(command)
+}
+
+/// The rest of the line after a space.
+fn argument(input: &mut &str, label: &'static str) -> ModalResult<String> {
+    preceded(
+        space1,
+        cut_err(take_till(1.., ['\r', '\n'])).context(StrContext::Label(label)),
+    )
+    .map(|s: &str| s.trim_end().to_string())
+    .parse_next(input)
+}
+
+/// `FROM:<path>` or `TO:<path>`, returning the address without brackets.
+/// Source routes (`<@a,@b:user@c>`) are dropped as RFC 5321 asks.
+fn path(input: &mut &str, prefix: &'static str) -> ModalResult<String> {
+    preceded(
+        (
+            cut_err((space1, Caseless(prefix)))
+                .context(StrContext::Expected(StrContextValue::StringLiteral(prefix))),
+            // Some clients put a space after the colon.
+            opt(space1),
+        ),
+        cut_err(preceded(
+            '<',
+            terminated(take_till(0.., ['>', '\r', '\n']), '>'),
+        ))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "an address in angle brackets",
+        ))),
+    )
+    .map(|path: &str| match path.split_once(':') {
+        Some((route, mailbox)) if route.starts_with('@') => mailbox.to_string(),
+        _ => path.to_string(),
+    })
+    .parse_next(input)
+}
+
+fn params(input: &mut &str) -> ModalResult<Vec<Param>> {
+    repeat(
+        0..,
+        preceded(
+            space1,
+            (
+                take_while(1.., |c: char| c.is_ascii_alphanumeric() || c == '-'),
+                opt(preceded(
+                    '=',
+                    take_while(1.., |c: char| c.is_ascii_graphic() && c != '='),
+                )),
+            ),
+        )
+        .map(|(keyword, value): (&str, Option<&str>)| Param {
+            keyword: keyword.to_ascii_uppercase(),
+            value: value.map(str::to_string),
+        }),
+    )
+    .parse_next(input)
+}
+
+fn word<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    take_while(1.., |c: char| c.is_ascii_graphic()).parse_next(input)
+}
+
+fn reply(input: &mut &str) -> ModalResult<Reply> {
+    let mut code = None;
+    let mut lines = Vec::new();
+    loop {
+        // Once a reply has started, every line must continue it.
+        let line_code = if lines.is_empty() {
+            reply_code.parse_next(input)?
+        } else {
+            cut_err(reply_code).parse_next(input)?
+        };
+        if *code.get_or_insert(line_code) != line_code {
+            return cut_err(fail)
+                .context(StrContext::Label("reply lines must share one code"))
+                .parse_next(input);
+        }
+        let separator = opt(one_of([' ', '-'])).parse_next(input)?;
+        // A bare code is a final line with no text.
+        let text = match separator {
+            Some(_) => take_till(0.., ['\r', '\n']).parse_next(input)?,
+            None => "",
+        };
+        cut_err(line_end).parse_next(input)?;
+        lines.push(text.to_string());
+        if separator != Some('-') {
+            break;
+        }
+    }
+    let code = code.unwrap();
+    let enhanced = lines
+        .first()
+        .and_then(|line| enhanced_status(line))
+        .filter(|status| u16::from(status.class) == code / 100);
+    if let Some(status) = enhanced {
+        let prefix = status.to_string();
+        for line in &mut lines {
+            if let Some(rest) = line.strip_prefix(&prefix) {
+                *line = rest.trim_start().to_string();
+            }
+        }
+    }
+    Ok(Reply {
+        code,
+        enhanced,
+        lines,
+    })
+}
+
+fn reply_code(input: &mut &str) -> ModalResult<u16> {
+    (
+        one_of('2'..='5'),
+        take_while(2, |c: char| c.is_ascii_digit()),
+    )
+        .take()
+        .parse_to()
+        .context(StrContext::Label("reply code"))
+        .parse_next(input)
+}
+
+fn enhanced_status(line: &str) -> Option<EnhancedStatus> {
+    let token = line.split(' ').next()?;
+    let mut parts = token.split('.');
+    let class = parts.next()?.parse().ok()?;
+    let subject = parts.next()?.parse().ok()?;
+    let detail = parts.next()?.parse().ok()?;
+    (parts.next().is_none() && matches!(class, 2 | 4 | 5)).then_some(EnhancedStatus {
+        class,
+        subject,
+        detail,
+    })
+}
+
+fn line_end(input: &mut &str) -> ModalResult<()> {
+    alt(("\r\n".void(), "\n".void()))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "line ending",
+        )))
+        .parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_command_should_work() -> Result<(), ParseError> {
+        assert_eq!(
+            parse_command("EHLO mail.example.com\r\n")?,
+            Command::Ehlo("mail.example.com".to_string())
+        );
+        assert_eq!(
+            parse_command("mail from:<alice@example.com> SIZE=1024 SMTPUTF8")?,
+            Command::Mail {
+                from: "alice@example.com".to_string(),
+                params: vec![
+                    Param {
+                        keyword: "SIZE".to_string(),
+                        value: Some("1024".to_string())
+                    },
+                    Param {
+                        keyword: "SMTPUTF8".to_string(),
+                        value: None
+                    },
+                ],
+            }
+        );
+        assert!(matches!(
+            parse_command("MAIL FROM:<>\r\n")?,
+            Command::Mail { from, .. } if from.is_empty()
+        ));
+        assert!(matches!(
+            parse_command("RCPT TO: <@relay.example:bob@example.org>")?,
+            Command::Rcpt { to, .. } if to == "bob@example.org"
+        ));
+        assert_eq!(
+            parse_command("BDAT 512 LAST")?,
+            Command::Bdat {
+                size: 512,
+                last: true
+            }
+        );
+        assert_eq!(
+            parse_command("auth plain AGFsaWNlAHNlY3JldA==")?,
+            Command::Auth {
+                mechanism: "PLAIN".to_string(),
+                initial_response: Some("AGFsaWNlAHNlY3JldA==".to_string())
+            }
+        );
+        assert_eq!(parse_command("NOOP")?, Command::Noop(None));
+        assert_eq!(parse_command("quit\r\n")?, Command::Quit);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_reply_should_work() -> Result<(), ParseError> {
+        let ehlo = parse_reply(
+            "250-mx.example.com at your service\r\n\
+             250-SIZE 35882577\r\n\
+             250-8BITMIME\r\n\
+             250 STARTTLS\r\n",
+        )?;
+        assert_eq!(ehlo.code, 250);
+        assert_eq!(ehlo.lines.len(), 4);
+        assert_eq!(ehlo.extension("size"), Some("35882577"));
+        assert_eq!(ehlo.extension("STARTTLS"), Some(""));
+        assert_eq!(ehlo.extension("mx.example.com"), None);
+        assert_eq!(ehlo.enhanced, None);
+
+        let bounce = parse_reply(
+            "550-5.1.1 The email account that you tried to reach does not exist.\r\n\
+             550 5.1.1 Please check the address.\r\n",
+        )?;
+        assert_eq!(bounce.class(), ReplyClass::PermanentFailure);
+        assert_eq!(bounce.enhanced.unwrap().to_string(), "5.1.1");
+        assert_eq!(
+            bounce.text(),
+            "The email account that you tried to reach does not exist. Please check the address."
+        );
+
+        let replies = parse_replies("250 2.1.0 Ok\r\n354\r\n421 4.7.0 Try again later\n")?;
+        assert_eq!(replies.len(), 3);
+        assert_eq!(replies[1].class(), ReplyClass::Intermediate);
+        assert_eq!(replies[1].lines, [""]);
+        assert!(!replies[2].is_success());
+        Ok(())
+    }
+
+    #[test]
+    fn parsers_should_report_errors() {
+        assert_eq!(parse_reply("250-a\r\n251 b\r\n").unwrap_err().offset(), 10);
+        assert_eq!(parse_reply("250-a\r\n").unwrap_err().offset(), 7);
+        assert_eq!(parse_command("FOO bar").unwrap_err().offset(), 0);
+        assert_eq!(parse_command("MAIL TO:<a@b>").unwrap_err().offset(), 5);
+        assert!(parse_command("RCPT TO:<>").is_err());
+        assert!(parse_command("MAIL FROM:alice@example.com").is_err());
+        assert!(parse_command("HELO").is_err());
+        assert!(parse_reply("250x").is_err());
+        assert!(parse_reply("hello\r\n").is_err());
+    }
+}
@@ -0,0 +1,780 @@
+use std::borrow::Cow;
+use std::fmt;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{Caseless, digit1};
+use winnow::combinator::{
+    alt, cut_err, delimited, eof, not, opt, peek, preceded, repeat, separated, terminated,
+};
+use winnow::error::{ContextError, ErrMode, StrContext, StrContextValue};
+use winnow::token::{one_of, take_till, take_while};
+
+use crate::ParseError;
+use crate::combinators::Trivia;
+use crate::pratt::{Assoc, Pratt};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Select {
+    pub distinct: bool,
+    pub projection: Vec<SelectItem>,
+    pub from: Option<TableRef>,
+    pub joins: Vec<Join>,
+    pub selection: Option<Expr>,
+    pub group_by: Vec<Expr>,
+    pub having: Option<Expr>,
+    pub order_by: Vec<OrderBy>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectItem {
+    /// `*`
+    Wildcard,
+    /// `t.*`
+    QualifiedWildcard(String),
+    Expr {
+        expr: Expr,
+        alias: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableRef {
+    /// Possibly schema-qualified, e.g. `["public", "users"]`.
+    pub name: Vec<String>,
+    pub alias: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Join {
+    pub kind: JoinKind,
+    pub table: TableRef,
+    /// The `ON` condition; absent for cross joins.
+    pub on: Option<Expr>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinKind {
+    Inner,
+    Left,
+    Right,
+    Full,
+    Cross,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderBy {
+    pub expr: Expr,
+    pub descending: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// A possibly qualified column name, e.g. `u.name`.
+    Identifier(Vec<String>),
+    Literal(Literal),
+    Unary {
+        op: UnaryOp,
+        expr: Box<Expr>,
+    },
+    Binary {
+        left: Box<Expr>,
+        op: BinaryOp,
+        right: Box<Expr>,
+    },
+    IsNull {
+        expr: Box<Expr>,
+        negated: bool,
+    },
+    InList {
+        expr: Box<Expr>,
+        list: Vec<Expr>,
+        negated: bool,
+    },
+    Between {
+        expr: Box<Expr>,
+        low: Box<Expr>,
+        high: Box<Expr>,
+        negated: bool,
+    },
+    Like {
+        expr: Box<Expr>,
+        pattern: Box<Expr>,
+        negated: bool,
+    },
+    /// A function call; `COUNT(*)` has no arguments and `star` set.
+    Function {
+        name: String,
+        args: Vec<Expr>,
+        distinct: bool,
+        star: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Boolean(bool),
+    Null,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Not,
+    Minus,
+    Plus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    Concat,
+    Plus,
+    Minus,
+    Multiply,
+    Divide,
+    Modulo,
+}
+
+impl BinaryOp {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BinaryOp::Or => "OR",
+            BinaryOp::And => "AND",
+            BinaryOp::Eq => "=",
+            BinaryOp::NotEq => "<>",
+            BinaryOp::Lt => "<",
+            BinaryOp::LtEq => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::GtEq => ">=",
+            BinaryOp::Concat => "||",
+            BinaryOp::Plus => "+",
+            BinaryOp::Minus => "-",
+            BinaryOp::Multiply => "*",
+            BinaryOp::Divide => "/",
+            BinaryOp::Modulo => "%",
+        }
+    }
+
+    fn binding_power(self) -> u8 {
+        match self {
+            BinaryOp::Or => OR_BP,
+            BinaryOp::And => AND_BP,
+            BinaryOp::Concat => CONCAT_BP,
+            BinaryOp::Plus | BinaryOp::Minus => SUM_BP,
+            BinaryOp::Multiply | BinaryOp::Divide | BinaryOp::Modulo => PRODUCT_BP,
+            _ => COMPARE_BP,
+        }
+    }
+}
+
+/// Binding powers, loosest first. Every infix operator is left associative.
+const OR_BP: u8 = 1;
+const AND_BP: u8 = 3;
+const NOT_BP: u8 = 5;
+const COMPARE_BP: u8 = 7;
+const CONCAT_BP: u8 = 9;
+const SUM_BP: u8 = 11;
+const PRODUCT_BP: u8 = 13;
+const UNARY_BP: u8 = 15;
+
+const RESERVED: &[&str] = &[
+    "all", "and", "as", "asc", "between", "by", "cross", "desc", "distinct", "false", "from",
+    "full", "group", "having", "in", "inner", "is", "join", "left", "like", "limit", "not", "null",
+    "offset", "on", "or", "order", "outer", "right", "select", "true", "where",
+];
+
+/// Parses one `SELECT` statement, optionally terminated by `;`.
+pub fn parse_select(input: &str) -> Result<Select, ParseError> {
+    delimited(ws, select, (ws, opt(';'), ws, eof))
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+/// Parses a standalone expression, e.g. a `WHERE` condition.
+pub fn parse_expr(input: &str) -> Result<Expr, ParseError> {
+    delimited(ws, expr, (ws, eof))
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+/// Whitespace and `--` comments.
+fn ws(input: &mut &str) -> ModalResult<()> {
+    let mut ws = Trivia::new().line_comments(&["--"]);
+    ws.parse_next(input)
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// A case-insensitive keyword that is not the prefix of a longer word.
+fn kw<'i>(word: &'static str) -> impl Parser<&'i str, &'i str, ErrMode<ContextError>> {
+    terminated(Caseless(word), not(one_of(is_ident_char)))
+        .context(StrContext::Expected(StrContextValue::StringLiteral(word)))
+}
+
+/// A token followed by optional whitespace.
+fn lex<'i, O>(
+    parser: impl Parser<&'i str, O, ErrMode<ContextError>>,
+) -> impl Parser<&'i str, O, ErrMode<ContextError>> {
+    terminated(parser, ws)
+}
+
+fn select(input: &mut &str) -> ModalResult<Select> {
+    lex(kw("select")).parse_next(input)?;
+    let distinct = opt(lex(alt((
+        kw("distinct").value(true),
+        kw("all").value(false),
+    ))))
+    .parse_next(input)?
+    .unwrap_or(false);
+    let projection = cut_err(separated(1.., lex(select_item), lex(',')))
+        .context(StrContext::Label("projection"))
+        .parse_next(input)?;
+
+    let mut from = None;
+    let mut joins = Vec::new();
+    if opt(lex(kw("from"))).parse_next(input)?.is_some() {
+        from = Some(
+            cut_err(table_ref)
+                .context(StrContext::Label("FROM clause"))
+                .parse_next(input)?,
+        );
+        joins = repeat(0.., join).parse_next(input)?;
+    }
+    let selection = opt(preceded(
+        lex(kw("where")),
+        cut_err(expr).context(StrContext::Label("WHERE clause")),
+    ))
+    .parse_next(input)?;
+    let group_by = opt(preceded(
+        (lex(kw("group")), cut_err(lex(kw("by")))),
+        cut_err(separated(1.., expr, lex(','))).context(StrContext::Label("GROUP BY clause")),
+    ))
+    .parse_next(input)?
+    .unwrap_or_default();
+    let having = opt(preceded(
+        lex(kw("having")),
+        cut_err(expr).context(StrContext::Label("HAVING clause")),
+    ))
+    .parse_next(input)?;
+    let order_by = opt(preceded(
+        (lex(kw("order")), cut_err(lex(kw("by")))),
+        cut_err(separated(1.., order_by_item, lex(',')))
+            .context(StrContext::Label("ORDER BY clause")),
+    ))
+    .parse_next(input)?
+    .unwrap_or_default();
+    let limit = opt(preceded(
+        lex(kw("limit")),
+        cut_err(lex(unsigned)).context(StrContext::Label("LIMIT clause")),
+    ))
+    .parse_next(input)?;
+    let offset = opt(preceded(
+        lex(kw("offset")),
+        cut_err(lex(unsigned)).context(StrContext::Label("OFFSET clause")),
+    ))
+    .parse_next(input)?;
+
+    Ok(Select {
+        distinct,
+        projection,
+        from,
+        joins,
+        selection,
+        group_by,
+        having,
+        order_by,
+        limit,
+        offset,
+    })
+}
+
+fn unsigned(input: &mut &str) -> ModalResult<u64> {
+    digit1.parse_to().parse_next(input)
+}
+
+fn select_item(input: &mut &str) -> ModalResult<SelectItem> {
+    alt((
+        '*'.value(SelectItem::Wildcard),
+        terminated(identifier, ('.', '*')).map(SelectItem::QualifiedWildcard),
+        (expr, opt(alias)).map(|(expr, alias)| SelectItem::Expr { expr, alias }),
+    ))
+    .parse_next(input)
+}
+
+/// `AS name`, or just `name` when it is not a keyword.
+fn alias(input: &mut &str) -> ModalResult<String> {
+    lex(alt((
+        preceded(lex(kw("as")), cut_err(identifier)),
+        identifier,
+    )))
+    .parse_next(input)
+}
+
+fn table_ref(input: &mut &str) -> ModalResult<TableRef> {
+    let name = lex(separated(1.., identifier, '.')).parse_next(input)?;
+    let alias = opt(alias).parse_next(input)?;
+    Ok(TableRef { name, alias })
+}
+
+fn join(input: &mut &str) -> ModalResult<Join> {
+    let kind = alt((
+        lex(',').value(JoinKind::Cross),
+        terminated(
+            alt((
+                lex(kw("cross")).value(JoinKind::Cross),
+                lex(kw("inner")).value(JoinKind::Inner),
+                terminated(lex(kw("left")), opt(lex(kw("outer")))).value(JoinKind::Left),
+                terminated(lex(kw("right")), opt(lex(kw("outer")))).value(JoinKind::Right),
+                terminated(lex(kw("full")), opt(lex(kw("outer")))).value(JoinKind::Full),
+                peek(kw("join")).value(JoinKind::Inner),
+            )),
+            cut_err(lex(kw("join"))),
+        ),
+    ))
+    .parse_next(input)?;
+    let table = cut_err(table_ref)
+        .context(StrContext::Label("join"))
+        .parse_next(input)?;
+    let on = if kind == JoinKind::Cross {
+        None
+    } else {
+        Some(
+            cut_err(preceded(lex(kw("on")), expr))
+                .context(StrContext::Label("join condition"))
+                .parse_next(input)?,
+        )
+    };
+    Ok(Join { kind, table, on })
+}
+
+fn order_by_item(input: &mut &str) -> ModalResult<OrderBy> {
+    let expr = expr(input)?;
+    let descending = opt(lex(alt((kw("asc").value(false), kw("desc").value(true)))))
+        .parse_next(input)?
+        .unwrap_or(false);
+    Ok(OrderBy { expr, descending })
+}
+
+fn identifier(input: &mut &str) -> ModalResult<String> {
+    alt((
+        delimited('"', take_till(0.., '"'), '"').map(String::from),
+        delimited('`', take_till(0.., '`'), '`').map(String::from),
+        (
+            one_of(|c: char| c.is_alphabetic() || c == '_'),
+            take_while(0.., is_ident_char),
+        )
+            .take()
+            .verify(|word: &str| !RESERVED.contains(&word.to_ascii_lowercase().as_str()))
+            .map(String::from),
+    ))
+    .context(StrContext::Expected(StrContextValue::Description(
+        "identifier",
+    )))
+    .parse_next(input)
+}
+
+fn expr(input: &mut &str) -> ModalResult<Expr> {
+    expr_bp(input, 0)
+}
+
+/// An expression whose operators all bind at least as tightly as `min_bp`.
+fn expr_bp(input: &mut &str, min_bp: u8) -> ModalResult<Expr> {
+    operators().parse_at(input, min_bp)
+}
+
+const SYMBOLS: &[(&str, BinaryOp)] = &[
+    ("<=", BinaryOp::LtEq),
+    (">=", BinaryOp::GtEq),
+    ("<>", BinaryOp::NotEq),
+    ("!=", BinaryOp::NotEq),
+    ("||", BinaryOp::Concat),
+    ("=", BinaryOp::Eq),
+    ("<", BinaryOp::Lt),
+    (">", BinaryOp::Gt),
+    ("+", BinaryOp::Plus),
+    ("-", BinaryOp::Minus),
+    ("*", BinaryOp::Multiply),
+    ("/", BinaryOp::Divide),
+    ("%", BinaryOp::Modulo),
+];
+
+fn operators<'i>() -> Pratt<'i, &'i str, Expr, ErrMode<ContextError>> {
+    let binary = |left, op, right| Expr::Binary {
+        left: Box::new(left),
+        op,
+        right: Box::new(right),
+    };
+    let mut pratt = Pratt::new(operand)
+        .prefix(NOT_BP, lex(kw("not")), |_, e| unary(UnaryOp::Not, e))
+        .prefix(UNARY_BP, lex('-'), |_, e| unary(UnaryOp::Minus, e))
+        .prefix(UNARY_BP, lex('+'), |_, e| unary(UnaryOp::Plus, e));
+    for &(symbol, op) in SYMBOLS {
+        pratt = pratt.infix(
+            op.binding_power(),
+            Assoc::Left,
+            lex(symbol.value(op)),
+            binary,
+        );
+    }
+    let is_null = preceded(
+        lex(kw("is")),
+        cut_err(alt((
+            preceded(lex(kw("not")), kw("null")).value(true),
+            kw("null").value(false),
+        )))
+        .context(StrContext::Expected(StrContextValue::StringLiteral("NULL"))),
+    );
+    let list = cut_err(delimited(
+        lex('('),
+        separated(1.., expr, lex(',')),
+        lex(')'),
+    ))
+    .context(StrContext::Label("IN list"));
+    // The bounds bind tighter than AND, which separates them.
+    let between = (
+        negatable("between"),
+        bound,
+        preceded(cut_err(lex(kw("and"))), bound),
+    );
+    pratt
+        .infix(
+            OR_BP,
+            Assoc::Left,
+            lex(kw("or")).value(BinaryOp::Or),
+            binary,
+        )
+        .infix(
+            AND_BP,
+            Assoc::Left,
+            lex(kw("and")).value(BinaryOp::And),
+            binary,
+        )
+        .postfix(COMPARE_BP, lex(is_null), |expr, negated| Expr::IsNull {
+            expr: Box::new(expr),
+            negated,
+        })
+        .postfix(
+            COMPARE_BP,
+            (negatable("in"), list),
+            |expr, (negated, list)| Expr::InList {
+                expr: Box::new(expr),
+                list,
+                negated,
+            },
+        )
+        .postfix(COMPARE_BP, between, |expr, (negated, low, high)| {
+            Expr::Between {
+                expr: Box::new(expr),
+                low: Box::new(low),
+                high: Box::new(high),
+                negated,
+            }
+        })
+        .infix(
+            COMPARE_BP,
+            Assoc::Left,
+            negatable("like"),
+            |expr, negated, pattern| Expr::Like {
+                expr: Box::new(expr),
+                pattern: Box::new(pattern),
+                negated,
+            },
+        )
+}
+
+/// `word` or `NOT word`, telling which it was.
+fn negatable<'i>(word: &'static str) -> impl Parser<&'i str, bool, ErrMode<ContextError>> {
+    (opt(lex(kw("not"))), lex(kw(word))).map(|(not, _)| not.is_some())
+}
+
+fn bound(input: &mut &str) -> ModalResult<Expr> {
+    cut_err(move |i: &mut &str| expr_bp(i, COMPARE_BP + 1))
+        .context(StrContext::Label("expression"))
+        .parse_next(input)
+}
+
+fn operand(input: &mut &str) -> ModalResult<Expr> {
+    lex(alt((
+        delimited(lex('('), cut_err(expr), cut_err(')')),
+        literal.map(Expr::Literal),
+        function,
+        separated(1.., identifier, '.').map(Expr::Identifier),
+    )))
+    .context(StrContext::Expected(StrContextValue::Description(
+        "expression",
+    )))
+    .parse_next(input)
+}
+
+fn unary(op: UnaryOp, expr: Expr) -> Expr {
+    Expr::Unary {
+        op,
+        expr: Box::new(expr),
+    }
+}
+
+fn literal(input: &mut &str) -> ModalResult<Literal> {
+    alt((
+        kw("null").value(Literal::Null),
+        kw("true").value(Literal::Boolean(true)),
+        kw("false").value(Literal::Boolean(false)),
+        string.map(Literal::String),
+        number,
+    ))
+    .parse_next(input)
+}
+
+/// A single-quoted string, where `''` is an escaped quote.
+fn string(input: &mut &str) -> ModalResult<String> {
+    preceded(
+        '\'',
+        cut_err(terminated(
+            repeat(0.., alt((take_till(1.., '\''), "''".value("'")))),
+            '\'',
+        ))
+        .context(StrContext::Label("string"))
+        .context(StrContext::Expected(StrContextValue::CharLiteral('\''))),
+    )
+    .map(|parts: Vec<&str>| parts.concat())
+    .parse_next(input)
+}
+
+fn number(input: &mut &str) -> ModalResult<Literal> {
+    let text = (
+        alt((
+            (digit1, opt(('.', opt(digit1)))).void(),
+            ('.', digit1).void(),
+        )),
+        opt((one_of(['e', 'E']), opt(one_of(['+', '-'])), digit1)),
+        not(one_of(is_ident_char)),
+    )
+        .take()
+        .parse_next(input)?;
+    if let Ok(i) = text.parse() {
+        return Ok(Literal::Integer(i));
+    }
+    // Past `f64::MAX` the text reads as infinity, which has no literal.
+    text.parse()
+        .ok()
+        .filter(|v: &f64| v.is_finite())
+        .map(Literal::Float)
+        .ok_or_else(|| ErrMode::Backtrack(ContextError::new()))
+}
+
+fn function(input: &mut &str) -> ModalResult<Expr> {
+    let name = terminated(identifier, lex('(')).parse_next(input)?;
+    let star = opt(lex('*')).parse_next(input)?.is_some();
+    let (distinct, args) = if star {
+        (false, Vec::new())
+    } else {
+        let distinct = opt(lex(kw("distinct"))).parse_next(input)?.is_some();
+        (distinct, separated(0.., expr, lex(',')).parse_next(input)?)
+    };
+    cut_err(')')
+        .context(StrContext::Label("function call"))
+        .context(StrContext::Expected(StrContextValue::CharLiteral(')')))
+        .parse_next(input)?;
+    Ok(Expr::Function {
+        name,
+        args,
+        distinct,
+        star,
+    })
+}
+
+/// Writes expressions back as SQL, parenthesizing every operation so the
+/// structure is explicit.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let not = |negated: &bool| if *negated { "NOT " } else { "" };
+        match self {
+            Expr::Identifier(parts) => {
+                let parts: Vec<_> = parts.iter().map(|part| quote_identifier(part)).collect();
+                write!(f, "{}", parts.join("."))
+            }
+            Expr::Literal(l) => write!(f, "{}", l),
+            Expr::Unary { op, expr } => match op {
+                UnaryOp::Not => write!(f, "(NOT {})", expr),
+                UnaryOp::Minus => write!(f, "(-{})", expr),
+                UnaryOp::Plus => write!(f, "(+{})", expr),
+            },
+            Expr::Binary { left, op, right } => write!(f, "({} {} {})", left, op.as_str(), right),
+            Expr::IsNull { expr, negated } => write!(f, "({} IS {}NULL)", expr, not(negated)),
+            Expr::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                let list: Vec<_> = list.iter().map(ToString::to_string).collect();
+                write!(f, "({} {}IN ({}))", expr, not(negated), list.join(", "))
+            }
+            Expr::Between {
+                expr,
+                low,
+                high,
+                negated,
+            } => write!(f, "({} {}BETWEEN {} AND {})", expr, not(negated), low, high),
+            Expr::Like {
+                expr,
+                pattern,
+                negated,
+            } => write!(f, "({} {}LIKE {})", expr, not(negated), pattern),
+            Expr::Function {
+                name,
+                args,
+                distinct,
+                star,
+            } => {
+                let args: Vec<_> = args.iter().map(ToString::to_string).collect();
+                let distinct = if *distinct { "DISTINCT " } else { "" };
+                let star = if *star { "*" } else { "" };
+                let name = quote_identifier(name);
+                write!(f, "{}({}{}{})", name, distinct, star, args.join(", "))
+            }
+        }
+    }
+}
+
+/// `name` as written in SQL: bare when it reads back as the same
+/// identifier, otherwise in double quotes, or backticks if it holds a `"`.
+fn quote_identifier(name: &str) -> Cow<'_, str> {
+    let bare = name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && name.chars().all(is_ident_char)
+        && !RESERVED.contains(&name.to_ascii_lowercase().as_str());
+    if bare {
+        Cow::Borrowed(name)
+    } else if name.contains('"') {
+        Cow::Owned(format!("`{name}`"))
+    } else {
+        Cow::Owned(format!("\"{name}\""))
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Literal::Integer(i) => write!(f, "{}", i),
+            Literal::Float(v) => write!(f, "{:?}", v),
+            Literal::String(s) => write!(f, "'{}'", s.replace('\'', "''")),
+            Literal::Boolean(b) => write!(f, "{}", if *b { "TRUE" } else { "FALSE" }),
+            Literal::Null => write!(f, "NULL"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn show(input: &str) -> String {
+        parse_expr(input).unwrap().to_string()
+    }
+
+    #[test]
+    fn parse_expr_should_respect_precedence() {
+        assert_eq!(show("1 + 2 * 3 - 4"), "((1 + (2 * 3)) - 4)");
+        assert_eq!(show("a or b and not c = 1"), "(a OR (b AND (NOT (c = 1))))");
+        assert_eq!(show("-x * 2 || 'it''s'"), "(((-x) * 2) || 'it''s')");
+        assert_eq!(
+            show("t.a between 1 and 2 and b not in (1, 2.5)"),
+            "((t.a BETWEEN 1 AND 2) AND (b NOT IN (1, 2.5)))"
+        );
+        assert_eq!(
+            show("name not like 'a%' or email is not null"),
+            "((name NOT LIKE 'a%') OR (email IS NOT NULL))"
+        );
+        assert_eq!(
+            show("count(distinct id) > 1e3"),
+            "(count(DISTINCT id) > 1000.0)"
+        );
+        assert_eq!(show("(a + b) * c"), "((a + b) * c)");
+        assert_eq!(
+            show(r#""" = "select".`"x"` + "a b"(1)"#),
+            r#"("" = ("select".`"x"` + "a b"(1)))"#
+        );
+    }
+
+    #[test]
+    fn parse_select_should_work() -> Result<(), ParseError> {
+        let select = parse_select(
+            "SELECT DISTINCT u.name AS name, count(*) n, o.*
+             FROM public.users u
+             LEFT OUTER JOIN orders AS o ON o.user_id = u.id
+             CROSS JOIN regions, \"weird table\"
+             WHERE u.active = true -- only active
+             GROUP BY u.name
+             HAVING count(*) >= 2
+             ORDER BY n DESC, name
+             LIMIT 10 OFFSET 20;",
+        )?;
+        assert!(select.distinct);
+        assert_eq!(select.projection.len(), 3);
+        assert_eq!(
+            select.projection[1],
+            SelectItem::Expr {
+                expr: Expr::Function {
+                    name: "count".into(),
+                    args: vec![],
+                    distinct: false,
+                    star: true
+                },
+                alias: Some("n".into())
+            }
+        );
+        assert_eq!(
+            select.projection[2],
+            SelectItem::QualifiedWildcard("o".into())
+        );
+        assert_eq!(
+            select.from,
+            Some(TableRef {
+                name: vec!["public".into(), "users".into()],
+                alias: Some("u".into())
+            })
+        );
+        let kinds: Vec<_> = select.joins.iter().map(|j| j.kind).collect();
+        assert_eq!(kinds, [JoinKind::Left, JoinKind::Cross, JoinKind::Cross]);
+        assert_eq!(select.joins[2].table.name, ["weird table"]);
+        assert_eq!(select.selection.unwrap().to_string(), "(u.active = TRUE)");
+        assert!(select.order_by[0].descending);
+        assert!(!select.order_by[1].descending);
+        assert_eq!((select.limit, select.offset), (Some(10), Some(20)));
+
+        let select = parse_select("select 1")?;
+        assert_eq!(select.from, None);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_select_should_report_errors() {
+        let input = "SELECT a FROM t WHERE a = ";
+        let err = parse_select(input).unwrap_err();
+        assert_eq!(err.offset(), input.len());
+        assert!(err.message().contains("expression"), "{}", err.message());
+
+        assert!(parse_select("SELECT FROM t").is_err());
+        assert!(parse_select("SELECT a FROM t JOIN u").is_err());
+        assert!(parse_select("SELECT a b c").is_err());
+        assert!(parse_select("SELECT 'open").is_err());
+
+        let err = parse_expr("1 + 1e400").unwrap_err();
+        assert_eq!(
+            (err.offset(), err.message()),
+            (4, "expected identifier or expression")
+        );
+    }
+}
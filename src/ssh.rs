@@ -0,0 +1,373 @@
+use crate::ParseError;
+use crate::codec::BASE64;
+
+/// A public key as OpenSSH writes it: `type base64 [comment]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKey {
+    /// `ssh-ed25519`, `ecdsa-sha2-nistp256`, `ssh-rsa-cert-v01@openssh.com`...
+    pub kind: String,
+    /// The decoded wire-format key, which begins with `kind` again.
+    pub blob: Vec<u8>,
+    pub comment: Option<String>,
+}
+
+/// A line of `authorized_keys`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthorizedKey {
+    /// 1-based.
+    pub line: usize,
+    pub options: Vec<KeyOption>,
+    pub key: PublicKey,
+}
+
+/// `no-pty`, or `command="..."` with its quotes and escapes removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyOption {
+    pub name: String,
+    pub value: Option<String>,
+}
+
+impl AuthorizedKey {
+    /// The first option called `name`, ignoring case as sshd does.
+    pub fn option(&self, name: &str) -> Option<&KeyOption> {
+        self.options
+            .iter()
+            .find(|o| o.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Whether the key may only run a forced command.
+    pub fn is_restricted(&self) -> bool {
+        self.option("command").is_some() || self.option("restrict").is_some()
+    }
+}
+
+/// A line of `known_hosts`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KnownHost {
+    /// 1-based.
+    pub line: usize,
+    pub marker: Option<Marker>,
+    pub hosts: Vec<HostPattern>,
+    pub key: PublicKey,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Marker {
+    /// `@cert-authority`: the key signs host certificates.
+    CertAuthority,
+    /// `@revoked`: the key must never be accepted.
+    Revoked,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostPattern {
+    /// A name, address or wildcard pattern such as `*.example.com`, with
+    /// the port of a `[host]:port` entry.
+    Plain {
+        pattern: String,
+        port: Option<u16>,
+        /// A leading `!` excludes the hosts the pattern matches.
+        negated: bool,
+    },
+    /// `|1|salt|hash`: an HMAC-SHA1 of the host name keyed by `salt`.
+    Hashed { salt: Vec<u8>, hash: Vec<u8> },
+}
+
+/// Parses an `authorized_keys` file. Blank lines and `#` comments are
+/// skipped; errors point into `input`.
+pub fn parse_authorized_keys(input: &str) -> Result<Vec<AuthorizedKey>, ParseError> {
+    entries(input, |number, line| {
+        let (options, rest) = if is_key_type(first_word(line)) {
+            (Vec::new(), 0)
+        } else {
+            options(line)?
+        };
+        Ok(AuthorizedKey {
+            line: number,
+            options,
+            key: public_key(line, rest)?,
+        })
+    })
+}
+
+/// Parses a `known_hosts` file. Blank lines and `#` comments are skipped;
+/// errors point into `input`.
+pub fn parse_known_hosts(input: &str) -> Result<Vec<KnownHost>, ParseError> {
+    entries(input, |number, line| {
+        let mut at = 0;
+        let marker = if line.starts_with('@') {
+            let word = first_word(line);
+            at = skip_space(line, word.len());
+            Some(match word {
+                "@cert-authority" => Marker::CertAuthority,
+                "@revoked" => Marker::Revoked,
+                _ => return Err(ParseError::new(0, format!("unknown marker `{word}`"))),
+            })
+        } else {
+            None
+        };
+        let hosts = first_word(&line[at..]);
+        if hosts.is_empty() {
+            return Err(ParseError::new(at, "expected host names"));
+        }
+        let mut patterns = Vec::new();
+        let mut pattern_at = at;
+        for pattern in hosts.split(',') {
+            patterns.push(
+                host_pattern(pattern)
+                    .ok_or_else(|| ParseError::new(pattern_at, "invalid host pattern"))?,
+            );
+            pattern_at += pattern.len() + 1;
+        }
+        Ok(KnownHost {
+            line: number,
+            marker,
+            hosts: patterns,
+            key: public_key(line, skip_space(line, at + hosts.len()))?,
+        })
+    })
+}
+
+/// Runs `entry` on each line that is not blank or a comment, with the
+/// line number.
+fn entries<T>(
+    input: &str,
+    mut entry: impl FnMut(usize, &str) -> Result<T, ParseError>,
+) -> Result<Vec<T>, ParseError> {
+    let mut out = Vec::new();
+    let mut offset = 0;
+    for (i, raw) in input.split_inclusive('\n').enumerate() {
+        let start = offset;
+        offset += raw.len();
+        let line = raw.trim_end_matches(['\n', '\r']);
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let at = start + (line.len() - trimmed.len());
+        out.push(entry(i + 1, trimmed).map_err(|e| ParseError::new(at + e.offset(), e.message()))?);
+    }
+    Ok(out)
+}
+
+fn first_word(text: &str) -> &str {
+    text.split([' ', '\t']).next().unwrap_or_default()
+}
+
+fn skip_space(line: &str, at: usize) -> usize {
+    at + (line[at..].len() - line[at..].trim_start_matches([' ', '\t']).len())
+}
+
+fn is_key_type(word: &str) -> bool {
+    ["ssh-", "ecdsa-sha2-", "sk-ssh-", "sk-ecdsa-sha2-"]
+        .iter()
+        .any(|prefix| word.starts_with(prefix))
+}
+
+/// The comma-separated options at the start of `line`, and where the key
+/// type begins.
+fn options(line: &str) -> Result<(Vec<KeyOption>, usize), ParseError> {
+    let mut options = Vec::new();
+    let mut chars = line.char_indices().peekable();
+    loop {
+        let start = chars.peek().map_or(line.len(), |&(i, _)| i);
+        let mut name = String::new();
+        while let Some(&(_, c)) = chars.peek() {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                name.push(c);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        if name.is_empty() {
+            return Err(ParseError::new(start, "expected an option or a key type"));
+        }
+        let mut value = None;
+        if chars.next_if(|&(_, c)| c == '=').is_some() {
+            if chars.next_if(|&(_, c)| c == '"').is_none() {
+                return Err(ParseError::new(start + name.len() + 1, "expected `\"`"));
+            }
+            let mut text = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, '"')) => break,
+                    Some((_, '\\')) if chars.peek().is_some_and(|&(_, c)| c == '"') => {
+                        text.push('"');
+                        chars.next();
+                    }
+                    Some((_, c)) => text.push(c),
+                    None => return Err(ParseError::new(start, "unterminated quoted value")),
+                }
+            }
+            value = Some(text);
+        }
+        options.push(KeyOption { name, value });
+        match chars.next() {
+            Some((_, ',')) => continue,
+            Some((i, ' ' | '\t')) => return Ok((options, skip_space(line, i))),
+            Some((i, _)) => return Err(ParseError::new(i, "expected `,` or a space")),
+            None => return Err(ParseError::new(line.len(), "expected a key type")),
+        }
+    }
+}
+
+/// `type base64 [comment]`, starting at `at` in `line`.
+fn public_key(line: &str, at: usize) -> Result<PublicKey, ParseError> {
+    let kind = first_word(&line[at..]);
+    if kind.is_empty() {
+        return Err(ParseError::new(at, "expected a key type"));
+    }
+    let blob_at = skip_space(line, at + kind.len());
+    let encoded = first_word(&line[blob_at..]);
+    if encoded.is_empty() {
+        return Err(ParseError::new(blob_at, "expected a base64 key"));
+    }
+    let blob = BASE64
+        .decode(encoded)
+        .map_err(|e| ParseError::new(blob_at + e.offset(), "invalid base64"))?;
+    // The blob opens with its own type as a length-prefixed string.
+    let embedded = blob
+        .get(..4)
+        .map(|len| u32::from_be_bytes(len.try_into().expect("four bytes")) as usize)
+        .and_then(|len| blob.get(4..4 + len));
+    if embedded != Some(kind.as_bytes()) {
+        return Err(ParseError::new(
+            blob_at,
+            format!("key is not of type `{kind}`"),
+        ));
+    }
+    let comment = line[blob_at + encoded.len()..].trim();
+    Ok(PublicKey {
+        kind: kind.to_string(),
+        blob,
+        comment: (!comment.is_empty()).then(|| comment.to_string()),
+    })
+}
+
+fn host_pattern(pattern: &str) -> Option<HostPattern> {
+    if let Some(hashed) = pattern.strip_prefix("|1|") {
+        let (salt, hash) = hashed.split_once('|')?;
+        return Some(HostPattern::Hashed {
+            salt: BASE64.decode(salt).ok()?,
+            hash: BASE64.decode(hash).ok()?,
+        });
+    }
+    let (negated, pattern) = match pattern.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, pattern),
+    };
+    let (pattern, port) = match pattern.strip_prefix('[') {
+        Some(bracketed) => {
+            let (host, port) = bracketed.split_once("]:")?;
+            (host, Some(port.parse().ok()?))
+        }
+        None => (pattern, None),
+    };
+    if pattern.is_empty() {
+        return None;
+    }
+    Some(HostPattern::Plain {
+        pattern: pattern.to_string(),
+        port,
+        negated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An ed25519 key blob: the type, then 32 zero bytes.
+    const ED25519: &str = "AAAAC3NzaC1lZDI1NTE5AAAAIAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA";
+
+    #[test]
+    fn parse_authorized_keys_should_work() -> Result<(), ParseError> {
+        let keys = parse_authorized_keys(&format!(
+            "# deploy keys\n\
+             ssh-ed25519 {ED25519} alice@laptop\n\
+             \n\
+             command=\"echo \\\"hi\\\", bye\",no-pty,from=\"10.0.0.0/8\" ssh-ed25519 {ED25519}\n\
+             restrict  ssh-ed25519\t{ED25519}  backup key \n"
+        ))?;
+        assert_eq!(keys.len(), 3);
+        assert_eq!(keys[0].line, 2);
+        assert_eq!(keys[0].key.kind, "ssh-ed25519");
+        assert_eq!(keys[0].key.blob.len(), 51);
+        assert_eq!(keys[0].key.comment.as_deref(), Some("alice@laptop"));
+        assert!(!keys[0].is_restricted());
+        let forced = &keys[1];
+        assert_eq!(
+            forced.option("COMMAND").unwrap().value.as_deref(),
+            Some("echo \"hi\", bye")
+        );
+        assert_eq!(forced.option("no-pty").unwrap().value, None);
+        assert_eq!(forced.options.len(), 3);
+        assert_eq!(forced.key.comment, None);
+        assert!(keys[2].is_restricted());
+        assert_eq!(keys[2].key.comment.as_deref(), Some("backup key"));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_known_hosts_should_work() -> Result<(), ParseError> {
+        let hosts = parse_known_hosts(&format!(
+            "github.com,140.82.112.3 ssh-ed25519 {ED25519}\n\
+             [git.example.com]:2222,!bad.example.com ssh-ed25519 {ED25519}\n\
+             |1|c2FsdA==|aGFzaA== ssh-ed25519 {ED25519}\n\
+             @cert-authority *.example.com ssh-ed25519 {ED25519} ca\n\
+             @revoked * ssh-ed25519 {ED25519}\n"
+        ))?;
+        assert_eq!(hosts.len(), 5);
+        assert_eq!(
+            hosts[0].hosts[1],
+            HostPattern::Plain {
+                pattern: "140.82.112.3".into(),
+                port: None,
+                negated: false
+            }
+        );
+        assert_eq!(
+            hosts[1].hosts,
+            [
+                HostPattern::Plain {
+                    pattern: "git.example.com".into(),
+                    port: Some(2222),
+                    negated: false
+                },
+                HostPattern::Plain {
+                    pattern: "bad.example.com".into(),
+                    port: None,
+                    negated: true
+                },
+            ]
+        );
+        assert_eq!(
+            hosts[2].hosts,
+            [HostPattern::Hashed {
+                salt: b"salt".to_vec(),
+                hash: b"hash".to_vec()
+            }]
+        );
+        assert_eq!(hosts[3].marker, Some(Marker::CertAuthority));
+        assert_eq!(hosts[3].key.comment.as_deref(), Some("ca"));
+        assert_eq!(hosts[4].marker, Some(Marker::Revoked));
+        Ok(())
+    }
+
+    #[test]
+    fn ssh_parsers_should_report_errors() {
+        let err = parse_authorized_keys(&format!("ssh-rsa {ED25519}\n")).unwrap_err();
+        assert_eq!(err.offset(), 8);
+        assert_eq!(err.message(), "key is not of type `ssh-rsa`");
+        let err = parse_authorized_keys("\nno-pty ssh-ed25519 AAA!\n").unwrap_err();
+        assert_eq!(err.offset(), 23);
+        assert!(parse_authorized_keys("command=\"open ssh-ed25519 AAAA\n").is_err());
+        assert!(parse_authorized_keys("command=x ssh-ed25519 AAAA\n").is_err());
+        assert!(parse_authorized_keys("no-pty\n").is_err());
+        assert!(parse_known_hosts(&format!("@trusted h ssh-ed25519 {ED25519}\n")).is_err());
+        assert!(parse_known_hosts(&format!("[h]:x ssh-ed25519 {ED25519}\n")).is_err());
+        assert!(parse_known_hosts(&format!("|1|!|x ssh-ed25519 {ED25519}\n")).is_err());
+        assert!(parse_known_hosts("host\n").is_err());
+    }
+}
@@ -0,0 +1,233 @@
+use std::str::FromStr;
+
+use crate::ParseError;
+
+/// One metric of a statsd datagram.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metric {
+    pub name: String,
+    pub value: MetricValue,
+    /// `@0.1`: the client sent about one in ten of these.
+    pub sample_rate: Option<f64>,
+    /// DogStatsD `#tag:value,flag` tags.
+    pub tags: Vec<Tag>,
+    /// DogStatsD `T<unix seconds>`.
+    pub timestamp: Option<u64>,
+    /// DogStatsD `c:<container id>`.
+    pub container_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetricValue {
+    /// `c`
+    Counter(f64),
+    /// `g` with a plain value.
+    Gauge(f64),
+    /// `g` with a leading `+` or `-`: a change to the current value.
+    GaugeDelta(f64),
+    /// `ms`, in milliseconds.
+    Timer(f64),
+    /// `h`
+    Histogram(f64),
+    /// `d`
+    Distribution(f64),
+    /// `s`: counts distinct values, which need not be numbers.
+    Set(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tag {
+    pub name: String,
+    /// `None` for a bare `#flag`.
+    pub value: Option<String>,
+}
+
+impl Metric {
+    /// A counter's value with the sample rate undone, so `1|c|@0.1` counts
+    /// ten. `None` for metrics that are not counters.
+    pub fn scaled_count(&self) -> Option<f64> {
+        match self.value {
+            MetricValue::Counter(n) => Some(n / self.sample_rate.unwrap_or(1.0)),
+            _ => None,
+        }
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&Tag> {
+        self.tags.iter().find(|t| t.name == name)
+    }
+}
+
+impl FromStr for Metric {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        metric(s)
+    }
+}
+
+/// Parses a datagram, which holds one metric per line. Empty lines are
+/// skipped; errors point into `datagram`.
+pub fn parse_statsd(datagram: &str) -> Result<Vec<Metric>, ParseError> {
+    let mut metrics = Vec::new();
+    let mut offset = 0;
+    for raw in datagram.split_inclusive('\n') {
+        let start = offset;
+        offset += raw.len();
+        let line = raw.trim_end_matches(['\n', '\r']);
+        if line.is_empty() {
+            continue;
+        }
+        metrics.push(metric(line).map_err(|e| ParseError::new(start + e.offset(), e.message()))?);
+    }
+    Ok(metrics)
+}
+
+/// `name:value|type` followed by optional `|`-separated sections.
+fn metric(line: &str) -> Result<Metric, ParseError> {
+    let mut at = 0;
+    let mut parts = line.split('|').map(|part| {
+        let start = at;
+        at += part.len() + 1;
+        (start, part)
+    });
+    let (_, head) = parts.next().unwrap_or_default();
+    let Some((name, value)) = head.split_once(':') else {
+        return Err(ParseError::new(
+            head.len(),
+            "expected `:` after the metric name",
+        ));
+    };
+    if name.is_empty() {
+        return Err(ParseError::new(0, "expected a metric name"));
+    }
+    let value_at = name.len() + 1;
+    let Some((type_at, kind)) = parts.next() else {
+        return Err(ParseError::new(
+            line.len(),
+            "expected `|` and a metric type",
+        ));
+    };
+    let number = || {
+        value
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite())
+            .ok_or_else(|| ParseError::new(value_at, "expected a number"))
+    };
+    let value = match kind {
+        "c" => MetricValue::Counter(number()?),
+        "g" if value.starts_with(['+', '-']) => MetricValue::GaugeDelta(number()?),
+        "g" => MetricValue::Gauge(number()?),
+        "ms" => MetricValue::Timer(number()?),
+        "h" => MetricValue::Histogram(number()?),
+        "d" => MetricValue::Distribution(number()?),
+        "s" if !value.is_empty() => MetricValue::Set(value.to_string()),
+        "s" => return Err(ParseError::new(value_at, "expected a set member")),
+        _ => {
+            return Err(ParseError::new(
+                type_at,
+                format!("unknown metric type `{kind}`"),
+            ));
+        }
+    };
+
+    let mut metric = Metric {
+        name: name.to_string(),
+        value,
+        sample_rate: None,
+        tags: Vec::new(),
+        timestamp: None,
+        container_id: None,
+    };
+    for (at, section) in parts {
+        let error = |message: &str| ParseError::new(at, message);
+        if let Some(rate) = section.strip_prefix('@') {
+            let rate = rate
+                .parse::<f64>()
+                .ok()
+                .filter(|r| *r > 0.0 && *r <= 1.0)
+                .ok_or_else(|| error("sample rate must be in (0, 1]"))?;
+            metric.sample_rate = Some(rate);
+        } else if let Some(tags) = section.strip_prefix('#') {
+            metric
+                .tags
+                .extend(tags.split(',').filter(|t| !t.is_empty()).map(|tag| {
+                    match tag.split_once(':') {
+                        Some((name, value)) => Tag {
+                            name: name.to_string(),
+                            value: Some(value.to_string()),
+                        },
+                        None => Tag {
+                            name: tag.to_string(),
+                            value: None,
+                        },
+                    }
+                }));
+        } else if let Some(id) = section.strip_prefix("c:") {
+            metric.container_id = Some(id.to_string());
+        } else if let Some(seconds) = section.strip_prefix('T') {
+            let seconds = seconds
+                .parse()
+                .map_err(|_| error("expected a Unix timestamp"))?;
+            metric.timestamp = Some(seconds);
+        } else {
+            return Err(error("unknown section"));
+        }
+    }
+    Ok(metric)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_statsd_should_work() -> Result<(), ParseError> {
+        let metrics = parse_statsd(
+            "gorets:1|c|@0.1|#env:prod,canary\nglork:320|ms\ngaugor:-10|g\n\nuniques:765|s\n",
+        )?;
+        assert_eq!(metrics.len(), 4);
+        let gorets = &metrics[0];
+        assert_eq!(gorets.value, MetricValue::Counter(1.0));
+        assert_eq!(gorets.sample_rate, Some(0.1));
+        assert_eq!(gorets.scaled_count(), Some(10.0));
+        assert_eq!(gorets.tag("env").unwrap().value.as_deref(), Some("prod"));
+        assert_eq!(gorets.tag("canary").unwrap().value, None);
+        assert_eq!(metrics[1].value, MetricValue::Timer(320.0));
+        assert_eq!(metrics[1].scaled_count(), None);
+        assert_eq!(metrics[2].value, MetricValue::GaugeDelta(-10.0));
+        assert_eq!(metrics[3].value, MetricValue::Set("765".into()));
+        Ok(())
+    }
+
+    #[test]
+    fn dogstatsd_extensions_should_parse() -> Result<(), ParseError> {
+        let metric: Metric = "page.views:1.5|d|#shell|c:83c0a99c0a54|T1656581400".parse()?;
+        assert_eq!(metric.value, MetricValue::Distribution(1.5));
+        assert_eq!(metric.container_id.as_deref(), Some("83c0a99c0a54"));
+        assert_eq!(metric.timestamp, Some(1656581400));
+        assert_eq!(metric.tags.len(), 1);
+        assert_eq!(
+            "users.online:42|g".parse::<Metric>()?.value,
+            MetricValue::Gauge(42.0)
+        );
+        assert_eq!(
+            "latency:7|h".parse::<Metric>()?.value,
+            MetricValue::Histogram(7.0)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_statsd_should_report_errors() {
+        let err = parse_statsd("a:1|c\nb:x|c\n").unwrap_err();
+        assert_eq!(err.offset(), 8);
+        assert_eq!(err.message(), "expected a number");
+        assert!("a:1".parse::<Metric>().is_err());
+        assert!("a|c".parse::<Metric>().is_err());
+        assert!(":1|c".parse::<Metric>().is_err());
+        assert!("a:1|x".parse::<Metric>().is_err());
+        assert!("a:1|c|@2".parse::<Metric>().is_err());
+        assert!("a:1|c|?".parse::<Metric>().is_err());
+    }
+}
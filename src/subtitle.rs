@@ -0,0 +1,469 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::ParseError;
+
+/// Subtitles from either format. SubRip fills in only `cues`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Track {
+    /// What follows `WEBVTT` on the first line, and any header lines.
+    pub header: Option<String>,
+    /// The bodies of WebVTT `STYLE` blocks.
+    pub styles: Vec<String>,
+    /// The bodies of WebVTT `REGION` blocks.
+    pub regions: Vec<String>,
+    pub cues: Vec<Cue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cue {
+    /// The SubRip index or WebVTT identifier.
+    pub id: Option<String>,
+    pub start: Duration,
+    pub end: Duration,
+    /// `name:value` settings after the timing, e.g. `align:start`.
+    pub settings: Vec<(String, String)>,
+    /// The payload as written, markup included.
+    pub text: String,
+}
+
+/// The text a `<v Speaker>` tag attributes to someone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Voice {
+    pub speaker: String,
+    pub text: String,
+}
+
+impl Track {
+    /// Moves every cue by `offset`, clamping at zero, as when re-syncing
+    /// subtitles to a different cut.
+    pub fn shift(&mut self, offset: chrono::Duration) {
+        for cue in &mut self.cues {
+            cue.start = shifted(cue.start, offset);
+            cue.end = shifted(cue.end, offset);
+        }
+    }
+
+    /// Adds `other`'s cues and keeps the whole list ordered by start time;
+    /// cues that start together keep their order.
+    pub fn merge(&mut self, other: Track) {
+        self.cues.extend(other.cues);
+        self.cues.sort_by_key(|cue| cue.start);
+        self.styles.extend(other.styles);
+        self.regions.extend(other.regions);
+    }
+
+    /// SubRip, with cues renumbered from 1 and the settings dropped.
+    pub fn to_srt(&self) -> String {
+        let mut out = String::new();
+        for (i, cue) in self.cues.iter().enumerate() {
+            let _ = write!(
+                out,
+                "{}\n{} --> {}\n{}\n\n",
+                i + 1,
+                timestamp(cue.start, ','),
+                timestamp(cue.end, ','),
+                cue.text
+            );
+        }
+        out
+    }
+
+    pub fn to_vtt(&self) -> String {
+        let mut out = String::from("WEBVTT");
+        if let Some(header) = &self.header {
+            let _ = write!(out, " {header}");
+        }
+        out.push_str("\n\n");
+        for style in &self.styles {
+            let _ = write!(out, "STYLE\n{style}\n\n");
+        }
+        for region in &self.regions {
+            let _ = write!(out, "REGION\n{region}\n\n");
+        }
+        for cue in &self.cues {
+            if let Some(id) = &cue.id {
+                let _ = writeln!(out, "{id}");
+            }
+            let _ = write!(
+                out,
+                "{} --> {}",
+                timestamp(cue.start, '.'),
+                timestamp(cue.end, '.')
+            );
+            for (name, value) in &cue.settings {
+                let _ = write!(out, " {name}:{value}");
+            }
+            let _ = write!(out, "\n{}\n\n", cue.text);
+        }
+        out
+    }
+}
+
+impl Cue {
+    pub fn duration(&self) -> Duration {
+        self.end.saturating_sub(self.start)
+    }
+
+    /// The text without tags, with the common character references
+    /// decoded.
+    pub fn plain_text(&self) -> String {
+        decode(&strip_tags(&self.text))
+    }
+
+    /// Each `<v Speaker>` span, which runs to `</v>`, the next voice or the
+    /// end of the cue.
+    pub fn voices(&self) -> Vec<Voice> {
+        let mut voices = Vec::new();
+        let mut rest = self.text.as_str();
+        while let Some(i) = rest.find("<v") {
+            let tag = &rest[i + 2..];
+            let Some(close) = tag.find('>') else { break };
+            rest = &tag[close + 1..];
+            // `<v.loud Esme>`: classes, then whitespace, then the name.
+            if !tag.starts_with(['.', ' ', '\t']) {
+                continue;
+            }
+            let Some((_, speaker)) = tag[..close].split_once([' ', '\t']) else {
+                continue;
+            };
+            let end = [rest.find("</v>"), rest.find("<v")]
+                .into_iter()
+                .flatten()
+                .min()
+                .unwrap_or(rest.len());
+            voices.push(Voice {
+                speaker: speaker.trim().to_string(),
+                text: decode(&strip_tags(&rest[..end])),
+            });
+            rest = &rest[end..];
+        }
+        voices
+    }
+}
+
+fn strip_tags(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out
+}
+
+fn decode(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", "\u{a0}")
+        .replace("&lrm;", "\u{200e}")
+        .replace("&rlm;", "\u{200f}")
+        .replace("&amp;", "&")
+}
+
+fn shifted(time: Duration, offset: chrono::Duration) -> Duration {
+    let millis = time.as_millis() as i64 + offset.num_milliseconds();
+    Duration::from_millis(millis.max(0) as u64)
+}
+
+fn timestamp(time: Duration, separator: char) -> String {
+    let millis = time.as_millis();
+    format!(
+        "{:02}:{:02}:{:02}{separator}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// `HH:MM:SS,mmm` for SubRip, `[HH:]MM:SS.mmm` for WebVTT.
+fn parse_timestamp(text: &str, separator: char) -> Option<Duration> {
+    let (clock, millis) = text.split_once(separator)?;
+    let parts: Vec<&str> = clock.split(':').collect();
+    let (hours, minutes, seconds) = match parts[..] {
+        [h, m, s] => (h, m, s),
+        [m, s] if separator == '.' => ("0", m, s),
+        _ => return None,
+    };
+    let number = |s: &str, digits: Option<usize>| {
+        let valid = !s.is_empty()
+            && s.bytes().all(|b| b.is_ascii_digit())
+            && digits.is_none_or(|n| s.len() == n);
+        valid.then(|| s.parse::<u64>().ok()).flatten()
+    };
+    let hours = number(hours, None)?;
+    let minutes = number(minutes, Some(2)).filter(|m| *m < 60)?;
+    let seconds = number(seconds, Some(2)).filter(|s| *s < 60)?;
+    let millis = number(millis, Some(3))?;
+    Some(Duration::from_millis(
+        ((hours * 60 + minutes) * 60 + seconds) * 1000 + millis,
+    ))
+}
+
+/// The lines of `input` with their offsets, split into blank-line
+/// separated blocks.
+fn blocks(input: &str) -> Vec<Vec<(usize, &str)>> {
+    let mut blocks = vec![Vec::new()];
+    let mut offset = 0;
+    for raw in input.split_inclusive('\n') {
+        let start = offset;
+        offset += raw.len();
+        let line = raw.trim_end_matches(['\n', '\r']);
+        if line.trim().is_empty() {
+            if !blocks.last().is_some_and(Vec::is_empty) {
+                blocks.push(Vec::new());
+            }
+        } else {
+            blocks.last_mut().expect("never empty").push((start, line));
+        }
+    }
+    blocks.retain(|block| !block.is_empty());
+    blocks
+}
+
+fn join(lines: &[(usize, &str)]) -> String {
+    lines
+        .iter()
+        .map(|(_, line)| *line)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+type Timing = (Duration, Duration, Vec<(String, String)>);
+
+/// `start --> end [settings]`; offsets in errors are into `line`.
+fn timing(line: &str, separator: char) -> Result<Timing, ParseError> {
+    let Some(arrow) = line.find("-->") else {
+        return Err(ParseError::new(0, "expected `-->`"));
+    };
+    let start = parse_timestamp(line[..arrow].trim(), separator)
+        .ok_or_else(|| ParseError::new(0, "invalid start time"))?;
+    let after = &line[arrow + 3..];
+    let rest = after.trim_start();
+    let end_at = arrow + 3 + (after.len() - rest.len());
+    let end_text = rest.split([' ', '\t']).next().unwrap_or_default();
+    let end = parse_timestamp(end_text, separator)
+        .ok_or_else(|| ParseError::new(end_at, "invalid end time"))?;
+    if end < start {
+        return Err(ParseError::new(end_at, "cue ends before it starts"));
+    }
+    let mut settings = Vec::new();
+    for setting in rest[end_text.len()..].split_whitespace() {
+        let (name, value) = setting
+            .split_once(':')
+            .ok_or_else(|| ParseError::new(end_at, format!("invalid setting `{setting}`")))?;
+        settings.push((name.to_string(), value.to_string()));
+    }
+    Ok((start, end, settings))
+}
+
+/// Parses a SubRip (`.srt`) file. A byte-order mark and a missing index are
+/// tolerated; errors point into `input`.
+pub fn parse_srt(input: &str) -> Result<Track, ParseError> {
+    let body = input.strip_prefix('\u{feff}').unwrap_or(input);
+    let base = input.len() - body.len();
+    let mut cues = Vec::new();
+    for block in blocks(body) {
+        let mut lines = block.iter().copied().peekable();
+        let id = lines
+            .next_if(|(_, line)| !line.contains("-->"))
+            .map(|(at, line)| (at, line.trim().to_string()));
+        if let Some((at, id)) = &id
+            && id.parse::<u64>().is_err()
+        {
+            return Err(ParseError::new(base + at, "expected a cue number"));
+        }
+        let Some((at, line)) = lines.next() else {
+            let at = id.map_or(0, |(at, _)| at);
+            return Err(ParseError::new(base + at, "expected a cue timing"));
+        };
+        let (start, end, settings) =
+            timing(line, ',').map_err(|e| ParseError::new(base + at + e.offset(), e.message()))?;
+        cues.push(Cue {
+            id: id.map(|(_, id)| id),
+            start,
+            end,
+            settings,
+            text: join(&block[block.len() - lines.count()..]),
+        });
+    }
+    Ok(Track {
+        cues,
+        ..Track::default()
+    })
+}
+
+/// Parses a WebVTT file. `NOTE` blocks are skipped; errors point into
+/// `input`.
+pub fn parse_vtt(input: &str) -> Result<Track, ParseError> {
+    let body = input.strip_prefix('\u{feff}').unwrap_or(input);
+    let base = input.len() - body.len();
+    let mut blocks = blocks(body).into_iter();
+    let header = blocks.next().unwrap_or_default();
+    let signature = header.first().map_or("", |(_, line)| *line);
+    let title = match signature.strip_prefix("WEBVTT") {
+        Some("") => None,
+        Some(title) if title.starts_with([' ', '\t']) => Some(title.trim().to_string()),
+        _ => return Err(ParseError::new(base, "expected `WEBVTT`")),
+    };
+    let mut lines: Vec<String> = title.into_iter().collect();
+    lines.extend(header.iter().skip(1).map(|(_, line)| line.to_string()));
+    let mut track = Track {
+        header: (!lines.is_empty()).then(|| lines.join("\n")),
+        ..Track::default()
+    };
+    for block in blocks {
+        let (first_at, first) = block[0];
+        let keyword = |word: &str| {
+            first == word
+                || first
+                    .strip_prefix(word)
+                    .is_some_and(|r| r.starts_with([' ', '\t']))
+        };
+        if keyword("NOTE") {
+            continue;
+        }
+        if keyword("STYLE") && !first.contains("-->") {
+            track.styles.push(join(&block[1..]));
+            continue;
+        }
+        if keyword("REGION") && !first.contains("-->") {
+            track.regions.push(join(&block[1..]));
+            continue;
+        }
+        let (id, (at, line), text) = if first.contains("-->") {
+            (None, block[0], &block[1..])
+        } else {
+            let Some(&timing_line) = block.get(1) else {
+                return Err(ParseError::new(base + first_at, "expected a cue timing"));
+            };
+            (Some(first.to_string()), timing_line, &block[2..])
+        };
+        let (start, end, settings) =
+            timing(line, '.').map_err(|e| ParseError::new(base + at + e.offset(), e.message()))?;
+        track.cues.push(Cue {
+            id,
+            start,
+            end,
+            settings,
+            text: join(text),
+        });
+    }
+    Ok(track)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn parse_srt_should_work() -> Result<(), ParseError> {
+        let track = parse_srt(
+            "\u{feff}1\r\n00:00:01,000 --> 00:00:04,250\r\nHello <i>there</i>\r\nsecond line\r\n\r\n\
+             2\n01:02:03,004 --> 01:02:05,000 X1:10 X2:20\nBye &amp; thanks\n\n\n",
+        )?;
+        assert_eq!(track.cues.len(), 2);
+        let first = &track.cues[0];
+        assert_eq!(first.id.as_deref(), Some("1"));
+        assert_eq!((first.start, first.end), (ms(1000), ms(4250)));
+        assert_eq!(first.text, "Hello <i>there</i>\nsecond line");
+        assert_eq!(first.plain_text(), "Hello there\nsecond line");
+        let second = &track.cues[1];
+        assert_eq!(second.start, ms(3_723_004));
+        assert_eq!(second.settings[0], ("X1".into(), "10".into()));
+        assert_eq!(second.plain_text(), "Bye & thanks");
+        assert_eq!(
+            track.to_srt(),
+            "1\n00:00:01,000 --> 00:00:04,250\nHello <i>there</i>\nsecond line\n\n\
+             2\n01:02:03,004 --> 01:02:05,000\nBye &amp; thanks\n\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_vtt_should_work() -> Result<(), ParseError> {
+        let track = parse_vtt(
+            "WEBVTT - Episode 1\nKind: captions\n\n\
+             NOTE written by hand\n\n\
+             STYLE\n::cue { color: yellow }\n\n\
+             intro\n00:01.000 --> 00:04.000 align:start line:90%\n<v.loud Esme>Hi!</v> <v Mary>Hello</v>\n\n\
+             01:00:00.500 --> 01:00:02.000\n<v Tom>one\ntwo\n",
+        )?;
+        assert_eq!(track.header.as_deref(), Some("- Episode 1\nKind: captions"));
+        assert_eq!(track.styles, ["::cue { color: yellow }"]);
+        assert_eq!(track.cues.len(), 2);
+        let intro = &track.cues[0];
+        assert_eq!(intro.id.as_deref(), Some("intro"));
+        assert_eq!(intro.start, ms(1000));
+        assert_eq!(
+            intro.settings,
+            [
+                ("align".to_string(), "start".to_string()),
+                ("line".to_string(), "90%".to_string())
+            ]
+        );
+        assert_eq!(
+            intro.voices(),
+            [
+                Voice {
+                    speaker: "Esme".into(),
+                    text: "Hi!".into()
+                },
+                Voice {
+                    speaker: "Mary".into(),
+                    text: "Hello".into()
+                }
+            ]
+        );
+        assert_eq!(track.cues[1].voices()[0].text, "one\ntwo");
+        assert_eq!(track.cues[1].duration(), ms(1500));
+        let again = parse_vtt(&track.to_vtt())?;
+        assert_eq!(again.cues, track.cues);
+        Ok(())
+    }
+
+    #[test]
+    fn shift_and_merge_should_work() -> Result<(), ParseError> {
+        let mut english = parse_srt("1\n00:00:02,000 --> 00:00:03,000\nHi\n")?;
+        let french = parse_vtt("WEBVTT\n\n00:00:01.000 --> 00:00:02.500\nSalut\n")?;
+        english.merge(french);
+        assert_eq!(
+            english
+                .cues
+                .iter()
+                .map(|c| c.text.as_str())
+                .collect::<Vec<_>>(),
+            ["Salut", "Hi"]
+        );
+        english.shift(chrono::Duration::milliseconds(-1500));
+        assert_eq!(english.cues[0].start, ms(0));
+        assert_eq!(english.cues[0].end, ms(1000));
+        assert_eq!(english.cues[1].start, ms(500));
+        Ok(())
+    }
+
+    #[test]
+    fn parsers_should_report_errors() {
+        let err = parse_srt("1\n00:00:01,000 --> 00:00:04,000\nok\n\n2\n00:00:05,000 -> 6\n")
+            .unwrap_err();
+        assert_eq!(err.offset(), 38);
+        let err = parse_srt("1\n00:00:01,000 --> 00:00:0x,000\n").unwrap_err();
+        assert_eq!(err.offset(), 19);
+        assert!(parse_srt("one\n00:00:01,000 --> 00:00:04,000\n").is_err());
+        assert!(parse_srt("1\n00:00:04,000 --> 00:00:01,000\n").is_err());
+        assert!(parse_srt("1\n00:01,000 --> 00:00:04,000\n").is_err());
+        assert!(parse_vtt("WEBVTTX\n").is_err());
+        assert!(parse_vtt("").is_err());
+        assert!(parse_vtt("WEBVTT\n\n00:01.000 --> 00:02.000 bad\nx\n").is_err());
+        assert!(parse_vtt("WEBVTT\n\nid only\n").is_err());
+    }
+}
@@ -0,0 +1,314 @@
+use crate::ParseError;
+
+/// A TAP stream, or one subtest of it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    /// From `# Subtest: name`.
+    pub name: Option<String>,
+    /// From `TAP version 13`; absent for TAP 12.
+    pub version: Option<u32>,
+    pub plan: Option<Plan>,
+    pub tests: Vec<TestPoint>,
+    /// The reason given by `Bail out!`.
+    pub bail_out: Option<String>,
+    /// `#` lines, without the `#`.
+    pub comments: Vec<String>,
+}
+
+/// `1..N`, possibly `1..0 # SKIP reason`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+    pub count: usize,
+    pub skip: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestPoint {
+    pub ok: bool,
+    pub number: Option<usize>,
+    /// Without the leading `- `.
+    pub description: String,
+    pub directive: Option<Directive>,
+    /// The YAML block between `---` and `...`, unindented but not parsed.
+    pub diagnostics: Option<String>,
+    /// The indented subtest reported by this point.
+    pub subtest: Option<Box<Report>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Directive {
+    Skip(String),
+    Todo(String),
+}
+
+impl TestPoint {
+    /// Whether the point counts as a failure: `not ok` and not `TODO`.
+    pub fn is_failure(&self) -> bool {
+        !self.ok && !matches!(self.directive, Some(Directive::Todo(_)))
+    }
+}
+
+impl Report {
+    pub fn failures(&self) -> impl Iterator<Item = &TestPoint> {
+        self.tests.iter().filter(|t| t.is_failure())
+    }
+
+    /// Whether the run passed: no bail out, no failures, and as many tests
+    /// as the plan promised.
+    pub fn is_success(&self) -> bool {
+        self.bail_out.is_none()
+            && self.failures().next().is_none()
+            && self
+                .plan
+                .as_ref()
+                .is_some_and(|p| p.count == self.tests.len())
+    }
+}
+
+/// Parses TAP output. Lines TAP does not define are ignored, as the spec
+/// asks; errors point into `input`.
+pub fn parse_tap(input: &str) -> Result<Report, ParseError> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for raw in input.split_inclusive('\n') {
+        lines.push((offset, raw.trim_end_matches(['\n', '\r'])));
+        offset += raw.len();
+    }
+    report(&lines, 0)
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// The report made of `lines`, each indented by at least `indent`.
+fn report(lines: &[(usize, &str)], indent: usize) -> Result<Report, ParseError> {
+    let mut report = Report::default();
+    let mut subtest: Option<Report> = None;
+    let mut subtest_name = None;
+    let mut i = 0;
+    while i < lines.len() {
+        let (start, raw) = lines[i];
+        i += 1;
+        if raw.trim().is_empty() {
+            continue;
+        }
+        if indent_of(raw) >= indent + 4 {
+            // A subtest runs until the first line back at this level.
+            let end = lines[i..]
+                .iter()
+                .position(|(_, l)| !l.trim().is_empty() && indent_of(l) < indent + 4)
+                .map_or(lines.len(), |n| i + n);
+            let mut child = self::report(&lines[i - 1..end], indent + 4)?;
+            child.name = child.name.or(subtest_name.take());
+            subtest = Some(child);
+            i = end;
+            continue;
+        }
+        let line = &raw[indent.min(indent_of(raw))..];
+        let at = start + (raw.len() - line.len());
+        if let Some(version) = line.strip_prefix("TAP version ") {
+            let version = version
+                .trim()
+                .parse()
+                .map_err(|_| ParseError::new(at + 12, "expected a version number"))?;
+            report.version = Some(version);
+        } else if let Some(count) = line.strip_prefix("1..") {
+            let (count, skip) = split_directive(count);
+            let count = count
+                .trim()
+                .parse()
+                .map_err(|_| ParseError::new(at + 3, "expected a test count"))?;
+            let skip = match skip {
+                Some(Directive::Skip(reason)) => Some(reason),
+                _ => None,
+            };
+            if report.plan.replace(Plan { count, skip }).is_some() {
+                return Err(ParseError::new(at, "duplicate plan"));
+            }
+        } else if let Some((ok, rest)) = test_line(line) {
+            let (text, directive) = split_directive(rest);
+            let text = text.trim_start();
+            let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            let number = text[..digits].parse().ok();
+            let description = text[digits..].trim();
+            let description = description.strip_prefix("- ").unwrap_or(description);
+            let description = if description == "-" { "" } else { description };
+
+            // A YAML block is indented two more than its test point.
+            let mut diagnostics = None;
+            let yaml_indent = indent + 2;
+            if let Some(&(_, next)) = lines.get(i)
+                && indent_of(next) == yaml_indent
+                && next.trim_end() == format!("{}---", " ".repeat(yaml_indent))
+            {
+                let close = lines[i + 1..]
+                    .iter()
+                    .position(|(_, l)| l.trim() == "..." && indent_of(l) == yaml_indent)
+                    .ok_or_else(|| ParseError::new(lines[i].0, "unterminated YAML block"))?;
+                let body = &lines[i + 1..i + 1 + close];
+                diagnostics = Some(
+                    body.iter()
+                        .map(|(_, l)| l.get(yaml_indent..).unwrap_or("").trim_end())
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                );
+                i += close + 2;
+            }
+            report.tests.push(TestPoint {
+                ok,
+                number,
+                description: description.to_string(),
+                directive,
+                diagnostics,
+                subtest: subtest.take().map(Box::new),
+            });
+        } else if let Some(reason) = line.strip_prefix("Bail out!") {
+            report.bail_out = Some(reason.trim().to_string());
+            break;
+        } else if let Some(comment) = line.strip_prefix('#') {
+            let comment = comment.trim();
+            match comment.strip_prefix("Subtest:") {
+                Some(name) if report.tests.is_empty() && report.plan.is_none() && indent > 0 => {
+                    report.name = Some(name.trim().to_string())
+                }
+                Some(name) => subtest_name = Some(name.trim().to_string()),
+                None => report.comments.push(comment.to_string()),
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// `ok ...` or `not ok ...`, with what follows.
+fn test_line(line: &str) -> Option<(bool, &str)> {
+    let (ok, rest) = match line.strip_prefix("not ok") {
+        Some(rest) => (false, rest),
+        None => (true, line.strip_prefix("ok")?),
+    };
+    (rest.is_empty() || rest.starts_with(' ')).then_some((ok, rest))
+}
+
+/// Splits off a `# SKIP` or `# TODO` directive; `\#` does not start one.
+fn split_directive(text: &str) -> (&str, Option<Directive>) {
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '#' => {
+                let rest = text[i + 1..].trim_start();
+                let word_end = rest.find([' ', '\t']).unwrap_or(rest.len());
+                let (word, reason) = (&rest[..word_end], rest[word_end..].trim().to_string());
+                let word = word.to_ascii_lowercase();
+                let directive = if word.starts_with("skip") {
+                    Directive::Skip(reason)
+                } else if word == "todo" {
+                    Directive::Todo(reason)
+                } else {
+                    continue;
+                };
+                return (&text[..i], Some(directive));
+            }
+            _ => {}
+        }
+    }
+    (text, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_tap_should_work() -> Result<(), ParseError> {
+        let report = parse_tap(
+            "TAP version 13\n\
+             1..5\n\
+             ok 1 - Input file opened\n\
+             not ok 2 - First line of the input valid\n\
+             \x20 ---\n\
+             \x20 message: 'First line invalid'\n\
+             \x20 severity: fail\n\
+             \x20 ...\n\
+             ok 3 - Read the rest of the file # skip no file\n\
+             not ok 4 - Summarized correctly # TODO Not written yet\n\
+             # all done\n\
+             ok 5 escaped \\# hash\n\
+             garbage is ignored\n",
+        )?;
+        assert_eq!(report.version, Some(13));
+        assert_eq!(
+            report.plan,
+            Some(Plan {
+                count: 5,
+                skip: None
+            })
+        );
+        assert_eq!(report.tests.len(), 5);
+        let second = &report.tests[1];
+        assert!(!second.ok);
+        assert_eq!(second.number, Some(2));
+        assert_eq!(second.description, "First line of the input valid");
+        assert_eq!(
+            second.diagnostics.as_deref(),
+            Some("message: 'First line invalid'\nseverity: fail")
+        );
+        assert_eq!(
+            report.tests[2].directive,
+            Some(Directive::Skip("no file".into()))
+        );
+        assert_eq!(report.tests[2].description, "Read the rest of the file");
+        assert!(!report.tests[3].is_failure());
+        assert_eq!(report.tests[4].description, "escaped \\# hash");
+        assert_eq!(report.comments, ["all done"]);
+        assert_eq!(report.failures().count(), 1);
+        assert!(!report.is_success());
+        Ok(())
+    }
+
+    #[test]
+    fn subtests_should_nest_by_indentation() -> Result<(), ParseError> {
+        let report = parse_tap(
+            "TAP version 14\n\
+             1..2\n\
+             # Subtest: foo.tap\n\
+             \x20   1..2\n\
+             \x20   ok 1\n\
+             \x20   not ok 2 - inner\n\
+             \x20     ---\n\
+             \x20     at: foo.tap:3\n\
+             \x20     ...\n\
+             not ok 1 - foo.tap\n\
+             \x20   # Subtest: bar\n\
+             \x20   ok 1 - deep\n\
+             \x20   1..1\n\
+             ok 2 - bar\n",
+        )?;
+        assert_eq!(report.tests.len(), 2);
+        let foo = report.tests[0].subtest.as_deref().unwrap();
+        assert_eq!(foo.name.as_deref(), Some("foo.tap"));
+        assert_eq!(foo.tests.len(), 2);
+        assert_eq!(foo.tests[1].diagnostics.as_deref(), Some("at: foo.tap:3"));
+        let bar = report.tests[1].subtest.as_deref().unwrap();
+        assert_eq!(bar.name.as_deref(), Some("bar"));
+        assert!(bar.is_success());
+        let skipped = parse_tap("1..0 # Skipped: no database\n")?;
+        assert_eq!(skipped.plan.unwrap().skip.as_deref(), Some("no database"));
+        let bailed = parse_tap("1..3\nok 1\nBail out! Disk full\nok 2\n")?;
+        assert_eq!(bailed.bail_out.as_deref(), Some("Disk full"));
+        assert_eq!(bailed.tests.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_tap_should_report_errors() {
+        let err = parse_tap("TAP version 13\n1..x\n").unwrap_err();
+        assert_eq!(err.offset(), 18);
+        let err = parse_tap("1..1\nnot ok 1\n  ---\n  a: b\n").unwrap_err();
+        assert_eq!(err.offset(), 14);
+        assert!(parse_tap("TAP version thirteen\n").is_err());
+        assert!(parse_tap("1..1\nok 1\n1..2\n").is_err());
+        assert!(parse_tap("1..1\n    1..x\nok 1\n").is_err());
+    }
+}
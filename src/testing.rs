@@ -0,0 +1,267 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::ParseError;
+
+/// Setting this environment variable to anything but `0` makes corpora
+/// rewrite their expectations instead of checking them.
+pub const BLESS_VAR: &str = "GRAMMAR_BLESS";
+
+/// A directory of test cases for one parser. Each case is a subdirectory
+/// holding an `input` file and the `expected.json` result of parsing it:
+/// the parsed value serialized, or `{"error": {"offset": .., "message": ..}}`
+/// when parsing fails.
+///
+/// In bless mode every `expected.json` is written from the current result,
+/// so new cases only need an `input`, and changed output can be reviewed as
+/// a diff of the corpus.
+#[derive(Debug, Clone)]
+pub struct Corpus {
+    dir: PathBuf,
+    bless: bool,
+}
+
+/// How one case compared with its expectation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    /// The expectation was written in bless mode.
+    Blessed,
+    /// There is no `expected.json` yet.
+    Missing,
+    /// The result differs; holds a line diff of the pretty-printed JSON,
+    /// `-` for expected lines and `+` for actual ones.
+    Failed(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Case {
+    pub name: String,
+    pub outcome: Outcome,
+}
+
+/// The outcome of every case in a corpus, in name order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub cases: Vec<Case>,
+}
+
+impl Corpus {
+    /// A corpus in `dir`, blessed if [`BLESS_VAR`] is set.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        let bless = std::env::var_os(BLESS_VAR).is_some_and(|value| value != "0");
+        Corpus {
+            dir: dir.into(),
+            bless,
+        }
+    }
+
+    pub fn bless(mut self, bless: bool) -> Self {
+        self.bless = bless;
+        self
+    }
+
+    /// Runs `parser` on the input of every case.
+    pub fn run<T: Serialize>(
+        &self,
+        mut parser: impl FnMut(&str) -> Result<T, ParseError>,
+    ) -> io::Result<Report> {
+        let mut dirs = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.join("input").is_file() {
+                dirs.push(path);
+            }
+        }
+        dirs.sort();
+
+        let mut cases = Vec::with_capacity(dirs.len());
+        for dir in dirs {
+            let input = fs::read_to_string(dir.join("input"))?;
+            let actual = match parser(&input) {
+                Ok(value) => serde_json::to_value(value).map_err(io::Error::other)?,
+                Err(e) => json!({ "error": { "offset": e.offset(), "message": e.message() } }),
+            };
+            cases.push(Case {
+                name: dir
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                outcome: self.check(&dir, &actual)?,
+            });
+        }
+        Ok(Report { cases })
+    }
+
+    fn check(&self, dir: &Path, actual: &Value) -> io::Result<Outcome> {
+        let path = dir.join("expected.json");
+        let actual_text = pretty(actual)?;
+        if self.bless {
+            fs::write(&path, actual_text + "\n")?;
+            return Ok(Outcome::Blessed);
+        }
+        let expected_text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Outcome::Missing),
+            Err(e) => return Err(e),
+        };
+        let expected: Value = serde_json::from_str(&expected_text).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {e}", path.display()),
+            )
+        })?;
+        if &expected == actual {
+            return Ok(Outcome::Passed);
+        }
+        Ok(Outcome::Failed(diff(&pretty(&expected)?, &actual_text)))
+    }
+}
+
+impl Report {
+    /// Cases that neither passed nor were blessed.
+    pub fn failures(&self) -> impl Iterator<Item = &Case> {
+        self.cases
+            .iter()
+            .filter(|case| matches!(case.outcome, Outcome::Failed(_) | Outcome::Missing))
+    }
+
+    pub fn passed(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Panics with every failure, for use at the end of a test.
+    #[track_caller]
+    pub fn assert_passed(&self) {
+        if !self.passed() {
+            panic!("{self}rerun with {BLESS_VAR}=1 to accept the new results");
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for case in self.failures() {
+            match &case.outcome {
+                Outcome::Failed(diff) => write!(f, "{} differs:\n{diff}", case.name)?,
+                _ => writeln!(f, "{} has no expected.json", case.name)?,
+            }
+        }
+        let failed = self.failures().count();
+        writeln!(f, "{} of {} cases failed", failed, self.cases.len())
+    }
+}
+
+fn pretty(value: &Value) -> io::Result<String> {
+    serde_json::to_string_pretty(value).map_err(io::Error::other)
+}
+
+/// A line diff from the longest common subsequence of lines.
+fn diff(expected: &str, actual: &str) -> String {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+    // `common[i][j]` is the LCS length of `old[i..]` and `new[j..]`.
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        let line = if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+            format!(" {}", old[i - 1])
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            i += 1;
+            format!("-{}", old[i - 1])
+        } else {
+            j += 1;
+            format!("+{}", new[j - 1])
+        };
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    /// A fresh directory with a case for each `(name, input)`.
+    fn corpus(test: &str, cases: &[(&str, &str)]) -> io::Result<PathBuf> {
+        let dir = std::env::temp_dir().join(format!("grammar-{test}-{}", std::process::id()));
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        for (name, input) in cases {
+            fs::create_dir_all(dir.join(name))?;
+            fs::write(dir.join(name).join("input"), input)?;
+        }
+        Ok(dir)
+    }
+
+    #[test]
+    fn corpus_should_check_the_checked_in_cases() -> io::Result<()> {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("corpus/json");
+        let report = Corpus::new(dir).run(parse_json)?;
+        report.assert_passed();
+        assert_eq!(report.cases.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn corpus_should_bless_then_pass() -> io::Result<()> {
+        let dir = corpus("bless", &[("array", "[1, 2]"), ("broken", "[1,")])?;
+        let report = Corpus::new(&dir).bless(false).run(parse_json)?;
+        assert_eq!(report.failures().count(), 2);
+        assert!(!report.passed());
+
+        let report = Corpus::new(&dir).bless(true).run(parse_json)?;
+        assert!(
+            report
+                .cases
+                .iter()
+                .all(|case| case.outcome == Outcome::Blessed)
+        );
+        let expected: Value =
+            serde_json::from_str(&fs::read_to_string(dir.join("broken/expected.json"))?)?;
+        assert_eq!(expected["error"]["offset"], 3);
+
+        Corpus::new(&dir)
+            .bless(false)
+            .run(parse_json)?
+            .assert_passed();
+        fs::remove_dir_all(dir)
+    }
+
+    #[test]
+    fn corpus_should_diff_changed_results() -> io::Result<()> {
+        let dir = corpus("diff", &[("array", "[1, 3]")])?;
+        fs::write(dir.join("array/expected.json"), "[\n  1.0,\n  2.0\n]\n")?;
+        let report = Corpus::new(&dir).bless(false).run(parse_json)?;
+        assert_eq!(
+            report.cases[0].outcome,
+            Outcome::Failed(" [\n   1.0,\n-  2.0\n+  3.0\n ]\n".to_string())
+        );
+        assert_eq!(
+            report.to_string(),
+            "array differs:\n [\n   1.0,\n-  2.0\n+  3.0\n ]\n1 of 1 cases failed\n"
+        );
+        fs::remove_dir_all(dir)
+    }
+}
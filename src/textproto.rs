@@ -0,0 +1,400 @@
+use std::fmt;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{digit1, hex_digit1, oct_digit1};
+use winnow::combinator::{alt, cut_err, delimited, opt, preceded, repeat, separated, terminated};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::{any, none_of, one_of, take_while};
+
+use crate::ParseError;
+use crate::combinators::Trivia;
+
+/// A message read without its schema. Fields keep their order, and a
+/// repeated field appears once per element.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Message {
+    pub fields: Vec<(String, Value)>,
+}
+
+/// A field value. Without the schema an identifier can only be an enum
+/// constant, except for the spellings of `true` and `false`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// Wide enough for every `int64` and `uint64` value.
+    Integer(i128),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    /// A string literal whose escapes produce invalid UTF-8.
+    Bytes(Vec<u8>),
+    Enum(String),
+    Message(Message),
+}
+
+impl Message {
+    /// The first value of `name`. Extension fields are named with their
+    /// brackets, as in `"[pkg.ext]"`.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.get_all(name).next()
+    }
+
+    pub fn get_all<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a Value> {
+        self.fields
+            .iter()
+            .filter(move |(n, _)| n == name)
+            .map(|(_, v)| v)
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+        for (name, value) in &self.fields {
+            write!(f, "{:indent$}{name}", "")?;
+            match value {
+                Value::Message(message) => {
+                    f.write_str(" {\n")?;
+                    message.write(f, indent + 2)?;
+                    writeln!(f, "{:indent$}}}", "")?;
+                }
+                value => writeln!(f, ": {value}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Prints canonical text format, one field per line.
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, 0)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Integer(n) => write!(f, "{n}"),
+            Value::Float(x) if x.is_nan() => f.write_str("nan"),
+            Value::Float(x) if x.is_infinite() => {
+                f.write_str(if *x > 0.0 { "inf" } else { "-inf" })
+            }
+            Value::Float(x) => write!(f, "{x:?}"),
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::String(s) => write_quoted(f, s.as_bytes()),
+            Value::Bytes(b) => write_quoted(f, b),
+            Value::Enum(name) => f.write_str(name),
+            Value::Message(message) => {
+                f.write_str("{ ")?;
+                for (name, value) in &message.fields {
+                    match value {
+                        Value::Message(_) => write!(f, "{name} {value} ")?,
+                        value => write!(f, "{name}: {value} ")?,
+                    }
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+fn write_quoted(f: &mut fmt::Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    f.write_str("\"")?;
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                c if c.is_control() => write!(f, "\\{:03o}", c as u32)?,
+                c => write!(f, "{c}")?,
+            }
+        }
+        for b in chunk.invalid() {
+            write!(f, "\\{b:03o}")?;
+        }
+    }
+    f.write_str("\"")
+}
+
+/// Parses a text-format message such as a `.txtpb` file or the output of
+/// `DebugString()`.
+pub fn parse_text_proto(input: &str) -> Result<Message, ParseError> {
+    delimited(trivia, fields, trivia)
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+fn fields(input: &mut &str) -> ModalResult<Message> {
+    let groups: Vec<Vec<(String, Value)>> = repeat(
+        0..,
+        terminated(field, (trivia, opt(one_of([',', ';'])), trivia)),
+    )
+    .parse_next(input)?;
+    Ok(Message {
+        fields: groups.concat(),
+    })
+}
+
+/// One field; list syntax (`tags: [1, 2]`) yields one entry per element.
+fn field(input: &mut &str) -> ModalResult<Vec<(String, Value)>> {
+    let name = field_name.parse_next(input)?;
+    trivia.parse_next(input)?;
+    if opt(':').parse_next(input)?.is_some() {
+        trivia.parse_next(input)?;
+        let values = cut_err(alt((
+            delimited(
+                ('[', trivia),
+                separated(0.., value, (trivia, ',', trivia)),
+                (trivia, cut_err(']')),
+            ),
+            value.map(|v| vec![v]),
+        )))
+        .context(StrContext::Label("field value"))
+        .parse_next(input)?;
+        return Ok(values.into_iter().map(|v| (name.clone(), v)).collect());
+    }
+    // The colon is optional before a message.
+    let message = cut_err(message)
+        .context(StrContext::Expected(StrContextValue::CharLiteral(':')))
+        .parse_next(input)?;
+    Ok(vec![(name, Value::Message(message))])
+}
+
+/// `name`, or `[pkg.extension]` / `[type.googleapis.com/pkg.Any]`.
+fn field_name(input: &mut &str) -> ModalResult<String> {
+    alt((
+        identifier.map(str::to_string),
+        (
+            '[',
+            cut_err(take_while(1.., |c: char| {
+                c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '/')
+            })),
+            cut_err(']'),
+        )
+            .take()
+            .map(str::to_string),
+    ))
+    .parse_next(input)
+}
+
+fn value(input: &mut &str) -> ModalResult<Value> {
+    alt((
+        message.map(Value::Message),
+        string,
+        number,
+        identifier.map(|id| match id {
+            "true" | "True" => Value::Bool(true),
+            "false" | "False" => Value::Bool(false),
+            _ => Value::Enum(id.to_string()),
+        }),
+    ))
+    .parse_next(input)
+}
+
+pub(crate) fn message(input: &mut &str) -> ModalResult<Message> {
+    let close = alt(('{'.value('}'), '<'.value('>'))).parse_next(input)?;
+    let message = preceded(trivia, fields).parse_next(input)?;
+    cut_err(close)
+        .context(StrContext::Expected(StrContextValue::CharLiteral(close)))
+        .parse_next(input)?;
+    Ok(message)
+}
+
+/// Adjacent literals are concatenated, as in C.
+pub(crate) fn string(input: &mut &str) -> ModalResult<Value> {
+    let mut bytes = quoted.parse_next(input)?;
+    let rest: Vec<Vec<u8>> = repeat(0.., preceded(trivia, quoted)).parse_next(input)?;
+    bytes.extend(rest.concat());
+    Ok(match String::from_utf8(bytes) {
+        Ok(s) => Value::String(s),
+        Err(e) => Value::Bytes(e.into_bytes()),
+    })
+}
+
+fn quoted(input: &mut &str) -> ModalResult<Vec<u8>> {
+    let quote = one_of(['"', '\'']).parse_next(input)?;
+    let mut bytes = Vec::new();
+    loop {
+        let c = cut_err(none_of(['\n']))
+            .context(StrContext::Expected(StrContextValue::Description(
+                "closing quote",
+            )))
+            .parse_next(input)?;
+        if c == quote {
+            return Ok(bytes);
+        }
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        let escaped = cut_err(alt((
+            preceded(
+                one_of(['x', 'X']),
+                take_while(1..=2, |c: char| c.is_ascii_hexdigit())
+                    .map(|h| vec![u8::from_str_radix(h, 16).unwrap()]),
+            ),
+            take_while(1..=3, |c: char| matches!(c, '0'..='7'))
+                .verify_map(|o| u8::from_str_radix(o, 8).ok().map(|b| vec![b])),
+            any.verify_map(|c| {
+                let b = match c {
+                    'n' => b'\n',
+                    't' => b'\t',
+                    'r' => b'\r',
+                    'a' => 0x07,
+                    'b' => 0x08,
+                    'f' => 0x0c,
+                    'v' => 0x0b,
+                    '\\' | '\'' | '"' | '?' => c as u8,
+                    _ => return None,
+                };
+                Some(vec![b])
+            }),
+        )))
+        .context(StrContext::Label("escape sequence"))
+        .parse_next(input)?;
+        bytes.extend(escaped);
+    }
+}
+
+pub(crate) fn number(input: &mut &str) -> ModalResult<Value> {
+    let negative = opt(terminated('-', trivia)).parse_next(input)?.is_some();
+    let sign = if negative { -1 } else { 1 };
+    alt((
+        preceded(alt(("0x", "0X")), cut_err(hex_digit1))
+            .verify_map(|h| i128::from_str_radix(h, 16).ok())
+            .map(|n| Value::Integer(sign * n)),
+        float.map(|x| Value::Float(sign as f64 * x)),
+        preceded('0', oct_digit1)
+            .verify_map(|o| i128::from_str_radix(o, 8).ok())
+            .map(|n| Value::Integer(sign * n)),
+        digit1
+            .verify_map(|d: &str| d.parse::<i128>().ok())
+            .map(|n| Value::Integer(sign * n)),
+        identifier.verify_map(|word| match word.to_ascii_lowercase().as_str() {
+            "inf" | "infinity" => Some(Value::Float(sign as f64 * f64::INFINITY)),
+            "nan" => Some(Value::Float(f64::NAN)),
+            _ => None,
+        }),
+    ))
+    .parse_next(input)
+}
+
+/// A literal with a fraction, exponent or `f` suffix.
+fn float(input: &mut &str) -> ModalResult<f64> {
+    let text = alt((
+        (
+            digit1,
+            alt((
+                (
+                    '.',
+                    opt(digit1),
+                    opt((one_of(['e', 'E']), opt(one_of(['+', '-'])), digit1)),
+                )
+                    .void(),
+                (one_of(['e', 'E']), opt(one_of(['+', '-'])), digit1).void(),
+                one_of(['f', 'F']).void(),
+            )),
+        )
+            .take(),
+        (
+            '.',
+            digit1,
+            opt((one_of(['e', 'E']), opt(one_of(['+', '-'])), digit1)),
+        )
+            .take(),
+    ))
+    .parse_next(input)?;
+    opt(one_of(['f', 'F'])).parse_next(input)?;
+    Ok(text
+        .trim_end_matches(['f', 'F'])
+        .parse()
+        .unwrap_or(f64::NAN))
+}
+
+fn identifier<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    (
+        one_of(|c: char| c.is_ascii_alphabetic() || c == '_'),
+        take_while(0.., |c: char| c.is_ascii_alphanumeric() || c == '_'),
+    )
+        .take()
+        .parse_next(input)
+}
+
+/// White space and `#` comments.
+fn trivia(input: &mut &str) -> ModalResult<()> {
+    let mut trivia = Trivia::HASH;
+    trivia.parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMP: &str = r#"
+        # A server config.
+        name: "web" 'server'
+        port: 8080
+        ratio: -0.5f
+        mask: 0x1F, mode: 017;
+        enabled: True
+        level: DEBUG
+        tags: ["a", "b"]
+        backend { host: "10.0.0.1" weight: 3 }
+        backend: < host: "10.0.0.2" >
+        [ext.priority]: 2
+        raw: "\xff\001"
+    "#;
+
+    #[test]
+    fn parse_text_proto_should_work() -> Result<(), ParseError> {
+        let message = parse_text_proto(DUMP)?;
+        assert_eq!(
+            message.get("name"),
+            Some(&Value::String("webserver".to_string()))
+        );
+        assert_eq!(message.get("port"), Some(&Value::Integer(8080)));
+        assert_eq!(message.get("ratio"), Some(&Value::Float(-0.5)));
+        assert_eq!(message.get("mask"), Some(&Value::Integer(31)));
+        assert_eq!(message.get("mode"), Some(&Value::Integer(15)));
+        assert_eq!(message.get("enabled"), Some(&Value::Bool(true)));
+        assert_eq!(
+            message.get("level"),
+            Some(&Value::Enum("DEBUG".to_string()))
+        );
+        assert_eq!(message.get_all("tags").count(), 2);
+        assert_eq!(message.get("[ext.priority]"), Some(&Value::Integer(2)));
+        assert_eq!(message.get("raw"), Some(&Value::Bytes(vec![0xff, 0x01])));
+
+        let backends: Vec<_> = message
+            .get_all("backend")
+            .map(|b| match b {
+                Value::Message(m) => m.get("host").unwrap().to_string(),
+                other => panic!("expected a message, got {other:?}"),
+            })
+            .collect();
+        assert_eq!(backends, ["\"10.0.0.1\"", "\"10.0.0.2\""]);
+        Ok(())
+    }
+
+    #[test]
+    fn text_proto_should_roundtrip() -> Result<(), ParseError> {
+        let message = parse_text_proto(DUMP)?;
+        let printed = message.to_string();
+        assert!(printed.contains("backend {\n  host: \"10.0.0.1\"\n  weight: 3\n}\n"));
+        assert!(printed.contains("raw: \"\\377\\001\"\n"));
+        assert_eq!(parse_text_proto(&printed)?, message);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_text_proto_should_report_errors() {
+        assert_eq!(parse_text_proto("a: 1\nb { c: 2").unwrap_err().offset(), 13);
+        assert!(parse_text_proto("a 1").is_err());
+        assert!(parse_text_proto("a: \"open").is_err());
+        assert!(parse_text_proto("a: [1, 2").is_err());
+        assert!(parse_text_proto("a: \"\\q\"").is_err());
+        assert!(parse_text_proto("a: {b: 1>").is_err());
+    }
+}
@@ -0,0 +1,785 @@
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{digit1, hex_digit1};
+use winnow::combinator::{
+    alt, cut_err, delimited, not, opt, preceded, repeat, separated, terminated,
+};
+use winnow::error::{ContextError, ErrMode, StrContext, StrContextValue};
+use winnow::token::{any, none_of, one_of, take_while};
+
+use crate::ParseError;
+use crate::combinators::Trivia;
+
+/// A Thrift IDL file: its headers followed by its definitions, in order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Document {
+    pub includes: Vec<String>,
+    pub cpp_includes: Vec<String>,
+    pub namespaces: Vec<Namespace>,
+    pub definitions: Vec<Definition>,
+}
+
+/// `namespace <scope> <name>`, where the scope is a language such as `java`
+/// or `*` for all of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Namespace {
+    pub scope: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Definition {
+    Const(Const),
+    Typedef(Typedef),
+    Enum(Enum),
+    Struct(Struct),
+    Service(Service),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Const {
+    pub ty: Type,
+    pub name: String,
+    pub value: ConstValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Typedef {
+    pub ty: Type,
+    pub name: String,
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enum {
+    pub name: String,
+    pub values: Vec<EnumValue>,
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumValue {
+    pub name: String,
+    /// Values without an explicit number follow the previous one, starting
+    /// from zero.
+    pub value: i64,
+    pub annotations: Vec<Annotation>,
+}
+
+/// A `struct`, `union` or `exception`; the three share one syntax.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Struct {
+    pub kind: StructKind,
+    pub name: String,
+    pub fields: Vec<Field>,
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StructKind {
+    Struct,
+    Union,
+    Exception,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    /// Missing ids are allowed but deprecated; the compiler assigns
+    /// negative ones.
+    pub id: Option<i32>,
+    pub requiredness: Requiredness,
+    pub ty: Type,
+    pub name: String,
+    pub default: Option<ConstValue>,
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Requiredness {
+    /// Written when set, read when present.
+    #[default]
+    Default,
+    Required,
+    Optional,
+}
+
+/// A field or return type. Annotations written directly after a type are
+/// accepted and dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    Bool,
+    /// `byte` and `i8`.
+    Byte,
+    I16,
+    I32,
+    I64,
+    Double,
+    String,
+    Binary,
+    Uuid,
+    /// A struct, enum or typedef, qualified as `other.Name` when it comes
+    /// from an include.
+    Named(String),
+    Map(Box<Type>, Box<Type>),
+    Set(Box<Type>),
+    List(Box<Type>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstValue {
+    Int(i64),
+    Double(f64),
+    String(String),
+    /// A reference to another constant or an enum value.
+    Identifier(String),
+    List(Vec<ConstValue>),
+    Map(Vec<(ConstValue, ConstValue)>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Service {
+    pub name: String,
+    pub extends: Option<String>,
+    pub functions: Vec<Function>,
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub oneway: bool,
+    /// `None` for `void`.
+    pub returns: Option<Type>,
+    pub name: String,
+    pub params: Vec<Field>,
+    pub throws: Vec<Field>,
+    pub annotations: Vec<Annotation>,
+}
+
+/// `key = "value"` inside parentheses. A bare key has no value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    pub key: String,
+    pub value: Option<String>,
+}
+
+impl Document {
+    pub fn find(&self, name: &str) -> Option<&Definition> {
+        self.definitions.iter().find(|d| d.name() == name)
+    }
+
+    pub fn structs(&self) -> impl Iterator<Item = &Struct> {
+        self.definitions.iter().filter_map(|d| match d {
+            Definition::Struct(s) => Some(s),
+            _ => None,
+        })
+    }
+
+    pub fn services(&self) -> impl Iterator<Item = &Service> {
+        self.definitions.iter().filter_map(|d| match d {
+            Definition::Service(s) => Some(s),
+            _ => None,
+        })
+    }
+}
+
+impl Definition {
+    pub fn name(&self) -> &str {
+        match self {
+            Definition::Const(c) => &c.name,
+            Definition::Typedef(t) => &t.name,
+            Definition::Enum(e) => &e.name,
+            Definition::Struct(s) => &s.name,
+            Definition::Service(s) => &s.name,
+        }
+    }
+}
+
+impl Struct {
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+/// Parses a Thrift IDL file. Headers must come before definitions, as the
+/// Thrift compiler requires.
+pub fn parse_thrift(input: &str) -> Result<Document, ParseError> {
+    (
+        repeat(0.., preceded(trivia, header)),
+        repeat(0.., preceded(trivia, definition)),
+        trivia,
+    )
+        .map(|(headers, definitions, _): (Vec<Header>, _, _)| {
+            let mut document = Document {
+                definitions,
+                ..Default::default()
+            };
+            for header in headers {
+                match header {
+                    Header::Include(path) => document.includes.push(path),
+                    Header::CppInclude(path) => document.cpp_includes.push(path),
+                    Header::Namespace(ns) => document.namespaces.push(ns),
+                }
+            }
+            document
+        })
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+enum Header {
+    Include(String),
+    CppInclude(String),
+    Namespace(Namespace),
+}
+
+fn header(input: &mut &str) -> ModalResult<Header> {
+    alt((
+        preceded(keyword("include"), cut_err(preceded(trivia, literal))).map(Header::Include),
+        preceded(keyword("cpp_include"), cut_err(preceded(trivia, literal)))
+            .map(Header::CppInclude),
+        preceded(
+            keyword("namespace"),
+            cut_err((
+                preceded(trivia, alt(("*", identifier))),
+                preceded(trivia, identifier),
+            )),
+        )
+        .map(|(scope, name)| {
+            Header::Namespace(Namespace {
+                scope: scope.to_string(),
+                name: name.to_string(),
+            })
+        }),
+    ))
+    .parse_next(input)
+}
+
+fn definition(input: &mut &str) -> ModalResult<Definition> {
+    alt((
+        constant.map(Definition::Const),
+        typedef.map(Definition::Typedef),
+        enumeration.map(Definition::Enum),
+        structure.map(Definition::Struct),
+        service.map(Definition::Service),
+    ))
+    .context(StrContext::Label("definition"))
+    .parse_next(input)
+}
+
+fn constant(input: &mut &str) -> ModalResult<Const> {
+    keyword("const").parse_next(input)?;
+    let (ty, name, _, value) = cut_err((
+        preceded(trivia, field_type),
+        preceded(trivia, identifier),
+        expect('='),
+        preceded(trivia, const_value),
+    ))
+    .parse_next(input)?;
+    list_separator(input)?;
+    Ok(Const {
+        ty,
+        name: name.to_string(),
+        value,
+    })
+}
+
+fn typedef(input: &mut &str) -> ModalResult<Typedef> {
+    keyword("typedef").parse_next(input)?;
+    let (ty, name) =
+        cut_err((preceded(trivia, field_type), preceded(trivia, identifier))).parse_next(input)?;
+    let annotations = annotations(input)?;
+    list_separator(input)?;
+    Ok(Typedef {
+        ty,
+        name: name.to_string(),
+        annotations,
+    })
+}
+
+fn enumeration(input: &mut &str) -> ModalResult<Enum> {
+    let name = preceded(keyword("enum"), cut_err(preceded(trivia, identifier)))
+        .context(StrContext::Label("enum name"))
+        .parse_next(input)?;
+    expect('{').parse_next(input)?;
+    let mut values: Vec<EnumValue> = Vec::new();
+    loop {
+        trivia(input)?;
+        if opt('}').parse_next(input)?.is_some() {
+            break;
+        }
+        let name = cut_err(identifier)
+            .context(StrContext::Label("enum value"))
+            .parse_next(input)?;
+        let explicit = opt(preceded(
+            expect_opt('='),
+            cut_err(preceded(trivia, int_constant)),
+        ))
+        .parse_next(input)?;
+        let value = explicit.unwrap_or_else(|| values.last().map_or(0, |v| v.value + 1));
+        let annotations = annotations(input)?;
+        list_separator(input)?;
+        values.push(EnumValue {
+            name: name.to_string(),
+            value,
+            annotations,
+        });
+    }
+    Ok(Enum {
+        name: name.to_string(),
+        values,
+        annotations: annotations(input)?,
+    })
+}
+
+fn structure(input: &mut &str) -> ModalResult<Struct> {
+    let kind = alt((
+        keyword("struct").value(StructKind::Struct),
+        keyword("union").value(StructKind::Union),
+        keyword("exception").value(StructKind::Exception),
+    ))
+    .parse_next(input)?;
+    let name = cut_err(preceded(trivia, identifier))
+        .context(StrContext::Label("struct name"))
+        .parse_next(input)?;
+    // A leftover from the XSD generator that the compiler still accepts.
+    opt(preceded(trivia, keyword("xsd_all"))).parse_next(input)?;
+    expect('{').parse_next(input)?;
+    let fields = field_list('}').parse_next(input)?;
+    Ok(Struct {
+        kind,
+        name: name.to_string(),
+        fields,
+        annotations: annotations(input)?,
+    })
+}
+
+/// Fields up to and including `close`. Two explicit ids may not collide.
+fn field_list<'i>(close: char) -> impl Parser<&'i str, Vec<Field>, ErrMode<ContextError>> {
+    move |input: &mut &'i str| {
+        let mut fields: Vec<Field> = Vec::new();
+        loop {
+            trivia(input)?;
+            if opt(close).parse_next(input)?.is_some() {
+                return Ok(fields);
+            }
+            let field =
+                cut_err(field.verify(|f: &Field| {
+                    f.id.is_none() || fields.iter().all(|other| other.id != f.id)
+                }))
+                .context(StrContext::Label("field"))
+                .context(StrContext::Expected(StrContextValue::Description(
+                    "a unique field id",
+                )))
+                .parse_next(input)?;
+            fields.push(field);
+        }
+    }
+}
+
+fn field(input: &mut &str) -> ModalResult<Field> {
+    let id = opt(terminated(
+        int_constant.verify_map(|n| i32::try_from(n).ok()),
+        expect(':'),
+    ))
+    .parse_next(input)?;
+    trivia(input)?;
+    let requiredness = opt(terminated(
+        alt((
+            keyword("required").value(Requiredness::Required),
+            keyword("optional").value(Requiredness::Optional),
+        )),
+        trivia,
+    ))
+    .parse_next(input)?
+    .unwrap_or_default();
+    let ty = field_type.parse_next(input)?;
+    let name = cut_err(preceded(trivia, identifier))
+        .context(StrContext::Label("field name"))
+        .parse_next(input)?;
+    let default = opt(preceded(
+        expect_opt('='),
+        cut_err(preceded(trivia, const_value)),
+    ))
+    .parse_next(input)?;
+    let annotations = annotations(input)?;
+    list_separator(input)?;
+    Ok(Field {
+        id,
+        requiredness,
+        ty,
+        name: name.to_string(),
+        default,
+        annotations,
+    })
+}
+
+fn service(input: &mut &str) -> ModalResult<Service> {
+    let name = preceded(keyword("service"), cut_err(preceded(trivia, identifier)))
+        .context(StrContext::Label("service name"))
+        .parse_next(input)?;
+    let extends = opt(preceded(
+        (trivia, keyword("extends")),
+        cut_err(preceded(trivia, identifier)),
+    ))
+    .parse_next(input)?;
+    expect('{').parse_next(input)?;
+    let functions = repeat(0.., preceded(trivia, function)).parse_next(input)?;
+    expect('}').parse_next(input)?;
+    Ok(Service {
+        name: name.to_string(),
+        extends: extends.map(str::to_string),
+        functions,
+        annotations: annotations(input)?,
+    })
+}
+
+fn function(input: &mut &str) -> ModalResult<Function> {
+    let oneway = opt(terminated(keyword("oneway"), trivia))
+        .parse_next(input)?
+        .is_some();
+    let returns = alt((keyword("void").map(|_| None), field_type.map(Some))).parse_next(input)?;
+    let name = cut_err(preceded(trivia, identifier))
+        .context(StrContext::Label("function name"))
+        .parse_next(input)?;
+    expect('(').parse_next(input)?;
+    let params = field_list(')').parse_next(input)?;
+    let throws = opt(preceded(
+        (trivia, keyword("throws")),
+        cut_err(preceded(expect('('), field_list(')'))),
+    ))
+    .parse_next(input)?
+    .unwrap_or_default();
+    let annotations = annotations(input)?;
+    list_separator(input)?;
+    Ok(Function {
+        oneway,
+        returns,
+        name: name.to_string(),
+        params,
+        throws,
+        annotations,
+    })
+}
+
+fn field_type(input: &mut &str) -> ModalResult<Type> {
+    let ty = alt((container_type, identifier.map(base_type)))
+        .context(StrContext::Label("type"))
+        .parse_next(input)?;
+    annotations(input)?;
+    Ok(ty)
+}
+
+fn base_type(name: &str) -> Type {
+    match name {
+        "bool" => Type::Bool,
+        "byte" | "i8" => Type::Byte,
+        "i16" => Type::I16,
+        "i32" => Type::I32,
+        "i64" => Type::I64,
+        "double" => Type::Double,
+        "string" => Type::String,
+        "binary" => Type::Binary,
+        "uuid" => Type::Uuid,
+        _ => Type::Named(name.to_string()),
+    }
+}
+
+fn container_type(input: &mut &str) -> ModalResult<Type> {
+    let kind = terminated(alt(("map", "set", "list")), (trivia, '<')).parse_next(input)?;
+    let element = || cut_err(preceded(trivia, field_type));
+    let ty = match kind {
+        "map" => {
+            let (key, _, value) = (element(), expect(','), element()).parse_next(input)?;
+            Type::Map(Box::new(key), Box::new(value))
+        }
+        "set" => Type::Set(Box::new(element().parse_next(input)?)),
+        _ => Type::List(Box::new(element().parse_next(input)?)),
+    };
+    expect('>').parse_next(input)?;
+    // `cpp_type "..."` only matters to the C++ generator.
+    opt((
+        trivia,
+        keyword("cpp_type"),
+        cut_err(preceded(trivia, literal)),
+    ))
+    .parse_next(input)?;
+    Ok(ty)
+}
+
+fn const_value(input: &mut &str) -> ModalResult<ConstValue> {
+    alt((
+        double.map(ConstValue::Double),
+        int_constant.map(ConstValue::Int),
+        literal.map(ConstValue::String),
+        identifier.map(|id| ConstValue::Identifier(id.to_string())),
+        delimited(
+            '[',
+            cut_err(repeat(0.., delimited(trivia, const_value, list_separator))),
+            expect(']'),
+        )
+        .map(ConstValue::List),
+        delimited(
+            '{',
+            cut_err(repeat(
+                0..,
+                (
+                    preceded(trivia, const_value),
+                    preceded(expect(':'), preceded(trivia, cut_err(const_value))),
+                    list_separator,
+                )
+                    .map(|(k, v, _)| (k, v)),
+            )),
+            expect('}'),
+        )
+        .map(ConstValue::Map),
+    ))
+    .context(StrContext::Label("constant"))
+    .parse_next(input)
+}
+
+fn int_constant(input: &mut &str) -> ModalResult<i64> {
+    let sign = opt(one_of(['+', '-'])).parse_next(input)?;
+    let n = alt((
+        preceded("0x", cut_err(hex_digit1)).verify_map(|h| i64::from_str_radix(h, 16).ok()),
+        digit1.verify_map(|d: &str| d.parse::<i64>().ok()),
+    ))
+    .parse_next(input)?;
+    Ok(if sign == Some('-') { -n } else { n })
+}
+
+/// A number with a fraction or an exponent.
+fn double(input: &mut &str) -> ModalResult<f64> {
+    let exponent = || (one_of(['e', 'E']), opt(one_of(['+', '-'])), digit1);
+    (
+        opt(one_of(['+', '-'])),
+        alt((
+            (digit1, '.', opt(digit1), opt(exponent())).void(),
+            ('.', digit1, opt(exponent())).void(),
+            (digit1, exponent()).void(),
+        )),
+    )
+        .take()
+        .parse_to()
+        .parse_next(input)
+}
+
+fn literal(input: &mut &str) -> ModalResult<String> {
+    let quote = one_of(['"', '\'']).parse_next(input)?;
+    cut_err(terminated(
+        repeat(
+            0..,
+            alt((
+                preceded(
+                    '\\',
+                    any.verify_map(|c| match c {
+                        'n' => Some('\n'),
+                        't' => Some('\t'),
+                        'r' => Some('\r'),
+                        '\\' | '"' | '\'' => Some(c),
+                        _ => None,
+                    }),
+                ),
+                none_of(move |c| c == quote || c == '\\'),
+            )),
+        ),
+        quote,
+    ))
+    .context(StrContext::Label("string literal"))
+    .parse_next(input)
+}
+
+/// `(key = "value", flag)` after a definition, field or type.
+fn annotations(input: &mut &str) -> ModalResult<Vec<Annotation>> {
+    let annotation = (
+        preceded(trivia, identifier),
+        opt(preceded(
+            expect_opt('='),
+            cut_err(preceded(trivia, literal)),
+        )),
+    )
+        .map(|(key, value): (&str, _)| Annotation {
+            key: key.to_string(),
+            value,
+        });
+    opt(preceded(
+        (trivia, '('),
+        cut_err(terminated(
+            separated(1.., annotation, (trivia, one_of([',', ';']))),
+            (opt((trivia, one_of([',', ';']))), expect(')')),
+        )),
+    ))
+    .map(Option::unwrap_or_default)
+    .parse_next(input)
+}
+
+/// Thrift lets `,` or `;` follow almost anything, and never requires it.
+fn list_separator(input: &mut &str) -> ModalResult<()> {
+    opt((trivia, one_of([',', ';']))).void().parse_next(input)
+}
+
+/// Identifiers may contain dots, which is how included names are written.
+fn identifier<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    (
+        one_of(|c: char| c.is_ascii_alphabetic() || c == '_'),
+        take_while(0.., |c: char| {
+            c.is_ascii_alphanumeric() || c == '_' || c == '.'
+        }),
+    )
+        .take()
+        .parse_next(input)
+}
+
+fn keyword<'i>(word: &'static str) -> impl Parser<&'i str, &'i str, ErrMode<ContextError>> {
+    terminated(
+        word,
+        not(one_of(|c: char| {
+            c.is_ascii_alphanumeric() || c == '_' || c == '.'
+        })),
+    )
+}
+
+fn expect<'i>(c: char) -> impl Parser<&'i str, char, ErrMode<ContextError>> {
+    preceded(
+        trivia,
+        cut_err(c).context(StrContext::Expected(StrContextValue::CharLiteral(c))),
+    )
+}
+
+/// Like `expect`, but backtracks when `c` is missing.
+fn expect_opt<'i>(c: char) -> impl Parser<&'i str, char, ErrMode<ContextError>> {
+    preceded(trivia, c)
+}
+
+/// White space and all three comment styles: `#`, `//` and `/* */`.
+const TRIVIA: Trivia = Trivia::C.line_comments(&["//", "#"]);
+
+fn trivia(input: &mut &str) -> ModalResult<()> {
+    let mut trivia = TRIVIA;
+    trivia.parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TUTORIAL: &str = r#"
+        include "shared.thrift"
+        namespace java tutorial
+        namespace * tutorial
+
+        /** Doc comments are plain block comments. */
+        typedef i32 MyInteger (js.type = "Int")
+        const i32 INT32CONSTANT = 9853
+        const map<string,string> MAPCONSTANT = {'hello':'world', 'goodnight':'moon'}
+        const list<double> RATES = [1.5, -2e3, 10];
+
+        enum Operation {
+          ADD = 1,
+          SUBTRACT,
+          MULTIPLY = 0x10;
+          DIVIDE
+        }
+
+        struct Work {
+          1: i32 num1 = 0,
+          2: i32 num2,
+          3: Operation op,
+          4: optional string comment (deprecated),
+          5: required list<map<i64, set<binary>>> blobs
+        } (final = "true")
+
+        exception InvalidOperation {
+          1: i32 whatOp,
+          2: string why
+        }
+
+        service Calculator extends shared.SharedService {
+           void ping(),
+           i32 add(1:i32 num1, 2:i32 num2),
+           i32 calculate(1:i32 logid, 2:Work w) throws (1:InvalidOperation ouch),
+           oneway void zip() (priority = "low")
+        }
+    "#;
+
+    #[test]
+    fn parse_thrift_should_work() -> Result<(), ParseError> {
+        let doc = parse_thrift(TUTORIAL)?;
+        assert_eq!(doc.includes, ["shared.thrift"]);
+        assert_eq!(doc.namespaces[1].scope, "*");
+        let Some(Definition::Typedef(typedef)) = doc.find("MyInteger") else {
+            panic!("missing typedef");
+        };
+        assert_eq!(typedef.ty, Type::I32);
+        assert_eq!(typedef.annotations[0].key, "js.type");
+        let Some(Definition::Const(rates)) = doc.find("RATES") else {
+            panic!("missing const");
+        };
+        assert_eq!(
+            rates.value,
+            ConstValue::List(vec![
+                ConstValue::Double(1.5),
+                ConstValue::Double(-2000.0),
+                ConstValue::Int(10),
+            ])
+        );
+        let Some(Definition::Enum(op)) = doc.find("Operation") else {
+            panic!("missing enum");
+        };
+        let values: Vec<_> = op.values.iter().map(|v| v.value).collect();
+        assert_eq!(values, [1, 2, 16, 17]);
+        Ok(())
+    }
+
+    #[test]
+    fn structs_and_services_should_keep_their_details() -> Result<(), ParseError> {
+        let doc = parse_thrift(TUTORIAL)?;
+        let work = doc.structs().next().unwrap();
+        assert_eq!(work.annotations[0].value.as_deref(), Some("true"));
+        assert_eq!(
+            work.field("num1").unwrap().default,
+            Some(ConstValue::Int(0))
+        );
+        let comment = work.field("comment").unwrap();
+        assert_eq!(comment.requiredness, Requiredness::Optional);
+        assert_eq!(comment.annotations[0].value, None);
+        assert_eq!(
+            work.field("blobs").unwrap().ty,
+            Type::List(Box::new(Type::Map(
+                Box::new(Type::I64),
+                Box::new(Type::Set(Box::new(Type::Binary)))
+            )))
+        );
+        assert_eq!(doc.structs().nth(1).unwrap().kind, StructKind::Exception);
+
+        let calculator = doc.services().next().unwrap();
+        assert_eq!(calculator.extends.as_deref(), Some("shared.SharedService"));
+        let [ping, add, calculate, zip] = calculator.functions.as_slice() else {
+            panic!("expected four functions");
+        };
+        assert_eq!(ping.returns, None);
+        assert_eq!(add.params[1].id, Some(2));
+        assert_eq!(
+            calculate.throws[0].ty,
+            Type::Named("InvalidOperation".to_string())
+        );
+        assert!(zip.oneway && !add.oneway);
+        assert_eq!(zip.annotations[0].key, "priority");
+        Ok(())
+    }
+
+    #[test]
+    fn parse_thrift_should_report_errors() {
+        assert_eq!(
+            parse_thrift("struct A {\n  1: i32 a,\n  1: i32 b\n}")
+                .unwrap_err()
+                .offset(),
+            25
+        );
+        assert!(parse_thrift("struct A { 1: i32 }").is_err());
+        assert!(parse_thrift("enum E { A = }").is_err());
+        assert!(parse_thrift("service S { void f( }").is_err());
+        assert!(parse_thrift("const string S = \"open").is_err());
+        assert!(parse_thrift("struct A {} include \"late.thrift\"").is_err());
+        assert!(parse_thrift("map<string> x").is_err());
+    }
+}
@@ -0,0 +1,1028 @@
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
+use std::fmt;
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat};
+use serde::{Serialize, Serializer};
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{line_ending, till_line_ending};
+use winnow::combinator::{alt, cut_err, delimited, opt, preceded, repeat, separated, trace};
+use winnow::error::{AddContext, ContextError, ErrMode, StrContext, StrContextValue};
+use winnow::stream::Stream;
+use winnow::token::{one_of, take_while};
+
+use crate::ParseError;
+use crate::combinators::Trivia;
+use crate::json::JsonValue;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TomlValue {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Datetime(TomlDatetime),
+    Array(Vec<TomlValue>),
+    Table(TomlTable),
+}
+
+pub type TomlTable = BTreeMap<String, TomlValue>;
+
+/// The four date-time flavors of TOML; only the first one names an instant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TomlDatetime {
+    Offset(DateTime<FixedOffset>),
+    Local(NaiveDateTime),
+    LocalDate(NaiveDate),
+    LocalTime(NaiveTime),
+}
+
+/// Parses a TOML document into its root table.
+pub fn parse_toml(input: &str) -> Result<TomlTable, ParseError> {
+    let items = parse_document.parse(input).map_err(ParseError::from)?;
+    let mut doc = Document::default();
+    for (offset, item) in items {
+        doc.apply(item)
+            .map_err(|message| ParseError::new(offset, message))?;
+    }
+    Ok(doc.root.into_table())
+}
+
+enum Item {
+    Table(Vec<String>),
+    ArrayOfTables(Vec<String>),
+    KeyValue(Vec<String>, TomlValue),
+}
+
+/// Splits the document into headers and key/value pairs, each with the byte
+/// offset it starts at so that semantic errors can point back at it.
+fn parse_document(input: &mut &str) -> ModalResult<Vec<(usize, Item)>> {
+    let len = input.len();
+    let mut items = Vec::new();
+    loop {
+        let _: () = repeat(0.., (ws, opt(comment), line_ending).void()).parse_next(input)?;
+        ws(input)?;
+        opt(comment).parse_next(input)?;
+        if input.is_empty() {
+            return Ok(items);
+        }
+        let offset = len - input.len();
+        let item = trace(
+            "item",
+            alt((
+                parse_array_table_header.map(Item::ArrayOfTables),
+                parse_table_header.map(Item::Table),
+                parse_keyval.map(|(k, v)| Item::KeyValue(k, v)),
+            )),
+        )
+        .parse_next(input)?;
+        items.push((offset, item));
+
+        ws(input)?;
+        opt(comment).parse_next(input)?;
+        if !input.is_empty() {
+            cut_err(line_ending)
+                .context(StrContext::Expected(StrContextValue::Description(
+                    "end of line",
+                )))
+                .parse_next(input)?;
+        }
+    }
+}
+
+fn ws<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    take_while(0.., [' ', '\t']).parse_next(input)
+}
+
+fn comment(input: &mut &str) -> ModalResult<()> {
+    ('#', till_line_ending).void().parse_next(input)
+}
+
+/// Whitespace, newlines and comments, as allowed between array elements.
+fn array_space(input: &mut &str) -> ModalResult<()> {
+    let mut trivia = Trivia::HASH;
+    trivia.parse_next(input)
+}
+
+/// Fails without backtracking, so the message survives enclosing `alt`s.
+fn cut<T>(input: &mut &str, label: &'static str, expected: &'static str) -> ModalResult<T> {
+    let start = input.checkpoint();
+    let e = ContextError::new()
+        .add_context(input, &start, StrContext::Label(label))
+        .add_context(
+            input,
+            &start,
+            StrContext::Expected(StrContextValue::Description(expected)),
+        );
+    Err(ErrMode::Cut(e))
+}
+
+fn parse_table_header(input: &mut &str) -> ModalResult<Vec<String>> {
+    delimited(('[', ws), cut_err(parse_key), cut_err((ws, ']')))
+        .context(StrContext::Label("table header"))
+        .parse_next(input)
+}
+
+fn parse_array_table_header(input: &mut &str) -> ModalResult<Vec<String>> {
+    delimited(("[[", ws), cut_err(parse_key), cut_err((ws, "]]")))
+        .context(StrContext::Label("array of tables header"))
+        .parse_next(input)
+}
+
+fn parse_keyval(input: &mut &str) -> ModalResult<(Vec<String>, TomlValue)> {
+    let key = parse_key(input)?;
+    let value = cut_err(preceded((ws, '=', ws), parse_value))
+        .context(StrContext::Label("key/value pair"))
+        .parse_next(input)?;
+    Ok((key, value))
+}
+
+fn parse_key(input: &mut &str) -> ModalResult<Vec<String>> {
+    separated(1.., parse_simple_key, (ws, '.', ws)).parse_next(input)
+}
+
+fn parse_simple_key(input: &mut &str) -> ModalResult<String> {
+    alt((
+        parse_basic_string,
+        parse_literal_string,
+        take_while(1.., ('A'..='Z', 'a'..='z', '0'..='9', '_', '-')).map(String::from),
+    ))
+    .parse_next(input)
+}
+
+fn parse_value(input: &mut &str) -> ModalResult<TomlValue> {
+    trace(
+        "value",
+        alt((
+            parse_string.map(TomlValue::String),
+            parse_bool.map(TomlValue::Boolean),
+            parse_datetime.map(TomlValue::Datetime),
+            parse_float.map(TomlValue::Float),
+            parse_integer.map(TomlValue::Integer),
+            parse_array.map(TomlValue::Array),
+            parse_inline_table.map(TomlValue::Table),
+        )),
+    )
+    .context(StrContext::Label("value"))
+    .parse_next(input)
+}
+
+fn parse_bool(input: &mut &str) -> ModalResult<bool> {
+    alt(("true".value(true), "false".value(false))).parse_next(input)
+}
+
+fn parse_string(input: &mut &str) -> ModalResult<String> {
+    alt((
+        parse_ml_basic_string,
+        parse_basic_string,
+        parse_ml_literal_string,
+        parse_literal_string,
+    ))
+    .parse_next(input)
+}
+
+/// Control characters other than tab are not allowed anywhere in strings.
+fn is_forbidden(c: char) -> bool {
+    (c.is_control() && c != '\t') || c == '\u{7f}'
+}
+
+fn parse_escape(input: &mut &str) -> ModalResult<char> {
+    '\\'.parse_next(input)?;
+    let c = match input.chars().next() {
+        Some(c) => c,
+        None => return cut(input, "escape sequence", "escaped character"),
+    };
+    let simple = match c {
+        'b' => Some('\u{8}'),
+        't' => Some('\t'),
+        'n' => Some('\n'),
+        'f' => Some('\u{c}'),
+        'r' => Some('\r'),
+        '"' => Some('"'),
+        '\\' => Some('\\'),
+        _ => None,
+    };
+    if let Some(escaped) = simple {
+        *input = &input[1..];
+        return Ok(escaped);
+    }
+    let digits = match c {
+        'u' => 4,
+        'U' => 8,
+        _ => return cut(input, "escape sequence", "one of `btnfr\"\\uU`"),
+    };
+    let hex = input
+        .get(1..digits + 1)
+        .filter(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()));
+    match hex
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .and_then(char::from_u32)
+    {
+        Some(escaped) => {
+            *input = &input[digits + 1..];
+            Ok(escaped)
+        }
+        None => cut(input, "escape sequence", "unicode scalar value"),
+    }
+}
+
+fn parse_basic_string(input: &mut &str) -> ModalResult<String> {
+    '"'.parse_next(input)?;
+    let mut s = String::new();
+    loop {
+        match input.chars().next() {
+            Some('"') => {
+                *input = &input[1..];
+                return Ok(s);
+            }
+            Some('\\') => s.push(parse_escape(input)?),
+            Some(c) if !is_forbidden(c) => {
+                s.push(c);
+                *input = &input[c.len_utf8()..];
+            }
+            _ => return cut(input, "string", "closing `\"`"),
+        }
+    }
+}
+
+fn parse_ml_basic_string(input: &mut &str) -> ModalResult<String> {
+    "\"\"\"".parse_next(input)?;
+    // A newline right after the opening delimiter is trimmed.
+    opt(line_ending).parse_next(input)?;
+    let mut s = String::new();
+    loop {
+        if let Some(closed) = ml_closing(input, '"', &mut s) {
+            return closed;
+        }
+        if input.starts_with('\\') {
+            // A line ending backslash eats all whitespace up to the next
+            // non-whitespace character.
+            let rest = input[1..].trim_start_matches([' ', '\t']);
+            if rest.starts_with('\n') || rest.starts_with("\r\n") {
+                *input = rest.trim_start_matches([' ', '\t', '\n', '\r']);
+            } else {
+                s.push(parse_escape(input)?);
+            }
+            continue;
+        }
+        if !ml_char(input, &mut s) {
+            return cut(input, "string", "closing `\"\"\"`");
+        }
+    }
+}
+
+fn parse_literal_string(input: &mut &str) -> ModalResult<String> {
+    '\''.parse_next(input)?;
+    let body = take_while(0.., |c: char| c != '\'' && !is_forbidden(c)).parse_next(input)?;
+    cut_err('\'')
+        .context(StrContext::Label("string"))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "closing `'`",
+        )))
+        .parse_next(input)?;
+    Ok(body.to_string())
+}
+
+fn parse_ml_literal_string(input: &mut &str) -> ModalResult<String> {
+    "'''".parse_next(input)?;
+    opt(line_ending).parse_next(input)?;
+    let mut s = String::new();
+    loop {
+        if let Some(closed) = ml_closing(input, '\'', &mut s) {
+            return closed;
+        }
+        if !ml_char(input, &mut s) {
+            return cut(input, "string", "closing `'''`");
+        }
+    }
+}
+
+/// Handles a run of `quote`s inside a multi-line string. Up to two quotes
+/// may directly precede the closing delimiter and belong to the content.
+fn ml_closing(input: &mut &str, quote: char, s: &mut String) -> Option<ModalResult<String>> {
+    let n = input.len() - input.trim_start_matches(quote).len();
+    if n == 0 {
+        return None;
+    }
+    if n > 5 {
+        return Some(cut(
+            input,
+            "string",
+            "at most two quotes before the delimiter",
+        ));
+    }
+    let content = if n >= 3 { n - 3 } else { n };
+    s.extend(std::iter::repeat_n(quote, content));
+    *input = &input[n..];
+    if n >= 3 {
+        Some(Ok(std::mem::take(s)))
+    } else {
+        None
+    }
+}
+
+/// Moves one literal character (or newline) of a multi-line string into `s`.
+fn ml_char(input: &mut &str, s: &mut String) -> bool {
+    if let Some(rest) = input.strip_prefix("\r\n") {
+        s.push('\n');
+        *input = rest;
+        return true;
+    }
+    match input.chars().next() {
+        Some(c) if c == '\n' || !is_forbidden(c) => {
+            s.push(c);
+            *input = &input[c.len_utf8()..];
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Digits with single underscores between them, returned without the
+/// underscores.
+fn underscored<'i>(digit: fn(char) -> bool) -> impl Parser<&'i str, String, ErrMode<ContextError>> {
+    (
+        one_of(digit),
+        take_while(0.., move |c: char| digit(c) || c == '_'),
+    )
+        .take()
+        .verify(|s: &str| !s.ends_with('_') && !s.contains("__"))
+        .map(|s: &str| s.replace('_', ""))
+}
+
+fn dec_int(input: &mut &str) -> ModalResult<String> {
+    let sign = opt(one_of(['+', '-'])).parse_next(input)?;
+    let digits = alt((
+        (
+            '0',
+            winnow::combinator::not(one_of(|c: char| c.is_ascii_digit() || c == '_')),
+        )
+            .value("0".to_string()),
+        // Leading zeros are not allowed.
+        underscored(|c| c.is_ascii_digit()).verify(|d: &String| !d.starts_with('0')),
+    ))
+    .parse_next(input)?;
+    Ok(match sign {
+        Some('-') => format!("-{}", digits),
+        _ => digits,
+    })
+}
+
+fn parse_integer(input: &mut &str) -> ModalResult<i64> {
+    alt((
+        preceded("0x", underscored(|c| c.is_ascii_hexdigit()))
+            .try_map(|s| i64::from_str_radix(&s, 16)),
+        preceded("0o", underscored(|c| ('0'..='7').contains(&c)))
+            .try_map(|s| i64::from_str_radix(&s, 8)),
+        preceded("0b", underscored(|c| c == '0' || c == '1'))
+            .try_map(|s| i64::from_str_radix(&s, 2)),
+        dec_int.try_map(|s| s.parse::<i64>()),
+    ))
+    .parse_next(input)
+}
+
+fn parse_float(input: &mut &str) -> ModalResult<f64> {
+    let special = (
+        opt(one_of(['+', '-'])),
+        alt(("inf".value(f64::INFINITY), "nan".value(f64::NAN))),
+    )
+        .map(|(sign, v)| if sign == Some('-') { -v } else { v });
+    let exp = || {
+        (
+            one_of(['e', 'E']),
+            opt(one_of(['+', '-'])),
+            underscored(|c| c.is_ascii_digit()),
+        )
+            .map(|(_, sign, digits)| format!("e{}{}", sign.unwrap_or('+'), digits))
+    };
+    let frac = preceded('.', underscored(|c| c.is_ascii_digit())).map(|d| format!(".{}", d));
+    let number = (
+        dec_int,
+        alt((
+            (frac, opt(exp())).map(|(f, e)| f + &e.unwrap_or_default()),
+            exp(),
+        )),
+    )
+        .try_map(|(int, rest)| format!("{}{}", int, rest).parse::<f64>());
+    alt((special, number)).parse_next(input)
+}
+
+fn fixed_digits<'i>(n: usize) -> impl Parser<&'i str, u32, ErrMode<ContextError>> {
+    take_while(n, |c: char| c.is_ascii_digit()).try_map(|s: &str| s.parse::<u32>())
+}
+
+fn parse_date(input: &mut &str) -> ModalResult<NaiveDate> {
+    (fixed_digits(4), '-', fixed_digits(2), '-', fixed_digits(2))
+        .verify_map(|(y, _, m, _, d)| NaiveDate::from_ymd_opt(y as i32, m, d))
+        .parse_next(input)
+}
+
+fn parse_time(input: &mut &str) -> ModalResult<NaiveTime> {
+    let (h, _, m, _, s) =
+        (fixed_digits(2), ':', fixed_digits(2), ':', fixed_digits(2)).parse_next(input)?;
+    let frac =
+        opt(preceded('.', take_while(1.., |c: char| c.is_ascii_digit()))).parse_next(input)?;
+    // Precision beyond nanoseconds is truncated, as the spec allows.
+    let nanos = frac.map_or(0, |f: &str| {
+        let f = &f[..f.len().min(9)];
+        f.parse::<u32>().unwrap_or(0) * 10u32.pow(9 - f.len() as u32)
+    });
+    NaiveTime::from_hms_nano_opt(h, m, s, nanos)
+        .ok_or_else(|| ErrMode::Backtrack(ContextError::new()))
+}
+
+fn parse_offset(input: &mut &str) -> ModalResult<FixedOffset> {
+    alt((
+        one_of(['Z', 'z']).map(|_| FixedOffset::east_opt(0)),
+        (one_of(['+', '-']), fixed_digits(2), ':', fixed_digits(2)).map(|(sign, h, _, m)| {
+            let secs = (h * 3600 + m * 60) as i32;
+            FixedOffset::east_opt(if sign == '-' { -secs } else { secs })
+        }),
+    ))
+    .verify_map(|offset| offset)
+    .parse_next(input)
+}
+
+fn parse_datetime(input: &mut &str) -> ModalResult<TomlDatetime> {
+    let date_time = (
+        parse_date,
+        opt((one_of(['T', 't', ' ']), parse_time, opt(parse_offset))),
+    )
+        .verify_map(|(date, rest)| match rest {
+            None => Some(TomlDatetime::LocalDate(date)),
+            Some((_, time, None)) => Some(TomlDatetime::Local(date.and_time(time))),
+            Some((_, time, Some(offset))) => date
+                .and_time(time)
+                .and_local_timezone(offset)
+                .single()
+                .map(TomlDatetime::Offset),
+        });
+    alt((date_time, parse_time.map(TomlDatetime::LocalTime))).parse_next(input)
+}
+
+fn parse_array(input: &mut &str) -> ModalResult<Vec<TomlValue>> {
+    '['.parse_next(input)?;
+    let mut values = Vec::new();
+    loop {
+        array_space(input)?;
+        if opt(']').parse_next(input)?.is_some() {
+            return Ok(values);
+        }
+        values.push(cut_err(parse_value).parse_next(input)?);
+        array_space(input)?;
+        if opt(',').parse_next(input)?.is_none() {
+            cut_err(']')
+                .context(StrContext::Label("array"))
+                .context(StrContext::Expected(StrContextValue::CharLiteral(',')))
+                .context(StrContext::Expected(StrContextValue::CharLiteral(']')))
+                .parse_next(input)?;
+            return Ok(values);
+        }
+    }
+}
+
+fn parse_inline_table(input: &mut &str) -> ModalResult<TomlTable> {
+    ('{', ws).parse_next(input)?;
+    let mut table = Table::default();
+    if opt('}').parse_next(input)?.is_some() {
+        return Ok(table.into_table());
+    }
+    loop {
+        let (keys, value) = cut_err(parse_keyval).parse_next(input)?;
+        if table.insert(&keys, value).is_err() {
+            return cut(input, "inline table", "unique keys");
+        }
+        ws(input)?;
+        if opt(',').parse_next(input)?.is_some() {
+            ws(input)?;
+            continue;
+        }
+        cut_err('}')
+            .context(StrContext::Label("inline table"))
+            .context(StrContext::Expected(StrContextValue::CharLiteral(',')))
+            .context(StrContext::Expected(StrContextValue::CharLiteral('}')))
+            .parse_next(input)?;
+        return Ok(table.into_table());
+    }
+}
+
+/// A table under construction, remembering how each sub-table came to be so
+/// that TOML's rules against redefining tables can be enforced.
+#[derive(Debug, Default)]
+struct Table {
+    entries: BTreeMap<String, Node>,
+    /// Defined by its own `[header]`.
+    explicit: bool,
+    /// Created by a dotted key, which rules out a later `[header]` for it.
+    dotted: bool,
+}
+
+#[derive(Debug)]
+enum Node {
+    /// Scalars, arrays and inline tables, none of which may be extended.
+    Value(TomlValue),
+    Table(Table),
+    ArrayOfTables(Vec<Table>),
+}
+
+impl Table {
+    fn insert(&mut self, keys: &[String], value: TomlValue) -> Result<(), String> {
+        let (last, parents) = keys.split_last().expect("keys are never empty");
+        let mut table = self;
+        for key in parents {
+            let node = table.entries.entry(key.clone()).or_insert_with(|| {
+                Node::Table(Table {
+                    dotted: true,
+                    ..Default::default()
+                })
+            });
+            table = match node {
+                Node::Table(t) if t.dotted => t,
+                _ => {
+                    return Err(format!(
+                        "cannot add keys to `{}`, it is already defined",
+                        key
+                    ));
+                }
+            };
+        }
+        match table.entries.entry(last.clone()) {
+            Entry::Occupied(_) => Err(format!("duplicate key `{}`", last)),
+            Entry::Vacant(e) => {
+                e.insert(Node::Value(value));
+                Ok(())
+            }
+        }
+    }
+
+    fn into_table(self) -> TomlTable {
+        self.entries
+            .into_iter()
+            .map(|(k, node)| {
+                let value = match node {
+                    Node::Value(v) => v,
+                    Node::Table(t) => TomlValue::Table(t.into_table()),
+                    Node::ArrayOfTables(tables) => TomlValue::Array(
+                        tables
+                            .into_iter()
+                            .map(|t| TomlValue::Table(t.into_table()))
+                            .collect(),
+                    ),
+                };
+                (k, value)
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Document {
+    root: Table,
+    /// Header of the table that key/value pairs currently go into.
+    current: Vec<String>,
+}
+
+impl Document {
+    fn apply(&mut self, item: Item) -> Result<(), String> {
+        match item {
+            Item::Table(keys) => self.open(keys, false),
+            Item::ArrayOfTables(keys) => self.open(keys, true),
+            Item::KeyValue(keys, value) => self.current_table().insert(&keys, value),
+        }
+    }
+
+    fn open(&mut self, keys: Vec<String>, array: bool) -> Result<(), String> {
+        let (last, parents) = keys.split_last().expect("keys are never empty");
+        let mut table = &mut self.root;
+        for key in parents {
+            let node = table
+                .entries
+                .entry(key.clone())
+                .or_insert_with(|| Node::Table(Table::default()));
+            table = match node {
+                Node::Table(t) => t,
+                Node::ArrayOfTables(tables) => {
+                    tables.last_mut().expect("arrays of tables are never empty")
+                }
+                Node::Value(_) => return Err(format!("`{}` is already defined as a value", key)),
+            };
+        }
+        let name = keys.join(".");
+        match (table.entries.entry(last.clone()), array) {
+            (Entry::Vacant(e), false) => {
+                e.insert(Node::Table(Table {
+                    explicit: true,
+                    ..Default::default()
+                }));
+            }
+            (Entry::Vacant(e), true) => {
+                e.insert(Node::ArrayOfTables(vec![Table::default()]));
+            }
+            (Entry::Occupied(mut e), false) => match e.get_mut() {
+                Node::Table(t) if !t.explicit && !t.dotted => t.explicit = true,
+                _ => return Err(format!("table `{}` is defined more than once", name)),
+            },
+            (Entry::Occupied(mut e), true) => match e.get_mut() {
+                Node::ArrayOfTables(tables) => tables.push(Table::default()),
+                _ => return Err(format!("`{}` is already defined as a non-array", name)),
+            },
+        }
+        self.current = keys;
+        Ok(())
+    }
+
+    fn current_table(&mut self) -> &mut Table {
+        let mut table = &mut self.root;
+        for key in &self.current {
+            table = match table.entries.get_mut(key) {
+                Some(Node::Table(t)) => t,
+                Some(Node::ArrayOfTables(tables)) => {
+                    tables.last_mut().expect("arrays of tables are never empty")
+                }
+                _ => unreachable!("headers are validated when opened"),
+            };
+        }
+        table
+    }
+}
+
+impl fmt::Display for TomlDatetime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TomlDatetime::Offset(dt) => {
+                write!(f, "{}", dt.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+            }
+            TomlDatetime::Local(dt) => write!(f, "{}", dt.format("%Y-%m-%dT%H:%M:%S%.f")),
+            TomlDatetime::LocalDate(d) => write!(f, "{}", d.format("%Y-%m-%d")),
+            TomlDatetime::LocalTime(t) => write!(f, "{}", t.format("%H:%M:%S%.f")),
+        }
+    }
+}
+
+/// Writes a value in its inline form: tables become `{ k = v }`.
+impl fmt::Display for TomlValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TomlValue::String(s) => write_string(f, s),
+            TomlValue::Integer(i) => write!(f, "{}", i),
+            TomlValue::Float(v) if v.is_nan() => write!(f, "nan"),
+            TomlValue::Float(v) if v.is_infinite() => {
+                write!(f, "{}", if *v > 0.0 { "inf" } else { "-inf" })
+            }
+            TomlValue::Float(v) if v.fract() == 0.0 => write!(f, "{:.1}", v),
+            TomlValue::Float(v) => write!(f, "{}", v),
+            TomlValue::Boolean(b) => write!(f, "{}", b),
+            TomlValue::Datetime(dt) => write!(f, "{}", dt),
+            TomlValue::Array(values) => {
+                write!(f, "[")?;
+                for (i, v) in values.iter().enumerate() {
+                    let sep = if i == 0 { "" } else { ", " };
+                    write!(f, "{}{}", sep, v)?;
+                }
+                write!(f, "]")
+            }
+            TomlValue::Table(table) if table.is_empty() => write!(f, "{{}}"),
+            TomlValue::Table(table) => {
+                write!(f, "{{ ")?;
+                for (i, (k, v)) in table.iter().enumerate() {
+                    let sep = if i == 0 { "" } else { ", " };
+                    write!(f, "{}", sep)?;
+                    write_key(f, k)?;
+                    write!(f, " = {}", v)?;
+                }
+                write!(f, " }}")
+            }
+        }
+    }
+}
+
+fn write_key(f: &mut impl fmt::Write, key: &str) -> fmt::Result {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare {
+        write!(f, "{}", key)
+    } else {
+        write_string(f, key)
+    }
+}
+
+fn write_string(f: &mut impl fmt::Write, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if is_forbidden(c) => write!(f, "\\u{:04X}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+/// Serializes `table` as a TOML document, using `[header]`s for sub-tables
+/// and `[[header]]`s for arrays that consist of tables only.
+pub fn to_string(table: &TomlTable) -> String {
+    let mut out = String::new();
+    write_table(&mut out, &mut Vec::new(), table);
+    out
+}
+
+fn is_array_of_tables(value: &TomlValue) -> bool {
+    matches!(value, TomlValue::Array(values)
+        if !values.is_empty() && values.iter().all(|v| matches!(v, TomlValue::Table(_))))
+}
+
+fn write_table(out: &mut String, path: &mut Vec<String>, table: &TomlTable) {
+    use fmt::Write;
+
+    for (k, v) in table {
+        if !matches!(v, TomlValue::Table(_)) && !is_array_of_tables(v) {
+            let _ = write_key(out, k);
+            let _ = writeln!(out, " = {}", v);
+        }
+    }
+    for (k, v) in table {
+        path.push(k.clone());
+        let mut header = String::new();
+        for (i, key) in path.iter().enumerate() {
+            if i > 0 {
+                header.push('.');
+            }
+            let _ = write_key(&mut header, key);
+        }
+        match v {
+            TomlValue::Table(sub) => {
+                if !out.is_empty() {
+                    out.push('\n');
+                }
+                let _ = writeln!(out, "[{}]", header);
+                write_table(out, path, sub);
+            }
+            TomlValue::Array(values) if is_array_of_tables(v) => {
+                for value in values {
+                    if let TomlValue::Table(sub) = value {
+                        if !out.is_empty() {
+                            out.push('\n');
+                        }
+                        let _ = writeln!(out, "[[{}]]", header);
+                        write_table(out, path, sub);
+                    }
+                }
+            }
+            _ => {}
+        }
+        path.pop();
+    }
+}
+
+/// Datetimes become RFC 3339 strings; every other value maps directly.
+impl From<TomlValue> for JsonValue {
+    fn from(value: TomlValue) -> Self {
+        match value {
+            TomlValue::String(s) => JsonValue::String(s),
+            TomlValue::Integer(i) => JsonValue::Number(i as f64),
+            TomlValue::Float(f) => JsonValue::Number(f),
+            TomlValue::Boolean(b) => JsonValue::Bool(b),
+            TomlValue::Datetime(dt) => JsonValue::String(dt.to_string()),
+            TomlValue::Array(values) => {
+                JsonValue::Array(values.into_iter().map(JsonValue::from).collect())
+            }
+            TomlValue::Table(table) => JsonValue::Object(
+                table
+                    .into_iter()
+                    .map(|(k, v)| (k, JsonValue::from(v)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl Serialize for TomlValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            TomlValue::String(s) => serializer.serialize_str(s),
+            TomlValue::Integer(i) => serializer.serialize_i64(*i),
+            TomlValue::Float(f) => serializer.serialize_f64(*f),
+            TomlValue::Boolean(b) => serializer.serialize_bool(*b),
+            TomlValue::Datetime(dt) => serializer.collect_str(dt),
+            TomlValue::Array(values) => values.serialize(serializer),
+            TomlValue::Table(table) => table.serialize(serializer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Timelike};
+
+    use super::*;
+
+    fn value(input: &str) -> TomlValue {
+        parse_value.parse(input).unwrap()
+    }
+
+    #[test]
+    fn parse_strings_should_work() {
+        assert_eq!(
+            value(r#""a\tb\u00E9\U0001F600""#),
+            TomlValue::String("a\tbé😀".into())
+        );
+        assert_eq!(value(r"'C:\path'"), TomlValue::String(r"C:\path".into()));
+        assert_eq!(
+            value("\"\"\"\nline one\nline \\\n    two\"\"\"\""),
+            TomlValue::String("line one\nline two\"".into())
+        );
+        assert_eq!(
+            value("'''\nraw \\n ''text'''"),
+            TomlValue::String("raw \\n ''text".into())
+        );
+        assert!(parse_value.parse(r#""bad \x""#).is_err());
+        assert!(parse_value.parse("\"open").is_err());
+    }
+
+    #[test]
+    fn parse_numbers_should_work() {
+        assert_eq!(value("+99"), TomlValue::Integer(99));
+        assert_eq!(value("-17"), TomlValue::Integer(-17));
+        assert_eq!(value("1_000_000"), TomlValue::Integer(1_000_000));
+        assert_eq!(value("0xdead_beef"), TomlValue::Integer(0xdead_beef));
+        assert_eq!(value("0o755"), TomlValue::Integer(0o755));
+        assert_eq!(value("0b1101"), TomlValue::Integer(0b1101));
+        assert_eq!(value("6.626e-34"), TomlValue::Float(6.626e-34));
+        assert_eq!(value("-0.01"), TomlValue::Float(-0.01));
+        assert_eq!(value("5e+22"), TomlValue::Float(5e22));
+        assert_eq!(value("-inf"), TomlValue::Float(f64::NEG_INFINITY));
+        assert!(matches!(value("nan"), TomlValue::Float(f) if f.is_nan()));
+        assert!(parse_value.parse("1__0").is_err());
+        assert!(parse_value.parse("01").is_err());
+        assert!(parse_value.parse("9223372036854775808").is_err());
+    }
+
+    #[test]
+    fn parse_datetimes_should_work() {
+        let offset = FixedOffset::west_opt(7 * 3600).unwrap();
+        assert_eq!(
+            value("1979-05-27T00:32:00.999999-07:00"),
+            TomlValue::Datetime(TomlDatetime::Offset(
+                offset
+                    .with_ymd_and_hms(1979, 5, 27, 0, 32, 0)
+                    .unwrap()
+                    .with_nanosecond(999_999_000)
+                    .unwrap()
+            ))
+        );
+        let TomlValue::Datetime(dt) = value("1979-05-27 07:32:00Z") else {
+            panic!("expected a datetime");
+        };
+        assert_eq!(dt.to_string(), "1979-05-27T07:32:00Z");
+        let TomlValue::Datetime(dt) = value("1979-05-27T07:32:00") else {
+            panic!("expected a datetime");
+        };
+        assert_eq!(dt.to_string(), "1979-05-27T07:32:00");
+        assert_eq!(
+            value("1979-05-27"),
+            TomlValue::Datetime(TomlDatetime::LocalDate(
+                NaiveDate::from_ymd_opt(1979, 5, 27).unwrap()
+            ))
+        );
+        assert_eq!(
+            value("07:32:00"),
+            TomlValue::Datetime(TomlDatetime::LocalTime(
+                NaiveTime::from_hms_opt(7, 32, 0).unwrap()
+            ))
+        );
+        assert!(parse_value.parse("1979-02-30").is_err());
+    }
+
+    #[test]
+    fn parse_toml_should_work() -> anyhow::Result<()> {
+        let input = r#"
+# This is a TOML document
+title = "TOML Example"
+
+[owner]
+name = "Tom Preston-Werner"
+dob = 1979-05-27T07:32:00-08:00
+
+[database]
+enabled = true
+ports = [ 8000, 8001, 8002 ]
+data = [ ["delta", "phi"], [3.14] ]
+temp_targets = { cpu = 79.5, case = 72.0 }
+
+[servers.alpha]
+ip = "10.0.0.1"
+role = "frontend"
+
+[[products]]
+name = "Hammer"
+sku = 738594937
+
+[[products]]  # empty table within the array
+
+[[products]]
+name = "Nail"
+color.name = "gray"
+"#;
+        let doc = parse_toml(input)?;
+        assert_eq!(doc["title"], TomlValue::String("TOML Example".into()));
+        let TomlValue::Table(database) = &doc["database"] else {
+            panic!("database should be a table");
+        };
+        assert_eq!(
+            database["ports"],
+            TomlValue::Array(vec![
+                TomlValue::Integer(8000),
+                TomlValue::Integer(8001),
+                TomlValue::Integer(8002)
+            ])
+        );
+        let TomlValue::Table(targets) = &database["temp_targets"] else {
+            panic!("temp_targets should be a table");
+        };
+        assert_eq!(targets["case"], TomlValue::Float(72.0));
+        let TomlValue::Array(products) = &doc["products"] else {
+            panic!("products should be an array");
+        };
+        assert_eq!(products.len(), 3);
+        assert_eq!(products[1], TomlValue::Table(TomlTable::new()));
+        let TomlValue::Table(servers) = &doc["servers"] else {
+            panic!("servers should be a table");
+        };
+        assert!(servers.contains_key("alpha"));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_toml_should_reject_redefinitions() {
+        let cases = [
+            ("a = 1\na = 2", "duplicate key `a`", 6),
+            ("[a]\n[a]", "table `a` is defined more than once", 4),
+            ("a = {}\n[a.b]", "`a` is already defined as a value", 7),
+            (
+                "[x]\ny.z = 1\n[x.y]",
+                "table `x.y` is defined more than once",
+                12,
+            ),
+            ("a = []\n[[a]]", "`a` is already defined as a non-array", 7),
+        ];
+        for (input, message, offset) in cases {
+            let err = parse_toml(input).unwrap_err();
+            assert_eq!(err.message(), message, "{}", input);
+            assert_eq!(err.offset(), offset, "{}", input);
+        }
+
+        let err = parse_toml("a = { b = 1, b = 2 }").unwrap_err();
+        assert_eq!(
+            err.message(),
+            "expected unique keys while parsing inline table"
+        );
+        let err = parse_toml("a = [1 2]").unwrap_err();
+        assert_eq!(err.line_col("a = [1 2]"), (1, 8));
+    }
+
+    #[test]
+    fn to_string_should_round_trip() -> anyhow::Result<()> {
+        let input = r#"
+name = "a \"quoted\" name"
+ratio = 1.0
+"dotted.key" = [1, 2]
+
+[nested.inner]
+when = 1979-05-27T07:32:00Z
+point = { x = 1, y = 2 }
+
+[[bin]]
+name = "one"
+
+[[bin]]
+name = "two"
+"#;
+        let doc = parse_toml(input)?;
+        let written = to_string(&doc);
+        assert_eq!(parse_toml(&written)?, doc);
+        assert!(written.contains("[[bin]]\nname = \"two\""));
+        Ok(())
+    }
+
+    #[test]
+    fn toml_to_json_should_work() -> anyhow::Result<()> {
+        let doc = parse_toml("a = 1\nb = 1979-05-27\n[c]\nd = [true]")?;
+        let JsonValue::Object(json) = JsonValue::from(TomlValue::Table(doc)) else {
+            panic!("expected an object");
+        };
+        assert_eq!(json["a"], JsonValue::Number(1.0));
+        assert_eq!(json["b"], JsonValue::String("1979-05-27".into()));
+        let JsonValue::Object(c) = &json["c"] else {
+            panic!("expected an object");
+        };
+        assert_eq!(c["d"], JsonValue::Array(vec![JsonValue::Bool(true)]));
+        Ok(())
+    }
+}
@@ -0,0 +1,365 @@
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::digit0;
+use winnow::combinator::{alt, cut_err, delimited, opt, preceded, repeat, terminated};
+use winnow::error::{ContextError, ErrMode, StrContext, StrContextValue};
+use winnow::token::{one_of, take_while};
+
+use crate::ParseError;
+use crate::urlencoded::{Form, parse_urlencoded};
+
+/// A URI reference split into its RFC 3986 components. Everything but the
+/// host is kept percent-encoded, exactly as written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Uri {
+    /// `None` for relative references.
+    pub scheme: Option<String>,
+    pub authority: Option<Authority>,
+    pub path: String,
+    pub query: Option<String>,
+    pub fragment: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Authority {
+    pub userinfo: Option<String>,
+    pub host: Host,
+    pub port: Option<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Host {
+    Ipv4(Ipv4Addr),
+    Ipv6(Ipv6Addr),
+    /// An `[vX.…]` literal for address formats not yet defined.
+    IpFuture(String),
+    Name(String),
+}
+
+impl Uri {
+    /// The `/`-separated path segments, still encoded. An absolute path
+    /// does not produce a leading empty segment.
+    pub fn path_segments(&self) -> impl Iterator<Item = &str> {
+        let path = self.path.strip_prefix('/').unwrap_or(&self.path);
+        path.split('/').filter(move |_| !path.is_empty())
+    }
+
+    pub fn decoded_path(&self) -> Result<String, ParseError> {
+        percent_decode(&self.path)
+    }
+
+    /// The query decoded as form pairs; an absent query gives an empty form.
+    pub fn query_pairs(&self) -> Result<Form, ParseError> {
+        parse_urlencoded(self.query.as_deref().unwrap_or(""))
+    }
+
+    pub fn host(&self) -> Option<&Host> {
+        self.authority.as_ref().map(|a| &a.host)
+    }
+}
+
+impl fmt::Display for Uri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(scheme) = &self.scheme {
+            write!(f, "{scheme}:")?;
+        }
+        if let Some(authority) = &self.authority {
+            write!(f, "//{authority}")?;
+        }
+        f.write_str(&self.path)?;
+        if let Some(query) = &self.query {
+            write!(f, "?{query}")?;
+        }
+        if let Some(fragment) = &self.fragment {
+            write!(f, "#{fragment}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Authority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(userinfo) = &self.userinfo {
+            write!(f, "{userinfo}@")?;
+        }
+        write!(f, "{}", self.host)?;
+        if let Some(port) = self.port {
+            write!(f, ":{port}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Host {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Host::Ipv4(addr) => write!(f, "{addr}"),
+            Host::Ipv6(addr) => write!(f, "[{addr}]"),
+            Host::IpFuture(literal) => write!(f, "[{literal}]"),
+            Host::Name(name) => f.write_str(name),
+        }
+    }
+}
+
+/// Parses a URI reference: either an absolute URI such as
+/// `https://example.com/a?b#c` or a relative one such as `../a?b`.
+pub fn parse_uri(input: &str) -> Result<Uri, ParseError> {
+    let (uri, path_offset) = uri_reference.parse(input).map_err(ParseError::from)?;
+    if uri.authority.is_some() && !uri.path.is_empty() && !uri.path.starts_with('/') {
+        return Err(ParseError::new(
+            path_offset,
+            "a path after an authority must start with `/`",
+        ));
+    }
+    // Otherwise `a:b` would be ambiguous with a scheme.
+    if uri.scheme.is_none()
+        && uri.authority.is_none()
+        && uri.path.split('/').next().is_some_and(|s| s.contains(':'))
+    {
+        return Err(ParseError::new(
+            path_offset,
+            "the first segment of a relative path cannot contain `:`",
+        ));
+    }
+    Ok(uri)
+}
+
+/// Also returns where the path starts, for the checks in [`parse_uri`].
+fn uri_reference(input: &mut &str) -> ModalResult<(Uri, usize)> {
+    let len = input.len();
+    let scheme = opt(terminated(scheme, ':')).parse_next(input)?;
+    let authority = opt(preceded("//", cut_err(authority))).parse_next(input)?;
+    let path_offset = len - input.len();
+    let path = chars(":@/").parse_next(input)?;
+    let query = opt(preceded('?', chars(":@/?"))).parse_next(input)?;
+    let fragment = opt(preceded('#', chars(":@/?"))).parse_next(input)?;
+    let uri = Uri {
+        scheme: scheme.map(str::to_ascii_lowercase),
+        authority,
+        path: path.to_string(),
+        query: query.map(str::to_string),
+        fragment: fragment.map(str::to_string),
+    };
+    Ok((uri, path_offset))
+}
+
+fn scheme<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    (
+        one_of(|c: char| c.is_ascii_alphabetic()),
+        take_while(0.., |c: char| {
+            c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')
+        }),
+    )
+        .take()
+        .parse_next(input)
+}
+
+fn authority(input: &mut &str) -> ModalResult<Authority> {
+    let userinfo = opt(terminated(chars(":"), '@')).parse_next(input)?;
+    let host = host.parse_next(input)?;
+    let port = opt(preceded(
+        ':',
+        digit0.try_map(|digits: &str| match digits {
+            "" => Ok(None),
+            digits => digits.parse::<u16>().map(Some),
+        }),
+    ))
+    .context(StrContext::Label("port"))
+    .parse_next(input)?;
+    Ok(Authority {
+        userinfo: userinfo.map(str::to_string),
+        host,
+        port: port.flatten(),
+    })
+}
+
+fn host(input: &mut &str) -> ModalResult<Host> {
+    let literal = delimited(
+        '[',
+        cut_err(alt((
+            preceded(
+                one_of(['v', 'V']),
+                (
+                    take_while(1.., |c: char| c.is_ascii_hexdigit()),
+                    '.',
+                    take_while(1.., |c: char| {
+                        is_unreserved(c) || is_sub_delim(c) || c == ':'
+                    }),
+                ),
+            )
+            .take()
+            .map(|_: &str| None),
+            take_while(2.., |c: char| c.is_ascii_hexdigit() || c == ':' || c == '.')
+                .parse_to::<Ipv6Addr>()
+                .map(Some),
+        )))
+        .context(StrContext::Expected(StrContextValue::Description(
+            "IPv6 address",
+        ))),
+        cut_err(']'),
+    )
+    .with_taken()
+    .map(|(addr, taken): (Option<Ipv6Addr>, &str)| match addr {
+        Some(addr) => Host::Ipv6(addr),
+        None => Host::IpFuture(taken[1..taken.len() - 1].to_string()),
+    });
+    let name = chars("").map(|name: &str| match name.parse::<Ipv4Addr>() {
+        Ok(addr) => Host::Ipv4(addr),
+        Err(_) => Host::Name(name.to_ascii_lowercase()),
+    });
+    alt((literal, name))
+        .context(StrContext::Label("host"))
+        .parse_next(input)
+}
+
+/// Unreserved characters, sub-delimiters, percent-encoded octets and the
+/// `extra` characters allowed by the component at hand.
+fn chars<'i>(extra: &'static str) -> impl Parser<&'i str, &'i str, ErrMode<ContextError>> {
+    repeat::<_, _, (), _, _>(
+        0..,
+        alt((
+            take_while(1.., move |c: char| {
+                is_unreserved(c) || is_sub_delim(c) || extra.contains(c)
+            })
+            .void(),
+            ('%', cut_err(hex_digit), cut_err(hex_digit))
+                .void()
+                .context(StrContext::Label("percent-encoding")),
+        )),
+    )
+    .take()
+}
+
+fn hex_digit(input: &mut &str) -> ModalResult<char> {
+    one_of(|c: char| c.is_ascii_hexdigit()).parse_next(input)
+}
+
+fn is_unreserved(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~')
+}
+
+fn is_sub_delim(c: char) -> bool {
+    matches!(
+        c,
+        '!' | '$' | '&' | '\'' | '(' | ')' | '*' | '+' | ',' | ';' | '='
+    )
+}
+
+/// Decodes `%XX` escapes; the result must be valid UTF-8.
+pub fn percent_decode(input: &str) -> Result<String, ParseError> {
+    decode(input, false)
+}
+
+/// Shared with the form decoder, which additionally reads `+` as a space.
+pub(crate) fn decode(input: &str, plus_as_space: bool) -> Result<String, ParseError> {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    // Where in `input` each byte of `out` came from, to report errors there.
+    let mut starts = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        starts.push(i);
+        match bytes[i] {
+            b'%' => {
+                let byte = input
+                    .get(i + 1..i + 3)
+                    .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| ParseError::new(i, "invalid percent-encoding"))?;
+                out.push(byte);
+                i += 3;
+            }
+            b'+' if plus_as_space => {
+                out.push(b' ');
+                i += 1;
+            }
+            b => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(out).map_err(|e| {
+        ParseError::new(
+            starts[e.utf8_error().valid_up_to()],
+            "percent-decoded bytes are not valid UTF-8",
+        )
+    })
+}
+
+/// Percent-encodes everything except unreserved characters.
+pub fn percent_encode(input: &str) -> String {
+    encode(input, false)
+}
+
+pub(crate) fn encode(input: &str, space_as_plus: bool) -> String {
+    let mut out = String::with_capacity(input.len());
+    for b in input.bytes() {
+        match b {
+            b' ' if space_as_plus => out.push('+'),
+            b if is_unreserved(b as char) => out.push(b as char),
+            b => out.push_str(&format!("%{b:02X}")),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_uri_should_work() -> Result<(), ParseError> {
+        let uri = parse_uri("HTTPS://user:pw@Example.COM:8443/a/b%20c/?q=1&r#frag")?;
+        assert_eq!(uri.scheme.as_deref(), Some("https"));
+        let authority = uri.authority.as_ref().unwrap();
+        assert_eq!(authority.userinfo.as_deref(), Some("user:pw"));
+        assert_eq!(authority.host, Host::Name("example.com".into()));
+        assert_eq!(authority.port, Some(8443));
+        assert_eq!(uri.path_segments().collect::<Vec<_>>(), ["a", "b%20c", ""]);
+        assert_eq!(uri.decoded_path()?, "/a/b c/");
+        assert_eq!(uri.query.as_deref(), Some("q=1&r"));
+        assert_eq!(uri.fragment.as_deref(), Some("frag"));
+        assert_eq!(
+            uri.to_string(),
+            "https://user:pw@example.com:8443/a/b%20c/?q=1&r#frag"
+        );
+
+        let uri = parse_uri("ldap://[2001:db8::7]/c=GB?objectClass?one")?;
+        assert_eq!(
+            uri.host(),
+            Some(&Host::Ipv6("2001:db8::7".parse().unwrap()))
+        );
+        let uri = parse_uri("//10.0.0.1:/x")?;
+        assert_eq!(uri.host(), Some(&Host::Ipv4(Ipv4Addr::new(10, 0, 0, 1))));
+        assert_eq!(uri.authority.unwrap().port, None);
+        let uri = parse_uri("urn:isbn:0451450523")?;
+        assert_eq!(uri.path, "isbn:0451450523");
+        let uri = parse_uri("../up?x")?;
+        assert_eq!((uri.scheme, uri.path.as_str()), (None, "../up"));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_uri_should_reject_invalid_input() {
+        assert_eq!(parse_uri("http://a/b c").unwrap_err().offset(), 10);
+        assert_eq!(parse_uri("/a%zz").unwrap_err().offset(), 3);
+        assert!(parse_uri("http://[::g]/").is_err());
+        assert!(parse_uri("http://a:99999/").is_err());
+        assert!(parse_uri("1a:b").is_err());
+    }
+
+    #[test]
+    fn percent_coding_should_work() -> Result<(), ParseError> {
+        assert_eq!(percent_decode("caf%C3%A9+x")?, "café+x");
+        assert_eq!(percent_encode("café x/y"), "caf%C3%A9%20x%2Fy");
+        assert_eq!(percent_decode("%4").unwrap_err().offset(), 0);
+        assert!(percent_decode("%ff").is_err());
+        assert_eq!(percent_decode("%41%41%ff").unwrap_err().offset(), 6);
+        assert_eq!(percent_decode("é%C3").unwrap_err().offset(), 2);
+        Ok(())
+    }
+}
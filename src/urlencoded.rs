@@ -0,0 +1,146 @@
+use std::fmt;
+
+use crate::ParseError;
+use crate::uri;
+
+/// Decoded `application/x-www-form-urlencoded` pairs. Keys may repeat and
+/// insertion order is preserved.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Form {
+    pairs: Vec<(String, String)>,
+}
+
+impl Form {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The first value for `key`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> {
+        self.pairs
+            .iter()
+            .filter(move |(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.pairs.iter().any(|(k, _)| k == key)
+    }
+
+    pub fn append(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.pairs.push((key.into(), value.into()));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pairs.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Form {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        Self {
+            pairs: iter
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        }
+    }
+}
+
+impl IntoIterator for Form {
+    type Item = (String, String);
+    type IntoIter = std::vec::IntoIter<(String, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.pairs.into_iter()
+    }
+}
+
+/// Serializes back to `a=1&b=two+words`.
+impl fmt::Display for Form {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (k, v)) in self.pairs.iter().enumerate() {
+            if i > 0 {
+                f.write_str("&")?;
+            }
+            write!(f, "{}={}", uri::encode(k, true), uri::encode(v, true))?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a form body or query string. Empty pieces (`a=1&&b=2`) are
+/// skipped and a key without `=` gets an empty value.
+pub fn parse_urlencoded(input: &str) -> Result<Form, ParseError> {
+    let mut form = Form::new();
+    let mut offset = 0;
+    for piece in input.split('&') {
+        if !piece.is_empty() {
+            let (key, value) = piece.split_once('=').unwrap_or((piece, ""));
+            let value_offset = offset + key.len() + 1;
+            let key = uri::decode(key, true).map_err(|e| shift(e, offset))?;
+            let value = uri::decode(value, true).map_err(|e| shift(e, value_offset))?;
+            form.append(key, value);
+        }
+        offset += piece.len() + 1;
+    }
+    Ok(form)
+}
+
+fn shift(e: ParseError, by: usize) -> ParseError {
+    ParseError::new(e.offset() + by, e.message())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_urlencoded_should_work() -> Result<(), ParseError> {
+        let form = parse_urlencoded("a=1&b=two+words&&b=3&flag&c=%26%3D")?;
+        assert_eq!(form.len(), 5);
+        assert_eq!(form.get("a"), Some("1"));
+        assert_eq!(form.get_all("b").collect::<Vec<_>>(), ["two words", "3"]);
+        assert_eq!(form.get("flag"), Some(""));
+        assert_eq!(form.get("c"), Some("&="));
+        assert!(!form.contains_key("d"));
+        Ok(())
+    }
+
+    #[test]
+    fn form_should_round_trip() -> Result<(), ParseError> {
+        let form: Form = [("q", "rust & winnow"), ("page", "2")]
+            .into_iter()
+            .collect();
+        let encoded = form.to_string();
+        assert_eq!(encoded, "q=rust+%26+winnow&page=2");
+        assert_eq!(parse_urlencoded(&encoded)?, form);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_urlencoded_should_report_offsets() -> Result<(), ParseError> {
+        assert_eq!(parse_urlencoded("a=1&b=%g1").unwrap_err().offset(), 6);
+        let uri = uri::parse_uri("/search?q=a+b&q=c")?;
+        assert_eq!(
+            uri.query_pairs()?.get_all("q").collect::<Vec<_>>(),
+            ["a b", "c"]
+        );
+        Ok(())
+    }
+}
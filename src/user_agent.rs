@@ -0,0 +1,225 @@
+use std::fmt;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{space0, space1};
+use winnow::combinator::{alt, cut_err, opt, preceded, repeat};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::{any, take_while};
+
+use crate::ParseError;
+
+/// A `User-Agent` value as RFC 9110 defines it: product tokens and
+/// parenthesized comments, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserAgent {
+    pub items: Vec<Item>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    Product(Product),
+    /// The `;`-separated parts of a comment, trimmed. Nested comments stay
+    /// inside their part with their parentheses.
+    Comment(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Product {
+    pub name: String,
+    pub version: Option<String>,
+}
+
+impl UserAgent {
+    pub fn products(&self) -> impl Iterator<Item = &Product> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Product(product) => Some(product),
+            Item::Comment(_) => None,
+        })
+    }
+
+    /// The first product with this name, ignoring ASCII case.
+    pub fn product(&self, name: &str) -> Option<&Product> {
+        self.products()
+            .find(|product| product.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Every comment part, flattened; handy for spotting platform tokens
+    /// like `Windows NT 10.0`.
+    pub fn comment_parts(&self) -> impl Iterator<Item = &str> {
+        self.items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Comment(parts) => Some(parts),
+                Item::Product(_) => None,
+            })
+            .flatten()
+            .map(String::as_str)
+    }
+}
+
+impl fmt::Display for UserAgent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, item) in self.items.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            match item {
+                Item::Product(product) => write!(f, "{product}")?,
+                Item::Comment(parts) => write!(f, "({})", parts.join("; "))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Product {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "{}/{version}", self.name),
+            None => f.write_str(&self.name),
+        }
+    }
+}
+
+/// Parses a `User-Agent` header value. Anything outside the grammar, such
+/// as a stray `[` or an unbalanced parenthesis, is an error.
+pub fn parse_user_agent(input: &str) -> Result<UserAgent, ParseError> {
+    (
+        preceded(space0, product),
+        repeat(0.., preceded(space1, alt((product, comment)))),
+        space0,
+    )
+        .map(|(first, mut rest, _): (Item, Vec<Item>, _)| {
+            rest.insert(0, first);
+            UserAgent { items: rest }
+        })
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+fn product(input: &mut &str) -> ModalResult<Item> {
+    let name = token
+        .context(StrContext::Label("product"))
+        .parse_next(input)?;
+    let version = opt(preceded(
+        '/',
+        cut_err(token).context(StrContext::Label("product version")),
+    ))
+    .parse_next(input)?;
+    Ok(Item::Product(Product {
+        name: name.to_string(),
+        version: version.map(str::to_string),
+    }))
+}
+
+fn comment(input: &mut &str) -> ModalResult<Item> {
+    let text = comment_text.parse_next(input)?;
+    // Only top-level semicolons separate parts.
+    let mut parts = vec![String::new()];
+    let mut depth = 0;
+    let mut escaped = false;
+    for c in text[1..text.len() - 1].chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ';' if depth == 0 => {
+                parts.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        parts.last_mut().unwrap().push(c);
+    }
+    Ok(Item::Comment(
+        parts
+            .into_iter()
+            .map(|part| part.trim().to_string())
+            .filter(|part| !part.is_empty())
+            .collect(),
+    ))
+}
+
+/// A balanced comment including its parentheses. Quoted pairs are kept as
+/// written.
+fn comment_text<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    (
+        '(',
+        cut_err((
+            repeat::<_, _, (), _, _>(
+                0..,
+                alt((
+                    take_while(1.., |c: char| !matches!(c, '(' | ')' | '\\')).void(),
+                    ('\\', any).void(),
+                    comment_text.void(),
+                )),
+            ),
+            ')',
+        ))
+        .context(StrContext::Expected(StrContextValue::CharLiteral(')'))),
+    )
+        .take()
+        .parse_next(input)
+}
+
+fn token<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    take_while(1.., |c: char| {
+        c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
+    })
+    .parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHROME: &str = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+
+    #[test]
+    fn parse_user_agent_should_work() -> Result<(), ParseError> {
+        let ua = parse_user_agent(CHROME)?;
+        let products: Vec<_> = ua.products().map(ToString::to_string).collect();
+        assert_eq!(
+            products,
+            [
+                "Mozilla/5.0",
+                "AppleWebKit/537.36",
+                "Chrome/120.0.0.0",
+                "Safari/537.36"
+            ]
+        );
+        assert_eq!(
+            ua.items[1],
+            Item::Comment(vec!["X11".to_string(), "Linux x86_64".to_string()])
+        );
+        assert_eq!(
+            ua.product("chrome").and_then(|p| p.version.as_deref()),
+            Some("120.0.0.0")
+        );
+        assert_eq!(ua.to_string(), CHROME);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_user_agent_should_handle_nested_comments() -> Result<(), ParseError> {
+        let ua = parse_user_agent("curl (a; (b; c) \\) d) Debian APT-HTTP/1.3")?;
+        assert_eq!(
+            ua.comment_parts().collect::<Vec<_>>(),
+            ["a", "(b; c) \\) d"]
+        );
+        assert_eq!(ua.product("curl").unwrap().version, None);
+        assert_eq!(ua.products().count(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_user_agent_should_report_errors() {
+        assert_eq!(parse_user_agent("Foo/ bar").unwrap_err().offset(), 4);
+        assert!(parse_user_agent("Foo (unclosed").is_err());
+        assert!(parse_user_agent("(comment first)").is_err());
+        assert!(parse_user_agent("App [FBAN/FBIOS]").is_err());
+        assert!(parse_user_agent("").is_err());
+    }
+}
@@ -0,0 +1,400 @@
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::combinator::{alt, cut_err, delimited, opt, preceded, repeat, separated};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::{rest, take_till, take_while};
+
+use crate::ParseError;
+
+/// One `BEGIN:VCARD` ... `END:VCARD` block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VCard {
+    /// Everything between BEGIN and END, in order.
+    pub properties: Vec<Property>,
+}
+
+/// A content line: `[group.]NAME[;param...]:value`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Property {
+    pub group: Option<String>,
+    /// Uppercased, since names are case-insensitive.
+    pub name: String,
+    pub params: Vec<Param>,
+    /// The value as written, escapes included; see [`Property::text`].
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    /// Uppercased. A vCard 2.1 bare parameter such as `;HOME` becomes `TYPE`.
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+/// A `TEL` or `EMAIL`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Contact {
+    pub value: String,
+    /// Lowercased `TYPE`s, e.g. `work` or `cell`.
+    pub types: Vec<String>,
+    /// `PREF`, 1 being the most preferred.
+    pub pref: Option<u8>,
+}
+
+/// An `ADR`, split into its seven components.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Address {
+    pub po_box: String,
+    pub extended: String,
+    pub street: String,
+    pub locality: String,
+    pub region: String,
+    pub postal_code: String,
+    pub country: String,
+    pub types: Vec<String>,
+}
+
+impl VCard {
+    pub fn get(&self, name: &str) -> Option<&Property> {
+        self.properties
+            .iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Property> {
+        self.properties
+            .iter()
+            .filter(move |p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn version(&self) -> Option<&str> {
+        self.get("VERSION").map(|p| p.value.as_str())
+    }
+
+    /// `FN`, unescaped.
+    pub fn formatted_name(&self) -> Option<String> {
+        self.get("FN").map(Property::text)
+    }
+
+    pub fn telephones(&self) -> Vec<Contact> {
+        self.contacts("TEL")
+    }
+
+    pub fn emails(&self) -> Vec<Contact> {
+        self.contacts("EMAIL")
+    }
+
+    pub fn addresses(&self) -> Vec<Address> {
+        self.all("ADR")
+            .map(|p| {
+                let mut parts = p.components().into_iter();
+                let mut next = || parts.next().unwrap_or_default();
+                Address {
+                    po_box: next(),
+                    extended: next(),
+                    street: next(),
+                    locality: next(),
+                    region: next(),
+                    postal_code: next(),
+                    country: next(),
+                    types: p.types(),
+                }
+            })
+            .collect()
+    }
+
+    fn contacts(&self, name: &str) -> Vec<Contact> {
+        self.all(name)
+            .map(|p| {
+                let types = p.types();
+                // vCard 3 spells `PREF=1` as `TYPE=pref`.
+                let pref = p
+                    .param("PREF")
+                    .and_then(|pref| pref.values.first()?.parse().ok())
+                    .or_else(|| types.iter().any(|t| t == "pref").then_some(1));
+                Contact {
+                    value: p.text(),
+                    types: types.into_iter().filter(|t| t != "pref").collect(),
+                    pref,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Property {
+    pub fn param(&self, name: &str) -> Option<&Param> {
+        self.params
+            .iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Every `TYPE` value, lowercased, including comma-separated ones.
+    pub fn types(&self) -> Vec<String> {
+        self.params
+            .iter()
+            .filter(|p| p.name == "TYPE")
+            .flat_map(|p| &p.values)
+            .flat_map(|v| v.split(','))
+            .map(str::to_ascii_lowercase)
+            .collect()
+    }
+
+    /// The value with `\n`, `\,`, `\;` and `\\` unescaped.
+    pub fn text(&self) -> String {
+        unescape(&self.value)
+    }
+
+    /// The value split at unescaped `;`, each part unescaped, as `N` and
+    /// `ADR` are structured.
+    pub fn components(&self) -> Vec<String> {
+        let mut parts = Vec::new();
+        let mut start = 0;
+        let mut escaped = false;
+        for (i, c) in self.value.char_indices() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                ';' => {
+                    parts.push(unescape(&self.value[start..i]));
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        parts.push(unescape(&self.value[start..]));
+        parts
+    }
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(c) => out.push(c),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Parses every vCard in `input`. Folded lines are joined first; errors
+/// point into `input`.
+pub fn parse_vcards(input: &str) -> Result<Vec<VCard>, ParseError> {
+    let mut cards = Vec::new();
+    let mut open: Option<(usize, Vec<Property>)> = None;
+    for (start, property) in content_lines(input)? {
+        let is = |name: &str, value: &str| {
+            property.name == name && property.value.eq_ignore_ascii_case(value)
+        };
+        if is("BEGIN", "VCARD") {
+            if open.is_some() {
+                return Err(ParseError::new(start, "nested BEGIN:VCARD"));
+            }
+            open = Some((start, Vec::new()));
+        } else if is("END", "VCARD") {
+            let (_, properties) = open
+                .take()
+                .ok_or_else(|| ParseError::new(start, "END:VCARD without BEGIN"))?;
+            cards.push(VCard { properties });
+        } else if let Some((_, properties)) = open.as_mut() {
+            properties.push(property);
+        } else {
+            return Err(ParseError::new(start, "expected BEGIN:VCARD"));
+        }
+    }
+    match open {
+        Some((start, _)) => Err(ParseError::new(start, "missing END:VCARD")),
+        None => Ok(cards),
+    }
+}
+
+/// Unfolds `input` and parses each logical line, paired with its offset.
+/// iCalendar shares the syntax.
+pub(crate) fn content_lines(input: &str) -> Result<Vec<(usize, Property)>, ParseError> {
+    let mut lines = Vec::new();
+    for (start, line, pieces) in unfold(input) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let property = content_line.parse(line.as_str()).map_err(|e| {
+            let e = ParseError::from(e);
+            // Map the offset in the unfolded line back onto `input`.
+            let (from, raw) = pieces
+                .iter()
+                .rev()
+                .find(|(from, _)| *from <= e.offset())
+                .copied()
+                .unwrap_or((0, start));
+            ParseError::new(raw + e.offset() - from, e.message())
+        })?;
+        lines.push((start, property));
+    }
+    Ok(lines)
+}
+
+/// A logical line: its offset, its text and, for each physical line in it,
+/// where that line's text begins in the unfolded and the raw input.
+type Unfolded = (usize, String, Vec<(usize, usize)>);
+
+fn unfold(input: &str) -> Vec<Unfolded> {
+    let mut lines: Vec<Unfolded> = Vec::new();
+    let mut offset = 0;
+    for raw in input.split_inclusive('\n') {
+        let start = offset;
+        offset += raw.len();
+        let text = raw.trim_end_matches(['\n', '\r']);
+        match (text.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some((_, line, pieces))) => {
+                pieces.push((line.len(), start + 1));
+                line.push_str(rest);
+            }
+            _ => lines.push((start, text.to_string(), vec![(0, start)])),
+        }
+    }
+    lines
+}
+
+fn content_line(input: &mut &str) -> ModalResult<Property> {
+    let first = name
+        .context(StrContext::Label("property name"))
+        .parse_next(input)?;
+    let (group, name) = match opt(preceded('.', cut_err(name))).parse_next(input)? {
+        Some(name) => (Some(first), name),
+        None => (None, first),
+    };
+    let params = repeat(0.., preceded(';', cut_err(param))).parse_next(input)?;
+    cut_err(':')
+        .context(StrContext::Expected(StrContextValue::CharLiteral(':')))
+        .parse_next(input)?;
+    Ok(Property {
+        group,
+        name: name.to_ascii_uppercase(),
+        params,
+        value: rest.parse_next(input)?.to_string(),
+    })
+}
+
+fn name(input: &mut &str) -> ModalResult<String> {
+    take_while(1.., ('A'..='Z', 'a'..='z', '0'..='9', '-'))
+        .map(String::from)
+        .parse_next(input)
+}
+
+fn param(input: &mut &str) -> ModalResult<Param> {
+    let name = name
+        .context(StrContext::Label("parameter name"))
+        .parse_next(input)?;
+    let values: Option<Vec<String>> = opt(preceded(
+        '=',
+        separated(
+            1..,
+            alt((
+                delimited('"', take_till(0.., '"'), cut_err('"')),
+                take_till(0.., [';', ':', ',', '"']),
+            ))
+            .map(String::from),
+            ',',
+        ),
+    ))
+    .parse_next(input)?;
+    Ok(match values {
+        Some(values) => Param {
+            name: name.to_ascii_uppercase(),
+            values,
+        },
+        None => Param {
+            name: "TYPE".to_string(),
+            values: vec![name],
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_vcards_should_work() -> Result<(), ParseError> {
+        let cards = parse_vcards(
+            "BEGIN:VCARD\r\n\
+             VERSION:4.0\r\n\
+             FN:Jane Q. Public\\, Esq.\r\n\
+             TEL;TYPE=\"work,voice\";PREF=1:tel:+1-555-555-0100\r\n\
+             tel;type=cell:+1-555-555-0199\r\n\
+             item1.EMAIL;TYPE=INTERNET:jane@exam\r\n\
+             \x20ple.com\r\n\
+             item1.X-ABLabel:Work\r\n\
+             NOTE:line one\\nline two\r\n\
+             END:VCARD\r\n\
+             BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Second\r\nEND:VCARD\r\n",
+        )?;
+        assert_eq!(cards.len(), 2);
+        let jane = &cards[0];
+        assert_eq!(jane.version(), Some("4.0"));
+        assert_eq!(
+            jane.formatted_name().as_deref(),
+            Some("Jane Q. Public, Esq.")
+        );
+        let phones = jane.telephones();
+        assert_eq!(phones[0].value, "tel:+1-555-555-0100");
+        assert_eq!(phones[0].types, ["work", "voice"]);
+        assert_eq!(phones[0].pref, Some(1));
+        assert_eq!(phones[1].types, ["cell"]);
+        let emails = jane.emails();
+        assert_eq!(emails[0].value, "jane@example.com");
+        let email = jane.get("email").unwrap();
+        assert_eq!(email.group.as_deref(), Some("item1"));
+        assert_eq!(jane.get("NOTE").unwrap().text(), "line one\nline two");
+        assert_eq!(cards[1].formatted_name().as_deref(), Some("Second"));
+        Ok(())
+    }
+
+    #[test]
+    fn addresses_and_vcard_21_params_should_parse() -> Result<(), ParseError> {
+        let cards = parse_vcards(
+            "BEGIN:VCARD\nVERSION:2.1\n\
+             N:Public;John;Quinlan;Mr.;Esq.\n\
+             TEL;HOME;VOICE;PREF:555-0100\n\
+             ADR;TYPE=home:;Suite 5;123 Main St\\; Rear;Any Town;CA;91921;USA\n\
+             END:VCARD\n",
+        )?;
+        let card = &cards[0];
+        assert_eq!(
+            card.get("N").unwrap().components(),
+            ["Public", "John", "Quinlan", "Mr.", "Esq."]
+        );
+        let phone = &card.telephones()[0];
+        assert_eq!(phone.types, ["home", "voice"]);
+        assert_eq!(phone.pref, Some(1));
+        let address = &card.addresses()[0];
+        assert_eq!(address.po_box, "");
+        assert_eq!(address.extended, "Suite 5");
+        assert_eq!(address.street, "123 Main St; Rear");
+        assert_eq!(address.locality, "Any Town");
+        assert_eq!(address.postal_code, "91921");
+        assert_eq!(address.country, "USA");
+        assert_eq!(address.types, ["home"]);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_vcards_should_report_errors() {
+        let err = parse_vcards("BEGIN:VCARD\nFN;TYPE=\"x:Jane\n").unwrap_err();
+        assert_eq!(err.offset(), 27);
+        let err = parse_vcards("BEGIN:VCARD\nFN:a\nX-Y\n").unwrap_err();
+        assert_eq!(err.offset(), 20);
+        let err = parse_vcards("BEGIN:VCARD\nFN:x\nEMAIL\n bad\nEND:VCARD\n").unwrap_err();
+        assert_eq!(err.offset(), 27);
+        assert!(parse_vcards("FN:x\n").is_err());
+        assert!(parse_vcards("BEGIN:VCARD\nFN:x\n").is_err());
+        assert!(parse_vcards("BEGIN:VCARD\nBEGIN:VCARD\n").is_err());
+        assert!(parse_vcards("END:VCARD\n").is_err());
+    }
+}
@@ -0,0 +1,441 @@
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::combinator::{alt, cut_err, delimited, repeat, separated};
+use winnow::error::{StrContext, StrContextValue};
+use winnow::token::{any, none_of, take_till, take_while};
+
+use crate::ParseError;
+
+/// A VCF file: the header and its variant records.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vcf {
+    pub header: Header,
+    pub records: Vec<Record>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Header {
+    /// From `##fileformat=`, as in `VCFv4.3`.
+    pub file_format: String,
+    /// The other `##` lines, in order.
+    pub meta: Vec<Meta>,
+    /// Sample names from the `#CHROM` line.
+    pub samples: Vec<String>,
+}
+
+/// A `##key=value` line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Meta {
+    pub key: String,
+    pub value: MetaValue,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetaValue {
+    Text(String),
+    /// `<ID=DP,Number=1,...>`, with quotes removed from values.
+    Structured(Vec<(String, String)>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub chrom: String,
+    /// 1-based.
+    pub pos: u64,
+    pub ids: Vec<String>,
+    pub reference: String,
+    pub alternates: Vec<String>,
+    pub quality: Option<f64>,
+    /// `PASS`, failed filter names, or empty when not filtered (`.`).
+    pub filters: Vec<String>,
+    /// Typed by the header's `##INFO` lines; undeclared keys are strings.
+    pub info: Vec<(String, Value)>,
+    /// The FORMAT keys, shared by every sample column.
+    pub format: Vec<String>,
+    /// One value per FORMAT key for each sample, typed by `##FORMAT`.
+    pub samples: Vec<Vec<Value>>,
+}
+
+/// A typed INFO or FORMAT value. Missing elements (`.`) are `None`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Flag,
+    Integer(Vec<Option<i64>>),
+    Float(Vec<Option<f64>>),
+    Character(Vec<Option<char>>),
+    String(Vec<Option<String>>),
+}
+
+/// A `GT` value such as `0|1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Genotype {
+    /// Allele indices, 0 being the reference; `None` for `.`.
+    pub alleles: Vec<Option<usize>>,
+    pub phased: bool,
+}
+
+impl MetaValue {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        match self {
+            MetaValue::Structured(fields) => fields
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str()),
+            MetaValue::Text(_) => None,
+        }
+    }
+}
+
+impl Header {
+    /// The first `##key=` line.
+    pub fn get(&self, key: &str) -> Option<&MetaValue> {
+        self.meta.iter().find(|m| m.key == key).map(|m| &m.value)
+    }
+
+    /// The `##INFO` declaration with `ID=id`.
+    pub fn info(&self, id: &str) -> Option<&MetaValue> {
+        self.declaration("INFO", id)
+    }
+
+    pub fn format(&self, id: &str) -> Option<&MetaValue> {
+        self.declaration("FORMAT", id)
+    }
+
+    fn declaration(&self, key: &str, id: &str) -> Option<&MetaValue> {
+        self.meta
+            .iter()
+            .filter(|m| m.key == key)
+            .map(|m| &m.value)
+            .find(|v| v.get("ID") == Some(id))
+    }
+}
+
+impl Record {
+    pub fn info(&self, key: &str) -> Option<&Value> {
+        self.info.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// The `key` value of the sample at `index`.
+    pub fn sample(&self, index: usize, key: &str) -> Option<&Value> {
+        let column = self.format.iter().position(|k| k == key)?;
+        self.samples.get(index)?.get(column)
+    }
+
+    pub fn genotype(&self, index: usize) -> Option<Genotype> {
+        let Value::String(values) = self.sample(index, "GT")? else {
+            return None;
+        };
+        let gt = values.first()?.as_deref()?;
+        Some(Genotype {
+            alleles: gt.split(['/', '|']).map(|a| a.parse().ok()).collect(),
+            phased: gt.contains('|'),
+        })
+    }
+
+    pub fn is_pass(&self) -> bool {
+        self.filters == ["PASS"]
+    }
+}
+
+/// Parses a VCF file. Header lines must come first, ending with the
+/// `#CHROM` column line.
+pub fn parse_vcf(input: &str) -> Result<Vcf, ParseError> {
+    let mut header = Header::default();
+    let mut columns_seen = false;
+    let mut records = Vec::new();
+    let mut offset = 0;
+    for raw in input.split_inclusive('\n') {
+        let start = offset;
+        offset += raw.len();
+        let line = raw.trim_end_matches(['\n', '\r']);
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(meta) = line.strip_prefix("##") {
+            if columns_seen {
+                return Err(ParseError::new(start, "header line after #CHROM"));
+            }
+            let meta = meta_line
+                .parse(meta)
+                .map_err(|e| shift(start + 2, ParseError::from(e)))?;
+            if meta.key == "fileformat"
+                && let MetaValue::Text(version) = &meta.value
+            {
+                header.file_format = version.clone();
+            } else {
+                header.meta.push(meta);
+            }
+        } else if let Some(columns) = line.strip_prefix('#') {
+            let names: Vec<&str> = columns.split('\t').collect();
+            let fixed = ["CHROM", "POS", "ID", "REF", "ALT", "QUAL", "FILTER", "INFO"];
+            if names.len() < fixed.len() || names[..fixed.len()] != fixed {
+                return Err(ParseError::new(start, "expected the #CHROM column line"));
+            }
+            if names.len() > fixed.len() {
+                if names[fixed.len()] != "FORMAT" {
+                    return Err(ParseError::new(start, "expected FORMAT before the samples"));
+                }
+                header.samples = names[fixed.len() + 1..]
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect();
+            }
+            columns_seen = true;
+        } else if !columns_seen {
+            return Err(ParseError::new(start, "expected the #CHROM column line"));
+        } else {
+            records.push(record(&header, line, start)?);
+        }
+    }
+    if !columns_seen {
+        return Err(ParseError::new(
+            input.len(),
+            "expected the #CHROM column line",
+        ));
+    }
+    Ok(Vcf { header, records })
+}
+
+fn shift(by: usize, e: ParseError) -> ParseError {
+    ParseError::new(by + e.offset(), e.message())
+}
+
+fn record(header: &Header, line: &str, start: usize) -> Result<Record, ParseError> {
+    let mut fields = Vec::new();
+    let mut at = start;
+    for field in line.split('\t') {
+        fields.push((at, field));
+        at += field.len() + 1;
+    }
+    let expected = if header.samples.is_empty() {
+        8
+    } else {
+        9 + header.samples.len()
+    };
+    if fields.len() != expected {
+        return Err(ParseError::new(
+            start,
+            format!("expected {expected} columns, found {}", fields.len()),
+        ));
+    }
+    let list = |field: &str, separator: char| -> Vec<String> {
+        if field == "." {
+            Vec::new()
+        } else {
+            field.split(separator).map(str::to_string).collect()
+        }
+    };
+    let (pos_at, pos) = fields[1];
+    let pos = pos
+        .parse()
+        .map_err(|_| ParseError::new(pos_at, "expected a position"))?;
+    let (qual_at, qual) = fields[5];
+    let quality = if qual == "." {
+        None
+    } else {
+        Some(
+            qual.parse()
+                .map_err(|_| ParseError::new(qual_at, "expected a quality"))?,
+        )
+    };
+
+    let (info_at, info_field) = fields[7];
+    let mut info = Vec::new();
+    if info_field != "." {
+        let mut at = info_at;
+        for entry in info_field.split(';') {
+            let (key, value) = match entry.split_once('=') {
+                Some((key, value)) => (key, Some((at + key.len() + 1, value))),
+                None => (entry, None),
+            };
+            let kind = header.info(key).and_then(|d| d.get("Type"));
+            let value = match value {
+                None => Value::Flag,
+                Some((value_at, value)) => typed(kind, value, value_at)?,
+            };
+            info.push((key.to_string(), value));
+            at += entry.len() + 1;
+        }
+    }
+
+    let format = fields.get(8).map(|(_, f)| list(f, ':')).unwrap_or_default();
+    let samples = fields
+        .iter()
+        .skip(9)
+        .map(|&(at, column)| {
+            let mut at = at;
+            let mut values = Vec::new();
+            // Trailing fields may be dropped from a sample.
+            for (key, value) in format
+                .iter()
+                .zip(column.split(':').chain(std::iter::repeat(".")))
+            {
+                let kind = header.format(key).and_then(|d| d.get("Type"));
+                values.push(typed(kind, value, at)?);
+                at += value.len() + 1;
+            }
+            Ok(values)
+        })
+        .collect::<Result<_, ParseError>>()?;
+
+    Ok(Record {
+        chrom: fields[0].1.to_string(),
+        pos,
+        ids: list(fields[2].1, ';'),
+        reference: fields[3].1.to_string(),
+        alternates: list(fields[4].1, ','),
+        quality,
+        filters: list(fields[6].1, ';'),
+        info,
+        format,
+        samples,
+    })
+}
+
+/// Types a comma-separated value by its declared `Type`.
+fn typed(kind: Option<&str>, value: &str, at: usize) -> Result<Value, ParseError> {
+    let items = value.split(',').map(|v| (v != ".").then_some(v));
+    Ok(match kind {
+        Some("Integer") => Value::Integer(numbers(items, at, "an integer")?),
+        Some("Float") => Value::Float(numbers(items, at, "a float")?),
+        Some("Flag") => Value::Flag,
+        Some("Character") => Value::Character(numbers(items, at, "a character")?),
+        _ => Value::String(items.map(|v| v.map(str::to_string)).collect()),
+    })
+}
+
+fn numbers<'a, T: std::str::FromStr>(
+    items: impl Iterator<Item = Option<&'a str>>,
+    at: usize,
+    what: &str,
+) -> Result<Vec<Option<T>>, ParseError> {
+    items
+        .map(|v| {
+            v.map(|v| {
+                v.parse()
+                    .map_err(|_| ParseError::new(at, format!("expected {what}, found {v:?}")))
+            })
+            .transpose()
+        })
+        .collect()
+}
+
+fn meta_line(input: &mut &str) -> ModalResult<Meta> {
+    let key = take_till(1.., '=')
+        .context(StrContext::Label("meta key"))
+        .parse_next(input)?;
+    cut_err('=')
+        .context(StrContext::Expected(StrContextValue::CharLiteral('=')))
+        .parse_next(input)?;
+    let value = alt((
+        delimited('<', separated(0.., pair, ','), cut_err('>'))
+            .context(StrContext::Expected(StrContextValue::CharLiteral('>')))
+            .map(MetaValue::Structured),
+        take_while(0.., |_| true).map(|s: &str| MetaValue::Text(s.to_string())),
+    ))
+    .parse_next(input)?;
+    Ok(Meta {
+        key: key.to_string(),
+        value,
+    })
+}
+
+fn pair(input: &mut &str) -> ModalResult<(String, String)> {
+    let key = take_till(1.., ['=', ',', '>']).parse_next(input)?;
+    cut_err('=')
+        .context(StrContext::Expected(StrContextValue::CharLiteral('=')))
+        .parse_next(input)?;
+    let value = alt((
+        delimited(
+            '"',
+            repeat(
+                0..,
+                alt((none_of(['"', '\\']), ('\\', any).map(|(_, c)| c))),
+            ),
+            cut_err('"').context(StrContext::Expected(StrContextValue::CharLiteral('"'))),
+        ),
+        take_till(0.., [',', '>']).map(str::to_string),
+    ))
+    .parse_next(input)?;
+    Ok((key.to_string(), value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VCF: &str = "##fileformat=VCFv4.3\n\
+##source=myImputationProgramV3.1\n\
+##INFO=<ID=DP,Number=1,Type=Integer,Description=\"Total Depth\">\n\
+##INFO=<ID=AF,Number=A,Type=Float,Description=\"Allele Frequency, \\\"estimated\\\"\">\n\
+##INFO=<ID=DB,Number=0,Type=Flag,Description=\"dbSNP membership\">\n\
+##FORMAT=<ID=GT,Number=1,Type=String,Description=\"Genotype\">\n\
+##FORMAT=<ID=GQ,Number=1,Type=Integer,Description=\"Genotype Quality\">\n\
+#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\tNA00001\tNA00002\n\
+20\t14370\trs6054257\tG\tA\t29\tPASS\tDP=14;AF=0.5;DB\tGT:GQ\t0|0:48\t1|0\n\
+20\t1110696\t.\tA\tG,T\t.\tq10;s50\tAF=0.333,.;XX=a\tGT:GQ\t1/2:.\t./.:21\n";
+
+    #[test]
+    fn parse_vcf_should_work() -> Result<(), ParseError> {
+        let vcf = parse_vcf(VCF)?;
+        assert_eq!(vcf.header.file_format, "VCFv4.3");
+        assert_eq!(vcf.header.samples, ["NA00001", "NA00002"]);
+        let af = vcf.header.info("AF").unwrap();
+        assert_eq!(
+            af.get("Description"),
+            Some("Allele Frequency, \"estimated\"")
+        );
+        assert_eq!(
+            vcf.header.get("source"),
+            Some(&MetaValue::Text("myImputationProgramV3.1".into()))
+        );
+
+        let first = &vcf.records[0];
+        assert_eq!(first.pos, 14370);
+        assert_eq!(first.ids, ["rs6054257"]);
+        assert!(first.is_pass());
+        assert_eq!(first.info("DP"), Some(&Value::Integer(vec![Some(14)])));
+        assert_eq!(first.info("DB"), Some(&Value::Flag));
+        assert_eq!(first.sample(0, "GQ"), Some(&Value::Integer(vec![Some(48)])));
+        assert_eq!(first.sample(1, "GQ"), Some(&Value::Integer(vec![None])));
+        assert_eq!(
+            first.genotype(1),
+            Some(Genotype {
+                alleles: vec![Some(1), Some(0)],
+                phased: true
+            })
+        );
+
+        let second = &vcf.records[1];
+        assert!(second.ids.is_empty());
+        assert_eq!(second.alternates, ["G", "T"]);
+        assert_eq!(second.quality, None);
+        assert_eq!(second.filters, ["q10", "s50"]);
+        assert_eq!(
+            second.info("AF"),
+            Some(&Value::Float(vec![Some(0.333), None]))
+        );
+        assert_eq!(
+            second.info("XX"),
+            Some(&Value::String(vec![Some("a".into())]))
+        );
+        assert_eq!(second.genotype(1).unwrap().alleles, [None, None]);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_vcf_should_report_errors() {
+        let bad_depth = VCF.replace("DP=14", "DP=x");
+        let err = parse_vcf(&bad_depth).unwrap_err();
+        let line = bad_depth.find("20\t14370").unwrap();
+        assert_eq!(
+            err.offset(),
+            line + "20\t14370\trs6054257\tG\tA\t29\tPASS\tDP=".len()
+        );
+        let err = parse_vcf("##INFO=<ID=DP,Description=\"open>\n").unwrap_err();
+        assert_eq!(err.offset(), 32);
+        assert!(parse_vcf("##fileformat=VCFv4.3\n20\t1\n").is_err());
+        assert!(parse_vcf(&VCF.replace("\t29\t", "\t29")).is_err());
+        assert!(parse_vcf("##fileformat=VCFv4.3\n").is_err());
+    }
+}
@@ -0,0 +1,362 @@
+use std::fmt;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::{Caseless, alpha1, float, multispace0, multispace1};
+use winnow::combinator::{alt, cut_err, delimited, fail, opt, peek, preceded, repeat, separated};
+use winnow::error::{ContextError, ErrMode, StrContext, StrContextValue};
+
+use crate::ParseError;
+
+/// Which ordinates each coordinate carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dimension {
+    Xy,
+    Xyz,
+    Xym,
+    Xyzm,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coord {
+    pub x: f64,
+    pub y: f64,
+    pub z: Option<f64>,
+    pub m: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Geometry {
+    pub dimension: Dimension,
+    pub shape: Shape,
+}
+
+/// Rings and line strings are coordinate lists; an empty list stands for
+/// `EMPTY`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Point(Option<Coord>),
+    LineString(Vec<Coord>),
+    /// The exterior ring, then any holes.
+    Polygon(Vec<Vec<Coord>>),
+    MultiPoint(Vec<Coord>),
+    MultiLineString(Vec<Vec<Coord>>),
+    MultiPolygon(Vec<Vec<Vec<Coord>>>),
+    GeometryCollection(Vec<Geometry>),
+}
+
+impl Dimension {
+    fn arity(self) -> usize {
+        match self {
+            Dimension::Xy => 2,
+            Dimension::Xyz | Dimension::Xym => 3,
+            Dimension::Xyzm => 4,
+        }
+    }
+}
+
+impl Coord {
+    pub fn xy(x: f64, y: f64) -> Self {
+        Coord {
+            x,
+            y,
+            z: None,
+            m: None,
+        }
+    }
+}
+
+impl Shape {
+    fn tag(&self) -> &'static str {
+        match self {
+            Shape::Point(_) => "POINT",
+            Shape::LineString(_) => "LINESTRING",
+            Shape::Polygon(_) => "POLYGON",
+            Shape::MultiPoint(_) => "MULTIPOINT",
+            Shape::MultiLineString(_) => "MULTILINESTRING",
+            Shape::MultiPolygon(_) => "MULTIPOLYGON",
+            Shape::GeometryCollection(_) => "GEOMETRYCOLLECTION",
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            Shape::Point(p) => p.is_none(),
+            Shape::LineString(v) | Shape::MultiPoint(v) => v.is_empty(),
+            Shape::Polygon(v) | Shape::MultiLineString(v) => v.is_empty(),
+            Shape::MultiPolygon(v) => v.is_empty(),
+            Shape::GeometryCollection(v) => v.is_empty(),
+        }
+    }
+}
+
+/// Writes ISO WKT, such as `POINT Z (1 2 3)`.
+impl fmt::Display for Geometry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.shape.tag())?;
+        f.write_str(match self.dimension {
+            Dimension::Xy => "",
+            Dimension::Xyz => " Z",
+            Dimension::Xym => " M",
+            Dimension::Xyzm => " ZM",
+        })?;
+        if self.shape.is_empty() {
+            return f.write_str(" EMPTY");
+        }
+        f.write_str(" ")?;
+        match &self.shape {
+            Shape::Point(Some(c)) => write_list(f, [c], write_coord),
+            Shape::Point(None) => unreachable!("empty shapes are written above"),
+            Shape::LineString(line) => write_list(f, line, write_coord),
+            Shape::Polygon(rings) => write_list(f, rings, write_line),
+            Shape::MultiPoint(points) => {
+                write_list(f, points, |f, c| write_list(f, [c], write_coord))
+            }
+            Shape::MultiLineString(lines) => write_list(f, lines, write_line),
+            Shape::MultiPolygon(polygons) => {
+                write_list(f, polygons, |f, rings| write_list(f, rings, write_line))
+            }
+            Shape::GeometryCollection(members) => write_list(f, members, |f, g| write!(f, "{g}")),
+        }
+    }
+}
+
+fn write_list<'a, T: 'a>(
+    f: &mut fmt::Formatter<'_>,
+    items: impl IntoIterator<Item = &'a T>,
+    mut write: impl FnMut(&mut fmt::Formatter<'_>, &'a T) -> fmt::Result,
+) -> fmt::Result {
+    f.write_str("(")?;
+    for (i, item) in items.into_iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        write(f, item)?;
+    }
+    f.write_str(")")
+}
+
+fn write_line(f: &mut fmt::Formatter<'_>, line: &Vec<Coord>) -> fmt::Result {
+    write_list(f, line, write_coord)
+}
+
+fn write_coord(f: &mut fmt::Formatter<'_>, c: &Coord) -> fmt::Result {
+    write!(f, "{} {}", c.x, c.y)?;
+    for extra in [c.z, c.m].into_iter().flatten() {
+        write!(f, " {extra}")?;
+    }
+    Ok(())
+}
+
+/// Parses a WKT geometry. Keywords are case-insensitive. Without a `Z`,
+/// `M` or `ZM` modifier, three ordinates mean XYZ and four XYZM, and every
+/// coordinate must have as many as the first.
+pub fn parse_wkt(input: &str) -> Result<Geometry, ParseError> {
+    delimited(multispace0, geometry, multispace0)
+        .parse(input)
+        .map_err(ParseError::from)
+}
+
+fn geometry(input: &mut &str) -> ModalResult<Geometry> {
+    let tag = cut_err(alpha1)
+        .context(StrContext::Label("geometry type"))
+        .parse_next(input)?
+        .to_ascii_uppercase();
+    let known = [
+        "POINT",
+        "LINESTRING",
+        "POLYGON",
+        "MULTIPOINT",
+        "MULTILINESTRING",
+        "MULTIPOLYGON",
+        "GEOMETRYCOLLECTION",
+    ];
+    if !known.contains(&tag.as_str()) {
+        return cut_err(fail)
+            .context(StrContext::Label("geometry type"))
+            .parse_next(input);
+    }
+    let modifier = opt(preceded(
+        multispace1,
+        alt((
+            Caseless("ZM").value(Dimension::Xyzm),
+            Caseless("Z").value(Dimension::Xyz),
+            Caseless("M").value(Dimension::Xym),
+        )),
+    ))
+    .parse_next(input)?;
+    multispace0.parse_next(input)?;
+    if opt(Caseless("EMPTY")).parse_next(input)?.is_some() {
+        let shape = match tag.as_str() {
+            "POINT" => Shape::Point(None),
+            "LINESTRING" => Shape::LineString(Vec::new()),
+            "POLYGON" => Shape::Polygon(Vec::new()),
+            "MULTIPOINT" => Shape::MultiPoint(Vec::new()),
+            "MULTILINESTRING" => Shape::MultiLineString(Vec::new()),
+            "MULTIPOLYGON" => Shape::MultiPolygon(Vec::new()),
+            _ => Shape::GeometryCollection(Vec::new()),
+        };
+        return Ok(Geometry {
+            dimension: modifier.unwrap_or(Dimension::Xy),
+            shape,
+        });
+    }
+    if tag == "GEOMETRYCOLLECTION" {
+        let members = list(geometry).parse_next(input)?;
+        return Ok(Geometry {
+            dimension: modifier.unwrap_or(Dimension::Xy),
+            shape: Shape::GeometryCollection(members),
+        });
+    }
+
+    let dimension = match modifier {
+        Some(dimension) => dimension,
+        None => {
+            // Count the ordinates of the first coordinate.
+            let first: ModalResult<Vec<f64>> = peek(preceded(
+                repeat::<_, _, (), _, _>(0.., alt(('(', ' ', '\t', '\n', '\r'))),
+                separated(1..=4, float::<_, f64, _>, multispace1),
+            ))
+            .parse_next(input);
+            match first.unwrap_or_default().len() {
+                3 => Dimension::Xyz,
+                4 => Dimension::Xyzm,
+                _ => Dimension::Xy,
+            }
+        }
+    };
+    let c = || coord(dimension);
+    let shape = match tag.as_str() {
+        "POINT" => Shape::Point(Some(delimited(open, c(), close).parse_next(input)?)),
+        "LINESTRING" => Shape::LineString(list(c()).parse_next(input)?),
+        "POLYGON" => Shape::Polygon(list(list(c())).parse_next(input)?),
+        "MULTIPOINT" => Shape::MultiPoint(
+            // Both `((1 2), (3 4))` and `(1 2, 3 4)` are in use.
+            list(alt((delimited(open, c(), close), c()))).parse_next(input)?,
+        ),
+        "MULTILINESTRING" => Shape::MultiLineString(list(list(c())).parse_next(input)?),
+        _ => Shape::MultiPolygon(list(list(list(c()))).parse_next(input)?),
+    };
+    Ok(Geometry { dimension, shape })
+}
+
+fn coord<'i>(dimension: Dimension) -> impl Parser<&'i str, Coord, ErrMode<ContextError>> {
+    separated(
+        dimension.arity(),
+        cut_err(float::<_, f64, _>).context(StrContext::Expected(StrContextValue::Description(
+            "a number",
+        ))),
+        multispace1,
+    )
+    .map(move |v: Vec<f64>| {
+        let (z, m) = match dimension {
+            Dimension::Xy => (None, None),
+            Dimension::Xyz => (Some(v[2]), None),
+            Dimension::Xym => (None, Some(v[2])),
+            Dimension::Xyzm => (Some(v[2]), Some(v[3])),
+        };
+        Coord {
+            x: v[0],
+            y: v[1],
+            z,
+            m,
+        }
+    })
+}
+
+/// `( item, item, ... )`
+fn list<'i, T>(
+    item: impl Parser<&'i str, T, ErrMode<ContextError>>,
+) -> impl Parser<&'i str, Vec<T>, ErrMode<ContextError>> {
+    delimited(
+        open,
+        separated(1.., item, (multispace0, ',', multispace0)),
+        close,
+    )
+}
+
+fn open(input: &mut &str) -> ModalResult<()> {
+    ('(', multispace0).void().parse_next(input)
+}
+
+fn close(input: &mut &str) -> ModalResult<()> {
+    preceded(
+        multispace0,
+        cut_err(')').context(StrContext::Expected(StrContextValue::CharLiteral(')'))),
+    )
+    .void()
+    .parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_wkt_should_work() -> Result<(), ParseError> {
+        let point = parse_wkt("point (30 10)")?;
+        assert_eq!(point.shape, Shape::Point(Some(Coord::xy(30.0, 10.0))));
+        let polygon = parse_wkt(
+            "POLYGON ((35 10, 45 45, 15 40, 10 20, 35 10),(20 30, 35 35, 30 20, 20 30))",
+        )?;
+        let Shape::Polygon(rings) = &polygon.shape else {
+            panic!("expected a polygon");
+        };
+        assert_eq!(rings.len(), 2);
+        assert_eq!(rings[1][1], Coord::xy(35.0, 35.0));
+
+        let measured = parse_wkt("LINESTRING M (1 2 3, 4 5 6)")?;
+        assert_eq!(measured.dimension, Dimension::Xym);
+        let Shape::LineString(line) = &measured.shape else {
+            panic!("expected a line string");
+        };
+        assert_eq!(line[0].m, Some(3.0));
+        assert_eq!(line[0].z, None);
+
+        let inferred = parse_wkt("MULTIPOINT ((1 2 3 4), (5 6 7 8))")?;
+        assert_eq!(inferred.dimension, Dimension::Xyzm);
+        let bare = parse_wkt("MULTIPOINT (1 2, 3 4)")?;
+        assert_eq!(
+            bare.shape,
+            Shape::MultiPoint(vec![Coord::xy(1.0, 2.0), Coord::xy(3.0, 4.0)])
+        );
+
+        let collection = parse_wkt("GEOMETRYCOLLECTION (POINT Z (4 6 1), LINESTRING EMPTY)")?;
+        let Shape::GeometryCollection(members) = &collection.shape else {
+            panic!("expected a collection");
+        };
+        assert_eq!(members[0].dimension, Dimension::Xyz);
+        assert_eq!(members[1].shape, Shape::LineString(vec![]));
+        Ok(())
+    }
+
+    #[test]
+    fn display_should_round_trip() -> Result<(), ParseError> {
+        for text in [
+            "POINT EMPTY",
+            "POINT ZM (1 2 3 4)",
+            "MULTIPOINT ((1 2), (3.5 -4))",
+            "MULTIPOLYGON (((30 20, 45 40, 10 40, 30 20)), ((15 5, 40 10, 10 20, 5 10, 15 5)))",
+            "GEOMETRYCOLLECTION (POINT (40 10), LINESTRING (10 10, 20 20), POLYGON M EMPTY)",
+        ] {
+            let geometry = parse_wkt(text)?;
+            assert_eq!(geometry.to_string(), text);
+        }
+        assert_eq!(
+            parse_wkt("linestring(1 2 3,4 5 6)")?.to_string(),
+            "LINESTRING Z (1 2 3, 4 5 6)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_wkt_should_report_errors() {
+        let err = parse_wkt("LINESTRING (1 2, 3 4 5)").unwrap_err();
+        assert_eq!(err.offset(), 21);
+        let err = parse_wkt("POINT Z (1 2)").unwrap_err();
+        assert_eq!(err.offset(), 12);
+        assert!(parse_wkt("CIRCLE (1 2)").is_err());
+        assert!(parse_wkt("POLYGON ((1 2, 3 4)").is_err());
+        assert!(parse_wkt("POINT (1 2) extra").is_err());
+    }
+}
@@ -0,0 +1,617 @@
+pub mod junit;
+
+use std::borrow::Cow;
+
+use winnow::ModalResult;
+use winnow::Parser;
+use winnow::ascii::multispace0;
+use winnow::combinator::{alt, cut_err, delimited, peek, preceded, repeat, terminated};
+use winnow::error::{ContextError, ErrMode, StrContext, StrContextValue};
+use winnow::token::{one_of, take_till, take_until, take_while};
+
+use crate::ParseError;
+
+/// One step of a document, as produced by [`XmlReader`]. Text and attribute
+/// values have their entities already decoded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<'a> {
+    /// `<?xml version="1.0"?>`
+    Declaration(Vec<Attribute<'a>>),
+    /// An element's start tag. Empty elements like `<br/>` are reported as
+    /// a `Start` immediately followed by an `End`.
+    Start {
+        name: &'a str,
+        attributes: Vec<Attribute<'a>>,
+    },
+    End {
+        name: &'a str,
+    },
+    Text(Cow<'a, str>),
+    CData(&'a str),
+    Comment(&'a str),
+    ProcessingInstruction {
+        target: &'a str,
+        data: &'a str,
+    },
+    Doctype(&'a str),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute<'a> {
+    pub name: &'a str,
+    pub value: Cow<'a, str>,
+}
+
+/// A pull parser over a complete document. It checks well-formedness as it
+/// goes: matching end tags, a single root element and no stray text around
+/// it. Iteration stops after the first error.
+pub struct XmlReader<'a> {
+    input: &'a str,
+    rest: &'a str,
+    open: Vec<&'a str>,
+    pending_end: Option<&'a str>,
+    seen_root: bool,
+    done: bool,
+}
+
+impl<'a> XmlReader<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            rest: input,
+            open: Vec::new(),
+            pending_end: None,
+            seen_root: false,
+            done: false,
+        }
+    }
+
+    fn offset(&self) -> usize {
+        self.input.len() - self.rest.len()
+    }
+
+    fn next_event(&mut self) -> Result<Option<Event<'a>>, ParseError> {
+        if let Some(name) = self.pending_end.take() {
+            self.close(name);
+            return Ok(Some(Event::End { name }));
+        }
+        loop {
+            if self.rest.is_empty() {
+                return match self.open.last() {
+                    Some(name) => Err(self.error(format!("unclosed element `{}`", name))),
+                    None if !self.seen_root => Err(self.error("missing root element")),
+                    None => Ok(None),
+                };
+            }
+            let start = self.offset();
+            let event = if self.rest.starts_with('<') {
+                parse_markup(&mut self.rest).map_err(|e| self.winnow_error(e))?
+            } else {
+                let raw = take_till(1.., '<')
+                    .parse_next(&mut self.rest)
+                    .map_err(|e| self.winnow_error(e))?;
+                if self.open.is_empty() {
+                    if raw.trim().is_empty() {
+                        continue;
+                    }
+                    return Err(ParseError::new(start, "text outside the root element"));
+                }
+                Event::Text(decode(raw, start)?)
+            };
+            return self.check(event, start).map(Some);
+        }
+    }
+
+    /// Applies the nesting rules to a freshly parsed event.
+    fn check(&mut self, event: Event<'a>, start: usize) -> Result<Event<'a>, ParseError> {
+        match &event {
+            Event::Start { name, .. } => {
+                if self.open.is_empty() && std::mem::replace(&mut self.seen_root, true) {
+                    return Err(ParseError::new(start, "more than one root element"));
+                }
+                self.open.push(*name);
+                // `parse_markup` leaves `/>` of an empty element unconsumed
+                // so that the synthetic end tag is emitted next.
+                if let Some(rest) = self.rest.strip_prefix("/>") {
+                    self.rest = rest;
+                    self.pending_end = Some(*name);
+                } else {
+                    self.rest = &self.rest[1..];
+                }
+            }
+            Event::End { name } => match self.open.last() {
+                Some(open) if open == name => self.close(name),
+                Some(open) => {
+                    let message = format!("expected `</{}>`, found `</{}>`", open, name);
+                    return Err(ParseError::new(start, message));
+                }
+                None => {
+                    let message = format!("unexpected end tag `</{}>`", name);
+                    return Err(ParseError::new(start, message));
+                }
+            },
+            Event::CData(_) if self.open.is_empty() => {
+                return Err(ParseError::new(start, "CDATA outside the root element"));
+            }
+            Event::Declaration(_) if start != 0 => {
+                return Err(ParseError::new(
+                    start,
+                    "the XML declaration must come first",
+                ));
+            }
+            _ => {}
+        }
+        Ok(event)
+    }
+
+    fn close(&mut self, name: &str) {
+        debug_assert_eq!(self.open.last().copied(), Some(name));
+        self.open.pop();
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.offset(), message)
+    }
+
+    fn winnow_error(&self, e: ErrMode<ContextError>) -> ParseError {
+        let message = match e {
+            ErrMode::Backtrack(e) | ErrMode::Cut(e) => e.to_string().replace('\n', "; "),
+            ErrMode::Incomplete(_) => String::new(),
+        };
+        if message.is_empty() {
+            self.error("invalid markup")
+        } else {
+            self.error(message)
+        }
+    }
+}
+
+impl<'a> Iterator for XmlReader<'a> {
+    type Item = Result<Event<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let event = self.next_event().transpose();
+        if !matches!(event, Some(Ok(_))) {
+            self.done = true;
+        }
+        event
+    }
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == ':'
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | ':' | '-' | '.')
+}
+
+fn parse_name<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    (one_of(is_name_start), take_while(0.., is_name_char))
+        .take()
+        .context(StrContext::Expected(StrContextValue::Description("name")))
+        .parse_next(input)
+}
+
+fn parse_markup<'i>(input: &mut &'i str) -> ModalResult<Event<'i>> {
+    alt((
+        parse_comment.map(Event::Comment),
+        parse_cdata.map(Event::CData),
+        parse_doctype.map(Event::Doctype),
+        parse_pi,
+        parse_end_tag.map(|name| Event::End { name }),
+        parse_start_tag.map(|(name, attributes)| Event::Start { name, attributes }),
+    ))
+    .parse_next(input)
+}
+
+fn parse_comment<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    preceded(
+        "<!--",
+        cut_err(terminated(take_until(0.., "-->"), "-->"))
+            .context(StrContext::Label("comment"))
+            .context(StrContext::Expected(StrContextValue::StringLiteral("-->"))),
+    )
+    .parse_next(input)
+}
+
+fn parse_cdata<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    preceded(
+        "<![CDATA[",
+        cut_err(terminated(take_until(0.., "]]>"), "]]>"))
+            .context(StrContext::Label("CDATA section"))
+            .context(StrContext::Expected(StrContextValue::StringLiteral("]]>"))),
+    )
+    .parse_next(input)
+}
+
+/// The doctype is kept as raw text; an internal subset in `[...]` may
+/// contain `>` of its own.
+fn parse_doctype<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    "<!DOCTYPE".parse_next(input)?;
+    let mut depth = 0usize;
+    for (i, c) in input.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth = depth.saturating_sub(1),
+            '>' if depth == 0 => {
+                let body = input[..i].trim();
+                *input = &input[i + 1..];
+                return Ok(body);
+            }
+            _ => {}
+        }
+    }
+    cut_err(winnow::combinator::fail)
+        .context(StrContext::Label("doctype"))
+        .context(StrContext::Expected(StrContextValue::CharLiteral('>')))
+        .parse_next(input)
+}
+
+fn parse_pi<'i>(input: &mut &'i str) -> ModalResult<Event<'i>> {
+    let target = preceded("<?", cut_err(parse_name)).parse_next(input)?;
+    if target.eq_ignore_ascii_case("xml") {
+        let attributes = cut_err(terminated(parse_attributes, (multispace0, "?>")))
+            .context(StrContext::Label("XML declaration"))
+            .parse_next(input)?;
+        return Ok(Event::Declaration(attributes));
+    }
+    let data = cut_err(terminated(take_until(0.., "?>"), "?>"))
+        .context(StrContext::Label("processing instruction"))
+        .context(StrContext::Expected(StrContextValue::StringLiteral("?>")))
+        .parse_next(input)?;
+    Ok(Event::ProcessingInstruction {
+        target,
+        data: data.trim(),
+    })
+}
+
+fn parse_end_tag<'i>(input: &mut &'i str) -> ModalResult<&'i str> {
+    preceded(
+        "</",
+        cut_err(terminated(parse_name, (multispace0, '>'))).context(StrContext::Label("end tag")),
+    )
+    .parse_next(input)
+}
+
+/// Parses up to, but not including, the closing `>` or `/>`.
+fn parse_start_tag<'i>(input: &mut &'i str) -> ModalResult<(&'i str, Vec<Attribute<'i>>)> {
+    let name = preceded('<', parse_name).parse_next(input)?;
+    let attributes = cut_err(terminated(
+        parse_attributes,
+        (multispace0, peek(alt((">", "/>")))),
+    ))
+    .context(StrContext::Label("start tag"))
+    .context(StrContext::Expected(StrContextValue::CharLiteral('>')))
+    .parse_next(input)?;
+    multispace0.parse_next(input)?;
+    Ok((name, attributes))
+}
+
+fn parse_attributes<'i>(input: &mut &'i str) -> ModalResult<Vec<Attribute<'i>>> {
+    let attributes: Vec<Attribute<'i>> =
+        repeat(0.., preceded(multispace0, parse_attribute)).parse_next(input)?;
+    for (i, attr) in attributes.iter().enumerate() {
+        if attributes[..i].iter().any(|a| a.name == attr.name) {
+            return cut_err(winnow::combinator::fail)
+                .context(StrContext::Label("start tag"))
+                .context(StrContext::Expected(StrContextValue::Description(
+                    "unique attribute names",
+                )))
+                .parse_next(input);
+        }
+    }
+    Ok(attributes)
+}
+
+fn parse_attribute<'i>(input: &mut &'i str) -> ModalResult<Attribute<'i>> {
+    let name = parse_name(input)?;
+    let raw = cut_err(preceded(
+        (multispace0, '=', multispace0),
+        alt((
+            delimited('"', take_till(0.., ['"', '<']), '"'),
+            delimited('\'', take_till(0.., ['\'', '<']), '\''),
+        )),
+    ))
+    .context(StrContext::Label("attribute"))
+    .context(StrContext::Expected(StrContextValue::Description(
+        "quoted value",
+    )))
+    .parse_next(input)?;
+    let value = match decode(raw, 0) {
+        Ok(value) => value,
+        Err(_) => {
+            return cut_err(winnow::combinator::fail)
+                .context(StrContext::Label("attribute"))
+                .context(StrContext::Expected(StrContextValue::Description(
+                    "valid entity reference",
+                )))
+                .parse_next(input);
+        }
+    };
+    Ok(Attribute { name, value })
+}
+
+/// Replaces entity and character references; `offset` is where `raw` starts
+/// in the document, for error positions.
+fn decode(raw: &str, offset: usize) -> Result<Cow<'_, str>, ParseError> {
+    if !raw.contains('&') {
+        return Ok(Cow::Borrowed(raw));
+    }
+    let mut out = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        let at = offset + raw.len() - rest.len() + amp;
+        let invalid = || ParseError::new(at, "invalid entity reference");
+        let end = rest[amp..].find(';').ok_or_else(invalid)?;
+        let entity = &rest[amp + 1..amp + end];
+        let c = match entity {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = if let Some(hex) = entity.strip_prefix("#x") {
+                    u32::from_str_radix(hex, 16).ok()
+                } else if let Some(dec) = entity.strip_prefix('#') {
+                    dec.parse().ok()
+                } else {
+                    None
+                };
+                code.and_then(char::from_u32).ok_or_else(invalid)?
+            }
+        };
+        out.push(c);
+        rest = &rest[amp + end + 1..];
+    }
+    out.push_str(rest);
+    Ok(Cow::Owned(out))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Element(Element),
+    Text(String),
+    CData(String),
+    Comment(String),
+    ProcessingInstruction { target: String, data: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Element {
+    /// The name as written, including any prefix.
+    pub name: String,
+    /// Namespace URI the prefix (or default namespace) is bound to.
+    pub namespace: Option<String>,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Node>,
+}
+
+impl Element {
+    pub fn local_name(&self) -> &str {
+        self.name
+            .split_once(':')
+            .map_or(&self.name, |(_, local)| local)
+    }
+
+    pub fn prefix(&self) -> Option<&str> {
+        self.name.split_once(':').map(|(prefix, _)| prefix)
+    }
+
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Child elements, skipping text and comments.
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|node| match node {
+            Node::Element(e) => Some(e),
+            _ => None,
+        })
+    }
+
+    /// Child elements whose name, as written, is `name`.
+    pub fn children_named<'s, 'n>(
+        &'s self,
+        name: &'n str,
+    ) -> impl Iterator<Item = &'s Element> + use<'s, 'n> {
+        self.elements().filter(move |e| e.name == name)
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children_named(name).next()
+    }
+
+    /// The concatenated text and CDATA of this element and its descendants.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        self.collect_text(&mut text);
+        text
+    }
+
+    fn collect_text(&self, out: &mut String) {
+        for node in &self.children {
+            match node {
+                Node::Text(t) | Node::CData(t) => out.push_str(t),
+                Node::Element(e) => e.collect_text(out),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Parses a document into its root element, resolving namespace prefixes
+/// declared with `xmlns` attributes along the way.
+pub fn parse_xml(input: &str) -> Result<Element, ParseError> {
+    let mut reader = XmlReader::new(input);
+    let mut stack: Vec<Element> = Vec::new();
+    // Namespace bindings per open element, innermost last.
+    let mut scopes: Vec<Vec<(Option<String>, String)>> = Vec::new();
+
+    let mut root = None;
+    for event in reader.by_ref() {
+        let node = match event? {
+            Event::Start { name, attributes } => {
+                let decls = attributes
+                    .iter()
+                    .filter_map(|a| match a.name.strip_prefix("xmlns") {
+                        Some("") => Some((None, a.value.to_string())),
+                        Some(prefix) => prefix
+                            .strip_prefix(':')
+                            .map(|p| (Some(p.to_string()), a.value.to_string())),
+                        None => None,
+                    })
+                    .collect();
+                scopes.push(decls);
+                let prefix = name.split_once(':').map(|(p, _)| p);
+                let namespace = scopes
+                    .iter()
+                    .rev()
+                    .flatten()
+                    .find(|(p, _)| p.as_deref() == prefix)
+                    .map(|(_, uri)| uri.clone())
+                    .filter(|uri| !uri.is_empty());
+                stack.push(Element {
+                    name: name.to_string(),
+                    namespace,
+                    attributes: attributes
+                        .into_iter()
+                        .map(|a| (a.name.to_string(), a.value.into_owned()))
+                        .collect(),
+                    children: Vec::new(),
+                });
+                continue;
+            }
+            Event::End { .. } => {
+                scopes.pop();
+                let element = stack.pop().expect("the reader matches end tags");
+                if stack.is_empty() {
+                    root = Some(element);
+                    break;
+                }
+                Node::Element(element)
+            }
+            Event::Text(t) => Node::Text(t.into_owned()),
+            Event::CData(t) => Node::CData(t.to_string()),
+            Event::Comment(t) => Node::Comment(t.to_string()),
+            Event::ProcessingInstruction { target, data } => Node::ProcessingInstruction {
+                target: target.to_string(),
+                data: data.to_string(),
+            },
+            Event::Declaration(_) | Event::Doctype(_) => continue,
+        };
+        // Comments and processing instructions outside the root are dropped.
+        if let Some(parent) = stack.last_mut() {
+            parent.children.push(node);
+        }
+    }
+    // Anything after the root element must still be well-formed.
+    for event in reader {
+        event?;
+    }
+    Ok(root.expect("the reader reports a missing root element"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reader_should_emit_events() -> Result<(), ParseError> {
+        let input = r#"<?xml version="1.0"?>
+<!DOCTYPE note [<!ENTITY x "y">]>
+<note id='1'><to>Tove &amp; co</to><br/><![CDATA[<raw>]]><!-- done --></note>"#;
+        let events = XmlReader::new(input).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            events,
+            [
+                Event::Declaration(vec![Attribute {
+                    name: "version",
+                    value: "1.0".into()
+                }]),
+                Event::Doctype(r#"note [<!ENTITY x "y">]"#),
+                Event::Start {
+                    name: "note",
+                    attributes: vec![Attribute {
+                        name: "id",
+                        value: "1".into()
+                    }]
+                },
+                Event::Start {
+                    name: "to",
+                    attributes: vec![]
+                },
+                Event::Text("Tove & co".into()),
+                Event::End { name: "to" },
+                Event::Start {
+                    name: "br",
+                    attributes: vec![]
+                },
+                Event::End { name: "br" },
+                Event::CData("<raw>"),
+                Event::Comment(" done "),
+                Event::End { name: "note" },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reader_should_check_well_formedness() {
+        let cases = [
+            ("<a><b></a>", "expected `</b>`, found `</a>`", 6),
+            ("<a></a><b/>", "more than one root element", 7),
+            ("<a>", "unclosed element `a`", 3),
+            ("hi<a/>", "text outside the root element", 0),
+            ("<a>&nope;</a>", "invalid entity reference", 3),
+            ("", "missing root element", 0),
+        ];
+        for (input, message, offset) in cases {
+            let err = parse_xml(input).unwrap_err();
+            assert_eq!(
+                (err.message(), err.offset()),
+                (message, offset),
+                "{}",
+                input
+            );
+        }
+        assert!(parse_xml(r#"<a x="1" x="2"/>"#).is_err());
+        assert!(parse_xml("<a><!-- open</a>").is_err());
+    }
+
+    #[test]
+    fn parse_xml_should_build_tree() -> Result<(), ParseError> {
+        let input = r#"<feed xmlns="http://www.w3.org/2005/Atom" xmlns:g="urn:g">
+  <title type="text">A &#x26; B&#33;</title>
+  <g:item g:id="7">one</g:item>
+  <g:item>two</g:item>
+</feed>"#;
+        let feed = parse_xml(input)?;
+        assert_eq!(
+            feed.namespace.as_deref(),
+            Some("http://www.w3.org/2005/Atom")
+        );
+        let title = feed.child("title").unwrap();
+        assert_eq!(title.attr("type"), Some("text"));
+        assert_eq!(title.text(), "A & B!");
+        let items: Vec<_> = feed.children_named("g:item").collect();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].local_name(), "item");
+        assert_eq!(items[0].prefix(), Some("g"));
+        assert_eq!(items[0].namespace.as_deref(), Some("urn:g"));
+        assert_eq!(items[0].attr("g:id"), Some("7"));
+        assert_eq!(
+            feed.text().split_whitespace().collect::<Vec<_>>(),
+            ["A", "&", "B!", "one", "two"]
+        );
+        Ok(())
+    }
+}
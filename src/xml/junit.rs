@@ -0,0 +1,348 @@
+use std::time::Duration;
+
+use super::{Element, parse_xml};
+use crate::ParseError;
+
+/// A JUnit report: the `<testsuites>` root, or a lone `<testsuite>`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    pub name: Option<String>,
+    pub time: Option<Duration>,
+    pub suites: Vec<TestSuite>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TestSuite {
+    pub name: String,
+    pub timestamp: Option<String>,
+    pub hostname: Option<String>,
+    pub time: Option<Duration>,
+    /// `<properties>`, in document order.
+    pub properties: Vec<(String, String)>,
+    pub cases: Vec<TestCase>,
+    /// Nested suites, as some xUnit tools write them.
+    pub suites: Vec<TestSuite>,
+    pub system_out: Option<String>,
+    pub system_err: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestCase {
+    pub name: String,
+    pub classname: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub time: Option<Duration>,
+    pub outcome: Outcome,
+    pub system_out: Option<String>,
+    pub system_err: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Passed,
+    /// An assertion failed.
+    Failure(Problem),
+    /// The test could not run to completion.
+    Error(Problem),
+    Skipped(Option<String>),
+}
+
+/// The body of a `<failure>` or `<error>`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Problem {
+    pub message: Option<String>,
+    /// The `type` attribute, usually an exception class.
+    pub kind: Option<String>,
+    /// The element text, usually a stack trace.
+    pub details: String,
+}
+
+/// Outcome tallies across a suite or report.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub tests: usize,
+    pub failures: usize,
+    pub errors: usize,
+    pub skipped: usize,
+}
+
+impl Counts {
+    pub fn passed(&self) -> usize {
+        self.tests - self.failures - self.errors - self.skipped
+    }
+
+    fn add(&mut self, other: Counts) {
+        self.tests += other.tests;
+        self.failures += other.failures;
+        self.errors += other.errors;
+        self.skipped += other.skipped;
+    }
+}
+
+impl TestSuite {
+    /// Tallies the cases of this suite and its nested suites. The suite's
+    /// own `tests`/`failures` attributes are not trusted.
+    pub fn counts(&self) -> Counts {
+        let mut counts = Counts::default();
+        for case in &self.cases {
+            counts.tests += 1;
+            match case.outcome {
+                Outcome::Passed => {}
+                Outcome::Failure(_) => counts.failures += 1,
+                Outcome::Error(_) => counts.errors += 1,
+                Outcome::Skipped(_) => counts.skipped += 1,
+            }
+        }
+        for suite in &self.suites {
+            counts.add(suite.counts());
+        }
+        counts
+    }
+
+    /// Every case, including those of nested suites.
+    pub fn all_cases(&self) -> Box<dyn Iterator<Item = &TestCase> + '_> {
+        Box::new(
+            self.cases
+                .iter()
+                .chain(self.suites.iter().flat_map(|s| s.all_cases())),
+        )
+    }
+}
+
+impl Report {
+    pub fn counts(&self) -> Counts {
+        let mut counts = Counts::default();
+        for suite in &self.suites {
+            counts.add(suite.counts());
+        }
+        counts
+    }
+
+    /// Cases that failed or errored.
+    pub fn problems(&self) -> impl Iterator<Item = &TestCase> {
+        self.suites
+            .iter()
+            .flat_map(|s| s.all_cases())
+            .filter(|c| matches!(c.outcome, Outcome::Failure(_) | Outcome::Error(_)))
+    }
+}
+
+/// Parses a JUnit XML report. Elements outside the schema are ignored.
+/// Errors found after the XML is read carry offset 0, as the tree keeps no
+/// positions.
+pub fn parse_junit(input: &str) -> Result<Report, ParseError> {
+    let root = parse_xml(input)?;
+    match root.local_name() {
+        "testsuites" => Ok(Report {
+            name: root.attr("name").map(str::to_string),
+            time: time(&root)?,
+            suites: root
+                .elements()
+                .filter(|e| e.local_name() == "testsuite")
+                .map(suite)
+                .collect::<Result<_, _>>()?,
+        }),
+        "testsuite" => {
+            let suite = suite(&root)?;
+            Ok(Report {
+                name: None,
+                time: suite.time,
+                suites: vec![suite],
+            })
+        }
+        other => Err(ParseError::new(
+            0,
+            format!("expected <testsuites> or <testsuite>, found <{other}>"),
+        )),
+    }
+}
+
+fn suite(element: &Element) -> Result<TestSuite, ParseError> {
+    let mut suite = TestSuite {
+        name: element.attr("name").unwrap_or_default().to_string(),
+        timestamp: element.attr("timestamp").map(str::to_string),
+        hostname: element.attr("hostname").map(str::to_string),
+        time: time(element)?,
+        ..TestSuite::default()
+    };
+    for child in element.elements() {
+        match child.local_name() {
+            "properties" => suite.properties.extend(
+                child
+                    .elements()
+                    .filter(|p| p.local_name() == "property")
+                    .map(|p| {
+                        let name = p.attr("name").unwrap_or_default().to_string();
+                        // Multi-line values may be written as text instead.
+                        let value = p.attr("value").map_or_else(|| p.text(), str::to_string);
+                        (name, value)
+                    }),
+            ),
+            "testcase" => suite.cases.push(case(child)?),
+            "testsuite" => suite.suites.push(self::suite(child)?),
+            "system-out" => suite.system_out = Some(child.text()),
+            "system-err" => suite.system_err = Some(child.text()),
+            _ => {}
+        }
+    }
+    Ok(suite)
+}
+
+fn case(element: &Element) -> Result<TestCase, ParseError> {
+    let name = element
+        .attr("name")
+        .ok_or_else(|| ParseError::new(0, "<testcase> without a name"))?;
+    let line = element
+        .attr("line")
+        .map(|l| {
+            l.parse()
+                .map_err(|_| ParseError::new(0, format!("invalid line number {l:?}")))
+        })
+        .transpose()?;
+    let mut case = TestCase {
+        name: name.to_string(),
+        classname: element.attr("classname").map(str::to_string),
+        file: element.attr("file").map(str::to_string),
+        line,
+        time: time(element)?,
+        outcome: Outcome::Passed,
+        system_out: None,
+        system_err: None,
+    };
+    for child in element.elements() {
+        let problem = || Problem {
+            message: child.attr("message").map(str::to_string),
+            kind: child.attr("type").map(str::to_string),
+            details: child.text().trim().to_string(),
+        };
+        // The first failure wins; later ones are reruns in some tools.
+        let passed = case.outcome == Outcome::Passed;
+        match child.local_name() {
+            "failure" if passed => case.outcome = Outcome::Failure(problem()),
+            "error" if passed => case.outcome = Outcome::Error(problem()),
+            "skipped" if passed => {
+                case.outcome = Outcome::Skipped(child.attr("message").map(str::to_string))
+            }
+            "system-out" => case.system_out = Some(child.text()),
+            "system-err" => case.system_err = Some(child.text()),
+            _ => {}
+        }
+    }
+    Ok(case)
+}
+
+/// The `time` attribute, in seconds. Some tools group digits with commas.
+fn time(element: &Element) -> Result<Option<Duration>, ParseError> {
+    let Some(raw) = element.attr("time") else {
+        return Ok(None);
+    };
+    raw.replace(',', "")
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+        .map(Some)
+        .ok_or_else(|| ParseError::new(0, format!("invalid time {raw:?}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_junit_should_work() -> Result<(), ParseError> {
+        let report = parse_junit(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="all" time="1,234.5">
+  <testsuite name="math" timestamp="2024-01-01T00:00:00" hostname="ci" time="0.25">
+    <properties>
+      <property name="seed" value="42"/>
+      <property name="env">line one
+line two</property>
+    </properties>
+    <testcase name="adds" classname="math.Add" time="0.1" file="add.rs" line="7"/>
+    <testcase name="divides" classname="math.Div" time="0.15">
+      <failure message="expected 2" type="AssertionError"><![CDATA[at div.rs:3
+at main.rs:1]]></failure>
+      <system-out>dividing</system-out>
+    </testcase>
+    <testcase name="roots"><skipped message="not ready"/></testcase>
+  </testsuite>
+  <testsuite name="io">
+    <testcase name="reads"><error message="boom" type="IoError"/></testcase>
+  </testsuite>
+</testsuites>"#,
+        )?;
+        assert_eq!(report.name.as_deref(), Some("all"));
+        assert_eq!(report.time, Some(Duration::from_secs_f64(1234.5)));
+        let math = &report.suites[0];
+        assert_eq!(math.hostname.as_deref(), Some("ci"));
+        assert_eq!(math.time, Some(Duration::from_millis(250)));
+        assert_eq!(
+            math.properties,
+            [
+                ("seed".to_string(), "42".to_string()),
+                ("env".to_string(), "line one\nline two".to_string())
+            ]
+        );
+        let adds = &math.cases[0];
+        assert_eq!(adds.outcome, Outcome::Passed);
+        assert_eq!(adds.line, Some(7));
+        let divides = &math.cases[1];
+        assert_eq!(
+            divides.outcome,
+            Outcome::Failure(Problem {
+                message: Some("expected 2".into()),
+                kind: Some("AssertionError".into()),
+                details: "at div.rs:3\nat main.rs:1".into(),
+            })
+        );
+        assert_eq!(divides.system_out.as_deref(), Some("dividing"));
+        assert_eq!(
+            math.cases[2].outcome,
+            Outcome::Skipped(Some("not ready".into()))
+        );
+        let counts = report.counts();
+        assert_eq!(
+            counts,
+            Counts {
+                tests: 4,
+                failures: 1,
+                errors: 1,
+                skipped: 1
+            }
+        );
+        assert_eq!(counts.passed(), 1);
+        let problems: Vec<_> = report.problems().map(|c| c.name.as_str()).collect();
+        assert_eq!(problems, ["divides", "reads"]);
+        Ok(())
+    }
+
+    #[test]
+    fn single_suites_should_nest() -> Result<(), ParseError> {
+        let report = parse_junit(
+            r#"<testsuite name="outer" time="2">
+  <testsuite name="inner"><testcase name="a"/><testcase name="b"/></testsuite>
+  <testcase name="c"><failure>oops</failure></testcase>
+</testsuite>"#,
+        )?;
+        assert_eq!(report.time, Some(Duration::from_secs(2)));
+        let outer = &report.suites[0];
+        let names: Vec<_> = outer.all_cases().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["c", "a", "b"]);
+        assert_eq!(outer.counts().tests, 3);
+        assert_eq!(outer.counts().failures, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_junit_should_report_errors() {
+        let err = parse_junit("<results/>").unwrap_err();
+        assert!(err.message().contains("<results>"));
+        assert!(parse_junit(r#"<testsuite><testcase time="x" name="a"/></testsuite>"#).is_err());
+        assert!(parse_junit("<testsuite><testcase/></testsuite>").is_err());
+        let err = parse_junit("<testsuites><testsuite></testsuites>").unwrap_err();
+        assert!(err.offset() > 0);
+    }
+}